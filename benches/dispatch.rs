@@ -0,0 +1,84 @@
+use criterion::{BatchSize, criterion_group, criterion_main, Criterion};
+
+use libot::Bot;
+use libot::client::BotClientBuilder;
+use libot::runner::BotRunner;
+use libot::runner::events::{EventReplay, RecordedLine};
+
+use tokio::runtime::Runtime;
+
+const GAME_ID: &str = "testId";
+const BOT_ID: &str = "testbot";
+
+const GAME_START_LINE: &str = r#"{"type":"gameStart","game":{"id":"testId"}}"#;
+
+const GAME_FULL_LINE: &str = concat!(
+    r#"{"type":"gameFull","id":"testId","variant":{"key":"standard"},"speed":"bullet","#,
+    r#""perf":{},"rated":false,"createdAt":1700000000000,"white":{"id":"testbot"},"#,
+    r#""black":{"id":"opponent"},"initialFen":"startpos","state":{"type":"gameState","#,
+    r#""moves":"","wtime":60000,"btime":60000,"winc":0,"binc":0,"status":"started"}}"#);
+
+const MOVES: [&str; 32] = [
+    "e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5a4", "g8f6", "e1g1", "f8e7", "f1e1", "b7b5",
+    "a4b3", "d7d6", "c2c3", "e8g8", "h2h3", "c6a5", "b3c2", "c7c5", "d2d4", "d8c7", "b1d2", "c5d4",
+    "c3d4", "a5c6", "d2b3", "a6a5", "c1e3", "a5a4", "b3d2", "c8d7"
+];
+
+struct IdleBot;
+
+impl Bot for IdleBot { }
+
+fn recorded_line(game_id: Option<&str>, line: String) -> RecordedLine {
+    RecordedLine {
+        timestamp: 0,
+        game_id: game_id.map(str::to_owned),
+        line
+    }
+}
+
+fn replay_of_game(game_lines: impl IntoIterator<Item = String>) -> EventReplay {
+    let mut lines = vec![
+        recorded_line(None, GAME_START_LINE.to_owned()),
+        recorded_line(Some(GAME_ID), GAME_FULL_LINE.to_owned())
+    ];
+    lines.extend(game_lines.into_iter().map(|line| recorded_line(Some(GAME_ID), line)));
+
+    EventReplay::new(lines)
+}
+
+/// A game in which every game state adds one move, so the runner has to determine the new move,
+/// update the tracked position, and track turns and offers for every state.
+fn game_state_replay() -> EventReplay {
+    replay_of_game((1..=MOVES.len()).map(|plies| format!(
+        r#"{{"type":"gameState","moves":"{}","wtime":60000,"btime":60000,"winc":0,"binc":0,"#,
+        MOVES[..plies].join(" ")) + r#""status":"started"}"#))
+}
+
+/// A game in which only chat lines arrive, which measures the bare dispatch overhead.
+fn chat_line_replay() -> EventReplay {
+    replay_of_game((0..MOVES.len()).map(|index| format!(
+        r#"{{"type":"chatLine","room":"player","username":"opponent","text":"message {index}"}}"#)))
+}
+
+fn bench_dispatch(criterion: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = criterion.benchmark_group("dispatch");
+
+    let replays = [("game_states", game_state_replay()), ("chat_lines", chat_line_replay())];
+
+    for (name, replay) in replays {
+        group.bench_function(name, |bencher| bencher.iter_batched(
+            || {
+                let client = BotClientBuilder::new().with_token("").build().unwrap();
+
+                (BotRunner::new(IdleBot, client), replay.clone())
+            },
+            |(runner, replay)| runtime.block_on(runner.replay(replay, BOT_ID.to_owned())),
+            BatchSize::SmallInput));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_dispatch);
+criterion_main!(benches);
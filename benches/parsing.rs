@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use libot::model::bot_event::BotEvent;
+use libot::model::game::event::GameEvent;
+
+use serde::de::DeserializeOwned;
+
+const GAME_FULL_LINE: &str = concat!(
+    r#"{"type":"gameFull","id":"testId","variant":{"key":"standard","name":"Standard","#,
+    r#""short":"Std"},"clock":{"initial":60000,"increment":0},"speed":"bullet","#,
+    r#""perf":{"name":"Bullet"},"rated":true,"createdAt":1700000000000,"#,
+    r#""white":{"id":"testbot","name":"TestBot","title":"BOT","rating":2000},"#,
+    r#""black":{"id":"opponent","name":"Opponent","rating":1950,"provisional":false},"#,
+    r#""initialFen":"startpos","state":{"type":"gameState","moves":"e2e4 e7e5 g1f3","#,
+    r#""wtime":58000,"btime":59000,"winc":0,"binc":0,"status":"started"}}"#);
+
+const GAME_STATE_LINE: &str = concat!(
+    r#"{"type":"gameState","moves":"e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1 f8e7 f1e1 "#,
+    r#"b7b5 a4b3 d7d6 c2c3 e8g8 h2h3 c6a5 b3c2 c7c5 d2d4 d8c7 b1d2 c5d4 c3d4 a5c6 d2b3 "#,
+    r#"a6a5 c1e3 a5a4 b3d2 c8d7","wtime":41230,"btime":39870,"winc":0,"binc":0,"#,
+    r#""status":"started"}"#);
+
+const CHAT_LINE: &str =
+    r#"{"type":"chatLine","room":"player","username":"opponent","text":"Good luck!"}"#;
+
+const CHALLENGE_LINE: &str = concat!(
+    r#"{"type":"challenge","challenge":{"id":"testId","url":"https://lichess.org/testId","#,
+    r#""status":"created","challenger":{"id":"opponent","name":"Opponent","rating":1950,"#,
+    r#""title":"BOT","online":true},"destUser":{"id":"testbot","name":"TestBot","#,
+    r#""rating":2000,"title":"BOT","online":true},"variant":{"key":"standard","#,
+    r#""name":"Standard","short":"Std"},"rated":true,"speed":"bullet","timeControl":{"#,
+    r#""type":"clock","limit":60,"increment":0,"show":"1+0"},"color":"random","#,
+    r#""perf":{"icon":"T","name":"Bullet"}}}"#);
+
+const GAME_START_LINE: &str = concat!(
+    r#"{"type":"gameStart","game":{"gameId":"testId","fullId":"testIdFull","#,
+    r#""color":"white","fen":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","#,
+    r#""hasMoved":false,"isMyTurn":true,"lastMove":"","opponent":{"id":"opponent","#,
+    r#""username":"Opponent","rating":1950},"perf":"bullet","rated":true,"secondsLeft":60,"#,
+    r#""source":"friend","speed":"bullet","variant":{"key":"standard","name":"Standard"},"#,
+    r#""compat":{"bot":true,"board":true},"id":"testId"}}"#);
+
+fn parse_serde_json<T: DeserializeOwned>(line: &str) -> T {
+    serde_json::from_str(line).unwrap()
+}
+
+#[cfg(feature = "simd-json")]
+fn parse_simd_json<T: DeserializeOwned>(line: &str) -> T {
+    let mut bytes = line.as_bytes().to_vec();
+
+    simd_json::serde::from_slice(&mut bytes).unwrap()
+}
+
+fn bench_lines<T: DeserializeOwned>(criterion: &mut Criterion, group_name: &str,
+        lines: &[(&str, &str)]) {
+    let mut group = criterion.benchmark_group(group_name);
+
+    for &(name, line) in lines {
+        group.bench_function(format!("{name}/serde_json"), |bencher|
+            bencher.iter(|| parse_serde_json::<T>(black_box(line))));
+
+        #[cfg(feature = "simd-json")]
+        group.bench_function(format!("{name}/simd_json"), |bencher|
+            bencher.iter(|| parse_simd_json::<T>(black_box(line))));
+    }
+
+    group.finish();
+}
+
+fn bench_parsing(criterion: &mut Criterion) {
+    bench_lines::<GameEvent>(criterion, "game_event", &[
+        ("game_full", GAME_FULL_LINE),
+        ("game_state", GAME_STATE_LINE),
+        ("chat_line", CHAT_LINE)
+    ]);
+    bench_lines::<BotEvent>(criterion, "bot_event", &[
+        ("challenge", CHALLENGE_LINE),
+        ("game_start", GAME_START_LINE)
+    ]);
+}
+
+criterion_group!(benches, bench_parsing);
+criterion_main!(benches);
@@ -0,0 +1,85 @@
+use std::env;
+use std::error::Error;
+
+use libot::Bot;
+use libot::chat_command::{ChatCommand, ChatCommands, CommandHandler, OperatorList, Permission};
+use libot::client::{BotClient, BotClientBuilder};
+use libot::combinator::Chained;
+use libot::context::{BotContext, GameContext};
+use libot::model::challenge::Challenge;
+use libot::model::game::event::GameStateEvent;
+use libot::runner::BotRunner;
+
+/// Accepts every challenge and plays the first legal move in every position.
+struct FirstMover;
+
+#[async_trait::async_trait]
+impl Bot for FirstMover {
+
+    async fn on_challenge(&self, _context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        let _ = client.accept_challenge(challenge.id).await;
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, _state: GameStateEvent,
+            client: &BotClient) {
+        let mov = context.position().and_then(|position| position.legal_moves().first().copied());
+
+        if let Some(mov) = mov {
+            let _ = context.make_move(client, mov.to_string(), false).await;
+        }
+    }
+}
+
+/// Resigns the game in which the command was posted.
+struct Resign;
+
+#[async_trait::async_trait]
+impl CommandHandler for Resign {
+    async fn execute(&self, context: &GameContext, _command: &ChatCommand, client: &BotClient)
+            -> Option<String> {
+        client.resign_game(context.id.clone()).await.err()
+            .map(|error| format!("Could not resign: {error}"))
+    }
+}
+
+fn evaluation(context: &GameContext, _command: &ChatCommand) -> Option<String> {
+    let position = context.position()?;
+
+    Some(format!("Material {:+}, static evaluation {:+} (centipawns for White)",
+        position.material_balance(), position.static_evaluation()))
+}
+
+fn opening(context: &GameContext, _command: &ChatCommand) -> Option<String> {
+    let reply = match context.opening() {
+        Some(opening) => format!("{} {}", opening.eco, opening.name),
+        None => "Unknown opening".to_owned()
+    };
+
+    Some(reply)
+}
+
+/// A bot which plays the first legal move in every position and responds to the chat commands
+/// `!eval` and `!opening` of everyone as well as `!resign` of its owner, which has to be confirmed.
+/// Run it with the token of a bot account in the `LIBOT_TOKEN` environment variable and the name
+/// of the owner in `LIBOT_OWNER`.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let client = BotClientBuilder::new()
+        .with_token_from_env("LIBOT_TOKEN")
+        .build()?;
+    let mut operators = OperatorList::new();
+
+    if let Ok(owner) = env::var("LIBOT_OWNER") {
+        operators = operators.with_owner(owner);
+    }
+
+    let commands = ChatCommands::new(operators)
+        .with_command("eval", Permission::Everyone, evaluation)
+        .with_command("opening", Permission::Everyone, opening)
+        .with_destructive_command("resign", Permission::Owner, Resign);
+
+    BotRunner::new(Chained(FirstMover, commands), client).run().await?;
+
+    Ok(())
+}
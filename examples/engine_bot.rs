@@ -0,0 +1,54 @@
+use std::env;
+use std::error::Error;
+
+use libot::Bot;
+use libot::analysis::engine_bot::EngineBot;
+use libot::analysis::uci_engine::{SearchLimits, UciEngine};
+use libot::chat_command::OperatorList;
+use libot::client::{BotClient, BotClientBuilder};
+use libot::combinator::Chained;
+use libot::context::BotContext;
+use libot::model::challenge::Challenge;
+use libot::model::game::Speed;
+use libot::runner::BotRunner;
+
+/// Accepts every challenge, leaving the moves to the [EngineBot] it is chained with.
+struct AcceptAll;
+
+#[async_trait::async_trait]
+impl Bot for AcceptAll {
+    async fn on_challenge(&self, _context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        let _ = client.accept_challenge(challenge.id).await;
+    }
+}
+
+/// A bot which accepts every challenge and plays the moves of a UCI engine, searching at most
+/// 8 half-moves deep in ultra bullet. Run it with the token of a bot account in the `LIBOT_TOKEN`
+/// environment variable. The engine is the program given in `LIBOT_ENGINE`, or `stockfish` from
+/// the `PATH` by default. The user given in `LIBOT_OPERATOR`, if any, may change the search
+/// limits with chat commands such as `!depth 12`.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let client = BotClientBuilder::new()
+        .with_token_from_env("LIBOT_TOKEN")
+        .build()?;
+    let program = env::var("LIBOT_ENGINE").unwrap_or_else(|_| "stockfish".to_owned());
+    let engine = UciEngine::start(program, Vec::<String>::new()).await?;
+    let mut operators = OperatorList::new();
+
+    if let Ok(operator) = env::var("LIBOT_OPERATOR") {
+        operators = operators.with_operator(operator);
+    }
+
+    let bot = EngineBot::new(engine)
+        .with_limits(Speed::UltraBullet, SearchLimits {
+            depth: Some(8),
+            ..SearchLimits::default()
+        })
+        .with_operators(operators);
+
+    BotRunner::new(Chained(AcceptAll, bot), client).run().await?;
+
+    Ok(())
+}
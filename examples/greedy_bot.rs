@@ -0,0 +1,72 @@
+use std::error::Error;
+
+use libot::Bot;
+use libot::chess::position::Position;
+use libot::chess::uci::UciMove;
+use libot::client::{BotClient, BotClientBuilder};
+use libot::context::{BotContext, GameContext};
+use libot::model::challenge::Challenge;
+use libot::model::game::Color;
+use libot::model::game::event::GameStateEvent;
+use libot::runner::BotRunner;
+
+/// A bot which accepts every challenge and plays the move after which the static evaluation of
+/// the position is best for it, without looking further ahead. Run it with the token of a bot
+/// account in the `LIBOT_TOKEN` environment variable.
+struct GreedyBot;
+
+impl GreedyBot {
+
+    fn evaluate(position: &Position, mover: Color) -> i32 {
+        if position.is_checkmate() {
+            return i32::MAX;
+        }
+
+        match mover {
+            Color::White => position.static_evaluation(),
+            Color::Black => -position.static_evaluation()
+        }
+    }
+
+    fn greedy_move(position: &Position) -> Option<UciMove> {
+        let mover = position.side_to_move();
+
+        position.legal_moves().into_iter()
+            .max_by_key(|mov| {
+                let mut after = position.clone();
+                let _ = after.play(mov);
+
+                GreedyBot::evaluate(&after, mover)
+            })
+    }
+}
+
+#[async_trait::async_trait]
+impl Bot for GreedyBot {
+
+    async fn on_challenge(&self, _context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        let _ = client.accept_challenge(challenge.id).await;
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, _state: GameStateEvent,
+            client: &BotClient) {
+        let Some(mov) = context.position().as_ref().and_then(GreedyBot::greedy_move)
+        else {
+            return;
+        };
+
+        let _ = context.make_move(client, mov.to_string(), false).await;
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let client = BotClientBuilder::new()
+        .with_token_from_env("LIBOT_TOKEN")
+        .build()?;
+
+    BotRunner::new(GreedyBot, client).run().await?;
+
+    Ok(())
+}
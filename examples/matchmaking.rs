@@ -0,0 +1,78 @@
+use std::env;
+use std::error::Error;
+
+use libot::Bot;
+use libot::client::{BotClient, BotClientBuilder};
+use libot::context::{BotContext, GameContext};
+use libot::model::bot_event::GameStartFinish;
+use libot::model::challenge::{ChallengeBuilder, ChallengeRequest};
+use libot::model::game::Color;
+use libot::model::game::event::GameStateEvent;
+use libot::model::game::result::GameResult;
+use libot::runner::BotRunner;
+
+/// A bot which keeps challenging the same opponent to casual 3+2 blitz games and plays the move
+/// with the best static evaluation in every position. Run it with the token of a bot account in
+/// the `LIBOT_TOKEN` environment variable. The opponent is the user given in `LIBOT_OPPONENT`,
+/// or the bot `maia1` by default.
+struct Matchmaker {
+    opponent: String,
+    request: ChallengeRequest
+}
+
+impl Matchmaker {
+
+    async fn challenge(&self, client: &BotClient) {
+        if let Err(error) = client.create_challenge(&self.opponent, &self.request).await {
+            eprintln!("could not challenge {}: {error}", self.opponent);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Bot for Matchmaker {
+
+    async fn on_game_finish(&self, _context: &BotContext, _game: GameStartFinish,
+            _result: GameResult, client: &BotClient) {
+        self.challenge(client).await;
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, _state: GameStateEvent,
+            client: &BotClient) {
+        let Some(position) = context.position()
+        else {
+            return;
+        };
+        let sign = match position.side_to_move() {
+            Color::White => 1,
+            Color::Black => -1
+        };
+        let best_move = position.legal_moves().into_iter()
+            .max_by_key(|mov| {
+                let mut after = position.clone();
+                let _ = after.play(mov);
+
+                sign * after.static_evaluation()
+            });
+
+        if let Some(mov) = best_move {
+            let _ = context.make_move(client, mov.to_string(), false).await;
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let client = BotClientBuilder::new()
+        .with_token_from_env("LIBOT_TOKEN")
+        .build()?;
+    let bot = Matchmaker {
+        opponent: env::var("LIBOT_OPPONENT").unwrap_or_else(|_| "maia1".to_owned()),
+        request: ChallengeBuilder::new().with_clock(180, 2).build()?
+    };
+
+    bot.challenge(&client).await;
+    BotRunner::new(bot, client).run().await?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::hash::{BuildHasher, Hasher};
+
+use libot::Bot;
+use libot::client::{BotClient, BotClientBuilder};
+use libot::context::{BotContext, GameContext};
+use libot::model::challenge::Challenge;
+use libot::model::game::event::GameStateEvent;
+use libot::runner::BotRunner;
+
+/// A bot which accepts every challenge and plays uniformly random legal moves in any variant. Run
+/// it with the token of a bot account in the `LIBOT_TOKEN` environment variable.
+struct RandomMover;
+
+fn random_index(len: usize) -> usize {
+    RandomState::new().build_hasher().finish() as usize % len
+}
+
+#[async_trait::async_trait]
+impl Bot for RandomMover {
+
+    async fn on_challenge(&self, _context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        let _ = client.accept_challenge(challenge.id).await;
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, _state: GameStateEvent,
+            client: &BotClient) {
+        let Some(position) = context.position()
+        else {
+            return;
+        };
+        let moves = position.legal_moves();
+
+        if moves.is_empty() {
+            return;
+        }
+
+        let mov = moves[random_index(moves.len())];
+        let _ = context.make_move(client, mov.to_string(), false).await;
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let client = BotClientBuilder::new()
+        .with_token_from_env("LIBOT_TOKEN")
+        .build()?;
+
+    BotRunner::new(RandomMover, client).run().await?;
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use libot::model::bot_event::BotEvent;
+use libot::model::game::event::GameEvent;
+use libot::model::user::preferences::UserPreferences;
+use libot::runner::parsing::ParsingConfig;
+
+// Feeds arbitrary lines to the parsing of the runner, which must reject invalid input with an
+// error instead of panicking. Run with `cargo +nightly fuzz run parse_events`.
+fuzz_target!(|data: &[u8]| {
+    let Ok(line) = std::str::from_utf8(data)
+    else {
+        return;
+    };
+    let config = ParsingConfig::tolerant();
+
+    let _ = config.parse_line::<BotEvent>(line);
+    let _ = config.parse_line::<GameEvent>(line);
+    let _ = config.parse_line::<UserPreferences>(line);
+});
@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::Bot;
+use crate::analysis::uci_engine::{SearchLimits, UciClock, UciEngine};
+use crate::chat_command::{ChatCommand, OperatorList, Permission};
+use crate::chess::position::Position;
+use crate::client::BotClient;
+use crate::config::EngineConfig;
+use crate::context::{BotContext, GameContext};
+use crate::model::Milliseconds;
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::Speed;
+use crate::model::game::event::{ChatLineEvent, GameStateEvent};
+use crate::model::game::result::GameResult;
+
+/// A chat command of the operator of an [EngineBot], which changes the [SearchLimits] of the
+/// speed of the game in which it was posted.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum LimitCommand {
+    Depth(Option<u32>),
+    Nodes(Option<u64>),
+    MoveTime(Option<Milliseconds>),
+    Show,
+    Reset
+}
+
+fn parse_limit<T: FromStr>(argument: Option<&str>) -> Option<Option<T>> {
+    match argument? {
+        "off" => Some(None),
+        argument => argument.parse().ok().map(Some)
+    }
+}
+
+impl LimitCommand {
+
+    fn parse(text: &str) -> Option<LimitCommand> {
+        let command = ChatCommand::parse(text)?;
+        let argument = match command.args.as_slice() {
+            [] => None,
+            [argument] => Some(argument.as_str()),
+            _ => return None
+        };
+
+        match command.name.as_str() {
+            "depth" => parse_limit(argument).map(LimitCommand::Depth),
+            "nodes" => parse_limit(argument).map(LimitCommand::Nodes),
+            "movetime" => parse_limit(argument).map(LimitCommand::MoveTime),
+            "limits" if argument.is_none() => Some(LimitCommand::Show),
+            "reset" if argument.is_none() => Some(LimitCommand::Reset),
+            _ => None
+        }
+    }
+
+    fn apply(self, limits: &mut SearchLimits) {
+        match self {
+            LimitCommand::Depth(depth) => limits.depth = depth,
+            LimitCommand::Nodes(nodes) => limits.nodes = nodes,
+            LimitCommand::MoveTime(move_time_ms) => limits.move_time_ms = move_time_ms,
+            LimitCommand::Show | LimitCommand::Reset => { }
+        }
+    }
+}
+
+/// A [Bot] which plays the best moves found by a local [UciEngine], searching with configurable
+/// [SearchLimits] per [Speed], e.g. to a fixed depth in ultra bullet or for a fixed time in rapid.
+///
+/// Users with at least [Permission::Operator] according to the [OperatorList] set with
+/// [EngineBot::with_operators] can override the limits of the speed of a game while it runs by
+/// posting the following commands in its chat. The bot replies with the resulting limits.
+///
+/// * `!depth <half-moves>`, `!nodes <nodes>`, `!movetime <milliseconds>`: Sets the respective
+///   limit. The argument `off` removes it instead.
+/// * `!limits`: Shows the current limits.
+/// * `!reset`: Removes all overrides, returning to the configured limits.
+///
+/// Overrides apply to all following searches in games of the same speed, until the bot is
+/// dropped.
+///
+/// If pondering is enabled with [EngineBot::with_pondering], the engine searches the position
+/// after the reply it predicts while the opponent thinks (see [UciEngine::ponder]).
+pub struct EngineBot {
+    engine: UciEngine,
+    limits: HashMap<Speed, SearchLimits>,
+    overrides: Mutex<HashMap<Speed, SearchLimits>>,
+    operators: OperatorList,
+    pondering: bool
+}
+
+impl EngineBot {
+
+    /// Creates a new engine bot which plays the moves of the given engine, without any limits
+    /// except the clock and without operators.
+    pub fn new(engine: UciEngine) -> EngineBot {
+        EngineBot {
+            engine,
+            limits: HashMap::new(),
+            overrides: Mutex::new(HashMap::new()),
+            operators: OperatorList::new(),
+            pondering: false
+        }
+    }
+
+    /// Starts the engine of the given configuration and creates an engine bot which uses it with
+    /// the configured [EngineConfig::limits].
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while starting the engine or setting its options.
+    pub async fn from_config(config: &EngineConfig) -> io::Result<EngineBot> {
+        let mut bot = EngineBot::new(config.start().await?);
+        bot.limits = config.limits.clone();
+
+        Ok(bot)
+    }
+
+    /// Sets the [SearchLimits] of searches in games of the given speed. The bot is returned for
+    /// chaining.
+    pub fn with_limits(mut self, speed: Speed, limits: SearchLimits) -> EngineBot {
+        self.limits.insert(speed, limits);
+        self
+    }
+
+    /// Sets the allowlist of users who may override the limits with chat commands. The bot is
+    /// returned for chaining.
+    pub fn with_operators(mut self, operators: OperatorList) -> EngineBot {
+        self.operators = operators;
+        self
+    }
+
+    /// Lets the engine ponder on the opponent's time after each move of the bot. If the opponent
+    /// plays the predicted reply, the search of the next move continues the ponder search, which
+    /// then ignores the [SearchLimits]. The ponder search is stopped when the game finishes. Some
+    /// engines additionally require their `Ponder` option to be enabled. The bot is returned for
+    /// chaining.
+    pub fn with_pondering(mut self) -> EngineBot {
+        self.pondering = true;
+        self
+    }
+
+    /// The engine which searches the moves of this bot.
+    pub fn engine(&self) -> &UciEngine {
+        &self.engine
+    }
+
+    /// The [SearchLimits] of searches in games of the given speed, i.e. the override set by the
+    /// operator, if any, or else the configured limits.
+    pub fn limits(&self, speed: Speed) -> SearchLimits {
+        self.overrides.lock().unwrap().get(&speed).copied()
+            .or_else(|| self.limits.get(&speed).copied())
+            .unwrap_or_default()
+    }
+
+    fn is_operator(&self, username: &str) -> bool {
+        self.operators.permission(username) >= Permission::Operator
+    }
+
+    /// Executes the given command for games of the given speed and returns the resulting limits.
+    fn execute(&self, command: LimitCommand, speed: Speed) -> SearchLimits {
+        match command {
+            LimitCommand::Show => {},
+            LimitCommand::Reset => {
+                self.overrides.lock().unwrap().remove(&speed);
+            },
+            command => {
+                let mut limits = self.limits(speed);
+                command.apply(&mut limits);
+                self.overrides.lock().unwrap().insert(speed, limits);
+            }
+        }
+
+        self.limits(speed)
+    }
+}
+
+#[async_trait::async_trait]
+impl Bot for EngineBot {
+
+    async fn on_my_turn(&self, context: &GameContext, state: GameStateEvent, client: &BotClient) {
+        // TODO enable error handling
+        let Ok(position) = Position::from_game_info(&context.info)
+        else {
+            return;
+        };
+        let fen = position.to_fen();
+        let clock = UciClock::from(&state);
+        let limits = self.limits(context.info.speed);
+        let Ok(best_move) = self.engine.best_move_with_limits(&context.info.id, &fen,
+            &state.moves, &clock, &limits).await
+        else {
+            return;
+        };
+
+        if context.make_move(client, best_move.mov.clone(), false).await.is_ok() && self.pondering {
+            let _ = self.engine.ponder(&context.info.id, &fen, &state.moves, &best_move, &clock)
+                .await;
+        }
+    }
+
+    async fn on_game_finish(&self, _: &BotContext, game: GameStartFinish, _: GameResult,
+            _: &BotClient) {
+        if let Some(game_id) = &game.id {
+            // TODO enable error handling
+            let _ = self.engine.stop_pondering_in(game_id).await;
+        }
+    }
+
+    async fn on_chat_line(&self, context: &GameContext, chat_line: ChatLineEvent,
+            client: &BotClient) {
+        let chat_line = chat_line.chat_line;
+
+        if !self.is_operator(&chat_line.username) {
+            return;
+        }
+
+        let Some(command) = LimitCommand::parse(&chat_line.text)
+        else {
+            return;
+        };
+        let speed = context.info.speed;
+        let limits = self.execute(command, speed);
+
+        // TODO enable error handling
+        let _ = client.send_chat_message(context.id.clone(), chat_line.room,
+            format!("{speed:?} limits: {limits}")).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{body_string_contains, method, path};
+
+    use crate::by_address::ByAddress;
+    use crate::model::game::{Color, GameInfo, GameStatus};
+    use crate::model::game::chat::{ChatLine, ChatRoom};
+    use crate::store::tests as store_tests;
+    use crate::test_util;
+
+    use super::*;
+
+    const FAKE_ENGINE: &str = r#"
+        while read line; do
+            case "$line" in
+                uci) echo "uciok";;
+                isready) echo "readyok";;
+                *"depth 6") echo "bestmove d2d4";;
+                "go "*) echo "bestmove e2e4";;
+                quit) exit 0;;
+            esac
+        done"#;
+
+    const PONDERING_ENGINE: &str = r#"
+        while read line; do
+            case "$line" in
+                uci) echo "uciok";;
+                isready) echo "readyok";;
+                "go ponder"*) pondering=1;;
+                "go "*) echo "bestmove e2e4 ponder e7e5";;
+                ponderhit) pondering=; echo "bestmove g1f3";;
+                stop) [ -n "$pondering" ] && echo "bestmove a2a3"; pondering=;;
+                quit) exit 0;;
+            esac
+        done"#;
+
+    async fn pondering_engine_bot() -> EngineBot {
+        let engine = UciEngine::start("sh", ["-c", PONDERING_ENGINE]).await.unwrap();
+
+        EngineBot::new(engine).with_pondering()
+    }
+
+    async fn mount_move(server: &MockServer, mov: &str) {
+        Mock::given(method("POST"))
+            .and(path(format!("/bot/game/testGameId/move/{mov}")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    fn finished_game() -> GameStartFinish {
+        GameStartFinish {
+            id: Some("testGameId".to_owned()),
+            source: None,
+            status: Some(GameStatus::Resign),
+            winner: Some(Color::White),
+            color: Some(Color::White),
+            rated: Some(false),
+            speed: Some(Speed::Rapid),
+            variant: None,
+            compat: None
+        }
+    }
+
+    async fn fake_engine_bot() -> EngineBot {
+        let engine = UciEngine::start("sh", ["-c", FAKE_ENGINE]).await.unwrap();
+
+        EngineBot::new(engine)
+            .with_limits(Speed::UltraBullet, SearchLimits {
+                depth: Some(6),
+                ..SearchLimits::default()
+            })
+            .with_operators(OperatorList::new().with_operator("Operator"))
+    }
+
+    fn test_context(speed: Speed) -> GameContext {
+        GameContext {
+            bot_id: "testBotId".to_owned(),
+            bot_color: Some(Color::White),
+            info: GameInfo {
+                speed,
+                initial_fen: "startpos".to_owned(),
+                ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    fn test_state() -> GameStateEvent {
+        GameStateEvent {
+            moves: String::new(),
+            white_time: 1000,
+            black_time: 1000,
+            white_increment: 0,
+            black_increment: 0,
+            status: GameStatus::Started,
+            winner: None,
+            white_draw_offer: false,
+            black_draw_offer: false,
+            white_take_back_proposal: false,
+            black_take_back_proposal: false,
+            skipped_states: 0
+        }
+    }
+
+    fn chat_line(username: &str, text: &str) -> ChatLineEvent {
+        ChatLineEvent {
+            chat_line: ChatLine {
+                room: ChatRoom::Player,
+                username: username.to_owned(),
+                text: text.to_owned()
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::depth("!depth 8", Some(LimitCommand::Depth(Some(8))))]
+    #[case::nodes("!nodes 100000", Some(LimitCommand::Nodes(Some(100000))))]
+    #[case::move_time(" !movetime 5000 ", Some(LimitCommand::MoveTime(Some(5000))))]
+    #[case::off("!depth off", Some(LimitCommand::Depth(None)))]
+    #[case::show("!limits", Some(LimitCommand::Show))]
+    #[case::reset("!reset", Some(LimitCommand::Reset))]
+    #[case::missing_argument("!depth", None)]
+    #[case::invalid_argument("!depth deep", None)]
+    #[case::extra_argument("!reset now", None)]
+    #[case::unknown("!resign", None)]
+    #[case::no_prefix("depth 8", None)]
+    fn parse_limit_command(#[case] text: &str, #[case] expected: Option<LimitCommand>) {
+        assert_that!(LimitCommand::parse(text)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn overrides_extend_configured_limits_until_reset() {
+        tokio_test::block_on(async {
+            let bot = fake_engine_bot().await;
+
+            let overridden = bot.execute(LimitCommand::MoveTime(Some(500)), Speed::UltraBullet);
+
+            assert_that!(overridden).is_equal_to(SearchLimits {
+                depth: Some(6),
+                nodes: None,
+                move_time_ms: Some(500)
+            });
+            assert_that!(bot.limits(Speed::Rapid)).is_equal_to(SearchLimits::default());
+            assert_that!(bot.execute(LimitCommand::Reset, Speed::UltraBullet))
+                .is_equal_to(bot.limits.get(&Speed::UltraBullet).copied().unwrap());
+        });
+    }
+
+    #[rstest]
+    #[case::limited(Speed::UltraBullet, "d2d4")]
+    #[case::unlimited(Speed::Rapid, "e2e4")]
+    fn plays_move_searched_with_limits_of_speed(#[case] speed: Speed, #[case] expected: &str) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let bot = fake_engine_bot().await;
+
+            Mock::given(method("POST"))
+                .and(path(format!("/bot/game/testGameId/move/{expected}")))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            bot.on_my_turn(&test_context(speed), test_state(), &client).await;
+        });
+    }
+
+    #[test]
+    fn ponder_search_is_continued_if_opponent_plays_predicted_reply() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let bot = pondering_engine_bot().await;
+            let context = test_context(Speed::Rapid);
+
+            mount_move(&server, "e2e4").await;
+            mount_move(&server, "g1f3").await;
+
+            bot.on_my_turn(&context, test_state(), &client).await;
+
+            assert_that!(bot.engine().is_pondering().await).is_true();
+
+            bot.on_my_turn(&context, GameStateEvent {
+                moves: "e2e4 e7e5".to_owned(),
+                ..test_state()
+            }, &client).await;
+
+            assert_that!(bot.engine().is_pondering().await).is_false();
+        });
+    }
+
+    #[test]
+    fn ponder_search_is_stopped_when_game_finishes() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let bot = pondering_engine_bot().await;
+            let bot_context = BotContext {
+                bot_id: "testBotId".to_owned(),
+                profile: ByAddress::default()
+            };
+            let game = finished_game();
+            let result = GameResult::new(&game, None, None);
+
+            mount_move(&server, "e2e4").await;
+
+            bot.on_my_turn(&test_context(Speed::Rapid), test_state(), &client).await;
+            bot.on_game_finish(&bot_context, game, result, &client).await;
+
+            assert_that!(bot.engine().is_pondering().await).is_false();
+        });
+    }
+
+    #[test]
+    fn no_pondering_unless_enabled() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let engine = UciEngine::start("sh", ["-c", PONDERING_ENGINE]).await.unwrap();
+            let bot = EngineBot::new(engine);
+
+            mount_move(&server, "e2e4").await;
+
+            bot.on_my_turn(&test_context(Speed::Rapid), test_state(), &client).await;
+
+            assert_that!(bot.engine().is_pondering().await).is_false();
+        });
+    }
+
+    #[rstest]
+    #[case::operator("operator", 1)]
+    #[case::other_user("opponent", 0)]
+    fn only_operator_can_override_limits(#[case] username: &str, #[case] expected_replies: u64) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let bot = fake_engine_bot().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/chat"))
+                .and(body_string_contains("depth+8"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(expected_replies)
+                .mount(&server)
+                .await;
+
+            bot.on_chat_line(&test_context(Speed::Blitz), chat_line(username, "!depth 8"), &client)
+                .await;
+
+            let expected_depth = if expected_replies > 0 { Some(8) } else { None };
+
+            assert_that!(bot.limits(Speed::Blitz).depth).is_equal_to(expected_depth);
+        });
+    }
+}
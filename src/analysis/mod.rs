@@ -0,0 +1,374 @@
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use thiserror::Error;
+
+use tokio::time::{self, Instant};
+
+use crate::analysis::uci_engine::UciEngine;
+use crate::chess::ChessError;
+use crate::chess::position::Position;
+use crate::client::BotClient;
+use crate::error::LibotRequestError;
+use crate::external_engine::ExternalEngineClient;
+use crate::model::analysis::Evaluation;
+use crate::model::external_engine::{
+    AnalysisUpdate,
+    ExternalEngine,
+    ExternalEngineWork,
+    UciVariant
+};
+use crate::model::game::Color;
+
+pub mod engine_bot;
+pub mod summary;
+pub mod uci_engine;
+pub mod work;
+
+/// The limit after which an analysis stops.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AnalysisLimit {
+
+    /// Analyse until the given search depth in half-moves is reached.
+    Depth(u32),
+
+    /// Analyse for the given amount of time.
+    Time(Duration)
+}
+
+#[derive(Debug, Error)]
+pub enum AnalysisError {
+
+    #[error("invalid FEN: {0}")]
+    InvalidFen(#[from] ChessError),
+
+    #[error("error requesting analysis: {0}")]
+    Request(#[from] LibotRequestError),
+
+    #[error("error communicating with local engine: {0}")]
+    Engine(#[from] io::Error),
+
+    #[error("no backend could evaluate the position")]
+    NoEvaluation
+}
+
+pub type AnalysisResult<T> = Result<T, AnalysisError>;
+
+struct ExternalEngineBackend {
+    client: ExternalEngineClient,
+    engine: ExternalEngine
+}
+
+/// A facade which evaluates positions using the best available backend. Backends are tried in the
+/// order cloud evaluation, external engine, local UCI engine, skipping those that are not
+/// configured. A backend that fails or cannot provide the requested analysis, e.g. because the
+/// position is not in the cloud evaluation database, is skipped in favor of the next.
+#[derive(Clone, Default)]
+pub struct Analyser {
+    cloud_eval: Option<BotClient>,
+    external_engine: Option<Arc<ExternalEngineBackend>>,
+    local_engine: Option<Arc<UciEngine>>
+}
+
+impl Analyser {
+
+    /// Creates a new analyser without any backends.
+    pub fn new() -> Analyser {
+        Analyser::default()
+    }
+
+    /// Uses the Lichess cloud evaluation database via the given client. Cloud evaluations are
+    /// only used if they reach the requested depth, or for any time limit. The analyser is
+    /// returned for chaining.
+    pub fn with_cloud_eval(mut self, client: BotClient) -> Analyser {
+        self.cloud_eval = Some(client);
+        self
+    }
+
+    /// Uses the given registered external engine, requesting analysis with the maximum number of
+    /// threads and hash size it supports. The analyser is returned for chaining.
+    pub fn with_external_engine(mut self, client: ExternalEngineClient, engine: ExternalEngine)
+            -> Analyser {
+        self.external_engine = Some(Arc::new(ExternalEngineBackend { client, engine }));
+        self
+    }
+
+    /// Uses the given local UCI engine. The analyser is returned for chaining.
+    pub fn with_local_engine(mut self, engine: UciEngine) -> Analyser {
+        self.local_engine = Some(Arc::new(engine));
+        self
+    }
+
+    async fn analyse_with_cloud_eval(&self, client: &BotClient, fen: &str, limit: AnalysisLimit,
+            multi_pv: u32) -> AnalysisResult<Option<Evaluation>> {
+        let Some(cloud_eval) = client.get_cloud_eval(fen, multi_pv).await?
+        else {
+            return Ok(None);
+        };
+        let deep_enough = match limit {
+            AnalysisLimit::Depth(depth) => cloud_eval.depth >= depth,
+            AnalysisLimit::Time(_) => true
+        };
+
+        if !deep_enough || cloud_eval.pvs.len() < multi_pv as usize {
+            return Ok(None);
+        }
+
+        Ok(Some(Evaluation::from(cloud_eval)))
+    }
+
+    async fn analyse_with_external_engine(&self, backend: &ExternalEngineBackend, fen: &str,
+            side_to_move: Color, limit: AnalysisLimit, multi_pv: u32)
+            -> AnalysisResult<Option<Evaluation>> {
+        let work = ExternalEngineWork {
+            session_id: format!("libot-{:016x}", rand::random::<u64>()),
+            threads: backend.engine.max_threads,
+            hash: backend.engine.max_hash,
+            infinite: matches!(limit, AnalysisLimit::Time(_)),
+            multi_pv,
+            variant: UciVariant::Chess,
+            initial_fen: fen.to_owned(),
+            moves: Vec::new()
+        };
+        let engine = &backend.engine;
+        let stream = backend.client.analyse(&engine.id, &engine.client_secret, &work).await?;
+        let mut stream = Box::pin(stream);
+        let deadline = match limit {
+            AnalysisLimit::Time(time) => Some(Instant::now() + time),
+            AnalysisLimit::Depth(_) => None
+        };
+        let mut last_update: Option<AnalysisUpdate> = None;
+
+        loop {
+            let next = match deadline {
+                Some(deadline) => match time::timeout_at(deadline, stream.next()).await {
+                    Ok(next) => next,
+                    Err(_) => break
+                },
+                None => stream.next().await
+            };
+            let Some(update) = next
+            else {
+                break;
+            };
+            let update = update?;
+            let done = matches!(limit, AnalysisLimit::Depth(depth) if update.depth >= depth);
+
+            last_update = Some(update);
+
+            if done {
+                break;
+            }
+        }
+
+        Ok(last_update.map(|update| Evaluation::from_analysis_update(update, side_to_move)))
+    }
+
+    async fn analyse_with_local_engine(&self, engine: &UciEngine, fen: &str,
+            side_to_move: Color, limit: AnalysisLimit, multi_pv: u32)
+            -> AnalysisResult<Option<Evaluation>> {
+        let infos = engine.analyse(fen, limit, multi_pv).await?;
+
+        Ok(Evaluation::from_uci_infos(infos, side_to_move))
+    }
+
+    /// Evaluates a position using the first configured backend that can provide the analysis.
+    ///
+    /// # Arguments
+    ///
+    /// * `fen`: The FEN of the position to evaluate.
+    /// * `limit`: The [AnalysisLimit] which the analysis should reach.
+    /// * `multi_pv`: The number of principal variations to compute.
+    ///
+    /// # Returns
+    ///
+    /// The [Evaluation] of the first backend that succeeded. All scores are given from White's
+    /// perspective.
+    ///
+    /// # Errors
+    ///
+    /// * [AnalysisError::InvalidFen] if the FEN cannot be parsed.
+    /// * The error of the last backend tried, if all configured backends failed.
+    /// * [AnalysisError::NoEvaluation] if no backend is configured or none could evaluate the
+    ///   position without failing.
+    pub async fn analyse_position(&self, fen: &str, limit: AnalysisLimit, multi_pv: u32)
+            -> AnalysisResult<Evaluation> {
+        let side_to_move = Position::from_fen(fen)?.side_to_move();
+        let multi_pv = multi_pv.max(1);
+        let mut error = None;
+
+        if let Some(client) = &self.cloud_eval {
+            match self.analyse_with_cloud_eval(client, fen, limit, multi_pv).await {
+                Ok(Some(evaluation)) => return Ok(evaluation),
+                Ok(None) => { },
+                Err(err) => error = Some(err)
+            }
+        }
+
+        if let Some(backend) = &self.external_engine {
+            match self.analyse_with_external_engine(backend, fen, side_to_move, limit, multi_pv)
+                    .await {
+                Ok(Some(evaluation)) => return Ok(evaluation),
+                Ok(None) => { },
+                Err(err) => error = Some(err)
+            }
+        }
+
+        if let Some(engine) = &self.local_engine {
+            match self.analyse_with_local_engine(engine, fen, side_to_move, limit, multi_pv)
+                    .await {
+                Ok(Some(evaluation)) => return Ok(evaluation),
+                Ok(None) => { },
+                Err(err) => error = Some(err)
+            }
+        }
+
+        Err(error.unwrap_or(AnalysisError::NoEvaluation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use serde_json::json;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{method, path};
+
+    use crate::model::analysis::{EvaluationSource, Score};
+    use crate::test_util;
+
+    use super::*;
+
+    const BLACK_TO_MOVE_FEN: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+
+    const FAKE_ENGINE: &str = r#"
+        while read line; do
+            case "$line" in
+                uci) echo "uciok";;
+                isready) echo "readyok";;
+                go*) echo "info depth 8 score cp 40 pv c7c5"; echo "bestmove c7c5";;
+            esac
+        done"#;
+
+    fn test_engine() -> ExternalEngine {
+        ExternalEngine {
+            id: "testEngineId".to_owned(),
+            name: "Engine".to_owned(),
+            client_secret: "testClientSecret".to_owned(),
+            user_id: "testUserId".to_owned(),
+            max_threads: 2,
+            max_hash: 128,
+            variants: vec![UciVariant::Chess],
+            provider_data: None
+        }
+    }
+
+    async fn mount_cloud_eval(server: &MockServer, depth: u32) {
+        Mock::given(method("GET"))
+            .and(path("/cloud-eval"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "fen": BLACK_TO_MOVE_FEN,
+                "knodes": 100,
+                "depth": depth,
+                "pvs": [{ "moves": "e7e5 g1f3", "cp": 20 }]
+            })))
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_external_engine(server: &MockServer) {
+        let body = concat!(
+            r#"{"time":10,"depth":5,"nodes":100,"pvs":[{"depth":5,"cp":15,"moves":["d7d5"]}]}"#,
+            "\n",
+            r#"{"time":20,"depth":10,"nodes":200,"pvs":[{"depth":10,"mate":2,"moves":["d8h4"]}]}"#,
+            "\n");
+
+        Mock::given(method("POST"))
+            .and(path("/external-engine/testEngineId/analyse"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(server)
+            .await;
+    }
+
+    #[rstest]
+    #[case::deep_cloud_eval(30, EvaluationSource::CloudEval, Score::Centipawns(20))]
+    #[case::shallow_cloud_eval(5, EvaluationSource::ExternalEngine, Score::Mate(-2))]
+    fn cloud_eval_is_preferred_if_deep_enough(#[case] cloud_depth: u32,
+            #[case] expected_source: EvaluationSource, #[case] expected_score: Score) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            mount_cloud_eval(&server, cloud_depth).await;
+            mount_external_engine(&server).await;
+            let external_engine_client = ExternalEngineClient::new().with_base_url(server.uri());
+            let analyser = Analyser::new()
+                .with_cloud_eval(client)
+                .with_external_engine(external_engine_client, test_engine());
+
+            let evaluation = analyser
+                .analyse_position(BLACK_TO_MOVE_FEN, AnalysisLimit::Depth(10), 1)
+                .await
+                .unwrap();
+
+            assert_that!(evaluation.source).is_equal_to(expected_source);
+            assert_that!(evaluation.score()).contains(expected_score);
+        });
+    }
+
+    #[test]
+    fn local_engine_is_used_if_other_backends_fail() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("GET"))
+                .and(path("/cloud-eval"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            let engine = UciEngine::start("sh", ["-c", FAKE_ENGINE]).await.unwrap();
+            let analyser = Analyser::new()
+                .with_cloud_eval(client)
+                .with_external_engine(
+                    ExternalEngineClient::new().with_base_url(server.uri()), test_engine())
+                .with_local_engine(engine);
+
+            let evaluation = analyser
+                .analyse_position(BLACK_TO_MOVE_FEN, AnalysisLimit::Depth(8), 1)
+                .await
+                .unwrap();
+
+            assert_that!(evaluation.source).is_equal_to(EvaluationSource::LocalEngine);
+            assert_that!(evaluation.depth).is_equal_to(8);
+            assert_that!(evaluation.score()).contains(Score::Centipawns(-40));
+            assert_that!(evaluation.best_move()).contains(&"c7c5".to_owned());
+        });
+    }
+
+    #[test]
+    fn analyse_position_fails_without_backends() {
+        tokio_test::block_on(async {
+            let result = Analyser::new()
+                .analyse_position(BLACK_TO_MOVE_FEN, AnalysisLimit::Depth(1), 1)
+                .await;
+
+            assert_that!(matches!(result, Err(AnalysisError::NoEvaluation))).is_true();
+        });
+    }
+
+    #[test]
+    fn analyse_position_fails_for_invalid_fen() {
+        tokio_test::block_on(async {
+            let result = Analyser::new()
+                .analyse_position("invalid", AnalysisLimit::Depth(1), 1)
+                .await;
+
+            assert_that!(matches!(result, Err(AnalysisError::InvalidFen(_)))).is_true();
+        });
+    }
+}
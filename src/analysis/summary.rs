@@ -0,0 +1,286 @@
+use crate::model::analysis::Score;
+use crate::model::game::Color;
+use crate::model::game::export::{ExportedGame, ExportedPlayerAnalysis};
+
+/// The evaluation in centipawns at which scores are capped, so that missed mates or large
+/// material gains in won positions do not dominate the average centipawn loss. Mates count as
+/// this evaluation.
+pub const CENTIPAWN_CAP: i32 = 1000;
+
+/// The minimum drop in winning chances, on a scale from -1 to 1, which makes a move an
+/// inaccuracy, mistake or blunder, respectively, as used by Lichess.
+const INACCURACY_THRESHOLD: f64 = 0.1;
+const MISTAKE_THRESHOLD: f64 = 0.2;
+const BLUNDER_THRESHOLD: f64 = 0.3;
+
+fn capped_centipawns(score: Score) -> i32 {
+    match score {
+        Score::Centipawns(centipawns) => centipawns.clamp(-CENTIPAWN_CAP, CENTIPAWN_CAP),
+        Score::Mate(moves) if moves > 0 => CENTIPAWN_CAP,
+        Score::Mate(_) => -CENTIPAWN_CAP
+    }
+}
+
+/// The winning chances of the side with the given evaluation, from -1 (certain loss) to 1
+/// (certain win), using the same model as Lichess.
+fn winning_chances(centipawns: i32) -> f64 {
+    2.0 / (1.0 + (-0.00368208 * centipawns as f64).exp()) - 1.0
+}
+
+/// The accuracy of a single move in percent, given the winning chances of the moving side before
+/// and after the move, using the same model as Lichess.
+fn move_accuracy(chances_before: f64, chances_after: f64) -> f64 {
+    let win_percent_drop = 50.0 * (chances_before - chances_after);
+
+    (103.1668 * (-0.04354 * win_percent_drop.max(0.0)).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// The summary of the analysis of the moves of one player in a game.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PlayerSummary {
+    pub inaccuracies: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+
+    /// The average centipawn loss of the player's moves.
+    pub average_centipawn_loss: u32,
+
+    /// The accuracy of the player's moves in percent, if known.
+    pub accuracy: Option<u32>
+}
+
+impl From<ExportedPlayerAnalysis> for PlayerSummary {
+    fn from(analysis: ExportedPlayerAnalysis) -> PlayerSummary {
+        PlayerSummary {
+            inaccuracies: analysis.inaccuracy,
+            mistakes: analysis.mistake,
+            blunders: analysis.blunder,
+            average_centipawn_loss: analysis.acpl,
+            accuracy: analysis.accuracy
+        }
+    }
+}
+
+/// Accumulates the moves of one player into a [PlayerSummary].
+#[derive(Default)]
+struct PlayerTally {
+    summary: PlayerSummary,
+    moves: u32,
+    centipawn_loss: i64,
+    accuracy: f64
+}
+
+impl PlayerTally {
+
+    fn add_move(&mut self, before: i32, after: i32) {
+        let chances_before = winning_chances(before);
+        let chances_after = winning_chances(after);
+        let chances_drop = chances_before - chances_after;
+
+        if chances_drop >= BLUNDER_THRESHOLD {
+            self.summary.blunders += 1;
+        }
+        else if chances_drop >= MISTAKE_THRESHOLD {
+            self.summary.mistakes += 1;
+        }
+        else if chances_drop >= INACCURACY_THRESHOLD {
+            self.summary.inaccuracies += 1;
+        }
+
+        self.moves += 1;
+        self.centipawn_loss += i64::from((before - after).max(0));
+        self.accuracy += move_accuracy(chances_before, chances_after);
+    }
+
+    fn finish(self) -> PlayerSummary {
+        if self.moves == 0 {
+            return self.summary;
+        }
+
+        let moves = f64::from(self.moves);
+
+        PlayerSummary {
+            average_centipawn_loss: (self.centipawn_loss as f64 / moves).round() as u32,
+            accuracy: Some((self.accuracy / moves).round() as u32),
+            ..self.summary
+        }
+    }
+}
+
+/// The summary of the analysis of a game for both players, which can be obtained from the server
+/// analysis of Lichess or computed from the evaluations of all positions of the game.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct GameSummary {
+    pub white: PlayerSummary,
+    pub black: PlayerSummary
+}
+
+impl GameSummary {
+
+    /// Gets the summary of the player of the given color.
+    pub fn of(&self, color: Color) -> &PlayerSummary {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black
+        }
+    }
+
+    /// Extracts the summary of the server analysis from an exported game, as returned by
+    /// [BotClient::export_game_with_analysis](crate::client::BotClient::export_game_with_analysis).
+    ///
+    /// # Returns
+    ///
+    /// The summary, or [None] if the game was not analysed by Lichess.
+    pub fn from_exported_game(game: &ExportedGame) -> Option<GameSummary> {
+        Some(GameSummary {
+            white: game.players.white.analysis?.into(),
+            black: game.players.black.analysis?.into()
+        })
+    }
+
+    /// Computes the summary of a game from the evaluations of all its positions. Moves are judged
+    /// by the drop in winning chances and the accuracy is computed per move as Lichess does, but
+    /// averaged without weights, so it may differ slightly from the accuracy shown by Lichess.
+    ///
+    /// # Arguments
+    ///
+    /// * `scores`: The scores of the initial position and the positions after every move, from
+    ///   White's perspective.
+    /// * `first_mover`: The player who moves in the initial position.
+    ///
+    /// # Returns
+    ///
+    /// The summary, or [None] if no move was evaluated, i.e. fewer than two scores are given.
+    pub fn from_scores(scores: &[Score], first_mover: Color) -> Option<GameSummary> {
+        if scores.len() < 2 {
+            return None;
+        }
+
+        let mut white = PlayerTally::default();
+        let mut black = PlayerTally::default();
+        let mut mover = first_mover;
+
+        for window in scores.windows(2) {
+            let before = capped_centipawns(window[0].relative_to(mover));
+            let after = capped_centipawns(window[1].relative_to(mover));
+
+            match mover {
+                Color::White => white.add_move(before, after),
+                Color::Black => black.add_move(before, after)
+            }
+
+            mover = mover.opposite();
+        }
+
+        Some(GameSummary {
+            white: white.finish(),
+            black: black.finish()
+        })
+    }
+
+    /// Formats this summary as a chat message which fits into the length limit of Lichess chat
+    /// messages, marking inaccuracies, mistakes and blunders with `?!`, `?` and `??`.
+    pub fn to_chat_message(&self) -> String {
+        let describe = |color: Color| {
+            let summary = self.of(color);
+            let accuracy = summary.accuracy
+                .map(|accuracy| format!("{accuracy}% accuracy, "))
+                .unwrap_or_default();
+
+            format!("{color:?}: {accuracy}ACPL {} (?! {}, ? {}, ?? {})",
+                summary.average_centipawn_loss, summary.inaccuracies, summary.mistakes,
+                summary.blunders)
+        };
+
+        format!("{}. {}.", describe(Color::White), describe(Color::Black))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::chat_throttle::MAX_CHAT_MESSAGE_LENGTH;
+
+    use super::*;
+
+    #[rstest]
+    #[case::equal(0, 0.0)]
+    #[case::winning(1000, 0.95)]
+    #[case::losing(-1000, -0.95)]
+    fn winning_chances_are_symmetric(#[case] centipawns: i32, #[case] expected: f64) {
+        assert_that!(winning_chances(centipawns)).is_close_to(expected, 0.01);
+    }
+
+    #[test]
+    fn perfect_moves_have_full_accuracy() {
+        let scores = [Score::Centipawns(20), Score::Centipawns(20), Score::Centipawns(20)];
+
+        let summary = GameSummary::from_scores(&scores, Color::White).unwrap();
+
+        assert_that!(summary.white).is_equal_to(PlayerSummary {
+            accuracy: Some(100),
+            ..PlayerSummary::default()
+        });
+        assert_that!(summary.black).is_equal_to(summary.white);
+    }
+
+    #[test]
+    fn moves_are_judged_by_drop_in_winning_chances() {
+        let scores = [
+            Score::Centipawns(0),
+            Score::Centipawns(-100), // White: drop of 0.18 => inaccuracy
+            Score::Centipawns(-100),
+            Score::Centipawns(-400), // White: drop of 0.44 => blunder
+            Score::Centipawns(-100), // Black: drop of 0.44 => blunder
+            Score::Mate(-1)          // White: from -100 to -1000 => blunder
+        ];
+
+        let summary = GameSummary::from_scores(&scores, Color::White).unwrap();
+
+        assert_that!(summary.white.inaccuracies).is_equal_to(1);
+        assert_that!(summary.white.blunders).is_equal_to(2);
+        assert_that!(summary.white.average_centipawn_loss).is_equal_to(433);
+        assert_that!(summary.black.blunders).is_equal_to(1);
+        assert_that!(summary.black.average_centipawn_loss).is_equal_to(150);
+        assert_that!(summary.white.accuracy.unwrap()).is_less_than(summary.black.accuracy.unwrap());
+    }
+
+    #[test]
+    fn first_mover_black_is_attributed_correctly() {
+        let scores = [Score::Centipawns(0), Score::Centipawns(500)];
+
+        let summary = GameSummary::from_scores(&scores, Color::Black).unwrap();
+
+        assert_that!(summary.black.blunders).is_equal_to(1);
+        assert_that!(summary.white).is_equal_to(PlayerSummary::default());
+    }
+
+    #[test]
+    fn no_summary_without_moves() {
+        assert_that!(GameSummary::from_scores(&[Score::Centipawns(0)], Color::White)).is_none();
+    }
+
+    #[test]
+    fn chat_message_fits_into_chat() {
+        let player = PlayerSummary {
+            inaccuracies: 12,
+            mistakes: 10,
+            blunders: 11,
+            average_centipawn_loss: 1000,
+            accuracy: Some(100)
+        };
+        let summary = GameSummary {
+            white: player,
+            black: player
+        };
+
+        let message = summary.to_chat_message();
+
+        assert_that!(message.len()).is_less_than_or_equal_to(MAX_CHAT_MESSAGE_LENGTH);
+        assert_that!(message.as_str()).starts_with("White: 100% accuracy, ACPL 1000 (?! 12, ? 10");
+    }
+}
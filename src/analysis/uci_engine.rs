@@ -0,0 +1,709 @@
+use std::ffi::OsStr;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, ErrorKind};
+use std::process::Stdio;
+
+use serde::Deserialize;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+use crate::analysis::AnalysisLimit;
+use crate::model::Milliseconds;
+use crate::model::analysis::UciInfo;
+use crate::model::game::GameId;
+use crate::model::game::event::GameStateEvent;
+
+/// The clocks of both players of a game, which are sent to the engine with every search so it can
+/// manage its time.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct UciClock {
+    pub white_time: Milliseconds,
+    pub black_time: Milliseconds,
+    pub white_increment: Milliseconds,
+    pub black_increment: Milliseconds
+}
+
+impl UciClock {
+
+    fn go_arguments(&self) -> String {
+        format!("wtime {} btime {} winc {} binc {}",
+            self.white_time.max(0), self.black_time.max(0),
+            self.white_increment.max(0), self.black_increment.max(0))
+    }
+}
+
+impl From<&GameStateEvent> for UciClock {
+    fn from(state: &GameStateEvent) -> UciClock {
+        UciClock {
+            white_time: state.white_time,
+            black_time: state.black_time,
+            white_increment: state.white_increment,
+            black_increment: state.black_increment
+        }
+    }
+}
+
+/// Limits of the search of a [UciEngine] for the best move in a game, which apply in addition to
+/// the clock. The search ends as soon as any of the set limits is reached. Limits which are not set
+/// leave the time management to the engine.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchLimits {
+
+    /// The maximum search depth in half-moves.
+    pub depth: Option<u32>,
+
+    /// The maximum number of nodes to search.
+    pub nodes: Option<u64>,
+
+    /// The maximum time to spend on the move, in milliseconds.
+    pub move_time_ms: Option<Milliseconds>
+}
+
+impl SearchLimits {
+
+    /// Indicates whether no limit is set, i.e. the engine manages its time on its own.
+    pub fn is_unlimited(&self) -> bool {
+        self.depth.is_none() && self.nodes.is_none() && self.move_time_ms.is_none()
+    }
+
+    fn go_arguments(&self) -> String {
+        let mut arguments = String::new();
+
+        if let Some(depth) = self.depth {
+            arguments.push_str(&format!(" depth {depth}"));
+        }
+
+        if let Some(nodes) = self.nodes {
+            arguments.push_str(&format!(" nodes {nodes}"));
+        }
+
+        if let Some(move_time_ms) = self.move_time_ms {
+            arguments.push_str(&format!(" movetime {}", move_time_ms.max(0)));
+        }
+
+        arguments
+    }
+}
+
+impl Display for SearchLimits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_unlimited() {
+            return write!(f, "clock only");
+        }
+
+        write!(f, "{}", self.go_arguments().trim_start())
+    }
+}
+
+/// The result of a search of a [UciEngine] for the best move in a game.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BestMove {
+
+    /// The best move in UCI notation.
+    pub mov: String,
+
+    /// The reply the engine expects from the opponent, on which it can ponder (see
+    /// [UciEngine::ponder]), if the engine reported one.
+    pub ponder: Option<String>
+}
+
+impl BestMove {
+
+    fn parse(line: &str) -> Option<BestMove> {
+        let mut tokens = line.split_whitespace();
+
+        if tokens.next() != Some("bestmove") {
+            return None;
+        }
+
+        let mov = tokens.next()?.to_owned();
+        let ponder = match (tokens.next(), tokens.next()) {
+            (Some("ponder"), Some(ponder)) => Some(ponder.to_owned()),
+            _ => None
+        };
+
+        Some(BestMove {
+            mov,
+            ponder
+        })
+    }
+}
+
+/// The position on which the engine currently ponders, given by the game in which it occurs and
+/// the moves played in that game, including the bot's move and the predicted reply.
+struct PonderPosition {
+    game_id: GameId,
+    moves: Vec<String>
+}
+
+fn split_moves(moves: &str) -> Vec<String> {
+    moves.split_whitespace().map(str::to_owned).collect()
+}
+
+fn position_command(fen: &str, moves: &[String]) -> String {
+    if moves.is_empty() {
+        format!("position fen {fen}")
+    }
+    else {
+        format!("position fen {fen} moves {}", moves.join(" "))
+    }
+}
+
+struct EngineProcess {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    pondering: Option<PonderPosition>
+}
+
+impl EngineProcess {
+
+    async fn send(&mut self, command: &str) -> io::Result<()> {
+        self.stdin.write_all(format!("{command}\n").as_bytes()).await?;
+        self.stdin.flush().await
+    }
+
+    async fn read_line(&mut self) -> io::Result<String> {
+        self.stdout.next_line().await?
+            .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "engine closed its output"))
+    }
+
+    async fn read_until(&mut self, prefix: &str) -> io::Result<String> {
+        loop {
+            let line = self.read_line().await?;
+
+            if line.starts_with(prefix) {
+                return Ok(line);
+            }
+        }
+    }
+
+    async fn read_best_move(&mut self) -> io::Result<BestMove> {
+        let line = self.read_until("bestmove").await?;
+
+        BestMove::parse(&line).ok_or_else(|| io::Error::new(ErrorKind::InvalidData,
+            format!("invalid best move: {line}")))
+    }
+
+    /// Stops the ponder search, if any, and discards its result, so the engine is ready for new
+    /// commands.
+    async fn stop_pondering(&mut self) -> io::Result<()> {
+        if self.pondering.take().is_some() {
+            self.send("stop").await?;
+            self.read_until("bestmove").await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A chess engine running as a local process, which communicates using the Universal Chess
+/// Interface (UCI). The process is killed when the engine is dropped.
+pub struct UciEngine {
+    process: Mutex<EngineProcess>,
+    name: Option<String>
+}
+
+impl UciEngine {
+
+    /// Starts the engine process and initializes UCI mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `program`: The path of the engine executable.
+    /// * `args`: The command line arguments to pass to the engine.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while starting the process or communicating with it, e.g. if
+    /// the engine terminates before confirming UCI mode.
+    pub async fn start<I, S>(program: impl AsRef<OsStr>, args: I) -> io::Result<UciEngine>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>
+    {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take()
+            .ok_or_else(|| io::Error::new(ErrorKind::BrokenPipe, "engine has no input"))?;
+        let stdout = child.stdout.take()
+            .ok_or_else(|| io::Error::new(ErrorKind::BrokenPipe, "engine has no output"))?;
+        let mut process = EngineProcess {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            pondering: None
+        };
+        let mut name = None;
+
+        process.send("uci").await?;
+
+        loop {
+            let line = process.read_line().await?;
+
+            if let Some(engine_name) = line.strip_prefix("id name ") {
+                name = Some(engine_name.to_owned());
+            }
+            else if line.trim() == "uciok" {
+                break;
+            }
+        }
+
+        process.send("isready").await?;
+        process.read_until("readyok").await?;
+
+        Ok(UciEngine {
+            process: Mutex::new(process),
+            name
+        })
+    }
+
+    /// The name the engine reported during initialization, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Sets a UCI option of the engine, such as `Threads` or `Hash`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the option to set.
+    /// * `value`: The new value of the option.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while communicating with the engine.
+    pub async fn set_option(&self, name: &str, value: &str) -> io::Result<()> {
+        let mut process = self.process.lock().await;
+
+        process.stop_pondering().await?;
+        process.send(&format!("setoption name {name} value {value}")).await?;
+        process.send("isready").await?;
+        process.read_until("readyok").await?;
+
+        Ok(())
+    }
+
+    /// Analyses a position until the given limit is reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `fen`: The FEN of the position to analyse.
+    /// * `limit`: The [AnalysisLimit] after which to stop.
+    /// * `multi_pv`: The number of principal variations to compute.
+    ///
+    /// # Returns
+    ///
+    /// The last reported [UciInfo] of each principal variation, best first.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while communicating with the engine.
+    pub async fn analyse(&self, fen: &str, limit: AnalysisLimit, multi_pv: u32)
+            -> io::Result<Vec<UciInfo>> {
+        let mut process = self.process.lock().await;
+        let go = match limit {
+            AnalysisLimit::Depth(depth) => format!("go depth {depth}"),
+            AnalysisLimit::Time(time) => format!("go movetime {}", time.as_millis())
+        };
+        let mut lines: Vec<UciInfo> = Vec::new();
+
+        process.stop_pondering().await?;
+        process.send(&format!("setoption name MultiPV value {multi_pv}")).await?;
+        process.send(&format!("position fen {fen}")).await?;
+        process.send(&go).await?;
+
+        loop {
+            let line = process.read_line().await?;
+
+            if line.starts_with("bestmove") {
+                break;
+            }
+
+            if let Some(info) = UciInfo::parse(&line) {
+                lines.retain(|line| line.multi_pv != info.multi_pv);
+                lines.push(info);
+            }
+        }
+
+        lines.sort_by_key(|line| line.multi_pv);
+
+        Ok(lines)
+    }
+
+    /// Searches the best move in a game, letting the engine manage its time according to the
+    /// given clock. If the engine ponders on exactly this position of the same game (see
+    /// [UciEngine::ponder]), i.e. the opponent played the predicted reply, the ponder search is
+    /// continued with `ponderhit`, so the time spent pondering is not lost. Otherwise, any ponder
+    /// search is stopped and a new search is started. Since the ponder search is identified by the
+    /// game, pondering in one game never answers a search in another game which reached the same
+    /// moves.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game in which to search.
+    /// * `fen`: The FEN of the initial position of the game.
+    /// * `moves`: The moves played so far in UCI notation, separated by spaces.
+    /// * `clock`: The current [UciClock] of the game.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while communicating with the engine, including the case that
+    /// the engine reports an invalid best move.
+    pub async fn best_move(&self, game_id: &GameId, fen: &str, moves: &str, clock: &UciClock)
+            -> io::Result<BestMove> {
+        self.best_move_with_limits(game_id, fen, moves, clock, &SearchLimits::default()).await
+    }
+
+    /// Searches the best move in a game like [UciEngine::best_move], but ends the search once any
+    /// of the given [SearchLimits] is reached. A continued ponder search is not limited, since it
+    /// was started without the limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game in which to search.
+    /// * `fen`: The FEN of the initial position of the game.
+    /// * `moves`: The moves played so far in UCI notation, separated by spaces.
+    /// * `clock`: The current [UciClock] of the game.
+    /// * `limits`: The [SearchLimits] of a new search.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while communicating with the engine, including the case that
+    /// the engine reports an invalid best move.
+    pub async fn best_move_with_limits(&self, game_id: &GameId, fen: &str, moves: &str,
+            clock: &UciClock, limits: &SearchLimits) -> io::Result<BestMove> {
+        let mut process = self.process.lock().await;
+        let moves = split_moves(moves);
+
+        match process.pondering.take() {
+            Some(ponder) if &ponder.game_id == game_id && ponder.moves == moves => {
+                process.send("ponderhit").await?;
+                return process.read_best_move().await;
+            },
+            Some(_) => {
+                process.send("stop").await?;
+                process.read_until("bestmove").await?;
+            },
+            None => { }
+        }
+
+        process.send(&position_command(fen, &moves)).await?;
+        process.send(&format!("go {}{}", clock.go_arguments(), limits.go_arguments())).await?;
+        process.read_best_move().await
+    }
+
+    /// Starts pondering on the reply the engine predicted with the given [BestMove], i.e. searches
+    /// the position after the bot's move and the predicted reply on the opponent's time. The
+    /// search continues until the next call of [UciEngine::best_move], which uses it if the
+    /// opponent played the predicted reply, or any other command. Some engines only ponder if
+    /// their `Ponder` option is enabled with [UciEngine::set_option].
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game in which to ponder.
+    /// * `fen`: The FEN of the initial position of the game.
+    /// * `moves`: The moves played before the bot's move in UCI notation, separated by spaces.
+    /// * `best_move`: The move of the bot, as returned by [UciEngine::best_move].
+    /// * `clock`: The [UciClock] of the game after the bot's move.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the engine started pondering and `false` if the best move contains no predicted
+    /// reply.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while communicating with the engine.
+    pub async fn ponder(&self, game_id: &GameId, fen: &str, moves: &str, best_move: &BestMove,
+            clock: &UciClock) -> io::Result<bool> {
+        let Some(predicted) = &best_move.ponder
+        else {
+            return Ok(false);
+        };
+        let mut process = self.process.lock().await;
+        let mut moves = split_moves(moves);
+        moves.push(best_move.mov.clone());
+        moves.push(predicted.clone());
+
+        process.stop_pondering().await?;
+        process.send(&position_command(fen, &moves)).await?;
+        process.send(&format!("go ponder {}", clock.go_arguments())).await?;
+        process.pondering = Some(PonderPosition {
+            game_id: game_id.clone(),
+            moves
+        });
+
+        Ok(true)
+    }
+
+    /// Indicates whether the engine currently ponders (see [UciEngine::ponder]).
+    pub async fn is_pondering(&self) -> bool {
+        self.process.lock().await.pondering.is_some()
+    }
+
+    /// Stops pondering, if the engine currently ponders, e.g. because the game finished.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while communicating with the engine.
+    pub async fn stop_pondering(&self) -> io::Result<()> {
+        self.process.lock().await.stop_pondering().await
+    }
+
+    /// Stops pondering like [UciEngine::stop_pondering], but only if the engine currently ponders
+    /// in the game with the given ID, so a ponder search in another game is not interrupted.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while communicating with the engine.
+    pub async fn stop_pondering_in(&self, game_id: &GameId) -> io::Result<()> {
+        let mut process = self.process.lock().await;
+
+        if process.pondering.as_ref().is_some_and(|ponder| &ponder.game_id == game_id) {
+            process.stop_pondering().await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::analysis::Score;
+
+    use super::*;
+
+    const FAKE_ENGINE: &str = r#"
+        while read line; do
+            case "$line" in
+                uci) echo "id name Fake Engine"; echo "uciok";;
+                isready) echo "readyok";;
+                "go depth"*|"go movetime"*)
+                    echo "info depth 1 multipv 1 score cp 10 nodes 20 pv e2e4"
+                    echo "info depth 1 multipv 2 score cp 5 nodes 20 pv d2d4"
+                    echo "info string some output"
+                    echo "info depth 2 multipv 1 score cp 30 nodes 80 pv e2e4 e7e5"
+                    echo "bestmove e2e4";;
+                quit) exit 0;;
+            esac
+        done"#;
+
+    const PONDERING_ENGINE: &str = r#"
+        while read line; do
+            case "$line" in
+                uci) echo "uciok";;
+                isready) echo "readyok";;
+                "go ponder"*) pondering=1;;
+                "go wtime 1000 btime 2000 winc 10 binc 0") echo "bestmove e2e4 ponder e7e5";;
+                "go wtime 1000 btime 2000 winc 10 binc 0 depth 6 movetime 500")
+                    echo "bestmove d2d4";;
+                ponderhit) pondering=; echo "bestmove g1f3 ponder b8c6";;
+                stop) [ -n "$pondering" ] && echo "bestmove a2a3"; pondering=;;
+                quit) exit 0;;
+            esac
+        done"#;
+
+    const GAME_ID: &str = "testGameId";
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    const CLOCK: UciClock = UciClock {
+        white_time: 1000,
+        black_time: 2000,
+        white_increment: 10,
+        black_increment: 0
+    };
+
+    fn game_id() -> GameId {
+        GAME_ID.to_owned()
+    }
+
+    async fn fake_engine() -> UciEngine {
+        UciEngine::start("sh", ["-c", FAKE_ENGINE]).await.unwrap()
+    }
+
+    async fn pondering_engine() -> UciEngine {
+        UciEngine::start("sh", ["-c", PONDERING_ENGINE]).await.unwrap()
+    }
+
+    fn best_move(mov: &str, ponder: Option<&str>) -> BestMove {
+        BestMove {
+            mov: mov.to_owned(),
+            ponder: ponder.map(str::to_owned)
+        }
+    }
+
+    #[rstest]
+    #[case::with_ponder("bestmove e2e4 ponder e7e5", Some(best_move("e2e4", Some("e7e5"))))]
+    #[case::without_ponder("bestmove e2e4", Some(best_move("e2e4", None)))]
+    #[case::no_move("bestmove", None)]
+    #[case::other_line("info depth 1", None)]
+    fn parse_best_move(#[case] line: &str, #[case] expected: Option<BestMove>) {
+        assert_that!(BestMove::parse(line)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn best_move_searches_with_clock() {
+        tokio_test::block_on(async {
+            let engine = pondering_engine().await;
+
+            let result = engine.best_move(&game_id(), START_FEN, "", &CLOCK).await.unwrap();
+
+            assert_that!(result).is_equal_to(best_move("e2e4", Some("e7e5")));
+            assert_that!(engine.is_pondering().await).is_false();
+        });
+    }
+
+    #[test]
+    fn best_move_with_limits_sends_limits_after_clock() {
+        tokio_test::block_on(async {
+            let engine = pondering_engine().await;
+            let limits = SearchLimits {
+                depth: Some(6),
+                move_time_ms: Some(500),
+                ..SearchLimits::default()
+            };
+
+            let result = engine.best_move_with_limits(&game_id(), START_FEN, "", &CLOCK, &limits)
+                .await
+                .unwrap();
+
+            assert_that!(result).is_equal_to(best_move("d2d4", None));
+        });
+    }
+
+    #[rstest]
+    #[case::unlimited(SearchLimits::default(), "clock only")]
+    #[case::depth(SearchLimits { depth: Some(6), ..SearchLimits::default() }, "depth 6")]
+    #[case::all(SearchLimits { depth: Some(6), nodes: Some(1000), move_time_ms: Some(5000) },
+        "depth 6 nodes 1000 movetime 5000")]
+    fn display_search_limits(#[case] limits: SearchLimits, #[case] expected: &str) {
+        assert_that!(limits.to_string()).is_equal_to(expected.to_owned());
+    }
+
+    #[test]
+    fn ponder_hit_continues_ponder_search() {
+        tokio_test::block_on(async {
+            let engine = pondering_engine().await;
+            let game_id = game_id();
+            let first = engine.best_move(&game_id, START_FEN, "", &CLOCK).await.unwrap();
+
+            let pondering = engine.ponder(&game_id, START_FEN, "", &first, &CLOCK).await.unwrap();
+            let second = engine.best_move(&game_id, START_FEN, "e2e4 e7e5", &CLOCK).await.unwrap();
+
+            assert_that!(pondering).is_true();
+            assert_that!(second).is_equal_to(best_move("g1f3", Some("b8c6")));
+            assert_that!(engine.is_pondering().await).is_false();
+        });
+    }
+
+    #[test]
+    fn ponder_miss_stops_ponder_search_and_searches_again() {
+        tokio_test::block_on(async {
+            let engine = pondering_engine().await;
+            let game_id = game_id();
+            let first = engine.best_move(&game_id, START_FEN, "", &CLOCK).await.unwrap();
+
+            engine.ponder(&game_id, START_FEN, "", &first, &CLOCK).await.unwrap();
+            let second = engine.best_move(&game_id, START_FEN, "e2e4 c7c5", &CLOCK).await.unwrap();
+
+            assert_that!(second).is_equal_to(best_move("e2e4", Some("e7e5")));
+        });
+    }
+
+    #[test]
+    fn ponder_search_of_other_game_with_same_moves_is_not_continued() {
+        tokio_test::block_on(async {
+            let engine = pondering_engine().await;
+            let first = engine.best_move(&game_id(), START_FEN, "", &CLOCK).await.unwrap();
+
+            engine.ponder(&game_id(), START_FEN, "", &first, &CLOCK).await.unwrap();
+            let other = engine.best_move(&"otherGameId".to_owned(), START_FEN, "e2e4 e7e5", &CLOCK)
+                .await
+                .unwrap();
+
+            assert_that!(other).is_equal_to(best_move("e2e4", Some("e7e5")));
+            assert_that!(engine.is_pondering().await).is_false();
+        });
+    }
+
+    #[test]
+    fn stop_pondering_in_other_game_keeps_ponder_search() {
+        tokio_test::block_on(async {
+            let engine = pondering_engine().await;
+            let first = engine.best_move(&game_id(), START_FEN, "", &CLOCK).await.unwrap();
+
+            engine.ponder(&game_id(), START_FEN, "", &first, &CLOCK).await.unwrap();
+            engine.stop_pondering_in(&"otherGameId".to_owned()).await.unwrap();
+
+            assert_that!(engine.is_pondering().await).is_true();
+
+            engine.stop_pondering_in(&game_id()).await.unwrap();
+
+            assert_that!(engine.is_pondering().await).is_false();
+        });
+    }
+
+    #[test]
+    fn no_pondering_without_predicted_reply() {
+        tokio_test::block_on(async {
+            let engine = pondering_engine().await;
+            let best_move = best_move("e2e4", None);
+
+            let pondering = engine.ponder(&game_id(), START_FEN, "", &best_move, &CLOCK).await
+                .unwrap();
+
+            assert_that!(pondering).is_false();
+            assert_that!(engine.is_pondering().await).is_false();
+        });
+    }
+
+    #[test]
+    fn start_reads_engine_name() {
+        tokio_test::block_on(async {
+            let engine = fake_engine().await;
+
+            assert_that!(engine.name()).contains("Fake Engine");
+        });
+    }
+
+    #[rstest]
+    #[case::depth(AnalysisLimit::Depth(2))]
+    #[case::time(AnalysisLimit::Time(Duration::from_millis(100)))]
+    fn analyse_returns_last_info_per_variation(#[case] limit: AnalysisLimit) {
+        tokio_test::block_on(async {
+            let engine = fake_engine().await;
+
+            engine.set_option("Threads", "2").await.unwrap();
+            let infos = engine.analyse("8/8/8/8/8/8/8/K6k w - - 0 1", limit, 2).await.unwrap();
+            let scores = infos.iter().map(|info| info.score).collect::<Vec<_>>();
+
+            assert_that!(scores)
+                .contains_exactly_in_given_order([Score::Centipawns(30), Score::Centipawns(5)]);
+        });
+    }
+
+    #[test]
+    fn start_fails_if_engine_terminates() {
+        tokio_test::block_on(async {
+            let result = UciEngine::start("sh", ["-c", "exit 0"]).await;
+
+            assert_that!(result.is_err()).is_true();
+        });
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use futures::{stream, StreamExt};
+
+use tokio::sync::{mpsc, oneshot, Mutex as AsyncMutex};
+
+use crate::analysis::{AnalysisError, AnalysisLimit, AnalysisResult, Analyser};
+use crate::model::analysis::Evaluation;
+
+/// Identifies a unit of [AnalysisWork] within the [WorkProvider] which produced it.
+pub type WorkId = u64;
+
+/// A position which needs to be evaluated, produced by a [WorkProvider].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AnalysisWork {
+    pub id: WorkId,
+
+    /// The FEN of the position to evaluate.
+    pub fen: String,
+
+    /// The [AnalysisLimit] which the analysis should reach.
+    pub limit: AnalysisLimit,
+
+    /// The number of principal variations to compute.
+    pub multi_pv: u32
+}
+
+/// A source of [AnalysisWork], such as the [WorkQueue] of a bot, from which workers acquire
+/// positions to evaluate and to which they report the results, similar to how fishnet clients
+/// work for Lichess. This allows moving heavy searches out of the event loop of the bot, e.g. to
+/// other tasks, processes, or machines.
+#[async_trait::async_trait]
+pub trait WorkProvider : Send + Sync {
+
+    /// Waits until work is available and acquires it.
+    ///
+    /// # Returns
+    ///
+    /// The acquired work, or [None] if this provider will not produce any more work.
+    async fn next_work(&self) -> Option<AnalysisWork>;
+
+    /// Reports the result of work previously acquired with [WorkProvider::next_work].
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: The [WorkId] of the processed work.
+    /// * `result`: The evaluation of the position, or the error that occurred evaluating it.
+    async fn submit_result(&self, id: WorkId, result: AnalysisResult<Evaluation>);
+}
+
+/// A worker which evaluates the positions of [AnalysisWork], e.g. with a local engine or by
+/// forwarding it to a remote machine. An [Analyser] can be used as a consumer directly.
+#[async_trait::async_trait]
+pub trait WorkConsumer : Send + Sync {
+
+    /// Evaluates the position of the given work.
+    ///
+    /// # Errors
+    ///
+    /// Any [AnalysisError] that occurs while evaluating the position.
+    async fn process(&self, work: &AnalysisWork) -> AnalysisResult<Evaluation>;
+}
+
+#[async_trait::async_trait]
+impl WorkConsumer for Analyser {
+    async fn process(&self, work: &AnalysisWork) -> AnalysisResult<Evaluation> {
+        self.analyse_position(&work.fen, work.limit, work.multi_pv).await
+    }
+}
+
+/// Acquires work from the given provider, processes it with the given consumer, and reports the
+/// results back to the provider, until the provider does not produce any more work.
+///
+/// # Arguments
+///
+/// * `provider`: The [WorkProvider] from which to acquire work.
+/// * `consumer`: The [WorkConsumer] which processes the work.
+/// * `concurrency`: The maximum number of units of work processed at the same time. Values below
+///   1 are treated as 1.
+pub async fn process_work<P, C>(provider: &P, consumer: &C, concurrency: usize)
+where
+    P: WorkProvider + ?Sized,
+    C: WorkConsumer + ?Sized
+{
+    stream::unfold((), |()| async { provider.next_work().await.map(|work| (work, ())) })
+        .for_each_concurrent(concurrency.max(1), |work| async move {
+            let result = consumer.process(&work).await;
+            provider.submit_result(work.id, result).await;
+        })
+        .await;
+}
+
+/// An evaluation requested with [WorkQueue::submit], which is resolved once a worker reported the
+/// result. Dropping it withdraws the work if no worker has acquired it yet.
+#[derive(Debug)]
+pub struct PendingEvaluation {
+    id: WorkId,
+    receiver: oneshot::Receiver<AnalysisResult<Evaluation>>
+}
+
+impl PendingEvaluation {
+
+    /// The [WorkId] of the requested work.
+    pub fn id(&self) -> WorkId {
+        self.id
+    }
+
+    /// Waits until a worker reported the result of the requested work.
+    ///
+    /// # Errors
+    ///
+    /// * The error reported by the worker, if the evaluation failed.
+    /// * [AnalysisError::NoEvaluation] if the queue was dropped before the result was reported.
+    pub async fn result(self) -> AnalysisResult<Evaluation> {
+        self.receiver.await.unwrap_or(Err(AnalysisError::NoEvaluation))
+    }
+}
+
+/// A [WorkProvider] to which a bot submits positions it wants evaluated, receiving a
+/// [PendingEvaluation] for each. Workers acquire the positions in the order in which they were
+/// submitted, e.g. using [process_work]. [WorkQueue::next_work](WorkProvider::next_work) waits
+/// for new positions while the queue is empty, so workers keep running until they are stopped.
+#[derive(Debug)]
+pub struct WorkQueue {
+    sender: mpsc::UnboundedSender<AnalysisWork>,
+    receiver: AsyncMutex<mpsc::UnboundedReceiver<AnalysisWork>>,
+    pending: Mutex<HashMap<WorkId, oneshot::Sender<AnalysisResult<Evaluation>>>>,
+    next_id: AtomicU64
+}
+
+impl WorkQueue {
+
+    /// Creates a new, empty work queue.
+    pub fn new() -> WorkQueue {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        WorkQueue {
+            sender,
+            receiver: AsyncMutex::new(receiver),
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0)
+        }
+    }
+
+    /// Submits a position to be evaluated by a worker.
+    ///
+    /// # Arguments
+    ///
+    /// * `fen`: The FEN of the position to evaluate.
+    /// * `limit`: The [AnalysisLimit] which the analysis should reach.
+    /// * `multi_pv`: The number of principal variations to compute.
+    ///
+    /// # Returns
+    ///
+    /// A [PendingEvaluation] which resolves to the result once it is reported.
+    pub fn submit(&self, fen: impl Into<String>, limit: AnalysisLimit, multi_pv: u32)
+            -> PendingEvaluation {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        let work = AnalysisWork {
+            id,
+            fen: fen.into(),
+            limit,
+            multi_pv
+        };
+
+        self.pending.lock().unwrap().insert(id, sender);
+
+        // The receiver is owned by this queue, so sending cannot fail.
+        let _ = self.sender.send(work);
+
+        PendingEvaluation {
+            id,
+            receiver
+        }
+    }
+
+    /// The number of submitted positions whose results have not been reported yet.
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
+
+impl Default for WorkQueue {
+    fn default() -> WorkQueue {
+        WorkQueue::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl WorkProvider for WorkQueue {
+    async fn next_work(&self) -> Option<AnalysisWork> {
+        let mut receiver = self.receiver.lock().await;
+
+        loop {
+            let work = receiver.recv().await?;
+            let mut pending = self.pending.lock().unwrap();
+            let withdrawn = pending.get(&work.id).is_none_or(oneshot::Sender::is_closed);
+
+            if !withdrawn {
+                return Some(work);
+            }
+
+            pending.remove(&work.id);
+        }
+    }
+
+    async fn submit_result(&self, id: WorkId, result: AnalysisResult<Evaluation>) {
+        if let Some(sender) = self.pending.lock().unwrap().remove(&id) {
+            let _ = sender.send(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use crate::model::analysis::{EvaluationSource, PrincipalVariation, Score};
+
+    use super::*;
+
+    const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    struct FixedDepthConsumer;
+
+    #[async_trait::async_trait]
+    impl WorkConsumer for FixedDepthConsumer {
+        async fn process(&self, work: &AnalysisWork) -> AnalysisResult<Evaluation> {
+            if work.fen.is_empty() {
+                return Err(AnalysisError::NoEvaluation);
+            }
+
+            Ok(Evaluation {
+                source: EvaluationSource::LocalEngine,
+                depth: work.multi_pv,
+                lines: vec![PrincipalVariation {
+                    score: Score::Centipawns(20),
+                    moves: vec!["e2e4".to_owned()]
+                }]
+            })
+        }
+    }
+
+    #[test]
+    fn submitted_work_is_processed_and_result_delivered() {
+        tokio_test::block_on(async {
+            let queue = WorkQueue::new();
+            let first = queue.submit(STARTPOS, AnalysisLimit::Depth(10), 1);
+            let second = queue.submit(STARTPOS, AnalysisLimit::Depth(10), 3);
+            let failing = queue.submit("", AnalysisLimit::Depth(10), 1);
+
+            let worker = process_work(&queue, &FixedDepthConsumer, 2);
+            let results = async {
+                (first.result().await, second.result().await, failing.result().await)
+            };
+            let (first, second, failing) = tokio::select! {
+                results = results => results,
+                _ = worker => unreachable!("work queue never runs out of work")
+            };
+
+            assert_that!(first.unwrap().depth).is_equal_to(1);
+            assert_that!(second.unwrap().best_move().cloned()).contains("e2e4".to_owned());
+            assert!(matches!(failing, Err(AnalysisError::NoEvaluation)));
+            assert_that!(queue.pending()).is_equal_to(0);
+        });
+    }
+
+    #[test]
+    fn dropped_pending_evaluation_withdraws_work() {
+        tokio_test::block_on(async {
+            let queue = WorkQueue::new();
+            let withdrawn = queue.submit(STARTPOS, AnalysisLimit::Depth(10), 1);
+            let kept = queue.submit(STARTPOS, AnalysisLimit::Depth(10), 1);
+            let kept_id = kept.id();
+            drop(withdrawn);
+
+            let work = tokio::time::timeout(Duration::from_secs(1), queue.next_work()).await;
+
+            assert_that!(work.unwrap().map(|work| work.id)).contains(kept_id);
+            assert_that!(queue.pending()).is_equal_to(1);
+        });
+    }
+}
@@ -0,0 +1,358 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use futures::StreamExt;
+
+use serde::de::DeserializeOwned;
+
+use serde_json::Value as JsonValue;
+
+use libot::client::{BotClient, BotClientBuilder};
+use libot::config::ENV_PREFIX;
+use libot::model::Seconds;
+use libot::model::challenge::{Challenge, ChallengeBuilder, ChallengeColor, DeclineReason};
+use libot::model::game::{GameId, Variant};
+
+const USAGE: &str = "\
+Usage: libot-cli [OPTIONS] <COMMAND>
+
+Options:
+    --token <TOKEN>          Lichess API token
+    --token-env <VARIABLE>   environment variable containing the token (default: LIBOT_TOKEN)
+    --token-file <PATH>      file containing the token
+    --base-url <URL>         base URL of the Lichess API
+
+Commands:
+    validate-token           show the account authenticated by the token
+    upgrade                  upgrade the account to a bot account (irreversible)
+    challenges               list pending challenges
+    accept <ID>              accept the challenge with the given ID
+    decline <ID> [REASON]    decline the challenge with the given ID, e.g. with reason tooFast
+    challenge <USERNAME> [--rated] [--clock <LIMIT>+<INCREMENT>] [--days <DAYS>]
+              [--color <white|black|random>] [--variant <KEY>]
+                             send a challenge to the given user
+    tail-events              print the event stream as pretty JSON";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum TokenOption {
+    Value(String),
+    EnvironmentVariable(String),
+    File(PathBuf)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Command {
+    ValidateToken,
+    Upgrade,
+    Challenges,
+    Accept(GameId),
+    Decline(GameId, Option<DeclineReason>),
+    Challenge {
+        username: String,
+        builder: ChallengeBuilder
+    },
+    TailEvents
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Options {
+    token: TokenOption,
+    base_url: Option<String>,
+    command: Command
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, name: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("missing value for {name}"))
+}
+
+fn parse_key<T: DeserializeOwned>(key: &str, kind: &str) -> Result<T, String> {
+    serde_json::from_value(JsonValue::String(key.to_owned()))
+        .map_err(|_| format!("invalid {kind}: {key}"))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {name}: {value}"))
+}
+
+fn parse_clock(clock: &str) -> Result<(Seconds, Seconds), String> {
+    let (limit, increment) = clock.split_once('+')
+        .ok_or_else(|| format!("invalid clock, expected <LIMIT>+<INCREMENT>: {clock}"))?;
+
+    Ok((parse_number(limit, "--clock")?, parse_number(increment, "--clock")?))
+}
+
+fn parse_challenge(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let username = args.next().ok_or("missing username")?;
+    let mut builder = ChallengeBuilder::new();
+
+    while let Some(arg) = args.next() {
+        builder = match arg.as_str() {
+            "--rated" => builder.with_rated(true),
+            "--clock" => {
+                let (limit, increment) = parse_clock(&next_value(&mut args, "--clock")?)?;
+                builder.with_clock(limit, increment)
+            },
+            "--days" => {
+                let days = parse_number(&next_value(&mut args, "--days")?, "--days")?;
+                builder.with_days(days)
+            },
+            "--color" => {
+                let color: ChallengeColor = parse_key(&next_value(&mut args, "--color")?, "color")?;
+                builder.with_color(color)
+            },
+            "--variant" => {
+                let key = next_value(&mut args, "--variant")?;
+                let variant = Variant::from_key(&key).ok_or(format!("invalid variant: {key}"))?;
+                builder.with_variant(variant)
+            },
+            _ => return Err(format!("unexpected argument: {arg}"))
+        };
+    }
+
+    Ok(Command::Challenge {
+        username,
+        builder
+    })
+}
+
+fn parse_command(name: &str, mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let command = match name {
+        "validate-token" => Command::ValidateToken,
+        "upgrade" => Command::Upgrade,
+        "challenges" => Command::Challenges,
+        "accept" => Command::Accept(args.next().ok_or("missing challenge ID")?),
+        "decline" => {
+            let id = args.next().ok_or("missing challenge ID")?;
+            let reason = args.next()
+                .map(|reason| parse_key(&reason, "decline reason"))
+                .transpose()?;
+
+            Command::Decline(id, reason)
+        },
+        "challenge" => return parse_challenge(args),
+        "tail-events" => Command::TailEvents,
+        _ => return Err(format!("unknown command: {name}"))
+    };
+
+    match args.next() {
+        Some(arg) => Err(format!("unexpected argument: {arg}")),
+        None => Ok(command)
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut args = args.into_iter();
+    let mut token = TokenOption::EnvironmentVariable(format!("{ENV_PREFIX}TOKEN"));
+    let mut base_url = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--token" => token = TokenOption::Value(next_value(&mut args, "--token")?),
+            "--token-env" =>
+                token = TokenOption::EnvironmentVariable(next_value(&mut args, "--token-env")?),
+            "--token-file" =>
+                token = TokenOption::File(next_value(&mut args, "--token-file")?.into()),
+            "--base-url" => base_url = Some(next_value(&mut args, "--base-url")?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {arg}")),
+            _ => {
+                let command = parse_command(&arg, args)?;
+
+                return Ok(Options {
+                    token,
+                    base_url,
+                    command
+                });
+            }
+        }
+    }
+
+    Err("missing command".to_owned())
+}
+
+fn build_client(token: TokenOption, base_url: Option<String>) -> Result<BotClient, String> {
+    let mut builder = match token {
+        TokenOption::Value(token) => BotClientBuilder::new().with_token(token),
+        TokenOption::EnvironmentVariable(name) => BotClientBuilder::new().with_token_from_env(name),
+        TokenOption::File(path) => BotClientBuilder::new().with_token_file(path)
+    };
+
+    if let Some(base_url) = base_url {
+        builder = builder.with_base_url(base_url);
+    }
+
+    builder.build().map_err(|error| error.to_string())
+}
+
+fn format_challenge(challenge: &Challenge) -> String {
+    let destination = challenge.dest_user.as_ref()
+        .map(|user| user.name.as_str())
+        .unwrap_or("open");
+    let variant = challenge.variant.map(Variant::key).unwrap_or("unknown");
+    let mode = if challenge.rated { "rated" } else { "casual" };
+
+    format!("{} {} -> {} ({variant}, {:?}, {mode})", challenge.id, challenge.challenger.name,
+        destination, challenge.speed)
+}
+
+async fn run_command(client: &BotClient, command: Command) -> Result<(), String> {
+    match command {
+        Command::ValidateToken => {
+            let profile = client.get_my_profile().await.map_err(|error| error.to_string())?;
+            let title = profile.title.map(|title| format!("{title:?}")).unwrap_or_default();
+
+            println!("token is valid for {} ({}) {title}", profile.username, profile.id);
+        },
+        Command::Upgrade => {
+            client.upgrade_to_bot_account().await.map_err(|error| error.to_string())?;
+
+            println!("account upgraded to bot account");
+        },
+        Command::Challenges => {
+            let challenges =
+                client.get_pending_challenges().await.map_err(|error| error.to_string())?;
+
+            println!("incoming:");
+
+            for challenge in &challenges.incoming {
+                println!("  {}", format_challenge(challenge));
+            }
+
+            println!("outgoing:");
+
+            for challenge in &challenges.outgoing {
+                println!("  {}", format_challenge(challenge));
+            }
+        },
+        Command::Accept(id) => {
+            client.accept_challenge(id.clone()).await.map_err(|error| error.to_string())?;
+
+            println!("accepted challenge {id}");
+        },
+        Command::Decline(id, reason) => {
+            client.decline_challenge(id.clone(), reason).await
+                .map_err(|error| error.to_string())?;
+
+            println!("declined challenge {id}");
+        },
+        Command::Challenge { username, builder } => {
+            let request = builder.build().map_err(|error| error.to_string())?;
+            let challenge = client.create_challenge(&username, &request).await
+                .map_err(|error| error.to_string())?;
+
+            println!("created challenge {}", format_challenge(&challenge));
+        },
+        Command::TailEvents => {
+            let mut events = Box::pin(
+                client.stream_raw_events().await.map_err(|error| error.to_string())?);
+
+            while let Some(event) = events.next().await {
+                let event = event.map_err(|error| error.to_string())?;
+                let pretty = serde_json::to_string_pretty(&event)
+                    .map_err(|error| error.to_string())?;
+
+                println!("{pretty}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let result = match build_client(options.token, options.base_url) {
+        Ok(client) => run_command(&client, options.command).await,
+        Err(message) => Err(message)
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[rstest]
+    #[case::validate_token("validate-token", Command::ValidateToken)]
+    #[case::upgrade("upgrade", Command::Upgrade)]
+    #[case::challenges("challenges", Command::Challenges)]
+    #[case::accept("accept abc", Command::Accept("abc".to_owned()))]
+    #[case::decline_without_reason("decline abc", Command::Decline("abc".to_owned(), None))]
+    #[case::decline_with_reason(
+        "decline abc tooFast",
+        Command::Decline("abc".to_owned(), Some(DeclineReason::TooFast))
+    )]
+    #[case::tail_events("tail-events", Command::TailEvents)]
+    fn parse_args_parses_command(#[case] arguments: &str, #[case] expected: Command) {
+        let options = parse_args(args(arguments)).unwrap();
+
+        assert_that!(options.command).is_equal_to(expected);
+        assert_that!(options.token)
+            .is_equal_to(TokenOption::EnvironmentVariable("LIBOT_TOKEN".to_owned()));
+        assert_that!(options.base_url).is_none();
+    }
+
+    #[test]
+    fn parse_args_parses_options() {
+        let options =
+            parse_args(args("--base-url http://localhost --token-file token.txt upgrade")).unwrap();
+
+        assert_that!(options.token).is_equal_to(TokenOption::File("token.txt".into()));
+        assert_that!(options.base_url).is_equal_to(Some("http://localhost".to_owned()));
+    }
+
+    #[test]
+    fn parse_args_parses_challenge() {
+        let options = parse_args(args(
+            "--token abc challenge someone --rated --clock 180+2 --color white --variant atomic"))
+            .unwrap();
+        let expected_builder = ChallengeBuilder::new()
+            .with_rated(true)
+            .with_clock(180, 2)
+            .with_color(ChallengeColor::White)
+            .with_variant(Variant::Atomic);
+
+        assert_that!(options.token).is_equal_to(TokenOption::Value("abc".to_owned()));
+        assert_that!(options.command).is_equal_to(Command::Challenge {
+            username: "someone".to_owned(),
+            builder: expected_builder
+        });
+    }
+
+    #[rstest]
+    #[case::missing_command("--token abc")]
+    #[case::unknown_command("resign")]
+    #[case::unknown_option("--verbose challenges")]
+    #[case::missing_option_value("challenges --token")]
+    #[case::missing_challenge_id("accept")]
+    #[case::invalid_decline_reason("decline abc because")]
+    #[case::extra_argument("challenges extra")]
+    #[case::invalid_clock("challenge someone --clock 180")]
+    #[case::invalid_variant("challenge someone --variant checkers")]
+    fn parse_args_fails(#[case] arguments: &str) {
+        assert_that!(parse_args(args(arguments))).is_err();
+    }
+}
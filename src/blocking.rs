@@ -0,0 +1,409 @@
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::TryStreamExt;
+
+use reqwest::Method;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::client::{BotClient, RawResponse};
+use crate::error::LibotResult;
+use crate::model::{Move, Seconds, Timestamp};
+use crate::model::broadcast::BroadcastPush;
+use crate::model::challenge::{Challenge, ChallengeRequest, Challenges, DeclineReason};
+use crate::model::cloud_eval::CloudEvaluation;
+use crate::model::external_engine::{ExternalEngine, ExternalEngineRegistration};
+use crate::model::game::chat::{ChatHistory, ChatMarker, ChatRoom, NewChatLines};
+use crate::model::game::export::ExportedGame;
+use crate::model::game::{Color, GameId};
+use crate::model::game::ongoing::OngoingGame;
+use crate::model::tournament::Tournament;
+use crate::model::tv::TvChannels;
+use crate::model::user::UserProfile;
+use crate::model::user::crosstable::Crosstable;
+use crate::model::user::preferences::UserPreferences;
+
+/// A blocking wrapper around a [BotClient] for simple scripts and tests which do not want to
+/// manage an async runtime themselves. Every method blocks the current thread until the request
+/// of the wrapped client completes, using an internal single-threaded runtime. Hence, it must not
+/// be called from within an async context.
+///
+/// Methods which return streams or only make sense within a running
+/// [BotRunner](crate::runner::BotRunner), such as [BotClient::create_seek],
+/// [BotClient::create_challenge_kept_alive] and [BotClient::stream_raw_events], are not mirrored.
+/// Use [BlockingBotClient::client] to access them asynchronously. An exception is
+/// [BotClient::export_games], whose finite stream is collected by
+/// [BlockingBotClient::export_games].
+#[derive(Clone, Debug)]
+pub struct BlockingBotClient {
+    client: BotClient,
+    runtime: Arc<Runtime>
+}
+
+impl BlockingBotClient {
+
+    /// Creates a new blocking client which sends its requests with the given [BotClient].
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while creating the internal runtime.
+    pub fn new(client: BotClient) -> io::Result<BlockingBotClient> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        Ok(BlockingBotClient {
+            client,
+            runtime: Arc::new(runtime)
+        })
+    }
+
+    /// Gets the wrapped asynchronous [BotClient].
+    pub fn client(&self) -> &BotClient {
+        &self.client
+    }
+
+    /// Consumes this blocking client and returns the wrapped asynchronous [BotClient].
+    pub fn into_inner(self) -> BotClient {
+        self.client
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Blocking version of [BotClient::prewarm].
+    pub fn prewarm(&self) -> LibotResult<()> {
+        self.block_on(self.client.prewarm())
+    }
+
+    /// Blocking version of [BotClient::send_request_raw].
+    pub fn send_request_raw(&self, method: Method, path: &str, body: Option<String>)
+            -> LibotResult<RawResponse> {
+        self.block_on(self.client.send_request_raw(method, path, body))
+    }
+
+    /// Blocking version of [BotClient::get_pending_challenges].
+    pub fn get_pending_challenges(&self) -> LibotResult<Challenges> {
+        self.block_on(self.client.get_pending_challenges())
+    }
+
+    /// Blocking version of [BotClient::accept_challenge].
+    pub fn accept_challenge(&self, challenge_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.accept_challenge(challenge_id))
+    }
+
+    /// Blocking version of [BotClient::decline_challenge].
+    pub fn decline_challenge(&self, challenge_id: GameId, reason: Option<DeclineReason>)
+            -> LibotResult<()> {
+        self.block_on(self.client.decline_challenge(challenge_id, reason))
+    }
+
+    /// Blocking version of [BotClient::create_challenge].
+    pub fn create_challenge(&self, username: &str, request: &ChallengeRequest)
+            -> LibotResult<Challenge> {
+        self.block_on(self.client.create_challenge(username, request))
+    }
+
+    /// Blocking version of [BotClient::get_game_gif].
+    pub fn get_game_gif(&self, game_id: GameId, color: Option<Color>) -> LibotResult<Vec<u8>> {
+        self.block_on(self.client.get_game_gif(game_id, color))
+    }
+
+    /// Blocking version of [BotClient::get_challenge].
+    pub fn get_challenge(&self, challenge_id: GameId) -> LibotResult<Challenge> {
+        self.block_on(self.client.get_challenge(challenge_id))
+    }
+
+    /// Blocking version of [BotClient::make_move].
+    pub fn make_move(&self, game_id: GameId, mov: Move, offer_draw: bool) -> LibotResult<()> {
+        self.block_on(self.client.make_move(game_id, mov, offer_draw))
+    }
+
+    /// Blocking version of [BotClient::abort_game].
+    pub fn abort_game(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.abort_game(game_id))
+    }
+
+    /// Blocking version of [BotClient::resign_game].
+    pub fn resign_game(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.resign_game(game_id))
+    }
+
+    /// Blocking version of [BotClient::claim_victory].
+    pub fn claim_victory(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.claim_victory(game_id))
+    }
+
+    /// Blocking version of [BotClient::offer_or_accept_draw].
+    pub fn offer_or_accept_draw(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.offer_or_accept_draw(game_id))
+    }
+
+    /// Blocking version of [BotClient::decline_draw].
+    pub fn decline_draw(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.decline_draw(game_id))
+    }
+
+    /// Blocking version of [BotClient::accept_takeback].
+    pub fn accept_takeback(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.accept_takeback(game_id))
+    }
+
+    /// Blocking version of [BotClient::decline_takeback].
+    pub fn decline_takeback(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.decline_takeback(game_id))
+    }
+
+    /// Blocking version of [BotClient::add_time].
+    pub fn add_time(&self, game_id: GameId, seconds: Seconds) -> LibotResult<()> {
+        self.block_on(self.client.add_time(game_id, seconds))
+    }
+
+    /// Blocking version of [BotClient::get_game_chat].
+    pub fn get_game_chat(&self, game_id: GameId) -> LibotResult<ChatHistory> {
+        self.block_on(self.client.get_game_chat(game_id))
+    }
+
+    /// Blocking version of [BotClient::get_new_chat_since].
+    pub fn get_new_chat_since(&self, game_id: GameId, marker: ChatMarker)
+            -> LibotResult<NewChatLines> {
+        self.block_on(self.client.get_new_chat_since(game_id, marker))
+    }
+
+    /// Blocking version of [BotClient::send_chat_message].
+    pub fn send_chat_message(&self, game_id: GameId, room: ChatRoom, text: impl Into<String>)
+            -> LibotResult<()> {
+        self.block_on(self.client.send_chat_message(game_id, room, text))
+    }
+
+    /// Blocking version of [BotClient::send_localized_chat_message].
+    pub fn send_localized_chat_message(&self, game_id: GameId, room: ChatRoom, key: &str,
+            language: Option<&str>, args: &[(&str, &str)]) -> LibotResult<()> {
+        self.block_on(self.client.send_localized_chat_message(game_id, room, key, language, args))
+    }
+
+    /// Blocking version of [BotClient::send_team_message].
+    pub fn send_team_message(&self, team_id: &str, text: impl Into<String>) -> LibotResult<()> {
+        self.block_on(self.client.send_team_message(team_id, text))
+    }
+
+    /// Blocking version of [BotClient::export_game].
+    pub fn export_game(&self, game_id: GameId) -> LibotResult<ExportedGame> {
+        self.block_on(self.client.export_game(game_id))
+    }
+
+    /// Blocking version of [BotClient::export_games], which collects the exported games.
+    ///
+    /// # Errors
+    ///
+    /// Any error which occurs while sending the request or parsing one of the exported games.
+    pub fn export_games<I>(&self, game_ids: I) -> LibotResult<Vec<ExportedGame>>
+    where
+        I: IntoIterator,
+        I::Item: Into<GameId>,
+        I::IntoIter: Send + Sync + 'static
+    {
+        self.block_on(async {
+            self.client.export_games(game_ids).await?.try_collect().await
+        })
+    }
+
+    /// Blocking version of [BotClient::export_game_with_analysis].
+    pub fn export_game_with_analysis(&self, game_id: GameId) -> LibotResult<ExportedGame> {
+        self.block_on(self.client.export_game_with_analysis(game_id))
+    }
+
+    /// Blocking version of [BotClient::request_game_analysis].
+    pub fn request_game_analysis(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.request_game_analysis(game_id))
+    }
+
+    /// Blocking version of [BotClient::wait_for_game_analysis], which blocks the current thread
+    /// for up to the given timeout.
+    pub fn wait_for_game_analysis(&self, game_id: GameId, poll_interval: Duration,
+            timeout: Duration) -> LibotResult<Option<ExportedGame>> {
+        self.block_on(self.client.wait_for_game_analysis(game_id, poll_interval, timeout))
+    }
+
+    /// Blocking version of [BotClient::export_user_games_pgn].
+    pub fn export_user_games_pgn(&self, username: &str, since: Option<Timestamp>)
+            -> LibotResult<String> {
+        self.block_on(self.client.export_user_games_pgn(username, since))
+    }
+
+    /// Blocking version of [BotClient::get_crosstable].
+    pub fn get_crosstable(&self, user_1: &str, user_2: &str) -> LibotResult<Crosstable> {
+        self.block_on(self.client.get_crosstable(user_1, user_2))
+    }
+
+    /// Blocking version of [BotClient::get_tv_channels].
+    pub fn get_tv_channels(&self) -> LibotResult<TvChannels> {
+        self.block_on(self.client.get_tv_channels())
+    }
+
+    /// Blocking version of [BotClient::get_ongoing_games].
+    pub fn get_ongoing_games(&self, limit: u32) -> LibotResult<Vec<OngoingGame>> {
+        self.block_on(self.client.get_ongoing_games(limit))
+    }
+
+    /// Blocking version of [BotClient::get_cloud_eval].
+    pub fn get_cloud_eval(&self, fen: &str, multi_pv: u32) -> LibotResult<Option<CloudEvaluation>> {
+        self.block_on(self.client.get_cloud_eval(fen, multi_pv))
+    }
+
+    /// Blocking version of [BotClient::list_external_engines].
+    pub fn list_external_engines(&self) -> LibotResult<Vec<ExternalEngine>> {
+        self.block_on(self.client.list_external_engines())
+    }
+
+    /// Blocking version of [BotClient::create_external_engine].
+    pub fn create_external_engine(&self, registration: &ExternalEngineRegistration)
+            -> LibotResult<ExternalEngine> {
+        self.block_on(self.client.create_external_engine(registration))
+    }
+
+    /// Blocking version of [BotClient::get_external_engine].
+    pub fn get_external_engine(&self, engine_id: &str) -> LibotResult<ExternalEngine> {
+        self.block_on(self.client.get_external_engine(engine_id))
+    }
+
+    /// Blocking version of [BotClient::update_external_engine].
+    pub fn update_external_engine(&self, engine_id: &str, registration: &ExternalEngineRegistration)
+            -> LibotResult<ExternalEngine> {
+        self.block_on(self.client.update_external_engine(engine_id, registration))
+    }
+
+    /// Blocking version of [BotClient::delete_external_engine].
+    pub fn delete_external_engine(&self, engine_id: &str) -> LibotResult<()> {
+        self.block_on(self.client.delete_external_engine(engine_id))
+    }
+
+    /// Blocking version of [BotClient::get_tournament].
+    pub fn get_tournament(&self, tournament_id: &str) -> LibotResult<Tournament> {
+        self.block_on(self.client.get_tournament(tournament_id))
+    }
+
+    /// Blocking version of [BotClient::join_tournament].
+    pub fn join_tournament(&self, tournament_id: &str, password: Option<&str>) -> LibotResult<()> {
+        self.block_on(self.client.join_tournament(tournament_id, password))
+    }
+
+    /// Blocking version of [BotClient::withdraw_from_tournament].
+    pub fn withdraw_from_tournament(&self, tournament_id: &str) -> LibotResult<()> {
+        self.block_on(self.client.withdraw_from_tournament(tournament_id))
+    }
+
+    /// Blocking version of [BotClient::push_broadcast_pgn].
+    pub fn push_broadcast_pgn(&self, round_id: &str, pgn: impl Into<String>)
+            -> LibotResult<BroadcastPush> {
+        self.block_on(self.client.push_broadcast_pgn(round_id, pgn))
+    }
+
+    /// Blocking version of [BotClient::berserk].
+    pub fn berserk(&self, game_id: GameId) -> LibotResult<()> {
+        self.block_on(self.client.berserk(game_id))
+    }
+
+    /// Blocking version of [BotClient::get_profile].
+    pub fn get_profile(&self, username: String) -> LibotResult<UserProfile> {
+        self.block_on(self.client.get_profile(username))
+    }
+
+    /// Blocking version of [BotClient::get_my_profile].
+    pub fn get_my_profile(&self) -> LibotResult<UserProfile> {
+        self.block_on(self.client.get_my_profile())
+    }
+
+    /// Blocking version of [BotClient::get_my_preferences].
+    pub fn get_my_preferences(&self) -> LibotResult<UserPreferences> {
+        self.block_on(self.client.get_my_preferences())
+    }
+
+    /// Blocking version of [BotClient::upgrade_to_bot_account].
+    pub fn upgrade_to_bot_account(&self) -> LibotResult<()> {
+        self.block_on(self.client.upgrade_to_bot_account())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{method, path};
+
+    use crate::client::BotClientBuilder;
+
+    use super::*;
+
+    fn setup_blocking_test(runtime: &Runtime) -> (BlockingBotClient, MockServer) {
+        let server = runtime.block_on(MockServer::start());
+        let client = BotClientBuilder::new()
+            .with_token("mock_token")
+            .with_base_url(server.uri())
+            .build()
+            .unwrap();
+
+        (BlockingBotClient::new(client).unwrap(), server)
+    }
+
+    #[test]
+    fn requests_are_sent_without_async_context() {
+        let runtime = Runtime::new().unwrap();
+        let (client, server) = setup_blocking_test(&runtime);
+
+        runtime.block_on(Mock::given(method("POST"))
+            .and(path("/bot/game/testGameId/resign"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server));
+
+        let result = client.resign_game("testGameId".to_owned());
+
+        assert_that!(result).is_ok();
+    }
+
+    #[test]
+    fn exported_games_are_collected() {
+        let runtime = Runtime::new().unwrap();
+        let (client, server) = setup_blocking_test(&runtime);
+
+        runtime.block_on(Mock::given(method("POST"))
+            .and(path("/games/export/_ids"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_string(concat!(r#"{"id":"game1","rated":true,"speed":"blitz","#,
+                    r#""createdAt":1514505150384,"status":"mate","players":{"white":{},"#,
+                    r#""black":{}}}"#, "\n", r#"{"id":"game2","rated":false,"#,
+                    r#""speed":"bullet","createdAt":1514505150384,"status":"draw","#,
+                    r#""players":{"white":{},"black":{}}}"#, "\n")))
+            .expect(1)
+            .mount(&server));
+
+        let games = client.export_games(["game1", "game2"]).unwrap();
+        let game_ids = games.into_iter().map(|game| game.id).collect::<Vec<_>>();
+
+        assert_that!(game_ids)
+            .contains_exactly_in_given_order(["game1".to_owned(), "game2".to_owned()]);
+    }
+
+    #[test]
+    fn errors_are_returned() {
+        let runtime = Runtime::new().unwrap();
+        let (client, server) = setup_blocking_test(&runtime);
+
+        runtime.block_on(Mock::given(method("GET"))
+            .and(path("/account"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server));
+
+        let result = client.get_my_profile();
+
+        assert_that!(result).is_err();
+    }
+}
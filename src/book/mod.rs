@@ -0,0 +1,227 @@
+use rand::{Rng, RngCore};
+
+use crate::chess::position::Position;
+use crate::chess::uci::UciMove;
+
+pub mod pgn;
+
+/// A move stored in an [OpeningBook] for a certain position, together with statistics on how
+/// often it was played and how well it scored.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BookEntry {
+
+    /// The move in the position for which this entry was queried.
+    pub mov: UciMove,
+
+    /// The weight of the move, such as the number of times it was played.
+    pub weight: u32,
+
+    /// The number of games won by the side playing the move.
+    pub wins: u32,
+
+    /// The number of games drawn after the move.
+    pub draws: u32,
+
+    /// The number of games lost by the side playing the move.
+    pub losses: u32
+}
+
+impl BookEntry {
+
+    /// The score of this move from the perspective of the side playing it, in the range `[0, 1]`,
+    /// where wins count 1 and draws count 0.5. If no results are known, the score is 0.5.
+    pub fn score(&self) -> f64 {
+        let games = self.wins + self.draws + self.losses;
+
+        if games == 0 {
+            0.5
+        }
+        else {
+            (self.wins as f64 + self.draws as f64 * 0.5) / games as f64
+        }
+    }
+}
+
+/// The strategy by which [OpeningBook::select_move] chooses among the available book moves.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum BookSelection {
+
+    /// Choose the move with the highest weight.
+    MostPlayed,
+
+    /// Choose the move with the highest [BookEntry::score].
+    BestScore,
+
+    /// Choose a random move with a probability proportional to its weight.
+    #[default]
+    Weighted
+}
+
+/// A source of opening moves for given positions.
+pub trait OpeningBook : Send + Sync {
+
+    /// Queries all book moves for the given position.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: The position for which to query book moves.
+    ///
+    /// # Returns
+    ///
+    /// A list of all [BookEntry]s for the given position. This is empty if the position is not
+    /// in the book.
+    fn entries(&self, position: &Position) -> Vec<BookEntry>;
+
+    /// Selects a move for the given position using the given selection strategy. Random choices
+    /// are made using the thread-local random number generator, see
+    /// [OpeningBook::select_move_with_rng] to provide another one.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: The position for which to select a book move.
+    /// * `selection`: The [BookSelection] strategy to apply.
+    ///
+    /// # Returns
+    ///
+    /// The selected move, or [None] if the position is not in the book.
+    fn select_move(&self, position: &Position, selection: BookSelection) -> Option<UciMove> {
+        self.select_move_with_rng(position, selection, &mut rand::thread_rng())
+    }
+
+    /// Selects a move for the given position like [OpeningBook::select_move], but makes random
+    /// choices using the given random number generator, e.g. a seeded one for reproducible games.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: The position for which to select a book move.
+    /// * `selection`: The [BookSelection] strategy to apply.
+    /// * `rng`: The random number generator used for [BookSelection::Weighted].
+    ///
+    /// # Returns
+    ///
+    /// The selected move, or [None] if the position is not in the book.
+    fn select_move_with_rng(&self, position: &Position, selection: BookSelection,
+            rng: &mut dyn RngCore) -> Option<UciMove> {
+        let entries = self.entries(position);
+
+        match selection {
+            BookSelection::MostPlayed => entries.iter()
+                .max_by_key(|entry| entry.weight)
+                .map(|entry| entry.mov),
+            BookSelection::BestScore => entries.iter()
+                .max_by(|entry_1, entry_2| entry_1.score().total_cmp(&entry_2.score()))
+                .map(|entry| entry.mov),
+            BookSelection::Weighted => select_weighted(&entries, rng.gen())
+        }
+    }
+}
+
+fn select_weighted(entries: &[BookEntry], random: f64) -> Option<UciMove> {
+    let total_weight = entries.iter().map(|entry| entry.weight as u64).sum::<u64>();
+
+    if total_weight == 0 {
+        return entries.first().map(|entry| entry.mov);
+    }
+
+    let mut remaining = (random * total_weight as f64) as u64;
+
+    for entry in entries {
+        if remaining < entry.weight as u64 {
+            return Some(entry.mov);
+        }
+
+        remaining -= entry.weight as u64;
+    }
+
+    entries.last().map(|entry| entry.mov)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn entry(mov: &str, weight: u32, wins: u32, draws: u32, losses: u32) -> BookEntry {
+        BookEntry {
+            mov: mov.parse().unwrap(),
+            weight,
+            wins,
+            draws,
+            losses
+        }
+    }
+
+    struct FixedBook(Vec<BookEntry>);
+
+    impl OpeningBook for FixedBook {
+        fn entries(&self, _: &Position) -> Vec<BookEntry> {
+            self.0.clone()
+        }
+    }
+
+    fn test_book() -> FixedBook {
+        FixedBook(vec![
+            entry("e2e4", 10, 2, 4, 4),
+            entry("d2d4", 5, 4, 1, 0),
+            entry("c2c4", 1, 0, 0, 1)
+        ])
+    }
+
+    #[rstest]
+    #[case::no_games(0, 0, 0, 0.5)]
+    #[case::only_wins(3, 0, 0, 1.0)]
+    #[case::mixed(1, 2, 1, 0.5)]
+    fn book_entry_score(#[case] wins: u32, #[case] draws: u32, #[case] losses: u32,
+            #[case] expected_score: f64) {
+        assert_that!(entry("e2e4", 1, wins, draws, losses).score()).is_equal_to(expected_score);
+    }
+
+    #[rstest]
+    #[case::most_played(BookSelection::MostPlayed, "e2e4")]
+    #[case::best_score(BookSelection::BestScore, "d2d4")]
+    fn select_move_deterministic(#[case] selection: BookSelection, #[case] expected_move: &str) {
+        let mov = test_book().select_move(&Position::standard(), selection);
+
+        assert_that!(mov).contains(expected_move.parse::<UciMove>().unwrap());
+    }
+
+    #[rstest]
+    #[case::start(0.0, "e2e4")]
+    #[case::first_bucket_end(0.62, "e2e4")]
+    #[case::second_bucket(0.63, "d2d4")]
+    #[case::last_bucket(0.99, "c2c4")]
+    fn select_weighted_respects_weights(#[case] random: f64, #[case] expected_move: &str) {
+        let mov = select_weighted(&test_book().0, random);
+
+        assert_that!(mov).contains(expected_move.parse::<UciMove>().unwrap());
+    }
+
+    #[test]
+    fn select_move_with_seeded_rng_is_reproducible() {
+        let book = test_book();
+        let select = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            (0..20)
+                .map(|_| book.select_move_with_rng(
+                    &Position::standard(), BookSelection::Weighted, &mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        assert_that!(select(42)).is_equal_to(select(42));
+    }
+
+    #[test]
+    fn select_move_returns_none_for_unknown_position() {
+        let mov = FixedBook(Vec::new()).select_move(&Position::standard(), BookSelection::Weighted);
+
+        assert_that!(mov).is_none();
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::book::{BookEntry, OpeningBook};
+use crate::chess::ChessError;
+use crate::chess::pgn::{self, PgnError, PgnGame, PgnNode};
+use crate::chess::position::Position;
+use crate::chess::uci::UciMove;
+use crate::model::game::Color;
+
+/// An error that occurs when building a [PgnBook].
+#[derive(Debug, Error)]
+pub enum PgnBookError {
+
+    #[error("error reading PGN: {0}")]
+    Pgn(#[from] PgnError),
+
+    #[error("error reading PGN file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid FEN tag: {0}")]
+    InvalidFen(ChessError),
+
+    #[error("invalid move `{san}`: {source}")]
+    InvalidMove {
+        san: String,
+        source: ChessError
+    }
+}
+
+pub type PgnBookResult<T> = Result<T, PgnBookError>;
+
+#[derive(Clone, Copy)]
+enum Outcome {
+    Win(Color),
+    Draw,
+    Unknown
+}
+
+fn outcome_of(game: &PgnGame) -> Outcome {
+    match game.tag("Result") {
+        Some("1-0") => Outcome::Win(Color::White),
+        Some("0-1") => Outcome::Win(Color::Black),
+        Some("1/2-1/2") => Outcome::Draw,
+        _ => Outcome::Unknown
+    }
+}
+
+/// An [OpeningBook] built from the lines of PGN games, such as a personal repertoire or a Lichess
+/// study export. All moves of the main lines and variations are stored in a trie of positions, so
+/// transpositions share their entries. Each occurrence of a move adds one to its weight, and the
+/// `Result` tag of the game is counted towards its wins, draws, and losses.
+#[derive(Clone, Debug, Default)]
+pub struct PgnBook {
+    entries: HashMap<String, Vec<BookEntry>>,
+    max_ply: Option<usize>
+}
+
+impl PgnBook {
+
+    /// Creates a new, empty PGN book without a ply limit.
+    pub fn new() -> PgnBook {
+        PgnBook::default()
+    }
+
+    /// Limits the number of half-moves of each line which are added to the book. This only
+    /// affects games added after this call.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_ply`: The maximum number of half-moves per line, counted from the start of the game.
+    ///
+    /// # Returns
+    ///
+    /// This book, for chaining.
+    pub fn with_max_ply(mut self, max_ply: usize) -> PgnBook {
+        self.max_ply = Some(max_ply);
+        self
+    }
+
+    /// Creates a new book from all games in the given PGN.
+    ///
+    /// # Arguments
+    ///
+    /// * `pgn`: The PGN text containing the games to add.
+    ///
+    /// # Errors
+    ///
+    /// Any [PgnBookError] if the PGN is malformed or contains illegal moves.
+    pub fn from_pgn(pgn: &str) -> PgnBookResult<PgnBook> {
+        let mut book = PgnBook::new();
+        book.add_pgn(pgn)?;
+        Ok(book)
+    }
+
+    /// Creates a new book from all games in the PGN file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the PGN file to load.
+    ///
+    /// # Errors
+    ///
+    /// * [PgnBookError::Io] if the file cannot be read.
+    /// * Any other [PgnBookError] if the PGN is malformed or contains illegal moves.
+    pub fn load(path: impl AsRef<Path>) -> PgnBookResult<PgnBook> {
+        PgnBook::from_pgn(&std::fs::read_to_string(path)?)
+    }
+
+    /// Adds all games in the given PGN to this book. If an error occurs, games before the
+    /// erroneous one remain in the book.
+    ///
+    /// # Arguments
+    ///
+    /// * `pgn`: The PGN text containing the games to add.
+    ///
+    /// # Errors
+    ///
+    /// Any [PgnBookError] if the PGN is malformed or contains illegal moves.
+    pub fn add_pgn(&mut self, pgn: &str) -> PgnBookResult<()> {
+        for game in pgn::read_pgn(pgn)? {
+            self.add_game(&game)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the main line and all variations of the given game to this book.
+    ///
+    /// # Arguments
+    ///
+    /// * `game`: The [PgnGame] to add.
+    ///
+    /// # Errors
+    ///
+    /// * [PgnBookError::InvalidFen] if the game has an invalid `FEN` tag.
+    /// * [PgnBookError::InvalidMove] if any move is invalid or illegal.
+    pub fn add_game(&mut self, game: &PgnGame) -> PgnBookResult<()> {
+        let position = match game.tag("FEN") {
+            Some(fen) => Position::from_fen(fen).map_err(PgnBookError::InvalidFen)?,
+            None => Position::standard()
+        };
+
+        self.add_line(position, &game.moves, 0, outcome_of(game))
+    }
+
+    fn add_line(&mut self, mut position: Position, line: &[PgnNode], mut ply: usize,
+            outcome: Outcome) -> PgnBookResult<()> {
+        for node in line {
+            if self.max_ply.is_some_and(|max_ply| ply >= max_ply) {
+                break;
+            }
+
+            for variation in &node.variations {
+                self.add_line(position.clone(), variation, ply, outcome)?;
+            }
+
+            let mov = position.parse_san(&node.san)
+                .map_err(|source| PgnBookError::InvalidMove {
+                    san: node.san.clone(),
+                    source
+                })?;
+
+            self.add_move(&position, mov, outcome);
+            position.apply(&mov);
+            ply += 1;
+        }
+
+        Ok(())
+    }
+
+    fn add_move(&mut self, position: &Position, mov: UciMove, outcome: Outcome) {
+        let entries = self.entries.entry(position.fen_key()).or_default();
+        let index = match entries.iter().position(|entry| entry.mov == mov) {
+            Some(index) => index,
+            None => {
+                entries.push(BookEntry {
+                    mov,
+                    weight: 0,
+                    wins: 0,
+                    draws: 0,
+                    losses: 0
+                });
+                entries.len() - 1
+            }
+        };
+        let entry = &mut entries[index];
+
+        entry.weight += 1;
+
+        match outcome {
+            Outcome::Win(color) if color == position.side_to_move() => entry.wins += 1,
+            Outcome::Win(_) => entry.losses += 1,
+            Outcome::Draw => entry.draws += 1,
+            Outcome::Unknown => { }
+        }
+    }
+
+    /// Gets the number of distinct positions stored in this book.
+    pub fn position_count(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl OpeningBook for PgnBook {
+    fn entries(&self, position: &Position) -> Vec<BookEntry> {
+        self.entries.get(&position.fen_key()).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::book::BookSelection;
+
+    use super::*;
+
+    const REPERTOIRE: &str = r#"[Event "Repertoire"]
+[Result "1-0"]
+
+1. e4 e5 (1... c5 2. Nf3) 2. Nf3 Nc6 1-0
+
+[Event "Repertoire"]
+[Result "1/2-1/2"]
+
+1. e4 c5 2. Nc3 1/2-1/2
+
+[Event "Repertoire"]
+[Result "0-1"]
+
+1. d4 d5 0-1
+"#;
+
+    fn position_after(moves: &str) -> Position {
+        let mut position = Position::standard();
+        position.play_uci_moves(moves).unwrap();
+        position
+    }
+
+    fn entry_for(book: &PgnBook, position: &Position, mov: &str) -> Option<BookEntry> {
+        let mov = mov.parse().unwrap();
+
+        book.entries(position).into_iter().find(|entry| entry.mov == mov)
+    }
+
+    #[test]
+    fn main_lines_and_variations_are_added() {
+        let book = PgnBook::from_pgn(REPERTOIRE).unwrap();
+
+        let entries = book.entries(&position_after("e2e4"));
+        let moves = entries.iter().map(|entry| entry.mov.to_string()).collect::<Vec<_>>();
+
+        assert_that!(moves).contains_exactly_in_any_order(["e7e5".to_owned(), "c7c5".to_owned()]);
+    }
+
+    #[rstest]
+    #[case::win_for_mover("", "e2e4", 2, 1, 1, 0)]
+    #[case::loss_for_mover("", "d2d4", 1, 0, 0, 1)]
+    #[case::black_perspective("e2e4", "c7c5", 2, 0, 1, 1)]
+    #[case::shared_by_variation("e2e4 c7c5", "g1f3", 1, 1, 0, 0)]
+    fn statistics_are_counted_from_mover_perspective(#[case] moves: &str, #[case] mov: &str,
+            #[case] weight: u32, #[case] wins: u32, #[case] draws: u32, #[case] losses: u32) {
+        let book = PgnBook::from_pgn(REPERTOIRE).unwrap();
+
+        let entry = entry_for(&book, &position_after(moves), mov).unwrap();
+
+        assert_that!(entry.weight).is_equal_to(weight);
+        assert_that!(entry.wins).is_equal_to(wins);
+        assert_that!(entry.draws).is_equal_to(draws);
+        assert_that!(entry.losses).is_equal_to(losses);
+    }
+
+    #[test]
+    fn transpositions_share_entries() {
+        let pgn = "1. Nf3 Nf6 2. Nc3 *\n\n1. Nc3 Nf6 2. Nf3 Nc6 *";
+        let book = PgnBook::from_pgn(pgn).unwrap();
+
+        let entry = entry_for(&book, &position_after("g1f3 g8f6 b1c3"), "b8c6");
+
+        assert_that!(entry.map(|entry| entry.weight)).contains(1);
+    }
+
+    #[test]
+    fn fen_tag_is_respected() {
+        let pgn = "[FEN \"4k3/8/8/8/8/8/8/R3K3 w Q - 0 1\"]\n\n1. O-O-O *";
+        let book = PgnBook::from_pgn(pgn).unwrap();
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+
+        let mov = book.select_move(&position, BookSelection::MostPlayed);
+
+        assert_that!(mov).contains("e1c1".parse::<UciMove>().unwrap());
+    }
+
+    #[test]
+    fn max_ply_limits_line_length() {
+        let mut book = PgnBook::new().with_max_ply(2);
+        book.add_pgn(REPERTOIRE).unwrap();
+
+        assert_that!(book.entries(&position_after("e2e4 e7e5"))).is_empty();
+        assert_that!(book.entries(&position_after("e2e4"))).is_not_empty();
+    }
+
+    #[test]
+    fn select_move_uses_statistics() {
+        let book = PgnBook::from_pgn(REPERTOIRE).unwrap();
+
+        let mov = book.select_move(&Position::standard(), BookSelection::BestScore);
+
+        assert_that!(mov).contains("e2e4".parse::<UciMove>().unwrap());
+    }
+
+    #[rstest]
+    #[case::illegal_move("1. e5 *")]
+    #[case::invalid_fen("[FEN \"not a fen\"]\n\n1. e4 *")]
+    #[case::malformed_pgn("1. e4 (1. d4 *")]
+    fn invalid_pgn_is_rejected(#[case] pgn: &str) {
+        assert_that!(PgnBook::from_pgn(pgn)).is_err();
+    }
+}
@@ -0,0 +1,65 @@
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// A shared reference which compares and hashes by the address of the referenced value instead of
+/// the value itself. This allows shared state, such as the trackers of a
+/// [BotRunner](crate::runner::BotRunner), to be part of value types such as
+/// [GameContext](crate::context::GameContext), whose clones are equal to each other.
+#[derive(Debug)]
+pub(crate) struct ByAddress<T>(pub(crate) Arc<T>);
+
+impl<T> Clone for ByAddress<T> {
+    fn clone(&self) -> ByAddress<T> {
+        ByAddress(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Default> Default for ByAddress<T> {
+    fn default() -> ByAddress<T> {
+        ByAddress(Arc::default())
+    }
+}
+
+impl<T> PartialEq for ByAddress<T> {
+    fn eq(&self, other: &ByAddress<T>) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> Eq for ByAddress<T> { }
+
+impl<T> Hash for ByAddress<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::hash_map::DefaultHasher;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn hash(value: &ByAddress<i32>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn clones_are_equal() {
+        let value = ByAddress(Arc::new(42));
+        let clone = value.clone();
+
+        assert_that!(&clone).is_equal_to(&value);
+        assert_that!(hash(&clone)).is_equal_to(hash(&value));
+    }
+
+    #[test]
+    fn references_to_equal_values_at_different_addresses_are_not_equal() {
+        assert_that!(ByAddress(Arc::new(42))).is_not_equal_to(ByAddress(Arc::new(42)));
+    }
+}
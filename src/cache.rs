@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+use serde_json::Error as JsonError;
+
+use thiserror::Error;
+
+use crate::model::Timestamp;
+
+/// An error that occurs when loading or saving a [ResponseCache].
+#[derive(Debug, Error)]
+pub enum CacheError {
+
+    #[error("error accessing cache file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("error serializing or deserializing cache: {0}")]
+    Json(#[from] JsonError)
+}
+
+pub type CacheResult<T> = Result<T, CacheError>;
+
+fn timestamp(time: SystemTime) -> Timestamp {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as Timestamp
+}
+
+/// Reduces the given FEN to the fields which determine the position, i.e. the piece placement,
+/// the side to move, the castling rights, and the en passant square. The move counters are
+/// dropped, so transpositions reached at different moves share a cache entry.
+fn cache_key(fen: &str) -> String {
+    fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ")
+}
+
+/// Counts of the lookups in a [ResponseCache] since it was created, e.g. to export them to a
+/// monitoring system.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+pub struct CacheStats {
+
+    /// The number of lookups which found an entry that had not expired.
+    pub hits: u64,
+
+    /// The number of lookups which found no entry or only an expired one.
+    pub misses: u64,
+
+    /// The number of entries currently in the cache, including expired ones which were not
+    /// removed yet.
+    pub entries: usize
+}
+
+impl CacheStats {
+
+    /// The fraction of lookups which were hits, in the range `[0, 1]`. If there were no lookups,
+    /// this is 0.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+
+        if lookups == 0 {
+            0.0
+        }
+        else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry<V> {
+    value: V,
+    expires_at: Timestamp
+}
+
+#[derive(Debug)]
+struct CacheState<V> {
+    entries: HashMap<String, CacheEntry<V>>,
+    stats: CacheStats
+}
+
+/// An in-memory cache of responses of rate-limited services keyed by position, such as the
+/// tablebase (see [CachedTablebase](crate::tablebase::CachedTablebase)), the opening explorer
+/// (see [CachedExplorer](crate::explorer::CachedExplorer)), or the cloud evaluation. Entries
+/// expire after a fixed time to live. Positions are identified by their FEN without the move
+/// counters. A cache created with [ResponseCache::open] is backed by a JSON file, so entries
+/// survive restarts of the bot. Share the cache using an [Arc](std::sync::Arc) to read its
+/// [CacheStats] or save it while it is in use. Register it with
+/// [HealthMonitor::with_cache](crate::runner::health::HealthMonitor::with_cache) to include its
+/// [CacheStats] in the [HealthReport](crate::runner::health::HealthReport).
+#[derive(Debug)]
+pub struct ResponseCache<V> {
+    ttl: Duration,
+    path: Option<PathBuf>,
+    state: Mutex<CacheState<V>>
+}
+
+impl<V: Clone> ResponseCache<V> {
+
+    /// Creates a new, empty cache which is only held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl`: The time after which entries expire.
+    pub fn new(ttl: Duration) -> ResponseCache<V> {
+        ResponseCache::with_entries(ttl, None, HashMap::new())
+    }
+
+    fn with_entries(ttl: Duration, path: Option<PathBuf>,
+            entries: HashMap<String, CacheEntry<V>>) -> ResponseCache<V> {
+        ResponseCache {
+            ttl,
+            path,
+            state: Mutex::new(CacheState {
+                entries,
+                stats: CacheStats::default()
+            })
+        }
+    }
+
+    /// The time after which entries expire.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Looks up the cached response for the position with the given FEN. Lookups are counted in
+    /// the [CacheStats].
+    ///
+    /// # Returns
+    ///
+    /// The cached response, or [None] if there is none or it expired.
+    pub fn get(&self, fen: &str) -> Option<V> {
+        self.get_at(fen, SystemTime::now())
+    }
+
+    fn get_at(&self, fen: &str, now: SystemTime) -> Option<V> {
+        let mut state = self.state.lock().unwrap();
+        let value = state.entries.get(&cache_key(fen))
+            .filter(|entry| entry.expires_at > timestamp(now))
+            .map(|entry| entry.value.clone());
+
+        match value {
+            Some(_) => state.stats.hits += 1,
+            None => state.stats.misses += 1
+        }
+
+        value
+    }
+
+    /// Stores the response for the position with the given FEN, replacing any previous one. It
+    /// expires after the time to live of this cache.
+    pub fn insert(&self, fen: &str, value: V) {
+        self.insert_at(fen, value, SystemTime::now());
+    }
+
+    fn insert_at(&self, fen: &str, value: V, now: SystemTime) {
+        let entry = CacheEntry {
+            value,
+            expires_at: timestamp(now + self.ttl)
+        };
+
+        self.state.lock().unwrap().entries.insert(cache_key(fen), entry);
+    }
+
+    /// Looks up the cached response for the position with the given FEN or, if there is none,
+    /// fetches it and stores it in the cache. Failed fetches are not cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `fen`: The FEN of the position whose response to get.
+    /// * `fetch`: Fetches the response if it is not cached.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by `fetch`.
+    pub async fn get_or_fetch<F, E>(&self, fen: &str, fetch: F) -> Result<V, E>
+    where
+        F: Future<Output = Result<V, E>>
+    {
+        if let Some(value) = self.get(fen) {
+            return Ok(value);
+        }
+
+        let value = fetch.await?;
+        self.insert(fen, value.clone());
+
+        Ok(value)
+    }
+
+    /// Removes all expired entries, e.g. to bound the memory of a long-running bot.
+    pub fn remove_expired(&self) {
+        let now = timestamp(SystemTime::now());
+
+        self.state.lock().unwrap().entries.retain(|_, entry| entry.expires_at > now);
+    }
+
+    /// Removes all entries. The [CacheStats] are kept.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+
+    /// Gets the current [CacheStats] of this cache.
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap();
+
+        CacheStats {
+            entries: state.entries.len(),
+            ..state.stats
+        }
+    }
+}
+
+/// A [ResponseCache] of any value type whose [CacheStats] can be read.
+pub(crate) trait CacheStatsSource : Send + Sync {
+
+    fn stats(&self) -> CacheStats;
+}
+
+impl<V: Clone + Send> CacheStatsSource for ResponseCache<V> {
+    fn stats(&self) -> CacheStats {
+        ResponseCache::stats(self)
+    }
+}
+
+impl<V: Clone + Serialize + DeserializeOwned> ResponseCache<V> {
+
+    /// Opens a cache backed by the JSON file at the given path. Entries which have not expired
+    /// are loaded from the file if it exists. Call [ResponseCache::save] to write the entries
+    /// back, e.g. when the bot shuts down.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the file in which to store the cache.
+    /// * `ttl`: The time after which entries expire.
+    ///
+    /// # Errors
+    ///
+    /// * [CacheError::Io] if the file exists but cannot be read.
+    /// * [CacheError::Json] if the file does not contain a valid cache.
+    pub fn open(path: impl AsRef<Path>, ttl: Duration) -> CacheResult<ResponseCache<V>> {
+        let path = path.as_ref().to_owned();
+        let mut entries = HashMap::<String, CacheEntry<V>>::new();
+
+        if path.exists() {
+            let now = timestamp(SystemTime::now());
+
+            entries = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            entries.retain(|_, entry| entry.expires_at > now);
+        }
+
+        Ok(ResponseCache::with_entries(ttl, Some(path), entries))
+    }
+
+    /// The path of the file backing this cache, or [None] if it is only held in memory.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Writes all entries which have not expired to the file backing this cache. If the cache is
+    /// only held in memory, nothing happens.
+    ///
+    /// # Errors
+    ///
+    /// * [CacheError::Io] if the file cannot be written.
+    /// * [CacheError::Json] if an entry cannot be serialized.
+    pub fn save(&self) -> CacheResult<()> {
+        let Some(path) = &self.path
+        else {
+            return Ok(());
+        };
+
+        self.remove_expired();
+
+        let json = serde_json::to_string(&self.state.lock().unwrap().entries)?;
+
+        fs::write(path, json)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::convert::Infallible;
+    use std::env;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    const FEN: &str = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+
+    fn cache() -> ResponseCache<u32> {
+        ResponseCache::new(Duration::from_secs(60))
+    }
+
+    #[test]
+    fn inserted_value_is_found_until_it_expires() {
+        let cache = cache();
+        let now = SystemTime::now();
+
+        cache.insert_at(FEN, 42, now);
+
+        assert_that!(cache.get_at(FEN, now + Duration::from_secs(59))).contains(42);
+        assert_that!(cache.get_at(FEN, now + Duration::from_secs(60))).is_none();
+    }
+
+    #[test]
+    fn move_counters_are_ignored() {
+        let cache = cache();
+
+        cache.insert(FEN, 42);
+
+        assert_that!(cache.get("4k3/8/8/8/8/8/8/4K2R w K - 12 37")).contains(42);
+        assert_that!(cache.get("4k3/8/8/8/8/8/8/4K2R b K - 0 1")).is_none();
+    }
+
+    #[test]
+    fn lookups_are_counted() {
+        let cache = cache();
+
+        cache.get(FEN);
+        cache.insert(FEN, 42);
+        cache.get(FEN);
+        cache.get(FEN);
+
+        let stats = cache.stats();
+
+        assert_that!(stats).is_equal_to(CacheStats {
+            hits: 2,
+            misses: 1,
+            entries: 1
+        });
+        assert_that!(stats.hit_rate()).is_close_to(2.0 / 3.0, 1e-9);
+    }
+
+    #[test]
+    fn hit_rate_without_lookups_is_zero() {
+        assert_that!(CacheStats::default().hit_rate()).is_equal_to(0.0);
+    }
+
+    #[test]
+    fn get_or_fetch_only_fetches_missing_values() {
+        tokio_test::block_on(async {
+            let cache = cache();
+
+            let first = cache.get_or_fetch(FEN, async { Ok::<_, Infallible>(1) }).await;
+            let second = cache.get_or_fetch(FEN, async { Ok::<_, Infallible>(2) }).await;
+
+            assert_that!(first).contains_value(1);
+            assert_that!(second).contains_value(1);
+        });
+    }
+
+    #[test]
+    fn failed_fetch_is_not_cached() {
+        tokio_test::block_on(async {
+            let cache = cache();
+
+            let result = cache.get_or_fetch(FEN, async { Err("unavailable") }).await;
+
+            assert_that!(result).contains_error("unavailable");
+            assert_that!(cache.stats().entries).is_equal_to(0);
+        });
+    }
+
+    #[test]
+    fn saved_cache_is_loaded_again() {
+        let path = env::temp_dir().join(format!("libot-cache-test-{}.json", std::process::id()));
+        let cache = ResponseCache::open(&path, Duration::from_secs(60)).unwrap();
+
+        cache.insert(FEN, 42);
+        cache.insert_at("8/8/8/8/8/8/8/K6k w - - 0 1", 7, SystemTime::now() - cache.ttl());
+        cache.save().unwrap();
+
+        let loaded = ResponseCache::<u32>::open(&path, Duration::from_secs(60)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_that!(loaded.path()).contains(path.as_path());
+        assert_that!(loaded.stats().entries).is_equal_to(1);
+        assert_that!(loaded.get(FEN)).contains(42);
+    }
+
+    #[test]
+    fn saving_in_memory_cache_does_nothing() {
+        let cache = cache();
+        cache.insert(FEN, 42);
+
+        assert_that!(cache.save()).is_ok();
+        assert_that!(cache.path()).is_none();
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::Bot;
+use crate::client::BotClient;
+use crate::context::GameContext;
+use crate::model::game::GameId;
+use crate::model::game::event::ChatLineEvent;
+use crate::model::user::UserId;
+
+/// The prefix of all chat commands, such as `!quit`.
+pub const COMMAND_PREFIX: char = '!';
+
+/// The name of the command with which destructive commands are confirmed.
+pub const CONFIRM_COMMAND: &str = "confirm";
+
+const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The level of permission required to issue a chat command, or granted to a user by an
+/// [OperatorList]. Higher levels include all lower ones.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Permission {
+
+    /// Any user who can post in the chat, including the opponent.
+    #[default]
+    Everyone,
+
+    /// Users trusted to adjust the bot while it plays, e.g. its search limits.
+    Operator,
+
+    /// The owner of the bot, who may also issue commands such as `!quit` or `!config`.
+    Owner
+}
+
+/// An allowlist of the users who may issue privileged chat commands, identified by their user
+/// IDs, i.e. their lower-case usernames. Users which are not listed have
+/// [Permission::Everyone].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OperatorList {
+    users: HashMap<UserId, Permission>
+}
+
+impl OperatorList {
+
+    /// Creates a new, empty allowlist.
+    pub fn new() -> OperatorList {
+        OperatorList::default()
+    }
+
+    /// Grants [Permission::Owner] to the user with the given ID. The list is returned for
+    /// chaining.
+    pub fn with_owner(self, user_id: impl Into<UserId>) -> OperatorList {
+        self.with_user(user_id, Permission::Owner)
+    }
+
+    /// Grants [Permission::Operator] to the user with the given ID. The list is returned for
+    /// chaining.
+    pub fn with_operator(self, user_id: impl Into<UserId>) -> OperatorList {
+        self.with_user(user_id, Permission::Operator)
+    }
+
+    /// Grants the given permission to the user with the given ID. The list is returned for
+    /// chaining.
+    pub fn with_user(mut self, user_id: impl Into<UserId>, permission: Permission)
+            -> OperatorList {
+        self.users.insert(user_id.into().to_lowercase(), permission);
+        self
+    }
+
+    /// The permission of the user with the given name, which may be capitalized differently than
+    /// the user ID.
+    pub fn permission(&self, username: &str) -> Permission {
+        self.users.get(&username.to_lowercase()).copied().unwrap_or_default()
+    }
+}
+
+/// A command posted in the chat of a game, consisting of a name following the
+/// [COMMAND_PREFIX] and whitespace-separated arguments.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ChatCommand {
+    pub name: String,
+    pub args: Vec<String>
+}
+
+impl ChatCommand {
+
+    /// Parses a chat line as a command, such as `!depth 8`.
+    ///
+    /// # Returns
+    ///
+    /// The parsed command, or [None] if the line does not start with the [COMMAND_PREFIX] or has
+    /// no name.
+    pub fn parse(text: &str) -> Option<ChatCommand> {
+        let mut tokens = text.trim().strip_prefix(COMMAND_PREFIX)?.split_whitespace();
+        let name = tokens.next()?.to_lowercase();
+
+        Some(ChatCommand {
+            name,
+            args: tokens.map(str::to_owned).collect()
+        })
+    }
+}
+
+/// Executes a chat command registered with a [ChatCommands] bot. This is implemented for all
+/// closures taking a [GameContext] and a [ChatCommand] reference and returning an optional reply.
+#[async_trait::async_trait]
+pub trait CommandHandler : Send + Sync {
+
+    /// Executes the given command, which was posted in the game with the given context by a user
+    /// with sufficient permission.
+    ///
+    /// # Returns
+    ///
+    /// A reply to post in the chat room of the command, if any.
+    async fn execute(&self, context: &GameContext, command: &ChatCommand, client: &BotClient)
+        -> Option<String>;
+}
+
+#[async_trait::async_trait]
+impl<F> CommandHandler for F
+where
+    F: Fn(&GameContext, &ChatCommand) -> Option<String> + Send + Sync
+{
+    async fn execute(&self, context: &GameContext, command: &ChatCommand, _client: &BotClient)
+            -> Option<String> {
+        self(context, command)
+    }
+}
+
+struct RegisteredCommand {
+    permission: Permission,
+    destructive: bool,
+    handler: Box<dyn CommandHandler>
+}
+
+struct PendingConfirmation {
+    command: ChatCommand,
+    code: String,
+    expires_at: Instant
+}
+
+/// A [Bot] which executes chat commands posted in games, but only if the poster has the
+/// [Permission] required by the command according to an [OperatorList]. Commands of users without
+/// permission and unknown commands are ignored without a reply. Combine it with other bots using
+/// the [combinators](crate::combinator).
+///
+/// Destructive commands, such as `!quit`, are only executed after a confirmation. Instead of
+/// executing them right away, the bot replies with a confirmation code, which the same user has to
+/// post with `!confirm <code>` in the same game before the confirmation timeout. Only one attempt
+/// is allowed per confirmation. The code is a random one-time value which is stored together with
+/// the pending command for the user and the game, so it cannot be used to confirm any other command
+/// or a later repetition of the same command. It is not a signature and does not authenticate the
+/// user beyond the [OperatorList], but guards against accidental or replayed commands.
+pub struct ChatCommands {
+    operators: OperatorList,
+    commands: HashMap<String, RegisteredCommand>,
+    confirmation_timeout: Duration,
+    pending: Mutex<HashMap<(GameId, UserId), PendingConfirmation>>
+}
+
+impl ChatCommands {
+
+    /// Creates a new bot without any commands, which grants permissions according to the given
+    /// allowlist. Destructive commands must be confirmed within 60 seconds.
+    pub fn new(operators: OperatorList) -> ChatCommands {
+        ChatCommands {
+            operators,
+            commands: HashMap::new(),
+            confirmation_timeout: DEFAULT_CONFIRMATION_TIMEOUT,
+            pending: Mutex::new(HashMap::new())
+        }
+    }
+
+    fn with_registered_command(mut self, name: &str, permission: Permission, destructive: bool,
+            handler: impl CommandHandler + 'static) -> ChatCommands {
+        self.commands.insert(name.to_lowercase(), RegisteredCommand {
+            permission,
+            destructive,
+            handler: Box::new(handler)
+        });
+        self
+    }
+
+    /// Registers a command with the given name, without [COMMAND_PREFIX], which is executed by the
+    /// given handler whenever a user with at least the given permission posts it. The bot is
+    /// returned for chaining.
+    pub fn with_command(self, name: &str, permission: Permission,
+            handler: impl CommandHandler + 'static) -> ChatCommands {
+        self.with_registered_command(name, permission, false, handler)
+    }
+
+    /// Registers a destructive command like [ChatCommands::with_command], which is only executed
+    /// once the user confirmed it. The bot is returned for chaining.
+    pub fn with_destructive_command(self, name: &str, permission: Permission,
+            handler: impl CommandHandler + 'static) -> ChatCommands {
+        self.with_registered_command(name, permission, true, handler)
+    }
+
+    /// Sets the time within which destructive commands must be confirmed. The bot is returned for
+    /// chaining.
+    pub fn with_confirmation_timeout(mut self, confirmation_timeout: Duration) -> ChatCommands {
+        self.confirmation_timeout = confirmation_timeout;
+        self
+    }
+
+    /// The allowlist which determines the permissions of users.
+    pub fn operators(&self) -> &OperatorList {
+        &self.operators
+    }
+
+    fn request_confirmation(&self, context: &GameContext, user_id: UserId, command: ChatCommand)
+            -> String {
+        let code = format!("{:08x}", rand::random::<u32>());
+        let reply = format!("Confirm {COMMAND_PREFIX}{} within {} seconds with \
+            {COMMAND_PREFIX}{CONFIRM_COMMAND} {code}", command.name,
+            self.confirmation_timeout.as_secs());
+
+        self.pending.lock().unwrap().insert((context.id.clone(), user_id), PendingConfirmation {
+            command,
+            code,
+            expires_at: Instant::now() + self.confirmation_timeout
+        });
+
+        reply
+    }
+
+    /// Handles a chat line posted by the user with the given name in the game with the given
+    /// context.
+    ///
+    /// # Returns
+    ///
+    /// The reply to post in the chat, if any.
+    async fn handle(&self, context: &GameContext, username: &str, text: &str,
+            client: &BotClient) -> Option<String> {
+        let command = ChatCommand::parse(text)?;
+        let permission = self.operators.permission(username);
+        let user_id = username.to_lowercase();
+
+        if command.name == CONFIRM_COMMAND {
+            let pending = self.pending.lock().unwrap().remove(&(context.id.clone(), user_id))?;
+
+            if command.args != [pending.code] || Instant::now() > pending.expires_at {
+                return Some("Invalid or expired confirmation.".to_owned());
+            }
+
+            let registered = self.commands.get(&pending.command.name)?;
+
+            return registered.handler.execute(context, &pending.command, client).await;
+        }
+
+        let registered = self.commands.get(&command.name)?;
+
+        if permission < registered.permission {
+            return None;
+        }
+
+        if registered.destructive {
+            return Some(self.request_confirmation(context, user_id, command));
+        }
+
+        registered.handler.execute(context, &command, client).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Bot for ChatCommands {
+
+    async fn on_chat_line(&self, context: &GameContext, chat_line: ChatLineEvent,
+            client: &BotClient) {
+        let chat_line = chat_line.chat_line;
+        let reply = self.handle(context, &chat_line.username, &chat_line.text, client).await;
+
+        if let Some(reply) = reply {
+            // TODO enable error handling
+            let _ = client.send_chat_message(context.id.clone(), chat_line.room, reply).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::game::Color;
+    use crate::store::tests as store_tests;
+    use crate::test_util;
+
+    use super::*;
+
+    fn test_context() -> GameContext {
+        GameContext {
+            bot_id: "testBotId".to_owned(),
+            bot_color: Some(Color::White),
+            info: store_tests::test_game_info("testGameId", "testBotId", "opponent"),
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    fn counting_handler(count: &Arc<AtomicUsize>)
+            -> impl Fn(&GameContext, &ChatCommand) -> Option<String> {
+        let count = Arc::clone(count);
+
+        move |_: &GameContext, command: &ChatCommand| {
+            count.fetch_add(1, Ordering::SeqCst);
+            Some(format!("executed {}", command.name))
+        }
+    }
+
+    fn test_commands(count: &Arc<AtomicUsize>) -> ChatCommands {
+        let operators = OperatorList::new()
+            .with_owner("owner")
+            .with_operator("Operator");
+
+        ChatCommands::new(operators)
+            .with_command("eval", Permission::Operator, counting_handler(count))
+            .with_command("help", Permission::Everyone, counting_handler(count))
+            .with_destructive_command("quit", Permission::Owner, counting_handler(count))
+    }
+
+    fn confirmation_code(reply: &str) -> String {
+        reply.rsplit(' ').next().unwrap().to_owned()
+    }
+
+    #[rstest]
+    #[case::no_arguments("!quit", "quit", &[])]
+    #[case::arguments(" !Depth 8 now ", "depth", &["8", "now"])]
+    fn parse_chat_command(#[case] text: &str, #[case] name: &str, #[case] args: &[&str]) {
+        assert_that!(ChatCommand::parse(text)).contains(ChatCommand {
+            name: name.to_owned(),
+            args: args.iter().map(|&arg| arg.to_owned()).collect()
+        });
+    }
+
+    #[rstest]
+    #[case::no_prefix("quit")]
+    #[case::no_name("! ")]
+    fn parse_invalid_chat_command(#[case] text: &str) {
+        assert_that!(ChatCommand::parse(text)).is_none();
+    }
+
+    #[rstest]
+    #[case::owner("OWNER", Permission::Owner)]
+    #[case::operator("operator", Permission::Operator)]
+    #[case::unlisted("opponent", Permission::Everyone)]
+    fn permission_of_user(#[case] username: &str, #[case] expected: Permission) {
+        let operators = OperatorList::new().with_owner("Owner").with_operator("operator");
+
+        assert_that!(operators.permission(username)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::operator_command_by_operator("operator", "!eval", Some("executed eval"))]
+    #[case::operator_command_by_owner("owner", "!eval", Some("executed eval"))]
+    #[case::operator_command_by_opponent("opponent", "!eval", None)]
+    #[case::public_command_by_opponent("opponent", "!help", Some("executed help"))]
+    #[case::owner_command_by_operator("operator", "!quit", None)]
+    #[case::unknown_command("owner", "!resign", None)]
+    fn commands_require_permission(#[case] username: &str, #[case] text: &str,
+            #[case] expected: Option<&str>) {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let commands = test_commands(&Arc::new(AtomicUsize::new(0)));
+
+            let reply = commands.handle(&test_context(), username, text, &client).await;
+
+            assert_that!(reply).is_equal_to(expected.map(str::to_owned));
+        });
+    }
+
+    #[test]
+    fn destructive_command_is_executed_after_confirmation() {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let count = Arc::new(AtomicUsize::new(0));
+            let commands = test_commands(&count);
+            let context = test_context();
+
+            let request = commands.handle(&context, "owner", "!quit", &client).await.unwrap();
+            let executed_before_confirmation = count.load(Ordering::SeqCst);
+            let confirm = format!("!confirm {}", confirmation_code(&request));
+            let reply = commands.handle(&context, "Owner", &confirm, &client).await;
+
+            assert_that!(executed_before_confirmation).is_equal_to(0);
+            assert_that!(reply).contains("executed quit".to_owned());
+            assert_that!(count.load(Ordering::SeqCst)).is_equal_to(1);
+        });
+    }
+
+    #[rstest]
+    #[case::wrong_code("owner", Some("00000000"), Duration::from_secs(60),
+        Some("Invalid or expired confirmation."))]
+    #[case::expired("owner", None, Duration::ZERO, Some("Invalid or expired confirmation."))]
+    #[case::other_user("operator", None, Duration::from_secs(60), None)]
+    fn invalid_confirmation_is_rejected(#[case] username: &str, #[case] code: Option<&str>,
+            #[case] timeout: Duration, #[case] expected: Option<&str>) {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let count = Arc::new(AtomicUsize::new(0));
+            let commands = test_commands(&count).with_confirmation_timeout(timeout);
+            let context = test_context();
+
+            let request = commands.handle(&context, "owner", "!quit", &client).await.unwrap();
+            let code = code.map_or_else(|| confirmation_code(&request), str::to_owned);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            let reply = commands.handle(&context, username, &format!("!confirm {code}"), &client)
+                .await;
+
+            assert_that!(reply).is_equal_to(expected.map(str::to_owned));
+            assert_that!(count.load(Ordering::SeqCst)).is_equal_to(0);
+        });
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use crate::model::user::preferences::Language;
+
+/// Fills the placeholders of the form `{name}` in the given template with the values of the
+/// arguments of the same name. Placeholders without a matching argument are kept unchanged. Use
+/// `{{` and `}}` to write literal braces.
+fn fill_template(template: &str, args: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(index) = rest.find(['{', '}']) {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            result.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let placeholder = rest.strip_prefix('{')
+            .and_then(|after| after.find('}').map(|end| &after[..end]))
+            .filter(|name| !name.contains('{'));
+        let value = placeholder.and_then(|name| args.iter()
+            .find(|(arg_name, _)| *arg_name == name)
+            .map(|(_, value)| *value));
+
+        match (placeholder, value) {
+            (Some(name), Some(value)) => {
+                result.push_str(value);
+                rest = &rest[name.len() + 2..];
+            },
+            _ => {
+                result.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// The primary subtag of a language tag, e.g. `de` for `de-CH`.
+pub(crate) fn primary_language(language: &str) -> &str {
+    language.split(['-', '_']).next().unwrap_or(language)
+}
+
+/// The chat message templates of a bot in one language, identified by keys chosen by the bot
+/// author. Templates may contain placeholders of the form `{name}`, which are filled when the
+/// message is localized (see [ChatLocalizer::localize]). Use `{{` and `}}` to write literal
+/// braces.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MessageCatalog {
+    language: Language,
+    messages: HashMap<String, String>
+}
+
+impl MessageCatalog {
+
+    /// Creates a new, empty catalog for the given language, such as `en` or `de-CH`.
+    pub fn new(language: impl Into<Language>) -> MessageCatalog {
+        MessageCatalog {
+            language: language.into(),
+            messages: HashMap::new()
+        }
+    }
+
+    /// Adds a message template with the given key, replacing any previous template with the same
+    /// key. The catalog is returned for chaining.
+    pub fn with_message(mut self, key: impl Into<String>, template: impl Into<String>)
+            -> MessageCatalog {
+        self.messages.insert(key.into(), template.into());
+        self
+    }
+
+    /// The language of the messages in this catalog.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Gets the template of the message with the given key, if there is one.
+    pub fn template(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+}
+
+/// Localizes templated chat messages using [MessageCatalog]s supplied by the bot author, based on
+/// the preferred language of the recipient. Register it with
+/// [BotClientBuilder::with_chat_localizer](crate::client::BotClientBuilder::with_chat_localizer)
+/// to send localized messages with
+/// [BotClient::send_localized_chat_message](crate::client::BotClient::send_localized_chat_message).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChatLocalizer {
+    default_language: Language,
+    catalogs: HashMap<Language, MessageCatalog>
+}
+
+impl ChatLocalizer {
+
+    /// Creates a new localizer which uses the given catalog for recipients whose language is
+    /// unknown or not supported, and for messages missing in the catalog of their language.
+    pub fn new(default_catalog: MessageCatalog) -> ChatLocalizer {
+        let default_language = default_catalog.language.to_lowercase();
+        let mut catalogs = HashMap::new();
+        catalogs.insert(default_language.clone(), default_catalog);
+
+        ChatLocalizer {
+            default_language,
+            catalogs
+        }
+    }
+
+    /// Adds a catalog for another language, replacing any previous catalog for the same language.
+    /// The localizer is returned for chaining.
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> ChatLocalizer {
+        self.catalogs.insert(catalog.language.to_lowercase(), catalog);
+        self
+    }
+
+    /// The languages for which catalogs are available.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.catalogs.values().map(MessageCatalog::language)
+    }
+
+    fn catalog(&self, language: &str) -> Option<&MessageCatalog> {
+        let language = language.to_lowercase();
+
+        self.catalogs.get(&language)
+            .or_else(|| self.catalogs.get(primary_language(&language)))
+    }
+
+    /// Localizes the message with the given key for a recipient with the given preferred
+    /// language. The catalog of that language is used if available, otherwise that of its primary
+    /// language (e.g. `de` for `de-CH`), otherwise the default catalog.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: The key of the message to localize.
+    /// * `language`: The preferred language of the recipient, if known.
+    /// * `args`: The values of the placeholders in the template, by name.
+    ///
+    /// # Returns
+    ///
+    /// The localized message, or [None] if no suitable catalog contains the given key.
+    pub fn localize(&self, key: &str, language: Option<&str>, args: &[(&str, &str)])
+            -> Option<String> {
+        let template = language
+            .and_then(|language| self.catalog(language))
+            .and_then(|catalog| catalog.template(key))
+            .or_else(|| self.catalogs.get(&self.default_language)?.template(key))?;
+
+        Some(fill_template(template, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn test_localizer() -> ChatLocalizer {
+        ChatLocalizer::new(MessageCatalog::new("en")
+                .with_message("greeting", "Good luck, {opponent}!")
+                .with_message("thanks", "Thanks for the game."))
+            .with_catalog(MessageCatalog::new("de")
+                .with_message("greeting", "Viel Glück, {opponent}!"))
+            .with_catalog(MessageCatalog::new("pt-BR")
+                .with_message("greeting", "Boa sorte, {opponent}!"))
+    }
+
+    #[rstest]
+    #[case::no_placeholders("hello", "hello")]
+    #[case::placeholder("hi {name}", "hi Alice")]
+    #[case::repeated("{name} {name}", "Alice Alice")]
+    #[case::unknown("hi {other}", "hi {other}")]
+    #[case::escaped("{{name}} {name}", "{name} Alice")]
+    #[case::unclosed("hi {name", "hi {name")]
+    #[case::nested("{{{name}}}", "{Alice}")]
+    fn templates_are_filled(#[case] template: &str, #[case] expected: &str) {
+        assert_that!(fill_template(template, &[("name", "Alice")]).as_str())
+            .is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::exact(Some("de"), "greeting", "Viel Glück, Bob!")]
+    #[case::case_insensitive(Some("PT-br"), "greeting", "Boa sorte, Bob!")]
+    #[case::primary_language(Some("de-CH"), "greeting", "Viel Glück, Bob!")]
+    #[case::unsupported_language(Some("fr"), "greeting", "Good luck, Bob!")]
+    #[case::unknown_language(None, "greeting", "Good luck, Bob!")]
+    #[case::missing_in_catalog(Some("de"), "thanks", "Thanks for the game.")]
+    fn messages_are_localized(#[case] language: Option<&str>, #[case] key: &str,
+            #[case] expected: &str) {
+        let message = test_localizer().localize(key, language, &[("opponent", "Bob")]);
+
+        assert_that!(message).contains(expected.to_owned());
+    }
+
+    #[test]
+    fn unknown_key_is_not_localized() {
+        assert_that!(test_localizer().localize("unknown", Some("de"), &[])).is_none();
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::model::game::chat::ChatRoom;
+use crate::model::game::GameId;
+
+/// The maximum number of characters Lichess accepts in a single chat message. Messages are only
+/// coalesced if the combined message does not exceed this length.
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 140;
+
+const COALESCE_SEPARATOR: &str = " ";
+
+/// Configures how [BotClient::send_chat_message](crate::client::BotClient::send_chat_message)
+/// limits the rate of outgoing chat messages. Set using
+/// [BotClientBuilder::with_chat_throttle](crate::client::BotClientBuilder::with_chat_throttle).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ChatThrottleConfig {
+    per_game_interval: Duration,
+    global_interval: Duration,
+    max_queue_delay: Option<Duration>,
+    coalesce: bool
+}
+
+impl ChatThrottleConfig {
+
+    /// Creates a new config with default values, i.e. at most one message every 2 seconds per
+    /// game and one message every 500 milliseconds across all games, queuing messages for up to
+    /// 10 seconds, and coalescing queued messages.
+    pub fn new() -> ChatThrottleConfig {
+        ChatThrottleConfig {
+            per_game_interval: Duration::from_secs(2),
+            global_interval: Duration::from_millis(500),
+            max_queue_delay: Some(Duration::from_secs(10)),
+            coalesce: true
+        }
+    }
+
+    /// Sets the minimum time between two messages sent in the same game. The config is returned
+    /// for chaining.
+    pub fn with_per_game_interval(mut self, interval: Duration) -> ChatThrottleConfig {
+        self.per_game_interval = interval;
+        self
+    }
+
+    /// Sets the minimum time between two messages sent in any games. The config is returned for
+    /// chaining.
+    pub fn with_global_interval(mut self, interval: Duration) -> ChatThrottleConfig {
+        self.global_interval = interval;
+        self
+    }
+
+    /// Sets the maximum time for which a message is delayed until it may be sent. Messages which
+    /// would have to wait longer are rejected. If [None], messages are never queued, i.e. every
+    /// message which cannot be sent immediately is rejected. The config is returned for chaining.
+    pub fn with_max_queue_delay(mut self, max_queue_delay: Option<Duration>)
+            -> ChatThrottleConfig {
+        self.max_queue_delay = max_queue_delay;
+        self
+    }
+
+    /// Sets whether a message is appended to a queued message in the same game and room instead
+    /// of being queued separately, as long as the combined message does not exceed
+    /// [MAX_CHAT_MESSAGE_LENGTH]. The config is returned for chaining.
+    pub fn with_coalesce(mut self, coalesce: bool) -> ChatThrottleConfig {
+        self.coalesce = coalesce;
+        self
+    }
+}
+
+impl Default for ChatThrottleConfig {
+    fn default() -> ChatThrottleConfig {
+        ChatThrottleConfig::new()
+    }
+}
+
+/// The outcome of requesting to send a chat message from a [ChatThrottle].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ChatReservation {
+
+    /// The message may be sent immediately.
+    Send,
+
+    /// The message may be sent at the given time. Afterwards, the final text of the message must
+    /// be obtained using [ChatThrottle::take_queued] with the given ticket.
+    Queued {
+        send_at: Instant,
+        ticket: u64
+    },
+
+    /// The message was appended to a queued message and must not be sent on its own.
+    Coalesced,
+
+    /// The message may not be sent, because it would have to wait longer than allowed.
+    Rejected {
+        retry_after: Duration
+    }
+}
+
+#[derive(Debug)]
+struct QueuedMessage {
+    ticket: u64,
+    text: String
+}
+
+#[derive(Debug, Default)]
+struct ChatThrottleState {
+    next_global: Option<Instant>,
+    next_per_game: HashMap<GameId, Instant>,
+    coalescable: HashMap<(GameId, ChatRoom), QueuedMessage>,
+    next_ticket: u64
+}
+
+/// Keeps track of the times at which chat messages were sent and decides when further messages
+/// may be sent according to a [ChatThrottleConfig]. Shared between all clones of a
+/// [BotClient](crate::client::BotClient).
+#[derive(Debug)]
+pub(crate) struct ChatThrottle {
+    config: ChatThrottleConfig,
+    state: Mutex<ChatThrottleState>
+}
+
+impl ChatThrottle {
+
+    pub(crate) fn new(config: ChatThrottleConfig) -> ChatThrottle {
+        ChatThrottle {
+            config,
+            state: Mutex::new(ChatThrottleState::default())
+        }
+    }
+
+    /// Requests to send a message with the given text in the given game and room at time `now`.
+    /// Unless the message is rejected or coalesced, its send time is reserved, i.e. subsequent
+    /// messages have to wait for it.
+    pub(crate) fn reserve(&self, game_id: &GameId, room: ChatRoom, text: &str, now: Instant)
+            -> ChatReservation {
+        let mut state = self.state.lock().unwrap();
+        let key = (game_id.clone(), room);
+
+        if self.config.coalesce {
+            if let Some(queued) = state.coalescable.get_mut(&key) {
+                let combined_length =
+                    queued.text.chars().count() + COALESCE_SEPARATOR.len() + text.chars().count();
+
+                if combined_length <= MAX_CHAT_MESSAGE_LENGTH {
+                    queued.text.push_str(COALESCE_SEPARATOR);
+                    queued.text.push_str(text);
+                    return ChatReservation::Coalesced;
+                }
+            }
+        }
+
+        let send_at = [state.next_global, state.next_per_game.get(game_id).cloned()].into_iter()
+            .flatten()
+            .fold(now, Instant::max);
+        let delay = send_at - now;
+
+        if !delay.is_zero() && self.config.max_queue_delay.is_none_or(|max| delay > max) {
+            return ChatReservation::Rejected {
+                retry_after: delay
+            };
+        }
+
+        state.next_global = Some(send_at + self.config.global_interval);
+        state.next_per_game.insert(game_id.clone(), send_at + self.config.per_game_interval);
+
+        if delay.is_zero() {
+            return ChatReservation::Send;
+        }
+
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+
+        if self.config.coalesce && !state.coalescable.contains_key(&key) {
+            state.coalescable.insert(key, QueuedMessage {
+                ticket,
+                text: text.to_owned()
+            });
+        }
+
+        ChatReservation::Queued {
+            send_at,
+            ticket
+        }
+    }
+
+    /// Gets the final text of a queued message once it is due, including any coalesced messages,
+    /// and stops further messages from being coalesced into it.
+    pub(crate) fn take_queued(&self, game_id: &GameId, room: ChatRoom, ticket: u64, text: String)
+            -> String {
+        let mut state = self.state.lock().unwrap();
+        let key = (game_id.clone(), room);
+
+        match state.coalescable.get(&key) {
+            Some(queued) if queued.ticket == ticket =>
+                state.coalescable.remove(&key).unwrap().text,
+            _ => text
+        }
+    }
+
+    /// Forgets the send times of the game with the given ID, which is no longer needed once it
+    /// has finished.
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.state.lock().unwrap().next_per_game.remove(game_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn game_id(id: &str) -> GameId {
+        id.to_owned()
+    }
+
+    fn throttle(config: ChatThrottleConfig) -> ChatThrottle {
+        ChatThrottle::new(config
+            .with_per_game_interval(Duration::from_secs(2))
+            .with_global_interval(Duration::from_secs(1)))
+    }
+
+    #[test]
+    fn first_message_is_sent_immediately() {
+        let throttle = throttle(ChatThrottleConfig::new());
+        let now = Instant::now();
+
+        assert_that!(throttle.reserve(&game_id("a"), ChatRoom::Player, "hi", now))
+            .is_equal_to(ChatReservation::Send);
+    }
+
+    #[test]
+    fn second_message_in_same_game_is_queued_by_per_game_interval() {
+        let throttle = throttle(ChatThrottleConfig::new().with_coalesce(false));
+        let now = Instant::now();
+
+        throttle.reserve(&game_id("a"), ChatRoom::Player, "hi", now);
+        let reservation = throttle.reserve(&game_id("a"), ChatRoom::Player, "there", now);
+
+        assert_that!(reservation).is_equal_to(ChatReservation::Queued {
+            send_at: now + Duration::from_secs(2),
+            ticket: 0
+        });
+    }
+
+    #[test]
+    fn message_in_other_game_is_queued_by_global_interval() {
+        let throttle = throttle(ChatThrottleConfig::new());
+        let now = Instant::now();
+
+        throttle.reserve(&game_id("a"), ChatRoom::Player, "hi", now);
+        let reservation = throttle.reserve(&game_id("b"), ChatRoom::Player, "hi", now);
+
+        assert_that!(reservation).is_equal_to(ChatReservation::Queued {
+            send_at: now + Duration::from_secs(1),
+            ticket: 0
+        });
+    }
+
+    #[test]
+    fn message_after_interval_is_sent_immediately() {
+        let throttle = throttle(ChatThrottleConfig::new());
+        let now = Instant::now();
+
+        throttle.reserve(&game_id("a"), ChatRoom::Player, "hi", now);
+        let later = now + Duration::from_secs(2);
+
+        assert_that!(throttle.reserve(&game_id("a"), ChatRoom::Player, "hi", later))
+            .is_equal_to(ChatReservation::Send);
+    }
+
+    #[test]
+    fn message_is_rejected_without_queuing() {
+        let throttle = throttle(ChatThrottleConfig::new().with_max_queue_delay(None));
+        let now = Instant::now();
+
+        throttle.reserve(&game_id("a"), ChatRoom::Player, "hi", now);
+        let reservation = throttle.reserve(&game_id("a"), ChatRoom::Player, "there", now);
+
+        assert_that!(reservation).is_equal_to(ChatReservation::Rejected {
+            retry_after: Duration::from_secs(2)
+        });
+    }
+
+    #[test]
+    fn message_is_rejected_if_queue_delay_is_too_long() {
+        let config = ChatThrottleConfig::new()
+            .with_max_queue_delay(Some(Duration::from_secs(3)))
+            .with_coalesce(false);
+        let throttle = throttle(config);
+        let now = Instant::now();
+
+        throttle.reserve(&game_id("a"), ChatRoom::Player, "1", now);
+        throttle.reserve(&game_id("a"), ChatRoom::Player, "2", now);
+        let reservation = throttle.reserve(&game_id("a"), ChatRoom::Player, "3", now);
+
+        assert_that!(reservation).is_equal_to(ChatReservation::Rejected {
+            retry_after: Duration::from_secs(4)
+        });
+    }
+
+    #[test]
+    fn queued_messages_are_coalesced() {
+        let throttle = throttle(ChatThrottleConfig::new());
+        let game_id = game_id("a");
+        let now = Instant::now();
+
+        throttle.reserve(&game_id, ChatRoom::Player, "hi", now);
+        let queued = throttle.reserve(&game_id, ChatRoom::Player, "good", now);
+        let coalesced = throttle.reserve(&game_id, ChatRoom::Player, "luck", now);
+        let other_room = throttle.reserve(&game_id, ChatRoom::Spectator, "hello", now);
+
+        assert_that!(coalesced).is_equal_to(ChatReservation::Coalesced);
+        assert_that!(other_room).is_equal_to(ChatReservation::Queued {
+            send_at: now + Duration::from_secs(4),
+            ticket: 1
+        });
+        assert_that!(throttle.take_queued(&game_id, ChatRoom::Player, 0, "good".to_owned()))
+            .is_equal_to("good luck".to_owned());
+        assert_that!(queued).is_equal_to(ChatReservation::Queued {
+            send_at: now + Duration::from_secs(2),
+            ticket: 0
+        });
+    }
+
+    #[test]
+    fn messages_exceeding_maximum_length_are_not_coalesced() {
+        let throttle = throttle(ChatThrottleConfig::new());
+        let game_id = game_id("a");
+        let now = Instant::now();
+        let long_text = "x".repeat(MAX_CHAT_MESSAGE_LENGTH - 2);
+
+        throttle.reserve(&game_id, ChatRoom::Player, "hi", now);
+        throttle.reserve(&game_id, ChatRoom::Player, &long_text, now);
+        let reservation = throttle.reserve(&game_id, ChatRoom::Player, "hi", now);
+
+        assert_that!(reservation).is_equal_to(ChatReservation::Queued {
+            send_at: now + Duration::from_secs(4),
+            ticket: 1
+        });
+        assert_that!(throttle.take_queued(&game_id, ChatRoom::Player, 1, "hi".to_owned()))
+            .is_equal_to("hi".to_owned());
+    }
+
+    #[test]
+    fn no_coalescing_after_queued_message_was_taken() {
+        let throttle = throttle(ChatThrottleConfig::new());
+        let game_id = game_id("a");
+        let now = Instant::now();
+
+        throttle.reserve(&game_id, ChatRoom::Player, "hi", now);
+        throttle.reserve(&game_id, ChatRoom::Player, "good", now);
+        throttle.take_queued(&game_id, ChatRoom::Player, 0, "good".to_owned());
+        let reservation = throttle.reserve(&game_id, ChatRoom::Player, "luck", now);
+
+        assert_that!(reservation).is_equal_to(ChatReservation::Queued {
+            send_at: now + Duration::from_secs(4),
+            ticket: 1
+        });
+    }
+
+    #[test]
+    fn finished_game_is_forgotten() {
+        let throttle = throttle(ChatThrottleConfig::new());
+        let game_id = game_id("a");
+        let now = Instant::now();
+
+        throttle.reserve(&game_id, ChatRoom::Player, "hi", now);
+        throttle.game_finished(&game_id);
+        let later = now + Duration::from_secs(1);
+
+        assert_that!(throttle.reserve(&game_id, ChatRoom::Player, "bye", later))
+            .is_equal_to(ChatReservation::Send);
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::chess::position::{Position, STANDARD_FEN};
+use crate::chess::uci::UciMove;
+use crate::model::game::opening::Opening;
+
+/// The bundled classification table, consisting of the ECO code, the name as used by Lichess and
+/// the moves in UCI notation from the standard starting position of every known opening. Openings
+/// are identified by the position after these moves, so other move orders reaching the same
+/// position are classified the same way.
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Anderssen's Opening", "a2a3"),
+    ("A00", "Ware Opening", "a2a4"),
+    ("A01", "Nimzo-Larsen Attack", "b2b3"),
+    ("A00", "Polish Opening", "b2b4"),
+    ("A00", "Saragossa Opening", "c2c3"),
+    ("A10", "English Opening", "c2c4"),
+    ("A20", "English Opening: King's English Variation", "c2c4 e7e5"),
+    ("A30", "English Opening: Symmetrical Variation", "c2c4 c7c5"),
+    ("A00", "Mieses Opening", "d2d3"),
+    ("A40", "Queen's Pawn Game", "d2d4"),
+    ("A40", "Englund Gambit", "d2d4 e7e5"),
+    ("A43", "Benoni Defense: Old Benoni", "d2d4 c7c5"),
+    ("A80", "Dutch Defense", "d2d4 f7f5"),
+    ("A45", "Indian Defense", "d2d4 g8f6"),
+    ("A51", "Indian Defense: Budapest Defense", "d2d4 g8f6 c2c4 e7e5"),
+    ("A56", "Benoni Defense", "d2d4 g8f6 c2c4 c7c5"),
+    ("A57", "Benko Gambit", "d2d4 g8f6 c2c4 c7c5 d4d5 b7b5"),
+    ("E00", "Catalan Opening", "d2d4 g8f6 c2c4 e7e6 g2g3"),
+    ("E12", "Queen's Indian Defense", "d2d4 g8f6 c2c4 e7e6 g1f3 b7b6"),
+    ("E20", "Nimzo-Indian Defense", "d2d4 g8f6 c2c4 e7e6 b1c3 f8b4"),
+    ("E60", "King's Indian Defense", "d2d4 g8f6 c2c4 g7g6"),
+    ("D80", "Grünfeld Defense", "d2d4 g8f6 c2c4 g7g6 b1c3 d7d5"),
+    ("D00", "Queen's Pawn Game", "d2d4 d7d5"),
+    ("D00", "Queen's Pawn Game: Accelerated London System", "d2d4 d7d5 c1f4"),
+    ("D00", "Blackmar-Diemer Gambit", "d2d4 d7d5 e2e4"),
+    ("D02", "Queen's Pawn Game: London System", "d2d4 d7d5 g1f3 g8f6 c1f4"),
+    ("D06", "Queen's Gambit", "d2d4 d7d5 c2c4"),
+    ("D07", "Queen's Gambit Declined: Chigorin Defense", "d2d4 d7d5 c2c4 b8c6"),
+    ("D08", "Queen's Gambit Declined: Albin Countergambit", "d2d4 d7d5 c2c4 e7e5"),
+    ("D10", "Slav Defense", "d2d4 d7d5 c2c4 c7c6"),
+    ("D20", "Queen's Gambit Accepted", "d2d4 d7d5 c2c4 d5c4"),
+    ("D30", "Queen's Gambit Declined", "d2d4 d7d5 c2c4 e7e6"),
+    ("D31", "Queen's Gambit Declined: Queen's Knight Variation", "d2d4 d7d5 c2c4 e7e6 b1c3"),
+    ("D43", "Semi-Slav Defense", "d2d4 d7d5 c2c4 c7c6 g1f3 g8f6 b1c3 e7e6"),
+    ("D85", "Grünfeld Defense: Exchange Variation", "d2d4 g8f6 c2c4 g7g6 b1c3 d7d5 c4d5 f6d5"),
+    ("A00", "Van't Kruijs Opening", "e2e3"),
+    ("B00", "King's Pawn Game", "e2e4"),
+    ("B00", "Owen Defense", "e2e4 b7b6"),
+    ("B00", "Nimzowitsch Defense", "e2e4 b8c6"),
+    ("B01", "Scandinavian Defense", "e2e4 d7d5"),
+    ("B01", "Scandinavian Defense: Mieses-Kotroc Variation", "e2e4 d7d5 e4d5 d8d5"),
+    ("B02", "Alekhine Defense", "e2e4 g8f6"),
+    ("B06", "Modern Defense", "e2e4 g7g6"),
+    ("B07", "Pirc Defense", "e2e4 d7d6 d2d4 g8f6"),
+    ("B10", "Caro-Kann Defense", "e2e4 c7c6"),
+    ("B12", "Caro-Kann Defense: Advance Variation", "e2e4 c7c6 d2d4 d7d5 e4e5"),
+    ("B13", "Caro-Kann Defense: Exchange Variation", "e2e4 c7c6 d2d4 d7d5 e4d5"),
+    ("B20", "Sicilian Defense", "e2e4 c7c5"),
+    ("B21", "Sicilian Defense: Smith-Morra Gambit", "e2e4 c7c5 d2d4"),
+    ("B22", "Sicilian Defense: Alapin Variation", "e2e4 c7c5 c2c3"),
+    ("B23", "Sicilian Defense: Closed", "e2e4 c7c5 b1c3"),
+    ("B30", "Sicilian Defense: Old Sicilian", "e2e4 c7c5 g1f3 b8c6"),
+    ("B40", "Sicilian Defense: French Variation", "e2e4 c7c5 g1f3 e7e6"),
+    ("B70", "Sicilian Defense: Dragon Variation",
+        "e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6 b1c3 g7g6"),
+    ("B90", "Sicilian Defense: Najdorf Variation",
+        "e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6 b1c3 a7a6"),
+    ("C00", "French Defense", "e2e4 e7e6"),
+    ("C01", "French Defense: Exchange Variation", "e2e4 e7e6 d2d4 d7d5 e4d5"),
+    ("C02", "French Defense: Advance Variation", "e2e4 e7e6 d2d4 d7d5 e4e5"),
+    ("C03", "French Defense: Tarrasch Variation", "e2e4 e7e6 d2d4 d7d5 b1d2"),
+    ("C10", "French Defense: Paulsen Variation", "e2e4 e7e6 d2d4 d7d5 b1c3"),
+    ("C11", "French Defense: Classical Variation", "e2e4 e7e6 d2d4 d7d5 b1c3 g8f6"),
+    ("C15", "French Defense: Winawer Variation", "e2e4 e7e6 d2d4 d7d5 b1c3 f8b4"),
+    ("C20", "King's Pawn Game", "e2e4 e7e5"),
+    ("C21", "Center Game", "e2e4 e7e5 d2d4"),
+    ("C23", "Bishop's Opening", "e2e4 e7e5 f1c4"),
+    ("C25", "Vienna Game", "e2e4 e7e5 b1c3"),
+    ("C30", "King's Gambit", "e2e4 e7e5 f2f4"),
+    ("C33", "King's Gambit Accepted", "e2e4 e7e5 f2f4 e5f4"),
+    ("C40", "King's Knight Opening", "e2e4 e7e5 g1f3"),
+    ("C40", "Latvian Gambit", "e2e4 e7e5 g1f3 f7f5"),
+    ("C41", "Philidor Defense", "e2e4 e7e5 g1f3 d7d6"),
+    ("C42", "Russian Game", "e2e4 e7e5 g1f3 g8f6"),
+    ("C44", "King's Knight Opening: Normal Variation", "e2e4 e7e5 g1f3 b8c6"),
+    ("C44", "Ponziani Opening", "e2e4 e7e5 g1f3 b8c6 c2c3"),
+    ("C44", "Scotch Game", "e2e4 e7e5 g1f3 b8c6 d2d4"),
+    ("C46", "Three Knights Opening", "e2e4 e7e5 g1f3 b8c6 b1c3"),
+    ("C47", "Four Knights Game", "e2e4 e7e5 g1f3 b8c6 b1c3 g8f6"),
+    ("C50", "Italian Game", "e2e4 e7e5 g1f3 b8c6 f1c4"),
+    ("C50", "Italian Game: Giuoco Piano", "e2e4 e7e5 g1f3 b8c6 f1c4 f8c5"),
+    ("C51", "Italian Game: Evans Gambit", "e2e4 e7e5 g1f3 b8c6 f1c4 f8c5 b2b4"),
+    ("C55", "Italian Game: Two Knights Defense", "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6"),
+    ("C57", "Italian Game: Two Knights Defense, Fried Liver Attack",
+        "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 f3g5 d7d5 e4d5 f6d5 g5f7"),
+    ("C60", "Ruy Lopez", "e2e4 e7e5 g1f3 b8c6 f1b5"),
+    ("C65", "Ruy Lopez: Berlin Defense", "e2e4 e7e5 g1f3 b8c6 f1b5 g8f6"),
+    ("C70", "Ruy Lopez: Morphy Defense", "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6"),
+    ("C68", "Ruy Lopez: Exchange Variation", "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5c6"),
+    ("A00", "Barnes Opening", "f2f3"),
+    ("A02", "Bird Opening", "f2f4"),
+    ("A02", "Bird Opening: From's Gambit", "f2f4 e7e5"),
+    ("A00", "Hungarian Opening", "g2g3"),
+    ("A00", "Grob Opening", "g2g4"),
+    ("A00", "Clemenz Opening", "h2h3"),
+    ("A00", "Kádas Opening", "h2h4"),
+    ("A00", "Sodium Attack", "b1a3"),
+    ("A00", "Van Geet Opening", "b1c3"),
+    ("A04", "Zukertort Opening", "g1f3"),
+    ("A00", "Amar Opening", "g1h3")
+];
+
+fn replay(moves: &str) -> Option<Position> {
+    let mut position = Position::from_fen(STANDARD_FEN).ok()?;
+
+    for mov in moves.split_whitespace() {
+        position.play(&mov.parse().ok()?).ok()?;
+    }
+
+    Some(position)
+}
+
+/// Maps the [Position::fen_key] of the position after the moves of each opening in [OPENINGS] to
+/// its index. If several openings reach the same position, the first one is used.
+fn openings_by_position() -> &'static HashMap<String, usize> {
+    static OPENINGS_BY_POSITION: OnceLock<HashMap<String, usize>> = OnceLock::new();
+
+    OPENINGS_BY_POSITION.get_or_init(|| {
+        let mut openings_by_position = HashMap::new();
+
+        for (index, &(_, _, moves)) in OPENINGS.iter().enumerate() {
+            if let Some(position) = replay(moves) {
+                openings_by_position.entry(position.fen_key()).or_insert(index);
+            }
+        }
+
+        openings_by_position
+    })
+}
+
+/// Classifies the opening of a game from the standard starting position offline, using the
+/// bundled ECO table, which covers the most common openings and all first moves. The moves are
+/// replayed and the opening of the last position found in the table is returned, so the
+/// classification becomes more precise as more moves are played and recognizes transpositions.
+///
+/// # Arguments
+///
+/// * `moves`: The moves of the game in UCI notation, separated by spaces. Replaying stops at the
+///   first invalid or illegal move.
+///
+/// # Returns
+///
+/// The identified [Opening], whose [Opening::ply] is the number of half-moves after which its
+/// position occurred in the game, or [None] if no position of the game is in the table, e.g.
+/// because no move was played or the game did not start from the standard starting position.
+pub fn classify(moves: &str) -> Option<Opening> {
+    let mut position = Position::from_fen(STANDARD_FEN).ok()?;
+    let mut opening = None;
+
+    for (ply, mov) in moves.split_whitespace().enumerate() {
+        let Ok(mov) = mov.parse::<UciMove>()
+        else {
+            break;
+        };
+
+        if position.play(&mov).is_err() {
+            break;
+        }
+
+        if let Some(&index) = openings_by_position().get(&position.fen_key()) {
+            opening = Some((index, ply + 1));
+        }
+    }
+
+    let (index, ply) = opening?;
+    let (eco, name, _) = OPENINGS[index];
+
+    Some(Opening {
+        eco: eco.to_owned(),
+        name: name.to_owned(),
+        ply: ply as u32
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::first_move("e2e4", "B00", "King's Pawn Game", 1)]
+    #[case::in_table("e2e4 e7e5 g1f3 b8c6 f1c4 f8c5", "C50", "Italian Game: Giuoco Piano", 6)]
+    #[case::beyond_table("e2e4 e7e5 g1f3 b8c6 f1c4 f8c5 c2c3 g8f6", "C50",
+        "Italian Game: Giuoco Piano", 6)]
+    #[case::between_entries("d2d4 g8f6 c2c4 e7e6 b1c3", "A45", "Indian Defense", 2)]
+    #[case::extra_whitespace(" c2c4  c7c5 ", "A30", "English Opening: Symmetrical Variation", 2)]
+    #[case::transposition("d2d4 d7d5 c2c4 e7e6 b1c3 g8f6 g1f3 c7c6", "D43", "Semi-Slav Defense",
+        8)]
+    #[case::transposition_to_first_move("g1f3 g8f6 f3g1 f6g8 e2e4", "B00", "King's Pawn Game", 5)]
+    #[case::illegal_move("e2e4 e7e5 e1e3 b8c6", "C20", "King's Pawn Game", 2)]
+    fn classify_known_opening(#[case] moves: &str, #[case] eco: &str, #[case] name: &str,
+            #[case] ply: u32) {
+        assert_that!(classify(moves)).contains(Opening {
+            eco: eco.to_owned(),
+            name: name.to_owned(),
+            ply
+        });
+    }
+
+    #[rstest]
+    #[case::no_moves("")]
+    #[case::unknown_first_move("e2e5")]
+    fn classify_unknown_opening(#[case] moves: &str) {
+        assert_that!(classify(moves)).is_none();
+    }
+
+    #[test]
+    fn all_openings_are_legal_and_reach_distinct_positions() {
+        let mut positions = HashSet::new();
+
+        for &(_, name, moves) in OPENINGS {
+            let position = replay(moves);
+
+            assert_that!(&position).is_some();
+            assert_that!(positions.insert(position.unwrap().fen_key())).is_true();
+            assert_that!(name).is_not_empty();
+        }
+    }
+}
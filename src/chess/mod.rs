@@ -0,0 +1,239 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::model::game::Color;
+
+pub mod eco;
+pub mod pgn;
+pub mod position;
+pub mod san;
+pub mod uci;
+
+/// An error that occurs when parsing or applying chess notation.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ChessError {
+
+    #[error("invalid square `{0}`")]
+    InvalidSquare(String),
+
+    #[error("invalid UCI move `{0}`")]
+    InvalidUciMove(String),
+
+    #[error("invalid SAN move `{0}`")]
+    InvalidSan(String),
+
+    #[error("ambiguous SAN move `{0}`")]
+    AmbiguousSan(String),
+
+    #[error("invalid FEN `{fen}`: {reason}")]
+    InvalidFen {
+        fen: String,
+        reason: String
+    },
+
+    #[error("illegal move `{0}`")]
+    IllegalMove(String)
+}
+
+pub type ChessResult<T> = Result<T, ChessError>;
+
+/// A square on the chess board, identified by its file (0 = a, ..., 7 = h) and rank (0 = 1st rank,
+/// ..., 7 = 8th rank).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Square(u8);
+
+impl Square {
+
+    /// Creates a new square from the given file and rank, both in the range `0..8`.
+    ///
+    /// # Returns
+    ///
+    /// The square, or [None] if the file or rank is out of range.
+    pub fn new(file: u8, rank: u8) -> Option<Square> {
+        if file < 8 && rank < 8 {
+            Some(Square(rank * 8 + file))
+        }
+        else {
+            None
+        }
+    }
+
+    pub(crate) fn from_index(index: usize) -> Square {
+        Square(index as u8)
+    }
+
+    /// The file of this square, where 0 represents the a-file and 7 the h-file.
+    pub fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    /// The rank of this square, where 0 represents the 1st rank and 7 the 8th rank.
+    pub fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub(crate) fn offset(self, file_delta: i8, rank_delta: i8) -> Option<Square> {
+        let file = self.file() as i8 + file_delta;
+        let rank = self.rank() as i8 + rank_delta;
+
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Square::new(file as u8, rank as u8)
+        }
+        else {
+            None
+        }
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, (b'1' + self.rank()) as char)
+    }
+}
+
+impl FromStr for Square {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> ChessResult<Square> {
+        let bytes = s.as_bytes();
+
+        if bytes.len() != 2 {
+            return Err(ChessError::InvalidSquare(s.to_owned()));
+        }
+
+        let file = bytes[0].wrapping_sub(b'a');
+        let rank = bytes[1].wrapping_sub(b'1');
+
+        Square::new(file, rank).ok_or_else(|| ChessError::InvalidSquare(s.to_owned()))
+    }
+}
+
+/// The kind of a chess piece, independent of its color.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King
+}
+
+impl PieceKind {
+
+    /// Parses a piece kind from its letter, ignoring case, i.e. `p`, `n`, `b`, `r`, `q`, or `k`.
+    pub fn from_char(c: char) -> Option<PieceKind> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(PieceKind::Pawn),
+            'n' => Some(PieceKind::Knight),
+            'b' => Some(PieceKind::Bishop),
+            'r' => Some(PieceKind::Rook),
+            'q' => Some(PieceKind::Queen),
+            'k' => Some(PieceKind::King),
+            _ => None
+        }
+    }
+
+    /// The conventional value of this piece kind in centipawns, as used by
+    /// [Position::material](crate::chess::position::Position::material). Kings have no value, as
+    /// they can never be traded.
+    pub fn value(self) -> i32 {
+        match self {
+            PieceKind::Pawn => 100,
+            PieceKind::Knight => 320,
+            PieceKind::Bishop => 330,
+            PieceKind::Rook => 500,
+            PieceKind::Queen => 900,
+            PieceKind::King => 0
+        }
+    }
+
+    /// The lowercase letter representing this piece kind.
+    pub fn to_char(self) -> char {
+        match self {
+            PieceKind::Pawn => 'p',
+            PieceKind::Knight => 'n',
+            PieceKind::Bishop => 'b',
+            PieceKind::Rook => 'r',
+            PieceKind::Queen => 'q',
+            PieceKind::King => 'k'
+        }
+    }
+}
+
+/// A chess piece of a certain [PieceKind] and [Color].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Piece {
+    pub color: Color,
+    pub kind: PieceKind
+}
+
+impl Piece {
+
+    /// Parses a piece from its FEN letter, where uppercase letters represent white pieces and
+    /// lowercase letters represent black pieces.
+    pub fn from_fen_char(c: char) -> Option<Piece> {
+        let kind = PieceKind::from_char(c)?;
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+
+        Some(Piece {
+            color,
+            kind
+        })
+    }
+
+    /// The FEN letter of this piece, which is uppercase for white pieces and lowercase for black
+    /// pieces.
+    pub fn to_fen_char(self) -> char {
+        match self.color {
+            Color::White => self.kind.to_char().to_ascii_uppercase(),
+            Color::Black => self.kind.to_char()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::a1("a1", 0, 0)]
+    #[case::e4("e4", 4, 3)]
+    #[case::h8("h8", 7, 7)]
+    fn square_round_trip(#[case] name: &str, #[case] file: u8, #[case] rank: u8) {
+        let square = name.parse::<Square>().unwrap();
+
+        assert_that!(square.file()).is_equal_to(file);
+        assert_that!(square.rank()).is_equal_to(rank);
+        assert_that!(square.to_string()).is_equal_to(name.to_owned());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::too_long("e44")]
+    #[case::invalid_file("i1")]
+    #[case::invalid_rank("a9")]
+    fn parse_invalid_square(#[case] name: &str) {
+        assert_that!(name.parse::<Square>()).is_err();
+    }
+
+    #[test]
+    fn piece_fen_char_round_trip() {
+        for c in "PNBRQKpnbrqk".chars() {
+            let piece = Piece::from_fen_char(c).unwrap();
+
+            assert_that!(piece.to_fen_char()).is_equal_to(c);
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt::{self, Display, Formatter};
+
+use thiserror::Error;
+
+/// An error that occurs when reading PGN.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum PgnError {
+
+    #[error("unterminated comment")]
+    UnterminatedComment,
+
+    #[error("unbalanced variation parentheses")]
+    UnbalancedVariation,
+
+    #[error("variation without preceding move")]
+    VariationWithoutMove,
+
+    #[error("invalid tag pair `{0}`")]
+    InvalidTag(String)
+}
+
+pub type PgnResult<T> = Result<T, PgnError>;
+
+/// A move in the movetext of a PGN game, together with the variations that branch off as
+/// alternatives to this move.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PgnNode {
+
+    /// The move in standard algebraic notation, as written in the PGN.
+    pub san: String,
+
+    /// Alternative lines which start with an alternative to this move.
+    pub variations: Vec<Vec<PgnNode>>
+}
+
+/// A game read from PGN, consisting of its tag pairs and the main line of moves including
+/// variations.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PgnGame {
+
+    /// The tag pairs (e.g. `[Event "..."]`) in the order in which they appear.
+    pub tags: Vec<(String, String)>,
+
+    /// The main line of the game.
+    pub moves: Vec<PgnNode>
+}
+
+impl PgnGame {
+
+    /// Gets the value of the tag with the given name, if present.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn start_ply(&self) -> usize {
+        let Some(fen) = self.tag("FEN")
+        else {
+            return 0;
+        };
+        let mut fields = fen.split_whitespace().skip(1);
+        let black_to_move = fields.next() == Some("b");
+        let fullmove_number = fields.nth(3)
+            .and_then(|number| number.parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1);
+
+        (fullmove_number - 1) * 2 + black_to_move as usize
+    }
+}
+
+fn write_line(tokens: &mut Vec<String>, line: &[PgnNode], start_ply: usize) {
+    for (index, node) in line.iter().enumerate() {
+        let ply = start_ply + index;
+
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", ply / 2 + 1));
+        }
+        else if index == 0 || !line[index - 1].variations.is_empty() {
+            tokens.push(format!("{}...", ply / 2 + 1));
+        }
+
+        tokens.push(node.san.clone());
+
+        for variation in &node.variations {
+            let mut variation_tokens = Vec::new();
+            write_line(&mut variation_tokens, variation, ply);
+
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+            }
+
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+            }
+
+            tokens.extend(variation_tokens);
+        }
+    }
+}
+
+/// Formats the game as PGN, with the tag pairs followed by the movetext wrapped at 80 columns and
+/// terminated by the value of the `Result` tag (or `*` if absent).
+impl Display for PgnGame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const MAX_LINE_LENGTH: usize = 80;
+
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{name} \"{value}\"]")?;
+        }
+
+        if !self.tags.is_empty() {
+            writeln!(f)?;
+        }
+
+        let mut tokens = Vec::new();
+        write_line(&mut tokens, &self.moves, self.start_ply());
+        tokens.push(self.tag("Result").unwrap_or("*").to_owned());
+
+        let mut line_length = 0;
+
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            else if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+
+            write!(f, "{token}")?;
+            line_length += token.len();
+        }
+
+        writeln!(f)
+    }
+}
+
+fn parse_tag(line: &str) -> PgnResult<(String, String)> {
+    let invalid = || PgnError::InvalidTag(line.to_owned());
+    let inner = line.trim()
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, value) = inner.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let value = value.trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    Ok((name.to_owned(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+fn is_move_number(token: &str) -> bool {
+    let digits = token.trim_end_matches('.');
+
+    digits.len() < token.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn strip_move_number(token: &str) -> &str {
+    let without_digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+
+    if without_digits.len() < token.len() && without_digits.starts_with('.') {
+        without_digits.trim_start_matches('.')
+    }
+    else {
+        token
+    }
+}
+
+fn tokenize(movetext: &str) -> PgnResult<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = movetext.chars();
+
+    fn flush(current: &mut String, tokens: &mut Vec<String>) {
+        if !current.is_empty() {
+            tokens.push(std::mem::take(current));
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                flush(&mut current, &mut tokens);
+
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err(PgnError::UnterminatedComment);
+                }
+            },
+            ';' => {
+                flush(&mut current, &mut tokens);
+                chars.by_ref().find(|&c| c == '\n');
+            },
+            '(' | ')' => {
+                flush(&mut current, &mut tokens);
+                tokens.push(c.to_string());
+            },
+            c if c.is_whitespace() => flush(&mut current, &mut tokens),
+            c => current.push(c)
+        }
+    }
+
+    flush(&mut current, &mut tokens);
+    Ok(tokens)
+}
+
+fn parse_movetext(movetext: &str) -> PgnResult<Vec<Vec<PgnNode>>> {
+    let mut lines = Vec::new();
+    let mut stack: Vec<Vec<PgnNode>> = vec![Vec::new()];
+
+    for token in tokenize(movetext)? {
+        match token.as_str() {
+            "(" => stack.push(Vec::new()),
+            ")" => {
+                let variation = stack.pop().ok_or(PgnError::UnbalancedVariation)?;
+                let line = stack.last_mut().ok_or(PgnError::UnbalancedVariation)?;
+                let node = line.last_mut().ok_or(PgnError::VariationWithoutMove)?;
+                node.variations.push(variation);
+            },
+            token if is_result(token) && stack.len() == 1 =>
+                lines.push(std::mem::take(&mut stack[0])),
+            token if token.starts_with('$') || is_result(token) || is_move_number(token) => { },
+            token => {
+                let san = strip_move_number(token);
+                let line = stack.last_mut().unwrap();
+
+                line.push(PgnNode {
+                    san: san.to_owned(),
+                    variations: Vec::new()
+                });
+            }
+        }
+
+        if stack.is_empty() {
+            return Err(PgnError::UnbalancedVariation);
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+
+    let line = stack.pop().unwrap();
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+/// Reads all games from the given PGN text, such as a database export or a Lichess study export.
+/// Comments, numeric annotation glyphs, and move numbers are skipped, while variations are
+/// retained. A game ends at its tag section or at a result token outside of variations.
+///
+/// # Errors
+///
+/// Any [PgnError] if the PGN is malformed.
+pub fn read_pgn(pgn: &str) -> PgnResult<Vec<PgnGame>> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+
+    let mut finish_game = |tags: &mut Vec<(String, String)>, movetext: &mut String|
+            -> PgnResult<()> {
+        if !tags.is_empty() || !movetext.trim().is_empty() {
+            for moves in parse_movetext(movetext)? {
+                games.push(PgnGame {
+                    tags: std::mem::take(tags),
+                    moves
+                });
+            }
+
+            movetext.clear();
+        }
+
+        Ok(())
+    };
+
+    for line in pgn.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('%') {
+            continue;
+        }
+
+        if trimmed.starts_with('[') && !movetext.trim().is_empty() && !is_in_comment(&movetext) {
+            finish_game(&mut tags, &mut movetext)?;
+        }
+
+        if trimmed.starts_with('[') && movetext.trim().is_empty() {
+            tags.push(parse_tag(trimmed)?);
+        }
+        else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    finish_game(&mut tags, &mut movetext)?;
+
+    Ok(games)
+}
+
+fn is_in_comment(movetext: &str) -> bool {
+    movetext.rfind('{').is_some_and(|open| movetext[open..].find('}').is_none())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn node(san: &str) -> PgnNode {
+        PgnNode {
+            san: san.to_owned(),
+            variations: Vec::new()
+        }
+    }
+
+    fn sans(nodes: &[PgnNode]) -> Vec<&str> {
+        nodes.iter().map(|node| node.san.as_str()).collect()
+    }
+
+    #[test]
+    fn read_single_game_with_tags() {
+        let pgn = "[Event \"Test\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 1-0\n";
+
+        let games = read_pgn(pgn).unwrap();
+
+        assert_that!(games.len()).is_equal_to(1);
+        assert_that!(games[0].tag("Event")).contains("Test");
+        assert_that!(games[0].tag("Result")).contains("1-0");
+        assert_that!(sans(&games[0].moves)).contains_exactly_in_given_order(["e4", "e5", "Nf3"]);
+    }
+
+    #[test]
+    fn read_multiple_games() {
+        let pgn = "[Event \"A\"]\n\n1. e4 *\n\n[Event \"B\"]\n\n1. d4 d5 *\n";
+
+        let games = read_pgn(pgn).unwrap();
+
+        assert_that!(games.len()).is_equal_to(2);
+        assert_that!(sans(&games[0].moves)).contains_exactly_in_given_order(["e4"]);
+        assert_that!(sans(&games[1].moves)).contains_exactly_in_given_order(["d4", "d5"]);
+    }
+
+    #[test]
+    fn games_without_tags_are_separated_by_result() {
+        let pgn = "1. e4 e5 1-0\n1. d4 0-1";
+
+        let games = read_pgn(pgn).unwrap();
+
+        assert_that!(games.len()).is_equal_to(2);
+        assert_that!(sans(&games[1].moves)).contains_exactly_in_given_order(["d4"]);
+    }
+
+    #[test]
+    fn comments_nags_and_move_numbers_are_skipped() {
+        let pgn = "1.e4 {best by test} e5 $1 ; line comment\n2... Nf3 3.Bc4 *";
+
+        let games = read_pgn(pgn).unwrap();
+
+        assert_that!(sans(&games[0].moves))
+            .contains_exactly_in_given_order(["e4", "e5", "Nf3", "Bc4"]);
+    }
+
+    #[test]
+    fn nested_variations_are_attached_to_alternative_move() {
+        let pgn = "1. e4 e5 (1... c5 2. Nf3 (2. c3)) 2. Nf3 *";
+
+        let games = read_pgn(pgn).unwrap();
+        let moves = &games[0].moves;
+
+        assert_that!(sans(moves)).contains_exactly_in_given_order(["e4", "e5", "Nf3"]);
+        assert_that!(moves[1].variations.clone()).contains_exactly_in_given_order([vec![
+            node("c5"),
+            PgnNode {
+                san: "Nf3".to_owned(),
+                variations: vec![vec![node("c3")]]
+            }
+        ]]);
+    }
+
+    #[test]
+    fn write_game_with_tags_and_variations() {
+        let game = PgnGame {
+            tags: vec![
+                ("Event".to_owned(), "Quoted \"Test\"".to_owned()),
+                ("Result".to_owned(), "1-0".to_owned())
+            ],
+            moves: vec![
+                node("e4"),
+                PgnNode {
+                    san: "e5".to_owned(),
+                    variations: vec![vec![node("c5"), node("Nf3")]]
+                },
+                node("Nf3")
+            ]
+        };
+
+        let pgn = game.to_string();
+
+        assert_that!(pgn.as_str()).is_equal_to(
+            "[Event \"Quoted \\\"Test\\\"\"]\n[Result \"1-0\"]\n\n\
+                1. e4 e5 (1... c5 2. Nf3) 2. Nf3 1-0\n");
+    }
+
+    #[test]
+    fn write_game_starting_with_black_from_fen() {
+        let game = PgnGame {
+            tags: vec![("FEN".to_owned(), "4k3/8/8/8/8/8/8/4K3 b - - 0 12".to_owned())],
+            moves: vec![node("Kd7"), node("Kd2")]
+        };
+
+        let pgn = game.to_string();
+
+        assert_that!(pgn.ends_with("\n\n12... Kd7 13. Kd2 *\n")).is_true();
+    }
+
+    #[test]
+    fn write_game_wraps_long_movetext() {
+        let game = PgnGame {
+            tags: Vec::new(),
+            moves: (0..40).map(|_| node("Nf3")).collect()
+        };
+
+        let pgn = game.to_string();
+
+        assert_that!(pgn.lines().count()).is_greater_than(1);
+        assert_that!(pgn.lines().all(|line| line.len() <= 80)).is_true();
+    }
+
+    #[test]
+    fn written_game_can_be_read_again() {
+        let pgn = "[Event \"Test\"]\n\n1. d4 d5 (1... Nf6 2. c4 (2. Nf3) 2... e6) 2. c4 *";
+        let game = read_pgn(pgn).unwrap().remove(0);
+
+        let read_again = read_pgn(&game.to_string()).unwrap().remove(0);
+
+        assert_that!(read_again).is_equal_to(game);
+    }
+
+    #[rstest]
+    #[case::unterminated_comment("1. e4 { comment")]
+    #[case::unclosed_variation("1. e4 (1. d4")]
+    #[case::unopened_variation("1. e4 )")]
+    #[case::variation_without_move("(1. e4) 1. d4")]
+    #[case::invalid_tag("[Event Test]\n\n1. e4")]
+    fn read_invalid_pgn(#[case] pgn: &str) {
+        assert_that!(read_pgn(pgn)).is_err();
+    }
+}
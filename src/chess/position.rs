@@ -0,0 +1,2050 @@
+use crate::chess::{ChessError, ChessResult, Piece, PieceKind, Square};
+use crate::chess::uci::UciMove;
+use crate::model::game::{Color, GameInfo, Variant};
+
+/// The FEN of the standard chess starting position.
+pub const STANDARD_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The FEN of the starting position of Horde.
+pub const HORDE_FEN: &str =
+    "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+
+/// The FEN of the starting position of Racing Kings.
+pub const RACING_KINGS_FEN: &str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] =
+    [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_OFFSETS: [(i8, i8); 8] =
+    [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+const PROMOTION_KINDS: [PieceKind; 4] =
+    [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+const POCKET_KINDS: [PieceKind; 5] =
+    [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight, PieceKind::Pawn];
+
+/// The number of checks with which a player wins a game of Three-check.
+const THREE_CHECK_CHECKS: u8 = 3;
+
+/// The value of a single move of mobility in centipawns in [Position::static_evaluation].
+const MOBILITY_WEIGHT: i32 = 5;
+
+/// The side of the board towards which a king castles.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CastlingSide {
+
+    /// Castling towards the h-file, ending with the king on g and the rook on f.
+    KingSide,
+
+    /// Castling towards the a-file, ending with the king on c and the rook on d.
+    QueenSide
+}
+
+impl CastlingSide {
+
+    fn index(self) -> usize {
+        match self {
+            CastlingSide::KingSide => 0,
+            CastlingSide::QueenSide => 1
+        }
+    }
+
+    fn king_destination_file(self) -> u8 {
+        match self {
+            CastlingSide::KingSide => 6,
+            CastlingSide::QueenSide => 2
+        }
+    }
+
+    fn rook_destination_file(self) -> u8 {
+        match self {
+            CastlingSide::KingSide => 5,
+            CastlingSide::QueenSide => 3
+        }
+    }
+}
+
+/// The captured pieces a player holds in Crazyhouse, which they can drop onto the board instead
+/// of making a regular move.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Pocket {
+    counts: [u8; 5]
+}
+
+impl Pocket {
+
+    fn index(kind: PieceKind) -> Option<usize> {
+        match kind {
+            PieceKind::Pawn => Some(0),
+            PieceKind::Knight => Some(1),
+            PieceKind::Bishop => Some(2),
+            PieceKind::Rook => Some(3),
+            PieceKind::Queen => Some(4),
+            PieceKind::King => None
+        }
+    }
+
+    /// The number of pieces of the given kind in this pocket, which is always 0 for kings.
+    pub fn count(&self, kind: PieceKind) -> u8 {
+        Pocket::index(kind).map(|index| self.counts[index]).unwrap_or(0)
+    }
+
+    /// Indicates whether this pocket holds no pieces.
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|&count| count == 0)
+    }
+
+    pub(crate) fn add(&mut self, kind: PieceKind) {
+        if let Some(index) = Pocket::index(kind) {
+            self.counts[index] += 1;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, kind: PieceKind) {
+        if let Some(index) = Pocket::index(kind) {
+            self.counts[index] = self.counts[index].saturating_sub(1);
+        }
+    }
+}
+
+/// The [Pocket]s of both players in Crazyhouse.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Pockets {
+    pub white: Pocket,
+    pub black: Pocket
+}
+
+impl Pockets {
+
+    /// The pocket of the player with the given color.
+    pub fn of(&self, color: Color) -> &Pocket {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black
+        }
+    }
+}
+
+fn is_light_square(square: Square) -> bool {
+    (square.file() + square.rank()) % 2 == 1
+}
+
+/// Checks whether the given pieces, not including kings, are at most a single minor piece or only
+/// bishops on squares of the same color.
+fn is_minor_material(pieces: &[(Square, Piece)]) -> bool {
+    match pieces {
+        [] => true,
+        [(_, piece)] => matches!(piece.kind, PieceKind::Knight | PieceKind::Bishop),
+        [(first_square, _), ..] => pieces.iter().all(|(square, piece)|
+            piece.kind == PieceKind::Bishop
+                && is_light_square(*square) == is_light_square(*first_square))
+    }
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1
+    }
+}
+
+fn back_rank(color: Color) -> u8 {
+    match color {
+        Color::White => 0,
+        Color::Black => 7
+    }
+}
+
+fn pawn_direction(color: Color) -> i8 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1
+    }
+}
+
+fn is_center(square: Square) -> bool {
+    (3..=4).contains(&square.file()) && (3..=4).contains(&square.rank())
+}
+
+fn center_distance(coordinate: u8) -> u8 {
+    3u8.saturating_sub(coordinate).max(coordinate.saturating_sub(4))
+}
+
+fn parse_checks(field: &str) -> Option<[u8; 2]> {
+    let (given, field) = match field.strip_prefix('+') {
+        Some(field) => (true, field),
+        None => (false, field)
+    };
+    let (white, black) = field.split_once('+')?;
+    let white = white.parse::<u8>().ok().filter(|&checks| checks <= THREE_CHECK_CHECKS)?;
+    let black = black.parse::<u8>().ok().filter(|&checks| checks <= THREE_CHECK_CHECKS)?;
+
+    if given {
+        Some([THREE_CHECK_CHECKS - white, THREE_CHECK_CHECKS - black])
+    }
+    else {
+        Some([white, black])
+    }
+}
+
+/// A flag of a FEN which is impossible in the position described by the FEN, and is therefore
+/// removed by [Position::from_fen_normalized].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FenIssue {
+
+    /// The castling right denoted by the given character, because the king is not on its back rank
+    /// or there is no rook on the corresponding side of it.
+    ImpossibleCastlingRight(char),
+
+    /// The given en-passant square, because no pawn of the player who did not move can have just
+    /// advanced two squares past it.
+    ImpossibleEnPassantSquare(Square)
+}
+
+/// A warning about the initial FEN of a [Variant::FromPosition] game, as detected by
+/// [InvalidPosition::detect].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvalidPosition {
+
+    /// The initial FEN cannot be parsed. Moves of the game are neither tracked nor validated.
+    Unparsable(ChessError),
+
+    /// The initial FEN contains the given impossible flags, which were removed. Moves of the game
+    /// are tracked from the normalized position with the given FEN.
+    Normalized {
+        fen: String,
+        issues: Vec<FenIssue>
+    }
+}
+
+impl InvalidPosition {
+
+    /// Checks the initial FEN of the given game. Only games of the variant
+    /// [Variant::FromPosition] are checked.
+    ///
+    /// # Returns
+    ///
+    /// A warning if the initial FEN of a [Variant::FromPosition] game cannot be parsed or contains
+    /// impossible flags, otherwise [None].
+    pub fn detect(info: &GameInfo) -> Option<InvalidPosition> {
+        if info.variant != Some(Variant::FromPosition) || info.initial_fen == "startpos" {
+            return None;
+        }
+
+        match Position::from_fen_normalized(&info.initial_fen, Variant::FromPosition) {
+            Ok((_, issues)) if issues.is_empty() => None,
+            Ok((position, issues)) => Some(InvalidPosition::Normalized {
+                fen: position.to_fen(),
+                issues
+            }),
+            Err(error) => Some(InvalidPosition::Unparsable(error))
+        }
+    }
+}
+
+/// A chess position, consisting of the placement of all pieces, the side to move, castling rights,
+/// en-passant square, and the move counters. Positions follow the rules of a [Variant], which also
+/// determines the additional state they track, such as the pockets in Crazyhouse or the remaining
+/// checks in Three-check.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Position {
+    board: [Option<Piece>; 64],
+    variant: Variant,
+    side_to_move: Color,
+    castling_rooks: [[Option<u8>; 2]; 2],
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    pockets: [Pocket; 2],
+    promoted: u64,
+    remaining_checks: [u8; 2]
+}
+
+impl Position {
+
+    /// Creates the standard chess starting position.
+    pub fn standard() -> Position {
+        Position::from_fen(STANDARD_FEN).unwrap()
+    }
+
+    /// Creates the starting position of the given variant.
+    ///
+    /// # Returns
+    ///
+    /// The starting position, or [None] for [Variant::Chess960] and [Variant::FromPosition], whose
+    /// starting position is not fixed and must be parsed from a FEN instead.
+    pub fn initial(variant: Variant) -> Option<Position> {
+        let fen = variant.initial_fen()?;
+
+        Some(Position::from_fen_with_variant(fen, variant).unwrap())
+    }
+
+    /// Creates the initial position of a game, following the rules of its
+    /// [variant](GameInfo::variant). Games without a variant are considered standard chess.
+    /// Impossible flags of the [initial FEN](GameInfo::initial_fen) are removed, as in
+    /// [Position::from_fen_normalized].
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::InvalidFen] if the [initial FEN](GameInfo::initial_fen) of the game is
+    /// neither `startpos` nor a valid FEN.
+    pub fn from_game_info(info: &GameInfo) -> ChessResult<Position> {
+        let variant = info.variant.unwrap_or(Variant::Standard);
+
+        if info.initial_fen != "startpos" {
+            return Position::from_fen_normalized(&info.initial_fen, variant)
+                .map(|(position, _)| position);
+        }
+
+        Position::from_fen_with_variant(variant.initial_fen().unwrap_or(STANDARD_FEN), variant)
+    }
+
+    /// Parses a standard chess position from its FEN. The castling field may use either the
+    /// standard `KQkq` letters or Shredder-FEN rook files (`HAha`). The move counters may be
+    /// omitted.
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::InvalidFen] if the given string is not a valid FEN.
+    pub fn from_fen(fen: &str) -> ChessResult<Position> {
+        Position::from_fen_with_variant(fen, Variant::Standard)
+    }
+
+    /// Parses a position of the given variant from its FEN, as in [Position::from_fen]. For
+    /// Crazyhouse, the pockets may be given in brackets after the placement (`...RNBQKBNR[Qn]`) or
+    /// as a ninth rank, and promoted pieces may be marked with a `~`. For Three-check, the
+    /// remaining checks of both players may be given after the en-passant square (`3+3`) or the
+    /// checks given so far at the end (`+0+0`). Castling rights are ignored in variants without
+    /// castling.
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::InvalidFen] if the given string is not a valid FEN, including the case that it
+    /// contains castling rights or an en-passant square which are impossible in the position.
+    pub fn from_fen_with_variant(fen: &str, variant: Variant) -> ChessResult<Position> {
+        Position::parse_fen(fen, variant, None)
+    }
+
+    /// Parses a position of the given variant from its FEN, as in
+    /// [Position::from_fen_with_variant], except that castling rights and en-passant squares which
+    /// are impossible in the position are removed instead of rejected.
+    ///
+    /// # Returns
+    ///
+    /// The normalized position and the issues of the FEN which were removed, in the order in which
+    /// they occur in the FEN.
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::InvalidFen] if the given string is not a valid FEN even after removing
+    /// impossible flags.
+    pub fn from_fen_normalized(fen: &str, variant: Variant)
+            -> ChessResult<(Position, Vec<FenIssue>)> {
+        let mut issues = Vec::new();
+        let position = Position::parse_fen(fen, variant, Some(&mut issues))?;
+
+        Ok((position, issues))
+    }
+
+    fn parse_fen(fen: &str, variant: Variant, mut issues: Option<&mut Vec<FenIssue>>)
+            -> ChessResult<Position> {
+        let invalid = |reason: &str| ChessError::InvalidFen {
+            fen: fen.to_owned(),
+            reason: reason.to_owned()
+        };
+        let mut fields = fen.split_whitespace().collect::<Vec<_>>();
+        let mut remaining_checks = [THREE_CHECK_CHECKS; 2];
+
+        if variant == Variant::ThreeCheck {
+            let check_field = fields.iter()
+                .enumerate()
+                .skip(4)
+                .find(|(_, field)| field.contains('+'))
+                .map(|(index, _)| index);
+
+            if let Some(index) = check_field {
+                remaining_checks = parse_checks(fields.remove(index))
+                    .ok_or_else(|| invalid("invalid check counts"))?;
+            }
+        }
+
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(invalid("expected 4 to 6 fields"));
+        }
+
+        let mut placement = fields[0];
+        let mut pocket = None;
+
+        if variant == Variant::Crazyhouse {
+            if let Some((board, rest)) = placement.split_once('[') {
+                placement = board;
+                pocket = Some(rest.strip_suffix(']').ok_or_else(|| invalid("unclosed pocket"))?);
+            }
+        }
+
+        let mut board = [None; 64];
+        let mut promoted = 0u64;
+        let mut ranks = placement.split('/').collect::<Vec<_>>();
+
+        if variant == Variant::Crazyhouse && pocket.is_none() && ranks.len() == 9 {
+            pocket = ranks.pop();
+        }
+
+        if ranks.len() != 8 {
+            return Err(invalid("expected 8 ranks"));
+        }
+
+        for (rank_index, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - rank_index as u8;
+            let mut file = 0u8;
+
+            for c in rank_str.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    file += empty as u8;
+                }
+                else if c == '~' && variant == Variant::Crazyhouse && file > 0 {
+                    promoted |= 1 << Square::new(file - 1, rank).unwrap().index();
+                }
+                else {
+                    let piece = Piece::from_fen_char(c).ok_or_else(|| invalid("invalid piece"))?;
+                    let square = Square::new(file, rank).ok_or_else(|| invalid("rank too long"))?;
+                    board[square.index()] = Some(piece);
+                    file += 1;
+                }
+
+                if file > 8 {
+                    return Err(invalid("rank too long"));
+                }
+            }
+
+            if file != 8 {
+                return Err(invalid("rank too short"));
+            }
+        }
+
+        let mut pockets = [Pocket::default(); 2];
+
+        for c in pocket.unwrap_or("").chars() {
+            let piece = Piece::from_fen_char(c)
+                .filter(|piece| piece.kind != PieceKind::King)
+                .ok_or_else(|| invalid("invalid pocket piece"))?;
+            pockets[color_index(piece.color)].add(piece.kind);
+        }
+
+        let side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(invalid("invalid side to move"))
+        };
+
+        let mut position = Position {
+            board,
+            variant,
+            side_to_move,
+            castling_rooks: [[None; 2]; 2],
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            pockets,
+            promoted,
+            remaining_checks
+        };
+
+        if fields[2] != "-" && !matches!(variant, Variant::Antichess | Variant::RacingKings) {
+            for c in fields[2].chars() {
+                let is_castling_char = matches!(c.to_ascii_lowercase(), 'k' | 'q' | 'a'..='h');
+
+                match (position.add_castling_right(c), issues.as_deref_mut()) {
+                    (Some(()), _) => { },
+                    (None, Some(issues)) if is_castling_char =>
+                        issues.push(FenIssue::ImpossibleCastlingRight(c)),
+                    (None, _) => return Err(invalid("invalid castling rights"))
+                }
+            }
+        }
+
+        if fields[3] != "-" {
+            let square = fields[3].parse().map_err(|_| invalid("invalid en-passant square"))?;
+
+            if position.is_possible_en_passant_square(square) {
+                position.en_passant = Some(square);
+            }
+            else if let Some(issues) = issues {
+                issues.push(FenIssue::ImpossibleEnPassantSquare(square));
+            }
+            else {
+                return Err(invalid("impossible en-passant square"));
+            }
+        }
+
+        if let Some(halfmove_clock) = fields.get(4) {
+            position.halfmove_clock =
+                halfmove_clock.parse().map_err(|_| invalid("invalid halfmove clock"))?;
+        }
+
+        if let Some(fullmove_number) = fields.get(5) {
+            position.fullmove_number =
+                fullmove_number.parse().map_err(|_| invalid("invalid fullmove number"))?;
+        }
+
+        Ok(position)
+    }
+
+    /// Checks whether a pawn of the player who did not move can have just advanced two squares
+    /// past the given square, i.e. whether the square and the square the pawn came from are empty
+    /// and the pawn stands in front of the square.
+    fn is_possible_en_passant_square(&self, square: Square) -> bool {
+        let color = self.side_to_move.opposite();
+        let direction = pawn_direction(color);
+        let Some(origin) = square.offset(0, -direction)
+        else {
+            return false;
+        };
+        let from_start_rank = origin.rank() as i8 == back_rank(color) as i8 + direction ||
+            (self.variant == Variant::Horde && origin.rank() == back_rank(color));
+        let pawn = square.offset(0, direction).and_then(|pawn| self.piece_at(pawn));
+
+        from_start_rank && self.piece_at(square).is_none() && self.piece_at(origin).is_none() &&
+            pawn == Some(Piece { color, kind: PieceKind::Pawn })
+    }
+
+    fn add_castling_right(&mut self, c: char) -> Option<()> {
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let rank = back_rank(color);
+        let king_file = self.king_square(color).filter(|king| king.rank() == rank)?.file();
+        let is_own_rook = |file: u8| self.piece_at(Square::new(file, rank).unwrap()) ==
+            Some(Piece { color, kind: PieceKind::Rook });
+        let (side, rook_file) = match c.to_ascii_lowercase() {
+            'k' => (CastlingSide::KingSide,
+                (king_file + 1..8).rev().find(|&file| is_own_rook(file))?),
+            'q' => (CastlingSide::QueenSide, (0..king_file).find(|&file| is_own_rook(file))?),
+            file @ 'a'..='h' => {
+                let file = file as u8 - b'a';
+                let side = if file > king_file {
+                    CastlingSide::KingSide
+                }
+                else {
+                    CastlingSide::QueenSide
+                };
+
+                if !is_own_rook(file) {
+                    return None;
+                }
+
+                (side, file)
+            },
+            _ => return None
+        };
+
+        self.castling_rooks[color_index(color)][side.index()] = Some(rook_file);
+        Some(())
+    }
+
+    /// Formats this position as a FEN, including the pockets in Crazyhouse and the remaining
+    /// checks in Three-check.
+    pub fn to_fen(&self) -> String {
+        if self.variant == Variant::ThreeCheck {
+            format!("{} {}+{} {} {}", self.fen_key(), self.remaining_checks[0],
+                self.remaining_checks[1], self.halfmove_clock, self.fullmove_number)
+        }
+        else {
+            format!("{} {} {}", self.fen_key(), self.halfmove_clock, self.fullmove_number)
+        }
+    }
+
+    /// The first four fields of the FEN of this position (placement, side to move, castling
+    /// rights, and en-passant square), which identify the position independent of move counters.
+    pub fn fen_key(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                let square = Square::new(file, rank).unwrap();
+
+                match self.piece_at(square) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+
+                        fen.push(piece.to_fen_char());
+
+                        if self.is_promoted(square) {
+                            fen.push('~');
+                        }
+                    },
+                    None => empty += 1
+                }
+            }
+
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        if self.variant == Variant::Crazyhouse {
+            fen.push('[');
+
+            for color in [Color::White, Color::Black] {
+                for kind in POCKET_KINDS {
+                    let piece = Piece { color, kind };
+
+                    for _ in 0..self.pocket(color).count(kind) {
+                        fen.push(piece.to_fen_char());
+                    }
+                }
+            }
+
+            fen.push(']');
+        }
+
+        fen.push(' ');
+        fen.push(match self.side_to_move {
+            Color::White => 'w',
+            Color::Black => 'b'
+        });
+        fen.push(' ');
+
+        let castling = self.castling_fen();
+
+        if castling.is_empty() {
+            fen.push('-');
+        }
+        else {
+            fen.push_str(&castling);
+        }
+
+        fen.push(' ');
+
+        match self.en_passant {
+            Some(square) => fen.push_str(&square.to_string()),
+            None => fen.push('-')
+        }
+
+        fen
+    }
+
+    fn castling_fen(&self) -> String {
+        let mut castling = String::new();
+
+        for color in [Color::White, Color::Black] {
+            let rank = back_rank(color);
+            let king_file = self.king_square(color).map(|king| king.file()).unwrap_or(4);
+
+            for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+                let Some(rook_file) = self.castling_rooks[color_index(color)][side.index()]
+                else {
+                    continue;
+                };
+
+                let is_outermost = match side {
+                    CastlingSide::KingSide => rook_file + 1..8,
+                    CastlingSide::QueenSide => 0..rook_file
+                }.all(|file| self.piece_at(Square::new(file, rank).unwrap()) !=
+                    Some(Piece { color, kind: PieceKind::Rook }));
+                let is_outermost = is_outermost && (side == CastlingSide::KingSide) ==
+                    (rook_file > king_file);
+                let c = if is_outermost {
+                    match side {
+                        CastlingSide::KingSide => 'k',
+                        CastlingSide::QueenSide => 'q'
+                    }
+                }
+                else {
+                    (b'a' + rook_file) as char
+                };
+
+                castling.push(match color {
+                    Color::White => c.to_ascii_uppercase(),
+                    Color::Black => c
+                });
+            }
+        }
+
+        castling
+    }
+
+    /// The variant whose rules this position follows.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// The piece on the given square, if any.
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.index()]
+    }
+
+    pub(crate) fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.board[square.index()] = piece;
+    }
+
+    /// An iterator over all pieces on the board together with their squares.
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.board.iter()
+            .enumerate()
+            .filter_map(|(index, piece)| piece.map(|piece| (Square::from_index(index), piece)))
+    }
+
+    /// The color whose turn it is.
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    /// The square on which an en-passant capture is possible, if any.
+    pub fn en_passant_square(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// The number of half moves since the last capture or pawn move.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// The number of the current full move, starting at 1 and incremented after each move of
+    /// black.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Indicates whether the given color still has the right to castle towards the given side.
+    pub fn has_castling_right(&self, color: Color, side: CastlingSide) -> bool {
+        self.castling_rooks[color_index(color)][side.index()].is_some()
+    }
+
+    /// The Crazyhouse pocket of the given color, which is always empty in other variants.
+    pub fn pocket(&self, color: Color) -> &Pocket {
+        &self.pockets[color_index(color)]
+    }
+
+    /// The Crazyhouse pockets of both colors, which are always empty in other variants.
+    pub fn pockets(&self) -> Pockets {
+        Pockets {
+            white: self.pockets[color_index(Color::White)],
+            black: self.pockets[color_index(Color::Black)]
+        }
+    }
+
+    /// Indicates whether the piece on the given square was promoted from a pawn, which is only
+    /// tracked in Crazyhouse, where captured promoted pieces are added to the pocket as pawns.
+    pub fn is_promoted(&self, square: Square) -> bool {
+        self.promoted & (1 << square.index()) != 0
+    }
+
+    /// The number of checks the given color still has to give to win a game of Three-check. In
+    /// other variants, this is always 3.
+    pub fn remaining_checks(&self, color: Color) -> u8 {
+        self.remaining_checks[color_index(color)]
+    }
+
+    /// The number of checks the given color has given so far in a game of Three-check. In other
+    /// variants, this is always 0.
+    pub fn checks_given(&self, color: Color) -> u8 {
+        THREE_CHECK_CHECKS - self.remaining_checks(color)
+    }
+
+    /// The number of king moves the king of the given color needs at least to reach one of the four
+    /// center squares (d4, d5, e4, e5), which wins a game of King of the Hill. Returns [None] if
+    /// the color has no king.
+    pub fn king_center_distance(&self, color: Color) -> Option<u8> {
+        let king = self.king_square(color)?;
+
+        Some(center_distance(king.file()).max(center_distance(king.rank())))
+    }
+
+    /// The square of the king of the given color, or [None] if the color has no king.
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.pieces()
+            .find(|(_, piece)| piece.color == color && piece.kind == PieceKind::King)
+            .map(|(square, _)| square)
+    }
+
+    /// Indicates whether the given square is attacked by any piece of the given color.
+    pub fn is_attacked(&self, square: Square, by: Color) -> bool {
+        let is_piece = |square: Option<Square>, kinds: &[PieceKind]| square
+            .and_then(|square| self.piece_at(square))
+            .is_some_and(|piece| piece.color == by && kinds.contains(&piece.kind));
+        let pawn_rank_delta = -pawn_direction(by);
+
+        if is_piece(square.offset(-1, pawn_rank_delta), &[PieceKind::Pawn]) ||
+                is_piece(square.offset(1, pawn_rank_delta), &[PieceKind::Pawn]) {
+            return true;
+        }
+
+        if KNIGHT_OFFSETS.iter()
+                .any(|&(df, dr)| is_piece(square.offset(df, dr), &[PieceKind::Knight])) {
+            return true;
+        }
+
+        if KING_OFFSETS.iter()
+                .any(|&(df, dr)| is_piece(square.offset(df, dr), &[PieceKind::King])) {
+            return true;
+        }
+
+        let slider_attacks = |directions: &[(i8, i8)], kinds: &[PieceKind]| directions.iter()
+            .any(|&(df, dr)| is_piece(self.first_piece_in_direction(square, df, dr), kinds));
+
+        slider_attacks(&ROOK_DIRECTIONS, &[PieceKind::Rook, PieceKind::Queen]) ||
+            slider_attacks(&BISHOP_DIRECTIONS, &[PieceKind::Bishop, PieceKind::Queen])
+    }
+
+    fn first_piece_in_direction(&self, from: Square, file_delta: i8, rank_delta: i8)
+            -> Option<Square> {
+        let mut square = from;
+
+        while let Some(next) = square.offset(file_delta, rank_delta) {
+            if self.piece_at(next).is_some() {
+                return Some(next);
+            }
+
+            square = next;
+        }
+
+        None
+    }
+
+    fn is_adjacent_to_king(&self, square: Square, color: Color) -> bool {
+        KING_OFFSETS.iter()
+            .filter_map(|&(df, dr)| square.offset(df, dr))
+            .any(|square| self.piece_at(square) == Some(Piece { color, kind: PieceKind::King }))
+    }
+
+    /// Indicates whether a king of the opposite color would be threatened on the given square. In
+    /// Atomic, a king next to the enemy king cannot be captured, since that would explode both.
+    fn is_threatened(&self, square: Square, by: Color) -> bool {
+        if self.variant == Variant::Atomic && self.is_adjacent_to_king(square, by) {
+            return false;
+        }
+
+        self.is_attacked(square, by)
+    }
+
+    fn is_in_check(&self, color: Color) -> bool {
+        if self.variant == Variant::Antichess {
+            return false;
+        }
+
+        self.king_square(color).is_some_and(|king| self.is_threatened(king, color.opposite()))
+    }
+
+    /// Indicates whether the king of the side to move is in check. There is no check in
+    /// Antichess, where the king is a regular piece.
+    pub fn is_check(&self) -> bool {
+        self.is_in_check(self.side_to_move)
+    }
+
+    /// Indicates whether the side to move is checkmated. Games which ended due to a
+    /// variant-specific rule (see [Position::is_variant_end]) are never considered checkmate.
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && !self.is_variant_end() && self.legal_moves().is_empty()
+    }
+
+    /// Indicates whether the side to move is stalemated. Games which ended due to a
+    /// variant-specific rule (see [Position::is_variant_end]) are never considered stalemate.
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && !self.is_variant_end() && self.legal_moves().is_empty()
+    }
+
+    /// Indicates whether the game ended due to a rule of the variant other than checkmate and
+    /// stalemate, such as a king reaching the center in King of the Hill, the third check in
+    /// Three-check, or a king exploding in Atomic.
+    pub fn is_variant_end(&self) -> bool {
+        match self.variant {
+            Variant::KingOfTheHill => self.king_on_center().is_some(),
+            Variant::ThreeCheck => self.remaining_checks.contains(&0),
+            Variant::Atomic =>
+                self.king_square(Color::White).is_none() ||
+                    self.king_square(Color::Black).is_none(),
+            Variant::Antichess =>
+                !self.has_pieces(Color::White) || !self.has_pieces(Color::Black) ||
+                    self.antichess_moves().is_empty(),
+            Variant::Horde => !self.has_pieces(Color::White),
+            Variant::RacingKings => self.racing_kings_end().is_some(),
+            _ => false
+        }
+    }
+
+    /// The winner of a game which ended due to a variant-specific rule, as determined by
+    /// [Position::is_variant_end].
+    ///
+    /// # Returns
+    ///
+    /// The winning color, or [None] if the game has not ended due to a variant-specific rule or
+    /// ended in a draw.
+    pub fn variant_winner(&self) -> Option<Color> {
+        if !self.is_variant_end() {
+            return None;
+        }
+
+        match self.variant {
+            Variant::KingOfTheHill => self.king_on_center(),
+            Variant::ThreeCheck => [Color::White, Color::Black].into_iter()
+                .find(|&color| self.remaining_checks(color) == 0),
+            Variant::Atomic => [Color::White, Color::Black].into_iter()
+                .find(|&color| self.king_square(color).is_some()),
+            Variant::Antichess => [Color::White, Color::Black].into_iter()
+                .find(|&color| !self.has_pieces(color))
+                .or(Some(self.side_to_move)),
+            Variant::Horde => Some(Color::Black),
+            Variant::RacingKings => self.racing_kings_end().flatten(),
+            _ => None
+        }
+    }
+
+    /// Indicates whether the given color has enough material to checkmate the opponent with the
+    /// opponent's cooperation, i.e. more than a lone king, a king and a single minor piece, or a
+    /// king and bishops all on squares of the same color. Always `true` in variants other than
+    /// [Variant::Standard], [Variant::Chess960] and [Variant::FromPosition], whose rules make
+    /// material assessments unreliable.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        if !self.has_standard_rules() {
+            return true;
+        }
+
+        let pieces = self.pieces()
+            .filter(|(_, piece)| piece.color == color && piece.kind != PieceKind::King)
+            .collect::<Vec<_>>();
+
+        !is_minor_material(&pieces)
+    }
+
+    /// Indicates whether this position is dead due to insufficient material, i.e. neither color
+    /// can checkmate by any sequence of legal moves, as is the case with only kings, kings and a
+    /// single minor piece, or kings and bishops which are all on squares of the same color. Always
+    /// `false` in variants other than [Variant::Standard], [Variant::Chess960] and
+    /// [Variant::FromPosition].
+    pub fn is_insufficient_material(&self) -> bool {
+        if !self.has_standard_rules() {
+            return false;
+        }
+
+        let pieces = self.pieces()
+            .filter(|(_, piece)| piece.kind != PieceKind::King)
+            .collect::<Vec<_>>();
+
+        is_minor_material(&pieces)
+    }
+
+    /// The total value of the pieces of the given color in centipawns (see [PieceKind::value]),
+    /// including the pieces in its Crazyhouse pocket.
+    pub fn material(&self, color: Color) -> i32 {
+        let board = self.pieces()
+            .filter(|(_, piece)| piece.color == color)
+            .map(|(_, piece)| piece.kind.value())
+            .sum::<i32>();
+        let pocket = POCKET_KINDS.iter()
+            .map(|&kind| i32::from(self.pocket(color).count(kind)) * kind.value())
+            .sum::<i32>();
+
+        board + pocket
+    }
+
+    /// The difference in [Position::material] between White and Black in centipawns, which is
+    /// positive if White is ahead.
+    pub fn material_balance(&self) -> i32 {
+        self.material(Color::White) - self.material(Color::Black)
+    }
+
+    /// The number of moves the given color could make if it was its turn, ignoring whether they
+    /// leave the king in check and not counting castling. This is a cheap measure of how active
+    /// the pieces of the color are.
+    pub fn mobility(&self, color: Color) -> usize {
+        let mut position = self.clone();
+        position.side_to_move = color;
+
+        if color != self.side_to_move {
+            position.en_passant = None;
+        }
+
+        position.pseudo_legal_moves().len()
+    }
+
+    /// A simple static evaluation of this position in centipawns from White's perspective,
+    /// consisting of the [Position::material_balance] and a small bonus for each move of
+    /// [Position::mobility] more than the opponent. It does not search and knows nothing about
+    /// checkmate, threats or variant rules, so it is only suitable for very simple bots or as a
+    /// sanity check for the output of an engine.
+    pub fn static_evaluation(&self) -> i32 {
+        let mobility = self.mobility(Color::White) as i32 - self.mobility(Color::Black) as i32;
+
+        self.material_balance() + MOBILITY_WEIGHT * mobility
+    }
+
+    fn has_standard_rules(&self) -> bool {
+        matches!(self.variant, Variant::Standard | Variant::Chess960 | Variant::FromPosition)
+    }
+
+    fn has_pieces(&self, color: Color) -> bool {
+        self.pieces().any(|(_, piece)| piece.color == color)
+    }
+
+    fn king_on_center(&self) -> Option<Color> {
+        [Color::White, Color::Black].into_iter()
+            .find(|&color| self.king_square(color).is_some_and(is_center))
+    }
+
+    /// Determines whether a game of Racing Kings is over. If white reaches the 8th rank first,
+    /// black gets one more move to reach it as well, which draws the game.
+    ///
+    /// # Returns
+    ///
+    /// [None] if the game is not over, `Some(None)` if it is drawn, and `Some(Some(color))` if the
+    /// given color won.
+    fn racing_kings_end(&self) -> Option<Option<Color>> {
+        let on_goal = |color| self.king_square(color).is_some_and(|king| king.rank() == 7);
+
+        match (on_goal(Color::White), on_goal(Color::Black)) {
+            (true, true) => Some(None),
+            (false, true) => Some(Some(Color::Black)),
+            (true, false) => {
+                let black_can_reach_goal = self.side_to_move == Color::Black &&
+                    self.generate_legal_moves().iter().any(|mov| {
+                        self.piece_at(mov.from).is_some_and(|piece| piece.kind == PieceKind::King)
+                            && mov.to.rank() == 7
+                    });
+
+                if black_can_reach_goal {
+                    None
+                }
+                else {
+                    Some(Some(Color::White))
+                }
+            },
+            (false, false) => None
+        }
+    }
+
+    fn push_pawn_moves(&self, from: Square, moves: &mut Vec<UciMove>) {
+        let color = self.side_to_move;
+        let direction = pawn_direction(color);
+        let promotion_rank = back_rank(color.opposite());
+        let start_rank = match color {
+            Color::White => 1,
+            Color::Black => 6
+        };
+        let can_double_push = from.rank() == start_rank ||
+            (self.variant == Variant::Horde && from.rank() == back_rank(color));
+        let promotion_kinds: &[PieceKind] = if self.variant == Variant::Antichess {
+            &[PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight,
+                PieceKind::King]
+        }
+        else {
+            &PROMOTION_KINDS
+        };
+        let mut push = |to: Square| {
+            if to.rank() == promotion_rank {
+                for &kind in promotion_kinds {
+                    moves.push(UciMove {
+                        from,
+                        to,
+                        promotion: Some(kind),
+                        drop: None
+                    });
+                }
+            }
+            else {
+                moves.push(UciMove::new(from, to));
+            }
+        };
+
+        if let Some(single) = from.offset(0, direction).filter(|&to| self.piece_at(to).is_none()) {
+            push(single);
+
+            if can_double_push {
+                if let Some(double) =
+                        single.offset(0, direction).filter(|&to| self.piece_at(to).is_none()) {
+                    push(double);
+                }
+            }
+        }
+
+        for file_delta in [-1, 1] {
+            if let Some(to) = from.offset(file_delta, direction) {
+                let is_capture = self.piece_at(to).is_some_and(|piece| piece.color != color);
+
+                if is_capture || self.en_passant == Some(to) {
+                    push(to);
+                }
+            }
+        }
+    }
+
+    fn push_step_moves(&self, from: Square, offsets: &[(i8, i8)], can_capture: bool,
+            moves: &mut Vec<UciMove>) {
+        for &(df, dr) in offsets {
+            if let Some(to) = from.offset(df, dr) {
+                let is_allowed = match self.piece_at(to) {
+                    Some(piece) => can_capture && piece.color != self.side_to_move,
+                    None => true
+                };
+
+                if is_allowed {
+                    moves.push(UciMove::new(from, to));
+                }
+            }
+        }
+    }
+
+    fn push_slider_moves(&self, from: Square, directions: &[(i8, i8)], moves: &mut Vec<UciMove>) {
+        for &(df, dr) in directions {
+            let mut square = from;
+
+            while let Some(to) = square.offset(df, dr) {
+                match self.piece_at(to) {
+                    Some(piece) => {
+                        if piece.color != self.side_to_move {
+                            moves.push(UciMove::new(from, to));
+                        }
+
+                        break;
+                    },
+                    None => moves.push(UciMove::new(from, to))
+                }
+
+                square = to;
+            }
+        }
+    }
+
+    fn push_drop_moves(&self, moves: &mut Vec<UciMove>) {
+        let pocket = self.pocket(self.side_to_move);
+
+        for kind in POCKET_KINDS.into_iter().filter(|&kind| pocket.count(kind) > 0) {
+            for index in 0..64 {
+                let square = Square::from_index(index);
+                let is_pawn_on_back_rank =
+                    kind == PieceKind::Pawn && (square.rank() == 0 || square.rank() == 7);
+
+                if self.piece_at(square).is_none() && !is_pawn_on_back_rank {
+                    moves.push(UciMove::new_drop(kind, square));
+                }
+            }
+        }
+    }
+
+    fn castling_move(&self, side: CastlingSide) -> Option<UciMove> {
+        let color = self.side_to_move;
+        let rank = back_rank(color);
+        let rook_file = self.castling_rooks[color_index(color)][side.index()]?;
+        let king = self.king_square(color).filter(|king| king.rank() == rank)?;
+        let rook = Square::new(rook_file, rank).unwrap();
+        let king_destination = Square::new(side.king_destination_file(), rank).unwrap();
+        let rook_destination = Square::new(side.rook_destination_file(), rank).unwrap();
+
+        if self.piece_at(rook) != Some(Piece { color, kind: PieceKind::Rook }) {
+            return None;
+        }
+
+        let min_file = king.file().min(rook_file).min(king_destination.file())
+            .min(rook_destination.file());
+        let max_file = king.file().max(rook_file).max(king_destination.file())
+            .max(rook_destination.file());
+        let path_is_free = (min_file..=max_file)
+            .map(|file| Square::new(file, rank).unwrap())
+            .all(|square| square == king || square == rook || self.piece_at(square).is_none());
+
+        if !path_is_free {
+            return None;
+        }
+
+        let king_min_file = king.file().min(king_destination.file());
+        let king_max_file = king.file().max(king_destination.file());
+        let king_path_is_safe = (king_min_file..=king_max_file)
+            .map(|file| Square::new(file, rank).unwrap())
+            .all(|square| !self.is_threatened(square, color.opposite()));
+
+        if !king_path_is_safe {
+            return None;
+        }
+
+        if king.file().abs_diff(king_destination.file()) == 2 {
+            Some(UciMove::new(king, king_destination))
+        }
+        else {
+            Some(UciMove::new(king, rook))
+        }
+    }
+
+    fn pseudo_legal_moves(&self) -> Vec<UciMove> {
+        let mut moves = Vec::new();
+        let king_can_capture = self.variant != Variant::Atomic;
+
+        for (from, piece) in self.pieces() {
+            if piece.color != self.side_to_move {
+                continue;
+            }
+
+            match piece.kind {
+                PieceKind::Pawn => self.push_pawn_moves(from, &mut moves),
+                PieceKind::Knight =>
+                    self.push_step_moves(from, &KNIGHT_OFFSETS, true, &mut moves),
+                PieceKind::Bishop => self.push_slider_moves(from, &BISHOP_DIRECTIONS, &mut moves),
+                PieceKind::Rook => self.push_slider_moves(from, &ROOK_DIRECTIONS, &mut moves),
+                PieceKind::Queen => {
+                    self.push_slider_moves(from, &ROOK_DIRECTIONS, &mut moves);
+                    self.push_slider_moves(from, &BISHOP_DIRECTIONS, &mut moves);
+                },
+                PieceKind::King =>
+                    self.push_step_moves(from, &KING_OFFSETS, king_can_capture, &mut moves)
+            }
+        }
+
+        if self.variant == Variant::Crazyhouse {
+            self.push_drop_moves(&mut moves);
+        }
+
+        moves
+    }
+
+    fn antichess_moves(&self) -> Vec<UciMove> {
+        let mut moves = self.pseudo_legal_moves();
+
+        if moves.iter().any(|mov| self.is_capture(mov)) {
+            moves.retain(|mov| self.is_capture(mov));
+        }
+
+        moves
+    }
+
+    fn generate_legal_moves(&self) -> Vec<UciMove> {
+        if self.variant == Variant::Antichess {
+            return self.antichess_moves();
+        }
+
+        let mut moves = self.pseudo_legal_moves().into_iter()
+            .filter(|mov| self.leaves_king_safe(mov))
+            .collect::<Vec<_>>();
+
+        if !self.is_check() {
+            moves.extend([CastlingSide::KingSide, CastlingSide::QueenSide].into_iter()
+                .filter_map(|side| self.castling_move(side))
+                .filter(|mov| self.variant != Variant::Atomic || self.leaves_king_safe(mov)));
+        }
+
+        moves
+    }
+
+    /// Computes all legal moves in this position according to the rules of its variant. Castling
+    /// moves are given in standard notation, i.e. with the king moving two squares, if possible,
+    /// and as the king moving onto the rook otherwise. If the game ended due to a
+    /// variant-specific rule, there are no legal moves.
+    pub fn legal_moves(&self) -> Vec<UciMove> {
+        if self.is_variant_end() {
+            return Vec::new();
+        }
+
+        self.generate_legal_moves()
+    }
+
+    fn leaves_king_safe(&self, mov: &UciMove) -> bool {
+        let color = self.side_to_move;
+        let mut after = self.clone();
+        after.apply(mov);
+
+        match self.variant {
+            Variant::Atomic => after.king_square(color).is_some() &&
+                (after.king_square(color.opposite()).is_none() || !after.is_in_check(color)),
+            Variant::RacingKings =>
+                !after.is_in_check(color) && !after.is_in_check(color.opposite()),
+            _ => !after.is_in_check(color)
+        }
+    }
+
+    /// Determines towards which side the given move castles, if it is a castling move in this
+    /// position. Both the standard notation (king moves two squares) and the king-onto-rook
+    /// notation are recognized.
+    pub fn castling_side(&self, mov: &UciMove) -> Option<CastlingSide> {
+        let color = self.side_to_move;
+        let piece = self.piece_at(mov.from)?;
+
+        if mov.drop.is_some() || piece != (Piece { color, kind: PieceKind::King }) ||
+                mov.from.rank() != mov.to.rank() || mov.from.rank() != back_rank(color) {
+            return None;
+        }
+
+        let side = if mov.to.file() > mov.from.file() {
+            CastlingSide::KingSide
+        }
+        else {
+            CastlingSide::QueenSide
+        };
+        let rook_file = self.castling_rooks[color_index(color)][side.index()]?;
+        let is_onto_rook = mov.to.file() == rook_file;
+        let is_two_squares = mov.from.file().abs_diff(mov.to.file()) == 2 &&
+            mov.to.file() == side.king_destination_file();
+
+        if is_onto_rook || is_two_squares {
+            Some(side)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Indicates whether the given move is a capture in this position, including en-passant.
+    pub fn is_capture(&self, mov: &UciMove) -> bool {
+        if mov.drop.is_some() || self.castling_side(mov).is_some() {
+            return false;
+        }
+
+        let is_pawn = self.piece_at(mov.from).is_some_and(|piece| piece.kind == PieceKind::Pawn);
+
+        self.piece_at(mov.to).is_some() || (is_pawn && self.en_passant == Some(mov.to))
+    }
+
+    fn normalize(&self, mov: &UciMove) -> UciMove {
+        match self.castling_side(mov) {
+            Some(side) => self.castling_move(side).unwrap_or(*mov),
+            None => *mov
+        }
+    }
+
+    /// Indicates whether the given move is legal in this position.
+    pub fn is_legal(&self, mov: &UciMove) -> bool {
+        let mov = self.normalize(mov);
+
+        self.legal_moves().contains(&mov)
+    }
+
+    /// Plays the given move, updating this position.
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::IllegalMove] if the move is not legal in this position. In that case, the
+    /// position remains unchanged.
+    pub fn play(&mut self, mov: &UciMove) -> ChessResult<()> {
+        if !self.is_legal(mov) {
+            return Err(ChessError::IllegalMove(mov.to_string()));
+        }
+
+        self.apply(mov);
+        Ok(())
+    }
+
+    /// Plays all moves from the given space-separated list of moves in UCI notation, as it is
+    /// provided by [GameStateEvent::moves](crate::model::game::event::GameStateEvent::moves).
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::InvalidUciMove] if any move is not valid UCI notation and
+    /// [ChessError::IllegalMove] if any move is illegal. Moves before the erroneous one remain
+    /// applied.
+    pub fn play_uci_moves(&mut self, moves: &str) -> ChessResult<()> {
+        for mov in moves.split_whitespace() {
+            self.play(&mov.parse()?)?;
+        }
+
+        Ok(())
+    }
+
+    fn clear_castling_rights_for_rook(&mut self, square: Square) {
+        for color in [Color::White, Color::Black] {
+            if square.rank() != back_rank(color) {
+                continue;
+            }
+
+            for rook_file in &mut self.castling_rooks[color_index(color)] {
+                if *rook_file == Some(square.file()) {
+                    *rook_file = None;
+                }
+            }
+        }
+    }
+
+    fn set_promoted(&mut self, square: Square, promoted: bool) {
+        if promoted {
+            self.promoted |= 1 << square.index();
+        }
+        else {
+            self.promoted &= !(1 << square.index());
+        }
+    }
+
+    fn remove_piece(&mut self, square: Square) {
+        if let Some(piece) = self.piece_at(square) {
+            if piece.kind == PieceKind::King {
+                self.castling_rooks[color_index(piece.color)] = [None; 2];
+            }
+        }
+
+        self.set_piece(square, None);
+        self.clear_castling_rights_for_rook(square);
+    }
+
+    /// Removes the piece on the given square and all non-pawn pieces around it, as happens after
+    /// a capture in Atomic.
+    fn explode(&mut self, center: Square) {
+        self.remove_piece(center);
+
+        for &(df, dr) in &KING_OFFSETS {
+            if let Some(square) = center.offset(df, dr) {
+                if self.piece_at(square).is_some_and(|piece| piece.kind != PieceKind::Pawn) {
+                    self.remove_piece(square);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn apply(&mut self, mov: &UciMove) {
+        let color = self.side_to_move;
+
+        if let Some(kind) = mov.drop {
+            self.pockets[color_index(color)].remove(kind);
+            self.set_piece(mov.to, Some(Piece { color, kind }));
+            self.halfmove_clock += 1;
+            self.en_passant = None;
+        }
+        else if !self.apply_board_move(mov) {
+            return;
+        }
+
+        if color == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.side_to_move = color.opposite();
+
+        if self.variant == Variant::ThreeCheck && self.is_check() {
+            let remaining_checks = &mut self.remaining_checks[color_index(color)];
+            *remaining_checks = remaining_checks.saturating_sub(1);
+        }
+    }
+
+    fn apply_board_move(&mut self, mov: &UciMove) -> bool {
+        let color = self.side_to_move;
+        let Some(piece) = self.piece_at(mov.from)
+        else {
+            return false;
+        };
+
+        self.halfmove_clock += 1;
+
+        if let Some(side) = self.castling_side(mov) {
+            let rank = back_rank(color);
+            let rook_file = self.castling_rooks[color_index(color)][side.index()].unwrap();
+            let rook = Square::new(rook_file, rank).unwrap();
+
+            self.set_piece(mov.from, None);
+            self.set_piece(rook, None);
+            self.set_piece(Square::new(side.king_destination_file(), rank).unwrap(), Some(piece));
+            self.set_piece(Square::new(side.rook_destination_file(), rank).unwrap(),
+                Some(Piece { color, kind: PieceKind::Rook }));
+            self.castling_rooks[color_index(color)] = [None; 2];
+            self.en_passant = None;
+
+            return true;
+        }
+
+        let is_pawn = piece.kind == PieceKind::Pawn;
+        let mut captured = self.piece_at(mov.to).map(|piece| (piece, self.is_promoted(mov.to)));
+
+        if captured.is_some() || is_pawn {
+            self.halfmove_clock = 0;
+        }
+
+        if is_pawn && self.en_passant == Some(mov.to) && captured.is_none() {
+            let captured_square = mov.to.offset(0, -pawn_direction(color)).unwrap();
+            captured = self.piece_at(captured_square).map(|piece| (piece, false));
+            self.set_piece(captured_square, None);
+        }
+
+        self.clear_castling_rights_for_rook(mov.from);
+        self.clear_castling_rights_for_rook(mov.to);
+
+        if piece.kind == PieceKind::King {
+            self.castling_rooks[color_index(color)] = [None; 2];
+        }
+
+        let placed = match mov.promotion {
+            Some(kind) if is_pawn => Piece { color, kind },
+            _ => piece
+        };
+
+        if self.variant == Variant::Crazyhouse {
+            if let Some((captured, is_promoted)) = captured {
+                let kind = if is_promoted { PieceKind::Pawn } else { captured.kind };
+                self.pockets[color_index(color)].add(kind);
+            }
+
+            let is_promoted = self.is_promoted(mov.from) || placed != piece;
+            self.set_promoted(mov.from, false);
+            self.set_promoted(mov.to, is_promoted);
+        }
+
+        self.set_piece(mov.from, None);
+        self.set_piece(mov.to, Some(placed));
+        self.en_passant = None;
+
+        if self.variant == Variant::Atomic && captured.is_some() {
+            self.explode(mov.to);
+        }
+
+        if is_pawn && mov.from.rank().abs_diff(mov.to.rank()) == 2 {
+            let passed = mov.from.offset(0, pawn_direction(color)).unwrap();
+            let enemy_pawn = Some(Piece { color: color.opposite(), kind: PieceKind::Pawn });
+            let can_be_captured = [-1, 1].into_iter()
+                .filter_map(|file_delta| mov.to.offset(file_delta, 0))
+                .any(|square| self.piece_at(square) == enemy_pawn);
+
+            if can_be_captured {
+                self.en_passant = Some(passed);
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for Position {
+    fn default() -> Position {
+        Position::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn perft(position: &Position, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        position.legal_moves().iter()
+            .map(|mov| {
+                let mut next = position.clone();
+                next.play(mov).unwrap();
+                perft(&next, depth - 1)
+            })
+            .sum()
+    }
+
+    #[rstest]
+    #[case::standard(STANDARD_FEN, 3, 8902)]
+    #[case::kiwipete(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2, 2039)]
+    #[case::en_passant_and_promotion("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3, 2812)]
+    #[case::promotion_and_castling(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 2, 264)]
+    fn perft_matches_reference(#[case] fen: &str, #[case] depth: u32, #[case] expected: u64) {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_that!(perft(&position, depth)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::standard(STANDARD_FEN)]
+    #[case::kiwipete("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")]
+    #[case::en_passant("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")]
+    #[case::shredder("4k3/8/8/8/8/8/8/RR2K2R w KB - 0 1")]
+    fn fen_round_trip(#[case] fen: &str) {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_that!(position.to_fen()).is_equal_to(fen.to_owned());
+    }
+
+    #[rstest]
+    #[case::too_few_fields("8/8/8/8/8/8/8/8 w")]
+    #[case::too_few_ranks("8/8/8/8/8/8/8 w - - 0 1")]
+    #[case::rank_too_long("9/8/8/8/8/8/8/8 w - - 0 1")]
+    #[case::invalid_piece("x7/8/8/8/8/8/8/8 w - - 0 1")]
+    #[case::invalid_side("8/8/8/8/8/8/8/8 x - - 0 1")]
+    #[case::castling_without_rook("4k3/8/8/8/8/8/8/4K3 w K - 0 1")]
+    #[case::invalid_en_passant("8/8/8/8/8/8/8/8 w - z9 0 1")]
+    #[case::en_passant_without_pawn("4k3/8/8/8/8/8/8/4K3 w - e6 0 1")]
+    #[case::en_passant_on_wrong_rank("4k3/8/8/8/4p3/8/8/4K3 w - e5 0 1")]
+    fn parse_invalid_fen(#[case] fen: &str) {
+        assert_that!(Position::from_fen(fen)).is_err();
+    }
+
+    #[rstest]
+    #[case::valid(STANDARD_FEN, STANDARD_FEN, vec![])]
+    #[case::castling_without_rook("4k3/8/8/8/8/8/8/R3K3 w KQ - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", vec![FenIssue::ImpossibleCastlingRight('K')])]
+    #[case::castling_with_moved_king("r3k2r/8/8/8/8/8/4K3/R6R w KQkq - 0 1",
+        "r3k2r/8/8/8/8/8/4K3/R6R w kq - 0 1", vec![
+            FenIssue::ImpossibleCastlingRight('K'),
+            FenIssue::ImpossibleCastlingRight('Q')
+        ])]
+    #[case::en_passant_without_pawn("4k3/8/8/8/8/8/8/4K3 w - e6 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        vec![FenIssue::ImpossibleEnPassantSquare("e6".parse().unwrap())])]
+    #[case::en_passant_with_blocked_origin("4k3/4p3/8/4p3/8/8/8/4K3 w - e6 0 1",
+        "4k3/4p3/8/4p3/8/8/8/4K3 w - - 0 1",
+        vec![FenIssue::ImpossibleEnPassantSquare("e6".parse().unwrap())])]
+    #[case::en_passant_for_wrong_side("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1",
+        "4k3/8/8/8/4P3/8/8/4K3 w - - 0 1",
+        vec![FenIssue::ImpossibleEnPassantSquare("e3".parse().unwrap())])]
+    fn fen_is_normalized(#[case] fen: &str, #[case] expected_fen: &str,
+            #[case] expected_issues: Vec<FenIssue>) {
+        let (position, issues) = Position::from_fen_normalized(fen, Variant::FromPosition).unwrap();
+
+        assert_that!(position.to_fen()).is_equal_to(expected_fen.to_owned());
+        assert_that!(issues).is_equal_to(expected_issues);
+    }
+
+    #[test]
+    fn normalization_rejects_invalid_castling_character() {
+        let result = Position::from_fen_normalized("4k3/8/8/8/8/8/8/4K3 w X - 0 1",
+            Variant::FromPosition);
+
+        assert_that!(result).is_err();
+    }
+
+    #[test]
+    fn impossible_en_passant_capture_is_not_generated_after_normalization() {
+        let fen = "4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1";
+        let fen_without_origin = "4k3/4n3/8/3Pp3/8/8/8/4K3 w - e6 0 1";
+
+        let (valid, _) = Position::from_fen_normalized(fen, Variant::FromPosition).unwrap();
+        let (normalized, _) =
+            Position::from_fen_normalized(fen_without_origin, Variant::FromPosition).unwrap();
+
+        assert_that!(valid.is_legal(&"d5e6".parse().unwrap())).is_true();
+        assert_that!(normalized.is_legal(&"d5e6".parse().unwrap())).is_false();
+    }
+
+    fn from_position_info(initial_fen: &str) -> GameInfo {
+        serde_json::from_value(json!({
+            "id": "testGameId",
+            "variant": { "key": Variant::FromPosition.key() },
+            "speed": "blitz",
+            "perf": {},
+            "rated": false,
+            "createdAt": 0,
+            "white": {},
+            "black": {},
+            "initialFen": initial_fen
+        })).unwrap()
+    }
+
+    #[test]
+    fn detect_accepts_valid_initial_fen() {
+        let info = from_position_info("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+
+        assert_that!(InvalidPosition::detect(&info)).is_none();
+    }
+
+    #[test]
+    fn detect_reports_normalized_initial_fen() {
+        let info = from_position_info("4k3/8/8/8/8/8/8/4K3 w K e6 0 1");
+
+        let warning = InvalidPosition::detect(&info);
+
+        assert_that!(warning).contains(InvalidPosition::Normalized {
+            fen: "4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_owned(),
+            issues: vec![
+                FenIssue::ImpossibleCastlingRight('K'),
+                FenIssue::ImpossibleEnPassantSquare("e6".parse().unwrap())
+            ]
+        });
+    }
+
+    #[test]
+    fn detect_reports_unparsable_initial_fen() {
+        let info = from_position_info("not a fen");
+
+        let warning = InvalidPosition::detect(&info);
+
+        assert!(matches!(warning,
+            Some(InvalidPosition::Unparsable(ChessError::InvalidFen { .. }))));
+    }
+
+    #[test]
+    fn shredder_castling_rights_of_outermost_rooks_are_formatted_as_standard() {
+        let fen = "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb - 0 1";
+
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_that!(position.to_fen()).is_equal_to(
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w KQkq - 0 1".to_owned());
+    }
+
+    #[test]
+    fn play_uci_moves_tracks_position() {
+        let mut position = Position::standard();
+
+        position.play_uci_moves("e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1").unwrap();
+
+        assert_that!(position.to_fen()).is_equal_to(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4".to_owned());
+    }
+
+    #[test]
+    fn castling_onto_rook_is_accepted() {
+        let mut position = Position::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+
+        position.play(&"e1h1".parse().unwrap()).unwrap();
+
+        assert_that!(position.to_fen()).is_equal_to("4k3/8/8/8/8/8/8/5RK1 b - - 1 1".to_owned());
+    }
+
+    #[test]
+    fn illegal_move_is_rejected() {
+        let mut position = Position::standard();
+
+        let result = position.play(&"e2e5".parse().unwrap());
+
+        assert_that!(result).is_err();
+        assert_that!(position).is_equal_to(Position::standard());
+    }
+
+    #[test]
+    fn checkmate_is_detected() {
+        let mut position = Position::standard();
+
+        position.play_uci_moves("f2f3 e7e5 g2g4 d8h4").unwrap();
+
+        assert_that!(position.is_checkmate()).is_true();
+        assert_that!(position.is_stalemate()).is_false();
+    }
+
+    #[test]
+    fn stalemate_is_detected() {
+        let position = Position::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+
+        assert_that!(position.is_stalemate()).is_true();
+        assert_that!(position.is_checkmate()).is_false();
+    }
+
+    fn perft_variant(fen: &str, variant: Variant, depth: u32) -> u64 {
+        perft(&Position::from_fen_with_variant(fen, variant).unwrap(), depth)
+    }
+
+    #[rstest]
+    #[case::horde(HORDE_FEN, Variant::Horde, 3, 1274)]
+    #[case::racing_kings(RACING_KINGS_FEN, Variant::RacingKings, 3, 11264)]
+    #[case::antichess("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+        Variant::Antichess, 3, 8067)]
+    #[case::atomic(STANDARD_FEN, Variant::Atomic, 3, 8902)]
+    #[case::crazyhouse(STANDARD_FEN, Variant::Crazyhouse, 3, 8902)]
+    #[case::three_check(STANDARD_FEN, Variant::ThreeCheck, 3, 8902)]
+    #[case::king_of_the_hill(STANDARD_FEN, Variant::KingOfTheHill, 3, 8902)]
+    fn variant_perft_matches_reference(#[case] fen: &str, #[case] variant: Variant,
+            #[case] depth: u32, #[case] expected: u64) {
+        assert_that!(perft_variant(fen, variant, depth)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::standard(Variant::Standard, Some(STANDARD_FEN))]
+    #[case::horde(Variant::Horde, Some(HORDE_FEN))]
+    #[case::racing_kings(Variant::RacingKings, Some(RACING_KINGS_FEN))]
+    #[case::chess960(Variant::Chess960, None)]
+    #[case::from_position(Variant::FromPosition, None)]
+    fn initial_position(#[case] variant: Variant, #[case] expected_fen: Option<&str>) {
+        let fen = Position::initial(variant).map(|position| position.to_fen());
+
+        assert_that!(fen).is_equal_to(expected_fen.map(str::to_owned));
+    }
+
+    #[rstest]
+    #[case::crazyhouse_brackets(
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[QNpp] w KQkq - 2 3",
+        Variant::Crazyhouse)]
+    #[case::crazyhouse_promoted("4k3/8/8/8/8/8/8/Q~3K3[] b - - 0 1", Variant::Crazyhouse)]
+    #[case::three_check(
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 2+3 0 2",
+        Variant::ThreeCheck)]
+    fn variant_fen_round_trip(#[case] fen: &str, #[case] variant: Variant) {
+        let position = Position::from_fen_with_variant(fen, variant).unwrap();
+
+        assert_that!(position.to_fen()).is_equal_to(fen.to_owned());
+    }
+
+    #[test]
+    fn crazyhouse_pocket_as_ninth_rank_is_parsed() {
+        let position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/8/8/4K3/RNp w - - 0 1", Variant::Crazyhouse).unwrap();
+
+        assert_that!(position.pocket(Color::White).count(PieceKind::Rook)).is_equal_to(1);
+        assert_that!(position.pocket(Color::White).count(PieceKind::Knight)).is_equal_to(1);
+        assert_that!(position.pocket(Color::Black).count(PieceKind::Pawn)).is_equal_to(1);
+    }
+
+    #[test]
+    fn three_check_given_checks_are_parsed() {
+        let position = Position::from_fen_with_variant(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2 +2+0",
+            Variant::ThreeCheck).unwrap();
+
+        assert_that!(position.remaining_checks(Color::White)).is_equal_to(1);
+        assert_that!(position.remaining_checks(Color::Black)).is_equal_to(3);
+    }
+
+    #[test]
+    fn crazyhouse_captures_are_added_to_pocket() {
+        let mut position = Position::initial(Variant::Crazyhouse).unwrap();
+
+        position.play_uci_moves("e2e4 d7d5 e4d5 d8d5").unwrap();
+
+        assert_that!(position.pocket(Color::White).count(PieceKind::Pawn)).is_equal_to(1);
+        assert_that!(position.pocket(Color::Black).count(PieceKind::Pawn)).is_equal_to(1);
+        assert_that!(position.is_legal(&"P@e6".parse().unwrap())).is_true();
+        assert_that!(position.is_legal(&"N@e6".parse().unwrap())).is_false();
+    }
+
+    #[test]
+    fn crazyhouse_pawns_cannot_be_dropped_on_back_rank() {
+        let position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/8/8/4K3[P] w - - 0 1", Variant::Crazyhouse).unwrap();
+
+        assert_that!(position.is_legal(&"P@a1".parse().unwrap())).is_false();
+        assert_that!(position.is_legal(&"P@a2".parse().unwrap())).is_true();
+    }
+
+    #[test]
+    fn crazyhouse_captured_promoted_piece_becomes_pawn() {
+        let mut position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/8/4K3/Q~6r[] b - - 0 1", Variant::Crazyhouse).unwrap();
+
+        position.play_uci_moves("h1a1").unwrap();
+
+        assert_that!(position.pocket(Color::Black).count(PieceKind::Pawn)).is_equal_to(1);
+        assert_that!(position.pocket(Color::Black).count(PieceKind::Queen)).is_equal_to(0);
+    }
+
+    #[test]
+    fn antichess_captures_are_compulsory() {
+        let mut position = Position::initial(Variant::Antichess).unwrap();
+
+        position.play_uci_moves("e2e4 d7d5").unwrap();
+
+        assert_that!(position.legal_moves())
+            .contains_exactly_in_any_order(["e4d5".parse::<UciMove>().unwrap()]);
+    }
+
+    #[test]
+    fn antichess_player_without_pieces_wins() {
+        let mut position = Position::from_fen_with_variant(
+            "8/8/8/8/8/8/8/Rp6 w - - 0 1", Variant::Antichess).unwrap();
+
+        position.play_uci_moves("a1b1").unwrap();
+
+        assert_that!(position.is_variant_end()).is_true();
+        assert_that!(position.variant_winner()).contains(Color::Black);
+    }
+
+    #[test]
+    fn atomic_capture_explodes_surrounding_pieces() {
+        let mut position = Position::from_fen_with_variant(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1", Variant::Atomic)
+            .unwrap();
+
+        position.play_uci_moves("f3e5").unwrap();
+
+        assert_that!(position.piece_at("e5".parse().unwrap())).is_none();
+        assert_that!(position.piece_at("d7".parse().unwrap())).is_some();
+        assert_that!(position.piece_at("f7".parse().unwrap())).is_some();
+    }
+
+    #[test]
+    fn atomic_exploding_king_ends_game() {
+        let mut position = Position::from_fen_with_variant(
+            "4k3/4q3/8/8/8/8/8/4RK2 w - - 0 1", Variant::Atomic).unwrap();
+
+        position.play_uci_moves("e1e7").unwrap();
+
+        assert_that!(position.is_variant_end()).is_true();
+        assert_that!(position.variant_winner()).contains(Color::White);
+    }
+
+    #[test]
+    fn atomic_king_cannot_capture() {
+        let position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/8/4p3/4K3 w - - 0 1", Variant::Atomic).unwrap();
+
+        assert_that!(position.is_legal(&"e1e2".parse().unwrap())).is_false();
+    }
+
+    #[test]
+    fn atomic_connected_kings_are_not_in_check() {
+        let position = Position::from_fen_with_variant(
+            "8/8/8/8/8/4k3/4K3/4r3 w - - 0 1", Variant::Atomic).unwrap();
+
+        assert_that!(position.is_check()).is_false();
+    }
+
+    #[test]
+    fn horde_pawns_can_double_push_from_first_rank() {
+        let position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/8/8/P7 w - - 0 1", Variant::Horde).unwrap();
+
+        assert_that!(position.is_legal(&"a1a3".parse().unwrap())).is_true();
+    }
+
+    #[test]
+    fn horde_ends_when_white_has_no_pieces() {
+        let position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/8/8/8 w - - 0 1", Variant::Horde).unwrap();
+
+        assert_that!(position.variant_winner()).contains(Color::Black);
+        assert_that!(position.legal_moves()).is_empty();
+    }
+
+    #[test]
+    fn racing_kings_forbids_giving_check() {
+        let position = Position::initial(Variant::RacingKings).unwrap();
+
+        assert_that!(position.is_legal(&"h2h3".parse().unwrap())).is_true();
+        assert_that!(position.is_legal(&"e2c3".parse().unwrap())).is_false();
+    }
+
+    #[rstest]
+    #[case::black_can_equalize("7K/k7/8/8/8/8/8/8 b - - 0 1", false, None)]
+    #[case::black_cannot_equalize("7K/8/8/k7/8/8/8/8 b - - 0 1", true, Some(Color::White))]
+    #[case::both_reached_goal("k6K/8/8/8/8/8/8/8 w - - 0 1", true, None)]
+    #[case::black_reached_goal("k7/8/8/8/8/8/8/7K w - - 0 1", true, Some(Color::Black))]
+    fn racing_kings_end(#[case] fen: &str, #[case] expected_end: bool,
+            #[case] expected_winner: Option<Color>) {
+        let position = Position::from_fen_with_variant(fen, Variant::RacingKings).unwrap();
+
+        assert_that!(position.is_variant_end()).is_equal_to(expected_end);
+        assert_that!(position.variant_winner()).is_equal_to(expected_winner);
+    }
+
+    #[rstest]
+    #[case::kings_only("4k3/8/8/8/8/8/8/4K3 w - - 0 1", false, false, true)]
+    #[case::king_and_knight("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", false, false, true)]
+    #[case::same_colored_bishops("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", false, false, true)]
+    #[case::opposite_colored_bishops("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1", false, false, false)]
+    #[case::knight_each("4kn2/8/8/8/8/8/8/4KN2 w - - 0 1", false, false, false)]
+    #[case::two_knights("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", true, false, false)]
+    #[case::pawn("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", true, false, false)]
+    #[case::rook("4k3/8/8/8/8/8/8/4K2r w - - 0 1", false, true, false)]
+    fn material(#[case] fen: &str, #[case] white_can_mate: bool, #[case] black_can_mate: bool,
+            #[case] expected_insufficient: bool) {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_that!(position.has_mating_material(Color::White)).is_equal_to(white_can_mate);
+        assert_that!(position.has_mating_material(Color::Black)).is_equal_to(black_can_mate);
+        assert_that!(position.is_insufficient_material()).is_equal_to(expected_insufficient);
+    }
+
+    #[rstest]
+    #[case::start(STANDARD_FEN, 4000, 0)]
+    #[case::white_up_a_rook("r3k3/8/8/8/8/8/8/R3K2R w - - 0 1", 1000, 500)]
+    #[case::black_up_a_queen("q3k3/8/8/8/8/8/8/4K3 w - - 0 1", 0, -900)]
+    fn material_is_counted(#[case] fen: &str, #[case] expected_white: i32,
+            #[case] expected_balance: i32) {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_that!(position.material(Color::White)).is_equal_to(expected_white);
+        assert_that!(position.material_balance()).is_equal_to(expected_balance);
+    }
+
+    #[test]
+    fn material_includes_pockets() {
+        let position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/8/8/4K3[Qp] w - - 0 1", Variant::Crazyhouse).unwrap();
+
+        assert_that!(position.material(Color::White)).is_equal_to(900);
+        assert_that!(position.material_balance()).is_equal_to(800);
+    }
+
+    #[test]
+    fn mobility_is_counted_for_both_colors() {
+        let mut position = Position::standard();
+
+        assert_that!(position.mobility(Color::White)).is_equal_to(20);
+        assert_that!(position.mobility(Color::Black)).is_equal_to(20);
+        assert_that!(position.static_evaluation()).is_equal_to(0);
+
+        position.play_uci_moves("e2e4").unwrap();
+
+        assert_that!(position.mobility(Color::White)).is_equal_to(30);
+        assert_that!(position.static_evaluation()).is_equal_to(50);
+    }
+
+    #[test]
+    fn insufficient_material_only_applies_to_standard_rules() {
+        let position = Position::from_fen_with_variant("4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            Variant::Crazyhouse).unwrap();
+
+        assert_that!(position.is_insufficient_material()).is_false();
+        assert_that!(position.has_mating_material(Color::White)).is_true();
+    }
+
+    #[test]
+    fn king_of_the_hill_ends_on_center() {
+        let mut position = Position::from_fen_with_variant(
+            "4k3/8/8/8/8/4K3/8/8 w - - 0 1", Variant::KingOfTheHill).unwrap();
+
+        position.play_uci_moves("e3e4").unwrap();
+
+        assert_that!(position.is_variant_end()).is_true();
+        assert_that!(position.variant_winner()).contains(Color::White);
+        assert_that!(position.is_stalemate()).is_false();
+    }
+
+    #[test]
+    fn three_check_counts_checks() {
+        let mut position = Position::initial(Variant::ThreeCheck).unwrap();
+
+        position.play_uci_moves("e2e4 e7e5 f1c4 b8c6 c4f7 e8f7 d1h5").unwrap();
+
+        assert_that!(position.remaining_checks(Color::White)).is_equal_to(1);
+        assert_that!(position.remaining_checks(Color::Black)).is_equal_to(3);
+        assert_that!(position.is_variant_end()).is_false();
+    }
+
+    #[test]
+    fn three_check_checks_given() {
+        let mut position = Position::initial(Variant::ThreeCheck).unwrap();
+
+        position.play_uci_moves("e2e4 e7e5 f1c4 b8c6 c4f7 e8f7 d1h5").unwrap();
+
+        assert_that!(position.checks_given(Color::White)).is_equal_to(2);
+        assert_that!(position.checks_given(Color::Black)).is_equal_to(0);
+    }
+
+    #[rstest]
+    #[case::on_center("4k3/8/8/8/3K4/8/8/8 w - - 0 1", 0)]
+    #[case::next_to_center("4k3/8/8/8/8/2K5/8/8 w - - 0 1", 1)]
+    #[case::corner("4k3/8/8/8/8/8/8/7K w - - 0 1", 3)]
+    #[case::edge("4k3/8/8/8/K7/8/8/8 w - - 0 1", 3)]
+    fn king_center_distance(#[case] fen: &str, #[case] expected_distance: u8) {
+        let position = Position::from_fen_with_variant(fen, Variant::KingOfTheHill).unwrap();
+
+        assert_that!(position.king_center_distance(Color::White)).contains(expected_distance);
+        assert_that!(position.king_center_distance(Color::Black)).contains(3);
+    }
+
+    #[rstest]
+    #[case::standard(None, "startpos", STANDARD_FEN)]
+    #[case::horde(Some(Variant::Horde), "startpos", HORDE_FEN)]
+    #[case::from_position(Some(Variant::FromPosition), "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1")]
+    fn position_from_game_info(#[case] variant: Option<Variant>, #[case] initial_fen: &str,
+            #[case] expected_fen: &str) {
+        let info = serde_json::from_value::<GameInfo>(json!({
+            "id": "testGameId",
+            "variant": variant.map(|variant| json!({ "key": variant.key() }))
+                .unwrap_or(json!({})),
+            "speed": "blitz",
+            "perf": {},
+            "rated": false,
+            "createdAt": 0,
+            "white": {},
+            "black": {},
+            "initialFen": initial_fen
+        })).unwrap();
+
+        let position = Position::from_game_info(&info).unwrap();
+
+        assert_that!(position.variant()).is_equal_to(variant.unwrap_or(Variant::Standard));
+        assert_that!(position.to_fen()).is_equal_to(expected_fen.to_owned());
+    }
+}
@@ -0,0 +1,302 @@
+use crate::chess::{ChessError, ChessResult, PieceKind, Square};
+use crate::chess::position::{CastlingSide, Position};
+use crate::chess::uci::UciMove;
+
+struct SanPattern {
+    kind: PieceKind,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    to: Square,
+    promotion: Option<PieceKind>
+}
+
+fn parse_castling(san: &str) -> Option<CastlingSide> {
+    match san.replace('0', "O").as_str() {
+        "O-O" => Some(CastlingSide::KingSide),
+        "O-O-O" => Some(CastlingSide::QueenSide),
+        _ => None
+    }
+}
+
+fn parse_drop(san: &str) -> Option<UciMove> {
+    let (kind, to) = san.split_once('@')?;
+    let kind = match kind {
+        "" => PieceKind::Pawn,
+        kind => kind.chars().next().and_then(PieceKind::from_char)
+            .filter(|_| kind.len() == 1)?
+    };
+
+    Some(UciMove::new_drop(kind, to.parse().ok()?))
+}
+
+fn parse_pattern(san: &str) -> Option<SanPattern> {
+    let mut chars = san.chars().collect::<Vec<_>>();
+    let mut promotion = None;
+
+    if let Some(&last) = chars.last() {
+        if last.is_ascii_uppercase() {
+            promotion = Some(PieceKind::from_char(last)?);
+            chars.pop();
+
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+    }
+
+    let kind = match chars.first() {
+        Some(&c) if c.is_ascii_uppercase() => {
+            chars.remove(0);
+            PieceKind::from_char(c)?
+        },
+        _ => PieceKind::Pawn
+    };
+
+    if chars.len() < 2 {
+        return None;
+    }
+
+    let to = chars[chars.len() - 2..].iter().collect::<String>().parse().ok()?;
+    let mut from_file = None;
+    let mut from_rank = None;
+
+    for &c in &chars[..chars.len() - 2] {
+        match c {
+            'a'..='h' => from_file = Some(c as u8 - b'a'),
+            '1'..='8' => from_rank = Some(c as u8 - b'1'),
+            'x' | ':' | '-' => { },
+            _ => return None
+        }
+    }
+
+    Some(SanPattern {
+        kind,
+        from_file,
+        from_rank,
+        to,
+        promotion
+    })
+}
+
+impl Position {
+
+    /// Parses a move in standard algebraic notation (SAN), such as `Nf3`, `exd5`, `O-O`,
+    /// `e8=Q+`, or the Crazyhouse drop `N@f3`, in the context of this position. Check, checkmate,
+    /// and annotation symbols are ignored.
+    ///
+    /// # Errors
+    ///
+    /// * [ChessError::InvalidSan] if the given string is not valid SAN.
+    /// * [ChessError::IllegalMove] if no legal move matches the SAN.
+    /// * [ChessError::AmbiguousSan] if multiple legal moves match the SAN.
+    pub fn parse_san(&self, san: &str) -> ChessResult<UciMove> {
+        let trimmed = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal_moves = self.legal_moves();
+
+        if let Some(side) = parse_castling(trimmed) {
+            return legal_moves.into_iter()
+                .find(|mov| self.castling_side(mov) == Some(side))
+                .ok_or_else(|| ChessError::IllegalMove(san.to_owned()));
+        }
+
+        if trimmed.contains('@') {
+            let mov = parse_drop(trimmed).ok_or_else(|| ChessError::InvalidSan(san.to_owned()))?;
+
+            return legal_moves.into_iter()
+                .find(|&legal_move| legal_move == mov)
+                .ok_or_else(|| ChessError::IllegalMove(san.to_owned()));
+        }
+
+        let pattern = parse_pattern(trimmed).ok_or_else(|| ChessError::InvalidSan(san.to_owned()))?;
+        let mut candidates = legal_moves.into_iter()
+            .filter(|mov| mov.drop.is_none() && self.castling_side(mov).is_none())
+            .filter(|mov| mov.to == pattern.to && mov.promotion == pattern.promotion)
+            .filter(|mov| pattern.from_file.is_none_or(|file| mov.from.file() == file))
+            .filter(|mov| pattern.from_rank.is_none_or(|rank| mov.from.rank() == rank))
+            .filter(|mov| self.piece_at(mov.from).is_some_and(|piece| piece.kind == pattern.kind));
+
+        match (candidates.next(), candidates.next()) {
+            (Some(mov), None) => Ok(mov),
+            (Some(_), Some(_)) => Err(ChessError::AmbiguousSan(san.to_owned())),
+            (None, _) => Err(ChessError::IllegalMove(san.to_owned()))
+        }
+    }
+
+    /// Formats the given move in standard algebraic notation (SAN) in the context of this
+    /// position, including check (`+`) and checkmate (`#`) markers.
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::IllegalMove] if the move is not legal in this position.
+    pub fn to_san(&self, mov: &UciMove) -> ChessResult<String> {
+        if !self.is_legal(mov) {
+            return Err(ChessError::IllegalMove(mov.to_string()));
+        }
+
+        let mut san = match (mov.drop, self.castling_side(mov)) {
+            (Some(kind), _) => format!("{}@{}", kind.to_char().to_ascii_uppercase(), mov.to),
+            (None, Some(CastlingSide::KingSide)) => "O-O".to_owned(),
+            (None, Some(CastlingSide::QueenSide)) => "O-O-O".to_owned(),
+            (None, None) => self.to_san_without_suffix(mov)
+        };
+
+        let mut after = self.clone();
+        after.apply(mov);
+
+        if after.is_checkmate() {
+            san.push('#');
+        }
+        else if after.is_check() {
+            san.push('+');
+        }
+
+        Ok(san)
+    }
+
+    fn to_san_without_suffix(&self, mov: &UciMove) -> String {
+        let kind = self.piece_at(mov.from).map(|piece| piece.kind).unwrap_or(PieceKind::Pawn);
+        let is_capture = self.is_capture(mov);
+        let mut san = String::new();
+
+        if kind == PieceKind::Pawn {
+            if is_capture {
+                san.push((b'a' + mov.from.file()) as char);
+            }
+        }
+        else {
+            san.push(kind.to_char().to_ascii_uppercase());
+
+            let others = self.legal_moves().into_iter()
+                .filter(|other| other.to == mov.to && other.from != mov.from)
+                .filter(|other| other.drop.is_none() && self.castling_side(other).is_none())
+                .filter(|other| self.piece_at(other.from).is_some_and(|piece| piece.kind == kind))
+                .collect::<Vec<_>>();
+
+            if !others.is_empty() {
+                let file_is_unique =
+                    others.iter().all(|other| other.from.file() != mov.from.file());
+                let rank_is_unique =
+                    others.iter().all(|other| other.from.rank() != mov.from.rank());
+
+                if file_is_unique {
+                    san.push((b'a' + mov.from.file()) as char);
+                }
+                else if rank_is_unique {
+                    san.push((b'1' + mov.from.rank()) as char);
+                }
+                else {
+                    san.push_str(&mov.from.to_string());
+                }
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+
+        san.push_str(&mov.to.to_string());
+
+        if let Some(promotion) = mov.promotion {
+            san.push('=');
+            san.push(promotion.to_char().to_ascii_uppercase());
+        }
+
+        san
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::chess::position::STANDARD_FEN;
+    use crate::model::game::Variant;
+
+    use super::*;
+
+    const KIWIPETE_FEN: &str =
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[rstest]
+    #[case::pawn_push(STANDARD_FEN, "e4", "e2e4")]
+    #[case::knight(STANDARD_FEN, "Nf3", "g1f3")]
+    #[case::with_check_suffix(STANDARD_FEN, "Nc3+", "b1c3")]
+    #[case::king_side_castling(KIWIPETE_FEN, "O-O", "e1g1")]
+    #[case::queen_side_castling(KIWIPETE_FEN, "0-0-0", "e1c1")]
+    #[case::pawn_capture(KIWIPETE_FEN, "dxe6", "d5e6")]
+    #[case::piece_capture(KIWIPETE_FEN, "Qxf6", "f3f6")]
+    #[case::file_disambiguation(KIWIPETE_FEN, "Ncb5", "c3b5")]
+    #[case::promotion("8/P6k/8/8/8/8/8/K7 w - - 0 1", "a8=Q", "a7a8q")]
+    #[case::promotion_without_equals("8/P6k/8/8/8/8/8/K7 w - - 0 1", "a8N", "a7a8n")]
+    fn parse_san_works(#[case] fen: &str, #[case] san: &str, #[case] expected_uci: &str) {
+        let position = Position::from_fen(fen).unwrap();
+
+        let mov = position.parse_san(san).unwrap();
+
+        assert_that!(mov.to_string()).is_equal_to(expected_uci.to_owned());
+    }
+
+    #[rstest]
+    #[case::invalid(STANDARD_FEN, "Zf3")]
+    #[case::illegal(STANDARD_FEN, "e5")]
+    #[case::ambiguous("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "Rd1")]
+    #[case::castling_not_allowed(STANDARD_FEN, "O-O")]
+    fn parse_san_fails(#[case] fen: &str, #[case] san: &str) {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_that!(position.parse_san(san)).is_err();
+    }
+
+    #[rstest]
+    #[case::pawn_push(STANDARD_FEN, "e2e4", "e4")]
+    #[case::castling(KIWIPETE_FEN, "e1c1", "O-O-O")]
+    #[case::capture(KIWIPETE_FEN, "e5f7", "Nxf7")]
+    #[case::rank_disambiguation("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3", "R1a3")]
+    #[case::check("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", "Ra8+")]
+    #[case::checkmate("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", "Ra8#")]
+    #[case::en_passant("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "exd6")]
+    fn to_san_works(#[case] fen: &str, #[case] uci: &str, #[case] expected_san: &str) {
+        let position = Position::from_fen(fen).unwrap();
+
+        let san = position.to_san(&uci.parse().unwrap()).unwrap();
+
+        assert_that!(san).is_equal_to(expected_san.to_owned());
+    }
+
+    const CRAZYHOUSE_FEN: &str =
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[Np] w KQkq - 2 3";
+
+    #[rstest]
+    #[case::piece_drop("N@d4", "N@d4")]
+    #[case::pawn_drop("P@d6", "P@d6")]
+    #[case::pawn_drop_without_letter("@d6", "P@d6")]
+    fn parse_san_drop(#[case] san: &str, #[case] expected_uci: &str) {
+        let fen = CRAZYHOUSE_FEN.replace("[Np] w", "[NPp] w");
+        let position = Position::from_fen_with_variant(&fen, Variant::Crazyhouse).unwrap();
+
+        let mov = position.parse_san(san).unwrap();
+
+        assert_that!(mov.to_string()).is_equal_to(expected_uci.to_owned());
+    }
+
+    #[test]
+    fn parse_san_rejects_drop_of_piece_not_in_pocket() {
+        let position = Position::from_fen_with_variant(CRAZYHOUSE_FEN, Variant::Crazyhouse)
+            .unwrap();
+
+        assert_that!(position.parse_san("Q@d4")).is_err();
+    }
+
+    #[test]
+    fn to_san_formats_drop_with_check() {
+        let position = Position::from_fen_with_variant(CRAZYHOUSE_FEN, Variant::Crazyhouse)
+            .unwrap();
+
+        let san = position.to_san(&"N@d6".parse().unwrap()).unwrap();
+
+        assert_that!(san).is_equal_to("N@d6+".to_owned());
+    }
+}
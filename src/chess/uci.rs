@@ -0,0 +1,198 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error as DeserializeError;
+
+use crate::chess::{ChessError, ChessResult, PieceKind, Square};
+
+/// A chess move in UCI notation, such as `e2e4` or `e7e8q`. Castling is represented either by the
+/// king moving two squares (`e1g1`) or by the king moving onto the castling rook (`e1h1`). Drops
+/// in Crazyhouse are written as the uppercase piece letter, `@`, and the target square (`N@f3`).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct UciMove {
+
+    /// The square from which the piece moves.
+    pub from: Square,
+
+    /// The square to which the piece moves.
+    pub to: Square,
+
+    /// The piece kind a pawn is promoted to, if this is a promotion.
+    pub promotion: Option<PieceKind>,
+
+    /// The kind of piece dropped from the pocket onto `to`, if this is a Crazyhouse drop. For
+    /// drops, `from` is equal to `to`.
+    pub drop: Option<PieceKind>
+}
+
+impl UciMove {
+
+    /// Creates a new move without promotion.
+    pub fn new(from: Square, to: Square) -> UciMove {
+        UciMove {
+            from,
+            to,
+            promotion: None,
+            drop: None
+        }
+    }
+
+    /// Creates a new move which drops a piece of the given kind onto the given square.
+    pub fn new_drop(kind: PieceKind, to: Square) -> UciMove {
+        UciMove {
+            from: to,
+            to,
+            promotion: None,
+            drop: Some(kind)
+        }
+    }
+}
+
+impl Display for UciMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(kind) = self.drop {
+            return write!(f, "{}@{}", kind.to_char().to_ascii_uppercase(), self.to);
+        }
+
+        write!(f, "{}{}", self.from, self.to)?;
+
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_char())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for UciMove {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> ChessResult<UciMove> {
+        let invalid = || ChessError::InvalidUciMove(s.to_owned());
+
+        if !s.is_ascii() || !(4..=5).contains(&s.len()) {
+            return Err(invalid());
+        }
+
+        if s.len() == 4 && &s[1..2] == "@" {
+            let kind = s[0..1].chars().next()
+                .and_then(PieceKind::from_char)
+                .filter(|&kind| kind != PieceKind::King)
+                .ok_or_else(invalid)?;
+            let to = s[2..4].parse().map_err(|_| invalid())?;
+
+            return Ok(UciMove::new_drop(kind, to));
+        }
+
+        let from = s[0..2].parse().map_err(|_| invalid())?;
+        let to = s[2..4].parse().map_err(|_| invalid())?;
+        let promotion = match s[4..].chars().next() {
+            Some(c) => match PieceKind::from_char(c) {
+                Some(PieceKind::Pawn) | None => return Err(invalid()),
+                promotion => promotion
+            },
+            None => None
+        };
+
+        Ok(UciMove {
+            from,
+            to,
+            promotion,
+            drop: None
+        })
+    }
+}
+
+impl Serialize for UciMove {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for UciMove {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(DeserializeError::custom)
+    }
+}
+
+/// Parses a space-separated list of moves in UCI notation, as it is provided by
+/// [GameStateEvent::moves](crate::model::game::event::GameStateEvent::moves).
+///
+/// # Errors
+///
+/// [ChessError::InvalidUciMove] if any of the moves is not valid UCI notation.
+pub fn parse_uci_moves(moves: &str) -> ChessResult<Vec<UciMove>> {
+    moves.split_whitespace()
+        .map(UciMove::from_str)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::simple("e2e4")]
+    #[case::promotion("e7e8q")]
+    #[case::under_promotion("b2a1n")]
+    #[case::pawn_drop("P@e4")]
+    #[case::knight_drop("N@f3")]
+    fn uci_move_round_trip(#[case] uci: &str) {
+        let mov = uci.parse::<UciMove>().unwrap();
+
+        assert_that!(mov.to_string()).is_equal_to(uci.to_owned());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::too_short("e2e")]
+    #[case::too_long("e7e8qq")]
+    #[case::invalid_square("e9e4")]
+    #[case::pawn_promotion("e7e8p")]
+    #[case::invalid_promotion("e7e8x")]
+    #[case::king_drop("K@e4")]
+    #[case::invalid_drop_square("P@e9")]
+    fn parse_invalid_uci_move(#[case] uci: &str) {
+        assert_that!(uci.parse::<UciMove>()).is_err();
+    }
+
+    #[test]
+    fn parse_drop_move() {
+        let mov = "Q@d5".parse::<UciMove>().unwrap();
+        let d5 = "d5".parse().unwrap();
+
+        assert_that!(mov).is_equal_to(UciMove::new_drop(PieceKind::Queen, d5));
+    }
+
+    #[test]
+    fn parse_uci_moves_splits_on_whitespace() {
+        let moves = parse_uci_moves("e2e4 e7e5  g1f3").unwrap();
+
+        assert_that!(moves).contains_exactly_in_given_order([
+            "e2e4".parse::<UciMove>().unwrap(),
+            "e7e5".parse::<UciMove>().unwrap(),
+            "g1f3".parse::<UciMove>().unwrap()
+        ]);
+    }
+
+    #[test]
+    fn uci_move_is_serialized_as_uci_notation() {
+        let mov = "e7e8q".parse::<UciMove>().unwrap();
+        let json = serde_json::to_string(&mov).unwrap();
+
+        assert_that!(json.as_str()).is_equal_to("\"e7e8q\"");
+        assert_that!(serde_json::from_str::<UciMove>(&json).unwrap()).is_equal_to(mov);
+        assert_that!(serde_json::from_str::<UciMove>("\"e9e8\"")).is_err();
+    }
+
+    #[test]
+    fn parse_uci_moves_accepts_empty_list() {
+        assert_that!(parse_uci_moves("").unwrap()).is_empty();
+    }
+}
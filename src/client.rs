@@ -1,26 +1,281 @@
-use std::sync::Arc;
-
-use reqwest::{Client, ClientBuilder, Method, Response};
-use reqwest::header::{AUTHORIZATION, HeaderMap};
+use std::env;
+use std::fmt::{self, Debug, Formatter};
+use std::fs;
+use std::hint;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::{Stream, StreamExt};
+
+use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+use ndjson_stream::fallible::FallibleNdjsonError;
+
+use reqwest::{Body, Client, ClientBuilder, Method, Request, RequestBuilder, Response, StatusCode};
+use reqwest::header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue};
 use reqwest::Result as ReqwestResult;
 
-use serde::Serialize;
-
-use crate::error::{BotClientBuilderError, BotClientBuilderResult, LibotRequestError, LibotResult};
-use crate::model::{Move, Seconds};
-use crate::model::challenge::{Challenges, DeclineReason};
-use crate::model::game::chat::{ChatHistory, ChatRoom};
-use crate::model::game::GameId;
-use crate::model::request::{DeclineRequest, SendChatMessageRequest};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+use serde_json::Value as JsonValue;
+
+use crate::chat_i18n::ChatLocalizer;
+use crate::chat_throttle::{ChatReservation, ChatThrottle, ChatThrottleConfig};
+use crate::config::BotConfig;
+use crate::connection::{ConnectionConfig, EndpointCategory, RequestTimeouts};
+use crate::dispatch::{DispatchConfig, Dispatcher};
+use crate::error::{
+    BotClientBuilderError,
+    BotClientBuilderResult,
+    ChallengeAcceptError,
+    LibotRequestError,
+    LibotResult
+};
+use crate::model::{Move, Seconds, Timestamp};
+use crate::model::bot_event::BotEvent;
+use crate::model::broadcast::BroadcastPush;
+use crate::model::challenge::{
+    Challenge,
+    ChallengeColor,
+    ChallengeOutcome,
+    ChallengeRequest,
+    Challenges,
+    ChallengeStatus,
+    DeclineReason
+};
+use crate::model::cloud_eval::CloudEvaluation;
+use crate::model::external_engine::{ExternalEngine, ExternalEngineRegistration};
+use crate::model::game::chat::{ChatHistory, ChatMarker, ChatRoom, NewChatLines};
+use crate::model::game::event::GameEvent;
+use crate::model::game::export::ExportedGame;
+use crate::model::game::{self, Color, GameId, GIF_URL, SITE_URL, Variant};
+use crate::model::game::ongoing::{OngoingGame, OngoingGames};
+use crate::model::request::{
+    DeclineRequest,
+    KeepAliveChallengeRequest,
+    SeekRequest,
+    SendChatMessageRequest,
+    SendTeamMessageRequest
+};
+use crate::model::user::crosstable::Crosstable;
 use crate::model::user::preferences::UserPreferences;
-use crate::model::user::UserProfile;
+use crate::model::tournament::Tournament;
+use crate::model::tv::TvChannels;
+use crate::model::user::{Rating, UserId, UserProfile};
+use crate::rate_limit::RateLimitInfo;
+use crate::transport::{HttpTransport, ReqwestTransport};
+
+/// The family of Lichess API endpoints through which a [BotClient] plays games.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ApiMode {
+
+    /// Uses the bot API (`/bot/...`), which requires a token of a bot account.
+    #[default]
+    Bot,
+
+    /// Uses the board API (`/board/...`), which allows playing with a regular account, e.g. for
+    /// casual automation or testing with board-only tokens. Note that the Lichess terms of
+    /// service restrict which kinds of games may be played this way.
+    Board
+}
+
+impl ApiMode {
+
+    pub(crate) fn prefix(self) -> &'static str {
+        match self {
+            ApiMode::Bot => "/bot",
+            ApiMode::Board => "/board"
+        }
+    }
+}
+
+/// The outcome of a move submitted with
+/// [GameContext::submit_move](crate::context::GameContext::submit_move).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MoveOutcome {
+
+    /// Lichess accepted the submitted move.
+    Applied,
+
+    /// A submission failed, but the game state shows that Lichess registered the move, e.g.
+    /// because an earlier request timed out after the move was processed.
+    AlreadyApplied,
+
+    /// The move was not registered, because it was invalid, Lichess refused it or another move
+    /// was registered instead.
+    Rejected
+}
 
 /// The Lichess API client to use for a bot. Each method call on this client represents a coll to
 /// one Lichess API endpoint.
 #[derive(Clone, Debug)]
 pub struct BotClient {
     client: Client,
-    base_url: Arc<str>
+    transport: Arc<dyn HttpTransport>,
+    authorization: HeaderValue,
+    timeouts: RequestTimeouts,
+    dispatcher: Option<Arc<Dispatcher>>,
+    base_url: Arc<str>,
+    gif_base_url: Arc<str>,
+    site_base_url: Arc<str>,
+    api_mode: ApiMode,
+    chat_throttle: Option<Arc<ChatThrottle>>,
+    chat_localizer: Option<Arc<ChatLocalizer>>,
+    rate_limit: Arc<Mutex<Option<RateLimitInfo>>>
+}
+
+/// A response to a request sent with [BotClient::send_request_raw]. Unlike the other methods of
+/// the client, responses with an unsuccessful status are returned as well.
+#[derive(Clone, Debug)]
+pub struct RawResponse {
+
+    /// The HTTP status of the response.
+    pub status: StatusCode,
+
+    /// The HTTP headers of the response.
+    pub headers: HeaderMap,
+
+    /// The body of the response as it was received.
+    pub body: Vec<u8>
+}
+
+impl RawResponse {
+
+    /// The body of the response as text, replacing invalid UTF-8 sequences.
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    /// Deserializes the body of the response from JSON.
+    ///
+    /// # Errors
+    ///
+    /// [LibotRequestError::JsonError] if the body is not valid JSON of the given type.
+    pub fn json<T: DeserializeOwned>(&self) -> LibotResult<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Parses the rate-limit related headers of the response, as in
+    /// [RateLimitInfo::from_response].
+    pub fn rate_limit_info(&self) -> Option<RateLimitInfo> {
+        RateLimitInfo::from_response(self.status, &self.headers)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChallengeStreamLine {
+    Done {
+        done: ChallengeStatus
+    },
+    Challenge(Box<Challenge>)
+}
+
+type ChallengeStream = Pin<Box<dyn Stream<Item = LibotResult<ChallengeStreamLine>> + Send>>;
+
+/// A challenge created with [BotClient::create_challenge_kept_alive]. Lichess keeps the challenge
+/// open as long as the connection which created it is open, i.e. until this is dropped or the
+/// challenge is answered. Dropping it before cancels the challenge.
+pub struct KeptAliveChallenge {
+    challenge: Challenge,
+    stream: ChallengeStream,
+    client: BotClient
+}
+
+impl KeptAliveChallenge {
+
+    /// The created [Challenge], as initially reported by Lichess.
+    pub fn challenge(&self) -> &Challenge {
+        &self.challenge
+    }
+
+    /// Waits until the challenge is accepted, declined, or canceled. For declined challenges, the
+    /// reason is queried with [BotClient::get_challenge], since it is not part of the stream.
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::ChallengeStreamEnded] if the connection was closed before the
+    ///   challenge was answered.
+    /// * [LibotRequestError::ReqwestError] or [LibotRequestError::JsonError] if receiving or
+    ///   parsing the stream failed.
+    pub async fn outcome(mut self) -> LibotResult<ChallengeOutcome> {
+        let challenge_id = self.challenge.id;
+
+        while let Some(line) = self.stream.next().await {
+            let status = match line? {
+                ChallengeStreamLine::Done { done } => done,
+                ChallengeStreamLine::Challenge(_) => continue
+            };
+
+            match status {
+                ChallengeStatus::Created => { },
+                ChallengeStatus::Accepted => return Ok(ChallengeOutcome::Accepted(challenge_id)),
+                ChallengeStatus::Declined => {
+                    let reason = self.client.get_challenge(challenge_id).await.ok()
+                        .and_then(|challenge| challenge.decline)
+                        .map(|decline| decline.key)
+                        .unwrap_or(DeclineReason::Generic);
+
+                    return Ok(ChallengeOutcome::Declined(reason));
+                },
+                ChallengeStatus::Canceled | ChallengeStatus::Offline =>
+                    return Ok(ChallengeOutcome::Canceled)
+            }
+        }
+
+        Err(LibotRequestError::ChallengeStreamEnded {
+            challenge_id: Some(challenge_id)
+        })
+    }
+}
+
+impl Debug for KeptAliveChallenge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeptAliveChallenge")
+            .field("challenge", &self.challenge)
+            .finish_non_exhaustive()
+    }
+}
+
+type SeekStream = Pin<Box<dyn Stream<Item = LibotResult<()>> + Send>>;
+
+/// A seek created with [BotClient::create_seek]. Lichess keeps the seek open as long as the
+/// connection which created it is open, i.e. until this is dropped or a game is started with it.
+/// Dropping it before cancels the seek.
+pub struct Seek {
+    stream: SeekStream
+}
+
+impl Seek {
+
+    /// Waits until Lichess closes the seek, which happens once another player accepted it and the
+    /// game was started. The game itself is reported by a `gameStart` event on the event stream.
+    ///
+    /// # Errors
+    ///
+    /// [LibotRequestError::ReqwestError] if the connection failed before the seek was closed.
+    pub async fn closed(mut self) -> LibotResult<()> {
+        while let Some(chunk) = self.stream.next().await {
+            chunk?;
+        }
+
+        Ok(())
+    }
+
+    /// Cancels the seek by closing the connection which keeps it open. This is equivalent to
+    /// dropping it.
+    pub fn cancel(self) { }
+}
+
+impl Debug for Seek {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Seek").finish_non_exhaustive()
+    }
 }
 
 pub(crate) fn join_url(base_url: &str, path: &str) -> String {
@@ -38,7 +293,7 @@ pub(crate) fn join_url(base_url: &str, path: &str) -> String {
     url
 }
 
-async fn handle_error(response: ReqwestResult<Response>) -> LibotResult<Response> {
+pub(crate) async fn handle_error(response: ReqwestResult<Response>) -> LibotResult<Response> {
     let response = response?;
 
     if !response.status().is_success() {
@@ -55,34 +310,230 @@ async fn handle_error(response: ReqwestResult<Response>) -> LibotResult<Response
     Ok(response)
 }
 
+/// Creates a request body which lists the given IDs separated by commas, as expected by the bulk
+/// endpoints of Lichess. The body is streamed, so the IDs are never joined into a single string.
+fn id_list_body<I>(ids: I) -> Body
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+    I::IntoIter: Send + Sync + 'static
+{
+    let chunks = ids.into_iter()
+        .enumerate()
+        .map(|(index, id)| {
+            let id = id.into();
+            let chunk = if index == 0 { id } else { format!(",{id}") };
+
+            Ok::<_, Infallible>(chunk)
+        });
+
+    Body::wrap_stream(futures::stream::iter(chunks))
+}
+
+/// Parses the body of the given response as a stream of NDJSON values, ignoring empty lines,
+/// which Lichess sends to keep the connection alive.
+fn parse_ndjson_response<T>(response: Response) -> impl Stream<Item = LibotResult<T>>
+where
+    T: DeserializeOwned
+{
+    let config = NdjsonConfig::default()
+        .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+    let stream = ndjson_stream::from_fallible_stream_with_config::<T, _>(
+        response.bytes_stream(), config);
+
+    stream.map(|result| result.map_err(|error| match error {
+        FallibleNdjsonError::InputError(error) => LibotRequestError::from(error),
+        FallibleNdjsonError::JsonError(error) => LibotRequestError::from(error)
+    }))
+}
+
+fn classify_accept_error(error: LibotRequestError) -> LibotRequestError {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        error: String
+    }
+
+    let LibotRequestError::ApiError { status, body, .. } = &error
+    else {
+        return error;
+    };
+    let message = body.as_deref()
+        .and_then(|body| serde_json::from_str::<ErrorBody>(body).ok())
+        .map(|body| body.error.to_lowercase())
+        .unwrap_or_default();
+
+    match *status {
+        StatusCode::NOT_FOUND => ChallengeAcceptError::NotFound.into(),
+        StatusCode::TOO_MANY_REQUESTS => ChallengeAcceptError::RateLimited.into(),
+        StatusCode::BAD_REQUEST if message.contains("accepted") =>
+            ChallengeAcceptError::AlreadyAccepted.into(),
+        _ => error
+    }
+}
+
 impl BotClient {
 
+    /// The [ApiMode] which determines the endpoints used to play games.
+    pub fn api_mode(&self) -> ApiMode {
+        self.api_mode
+    }
+
+    /// Prepends the prefix of the [ApiMode] of this client to the given game path, such as
+    /// `/game/{id}/move/{move}`.
+    pub(crate) fn game_path(&self, path: &str) -> String {
+        format!("{}{path}", self.api_mode.prefix())
+    }
+
+    pub(crate) fn chat_game_finished(&self, game_id: &GameId) {
+        if let Some(chat_throttle) = &self.chat_throttle {
+            chat_throttle.game_finished(game_id);
+        }
+    }
+
+    /// The rate-limit related information of the most recent response which contained any, i.e.
+    /// which had rate-limit related headers or a `429 Too Many Requests` status. This is shared
+    /// between all clones of this client.
+    pub fn rate_limit_info(&self) -> Option<RateLimitInfo> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    fn record_rate_limit(&self, response: &Response) {
+        if let Some(info) = RateLimitInfo::from_response(response.status(), response.headers()) {
+            *self.rate_limit.lock().unwrap() = Some(info);
+        }
+    }
+
+    /// Sends the given request through the [HttpTransport] of this client, after adding the
+    /// authorization header and the timeout of the given [EndpointCategory] and waiting for the
+    /// dispatcher to admit it, and records the rate-limit information of the response.
+    async fn send_in_category(&self, mut request: Request, category: EndpointCategory)
+            -> LibotResult<Response> {
+        *request.timeout_mut() = self.timeouts.timeout(category);
+        request.headers_mut().insert(AUTHORIZATION, self.authorization.clone());
+        let permit = match &self.dispatcher {
+            Some(dispatcher) => dispatcher.acquire(category).await,
+            None => None
+        };
+        let response = self.transport.execute(request).await?;
+        drop(permit);
+        self.record_rate_limit(&response);
+
+        Ok(response)
+    }
+
+    async fn send(&self, request: RequestBuilder) -> LibotResult<Response> {
+        let request = request.build()?;
+        let category = EndpointCategory::of(request.method(), request.url().path());
+
+        self.send_in_category(request, category).await
+    }
+
+    async fn execute(&self, request: RequestBuilder) -> LibotResult<Response> {
+        let response = self.send(request).await?;
+
+        handle_error(Ok(response)).await
+    }
+
     pub(crate) async fn send_request(&self, method: Method, path: &str)
             -> LibotResult<Response> {
         let url = join_url(&self.base_url, path);
 
-        handle_error(self.client.request(method, url).send().await).await
+        self.execute(self.client.request(method, url)).await
     }
 
     pub(crate) async fn send_request_with_body(&self, method: Method, path: &str,
             body: impl Serialize) -> LibotResult<Response> {
         let url = join_url(&self.base_url, path);
 
-        handle_error(self.client.request(method, url).json(&body).send().await).await
+        self.execute(self.client.request(method, url).json(&body)).await
     }
 
     pub(crate) async fn send_request_with_form(&self, method: Method, path: &str,
             form: impl Serialize) -> LibotResult<Response> {
         let url = join_url(&self.base_url, path);
 
-        handle_error(self.client.request(method, url).form(&form).send().await).await
+        self.execute(self.client.request(method, url).form(&form)).await
     }
 
     pub(crate) async fn send_request_with_query(&self, method: Method, path: &str,
             query: impl Serialize) -> LibotResult<Response> {
         let url = join_url(&self.base_url, path);
 
-        handle_error(self.client.request(method, url).query(&query).send().await).await
+        self.execute(self.client.request(method, url).query(&query)).await
+    }
+
+    /// Sends a `POST` request whose body lists the given IDs separated by commas and which accepts
+    /// an NDJSON response. The body is streamed, so huge sets of IDs do not have to be joined into
+    /// a single string first.
+    pub(crate) async fn send_request_with_id_list<I>(&self, path: &str, query: impl Serialize,
+            ids: I) -> LibotResult<Response>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+        I::IntoIter: Send + Sync + 'static
+    {
+        let url = join_url(&self.base_url, path);
+        let request = self.client.post(url)
+            .query(&query)
+            .header(ACCEPT, "application/x-ndjson")
+            .body(id_list_body(ids));
+
+        self.execute(request).await
+    }
+
+    /// Opens a connection to the Lichess API, which is then kept in the connection pool for
+    /// subsequent requests, so the first move of a game does not have to wait for a TCP and TLS
+    /// handshake. This is useful before the first bullet game starts, in particular together with
+    /// [ConnectionConfig::low_latency], which keeps the connection open while idle. The response
+    /// status is irrelevant, so this only fails if no connection could be established.
+    ///
+    /// # Errors
+    ///
+    /// [LibotRequestError::ReqwestError] if the request could not be sent.
+    pub async fn prewarm(&self) -> LibotResult<()> {
+        self.send_request_raw(Method::HEAD, "/", None).await?;
+        Ok(())
+    }
+
+    /// Sends an authenticated request to an arbitrary Lichess API endpoint, as an escape hatch for
+    /// endpoints not covered by this client or for custom pacing based on the response headers.
+    /// The rate-limit information of the response is recorded as for all other requests (see
+    /// [BotClient::rate_limit_info]).
+    ///
+    /// # Arguments
+    ///
+    /// * `method`: The HTTP method of the request.
+    /// * `path`: The path of the endpoint relative to the base URL, such as `/account`.
+    /// * `body`: The body of the request, which is sent as-is, or [None] to send no body.
+    ///
+    /// # Returns
+    ///
+    /// The status, headers, and body of the response, regardless of whether the status indicates
+    /// success.
+    ///
+    /// # Errors
+    ///
+    /// [LibotRequestError::ReqwestError] if the request could not be sent or the body could not be
+    /// received.
+    pub async fn send_request_raw(&self, method: Method, path: &str, body: Option<String>)
+            -> LibotResult<RawResponse> {
+        let url = join_url(&self.base_url, path);
+        let mut request = self.client.request(method, url);
+
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let response = self.send(request).await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?.to_vec();
+
+        Ok(RawResponse {
+            status,
+            headers,
+            body
+        })
     }
 
     /// Queries a list of all pending challenges created by or targeted at the bot.
@@ -95,11 +546,19 @@ impl BotClient {
     /// # Arguments
     ///
     /// * `challenge_id`: The ID of the challenge to accept.
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::ChallengeAcceptError] if Lichess refused to accept the challenge for
+    ///   a known reason, such as the challenge having been cancelled (see [ChallengeAcceptError]).
+    /// * Any other [LibotRequestError] that occurs while sending the request.
     pub async fn accept_challenge(&self, challenge_id: GameId) -> LibotResult<()> {
         let path = format!("/challenge/{challenge_id}/accept");
-        self.send_request(Method::POST, &path).await?;
 
-        Ok(())
+        match self.send_request(Method::POST, &path).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(classify_accept_error(error))
+        }
     }
 
     /// Declines the challenge with the given ID. A reason why the challenge was declined can be
@@ -109,7 +568,7 @@ impl BotClient {
     ///
     /// * `challenge_id`: The ID of the challenge to decline.
     /// * `reason`: If present, this reason why the challenge was declined will be provided to the
-    /// challenger.
+    ///   challenger.
     pub async fn decline_challenge(&self, challenge_id: GameId, reason: Option<DeclineReason>)
             -> LibotResult<()> {
         let path = format!("/challenge/{challenge_id}/decline");
@@ -121,6 +580,144 @@ impl BotClient {
         Ok(())
     }
 
+    /// Challenges the user with the given name to a game. The request is built and validated using
+    /// a [ChallengeBuilder](crate::model::challenge::ChallengeBuilder).
+    ///
+    /// # Arguments
+    ///
+    /// * `username`: The name of the user to challenge.
+    /// * `request`: The settings of the game to which to challenge the user.
+    ///
+    /// # Returns
+    ///
+    /// The created [Challenge].
+    pub async fn create_challenge(&self, username: &str, request: &ChallengeRequest)
+            -> LibotResult<Challenge> {
+        let path = format!("/challenge/{username}");
+
+        Ok(self.send_request_with_form(Method::POST, &path, request).await?.json().await?)
+    }
+
+    /// Challenges the user with the given name to a game like [BotClient::create_challenge], but
+    /// keeps the challenge open until it is answered, instead of letting it expire after a short
+    /// time. Lichess streams the outcome of the challenge on the same connection, which can be
+    /// awaited with [KeptAliveChallenge::outcome].
+    ///
+    /// # Arguments
+    ///
+    /// * `username`: The name of the user to challenge.
+    /// * `request`: The settings of the game to which to challenge the user.
+    ///
+    /// # Returns
+    ///
+    /// The created challenge, which is canceled when dropped before it is answered.
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::ChallengeStreamEnded] if the connection was closed before the
+    ///   challenge was reported.
+    /// * Any other [LibotRequestError] that occurs while creating the challenge or receiving it.
+    pub async fn create_challenge_kept_alive(&self, username: &str, request: &ChallengeRequest)
+            -> LibotResult<KeptAliveChallenge> {
+        let path = format!("/challenge/{username}");
+        let form = KeepAliveChallengeRequest {
+            request,
+            keep_alive_stream: true
+        };
+        let url = join_url(&self.base_url, &path);
+        let request = self.client.post(url).form(&form).build()?;
+        let response = self.send_in_category(request, EndpointCategory::Stream).await?;
+        let response = handle_error(Ok(response)).await?;
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+        let stream = ndjson_stream::from_fallible_stream_with_config::<ChallengeStreamLine, _>(
+            response.bytes_stream(), config);
+        let mut stream: ChallengeStream = Box::pin(stream.map(|result|
+            result.map_err(|error| match error {
+                FallibleNdjsonError::InputError(error) => LibotRequestError::from(error),
+                FallibleNdjsonError::JsonError(error) => LibotRequestError::from(error)
+            })));
+
+        while let Some(line) = stream.next().await {
+            if let ChallengeStreamLine::Challenge(challenge) = line? {
+                return Ok(KeptAliveChallenge {
+                    challenge: *challenge,
+                    stream,
+                    client: self.clone()
+                });
+            }
+        }
+
+        Err(LibotRequestError::ChallengeStreamEnded {
+            challenge_id: None
+        })
+    }
+
+    /// Creates a public seek for a real-time game, which is paired with a random player whose seek
+    /// or preferences match. This is only available for accounts using the board API (see
+    /// [ApiMode::Board]), since bot accounts cannot seek. The seek stays open until a game is
+    /// started with it or the returned [Seek] is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The initial time on the clock in seconds. Lichess only accepts certain values,
+    ///   such as 15, 30, 45, or any number of full minutes up to 180.
+    /// * `increment`: The clock increment per move in seconds.
+    /// * `rated`: Whether the game should be rated.
+    /// * `variant`: The variant of the game.
+    /// * `color`: The color the account wants to play.
+    /// * `rating_range`: If present, only players whose rating lies in this range are paired.
+    ///
+    /// # Returns
+    ///
+    /// The created seek, which is canceled when dropped before a game is started.
+    pub async fn create_seek(&self, time: Seconds, increment: Seconds, rated: bool,
+            variant: Variant, color: ChallengeColor, rating_range: Option<RangeInclusive<Rating>>)
+            -> LibotResult<Seek> {
+        let form = SeekRequest {
+            rated,
+            time: time as f64 / 60.0,
+            increment,
+            variant: variant.key(),
+            color,
+            rating_range: rating_range.map(|range| format!("{}-{}", range.start(), range.end()))
+        };
+        let response = self.send_request_with_form(Method::POST, "/board/seek", form).await?;
+        let stream = response.bytes_stream()
+            .map(|chunk| chunk.map(|_| ()).map_err(LibotRequestError::from));
+
+        Ok(Seek {
+            stream: Box::pin(stream)
+        })
+    }
+
+    /// Downloads an animated GIF of the game with the given ID, e.g. to share it after the game.
+    /// See also [GameInfo::gif_url](crate::model::game::GameInfo::gif_url).
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game to render.
+    /// * `color`: The color from whose perspective the board is rendered, or [None] for White.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the GIF.
+    pub async fn get_game_gif(&self, game_id: GameId, color: Option<Color>)
+            -> LibotResult<Vec<u8>> {
+        let url = join_url(&self.gif_base_url, &game::gif_path(&game_id, color));
+        let response = self.execute(self.client.get(url)).await?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Queries the challenge with the given ID, which must have been created by or targeted at
+    /// the bot.
+    pub async fn get_challenge(&self, challenge_id: GameId) -> LibotResult<Challenge> {
+        let path = format!("/challenge/{challenge_id}/show");
+
+        Ok(self.send_request(Method::GET, &path).await?.json().await?)
+    }
+
     /// Makes the given move in the game with the given ID. Additionally, it is possible to offer a
     /// draw or accept a pending draw offer by setting the `offer_draw` flag. This is equivalent to
     /// calling [BotClient::offer_or_accept_draw] at the same time.
@@ -130,6 +727,10 @@ impl BotClient {
     /// * `game_id`: The ID of the game in which to play a move.
     /// * `mov`: The move to play.
     /// * `offer_draw`: If `true`, the bot will offer a draw or accept a pending draw offer.
+    ///
+    /// The move is submitted as is. Bots run by a [BotRunner](crate::runner::BotRunner) can use
+    /// [GameContext::make_move](crate::context::GameContext::make_move) instead to validate it
+    /// against the tracked position of the game first.
     pub async fn make_move(&self, game_id: GameId, mov: Move, offer_draw: bool) -> LibotResult<()> {
         #[derive(Serialize)]
         struct OfferDraw {
@@ -137,7 +738,7 @@ impl BotClient {
             offer_draw: bool
         }
 
-        let path = format!("/bot/game/{game_id}/move/{mov}");
+        let path = self.game_path(&format!("/game/{game_id}/move/{mov}"));
         let query = OfferDraw { offer_draw };
 
         self.send_request_with_query(Method::POST, &path, query).await?;
@@ -151,7 +752,7 @@ impl BotClient {
     ///
     /// * `game_id`: The ID of the game to resign.
     pub async fn abort_game(&self, game_id: GameId) -> LibotResult<()> {
-        let path = format!("/bot/game/{game_id}/abort");
+        let path = self.game_path(&format!("/game/{game_id}/abort"));
 
         self.send_request(Method::POST, &path).await?;
 
@@ -164,7 +765,22 @@ impl BotClient {
     ///
     /// * `game_id`: The ID of the game to resign.
     pub async fn resign_game(&self, game_id: GameId) -> LibotResult<()> {
-        let path = format!("/bot/game/{game_id}/resign");
+        let path = self.game_path(&format!("/game/{game_id}/resign"));
+
+        self.send_request(Method::POST, &path).await?;
+
+        Ok(())
+    }
+
+    /// Claims victory in a game in which the opponent left, once the time given by
+    /// [OpponentGoneEvent::claim_win_in_seconds](crate::model::game::event::OpponentGoneEvent)
+    /// has elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game in which to claim victory.
+    pub async fn claim_victory(&self, game_id: GameId) -> LibotResult<()> {
+        let path = self.game_path(&format!("/game/{game_id}/claim-victory"));
 
         self.send_request(Method::POST, &path).await?;
 
@@ -178,7 +794,7 @@ impl BotClient {
     ///
     /// * `game_id`: The ID of the game in which to offer a draw or accept a draw offer.
     pub async fn offer_or_accept_draw(&self, game_id: GameId) -> LibotResult<()> {
-        let path = format!("/bot/game/{game_id}/draw/yes");
+        let path = self.game_path(&format!("/game/{game_id}/draw/yes"));
 
         self.send_request(Method::POST, &path).await?;
 
@@ -191,7 +807,33 @@ impl BotClient {
     ///
     /// * `game_id`: The ID of the game in which to decline a draw offer.
     pub async fn decline_draw(&self, game_id: GameId) -> LibotResult<()> {
-        let path = format!("/bot/game/{game_id}/draw/no");
+        let path = self.game_path(&format!("/game/{game_id}/draw/no"));
+
+        self.send_request(Method::POST, &path).await?;
+
+        Ok(())
+    }
+
+    /// Accepts a pending takeback proposal by the opponent in a game.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game in which to accept a takeback proposal.
+    pub async fn accept_takeback(&self, game_id: GameId) -> LibotResult<()> {
+        let path = self.game_path(&format!("/game/{game_id}/takeback/yes"));
+
+        self.send_request(Method::POST, &path).await?;
+
+        Ok(())
+    }
+
+    /// Declines a pending takeback proposal by the opponent in a game.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game in which to decline a takeback proposal.
+    pub async fn decline_takeback(&self, game_id: GameId) -> LibotResult<()> {
+        let path = self.game_path(&format!("/game/{game_id}/takeback/no"));
 
         self.send_request(Method::POST, &path).await?;
 
@@ -217,11 +859,34 @@ impl BotClient {
     ///
     /// * `game_id`: The ID of the game whose chat history to fetch.
     pub async fn get_game_chat(&self, game_id: GameId) -> LibotResult<ChatHistory> {
-        let path = format!("/bot/game/{game_id}/chat");
+        let path = self.game_path(&format!("/game/{game_id}/chat"));
 
         Ok(self.send_request(Method::GET, &path).await?.json().await?)
     }
 
+    /// Fetches the lines posted in the chat of a given game since a [ChatMarker] was obtained,
+    /// which allows polling the chat without processing the same lines twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game whose new chat lines to fetch.
+    /// * `marker`: A marker returned by a previous call or obtained from a previously fetched
+    ///   [ChatHistory] using [ChatHistory::marker]. Use [ChatMarker::default] to fetch all lines.
+    ///
+    /// # Returns
+    ///
+    /// The [NewChatLines], containing the lines posted since `marker` and a new marker to pass to
+    /// the next call.
+    pub async fn get_new_chat_since(&self, game_id: GameId, marker: ChatMarker)
+            -> LibotResult<NewChatLines> {
+        let history = self.get_game_chat(game_id).await?;
+
+        Ok(NewChatLines {
+            lines: history.lines_since(marker).to_vec(),
+            marker: history.marker()
+        })
+    }
+
     /// Sends a chat message in a game chat as the user as which this bot is authenticated.
     ///
     /// # Arguments
@@ -229,12 +894,34 @@ impl BotClient {
     /// * `game_id`: The ID of the game in whose chat to post a message.
     /// * `room`: The chat room (player/spectator) in which to post the message.
     /// * `text`: The text of the chat message to send.
+    ///
+    /// # Errors
+    ///
+    /// [LibotRequestError::ChatThrottled] if the message is not sent because it would exceed the
+    /// rate limit configured with [BotClientBuilder::with_chat_throttle]. If the throttle allows
+    /// queuing, the message is instead delayed until it may be sent, or appended to an already
+    /// queued message in the same game and room.
     pub async fn send_chat_message(&self, game_id: GameId, room: ChatRoom, text: impl Into<String>)
         -> LibotResult<()> {
-        let path = format!("/bot/game/{game_id}/chat");
+        let mut text = text.into();
+
+        if let Some(chat_throttle) = &self.chat_throttle {
+            match chat_throttle.reserve(&game_id, room, &text, Instant::now()) {
+                ChatReservation::Send => { },
+                ChatReservation::Queued { send_at, ticket } => {
+                    tokio::time::sleep_until(send_at.into()).await;
+                    text = chat_throttle.take_queued(&game_id, room, ticket, text);
+                },
+                ChatReservation::Coalesced => return Ok(()),
+                ChatReservation::Rejected { retry_after } =>
+                    return Err(LibotRequestError::ChatThrottled { retry_after })
+            }
+        }
+
+        let path = self.game_path(&format!("/game/{game_id}/chat"));
         let body = SendChatMessageRequest {
             room,
-            text: text.into()
+            text
         };
 
         self.send_request_with_form(Method::POST, &path, body).await?;
@@ -242,679 +929,2735 @@ impl BotClient {
         Ok(())
     }
 
-    /// Queries the [UserProfile] of the user with the given name.
+    /// Localizes the message with the given key using the [ChatLocalizer] configured with
+    /// [BotClientBuilder::with_chat_localizer] and sends it to the chat of a game (see
+    /// [BotClient::send_chat_message]). During a game, the preferred language of the opponent is
+    /// available via [GameContext::opponent_language](crate::context::GameContext::opponent_language).
     ///
     /// # Arguments
     ///
-    /// * `username`: The username of the user whose profile to query.
-    pub async fn get_profile(&self, username: String) -> LibotResult<UserProfile> {
-        let path = format!("/user/{username}");
-
-        Ok(self.send_request(Method::GET, &path).await?.json().await?)
+    /// * `game_id`: The ID of the game to whose chat to send the message.
+    /// * `room`: The [ChatRoom] to which to send the message.
+    /// * `key`: The key of the message in the catalogs of the localizer.
+    /// * `language`: The preferred language of the recipient, if known.
+    /// * `args`: The values of the placeholders in the message template, by name.
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::MissingChatMessage] if no localizer is configured or no suitable
+    ///   catalog contains the given key.
+    /// * Any error returned by [BotClient::send_chat_message].
+    pub async fn send_localized_chat_message(&self, game_id: GameId, room: ChatRoom, key: &str,
+            language: Option<&str>, args: &[(&str, &str)]) -> LibotResult<()> {
+        let text = self.chat_localizer.as_ref()
+            .and_then(|localizer| localizer.localize(key, language, args))
+            .ok_or_else(|| LibotRequestError::MissingChatMessage {
+                key: key.to_owned()
+            })?;
+
+        self.send_chat_message(game_id, room, text).await
     }
 
-    /// Queries the [UserProfile] of the user as which this bot is authenticated.
-    pub async fn get_my_profile(&self) -> LibotResult<UserProfile> {
-        Ok(self.send_request(Method::GET, "/account").await?.json().await?)
-    }
+    /// Sends a private message to all members of the team with the given ID, e.g. to announce an
+    /// upcoming tournament. This requires the bot to be a leader of the team and the `team:lead`
+    /// OAuth scope. The message is not subject to the chat throttle.
+    ///
+    /// # Arguments
+    ///
+    /// * `team_id`: The ID of the team whose members to message.
+    /// * `text`: The text of the message to send.
+    pub async fn send_team_message(&self, team_id: &str, text: impl Into<String>)
+            -> LibotResult<()> {
+        let path = format!("/team/{team_id}/pm-all");
+        let body = SendTeamMessageRequest {
+            message: text.into()
+        };
 
-    /// Queries the [UserPreferences] of the user as which this bot is authenticated.
-    pub async fn get_my_preferences(&self) -> LibotResult<UserPreferences> {
-        Ok(self.send_request(Method::GET, "/account/preferences").await?.json().await?)
+        self.send_request_with_form(Method::POST, &path, body).await?;
+
+        Ok(())
     }
-}
 
-/// The URL used by default as the base URL, if no other base URL is provided using
-/// [BotClientBuilder::with_base_url]. This is the public production instance of Lichess.
-pub const DEFAULT_BASE_URL: &str = "https://lichess.org/api";
+    /// Exports the game with the given ID, including the players' rating changes once the game is
+    /// finished and the opening classified by Lichess.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game to export.
+    pub async fn export_game(&self, game_id: GameId) -> LibotResult<ExportedGame> {
+        self.export_game_with_query(game_id, &[("opening", "true")]).await
+    }
 
-/// A builder for [BotClient]s.
-#[derive(Clone, Debug)]
-pub struct BotClientBuilder {
-    token: Option<String>,
-    base_url: String
-}
+    /// Exports the game with the given ID like [BotClient::export_game], but also requests the
+    /// summary of the server analysis of each player, including their accuracy (see
+    /// [ExportedPlayer::analysis](crate::model::game::export::ExportedPlayer::analysis)). The
+    /// summary is only present if the game was analysed by Lichess.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game to export.
+    pub async fn export_game_with_analysis(&self, game_id: GameId) -> LibotResult<ExportedGame> {
+        let query = [("opening", "true"), ("evals", "true"), ("accuracy", "true")];
 
-impl BotClientBuilder {
+        self.export_game_with_query(game_id, &query).await
+    }
 
-    /// Creates a new builder with default values. A token must be provided using
-    /// [BotClientBuilder::with_token] before [BotClientBuilder::build] can be called.
-    pub fn new() -> BotClientBuilder {
-        BotClientBuilder {
-            token: None,
-            base_url: DEFAULT_BASE_URL.to_owned()
+    /// Requests the server analysis of the finished game with the given ID, which Lichess then
+    /// enqueues for its distributed analysis network. This uses the endpoint of the Lichess
+    /// website, which is not part of the official API, so Lichess may reject it for some tokens
+    /// or games, e.g. if the game is too short or was already analysed. Use
+    /// [BotClient::wait_for_game_analysis] to wait until the analysis is available.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game to analyse.
+    pub async fn request_game_analysis(&self, game_id: GameId) -> LibotResult<()> {
+        let url = join_url(&self.site_base_url, &format!("/{game_id}/request-analysis"));
+
+        self.execute(self.client.post(url)).await?;
+
+        Ok(())
+    }
+
+    /// Repeatedly exports the game with the given ID using [BotClient::export_game_with_analysis]
+    /// until the summary of its server analysis is available or the timeout elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game whose analysis to wait for.
+    /// * `poll_interval`: The time between two exports.
+    /// * `timeout`: The maximum time to wait for the analysis.
+    ///
+    /// # Returns
+    ///
+    /// The exported game including the summary of the server analysis, or [None] if the game was
+    /// not analysed before the timeout.
+    pub async fn wait_for_game_analysis(&self, game_id: GameId, poll_interval: Duration,
+            timeout: Duration) -> LibotResult<Option<ExportedGame>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let game = self.export_game_with_analysis(game_id.clone()).await?;
+            let players = &game.players;
+
+            if players.white.analysis.is_some() && players.black.analysis.is_some() {
+                return Ok(Some(game));
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            tokio::time::sleep(poll_interval.min(deadline - now)).await;
         }
     }
 
-    /// Sets the Lichess API OAuth token for the bot to use. The builder is returned for chaining.
-    pub fn with_token(mut self, token: impl Into<String>) -> BotClientBuilder {
-        self.token = Some(token.into());
-        self
+    async fn export_game_with_query(&self, game_id: GameId, query: &[(&str, &str)])
+            -> LibotResult<ExportedGame> {
+        let url = join_url(&self.base_url, "/games/export/_ids");
+        let request = self.client.post(url)
+            .query(query)
+            .header(ACCEPT, "application/x-ndjson")
+            .body(game_id);
+        let text = self.execute(request).await?.text().await?;
+        let line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+
+        Ok(serde_json::from_str(line)?)
     }
 
-    /// Sets the base URL of the Lichess API with which the client should communicate. The builder
-    /// is returned for chaining. By default, i.e. if this method is not called, the base URL is
-    /// [DEFAULT_BASE_URL]. The builder is returned for chaining.
-    pub fn with_base_url(mut self, base_url: impl Into<String>) -> BotClientBuilder {
-        self.base_url = base_url.into();
-        self
+    /// Exports the games with the given IDs like [BotClient::export_game]. The IDs are streamed to
+    /// Lichess as they are produced by the iterator, so they do not have to be collected first.
+    /// Lichess exports at most 300 games per request.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_ids`: The IDs of the games to export.
+    ///
+    /// # Returns
+    ///
+    /// A stream of the exported games in the order of their IDs. Games which do not exist are
+    /// skipped.
+    pub async fn export_games<I>(&self, game_ids: I)
+            -> LibotResult<impl Stream<Item = LibotResult<ExportedGame>>>
+    where
+        I: IntoIterator,
+        I::Item: Into<GameId>,
+        I::IntoIter: Send + Sync + 'static
+    {
+        let response = self.send_request_with_id_list(
+            "/games/export/_ids", [("opening", "true")], game_ids).await?;
+
+        Ok(parse_ndjson_response(response))
     }
 
-    /// Builds a new Lichess bot client from the provided information. At least a token must be
-    /// provided, i.e. [BotClientBuilder::with_token] must have been called.
+    /// Streams the games played between any two of the users with the given IDs as they start
+    /// and finish, as raw JSON values. The IDs are streamed to Lichess as they are produced by the
+    /// iterator, so they do not have to be collected first. Lichess accepts at most 300 IDs.
     ///
-    /// # Errors
+    /// # Arguments
     ///
-    /// * [BotClientBuilderError::InvalidToken] if it is not possible to parse the provided token
-    /// into a HTTP header value.
-    /// * [BotClientBuilderError::ClientError] if creating the `reqwest` client failed.
-    /// * [BotClientBuilderError::NoToken] if no token was provided.
-    pub fn build(self) -> BotClientBuilderResult {
-        if let Some(token) = self.token {
-            let mut headers = HeaderMap::new();
-            let authorization_value = format!("Bearer {}", token).parse()?;
-            headers.insert(AUTHORIZATION, authorization_value);
-            let client = ClientBuilder::new().default_headers(headers).build()?;
+    /// * `user_ids`: The IDs of the users whose games to stream.
+    /// * `with_current_games`: Whether the games which are already ongoing are sent first.
+    pub async fn stream_games_by_users<I>(&self, user_ids: I, with_current_games: bool)
+            -> LibotResult<impl Stream<Item = LibotResult<JsonValue>>>
+    where
+        I: IntoIterator,
+        I::Item: Into<UserId>,
+        I::IntoIter: Send + Sync + 'static
+    {
+        let query = [("withCurrentGames", with_current_games)];
+        let response = self.send_request_with_id_list(
+            "/stream/games-by-users", query, user_ids).await?;
+
+        Ok(parse_ndjson_response(response))
+    }
 
-            Ok(BotClient {
-                client,
-                base_url: Arc::from(self.base_url)
-            })
-        }
-        else {
-            Err(BotClientBuilderError::NoToken)
+    /// Exports the finished games of the user with the given name as PGN, most recent first.
+    ///
+    /// # Arguments
+    ///
+    /// * `username`: The name of the user whose games to export.
+    /// * `since`: If present, only games created at or after this timestamp are exported.
+    ///
+    /// # Returns
+    ///
+    /// The PGN of all exported games, separated by blank lines.
+    pub async fn export_user_games_pgn(&self, username: &str, since: Option<Timestamp>)
+            -> LibotResult<String> {
+        #[derive(Serialize)]
+        struct ExportQuery {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            since: Option<Timestamp>
         }
+
+        let url = join_url(&self.base_url, &format!("/games/user/{username}"));
+        let request = self.client.get(url)
+            .query(&ExportQuery { since })
+            .header(ACCEPT, "application/x-chess-pgn");
+
+        Ok(self.execute(request).await?.text().await?)
     }
-}
 
-impl Default for BotClientBuilder {
-    fn default() -> BotClientBuilder {
-        BotClientBuilder::new()
+    /// Queries the total scores of two users in all games they played against each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_1`: The username or ID of the first user.
+    /// * `user_2`: The username or ID of the second user.
+    pub async fn get_crosstable(&self, user_1: &str, user_2: &str) -> LibotResult<Crosstable> {
+        let path = format!("/crosstable/{user_1}/{user_2}");
+
+        Ok(self.send_request(Method::GET, &path).await?.json().await?)
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Queries the games currently featured on the channels of Lichess TV, e.g. to find out
+    /// whether a game of the bot is being watched.
+    pub async fn get_tv_channels(&self) -> LibotResult<TvChannels> {
+        Ok(self.send_request(Method::GET, "/tv/channels").await?.json().await?)
+    }
 
-    use kernal::prelude::*;
+    /// Queries the games the bot is currently playing, including correspondence games, ordered by
+    /// urgency, i.e. games in which it is the bot's turn with little time left come first.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: The maximum number of games to query, at most 50.
+    pub async fn get_ongoing_games(&self, limit: u32) -> LibotResult<Vec<OngoingGame>> {
+        let query = [("nb", limit)];
+        let response = self.send_request_with_query(Method::GET, "/account/playing", query).await?;
 
-    use rstest::rstest;
+        Ok(response.json::<OngoingGames>().await?.now_playing)
+    }
 
-    use wiremock::{Mock, ResponseTemplate};
-    use wiremock::matchers::{body_json_string, body_string, method, path, query_param};
-    use crate::model::challenge::{Challenge, ChallengeColor, ChallengePerf, ChallengeStatus};
+    /// Queries the cached evaluation of a position from the Lichess cloud evaluation database.
+    ///
+    /// # Arguments
+    ///
+    /// * `fen`: The FEN of the position to look up.
+    /// * `multi_pv`: The number of principal variations to request.
+    ///
+    /// # Returns
+    ///
+    /// The [CloudEvaluation] of the position, or [None] if the position is not in the database.
+    pub async fn get_cloud_eval(&self, fen: &str, multi_pv: u32)
+            -> LibotResult<Option<CloudEvaluation>> {
+        #[derive(Serialize)]
+        struct Query<'a> {
+            fen: &'a str,
 
-    use crate::model::game::chat::ChatLine;
-    use crate::model::game::Speed;
-    use crate::model::TimeControl;
-    use crate::model::user::{PlayTime, User, UserProfileStats};
-    use crate::model::user::preferences::{
-        AutoQueen,
-        AutoThreefold,
-        CastlingMethod,
-        ChallengeFilter,
-        ClockTenths,
-        Coordinates,
-        InsightShare,
-        MessageFilter,
-        MoreTime,
-        MoveConfirmations,
-        MoveEvent,
-        PieceAnimation,
-        Replay,
-        TakeBack,
-        ZenMode
-    };
-    use crate::test_util;
+            #[serde(rename = "multiPv")]
+            multi_pv: u32
+        }
 
-    use super::*;
+        let query = Query { fen, multi_pv };
 
-    #[test]
-    fn building_bot_client_fails_without_token() {
-        let result = BotClientBuilder::new().build();
+        match self.send_request_with_query(Method::GET, "/cloud-eval", query).await {
+            Ok(response) => Ok(Some(response.json().await?)),
+            Err(LibotRequestError::ApiError { status: StatusCode::NOT_FOUND, .. }) => Ok(None),
+            Err(error) => Err(error)
+        }
+    }
 
-        assert!(matches!(result, Err(BotClientBuilderError::NoToken)));
+    /// Queries all external engines registered by the user as which this bot is authenticated.
+    /// Requires the `engine:read` OAuth scope.
+    pub async fn list_external_engines(&self) -> LibotResult<Vec<ExternalEngine>> {
+        Ok(self.send_request(Method::GET, "/external-engine").await?.json().await?)
     }
 
-    #[test]
-    fn building_bot_client_fails_with_invalid_token() {
-        let result = BotClientBuilder::new()
-            .with_token("\0")
-            .build();
+    /// Registers a new external engine for the user as which this bot is authenticated. Requires
+    /// the `engine:write` OAuth scope. Analysis can then be requested from the engine using an
+    /// [ExternalEngineClient](crate::external_engine::ExternalEngineClient).
+    ///
+    /// # Arguments
+    ///
+    /// * `registration`: The data of the engine to register.
+    pub async fn create_external_engine(&self, registration: &ExternalEngineRegistration)
+            -> LibotResult<ExternalEngine> {
+        let response =
+            self.send_request_with_body(Method::POST, "/external-engine", registration).await?;
 
-        assert!(matches!(result, Err(BotClientBuilderError::InvalidToken(_))));
+        Ok(response.json().await?)
     }
 
-    #[test]
-    fn building_bot_client_succeeds_with_valid_token_and_default_base_url() {
-        let result = BotClientBuilder::new()
-            .with_token("abc123")
-            .build();
+    /// Queries the external engine with the given ID. Requires the `engine:read` OAuth scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine_id`: The ID of the external engine to query.
+    pub async fn get_external_engine(&self, engine_id: &str) -> LibotResult<ExternalEngine> {
+        let path = format!("/external-engine/{engine_id}");
 
-        assert_that!(&result).is_ok();
-        assert_that!(result.unwrap().base_url.as_ref()).is_equal_to(DEFAULT_BASE_URL);
+        Ok(self.send_request(Method::GET, &path).await?.json().await?)
     }
 
-    #[test]
-    fn building_bot_client_succeeds_with_valid_token_and_overridden_base_url() {
-        let base_url = "https://base.url/path";
-        let result = BotClientBuilder::new()
-            .with_token("abc123")
-            .with_base_url(base_url)
-            .build();
+    /// Replaces the data of a registered external engine. Requires the `engine:write` OAuth scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine_id`: The ID of the external engine to update.
+    /// * `registration`: The new data of the engine.
+    pub async fn update_external_engine(&self, engine_id: &str,
+            registration: &ExternalEngineRegistration) -> LibotResult<ExternalEngine> {
+        let path = format!("/external-engine/{engine_id}");
+        let response = self.send_request_with_body(Method::PUT, &path, registration).await?;
+
+        Ok(response.json().await?)
+    }
 
-        assert_that!(&result).is_ok();
-        assert_that!(result.unwrap().base_url.as_ref()).is_equal_to(base_url);
+    /// Unregisters an external engine. Requires the `engine:write` OAuth scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine_id`: The ID of the external engine to delete.
+    pub async fn delete_external_engine(&self, engine_id: &str) -> LibotResult<()> {
+        let path = format!("/external-engine/{engine_id}");
+
+        self.send_request(Method::DELETE, &path).await?;
+
+        Ok(())
     }
 
-    #[test]
-    fn joining_url_works_if_no_slash_is_present() {
-        let base_url = "https://base.url/path";
-        let path = "sub/path";
+    /// Queries the arena tournament with the given ID, including the participation of the bot.
+    pub async fn get_tournament(&self, tournament_id: &str) -> LibotResult<Tournament> {
+        let path = format!("/tournament/{tournament_id}");
 
-        let url = join_url(base_url, path);
+        Ok(self.send_request(Method::GET, &path).await?.json().await?)
+    }
 
-        assert_that!(url.as_str()).is_equal_to("https://base.url/path/sub/path");
+    /// Joins the arena tournament with the given ID or, if the bot withdrew from or was paused in
+    /// it, joins it again.
+    ///
+    /// # Arguments
+    ///
+    /// * `tournament_id`: The ID of the tournament to join.
+    /// * `password`: The password of the tournament, if it is private.
+    pub async fn join_tournament(&self, tournament_id: &str, password: Option<&str>)
+            -> LibotResult<()> {
+        #[derive(Serialize)]
+        struct JoinRequest<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            password: Option<&'a str>
+        }
+
+        let path = format!("/tournament/{tournament_id}/join");
+
+        self.send_request_with_form(Method::POST, &path, JoinRequest { password }).await?;
+
+        Ok(())
     }
 
-    #[test]
-    fn joining_url_works_if_base_url_has_slash() {
-        let base_url = "https://lichess.org/";
-        let path = "my/path";
+    /// Withdraws from the arena tournament with the given ID. The bot is no longer paired, but
+    /// keeps its score and can join again with [BotClient::join_tournament].
+    pub async fn withdraw_from_tournament(&self, tournament_id: &str) -> LibotResult<()> {
+        let path = format!("/tournament/{tournament_id}/withdraw");
 
-        let url = join_url(base_url, path);
+        self.send_request(Method::POST, &path).await?;
 
-        assert_that!(url.as_str()).is_equal_to("https://lichess.org/my/path");
+        Ok(())
     }
 
-    #[test]
-    fn joining_url_works_if_base_path_has_slash() {
-        let base_url = "https://lichess.org/api";
-        let path = "/sub/path";
+    /// Pushes games in PGN format to the broadcast round with the given ID, e.g. to relay the
+    /// games of the bot or of an over-the-board event into a Lichess broadcast. Games are matched
+    /// to those already in the round by their tags, so repeatedly pushing the PGN of ongoing
+    /// games updates them. Requires the `study:write` OAuth scope and that the bot is a
+    /// contributor of the broadcast.
+    ///
+    /// # Arguments
+    ///
+    /// * `round_id`: The ID of the broadcast round to which to push the games.
+    /// * `pgn`: The PGN of one or more games, separated by blank lines.
+    ///
+    /// # Returns
+    ///
+    /// A [BroadcastPush] reporting for every pushed game whether it was accepted.
+    pub async fn push_broadcast_pgn(&self, round_id: &str, pgn: impl Into<String>)
+            -> LibotResult<BroadcastPush> {
+        let url = join_url(&self.base_url, &format!("/broadcast/round/{round_id}/push"));
+        let request = self.client.post(url).body(pgn.into());
 
-        let url = join_url(base_url, path);
+        Ok(self.execute(request).await?.json().await?)
+    }
 
-        assert_that!(url.as_str()).is_equal_to("https://lichess.org/api/sub/path");
+    /// Goes berserk in the arena tournament game with the given ID, which halves the clock of the
+    /// bot in exchange for an extra tournament point on a win. This is only possible before the
+    /// bot made its first move and uses the board API regardless of the [ApiMode] of this client,
+    /// since the bot API offers no such endpoint.
+    pub async fn berserk(&self, game_id: GameId) -> LibotResult<()> {
+        let path = format!("/board/game/{game_id}/berserk");
+
+        self.send_request(Method::POST, &path).await?;
+
+        Ok(())
     }
 
-    #[test]
-    fn joining_url_works_if_both_have_slash() {
-        let base_url = "https://lichess.org/api/";
-        let path = "/bot/whatever";
+    /// Queries the [UserProfile] of the user with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `username`: The username of the user whose profile to query.
+    pub async fn get_profile(&self, username: String) -> LibotResult<UserProfile> {
+        let path = format!("/user/{username}");
 
-        let url = join_url(base_url, path);
+        Ok(self.send_request(Method::GET, &path).await?.json().await?)
+    }
 
-        assert_that!(url.as_str()).is_equal_to("https://lichess.org/api/bot/whatever");
+    /// Queries the [UserProfile] of the user as which this bot is authenticated.
+    pub async fn get_my_profile(&self) -> LibotResult<UserProfile> {
+        Ok(self.send_request(Method::GET, "/account").await?.json().await?)
     }
 
-    fn minimal_challenge() -> Challenge {
-        Challenge {
-            id: "testId".to_owned(),
-            url: "testUrl".to_owned(),
-            status: ChallengeStatus::Created,
-            challenger: User {
-                rating: None,
-                provisional: false,
-                online: false,
-                id: "testChallengerId".to_owned(),
-                name: "testChallengerName".to_owned(),
-                title: None,
-                patron: false
-            },
-            dest_user: None,
-            variant: None,
-            rated: false,
-            speed: Speed::Correspondence,
-            time_control: TimeControl::Unlimited,
-            color: ChallengeColor::Random,
-            perf: ChallengePerf {
-                icon: None,
-                name: None
-            },
-            direction: None,
-            initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
-        }
+    /// Queries the [UserPreferences] of the user as which this bot is authenticated.
+    pub async fn get_my_preferences(&self) -> LibotResult<UserPreferences> {
+        Ok(self.send_request(Method::GET, "/account/preferences").await?.json().await?)
     }
 
-    #[rstest]
-    #[case::empty(
-        r#"{
-            "in": [],
-            "out": []
-        }"#,
-        Challenges {
-            incoming: Vec::new(),
-            outgoing: Vec::new()
+    /// Upgrades the account as which this bot is authenticated to a bot account. This is only
+    /// possible for accounts which have not played any games yet and cannot be reverted.
+    pub async fn upgrade_to_bot_account(&self) -> LibotResult<()> {
+        self.send_request(Method::POST, "/bot/account/upgrade").await?;
+
+        Ok(())
+    }
+
+    /// Opens the event stream of the bot and returns its events as raw JSON values, without
+    /// interpreting them. Keep-alive empty lines are skipped. This is intended for debugging, use
+    /// a [BotRunner](crate::runner::BotRunner) or [BotClient::stream_bot_events] to react to
+    /// events.
+    pub async fn stream_raw_events(&self)
+            -> LibotResult<impl Stream<Item = LibotResult<JsonValue>>> {
+        let response = self.send_request(Method::GET, "/stream/event").await?;
+
+        Ok(parse_ndjson_response(response))
+    }
+
+    /// Opens the event stream of the bot and returns its events as typed [BotEvent]s, e.g. to
+    /// process them in a custom event loop instead of implementing the [Bot](crate::Bot) trait.
+    /// Keep-alive empty lines are skipped. Lines which cannot be parsed, such as events of types
+    /// unknown to this library, are returned as errors, after which the stream continues. Unlike
+    /// a [BotRunner](crate::runner::BotRunner), no game event streams are opened automatically,
+    /// use [BotClient::stream_game_events] for that.
+    pub async fn stream_bot_events(&self)
+            -> LibotResult<impl Stream<Item = LibotResult<BotEvent>>> {
+        let response = self.send_request(Method::GET, "/stream/event").await?;
+
+        Ok(parse_ndjson_response(response))
+    }
+
+    /// Opens the event stream of the game with the given ID and returns its events as typed
+    /// [GameEvent]s. The first event is usually a [GameEvent::GameFull]. Keep-alive empty lines
+    /// are skipped and lines which cannot be parsed are returned as errors, like in
+    /// [BotClient::stream_bot_events]. The stream ends when the game is over.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_id`: The ID of the game whose events to stream.
+    pub async fn stream_game_events(&self, game_id: GameId)
+            -> LibotResult<impl Stream<Item = LibotResult<GameEvent>>> {
+        let path = self.game_path(&format!("/game/stream/{game_id}"));
+        let response = self.send_request(Method::GET, &path).await?;
+
+        Ok(parse_ndjson_response(response))
+    }
+}
+
+/// The URL used by default as the base URL, if no other base URL is provided using
+/// [BotClientBuilder::with_base_url]. This is the public production instance of Lichess.
+pub const DEFAULT_BASE_URL: &str = "https://lichess.org/api";
+
+/// A secret string, such as an API token, which is redacted in [Debug] output and overwritten with
+/// zeros when dropped.
+#[derive(Clone, Deserialize, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+
+    /// Wraps the given string as a secret.
+    pub fn new(secret: impl Into<String>) -> Secret {
+        Secret(secret.into())
+    }
+
+    /// Gets the secret string. Avoid keeping copies of it around longer than necessary.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("\"<redacted>\"")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        let mut bytes = std::mem::take(&mut self.0).into_bytes();
+        bytes.fill(0);
+
+        // Prevents the compiler from eliding the writes to memory which is freed afterwards.
+        hint::black_box(&bytes);
+    }
+}
+
+/// The origin from which a [BotClientBuilder] obtains the API token. Tokens from environment
+/// variables and files are resolved only when [BotClientBuilder::build] is called.
+#[derive(Clone, Debug)]
+enum TokenSource {
+    Value(Secret),
+    EnvironmentVariable(String),
+    File(PathBuf)
+}
+
+impl TokenSource {
+
+    fn resolve(self) -> Result<Secret, BotClientBuilderError> {
+        match self {
+            TokenSource::Value(token) => Ok(token),
+            TokenSource::EnvironmentVariable(name) => env::var(&name)
+                .map(Secret)
+                .map_err(|source| BotClientBuilderError::TokenEnvironmentVariable {
+                    name,
+                    source
+                }),
+            TokenSource::File(path) => match fs::read_to_string(&path) {
+                Ok(content) => {
+                    let content = Secret(content);
+                    Ok(Secret(content.expose().trim().to_owned()))
+                },
+                Err(source) => Err(BotClientBuilderError::TokenFile { path, source })
+            }
         }
-    )]
-    #[case::incoming(
-        r#"{
-            "in": [
-                {
-                    "id": "testId",
-                    "url": "testUrl",
-                    "status": "created",
-                    "challenger": {
-                        "id": "testChallengerId",
-                        "name": "testChallengerName"
-                    },
-                    "variant": { },
-                    "rated": false,
-                    "speed": "correspondence",
-                    "timeControl": {
-                        "type": "unlimited"
-                    },
-                    "color": "random",
-                    "perf": {}
-                }
-            ],
-            "out": []
-        }"#,
-        Challenges {
-            incoming: vec![minimal_challenge()],
-            outgoing: Vec::new()
+    }
+}
+
+/// A builder for [BotClient]s. The API token is redacted from the [Debug] output of the builder.
+#[derive(Clone, Debug)]
+pub struct BotClientBuilder {
+    token: Option<TokenSource>,
+    base_url: String,
+    gif_base_url: String,
+    site_base_url: String,
+    api_mode: ApiMode,
+    chat_throttle: Option<ChatThrottleConfig>,
+    chat_localizer: Option<Arc<ChatLocalizer>>,
+    connection: ConnectionConfig,
+    timeouts: RequestTimeouts,
+    dispatch: Option<DispatchConfig>,
+    transport: Option<Arc<dyn HttpTransport>>
+}
+
+impl BotClientBuilder {
+
+    /// Creates a new builder with default values. A token must be provided using
+    /// [BotClientBuilder::with_token] before [BotClientBuilder::build] can be called.
+    pub fn new() -> BotClientBuilder {
+        BotClientBuilder {
+            token: None,
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            gif_base_url: GIF_URL.to_owned(),
+            site_base_url: SITE_URL.to_owned(),
+            api_mode: ApiMode::Bot,
+            chat_throttle: Some(ChatThrottleConfig::default()),
+            chat_localizer: None,
+            connection: ConnectionConfig::default(),
+            timeouts: RequestTimeouts::default(),
+            dispatch: Some(DispatchConfig::default()),
+            transport: None
         }
-    )]
-    #[case::outgoing(
-        r#"{
-            "in": [],
-            "out": [
-                {
-                    "id": "testId",
-                    "url": "testUrl",
-                    "status": "created",
-                    "challenger": {
-                        "id": "testChallengerId",
-                        "name": "testChallengerName"
-                    },
-                    "variant": { },
-                    "rated": false,
-                    "speed": "correspondence",
-                    "timeControl": {
-                        "type": "unlimited"
-                    },
-                    "color": "random",
-                    "perf": {}
-                }
-            ]
-        }"#,
-        Challenges {
-            incoming: Vec::new(),
-            outgoing: vec![minimal_challenge()],
+    }
+
+    /// Sets the Lichess API OAuth token for the bot to use. The builder is returned for chaining.
+    pub fn with_token(mut self, token: impl Into<String>) -> BotClientBuilder {
+        self.token = Some(TokenSource::Value(Secret(token.into())));
+        self
+    }
+
+    /// Specifies that the Lichess API OAuth token for the bot to use is read from the environment
+    /// variable with the given name. The variable is read when [BotClientBuilder::build] is
+    /// called. This replaces any previously provided token. The builder is returned for chaining.
+    pub fn with_token_from_env(mut self, name: impl Into<String>) -> BotClientBuilder {
+        self.token = Some(TokenSource::EnvironmentVariable(name.into()));
+        self
+    }
+
+    /// Specifies that the Lichess API OAuth token for the bot to use is read from the file at the
+    /// given path. Leading and trailing whitespace, such as a final line break, is ignored. The
+    /// file is read when [BotClientBuilder::build] is called. This replaces any previously provided
+    /// token. The builder is returned for chaining.
+    pub fn with_token_file(mut self, path: impl Into<PathBuf>) -> BotClientBuilder {
+        self.token = Some(TokenSource::File(path.into()));
+        self
+    }
+
+    /// Sets the base URL of the Lichess API with which the client should communicate. The builder
+    /// is returned for chaining. By default, i.e. if this method is not called, the base URL is
+    /// [DEFAULT_BASE_URL]. The builder is returned for chaining.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> BotClientBuilder {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sets the base URL of the server which renders games as GIFs, which is used by
+    /// [BotClient::get_game_gif]. By default, i.e. if this method is not called, the base URL is
+    /// [GIF_URL]. The builder is returned for chaining.
+    pub fn with_gif_base_url(mut self, gif_base_url: impl Into<String>) -> BotClientBuilder {
+        self.gif_base_url = gif_base_url.into();
+        self
+    }
+
+    /// Sets the base URL of the Lichess website, which is used by
+    /// [BotClient::request_game_analysis]. By default, i.e. if this method is not called, the base
+    /// URL is [SITE_URL]. The builder is returned for chaining.
+    pub fn with_site_base_url(mut self, site_base_url: impl Into<String>) -> BotClientBuilder {
+        self.site_base_url = site_base_url.into();
+        self
+    }
+
+    /// Sets the [ApiMode], i.e. whether games are played through the bot or the board API. By
+    /// default, the bot API is used. The builder is returned for chaining.
+    pub fn with_api_mode(mut self, api_mode: ApiMode) -> BotClientBuilder {
+        self.api_mode = api_mode;
+        self
+    }
+
+    /// Applies the token and base URL of the given [BotConfig], if they are set. Values which are
+    /// not set in the config are left unchanged. The token is moved out of the config, so no copy
+    /// of it remains there. The builder is returned for chaining.
+    pub fn with_config(mut self, config: &mut BotConfig) -> BotClientBuilder {
+        if let Some(token) = config.token.take() {
+            self.token = Some(TokenSource::Value(token));
         }
-    )]
-    fn get_pending_challenges(#[case] json: &str, #[case] expected_challenges: Challenges) {
+
+        if let Some(base_url) = &config.base_url {
+            self = self.with_base_url(base_url.clone());
+        }
+
+        self
+    }
+
+    /// Sets the [ChatThrottleConfig] which limits the rate at which
+    /// [BotClient::send_chat_message] sends messages, per game and across all games. By default,
+    /// [ChatThrottleConfig::default] is used. The builder is returned for chaining.
+    pub fn with_chat_throttle(mut self, config: ChatThrottleConfig) -> BotClientBuilder {
+        self.chat_throttle = Some(config);
+        self
+    }
+
+    /// Disables limiting the rate at which [BotClient::send_chat_message] sends messages. Note
+    /// that Lichess may mute bots which send too many messages. The builder is returned for
+    /// chaining.
+    pub fn without_chat_throttle(mut self) -> BotClientBuilder {
+        self.chat_throttle = None;
+        self
+    }
+
+    /// Sets the [ChatLocalizer] used by [BotClient::send_localized_chat_message] to localize
+    /// chat messages. The builder is returned for chaining.
+    pub fn with_chat_localizer(mut self, localizer: ChatLocalizer) -> BotClientBuilder {
+        self.chat_localizer = Some(Arc::new(localizer));
+        self
+    }
+
+    /// Sets the [ConnectionConfig] which determines connection pooling, TCP options, and HTTP/2
+    /// keep-alive of the client. By default, the defaults of `reqwest` are used. Bots playing fast
+    /// time controls may use [ConnectionConfig::low_latency]. The builder is returned for
+    /// chaining.
+    pub fn with_connection_config(mut self, config: ConnectionConfig) -> BotClientBuilder {
+        self.connection = config;
+        self
+    }
+
+    /// Sets the [RequestTimeouts] which assign each [EndpointCategory] the timeout of its requests.
+    /// By default, only game actions time out, after
+    /// [DEFAULT_GAME_ACTION_TIMEOUT](crate::connection::DEFAULT_GAME_ACTION_TIMEOUT). The builder
+    /// is returned for chaining.
+    pub fn with_request_timeouts(mut self, timeouts: RequestTimeouts) -> BotClientBuilder {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Sets the [DispatchConfig] which limits the number of concurrent requests and reserves some
+    /// of them for game actions, so moves are not delayed by other requests. By default,
+    /// [DispatchConfig::default] is used. The builder is returned for chaining.
+    pub fn with_dispatch(mut self, config: DispatchConfig) -> BotClientBuilder {
+        self.dispatch = Some(config);
+        self
+    }
+
+    /// Disables limiting the number of concurrent requests, i.e. all requests are sent
+    /// immediately. The builder is returned for chaining.
+    pub fn without_dispatch_limit(mut self) -> BotClientBuilder {
+        self.dispatch = None;
+        self
+    }
+
+    /// Sets the [HttpTransport] through which the client sends its requests. By default, requests
+    /// are sent with a [ReqwestTransport], to which the [ConnectionConfig] applies. The builder is
+    /// returned for chaining.
+    pub fn with_transport(mut self, transport: impl HttpTransport + 'static) -> BotClientBuilder {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Builds a new Lichess bot client from the provided information. At least a token must be
+    /// provided, i.e. [BotClientBuilder::with_token], [BotClientBuilder::with_token_from_env] or
+    /// [BotClientBuilder::with_token_file] must have been called. After the authorization header
+    /// has been constructed, the token is overwritten in memory. The header itself is marked as
+    /// sensitive, so it is not included in the [Debug] output of the client.
+    ///
+    /// # Errors
+    ///
+    /// * [BotClientBuilderError::InvalidToken] if it is not possible to parse the provided token
+    ///   into a HTTP header value.
+    /// * [BotClientBuilderError::ClientError] if creating the `reqwest` client failed.
+    /// * [BotClientBuilderError::NoToken] if no token was provided.
+    /// * [BotClientBuilderError::TokenEnvironmentVariable] if the token should be read from an
+    ///   environment variable which is not set or not valid unicode.
+    /// * [BotClientBuilderError::TokenFile] if the token should be read from a file which could
+    ///   not be read.
+    pub fn build(self) -> BotClientBuilderResult {
+        if let Some(token) = self.token {
+            let token = token.resolve()?;
+            let header_content = Secret(format!("Bearer {}", token.expose()));
+            let mut authorization_value = HeaderValue::from_str(header_content.expose())?;
+            authorization_value.set_sensitive(true);
+            drop(header_content);
+            drop(token);
+
+            let client = self.connection.apply(ClientBuilder::new()).build()?;
+            let transport = self.transport
+                .unwrap_or_else(|| Arc::new(ReqwestTransport::new(client.clone())));
+
+            Ok(BotClient {
+                client,
+                transport,
+                authorization: authorization_value,
+                timeouts: self.timeouts,
+                dispatcher: self.dispatch.map(|config| Arc::new(Dispatcher::new(config))),
+                base_url: Arc::from(self.base_url),
+                gif_base_url: Arc::from(self.gif_base_url),
+                site_base_url: Arc::from(self.site_base_url),
+                api_mode: self.api_mode,
+                chat_throttle: self.chat_throttle.map(|config| Arc::new(ChatThrottle::new(config))),
+                chat_localizer: self.chat_localizer,
+                rate_limit: Arc::new(Mutex::new(None))
+            })
+        }
+        else {
+            Err(BotClientBuilderError::NoToken)
+        }
+    }
+}
+
+impl Default for BotClientBuilder {
+    fn default() -> BotClientBuilder {
+        BotClientBuilder::new()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{body_json_string, body_string, header, method, path, query_param};
+    use crate::model::challenge::{ChallengeBuilder, ChallengeColor, ChallengePerf, ChallengeStatus};
+
+    use crate::model::external_engine::UciVariant;
+    use crate::model::game::chat::ChatLine;
+    use crate::model::game::event::{ChatLineEvent, OpponentGoneEvent};
+    use crate::model::game::{Color, Speed};
+    use crate::model::TimeControl;
+    use crate::model::user::{PlayTime, User, UserProfileStats};
+    use crate::model::user::preferences::{
+        AutoQueen,
+        AutoThreefold,
+        CastlingMethod,
+        ChallengeFilter,
+        ClockTenths,
+        Coordinates,
+        InsightShare,
+        MessageFilter,
+        MoreTime,
+        MoveConfirmations,
+        MoveEvent,
+        PieceAnimation,
+        Replay,
+        TakeBack,
+        ZenMode
+    };
+    use crate::test_util;
+
+    use super::*;
+
+    #[test]
+    fn building_bot_client_fails_without_token() {
+        let result = BotClientBuilder::new().build();
+
+        assert!(matches!(result, Err(BotClientBuilderError::NoToken)));
+    }
+
+    #[test]
+    fn building_bot_client_fails_with_invalid_token() {
+        let result = BotClientBuilder::new()
+            .with_token("\0")
+            .build();
+
+        assert!(matches!(result, Err(BotClientBuilderError::InvalidToken(_))));
+    }
+
+    #[test]
+    fn building_bot_client_succeeds_with_valid_token_and_default_base_url() {
+        let result = BotClientBuilder::new()
+            .with_token("abc123")
+            .build();
+
+        assert_that!(&result).is_ok();
+        assert_that!(result.unwrap().base_url.as_ref()).is_equal_to(DEFAULT_BASE_URL);
+    }
+
+    #[test]
+    fn building_bot_client_succeeds_with_valid_token_and_overridden_base_url() {
+        let base_url = "https://base.url/path";
+        let result = BotClientBuilder::new()
+            .with_token("abc123")
+            .with_base_url(base_url)
+            .build();
+
+        assert_that!(&result).is_ok();
+        assert_that!(result.unwrap().base_url.as_ref()).is_equal_to(base_url);
+    }
+
+    #[test]
+    fn building_bot_client_reads_token_from_environment_variable() {
+        let name = "LIBOT_TEST_BUILDER_TOKEN";
+        std::env::set_var(name, "abc123");
+
+        let result = BotClientBuilder::new()
+            .with_token_from_env(name)
+            .build();
+
+        std::env::remove_var(name);
+
+        assert_that!(result).is_ok();
+    }
+
+    #[test]
+    fn building_bot_client_fails_with_missing_token_environment_variable() {
+        let result = BotClientBuilder::new()
+            .with_token_from_env("LIBOT_TEST_MISSING_TOKEN")
+            .build();
+
+        assert!(matches!(result,
+            Err(BotClientBuilderError::TokenEnvironmentVariable { name, .. })
+                if name == "LIBOT_TEST_MISSING_TOKEN"));
+    }
+
+    #[tokio::test]
+    async fn building_bot_client_reads_trimmed_token_from_file() {
+        let token_path = std::env::temp_dir()
+            .join(format!("libot-test-token-{}", std::process::id()));
+        std::fs::write(&token_path, "abc123\n").unwrap();
+        let mock_server = MockServer::start().await;
+        let client = BotClientBuilder::new()
+            .with_token_file(&token_path)
+            .with_base_url(mock_server.uri())
+            .build()
+            .unwrap();
+        std::fs::remove_file(&token_path).unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/account"))
+            .and(header("Authorization", "Bearer abc123"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let _ = client.get_my_profile().await;
+    }
+
+    #[test]
+    fn building_bot_client_fails_with_missing_token_file() {
+        let result = BotClientBuilder::new()
+            .with_token_file("/nonexistent/libot/token")
+            .build();
+
+        assert!(matches!(result, Err(BotClientBuilderError::TokenFile { .. })));
+    }
+
+    #[test]
+    fn builder_and_client_debug_output_does_not_contain_token() {
+        let token = "secret-token-abc123";
+        let builder = BotClientBuilder::new().with_token(token);
+        let builder_debug = format!("{:?}", builder);
+        let client_debug = format!("{:?}", builder.build().unwrap());
+
+        assert_that!(builder_debug.contains(token)).is_false();
+        assert_that!(builder_debug.contains("<redacted>")).is_true();
+        assert_that!(client_debug.contains(token)).is_false();
+    }
+
+    #[test]
+    fn applying_config_moves_token_into_builder() {
+        let token = "secret-token-abc123";
+        let mut config = BotConfig {
+            token: Some(Secret::new(token)),
+            ..BotConfig::default()
+        };
+        let builder = BotClientBuilder::new().with_config(&mut config);
+
+        assert_that!(config.token).is_none();
+        assert_that!(format!("{:?}", builder).contains(token)).is_false();
+        assert_that!(builder.build()).is_ok();
+    }
+
+    #[test]
+    fn building_bot_client_uses_bot_api_by_default() {
+        let client = BotClientBuilder::new()
+            .with_token("abc123")
+            .build()
+            .unwrap();
+
+        assert_that!(client.api_mode()).is_equal_to(ApiMode::Bot);
+        assert_that!(client.game_path("/game/testId/abort").as_str())
+            .is_equal_to("/bot/game/testId/abort");
+    }
+
+    #[test]
+    fn joining_url_works_if_no_slash_is_present() {
+        let base_url = "https://base.url/path";
+        let path = "sub/path";
+
+        let url = join_url(base_url, path);
+
+        assert_that!(url.as_str()).is_equal_to("https://base.url/path/sub/path");
+    }
+
+    #[test]
+    fn joining_url_works_if_base_url_has_slash() {
+        let base_url = "https://lichess.org/";
+        let path = "my/path";
+
+        let url = join_url(base_url, path);
+
+        assert_that!(url.as_str()).is_equal_to("https://lichess.org/my/path");
+    }
+
+    #[test]
+    fn joining_url_works_if_base_path_has_slash() {
+        let base_url = "https://lichess.org/api";
+        let path = "/sub/path";
+
+        let url = join_url(base_url, path);
+
+        assert_that!(url.as_str()).is_equal_to("https://lichess.org/api/sub/path");
+    }
+
+    #[test]
+    fn joining_url_works_if_both_have_slash() {
+        let base_url = "https://lichess.org/api/";
+        let path = "/bot/whatever";
+
+        let url = join_url(base_url, path);
+
+        assert_that!(url.as_str()).is_equal_to("https://lichess.org/api/bot/whatever");
+    }
+
+    fn minimal_challenge() -> Challenge {
+        Challenge {
+            id: "testId".to_owned(),
+            url: "testUrl".to_owned(),
+            status: ChallengeStatus::Created,
+            challenger: User {
+                rating: None,
+                provisional: false,
+                online: false,
+                id: "testChallengerId".to_owned(),
+                name: "testChallengerName".to_owned(),
+                title: None,
+                patron: false
+            },
+            dest_user: None,
+            variant: None,
+            rated: false,
+            speed: Speed::Correspondence,
+            time_control: TimeControl::Unlimited,
+            color: ChallengeColor::Random,
+            perf: ChallengePerf {
+                icon: None,
+                name: None
+            },
+            direction: None,
+            initial_fen: None,
+            rematch_of: None,
+            decline: None
+        }
+    }
+
+    #[rstest]
+    #[case::empty(
+        r#"{
+            "in": [],
+            "out": []
+        }"#,
+        Challenges {
+            incoming: Vec::new(),
+            outgoing: Vec::new()
+        }
+    )]
+    #[case::incoming(
+        r#"{
+            "in": [
+                {
+                    "id": "testId",
+                    "url": "testUrl",
+                    "status": "created",
+                    "challenger": {
+                        "id": "testChallengerId",
+                        "name": "testChallengerName"
+                    },
+                    "variant": { },
+                    "rated": false,
+                    "speed": "correspondence",
+                    "timeControl": {
+                        "type": "unlimited"
+                    },
+                    "color": "random",
+                    "perf": {}
+                }
+            ],
+            "out": []
+        }"#,
+        Challenges {
+            incoming: vec![minimal_challenge()],
+            outgoing: Vec::new()
+        }
+    )]
+    #[case::outgoing(
+        r#"{
+            "in": [],
+            "out": [
+                {
+                    "id": "testId",
+                    "url": "testUrl",
+                    "status": "created",
+                    "challenger": {
+                        "id": "testChallengerId",
+                        "name": "testChallengerName"
+                    },
+                    "variant": { },
+                    "rated": false,
+                    "speed": "correspondence",
+                    "timeControl": {
+                        "type": "unlimited"
+                    },
+                    "color": "random",
+                    "perf": {}
+                }
+            ]
+        }"#,
+        Challenges {
+            incoming: Vec::new(),
+            outgoing: vec![minimal_challenge()],
+        }
+    )]
+    fn get_pending_challenges(#[case] json: &str, #[case] expected_challenges: Challenges) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/challenge"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(json))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.get_pending_challenges().await;
+
+            assert_that!(result).contains_value(expected_challenges);
+        });
+    }
+
+    #[test]
+    fn create_challenge_sends_form_and_parses_challenge() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let json = r#"{
+                "id": "testId",
+                "url": "testUrl",
+                "status": "created",
+                "challenger": {
+                    "id": "testChallengerId",
+                    "name": "testChallengerName"
+                },
+                "variant": { },
+                "rated": false,
+                "speed": "correspondence",
+                "timeControl": {
+                    "type": "unlimited"
+                },
+                "color": "random",
+                "perf": {}
+            }"#;
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testUser"))
+                .and(body_string(
+                    "rated=false&clock.limit=60&clock.increment=1&color=black&variant=standard"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(json))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let request = ChallengeBuilder::new()
+                .with_clock(60, 1)
+                .with_color(ChallengeColor::Black)
+                .build()
+                .unwrap();
+            let result = client.create_challenge("testUser", &request).await;
+
+            assert_that!(result).contains_value(minimal_challenge());
+        });
+    }
+
+    const CHALLENGE_LINE: &str = concat!(
+        r#"{"id":"testId","url":"testUrl","status":"created","#,
+        r#""challenger":{"id":"testChallengerId","name":"testChallengerName"},"variant":{},"#,
+        r#""rated":false,"speed":"correspondence","timeControl":{"type":"unlimited"},"#,
+        r#""color":"random","perf":{}}"#);
+
+    #[rstest]
+    #[case::accepted(r#"{"done":"accepted"}"#, ChallengeOutcome::Accepted("testId".to_owned()))]
+    #[case::declined(
+        r#"{"done":"declined"}"#, ChallengeOutcome::Declined(DeclineReason::TooFast))]
+    #[case::canceled(r#"{"done":"canceled"}"#, ChallengeOutcome::Canceled)]
+    fn kept_alive_challenge_resolves_to_outcome(#[case] done_line: &str,
+            #[case] expected_outcome: ChallengeOutcome) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let declined_challenge = CHALLENGE_LINE.replace(
+                r#""status":"created""#, r#""status":"declined","declineReasonKey":"tooFast""#);
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testUser"))
+                .and(body_string(concat!("rated=false&clock.limit=60&clock.increment=1&",
+                    "color=black&variant=standard&keepAliveStream=true")))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(format!("{CHALLENGE_LINE}\n\n\n{done_line}\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/challenge/testId/show"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(declined_challenge))
+                .mount(&server)
+                .await;
+
+            let request = ChallengeBuilder::new()
+                .with_clock(60, 1)
+                .with_color(ChallengeColor::Black)
+                .build()
+                .unwrap();
+            let challenge = client.create_challenge_kept_alive("testUser", &request).await
+                .unwrap();
+
+            assert_that!(challenge.challenge()).is_equal_to(&minimal_challenge());
+            assert_that!(challenge.outcome().await).contains_value(expected_outcome);
+        });
+    }
+
+    #[rstest]
+    #[case::before_challenge("", None)]
+    #[case::before_outcome(CHALLENGE_LINE, Some("testId"))]
+    fn kept_alive_challenge_fails_if_stream_ends(#[case] body: &str,
+            #[case] expected_challenge_id: Option<&str>) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testUser"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(format!("{body}\n")))
+                .mount(&server)
+                .await;
+
+            let request = ChallengeBuilder::new().build().unwrap();
+            let result = match client.create_challenge_kept_alive("testUser", &request).await {
+                Ok(challenge) => challenge.outcome().await,
+                Err(error) => Err(error)
+            };
+
+            assert!(matches!(result,
+                Err(LibotRequestError::ChallengeStreamEnded { challenge_id })
+                    if challenge_id.as_deref() == expected_challenge_id));
+        });
+    }
+
+    #[rstest]
+    #[case::without_rating_range(180, 2, false, Variant::Standard, ChallengeColor::Random, None,
+        "rated=false&time=3.0&increment=2&variant=standard&color=random")]
+    #[case::with_rating_range(30, 0, true, Variant::Atomic, ChallengeColor::White,
+        Some(1500..=1800),
+        "rated=true&time=0.5&increment=0&variant=atomic&color=white&ratingRange=1500-1800")]
+    fn create_seek_sends_form_and_waits_until_closed(#[case] time: Seconds,
+            #[case] increment: Seconds, #[case] rated: bool, #[case] variant: Variant,
+            #[case] color: ChallengeColor, #[case] rating_range: Option<RangeInclusive<Rating>>,
+            #[case] expected_body: &str) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/board/seek"))
+                .and(body_string(expected_body))
+                .respond_with(ResponseTemplate::new(200).set_body_string("\n\n"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let seek = client.create_seek(time, increment, rated, variant, color, rating_range)
+                .await
+                .unwrap();
+
+            assert_that!(seek.closed().await).is_ok();
+        });
+    }
+
+    #[rstest]
+    #[case::default_perspective(None, "/game/export/gif/testGameId.gif")]
+    #[case::black_perspective(Some(Color::Black), "/game/export/gif/black/testGameId.gif")]
+    fn get_game_gif_returns_bytes(#[case] color: Option<Color>, #[case] expected_path: &str) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path(expected_path))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(b"GIF89a".to_vec()))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.get_game_gif("testGameId".to_owned(), color).await;
+
+            assert_that!(result).contains_value(b"GIF89a".to_vec());
+        });
+    }
+
+    #[test]
+    fn export_user_games_pgn_requests_pgn_since_timestamp() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/games/user/testUser"))
+                .and(query_param("since", "1234"))
+                .and(header("Accept", "application/x-chess-pgn"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("1. e4 *\n"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.export_user_games_pgn("testUser", Some(1234)).await;
+
+            assert_that!(result).contains_value("1. e4 *\n".to_owned());
+        });
+    }
+
+    #[test]
+    fn create_seek_fails_if_rejected() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/board/seek"))
+                .respond_with(ResponseTemplate::new(400))
+                .mount(&server)
+                .await;
+
+            let result = client.create_seek(
+                60, 0, false, Variant::Standard, ChallengeColor::Random, None).await;
+
+            assert_that!(result).is_err();
+        });
+    }
+
+    #[test]
+    fn accept_challenge_success() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testChallengeId/accept"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.accept_challenge("testChallengeId".to_owned()).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[rstest]
+    #[case::not_found(404, r#"{"error":"Not found"}"#, ChallengeAcceptError::NotFound)]
+    #[case::already_accepted(
+        400,
+        r#"{"error":"This challenge has already been accepted"}"#,
+        ChallengeAcceptError::AlreadyAccepted
+    )]
+    #[case::rate_limited(429, "", ChallengeAcceptError::RateLimited)]
+    fn accept_challenge_failure_is_typed(#[case] status: u16, #[case] body: &str,
+            #[case] expected: ChallengeAcceptError) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testChallengeId/accept"))
+                .respond_with(ResponseTemplate::new(status).set_body_string(body))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.accept_challenge("testChallengeId".to_owned()).await;
+
+            assert!(matches!(result,
+                Err(LibotRequestError::ChallengeAcceptError(error)) if error == expected));
+        });
+    }
+
+    #[test]
+    fn accept_challenge_with_unknown_failure_returns_api_error() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testChallengeId/accept"))
+                .respond_with(ResponseTemplate::new(400)
+                    .set_body_string(r#"{"error":"Something else"}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.accept_challenge("testChallengeId".to_owned()).await;
+
+            assert!(matches!(result, Err(LibotRequestError::ApiError { status, .. })
+                if status == StatusCode::BAD_REQUEST));
+        });
+    }
+
+    #[test]
+    fn decline_challenge_success_without_reason() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testChallengeId/decline"))
+                .and(body_json_string("{}"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.decline_challenge("testChallengeId".to_owned(), None).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn decline_challenge_success_with_reason() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/challenge/testChallengeId/decline"))
+                .and(body_json_string("{\"reason\":\"generic\"}"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.decline_challenge(
+                "testChallengeId".to_owned(), Some(DeclineReason::Generic)).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn make_move(#[case] offer_draw: bool) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/testMove"))
+                .and(query_param("offeringDraw", offer_draw.to_string()))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result =
+                client.make_move("testGameId".to_owned(), "testMove".to_owned(), offer_draw).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn make_move_uses_board_api_in_board_mode() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let client = BotClientBuilder::new()
+                .with_token("mock_token")
+                .with_base_url(server.uri())
+                .with_api_mode(ApiMode::Board)
+                .build()
+                .unwrap();
+
+            Mock::given(method("POST"))
+                .and(path("/board/game/testGameId/move/testMove"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result =
+                client.make_move("testGameId".to_owned(), "testMove".to_owned(), false).await;
+
+            assert_that!(client.api_mode()).is_equal_to(ApiMode::Board);
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[rstest]
+    #[case::empty("[]", vec![].into())]
+    #[case::single_entry(
+        r#"[
+            {
+                "username": "testUsername",
+                "text": "testText"
+            }
+        ]"#,
+        vec![
+            ChatLine {
+                room: ChatRoom::Player,
+                username: "testUsername".to_owned(),
+                text: "testText".to_owned()
+            }
+        ].into()
+    )]
+    #[case::multiple_entries(
+        r#"[
+            {
+                "username": "testUsername1",
+                "text": "testText1"
+            },
+            {
+                "username": "testUsername2",
+                "text": "testText2"
+            }
+        ]"#,
+        vec![
+            ChatLine {
+                room: ChatRoom::Player,
+                username: "testUsername1".to_owned(),
+                text: "testText1".to_owned()
+            },
+            ChatLine {
+                room: ChatRoom::Player,
+                username: "testUsername2".to_owned(),
+                text: "testText2".to_owned()
+            }
+        ].into()
+    )]
+    fn get_game_chat(#[case] json: &str, #[case] expected_chat_history: ChatHistory) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/testGameId/chat"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(json))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.get_game_chat("testGameId".to_owned()).await;
+
+            assert_that!(result).contains_value(expected_chat_history);
+        });
+    }
+
+    #[test]
+    fn get_new_chat_since() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let old_history = ChatHistory::from(vec![
+                ChatLine {
+                    room: ChatRoom::Player,
+                    username: "testUsername1".to_owned(),
+                    text: "testText1".to_owned()
+                }
+            ]);
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/testGameId/chat"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"[
+                        {
+                            "username": "testUsername1",
+                            "text": "testText1"
+                        },
+                        {
+                            "room": "spectator",
+                            "username": "testUsername2",
+                            "text": "testText2"
+                        }
+                    ]"#))
+                .expect(2)
+                .mount(&server)
+                .await;
+
+            let new_lines = client
+                .get_new_chat_since("testGameId".to_owned(), old_history.marker()).await.unwrap();
+            let no_new_lines = client
+                .get_new_chat_since("testGameId".to_owned(), new_lines.marker).await.unwrap();
+
+            assert_that!(new_lines.lines).contains_exactly_in_given_order([
+                ChatLine {
+                    room: ChatRoom::Spectator,
+                    username: "testUsername2".to_owned(),
+                    text: "testText2".to_owned()
+                }
+            ]);
+            assert_that!(no_new_lines.lines).is_empty();
+            assert_that!(no_new_lines.marker).is_equal_to(new_lines.marker);
+        });
+    }
+
+    #[test]
+    fn send_chat_message() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/chat"))
+                .and(body_string("room=player&text=testText"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client
+                .send_chat_message("testGameId".to_owned(), ChatRoom::Player, "testText").await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    fn throttled_client(server: &MockServer, config: ChatThrottleConfig) -> BotClient {
+        BotClientBuilder::new()
+            .with_token("mock_token")
+            .with_base_url(server.uri())
+            .with_chat_throttle(config
+                .with_per_game_interval(Duration::from_millis(50))
+                .with_global_interval(Duration::ZERO))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn send_chat_message_queues_and_coalesces_throttled_messages() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let client = throttled_client(&server, ChatThrottleConfig::new());
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/chat"))
+                .and(body_string("room=player&text=hello"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/chat"))
+                .and(body_string("room=player&text=good+luck"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let (first, second, third) = futures::join!(
+                client.send_chat_message("testGameId".to_owned(), ChatRoom::Player, "hello"),
+                client.send_chat_message("testGameId".to_owned(), ChatRoom::Player, "good"),
+                client.send_chat_message("testGameId".to_owned(), ChatRoom::Player, "luck"));
+
+            assert_that!(first).is_ok();
+            assert_that!(second).is_ok();
+            assert_that!(third).is_ok();
+        });
+    }
+
+    #[test]
+    fn send_chat_message_rejects_throttled_message_without_queuing() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let client =
+                throttled_client(&server, ChatThrottleConfig::new().with_max_queue_delay(None));
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/chat"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let first =
+                client.send_chat_message("testGameId".to_owned(), ChatRoom::Player, "hello").await;
+            let second =
+                client.send_chat_message("testGameId".to_owned(), ChatRoom::Player, "hello").await;
+
+            assert_that!(first).is_ok();
+            assert!(matches!(second, Err(LibotRequestError::ChatThrottled { .. })));
+        });
+    }
+
+    #[test]
+    fn send_request_raw_returns_unsuccessful_response_with_headers() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/api/custom"))
+                .and(body_string("testBody"))
+                .respond_with(ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "30")
+                    .set_body_string(r#"{"error":"Too many requests"}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let response = client
+                .send_request_raw(Method::POST, "/api/custom", Some("testBody".to_owned()))
+                .await
+                .unwrap();
+
+            assert_that!(response.status).is_equal_to(StatusCode::TOO_MANY_REQUESTS);
+            assert_that!(response.headers.get("retry-after").cloned())
+                .contains(HeaderValue::from_static("30"));
+            assert_that!(response.text().into_owned())
+                .is_equal_to(r#"{"error":"Too many requests"}"#.to_owned());
+            assert_that!(response.json::<JsonValue>().unwrap()["error"].as_str())
+                .contains("Too many requests");
+            assert_that!(client.rate_limit_info().and_then(|info| info.retry_after))
+                .contains(Duration::from_secs(30));
+        });
+    }
+
+    #[test]
+    fn rate_limit_info_is_recorded_and_shared_between_clones() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/abort"))
+                .respond_with(ResponseTemplate::new(200)
+                    .insert_header("X-RateLimit-Limit", "30")
+                    .insert_header("X-RateLimit-Remaining", "29"))
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/resign"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&server)
+                .await;
+
+            let clone = client.clone();
+
+            assert_that!(client.rate_limit_info()).is_none();
+
+            client.abort_game("testGameId".to_owned()).await.unwrap();
+            client.resign_game("testGameId".to_owned()).await.unwrap();
+
+            let info = clone.rate_limit_info().unwrap();
+            assert_that!(info.limit).contains(30);
+            assert_that!(info.remaining).contains(29);
+        });
+    }
+
+    #[test]
+    fn prewarm_succeeds_regardless_of_status() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("HEAD"))
+                .and(path("/"))
+                .respond_with(ResponseTemplate::new(404))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            assert_that!(client.prewarm().await).is_ok();
+        });
+    }
+
+    #[test]
+    fn prewarm_fails_if_no_connection_can_be_established() {
+        tokio_test::block_on(async {
+            let client = BotClientBuilder::new()
+                .with_token("testToken")
+                .with_base_url("http://127.0.0.1:1")
+                .with_connection_config(ConnectionConfig::low_latency())
+                .build()
+                .unwrap();
+
+            let result = client.prewarm().await;
+
+            assert!(matches!(result, Err(LibotRequestError::ReqwestError(_))));
+        });
+    }
+
+    fn build_client_with_timeouts(server: &MockServer, timeouts: RequestTimeouts) -> BotClient {
+        BotClientBuilder::new()
+            .with_token("testToken")
+            .with_base_url(server.uri())
+            .with_request_timeouts(timeouts)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn game_action_fails_after_game_action_timeout() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let timeouts = RequestTimeouts::none()
+                .with_timeout(EndpointCategory::GameAction, Some(Duration::from_millis(50)));
+            let client = build_client_with_timeouts(&server, timeouts);
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/resign"))
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+                .mount(&server)
+                .await;
+
+            let result = client.resign_game("testGameId".to_owned()).await;
+
+            assert!(matches!(result, Err(LibotRequestError::ReqwestError(error))
+                if error.is_timeout()));
+        });
+    }
+
+    #[test]
+    fn game_action_timeout_does_not_apply_to_other_requests() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let timeouts = RequestTimeouts::none()
+                .with_timeout(EndpointCategory::GameAction, Some(Duration::from_millis(50)));
+            let client = build_client_with_timeouts(&server, timeouts);
+
+            Mock::given(method("GET"))
+                .and(path("/account/playing"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{"nowPlaying":[]}"#)
+                    .set_delay(Duration::from_millis(200)))
+                .mount(&server)
+                .await;
+
+            let result = client.get_ongoing_games(10).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn game_action_is_not_delayed_by_other_requests_when_dispatch_is_saturated() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let client = BotClientBuilder::new()
+                .with_token("testToken")
+                .with_base_url(server.uri())
+                .with_dispatch(DispatchConfig::new()
+                    .with_max_concurrent_requests(2)
+                    .with_reserved_for_game_actions(1))
+                .build()
+                .unwrap();
+
+            Mock::given(method("GET"))
+                .and(path("/account/playing"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{"nowPlaying":[]}"#)
+                    .set_delay(Duration::from_secs(1)))
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e2e4"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&server)
+                .await;
+
+            let timed_move = async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let start = Instant::now();
+                client.make_move("testGameId".to_owned(), "e2e4".to_owned(), false).await
+                    .unwrap();
+                start.elapsed()
+            };
+            let (_, _, move_duration) = tokio::join!(
+                client.get_ongoing_games(10), client.get_ongoing_games(10), timed_move);
+
+            assert_that!(move_duration).is_less_than(Duration::from_millis(500));
+        });
+    }
+
+    #[test]
+    fn get_ongoing_games() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/account/playing"))
+                .and(query_param("nb", "50"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(r#"{
+                    "nowPlaying": [
+                        { "gameId": "testGameId", "color": "white", "isMyTurn": true }
+                    ]
+                }"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let games = client.get_ongoing_games(50).await.unwrap();
+
+            assert_that!(games.len()).is_equal_to(1);
+            assert_that!(games[0].game_id.as_str()).is_equal_to("testGameId");
+            assert_that!(games[0].is_my_turn).is_true();
+        });
+    }
+
+    #[test]
+    fn abort_game() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/abort"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.abort_game("testGameId".to_owned()).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn resign_game() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/resign"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.resign_game("testGameId".to_owned()).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn claim_victory() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/claim-victory"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.claim_victory("testGameId".to_owned()).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn offer_or_accept_draw() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/draw/yes"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.offer_or_accept_draw("testGameId".to_owned()).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn decline_draw() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/draw/no"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.decline_draw("testGameId".to_owned()).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[rstest]
+    #[case::accept("yes")]
+    #[case::decline("no")]
+    fn respond_to_takeback(#[case] answer: &str) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path(format!("/bot/game/testGameId/takeback/{answer}")))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let game_id = "testGameId".to_owned();
+            let result = match answer {
+                "yes" => client.accept_takeback(game_id).await,
+                _ => client.decline_takeback(game_id).await
+            };
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn add_time() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/round/testGameId/add-time/240"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.add_time("testGameId".to_owned(), 240).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    pub(crate) fn get_test_user_json() -> &'static str {
+        r#"{
+            "id": "testId",
+            "username": "testName",
+            "createdAt": 12345,
+            "seenAt": 23456,
+            "playTime": {
+                "total": 34567,
+                "tv": 4567
+            },
+            "url": "testUrl",
+            "count": {
+                "all": 123,
+                "rated": 234,
+                "ai": 345,
+                "draw": 456,
+                "drawH": 567,
+                "loss": 678,
+                "lossH": 789,
+                "win": 890,
+                "winH": 123,
+                "bookmark": 234,
+                "playing": 345,
+                "import": 456,
+                "me": 567
+            }
+        }"#
+    }
+
+    pub(crate) fn get_test_user() -> UserProfile {
+        UserProfile {
+            id: "testId".to_string(),
+            username: "testName".to_string(),
+            perfs: Default::default(),
+            created_at: 12345,
+            disabled: false,
+            tos_violation: false,
+            profile: Default::default(),
+            seen_at: 23456,
+            patron: false,
+            verified: false,
+            play_time: PlayTime {
+                total: 34567,
+                tv: 4567
+            },
+            title: None,
+            url: "testUrl".to_string(),
+            playing: None,
+            count: UserProfileStats {
+                all: 123,
+                rated: 234,
+                ai: 345,
+                draw: 456,
+                draw_h: 567,
+                loss: 678,
+                loss_h: 789,
+                win: 890,
+                win_h: 123,
+                bookmark: 234,
+                playing: 345,
+                import: 456,
+                me: 567
+            },
+            streaming: false,
+            streamer: None,
+            followable: false,
+            following: false,
+            blocking: false,
+            follows_you: false,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn export_game() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .and(query_param("opening", "true"))
+                .and(body_string("testGameId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{
+                        "id": "testGameId",
+                        "rated": true,
+                        "speed": "blitz",
+                        "createdAt": 1514505150384,
+                        "status": "mate",
+                        "players": {
+                            "white": {
+                                "user": { "id": "white", "name": "White" },
+                                "rating": 1500,
+                                "ratingDiff": 7
+                            },
+                            "black": {
+                                "user": { "id": "black", "name": "Black" },
+                                "rating": 1600,
+                                "ratingDiff": -7
+                            }
+                        },
+                        "winner": "white",
+                        "opening": { "eco": "C20", "name": "King's Pawn Game", "ply": 2 }
+                    }"#.replace('\n', "") + "\n"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let game = client.export_game("testGameId".to_owned()).await.unwrap();
+
+            assert_that!(game.id).is_equal_to("testGameId".to_owned());
+            assert_that!(game.players.white.rating_diff).contains(7);
+            assert_that!(game.winner).contains(Color::White);
+            assert_that!(game.opening.map(|opening| opening.eco)).contains("C20".to_owned());
+        })
+    }
+
+    #[test]
+    fn export_games_streams_id_list() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .and(query_param("opening", "true"))
+                .and(header("accept", "application/x-ndjson"))
+                .and(body_string("game1,game2,game3"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(concat!(r#"{"id":"game1","rated":true,"speed":"blitz","#,
+                        r#""createdAt":1514505150384,"status":"mate","players":{"white":{},"#,
+                        r#""black":{}}}"#, "\n", r#"{"id":"game3","rated":false,"#,
+                        r#""speed":"bullet","createdAt":1514505150384,"status":"draw","#,
+                        r#""players":{"white":{},"black":{}}}"#, "\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let game_ids = (1..=3).map(|index| format!("game{index}"));
+            let games = client.export_games(game_ids).await.unwrap()
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .map(|game| game.unwrap().id)
+                .collect::<Vec<_>>();
+
+            assert_that!(games)
+                .contains_exactly_in_given_order(["game1".to_owned(), "game3".to_owned()]);
+        })
+    }
+
+    #[test]
+    fn export_games_sends_empty_body_for_no_ids() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .and(body_string(""))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let games = client.export_games(Vec::<GameId>::new()).await.unwrap()
+                .collect::<Vec<_>>()
+                .await;
+
+            assert_that!(games).is_empty();
+        })
+    }
+
+    #[test]
+    fn stream_games_by_users() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/stream/games-by-users"))
+                .and(query_param("withCurrentGames", "true"))
+                .and(body_string("user1,user2"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string("{\"id\":\"testGameId\",\"status\":20}\n\n"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let games = client.stream_games_by_users(["user1", "user2"], true).await.unwrap()
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .map(|game| game.unwrap())
+                .collect::<Vec<_>>();
+
+            assert_that!(games).contains_exactly_in_given_order([
+                serde_json::json!({ "id": "testGameId", "status": 20 })
+            ]);
+        })
+    }
+
+    #[test]
+    fn export_game_with_analysis() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .and(query_param("evals", "true"))
+                .and(query_param("accuracy", "true"))
+                .and(body_string("testGameId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(concat!(r#"{"id":"testGameId","rated":false,"#,
+                        r#""speed":"blitz","createdAt":1514505150384,"status":"mate","#,
+                        r#""players":{"white":{"analysis":{"inaccuracy":1,"mistake":2,"#,
+                        r#""blunder":3,"acpl":40,"accuracy":80}},"black":{}}}"#, "\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let game = client.export_game_with_analysis("testGameId".to_owned()).await.unwrap();
+
+            assert_that!(game.players.white.analysis.map(|analysis| analysis.blunder))
+                .contains(3);
+            assert_that!(game.players.black.analysis).is_none();
+        })
+    }
+
+    #[test]
+    fn request_game_analysis() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/testGameId/request-analysis"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            assert_that!(client.request_game_analysis("testGameId".to_owned()).await).is_ok();
+        })
+    }
+
+    fn exported_game_json(analysis: &str) -> String {
+        format!(concat!(r#"{{"id":"testGameId","rated":false,"speed":"blitz","#,
+            r#""createdAt":1514505150384,"status":"mate","players":{{"white":{{{}}},"#,
+            r#""black":{{{}}}}}}}"#, "\n"), analysis, analysis)
+    }
+
+    #[test]
+    fn wait_for_game_analysis_polls_until_analysis_is_attached() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let analysis = r#""analysis":{"inaccuracy":0,"mistake":0,"blunder":1,"acpl":20}"#;
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(exported_game_json("")))
+                .up_to_n_times(2)
+                .expect(2)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(exported_game_json(analysis)))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let game = client.wait_for_game_analysis("testGameId".to_owned(),
+                Duration::from_millis(10), Duration::from_secs(10)).await.unwrap();
+
+            assert_that!(game.and_then(|game| game.players.black.analysis)
+                .map(|analysis| analysis.blunder)).contains(1);
+        })
+    }
+
+    #[test]
+    fn wait_for_game_analysis_times_out() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(exported_game_json("")))
+                .mount(&server)
+                .await;
+
+            let game = client.wait_for_game_analysis("testGameId".to_owned(),
+                Duration::from_millis(10), Duration::from_millis(50)).await.unwrap();
+
+            assert_that!(game).is_none();
+        })
+    }
+
+    #[test]
+    fn get_crosstable() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/crosstable/testBot/testOpponent"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{"users":{"testbot":2.5,"testopponent":0.5},"nbGames":3}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let crosstable = client.get_crosstable("testBot", "testOpponent").await.unwrap();
+
+            assert_that!(crosstable.games).is_equal_to(3);
+            assert_that!(crosstable.users.get("testbot").copied()).contains(2.5);
+        })
+    }
+
+    #[test]
+    fn get_tv_channels() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/tv/channels"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                    r#"{"bot":{"user":{"id":"testbot","name":"TestBot"},"rating":2000,"#,
+                    r#""gameId":"testGameId","color":"white"}}"#)))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let channels = client.get_tv_channels().await.unwrap();
+
+            assert_that!(channels.get("bot").map(|game| game.game_id.as_str()))
+                .contains("testGameId");
+        })
+    }
+
+    const TEST_EXTERNAL_ENGINE_JSON: &str = r#"{
+        "id": "testEngineId",
+        "name": "Engine",
+        "clientSecret": "testClientSecret",
+        "userId": "testUserId",
+        "maxThreads": 4,
+        "maxHash": 512,
+        "variants": ["chess"],
+        "providerData": null
+    }"#;
+
+    fn test_registration() -> ExternalEngineRegistration {
+        ExternalEngineRegistration {
+            name: "Engine".to_owned(),
+            max_threads: 4,
+            max_hash: 512,
+            variants: vec![UciVariant::Chess],
+            provider_secret: "testProviderSecret".to_owned(),
+            provider_data: None
+        }
+    }
+
+    #[test]
+    fn get_cloud_eval() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/cloud-eval"))
+                .and(query_param("fen", "testFen"))
+                .and(query_param("multiPv", "2"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{"fen":"testFen","knodes":1,"depth":20,"pvs":[]}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let evaluation = client.get_cloud_eval("testFen", 2).await.unwrap();
+
+            assert_that!(evaluation.map(|evaluation| evaluation.depth)).contains(20);
+        })
+    }
+
+    #[test]
+    fn get_cloud_eval_returns_none_if_not_found() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/cloud-eval"))
+                .respond_with(ResponseTemplate::new(404)
+                    .set_body_string(r#"{"error":"No cloud evaluation available for that position"}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let evaluation = client.get_cloud_eval("testFen", 1).await;
+
+            assert_that!(evaluation).contains_value(None);
+        })
+    }
+
+    #[test]
+    fn list_external_engines() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("GET"))
-                .and(path("/challenge"))
+                .and(path("/external-engine"))
                 .respond_with(ResponseTemplate::new(200)
-                    .set_body_string(json))
+                    .set_body_string(format!("[{TEST_EXTERNAL_ENGINE_JSON}]")))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.get_pending_challenges().await;
+            let engines = client.list_external_engines().await.unwrap();
 
-            assert_that!(result).contains_value(expected_challenges);
-        });
+            assert_that!(engines.len()).is_equal_to(1);
+            assert_that!(engines[0].id.as_str()).is_equal_to("testEngineId");
+        })
     }
 
-    #[test]
-    fn accept_challenge_success() {
+    #[rstest]
+    #[case::create("POST", "/external-engine")]
+    #[case::update("PUT", "/external-engine/testEngineId")]
+    fn register_external_engine(#[case] expected_method: &str, #[case] expected_path: &str) {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
+            let expected_body = r#"{"name":"Engine","maxThreads":4,"maxHash":512,"variants":["chess"],"providerSecret":"testProviderSecret"}"#;
 
-            Mock::given(method("POST"))
-                .and(path("/challenge/testChallengeId/accept"))
-                .respond_with(ResponseTemplate::new(200))
+            Mock::given(method(expected_method))
+                .and(path(expected_path))
+                .and(body_json_string(expected_body))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(TEST_EXTERNAL_ENGINE_JSON))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.accept_challenge("testChallengeId".to_owned()).await;
+            let registration = test_registration();
+            let engine = match expected_method {
+                "POST" => client.create_external_engine(&registration).await,
+                _ => client.update_external_engine("testEngineId", &registration).await
+            };
 
-            assert_that!(result).is_ok();
-        });
+            assert_that!(engine.unwrap().client_secret.as_str()).is_equal_to("testClientSecret");
+        })
     }
 
     #[test]
-    fn decline_challenge_success_without_reason() {
+    fn get_external_engine() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
-            Mock::given(method("POST"))
-                .and(path("/challenge/testChallengeId/decline"))
-                .and(body_json_string("{}"))
-                .respond_with(ResponseTemplate::new(200))
+            Mock::given(method("GET"))
+                .and(path("/external-engine/testEngineId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(TEST_EXTERNAL_ENGINE_JSON))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.decline_challenge("testChallengeId".to_owned(), None).await;
+            let engine = client.get_external_engine("testEngineId").await.unwrap();
 
-            assert_that!(result).is_ok();
-        });
+            assert_that!(engine.max_threads).is_equal_to(4);
+        })
     }
 
     #[test]
-    fn decline_challenge_success_with_reason() {
+    fn delete_external_engine() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
-            Mock::given(method("POST"))
-                .and(path("/challenge/testChallengeId/decline"))
-                .and(body_json_string("{\"reason\":\"generic\"}"))
+            Mock::given(method("DELETE"))
+                .and(path("/external-engine/testEngineId"))
                 .respond_with(ResponseTemplate::new(200))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.decline_challenge(
-                "testChallengeId".to_owned(), Some(DeclineReason::Generic)).await;
+            let result = client.delete_external_engine("testEngineId").await;
 
             assert_that!(result).is_ok();
-        });
+        })
     }
 
-    #[rstest]
-    #[case(false)]
-    #[case(true)]
-    fn make_move(#[case] offer_draw: bool) {
+    #[test]
+    fn get_profile() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
-            Mock::given(method("POST"))
-                .and(path("/bot/game/testGameId/move/testMove"))
-                .and(query_param("offeringDraw", offer_draw.to_string()))
-                .respond_with(ResponseTemplate::new(200))
+            Mock::given(method("GET"))
+                .and(path("/user/testId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(get_test_user_json()))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result =
-                client.make_move("testGameId".to_owned(), "testMove".to_owned(), offer_draw).await;
+            let result = client.get_profile("testId".to_owned()).await;
 
-            assert_that!(result).is_ok();
-        });
+            assert_that!(result).contains_value(get_test_user());
+        })
     }
 
-    #[rstest]
-    #[case::empty("[]", vec![])]
-    #[case::single_entry(
-        r#"[
-            {
-                "username": "testUsername",
-                "text": "testText"
-            }
-        ]"#,
-        vec![
-            ChatLine {
-                username: "testUsername".to_owned(),
-                text: "testText".to_owned()
-            }
-        ]
-    )]
-    #[case::multiple_entries(
-        r#"[
-            {
-                "username": "testUsername1",
-                "text": "testText1"
-            },
-            {
-                "username": "testUsername2",
-                "text": "testText2"
-            }
-        ]"#,
-        vec![
-            ChatLine {
-                username: "testUsername1".to_owned(),
-                text: "testText1".to_owned()
-            },
-            ChatLine {
-                username: "testUsername2".to_owned(),
-                text: "testText2".to_owned()
-            }
-        ]
-    )]
-    fn get_game_chat(#[case] json: &str, #[case] expected_chat_history: ChatHistory) {
+    #[test]
+    fn get_my_profile() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("GET"))
-                .and(path("/bot/game/testGameId/chat"))
+                .and(path("/account"))
                 .respond_with(ResponseTemplate::new(200)
-                    .set_body_string(json))
+                    .set_body_string(get_test_user_json()))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.get_game_chat("testGameId".to_owned()).await;
+            let result = client.get_my_profile().await;
 
-            assert_that!(result).contains_value(expected_chat_history);
-        });
+            assert_that!(result).contains_value(get_test_user());
+        })
     }
 
     #[test]
-    fn send_chat_message() {
+    fn send_team_message() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("POST"))
-                .and(path("/bot/game/testGameId/chat"))
-                .and(body_string("room=player&text=testText"))
+                .and(path("/team/testTeamId/pm-all"))
+                .and(body_string("message=Arena+starts+soon%21"))
                 .respond_with(ResponseTemplate::new(200))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client
-                .send_chat_message("testGameId".to_owned(), ChatRoom::Player, "testText").await;
+            let result = client.send_team_message("testTeamId", "Arena starts soon!").await;
 
             assert_that!(result).is_ok();
-        });
+        })
     }
 
     #[test]
-    fn abort_game() {
+    fn get_tournament() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let json = r#"{"id":"testTournamentId","fullName":"Test Arena","isFinished":true}"#;
+
+            Mock::given(method("GET"))
+                .and(path("/tournament/testTournamentId"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(json))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = client.get_tournament("testTournamentId").await;
+
+            assert_that!(result.map(|tournament| tournament.is_finished)).contains_value(true);
+        })
+    }
+
+    #[rstest]
+    #[case::public(None, "")]
+    #[case::private(Some("testPassword"), "password=testPassword")]
+    fn join_tournament(#[case] password: Option<&str>, #[case] expected_body: &str) {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("POST"))
-                .and(path("/bot/game/testGameId/abort"))
+                .and(path("/tournament/testTournamentId/join"))
+                .and(body_string(expected_body))
                 .respond_with(ResponseTemplate::new(200))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.abort_game("testGameId".to_owned()).await;
+            let result = client.join_tournament("testTournamentId", password).await;
 
             assert_that!(result).is_ok();
-        });
+        })
     }
 
     #[test]
-    fn resign_game() {
+    fn push_broadcast_pgn() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
+            let pgn = "[White \"TestBot\"]\n\n1. e4 e5 *\n";
 
             Mock::given(method("POST"))
-                .and(path("/bot/game/testGameId/resign"))
-                .respond_with(ResponseTemplate::new(200))
+                .and(path("/broadcast/round/testRoundId/push"))
+                .and(body_string(pgn))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{"games":[{"tags":{"White":"TestBot"},"moves":2}]}"#))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.resign_game("testGameId".to_owned()).await;
+            let push = client.push_broadcast_pgn("testRoundId", pgn).await.unwrap();
 
-            assert_that!(result).is_ok();
-        });
+            assert_that!(&push.games).has_length(1);
+            assert_that!(push.games[0].moves).contains(2);
+        })
     }
 
     #[test]
-    fn offer_or_accept_draw() {
+    fn withdraw_from_tournament() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("POST"))
-                .and(path("/bot/game/testGameId/draw/yes"))
+                .and(path("/tournament/testTournamentId/withdraw"))
                 .respond_with(ResponseTemplate::new(200))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.offer_or_accept_draw("testGameId".to_owned()).await;
+            let result = client.withdraw_from_tournament("testTournamentId").await;
 
             assert_that!(result).is_ok();
-        });
+        })
     }
 
     #[test]
-    fn decline_draw() {
+    fn berserk() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("POST"))
-                .and(path("/bot/game/testGameId/draw/no"))
+                .and(path("/board/game/testGameId/berserk"))
                 .respond_with(ResponseTemplate::new(200))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.decline_draw("testGameId".to_owned()).await;
+            let result = client.berserk("testGameId".to_owned()).await;
 
             assert_that!(result).is_ok();
-        });
+        })
     }
 
     #[test]
-    fn add_time() {
+    fn upgrade_to_bot_account() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("POST"))
-                .and(path("/round/testGameId/add-time/240"))
+                .and(path("/bot/account/upgrade"))
                 .respond_with(ResponseTemplate::new(200))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.add_time("testGameId".to_owned(), 240).await;
+            let result = client.upgrade_to_bot_account().await;
 
             assert_that!(result).is_ok();
-        });
+        })
     }
 
-    fn get_test_user_json() -> &'static str {
-        r#"{
-            "id": "testId",
-            "username": "testName",
-            "createdAt": 12345,
-            "seenAt": 23456,
-            "playTime": {
-                "total": 34567,
-                "tv": 4567
-            },
-            "url": "testUrl",
-            "count": {
-                "all": 123,
-                "rated": 234,
-                "ai": 345,
-                "draw": 456,
-                "drawH": 567,
-                "loss": 678,
-                "lossH": 789,
-                "win": 890,
-                "winH": 123,
-                "bookmark": 234,
-                "playing": 345,
-                "import": 456,
-                "me": 567
-            }
-        }"#
-    }
+    #[test]
+    fn stream_raw_events_skips_empty_lines() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
 
-    fn get_test_user() -> UserProfile {
-        UserProfile {
-            id: "testId".to_string(),
-            username: "testName".to_string(),
-            perfs: Default::default(),
-            created_at: 12345,
-            disabled: false,
-            tos_violation: false,
-            profile: Default::default(),
-            seen_at: 23456,
-            patron: false,
-            verified: false,
-            play_time: PlayTime {
-                total: 34567,
-                tv: 4567
-            },
-            title: None,
-            url: "testUrl".to_string(),
-            playing: None,
-            count: UserProfileStats {
-                all: 123,
-                rated: 234,
-                ai: 345,
-                draw: 456,
-                draw_h: 567,
-                loss: 678,
-                loss_h: 789,
-                win: 890,
-                win_h: 123,
-                bookmark: 234,
-                playing: 345,
-                import: 456,
-                me: 567
-            },
-            streaming: false,
-            streamer: None,
-            followable: false,
-            following: false,
-            blocking: false,
-            follows_you: false,
-        }
+            Mock::given(method("GET"))
+                .and(path("/stream/event"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string("{\"type\":\"unknown\"}\n\n{\"type\":\"gameStart\"}\n"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let events = client.stream_raw_events().await.unwrap()
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .map(|event| event.unwrap())
+                .collect::<Vec<_>>();
+
+            assert_that!(events).contains_exactly_in_given_order([
+                serde_json::json!({ "type": "unknown" }),
+                serde_json::json!({ "type": "gameStart" })
+            ]);
+        })
     }
 
     #[test]
-    fn get_profile() {
+    fn stream_bot_events_parses_events_and_continues_after_unknown_event() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
 
             Mock::given(method("GET"))
-                .and(path("/user/testId"))
-                .respond_with(ResponseTemplate::new(200)
-                    .set_body_string(get_test_user_json()))
+                .and(path("/stream/event"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                    r#"{"type":"unknown"}"#, "\n\n",
+                    r#"{"type":"gameStart","game":{"id":"testId"}}"#, "\n")))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.get_profile("testId".to_owned()).await;
+            let events = client.stream_bot_events().await.unwrap()
+                .collect::<Vec<_>>()
+                .await;
 
-            assert_that!(result).contains_value(get_test_user());
+            assert_that!(&events).has_length(2);
+            assert!(matches!(&events[0], Err(LibotRequestError::JsonError(_))));
+            assert!(matches!(&events[1], Ok(BotEvent::GameStart(game))
+                if game.id.as_deref() == Some("testId")));
         })
     }
 
     #[test]
-    fn get_my_profile() {
+    fn stream_game_events_parses_events() {
         tokio_test::block_on(async {
             let (client, server) = test_util::setup_wiremock_test().await;
+            let chat_line =
+                r#"{"type":"chatLine","room":"player","username":"testUser","text":"hi"}"#;
+            let opponent_gone = r#"{"type":"opponentGone","gone":true}"#;
 
             Mock::given(method("GET"))
-                .and(path("/account"))
+                .and(path("/bot/game/stream/testGameId"))
                 .respond_with(ResponseTemplate::new(200)
-                    .set_body_string(get_test_user_json()))
+                    .set_body_string(format!("{chat_line}\n\n{opponent_gone}\n")))
                 .expect(1)
                 .mount(&server)
                 .await;
 
-            let result = client.get_my_profile().await;
+            let events = client.stream_game_events("testGameId".to_owned()).await.unwrap()
+                .map(|event| event.unwrap())
+                .collect::<Vec<_>>()
+                .await;
 
-            assert_that!(result).contains_value(get_test_user());
+            assert_that!(events).contains_exactly_in_given_order([
+                GameEvent::ChatLine(ChatLineEvent {
+                    chat_line: ChatLine {
+                        room: ChatRoom::Player,
+                        username: "testUser".to_owned(),
+                        text: "hi".to_owned()
+                    }
+                }),
+                GameEvent::OpponentGone(OpponentGoneEvent {
+                    gone: true,
+                    claim_win_in_seconds: None
+                })
+            ]);
         })
     }
 
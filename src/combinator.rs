@@ -0,0 +1,801 @@
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::Bot;
+use crate::chess::position::InvalidPosition;
+use crate::client::BotClient;
+use crate::context::{BotContext, GameContext};
+use crate::model::bot_event::GameStartFinish;
+use crate::model::challenge::{Challenge, ChallengeDeclined};
+use crate::model::game::{Color, GameId};
+use crate::model::game::event::{ChatLineEvent, GameStateEvent, OpponentGoneEvent};
+use crate::model::game::result::GameResult;
+use crate::model::tournament::TournamentPairing;
+use crate::runner::error::RunnerError;
+use crate::stats::RatingUpdate;
+
+/// A [Bot] which combines two bots by calling every hook first on the first bot and then on the
+/// second one. This allows separating concerns into individual bots, e.g. a bot which plays moves
+/// and another one which manages chat messages.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Chained<A, B>(pub A, pub B);
+
+#[async_trait::async_trait]
+impl<A: Bot, B: Bot> Bot for Chained<A, B> {
+
+    async fn on_game_start(&self, context: &BotContext, game: GameStartFinish,
+            client: &BotClient) {
+        self.0.on_game_start(context, game.clone(), client).await;
+        self.1.on_game_start(context, game, client).await
+    }
+
+    async fn on_game_finish(&self, context: &BotContext, game: GameStartFinish,
+            result: GameResult, client: &BotClient) {
+        self.0.on_game_finish(context, game.clone(), result.clone(), client).await;
+        self.1.on_game_finish(context, game, result, client).await
+    }
+
+    async fn on_rating_update(&self, context: &BotContext, game: GameStartFinish,
+            update: RatingUpdate, client: &BotClient) {
+        self.0.on_rating_update(context, game.clone(), update.clone(), client).await;
+        self.1.on_rating_update(context, game, update, client).await
+    }
+
+    async fn on_challenge(&self, context: &BotContext, challenge: Challenge, client: &BotClient) {
+        self.0.on_challenge(context, challenge.clone(), client).await;
+        self.1.on_challenge(context, challenge, client).await
+    }
+
+    async fn on_rematch_offer(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        self.0.on_rematch_offer(context, challenge.clone(), client).await;
+        self.1.on_rematch_offer(context, challenge, client).await
+    }
+
+    async fn on_challenge_cancelled(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        self.0.on_challenge_cancelled(context, challenge.clone(), client).await;
+        self.1.on_challenge_cancelled(context, challenge, client).await
+    }
+
+    async fn on_challenge_declined(&self, context: &BotContext, challenge: ChallengeDeclined,
+            client: &BotClient) {
+        self.0.on_challenge_declined(context, challenge.clone(), client).await;
+        self.1.on_challenge_declined(context, challenge, client).await
+    }
+
+    async fn on_tournament_pairing(&self, context: &BotContext, pairing: TournamentPairing,
+            client: &BotClient) {
+        self.0.on_tournament_pairing(context, pairing.clone(), client).await;
+        self.1.on_tournament_pairing(context, pairing, client).await
+    }
+
+    async fn on_error(&self, context: &BotContext, game_id: Option<GameId>,
+            error: RunnerError, client: &BotClient) {
+        self.0.on_error(context, game_id.clone(), error.clone(), client).await;
+        self.1.on_error(context, game_id, error, client).await
+    }
+
+    async fn on_game_state(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.0.on_game_state(context, state.clone(), client).await;
+        self.1.on_game_state(context, state, client).await
+    }
+
+    async fn on_chat_line(&self, context: &GameContext, chat_line: ChatLineEvent,
+            client: &BotClient) {
+        self.0.on_chat_line(context, chat_line.clone(), client).await;
+        self.1.on_chat_line(context, chat_line, client).await
+    }
+
+    async fn on_opponent_gone(&self, context: &GameContext, opponent_gone: OpponentGoneEvent,
+            client: &BotClient) {
+        self.0.on_opponent_gone(context, opponent_gone, client).await;
+        self.1.on_opponent_gone(context, opponent_gone, client).await
+    }
+
+    async fn on_claimable_win(&self, context: &GameContext, client: &BotClient) {
+        self.0.on_claimable_win(context, client).await;
+        self.1.on_claimable_win(context, client).await
+    }
+
+    async fn on_invalid_position(&self, context: &GameContext, warning: InvalidPosition,
+            client: &BotClient) {
+        self.0.on_invalid_position(context, warning.clone(), client).await;
+        self.1.on_invalid_position(context, warning, client).await
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, state: GameStateEvent, client: &BotClient) {
+        self.0.on_my_turn(context, state.clone(), client).await;
+        self.1.on_my_turn(context, state, client).await
+    }
+
+    async fn on_draw_offered(&self, context: &GameContext, color: Color, client: &BotClient) {
+        self.0.on_draw_offered(context, color, client).await;
+        self.1.on_draw_offered(context, color, client).await
+    }
+
+    async fn on_takeback_proposed(&self, context: &GameContext, color: Color,
+            client: &BotClient) {
+        self.0.on_takeback_proposed(context, color, client).await;
+        self.1.on_takeback_proposed(context, color, client).await
+    }
+
+    async fn on_draw_offer(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.0.on_draw_offer(context, state.clone(), client).await;
+        self.1.on_draw_offer(context, state, client).await
+    }
+
+    async fn on_takeback_proposal(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.0.on_takeback_proposal(context, state.clone(), client).await;
+        self.1.on_takeback_proposal(context, state, client).await
+    }
+}
+
+/// A [Bot] which only handles some games and challenges. Used as the primary bot of a
+/// [Fallback], which passes everything this bot does not handle to the fallback bot. By default,
+/// everything is handled.
+pub trait PartialBot : Bot {
+
+    /// Determines whether this bot handles the game which starts or finishes with the given event,
+    /// i.e. whether [Bot::on_game_start] and [Bot::on_game_finish] are called for it.
+    fn handles_game_event(&self, _game: &GameStartFinish) -> bool {
+        true
+    }
+
+    /// Determines whether this bot handles the game with the given context, i.e. whether the hooks
+    /// which receive a [GameContext] are called for it.
+    fn handles_game(&self, _context: &GameContext) -> bool {
+        true
+    }
+
+    /// Determines whether this bot handles the given challenge, i.e. whether
+    /// [Bot::on_challenge], [Bot::on_challenge_cancelled] and [Bot::on_challenge_declined] are
+    /// called for it.
+    fn handles_challenge(&self, _challenge: &Challenge) -> bool {
+        true
+    }
+}
+
+/// A [Bot] which passes every event to a primary [PartialBot] if it handles the concerned game or
+/// challenge and to a fallback bot otherwise. Declined challenges only carry the ID of the
+/// challenge, so they are passed to the fallback bot if and only if it received the challenge
+/// when it was created.
+pub struct Fallback<A, B> {
+    primary: A,
+    fallback: B,
+    fallback_challenges: Mutex<HashSet<GameId>>
+}
+
+impl<A: PartialBot, B: Bot> Fallback<A, B> {
+
+    /// Creates a new fallback combinator which passes everything the given primary bot does not
+    /// handle to the given fallback bot.
+    pub fn new(primary: A, fallback: B) -> Fallback<A, B> {
+        Fallback {
+            primary,
+            fallback,
+            fallback_challenges: Mutex::new(HashSet::new())
+        }
+    }
+
+    /// The primary bot, which gets to handle every event first.
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    /// The fallback bot, which handles every event not handled by the primary bot.
+    pub fn fallback(&self) -> &B {
+        &self.fallback
+    }
+
+    fn game_event_bot(&self, game: &GameStartFinish) -> &dyn Bot {
+        if self.primary.handles_game_event(game) {
+            &self.primary
+        }
+        else {
+            &self.fallback
+        }
+    }
+
+    fn game_bot(&self, context: &GameContext) -> &dyn Bot {
+        if self.primary.handles_game(context) {
+            &self.primary
+        }
+        else {
+            &self.fallback
+        }
+    }
+}
+
+impl<A: Debug, B: Debug> Debug for Fallback<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fallback")
+            .field("primary", &self.primary)
+            .field("fallback", &self.fallback)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl<A: PartialBot, B: Bot> Bot for Fallback<A, B> {
+
+    async fn on_game_start(&self, context: &BotContext, game: GameStartFinish,
+            client: &BotClient) {
+        if let Some(game_id) = &game.id {
+            self.fallback_challenges.lock().unwrap().remove(game_id);
+        }
+
+        self.game_event_bot(&game).on_game_start(context, game, client).await
+    }
+
+    async fn on_game_finish(&self, context: &BotContext, game: GameStartFinish,
+            result: GameResult, client: &BotClient) {
+        self.game_event_bot(&game).on_game_finish(context, game, result, client).await
+    }
+
+    async fn on_rating_update(&self, context: &BotContext, game: GameStartFinish,
+            update: RatingUpdate, client: &BotClient) {
+        self.game_event_bot(&game).on_rating_update(context, game, update, client).await
+    }
+
+    async fn on_challenge(&self, context: &BotContext, challenge: Challenge, client: &BotClient) {
+        if self.primary.handles_challenge(&challenge) {
+            self.primary.on_challenge(context, challenge, client).await
+        }
+        else {
+            self.fallback_challenges.lock().unwrap().insert(challenge.id.clone());
+            self.fallback.on_challenge(context, challenge, client).await
+        }
+    }
+
+    async fn on_rematch_offer(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        if self.primary.handles_challenge(&challenge) {
+            self.primary.on_rematch_offer(context, challenge, client).await
+        }
+        else {
+            self.fallback_challenges.lock().unwrap().insert(challenge.id.clone());
+            self.fallback.on_rematch_offer(context, challenge, client).await
+        }
+    }
+
+    async fn on_challenge_cancelled(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        self.fallback_challenges.lock().unwrap().remove(&challenge.id);
+
+        if self.primary.handles_challenge(&challenge) {
+            self.primary.on_challenge_cancelled(context, challenge, client).await
+        }
+        else {
+            self.fallback.on_challenge_cancelled(context, challenge, client).await
+        }
+    }
+
+    async fn on_challenge_declined(&self, context: &BotContext, challenge: ChallengeDeclined,
+            client: &BotClient) {
+        if self.fallback_challenges.lock().unwrap().remove(&challenge.id) {
+            self.fallback.on_challenge_declined(context, challenge, client).await
+        }
+        else {
+            self.primary.on_challenge_declined(context, challenge, client).await
+        }
+    }
+
+    async fn on_tournament_pairing(&self, context: &BotContext, pairing: TournamentPairing,
+            client: &BotClient) {
+        self.game_event_bot(&pairing.to_game_start())
+            .on_tournament_pairing(context, pairing, client).await
+    }
+
+    async fn on_error(&self, context: &BotContext, game_id: Option<GameId>,
+            error: RunnerError, client: &BotClient) {
+        self.primary.on_error(context, game_id.clone(), error.clone(), client).await;
+        self.fallback.on_error(context, game_id, error, client).await
+    }
+
+    async fn on_game_state(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.game_bot(context).on_game_state(context, state, client).await
+    }
+
+    async fn on_chat_line(&self, context: &GameContext, chat_line: ChatLineEvent,
+            client: &BotClient) {
+        self.game_bot(context).on_chat_line(context, chat_line, client).await
+    }
+
+    async fn on_opponent_gone(&self, context: &GameContext, opponent_gone: OpponentGoneEvent,
+            client: &BotClient) {
+        self.game_bot(context).on_opponent_gone(context, opponent_gone, client).await
+    }
+
+    async fn on_claimable_win(&self, context: &GameContext, client: &BotClient) {
+        self.game_bot(context).on_claimable_win(context, client).await
+    }
+
+    async fn on_invalid_position(&self, context: &GameContext, warning: InvalidPosition,
+            client: &BotClient) {
+        self.game_bot(context).on_invalid_position(context, warning, client).await
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, state: GameStateEvent, client: &BotClient) {
+        self.game_bot(context).on_my_turn(context, state, client).await
+    }
+
+    async fn on_draw_offered(&self, context: &GameContext, color: Color, client: &BotClient) {
+        self.game_bot(context).on_draw_offered(context, color, client).await
+    }
+
+    async fn on_takeback_proposed(&self, context: &GameContext, color: Color,
+            client: &BotClient) {
+        self.game_bot(context).on_takeback_proposed(context, color, client).await
+    }
+
+    async fn on_draw_offer(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.game_bot(context).on_draw_offer(context, state, client).await
+    }
+
+    async fn on_takeback_proposal(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.game_bot(context).on_takeback_proposal(context, state, client).await
+    }
+}
+
+/// A record of a single call of a [Bot] hook, passed to the sink of an [Instrumented] bot.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct HookCall {
+
+    /// The name of the called hook, e.g. `"on_game_state"`.
+    pub hook: &'static str,
+
+    /// The ID of the game or challenge the call concerns, if known.
+    pub id: Option<GameId>,
+
+    /// The time the inner bot took to handle the call.
+    pub duration: Duration
+}
+
+type HookCallSink = Box<dyn Fn(&HookCall) + Send + Sync>;
+
+fn log_to_stderr(call: &HookCall) {
+    let id = call.id.as_deref().unwrap_or("-");
+
+    eprintln!("libot: {} [{id}] took {:?}", call.hook, call.duration);
+}
+
+/// A [Bot] which passes every event to an inner bot and logs each hook call, including the time
+/// the inner bot took to handle it, to a sink. By default, calls are logged to standard error.
+pub struct Instrumented<A> {
+    bot: A,
+    sink: HookCallSink
+}
+
+impl<A: Bot> Instrumented<A> {
+
+    /// Creates a new instrumented bot which logs every hook call of the given bot to standard
+    /// error.
+    pub fn new(bot: A) -> Instrumented<A> {
+        Instrumented {
+            bot,
+            sink: Box::new(log_to_stderr)
+        }
+    }
+
+    /// Sets the sink to which every [HookCall] is passed after the inner bot handled it, instead
+    /// of logging it to standard error. The bot is returned for chaining.
+    pub fn with_sink(mut self, sink: impl Fn(&HookCall) + Send + Sync + 'static)
+            -> Instrumented<A> {
+        self.sink = Box::new(sink);
+        self
+    }
+
+    /// The inner bot, to which every event is passed.
+    pub fn inner(&self) -> &A {
+        &self.bot
+    }
+
+    async fn instrument(&self, hook: &'static str, id: Option<GameId>,
+            call: impl Future<Output = ()>) {
+        let start = Instant::now();
+
+        call.await;
+
+        (self.sink)(&HookCall {
+            hook,
+            id,
+            duration: start.elapsed()
+        });
+    }
+}
+
+impl<A: Debug> Debug for Instrumented<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instrumented")
+            .field("bot", &self.bot)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl<A: Bot> Bot for Instrumented<A> {
+
+    async fn on_game_start(&self, context: &BotContext, game: GameStartFinish,
+            client: &BotClient) {
+        let id = game.id.clone();
+        self.instrument("on_game_start", id, self.bot.on_game_start(context, game, client)).await
+    }
+
+    async fn on_game_finish(&self, context: &BotContext, game: GameStartFinish,
+            result: GameResult, client: &BotClient) {
+        let id = game.id.clone();
+        let call = self.bot.on_game_finish(context, game, result, client);
+        self.instrument("on_game_finish", id, call).await
+    }
+
+    async fn on_rating_update(&self, context: &BotContext, game: GameStartFinish,
+            update: RatingUpdate, client: &BotClient) {
+        let id = game.id.clone();
+        let call = self.bot.on_rating_update(context, game, update, client);
+        self.instrument("on_rating_update", id, call).await
+    }
+
+    async fn on_challenge(&self, context: &BotContext, challenge: Challenge, client: &BotClient) {
+        let id = Some(challenge.id.clone());
+        self.instrument("on_challenge", id, self.bot.on_challenge(context, challenge, client)).await
+    }
+
+    async fn on_rematch_offer(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        let id = Some(challenge.id.clone());
+        let call = self.bot.on_rematch_offer(context, challenge, client);
+        self.instrument("on_rematch_offer", id, call).await
+    }
+
+    async fn on_challenge_cancelled(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        let id = Some(challenge.id.clone());
+        let call = self.bot.on_challenge_cancelled(context, challenge, client);
+        self.instrument("on_challenge_cancelled", id, call).await
+    }
+
+    async fn on_challenge_declined(&self, context: &BotContext, challenge: ChallengeDeclined,
+            client: &BotClient) {
+        let id = Some(challenge.id.clone());
+        let call = self.bot.on_challenge_declined(context, challenge, client);
+        self.instrument("on_challenge_declined", id, call).await
+    }
+
+    async fn on_tournament_pairing(&self, context: &BotContext, pairing: TournamentPairing,
+            client: &BotClient) {
+        let id = Some(pairing.game_id.clone());
+        let call = self.bot.on_tournament_pairing(context, pairing, client);
+        self.instrument("on_tournament_pairing", id, call).await
+    }
+
+    async fn on_error(&self, context: &BotContext, game_id: Option<GameId>,
+            error: RunnerError, client: &BotClient) {
+        let call = self.bot.on_error(context, game_id.clone(), error, client);
+        self.instrument("on_error", game_id, call).await
+    }
+
+    async fn on_game_state(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        let id = Some(context.id.clone());
+        self.instrument("on_game_state", id, self.bot.on_game_state(context, state, client)).await
+    }
+
+    async fn on_chat_line(&self, context: &GameContext, chat_line: ChatLineEvent,
+            client: &BotClient) {
+        let id = Some(context.id.clone());
+        let call = self.bot.on_chat_line(context, chat_line, client);
+        self.instrument("on_chat_line", id, call).await
+    }
+
+    async fn on_opponent_gone(&self, context: &GameContext, opponent_gone: OpponentGoneEvent,
+            client: &BotClient) {
+        let id = Some(context.id.clone());
+        let call = self.bot.on_opponent_gone(context, opponent_gone, client);
+        self.instrument("on_opponent_gone", id, call).await
+    }
+
+    async fn on_claimable_win(&self, context: &GameContext, client: &BotClient) {
+        let id = Some(context.id.clone());
+        self.instrument("on_claimable_win", id, self.bot.on_claimable_win(context, client)).await
+    }
+
+    async fn on_invalid_position(&self, context: &GameContext, warning: InvalidPosition,
+            client: &BotClient) {
+        let id = Some(context.id.clone());
+        let call = self.bot.on_invalid_position(context, warning, client);
+        self.instrument("on_invalid_position", id, call).await
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, state: GameStateEvent, client: &BotClient) {
+        let id = Some(context.id.clone());
+        self.instrument("on_my_turn", id, self.bot.on_my_turn(context, state, client)).await
+    }
+
+    async fn on_draw_offered(&self, context: &GameContext, color: Color, client: &BotClient) {
+        let id = Some(context.id.clone());
+        let call = self.bot.on_draw_offered(context, color, client);
+        self.instrument("on_draw_offered", id, call).await
+    }
+
+    async fn on_takeback_proposed(&self, context: &GameContext, color: Color,
+            client: &BotClient) {
+        let id = Some(context.id.clone());
+        let call = self.bot.on_takeback_proposed(context, color, client);
+        self.instrument("on_takeback_proposed", id, call).await
+    }
+
+    async fn on_draw_offer(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        let id = Some(context.id.clone());
+        let call = self.bot.on_draw_offer(context, state, client);
+        self.instrument("on_draw_offer", id, call).await
+    }
+
+    async fn on_takeback_proposal(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        let id = Some(context.id.clone());
+        let call = self.bot.on_takeback_proposal(context, state, client);
+        self.instrument("on_takeback_proposal", id, call).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use kernal::prelude::*;
+
+    use crate::client::BotClientBuilder;
+    use crate::by_address::ByAddress;
+    use crate::model::game::{GameInfo, Speed};
+    use crate::store::tests as store_tests;
+
+    use super::*;
+
+    type Log = Arc<Mutex<Vec<(&'static str, &'static str)>>>;
+
+    struct RecordingBot {
+        name: &'static str,
+        log: Log
+    }
+
+    impl RecordingBot {
+
+        fn new(name: &'static str, log: &Log) -> RecordingBot {
+            RecordingBot {
+                name,
+                log: Arc::clone(log)
+            }
+        }
+
+        fn record(&self, hook: &'static str) {
+            self.log.lock().unwrap().push((self.name, hook));
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for RecordingBot {
+        async fn on_challenge(&self, _: &BotContext, _: Challenge, _: &BotClient) {
+            self.record("challenge");
+        }
+
+        async fn on_rematch_offer(&self, _: &BotContext, _: Challenge, _: &BotClient) {
+            self.record("rematchOffer");
+        }
+
+        async fn on_challenge_declined(&self, _: &BotContext, _: ChallengeDeclined,
+                _: &BotClient) {
+            self.record("challengeDeclined");
+        }
+
+        async fn on_game_state(&self, _: &GameContext, _: GameStateEvent, _: &BotClient) {
+            self.record("gameState");
+        }
+    }
+
+    impl PartialBot for RecordingBot {
+        fn handles_game(&self, context: &GameContext) -> bool {
+            context.speed == Speed::Bullet
+        }
+
+        fn handles_challenge(&self, challenge: &Challenge) -> bool {
+            challenge.speed == Speed::Bullet
+        }
+    }
+
+    fn bot_context() -> BotContext {
+        BotContext {
+            bot_id: "testBotId".to_owned(),
+            profile: ByAddress::default()
+        }
+    }
+
+    fn test_client() -> BotClient {
+        BotClientBuilder::new().with_token("").build().unwrap()
+    }
+
+    fn game_context(speed: Speed) -> GameContext {
+        GameContext {
+            bot_color: None,
+            bot_id: "testBotId".to_owned(),
+            info: GameInfo {
+                speed,
+                ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    fn game_state() -> GameStateEvent {
+        serde_json::from_str(
+            r#"{"moves":"","wtime":0,"btime":0,"winc":0,"binc":0,"status":"started"}"#).unwrap()
+    }
+
+    fn test_challenge(id: &str, speed: &str) -> Challenge {
+        serde_json::from_str(&format!(r#"{{
+            "id": "{id}",
+            "url": "testUrl",
+            "status": "created",
+            "challenger": {{ "id": "testChallengerId", "name": "testChallengerName" }},
+            "variant": {{ "key": "standard" }},
+            "rated": false,
+            "speed": "{speed}",
+            "timeControl": {{ "type": "unlimited" }},
+            "color": "random",
+            "perf": {{ }}
+        }}"#)).unwrap()
+    }
+
+    #[test]
+    fn chained_calls_both_bots_in_order() {
+        let log = Log::default();
+        let bot = Chained(RecordingBot::new("first", &log), RecordingBot::new("second", &log));
+
+        tokio_test::block_on(
+            bot.on_game_state(&game_context(Speed::Blitz), game_state(), &test_client()));
+
+        assert_that!(log.lock().unwrap().clone()).contains_exactly_in_given_order([
+            ("first", "gameState"),
+            ("second", "gameState")
+        ]);
+    }
+
+    #[test]
+    fn chained_forwards_rematch_offer_to_both_bots() {
+        let log = Log::default();
+        let bot = Chained(RecordingBot::new("first", &log), RecordingBot::new("second", &log));
+
+        tokio_test::block_on(bot.on_rematch_offer(
+            &bot_context(), test_challenge("rematchId", "blitz"), &test_client()));
+
+        assert_that!(log.lock().unwrap().clone()).contains_exactly_in_given_order([
+            ("first", "rematchOffer"),
+            ("second", "rematchOffer")
+        ]);
+    }
+
+    #[test]
+    fn fallback_passes_unhandled_games_to_fallback_bot() {
+        let log = Log::default();
+        let bot = Fallback::new(
+            RecordingBot::new("primary", &log), RecordingBot::new("fallback", &log));
+        let client = test_client();
+
+        tokio_test::block_on(async {
+            bot.on_game_state(&game_context(Speed::Bullet), game_state(), &client).await;
+            bot.on_game_state(&game_context(Speed::Rapid), game_state(), &client).await;
+        });
+
+        assert_that!(log.lock().unwrap().clone()).contains_exactly_in_given_order([
+            ("primary", "gameState"),
+            ("fallback", "gameState")
+        ]);
+    }
+
+    #[test]
+    fn fallback_passes_declined_challenge_to_bot_which_received_it() {
+        let log = Log::default();
+        let bot = Fallback::new(
+            RecordingBot::new("primary", &log), RecordingBot::new("fallback", &log));
+        let client = test_client();
+        let declined = |id: &str| ChallengeDeclined {
+            id: id.to_owned()
+        };
+
+        tokio_test::block_on(async {
+            bot.on_challenge(&bot_context(), test_challenge("bulletId", "bullet"), &client).await;
+            bot.on_challenge(&bot_context(), test_challenge("rapidId", "rapid"), &client).await;
+            bot.on_challenge_declined(&bot_context(), declined("rapidId"), &client).await;
+            bot.on_challenge_declined(&bot_context(), declined("bulletId"), &client).await;
+        });
+
+        assert_that!(log.lock().unwrap().clone()).contains_exactly_in_given_order([
+            ("primary", "challenge"),
+            ("fallback", "challenge"),
+            ("fallback", "challengeDeclined"),
+            ("primary", "challengeDeclined")
+        ]);
+    }
+
+    #[test]
+    fn fallback_forwards_rematch_offer_to_bot_which_handles_challenge() {
+        let log = Log::default();
+        let bot = Fallback::new(
+            RecordingBot::new("primary", &log), RecordingBot::new("fallback", &log));
+        let client = test_client();
+        let declined = ChallengeDeclined {
+            id: "rapidId".to_owned()
+        };
+
+        tokio_test::block_on(async {
+            bot.on_rematch_offer(&bot_context(), test_challenge("bulletId", "bullet"), &client)
+                .await;
+            bot.on_rematch_offer(&bot_context(), test_challenge("rapidId", "rapid"), &client)
+                .await;
+            bot.on_challenge_declined(&bot_context(), declined, &client).await;
+        });
+
+        assert_that!(log.lock().unwrap().clone()).contains_exactly_in_given_order([
+            ("primary", "rematchOffer"),
+            ("fallback", "rematchOffer"),
+            ("fallback", "challengeDeclined")
+        ]);
+    }
+
+    #[test]
+    fn instrumented_passes_calls_to_inner_bot_and_sink() {
+        let log = Log::default();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink_calls = Arc::clone(&calls);
+        let bot = Instrumented::new(RecordingBot::new("inner", &log))
+            .with_sink(move |call| sink_calls.lock().unwrap().push(call.clone()));
+
+        tokio_test::block_on(
+            bot.on_game_state(&game_context(Speed::Blitz), game_state(), &test_client()));
+
+        let calls = calls.lock().unwrap();
+
+        assert_that!(log.lock().unwrap().clone())
+            .contains_exactly_in_given_order([("inner", "gameState")]);
+        assert_that!(calls.len()).is_equal_to(1);
+        assert_that!(calls[0].hook).is_equal_to("on_game_state");
+        assert_that!(calls[0].id.clone()).is_equal_to(Some("testGameId".to_owned()));
+    }
+
+    #[test]
+    fn instrumented_forwards_rematch_offer_to_inner_bot() {
+        let log = Log::default();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink_calls = Arc::clone(&calls);
+        let bot = Instrumented::new(RecordingBot::new("inner", &log))
+            .with_sink(move |call| sink_calls.lock().unwrap().push(call.clone()));
+
+        tokio_test::block_on(bot.on_rematch_offer(
+            &bot_context(), test_challenge("rematchId", "blitz"), &test_client()));
+
+        let calls = calls.lock().unwrap();
+
+        assert_that!(log.lock().unwrap().clone())
+            .contains_exactly_in_given_order([("inner", "rematchOffer")]);
+        assert_that!(calls.len()).is_equal_to(1);
+        assert_that!(calls[0].hook).is_equal_to("on_rematch_offer");
+        assert_that!(calls[0].id.clone()).is_equal_to(Some("rematchId".to_owned()));
+    }
+}
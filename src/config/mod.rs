@@ -0,0 +1,844 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+use serde::de::Error as DeserializeError;
+
+use serde_json::Error as JsonError;
+
+use serde_yaml::Error as YamlError;
+
+use thiserror::Error;
+
+use toml::de::Error as TomlError;
+
+use crate::analysis::uci_engine::{SearchLimits, UciEngine};
+use crate::client::Secret;
+use crate::model::Milliseconds;
+use crate::model::challenge::{Challenge, DeclineReason};
+use crate::model::game::{Speed, Variant};
+use crate::model::user::{Rating, Title};
+use crate::runner::challenge_queue::{
+    ChallengeDecision,
+    ChallengePolicy,
+    ChallengeQueueConfig,
+    DEFAULT_MAX_CONCURRENT_GAMES,
+    QueueOrdering
+};
+
+/// An error that occurs when loading a [BotConfig].
+#[derive(Debug, Error)]
+pub enum ConfigError {
+
+    #[error("error reading configuration file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid configuration: {0}")]
+    Json(#[from] JsonError),
+
+    #[error("invalid TOML configuration: {0}")]
+    Toml(#[from] TomlError),
+
+    #[error("invalid YAML configuration: {0}")]
+    Yaml(#[from] YamlError),
+
+    #[error("unsupported configuration file format of {0}, expected .toml, .yaml or .json")]
+    UnsupportedFormat(PathBuf),
+
+    #[error("invalid value {value:?} of environment variable {name}")]
+    InvalidEnvironmentVariable {
+        name: String,
+        value: String
+    }
+}
+
+pub type ConfigResult<T> = Result<T, ConfigError>;
+
+/// The prefix of all environment variables read by [BotConfig::with_env_overrides].
+pub const ENV_PREFIX: &str = "LIBOT_";
+
+/// The configuration of a bot deployment, loaded from a file and/or environment variables. Apply
+/// it using [BotClientBuilder::with_config](crate::client::BotClientBuilder::with_config) and
+/// [BotRunner::with_config](crate::runner::BotRunner::with_config).
+///
+/// A configuration file in TOML might look as follows. YAML and JSON files use the same structure.
+/// All keys are optional.
+///
+/// ```toml
+/// token = "lip_..."
+/// base_url = "https://lichess.org/api"
+///
+/// [challenge]
+/// max_concurrent_games = 2
+/// ordering = "priority"
+/// variants = ["standard", "chess960"]
+/// speeds = ["blitz", "rapid"]
+/// accept_humans = false
+///
+/// [engine]
+/// path = "/usr/bin/stockfish"
+/// options = { Threads = 4, Hash = 256 }
+///
+/// [engine.limits]
+/// ultraBullet = { depth = 6 }
+/// rapid = { move_time_ms = 5000 }
+///
+/// [time]
+/// move_overhead_ms = 200
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotConfig {
+
+    /// The Lichess API OAuth token of the bot, which is redacted in [Debug] output.
+    pub token: Option<Secret>,
+
+    /// The base URL of the Lichess API, if it differs from the
+    /// [DEFAULT_BASE_URL](crate::client::DEFAULT_BASE_URL).
+    pub base_url: Option<String>,
+
+    /// The configuration of the challenge queue, which is only enabled if this is present.
+    pub challenge: Option<ChallengeConfig>,
+
+    /// The local UCI engine used by the bot, if any.
+    pub engine: Option<EngineConfig>,
+
+    /// Settings for managing the bot's thinking time.
+    pub time: TimeConfig
+}
+
+impl BotConfig {
+
+    /// Loads a configuration from the file at the given path. The format is determined by the file
+    /// extension, which must be `.toml`, `.yaml`, `.yml`, or `.json`.
+    ///
+    /// # Errors
+    ///
+    /// * [ConfigError::UnsupportedFormat] if the file extension is not supported.
+    /// * [ConfigError::Io] if the file cannot be read.
+    /// * [ConfigError::Toml], [ConfigError::Yaml], or [ConfigError::Json] if the file is malformed
+    ///   or does not describe a valid configuration.
+    pub fn load(path: impl AsRef<Path>) -> ConfigResult<BotConfig> {
+        let path = path.as_ref();
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("toml") => BotConfig::from_toml_str(&fs::read_to_string(path)?),
+            Some("yaml" | "yml") => BotConfig::from_yaml_str(&fs::read_to_string(path)?),
+            Some("json") => BotConfig::from_json_str(&fs::read_to_string(path)?),
+            _ => Err(ConfigError::UnsupportedFormat(path.to_owned()))
+        }
+    }
+
+    /// Parses a configuration from the given TOML source.
+    ///
+    /// # Errors
+    ///
+    /// [ConfigError::Toml] if the source is not valid TOML or does not describe a valid
+    /// configuration.
+    pub fn from_toml_str(source: &str) -> ConfigResult<BotConfig> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Parses a configuration from the given YAML source, which uses the same structure as a TOML
+    /// or JSON configuration.
+    ///
+    /// # Errors
+    ///
+    /// [ConfigError::Yaml] if the source is not valid YAML or does not describe a valid
+    /// configuration.
+    pub fn from_yaml_str(source: &str) -> ConfigResult<BotConfig> {
+        Ok(serde_yaml::from_str(source)?)
+    }
+
+    /// Parses a configuration from the given JSON source.
+    ///
+    /// # Errors
+    ///
+    /// [ConfigError::Json] if the source is not valid JSON or does not describe a valid
+    /// configuration.
+    pub fn from_json_str(source: &str) -> ConfigResult<BotConfig> {
+        Ok(serde_json::from_str(source)?)
+    }
+
+    /// Overrides values of this configuration with those of the following environment variables,
+    /// if they are set, so secrets and deployment-specific settings need not be stored in files.
+    ///
+    /// * `LIBOT_TOKEN`: [BotConfig::token]
+    /// * `LIBOT_BASE_URL`: [BotConfig::base_url]
+    /// * `LIBOT_MAX_CONCURRENT_GAMES`: [ChallengeConfig::max_concurrent_games], enabling the
+    ///   challenge queue if necessary
+    /// * `LIBOT_ENGINE_PATH`: [EngineConfig::path], configuring an engine if necessary
+    /// * `LIBOT_MOVE_OVERHEAD_MS`: [TimeConfig::move_overhead_ms]
+    ///
+    /// The configuration is returned for chaining.
+    ///
+    /// # Errors
+    ///
+    /// [ConfigError::InvalidEnvironmentVariable] if a numeric variable cannot be parsed.
+    pub fn with_env_overrides(self) -> ConfigResult<BotConfig> {
+        self.with_overrides(|name| std::env::var(name).ok())
+    }
+
+    fn with_overrides(mut self, lookup: impl Fn(&str) -> Option<String>)
+            -> ConfigResult<BotConfig> {
+        let lookup = |key: &str| {
+            let name = format!("{ENV_PREFIX}{key}");
+            lookup(&name).map(|value| (name, value))
+        };
+
+        if let Some((_, token)) = lookup("TOKEN") {
+            self.token = Some(Secret::new(token));
+        }
+
+        if let Some((_, base_url)) = lookup("BASE_URL") {
+            self.base_url = Some(base_url);
+        }
+
+        if let Some(max_concurrent_games) = lookup("MAX_CONCURRENT_GAMES") {
+            self.challenge.get_or_insert_with(ChallengeConfig::default).max_concurrent_games =
+                parse_env_var(max_concurrent_games)?;
+        }
+
+        if let Some((_, path)) = lookup("ENGINE_PATH") {
+            match &mut self.engine {
+                Some(engine) => engine.path = PathBuf::from(path),
+                None => self.engine = Some(EngineConfig::new(path))
+            }
+        }
+
+        if let Some(move_overhead_ms) = lookup("MOVE_OVERHEAD_MS") {
+            self.time.move_overhead_ms = parse_env_var(move_overhead_ms)?;
+        }
+
+        Ok(self)
+    }
+}
+
+fn parse_env_var<T: FromStr>((name, value): (String, String)) -> ConfigResult<T> {
+    value.trim().parse().map_err(|_| ConfigError::InvalidEnvironmentVariable {
+        name,
+        value
+    })
+}
+
+fn deserialize_variants<'de, D>(deserializer: D) -> Result<Vec<Variant>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    Vec::<String>::deserialize(deserializer)?.iter()
+        .map(|key| Variant::from_key(key)
+            .ok_or_else(|| D::Error::custom(format!("unknown variant {key:?}"))))
+        .collect()
+}
+
+/// Configures which incoming challenges the challenge queue of a
+/// [BotRunner](crate::runner::BotRunner) accepts. As a [ChallengePolicy], it queues all
+/// challenges matching the criteria with equal priority and declines all others with a fitting
+/// [DeclineReason].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChallengeConfig {
+
+    /// The maximum number of games played at the same time (see
+    /// [ChallengeQueueConfig::with_max_concurrent_games]).
+    pub max_concurrent_games: usize,
+
+    /// The order in which queued challenges are accepted.
+    pub ordering: QueueOrdering,
+
+    /// The number of seconds after which a queued challenge is declined, or [None] if challenges
+    /// never expire.
+    pub expiry_seconds: Option<u64>,
+
+    /// The accepted variants, given by their keys (see [Variant::key]). If empty, all variants
+    /// are accepted.
+    #[serde(deserialize_with = "deserialize_variants")]
+    pub variants: Vec<Variant>,
+
+    /// The accepted speeds. If empty, all speeds are accepted.
+    pub speeds: Vec<Speed>,
+
+    /// Whether rated challenges are accepted.
+    pub accept_rated: bool,
+
+    /// Whether casual challenges are accepted.
+    pub accept_casual: bool,
+
+    /// Whether challenges from bots are accepted.
+    pub accept_bots: bool,
+
+    /// Whether challenges from humans are accepted.
+    pub accept_humans: bool,
+
+    /// The minimum rating of accepted challengers, if any.
+    pub min_rating: Option<Rating>,
+
+    /// The maximum rating of accepted challengers, if any.
+    pub max_rating: Option<Rating>
+}
+
+impl ChallengeConfig {
+
+    /// Creates a [ChallengeQueueConfig] which uses this configuration as its policy.
+    pub fn queue_config(&self) -> ChallengeQueueConfig {
+        let mut queue_config = ChallengeQueueConfig::new(self.clone())
+            .with_max_concurrent_games(self.max_concurrent_games)
+            .with_ordering(self.ordering);
+
+        if let Some(expiry_seconds) = self.expiry_seconds {
+            queue_config = queue_config.with_expiry(Duration::from_secs(expiry_seconds));
+        }
+
+        queue_config
+    }
+}
+
+impl Default for ChallengeConfig {
+    fn default() -> ChallengeConfig {
+        ChallengeConfig {
+            max_concurrent_games: DEFAULT_MAX_CONCURRENT_GAMES,
+            ordering: QueueOrdering::default(),
+            expiry_seconds: None,
+            variants: Vec::new(),
+            speeds: Vec::new(),
+            accept_rated: true,
+            accept_casual: true,
+            accept_bots: true,
+            accept_humans: true,
+            min_rating: None,
+            max_rating: None
+        }
+    }
+}
+
+impl ChallengePolicy for ChallengeConfig {
+    fn decide(&self, challenge: &Challenge) -> ChallengeDecision {
+        let is_bot = challenge.challenger.title == Some(Title::Bot);
+        let variant = challenge.variant.unwrap_or(Variant::Standard);
+        let rating = challenge.challenger.rating;
+        let decline = |reason| ChallengeDecision::Decline(Some(reason));
+
+        if is_bot && !self.accept_bots {
+            return decline(DeclineReason::NoBot);
+        }
+
+        if !is_bot && !self.accept_humans {
+            return decline(DeclineReason::OnlyBot);
+        }
+
+        if !self.variants.is_empty() && !self.variants.contains(&variant) {
+            return if self.variants == [Variant::Standard] {
+                decline(DeclineReason::Standard)
+            }
+            else {
+                decline(DeclineReason::Variant)
+            };
+        }
+
+        if !self.speeds.is_empty() && !self.speeds.contains(&challenge.speed) {
+            return decline(DeclineReason::TimeControl);
+        }
+
+        if challenge.rated && !self.accept_rated {
+            return decline(DeclineReason::Casual);
+        }
+
+        if !challenge.rated && !self.accept_casual {
+            return decline(DeclineReason::Rated);
+        }
+
+        let too_low = self.min_rating.is_some_and(|min| rating.is_none_or(|rating| rating < min));
+        let too_high = self.max_rating.is_some_and(|max| rating.is_none_or(|rating| rating > max));
+
+        if too_low || too_high {
+            return decline(DeclineReason::Generic);
+        }
+
+        ChallengeDecision::Enqueue {
+            priority: 0
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OptionValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool)
+}
+
+fn deserialize_options<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    let options = BTreeMap::<String, OptionValue>::deserialize(deserializer)?;
+
+    Ok(options.into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                OptionValue::String(value) => value,
+                OptionValue::Integer(value) => value.to_string(),
+                OptionValue::Float(value) => value.to_string(),
+                OptionValue::Bool(value) => value.to_string()
+            };
+
+            (name, value)
+        })
+        .collect())
+}
+
+/// Configures a local UCI engine process.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EngineConfig {
+
+    /// The path of the engine executable.
+    pub path: PathBuf,
+
+    /// The command line arguments passed to the engine.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// The UCI options set after starting the engine, such as `Threads` or `Hash`. Numbers and
+    /// booleans are converted to their textual UCI representation.
+    #[serde(default, deserialize_with = "deserialize_options")]
+    pub options: BTreeMap<String, String>,
+
+    /// The [SearchLimits] of the engine's searches in games of each [Speed], keyed by the speed
+    /// in camel case, such as `ultraBullet`. Searches in games of other speeds are only limited by
+    /// the clock.
+    #[serde(default)]
+    pub limits: HashMap<Speed, SearchLimits>
+}
+
+impl EngineConfig {
+
+    /// Creates a new engine configuration for the executable at the given path, without
+    /// arguments, options or limits.
+    pub fn new(path: impl Into<PathBuf>) -> EngineConfig {
+        EngineConfig {
+            path: path.into(),
+            args: Vec::new(),
+            options: BTreeMap::new(),
+            limits: HashMap::new()
+        }
+    }
+
+    /// The configured [SearchLimits] of searches in games of the given speed, which are
+    /// unlimited if none are configured.
+    pub fn limits(&self, speed: Speed) -> SearchLimits {
+        self.limits.get(&speed).copied().unwrap_or_default()
+    }
+
+    /// Starts the configured engine and sets all configured options.
+    ///
+    /// # Errors
+    ///
+    /// Any [io::Error] that occurs while starting the engine or setting its options.
+    pub async fn start(&self) -> io::Result<UciEngine> {
+        let engine = UciEngine::start(&self.path, &self.args).await?;
+
+        for (name, value) in &self.options {
+            engine.set_option(name, value).await?;
+        }
+
+        Ok(engine)
+    }
+}
+
+/// Settings for managing the bot's thinking time, which can be used to compute a time budget for
+/// each move with [TimeConfig::move_time].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeConfig {
+
+    /// The time reserved per move for network latency and overhead, in milliseconds.
+    pub move_overhead_ms: Milliseconds,
+
+    /// The number of moves the remaining time is expected to last for.
+    pub moves_to_go: u32,
+
+    /// The minimum time spent on a move if enough time is left, in milliseconds.
+    pub min_move_time_ms: Milliseconds,
+
+    /// The maximum time spent on a move, in milliseconds, if any.
+    pub max_move_time_ms: Option<Milliseconds>
+}
+
+impl TimeConfig {
+
+    /// Computes the time the bot should spend on its next move.
+    ///
+    /// # Arguments
+    ///
+    /// * `remaining`: The bot's remaining time on the clock, in milliseconds.
+    /// * `increment`: The bot's increment per move, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// The time budget for the move in milliseconds, which is never more than the remaining time
+    /// minus [TimeConfig::move_overhead_ms].
+    pub fn move_time(&self, remaining: Milliseconds, increment: Milliseconds) -> Milliseconds {
+        let available = (remaining - self.move_overhead_ms).max(0);
+        let budget = remaining / Milliseconds::from(self.moves_to_go.max(1)) + increment;
+        let budget = self.max_move_time_ms.map_or(budget, |max| budget.min(max));
+
+        budget.max(self.min_move_time_ms).min(available)
+    }
+}
+
+impl Default for TimeConfig {
+    fn default() -> TimeConfig {
+        TimeConfig {
+            move_overhead_ms: 100,
+            moves_to_go: 30,
+            min_move_time_ms: 50,
+            max_move_time_ms: None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::TimeControl;
+    use crate::model::challenge::{ChallengeColor, ChallengePerf, ChallengeStatus};
+    use crate::model::user::User;
+
+    use super::*;
+
+    const TOML_CONFIG: &str = r#"
+        token = "testToken"
+
+        [challenge]
+        max_concurrent_games = 3
+        ordering = "priority"
+        expiry_seconds = 60
+        variants = ["standard", "kingOfTheHill"]
+        speeds = ["blitz"]
+        accept_humans = false
+        min_rating = 1500
+
+        [engine]
+        path = "/usr/bin/stockfish"
+        args = ["--quiet"]
+        options = { Threads = 4, Ponder = false, "Skill Level" = "20" }
+
+        [engine.limits]
+        ultraBullet = { depth = 6 }
+        rapid = { move_time_ms = 5000, nodes = 1000000 }
+
+        [time]
+        move_overhead_ms = 250
+    "#;
+
+    const YAML_CONFIG: &str = r#"
+        token: testToken
+        challenge:
+          max_concurrent_games: 3
+          ordering: priority
+          expiry_seconds: 60
+          variants: [standard, kingOfTheHill]
+          speeds: [blitz]
+          accept_humans: false
+          min_rating: 1500
+        engine:
+          path: /usr/bin/stockfish
+          args: [--quiet]
+          options:
+            Threads: 4
+            Ponder: false
+            Skill Level: "20"
+          limits:
+            ultraBullet:
+              depth: 6
+            rapid:
+              move_time_ms: 5000
+              nodes: 1000000
+        time:
+          move_overhead_ms: 250
+    "#;
+
+    fn expected_config() -> BotConfig {
+        BotConfig {
+            token: Some(Secret::new("testToken")),
+            base_url: None,
+            challenge: Some(ChallengeConfig {
+                max_concurrent_games: 3,
+                ordering: QueueOrdering::Priority,
+                expiry_seconds: Some(60),
+                variants: vec![Variant::Standard, Variant::KingOfTheHill],
+                speeds: vec![Speed::Blitz],
+                accept_humans: false,
+                min_rating: Some(1500),
+                ..ChallengeConfig::default()
+            }),
+            engine: Some(EngineConfig {
+                path: PathBuf::from("/usr/bin/stockfish"),
+                args: vec!["--quiet".to_owned()],
+                options: BTreeMap::from([
+                    ("Threads".to_owned(), "4".to_owned()),
+                    ("Ponder".to_owned(), "false".to_owned()),
+                    ("Skill Level".to_owned(), "20".to_owned())
+                ]),
+                limits: HashMap::from([
+                    (Speed::UltraBullet, SearchLimits {
+                        depth: Some(6),
+                        ..SearchLimits::default()
+                    }),
+                    (Speed::Rapid, SearchLimits {
+                        depth: None,
+                        nodes: Some(1000000),
+                        move_time_ms: Some(5000)
+                    })
+                ])
+            }),
+            time: TimeConfig {
+                move_overhead_ms: 250,
+                ..TimeConfig::default()
+            }
+        }
+    }
+
+    #[test]
+    fn parses_toml_config() {
+        assert_that!(BotConfig::from_toml_str(TOML_CONFIG).unwrap())
+            .is_equal_to(expected_config());
+    }
+
+    #[test]
+    fn parses_json_config() {
+        let json = r#"{
+            "token": "testToken",
+            "challenge": {
+                "max_concurrent_games": 3,
+                "ordering": "priority",
+                "expiry_seconds": 60,
+                "variants": ["standard", "kingOfTheHill"],
+                "speeds": ["blitz"],
+                "accept_humans": false,
+                "min_rating": 1500
+            },
+            "engine": {
+                "path": "/usr/bin/stockfish",
+                "args": ["--quiet"],
+                "options": { "Threads": 4, "Ponder": false, "Skill Level": "20" },
+                "limits": {
+                    "ultraBullet": { "depth": 6 },
+                    "rapid": { "move_time_ms": 5000, "nodes": 1000000 }
+                }
+            },
+            "time": {
+                "move_overhead_ms": 250
+            }
+        }"#;
+
+        assert_that!(BotConfig::from_json_str(json).unwrap()).is_equal_to(expected_config());
+    }
+
+    #[test]
+    fn empty_config_has_defaults() {
+        assert_that!(BotConfig::from_toml_str("").unwrap()).is_equal_to(BotConfig::default());
+    }
+
+    #[rstest]
+    #[case::unknown_key("unknown = 1")]
+    #[case::unknown_variant("[challenge]\nvariants = [\"chess\"]")]
+    #[case::wrong_type("token = 1")]
+    #[case::engine_without_path("[engine]\nargs = []")]
+    fn rejects_invalid_config(#[case] source: &str) {
+        assert!(matches!(BotConfig::from_toml_str(source), Err(ConfigError::Toml(_))));
+    }
+
+    #[rstest]
+    #[case::missing_value("token =")]
+    #[case::unclosed_table("[challenge")]
+    #[case::duplicate_key("token = \"a\"\ntoken = \"b\"")]
+    fn rejects_malformed_toml(#[case] source: &str) {
+        assert!(matches!(BotConfig::from_toml_str(source), Err(ConfigError::Toml(_))));
+    }
+
+    #[test]
+    fn parses_yaml_config() {
+        assert_that!(BotConfig::from_yaml_str(YAML_CONFIG).unwrap())
+            .is_equal_to(expected_config());
+    }
+
+    #[rstest]
+    #[case::unknown_key("unknown: 1")]
+    #[case::unknown_variant("challenge:\n  variants: [chess]")]
+    #[case::malformed("token: [testToken")]
+    fn rejects_invalid_yaml_config(#[case] source: &str) {
+        assert!(matches!(BotConfig::from_yaml_str(source), Err(ConfigError::Yaml(_))));
+    }
+
+    #[test]
+    fn load_rejects_unsupported_format() {
+        assert!(matches!(BotConfig::load("config.ini"),
+            Err(ConfigError::UnsupportedFormat(path)) if path == Path::new("config.ini")));
+    }
+
+    #[rstest]
+    #[case::toml("toml", TOML_CONFIG)]
+    #[case::yaml("yaml", YAML_CONFIG)]
+    #[case::yml("yml", YAML_CONFIG)]
+    fn load_reads_config_file(#[case] extension: &str, #[case] source: &str) {
+        let path = std::env::temp_dir()
+            .join(format!("libot-config-{}.{extension}", std::process::id()));
+        fs::write(&path, source).unwrap();
+
+        let config = BotConfig::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_that!(config.unwrap()).is_equal_to(expected_config());
+    }
+
+    #[test]
+    fn debug_output_does_not_contain_token() {
+        let config = BotConfig::from_toml_str("token = \"secret-token-abc123\"").unwrap();
+        let debug = format!("{:?}", config);
+
+        assert_that!(debug.contains("secret-token-abc123")).is_false();
+        assert_that!(debug.contains("<redacted>")).is_true();
+    }
+
+    #[test]
+    fn environment_overrides_values() {
+        let env = HashMap::from([
+            ("LIBOT_TOKEN", "envToken"),
+            ("LIBOT_BASE_URL", "http://localhost"),
+            ("LIBOT_MAX_CONCURRENT_GAMES", "5"),
+            ("LIBOT_ENGINE_PATH", "/opt/engine"),
+            ("LIBOT_MOVE_OVERHEAD_MS", " 300 ")
+        ]);
+
+        let config = BotConfig::default()
+            .with_overrides(|name| env.get(name).map(|&value| value.to_owned()))
+            .unwrap();
+
+        assert_that!(config.token).contains(Secret::new("envToken"));
+        assert_that!(config.base_url).contains("http://localhost".to_owned());
+        assert_that!(config.challenge.unwrap().max_concurrent_games).is_equal_to(5);
+        assert_that!(config.engine).contains(EngineConfig::new("/opt/engine"));
+        assert_that!(config.time.move_overhead_ms).is_equal_to(300);
+    }
+
+    #[test]
+    fn environment_override_rejects_invalid_number() {
+        let config = BotConfig::default().with_overrides(|name|
+            (name == "LIBOT_MOVE_OVERHEAD_MS").then(|| "fast".to_owned()));
+
+        assert!(matches!(config, Err(ConfigError::InvalidEnvironmentVariable { name, value })
+            if name == "LIBOT_MOVE_OVERHEAD_MS" && value == "fast"));
+    }
+
+    fn challenge(title: Option<Title>, rating: Option<Rating>, variant: Variant, speed: Speed,
+            rated: bool) -> Challenge {
+        Challenge {
+            id: "testChallengeId".to_owned(),
+            url: "testUrl".to_owned(),
+            status: ChallengeStatus::Created,
+            challenger: User {
+                rating,
+                provisional: false,
+                online: true,
+                id: "testUserId".to_owned(),
+                name: "testUserName".to_owned(),
+                title,
+                patron: false
+            },
+            dest_user: None,
+            variant: Some(variant),
+            rated,
+            speed,
+            time_control: TimeControl::Unlimited,
+            color: ChallengeColor::Random,
+            perf: ChallengePerf {
+                icon: None,
+                name: None
+            },
+            direction: None,
+            initial_fen: None,
+            rematch_of: None,
+            decline: None
+        }
+    }
+
+    #[rstest]
+    #[case::accepted(Some(Title::Bot), Some(2000), Variant::Standard, Speed::Blitz, true,
+        ChallengeDecision::Enqueue { priority: 0 })]
+    #[case::human(None, Some(2000), Variant::Standard, Speed::Blitz, true,
+        ChallengeDecision::Decline(Some(DeclineReason::OnlyBot)))]
+    #[case::variant(Some(Title::Bot), Some(2000), Variant::Atomic, Speed::Blitz, true,
+        ChallengeDecision::Decline(Some(DeclineReason::Variant)))]
+    #[case::speed(Some(Title::Bot), Some(2000), Variant::Standard, Speed::Bullet, true,
+        ChallengeDecision::Decline(Some(DeclineReason::TimeControl)))]
+    #[case::casual(Some(Title::Bot), Some(2000), Variant::Standard, Speed::Blitz, false,
+        ChallengeDecision::Decline(Some(DeclineReason::Rated)))]
+    #[case::rating_too_low(Some(Title::Bot), Some(1400), Variant::Standard, Speed::Blitz, true,
+        ChallengeDecision::Decline(Some(DeclineReason::Generic)))]
+    #[case::rating_unknown(Some(Title::Bot), None, Variant::Standard, Speed::Blitz, true,
+        ChallengeDecision::Decline(Some(DeclineReason::Generic)))]
+    fn challenge_config_decides(#[case] title: Option<Title>, #[case] rating: Option<Rating>,
+            #[case] variant: Variant, #[case] speed: Speed, #[case] rated: bool,
+            #[case] expected_decision: ChallengeDecision) {
+        let config = ChallengeConfig {
+            variants: vec![Variant::Standard, Variant::Chess960],
+            speeds: vec![Speed::Blitz],
+            accept_casual: false,
+            accept_humans: false,
+            min_rating: Some(1500),
+            ..ChallengeConfig::default()
+        };
+        let challenge = challenge(title, rating, variant, speed, rated);
+
+        assert_that!(config.decide(&challenge)).is_equal_to(expected_decision);
+    }
+
+    #[test]
+    fn standard_only_config_declines_variants_with_standard_reason() {
+        let config = ChallengeConfig {
+            variants: vec![Variant::Standard],
+            ..ChallengeConfig::default()
+        };
+        let challenge = challenge(None, None, Variant::Horde, Speed::Blitz, false);
+
+        assert_that!(config.decide(&challenge))
+            .is_equal_to(ChallengeDecision::Decline(Some(DeclineReason::Standard)));
+    }
+
+    #[rstest]
+    #[case::regular(60_000, 0, 2_000)]
+    #[case::with_increment(60_000, 1_000, 3_000)]
+    #[case::minimum(600, 0, 50)]
+    #[case::limited_by_remaining_time(120, 0, 20)]
+    #[case::no_time_left(50, 0, 0)]
+    fn time_config_move_time(#[case] remaining: Milliseconds, #[case] increment: Milliseconds,
+            #[case] expected_move_time: Milliseconds) {
+        assert_that!(TimeConfig::default().move_time(remaining, increment))
+            .is_equal_to(expected_move_time);
+    }
+
+    #[test]
+    fn time_config_respects_maximum_move_time() {
+        let config = TimeConfig {
+            max_move_time_ms: Some(1_000),
+            ..TimeConfig::default()
+        };
+
+        assert_that!(config.move_time(60_000, 0)).is_equal_to(1_000);
+    }
+}
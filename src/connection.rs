@@ -0,0 +1,351 @@
+use std::time::Duration;
+
+use reqwest::{ClientBuilder, Method};
+
+/// Configures the HTTP connections of a [BotClient](crate::client::BotClient), i.e. connection
+/// pooling, TCP options, and HTTP/2 keep-alive. Set using the `with_connection_config` method of
+/// [BotClientBuilder](crate::client::BotClientBuilder).
+///
+/// Unset options keep the defaults of `reqwest`. For bots playing fast time controls,
+/// [ConnectionConfig::low_latency] keeps idle connections open indefinitely and probes them with
+/// keep-alive pings, so moves are posted on warm connections instead of paying for a new TCP and
+/// TLS handshake after a pause between games. In addition,
+/// [BotClient::prewarm](crate::client::BotClient::prewarm) can be used to open a connection before
+/// the first game starts.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ConnectionConfig {
+    pool_idle_timeout: Option<Option<Duration>>,
+    pool_max_idle_per_host: Option<usize>,
+    connect_timeout: Option<Duration>,
+    tcp_nodelay: Option<bool>,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_keep_alive_timeout: Option<Duration>,
+    http2_keep_alive_while_idle: Option<bool>,
+    http2_adaptive_window: Option<bool>
+}
+
+impl ConnectionConfig {
+
+    /// Creates a new config which keeps all defaults of `reqwest`.
+    pub fn new() -> ConnectionConfig {
+        ConnectionConfig::default()
+    }
+
+    /// Creates a config tuned to keep connections warm, i.e. idle connections are never closed by
+    /// the pool, TCP keep-alive probes are sent every 30 seconds, Nagle's algorithm is disabled,
+    /// and HTTP/2 connections are pinged every 20 seconds even when idle, closing them if a ping
+    /// is not answered within 10 seconds.
+    pub fn low_latency() -> ConnectionConfig {
+        ConnectionConfig::new()
+            .with_pool_idle_timeout(None)
+            .with_tcp_nodelay(true)
+            .with_tcp_keepalive(Duration::from_secs(30))
+            .with_http2_keep_alive_interval(Duration::from_secs(20))
+            .with_http2_keep_alive_timeout(Duration::from_secs(10))
+            .with_http2_keep_alive_while_idle(true)
+    }
+
+    /// Sets the time after which idle pooled connections are closed, or [None] to keep them open
+    /// until the server closes them. The config is returned for chaining.
+    pub fn with_pool_idle_timeout(mut self, timeout: Option<Duration>) -> ConnectionConfig {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept in the pool per host. The config is
+    /// returned for chaining.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> ConnectionConfig {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets the timeout for establishing a new connection. The config is returned for chaining.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> ConnectionConfig {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether Nagle's algorithm is disabled on new connections, so small requests such as
+    /// moves are sent without delay. The config is returned for chaining.
+    pub fn with_tcp_nodelay(mut self, enabled: bool) -> ConnectionConfig {
+        self.tcp_nodelay = Some(enabled);
+        self
+    }
+
+    /// Sets the interval of TCP keep-alive probes on new connections. The config is returned for
+    /// chaining.
+    pub fn with_tcp_keepalive(mut self, interval: Duration) -> ConnectionConfig {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Sets the interval of HTTP/2 keep-alive pings. The config is returned for chaining.
+    pub fn with_http2_keep_alive_interval(mut self, interval: Duration) -> ConnectionConfig {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets the time after which a connection is closed if an HTTP/2 keep-alive ping is not
+    /// answered. Only effective if a keep-alive interval is set. The config is returned for
+    /// chaining.
+    pub fn with_http2_keep_alive_timeout(mut self, timeout: Duration) -> ConnectionConfig {
+        self.http2_keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether HTTP/2 keep-alive pings are also sent while there are no open requests or
+    /// streams on the connection. Only effective if a keep-alive interval is set. The config is
+    /// returned for chaining.
+    pub fn with_http2_keep_alive_while_idle(mut self, enabled: bool) -> ConnectionConfig {
+        self.http2_keep_alive_while_idle = Some(enabled);
+        self
+    }
+
+    /// Sets whether the HTTP/2 flow-control window is adapted to the measured bandwidth-delay
+    /// product. The config is returned for chaining.
+    pub fn with_http2_adaptive_window(mut self, enabled: bool) -> ConnectionConfig {
+        self.http2_adaptive_window = Some(enabled);
+        self
+    }
+
+    pub(crate) fn apply(&self, mut builder: ClientBuilder) -> ClientBuilder {
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(enabled) = self.tcp_nodelay {
+            builder = builder.tcp_nodelay(enabled);
+        }
+
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder.http2_keep_alive_interval(interval);
+        }
+
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder = builder.http2_keep_alive_timeout(timeout);
+        }
+
+        if let Some(enabled) = self.http2_keep_alive_while_idle {
+            builder = builder.http2_keep_alive_while_idle(enabled);
+        }
+
+        if let Some(enabled) = self.http2_adaptive_window {
+            builder = builder.http2_adaptive_window(enabled);
+        }
+
+        builder
+    }
+}
+
+/// The timeout of requests in the [EndpointCategory::GameAction] category used by default.
+pub const DEFAULT_GAME_ACTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A category of Lichess API endpoints, which determines the timeout of requests to them (see
+/// [RequestTimeouts]).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EndpointCategory {
+
+    /// Actions in a game, such as making a move, resigning, or offering a draw. These should fail
+    /// quickly rather than hang while the clock is running.
+    GameAction,
+
+    /// Long-lived streams, such as the event stream, the game streams, and the stream of a
+    /// challenge which is kept alive. These are open as long as the events are of interest, so a
+    /// timeout would cut them off.
+    Stream,
+
+    /// All other requests, such as chat messages, queries of profiles, challenges, or
+    /// tournaments, and exports of games.
+    Other
+}
+
+impl EndpointCategory {
+
+    /// Determines the category of a request with the given method to the given URL path. Paths of
+    /// streams contain a `stream` segment, while game actions are any other requests to a
+    /// `game` or `round` path which do not just read data, except for chat messages and pushes to
+    /// broadcast rounds.
+    pub fn of(method: &Method, path: &str) -> EndpointCategory {
+        let has_segment = |name: &str| path.split('/').any(|segment| segment == name);
+
+        if has_segment("stream") {
+            EndpointCategory::Stream
+        }
+        else if method != Method::GET && !has_segment("chat") && !has_segment("broadcast")
+                && (has_segment("game") || has_segment("round")) {
+            EndpointCategory::GameAction
+        }
+        else {
+            EndpointCategory::Other
+        }
+    }
+}
+
+/// Assigns each [EndpointCategory] the timeout of requests to its endpoints, which covers the
+/// entire request, from connecting to receiving the last byte of the response. Set using the
+/// `with_request_timeouts` method of [BotClientBuilder](crate::client::BotClientBuilder).
+///
+/// By default, game actions time out after [DEFAULT_GAME_ACTION_TIMEOUT], while streams and
+/// other requests, such as exports of many games, have no timeout.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RequestTimeouts {
+    game_action: Option<Duration>,
+    stream: Option<Duration>,
+    other: Option<Duration>
+}
+
+impl RequestTimeouts {
+
+    /// Creates a new table of timeouts with the default values.
+    pub fn new() -> RequestTimeouts {
+        RequestTimeouts::default()
+    }
+
+    /// Creates a new table in which no request times out.
+    pub fn none() -> RequestTimeouts {
+        RequestTimeouts {
+            game_action: None,
+            stream: None,
+            other: None
+        }
+    }
+
+    /// Sets the timeout of requests in the given category, or [None] to let them run
+    /// indefinitely. The table is returned for chaining.
+    pub fn with_timeout(mut self, category: EndpointCategory, timeout: Option<Duration>)
+            -> RequestTimeouts {
+        *self.timeout_mut(category) = timeout;
+        self
+    }
+
+    /// Gets the timeout of requests in the given category, or [None] if they do not time out.
+    pub fn timeout(&self, category: EndpointCategory) -> Option<Duration> {
+        match category {
+            EndpointCategory::GameAction => self.game_action,
+            EndpointCategory::Stream => self.stream,
+            EndpointCategory::Other => self.other
+        }
+    }
+
+    fn timeout_mut(&mut self, category: EndpointCategory) -> &mut Option<Duration> {
+        match category {
+            EndpointCategory::GameAction => &mut self.game_action,
+            EndpointCategory::Stream => &mut self.stream,
+            EndpointCategory::Other => &mut self.other
+        }
+    }
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> RequestTimeouts {
+        RequestTimeouts::none()
+            .with_timeout(EndpointCategory::GameAction, Some(DEFAULT_GAME_ACTION_TIMEOUT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn new_config_keeps_reqwest_defaults() {
+        let config = ConnectionConfig::new();
+
+        assert_that!(config.pool_idle_timeout).is_none();
+        assert_that!(config.tcp_nodelay).is_none();
+        assert_that!(config.http2_keep_alive_interval).is_none();
+    }
+
+    #[test]
+    fn low_latency_config_keeps_idle_connections_open() {
+        let config = ConnectionConfig::low_latency();
+
+        assert_that!(config.pool_idle_timeout).contains(None);
+        assert_that!(config.tcp_nodelay).contains(true);
+        assert_that!(config.http2_keep_alive_while_idle).contains(true);
+        assert_that!(config.http2_keep_alive_interval).contains(Duration::from_secs(20));
+    }
+
+    #[test]
+    fn builder_methods_override_presets() {
+        let config = ConnectionConfig::low_latency()
+            .with_pool_idle_timeout(Some(Duration::from_secs(5)))
+            .with_pool_max_idle_per_host(2)
+            .with_connect_timeout(Duration::from_secs(3))
+            .with_http2_adaptive_window(true);
+
+        assert_that!(config.pool_idle_timeout).contains(Some(Duration::from_secs(5)));
+        assert_that!(config.pool_max_idle_per_host).contains(2);
+        assert_that!(config.connect_timeout).contains(Duration::from_secs(3));
+        assert_that!(config.http2_adaptive_window).contains(true);
+    }
+
+    #[test]
+    fn applied_config_builds_client() {
+        let builder = ConnectionConfig::low_latency()
+            .with_pool_max_idle_per_host(2)
+            .with_connect_timeout(Duration::from_secs(3))
+            .apply(ClientBuilder::new());
+
+        assert_that!(builder.build()).is_ok();
+    }
+
+    #[rstest]
+    #[case::event_stream(Method::GET, "/api/stream/event", EndpointCategory::Stream)]
+    #[case::game_stream(Method::GET, "/api/bot/game/stream/testId", EndpointCategory::Stream)]
+    #[case::games_by_users(
+        Method::POST, "/api/stream/games-by-users", EndpointCategory::Stream)]
+    #[case::make_move(
+        Method::POST, "/api/bot/game/testId/move/e2e4", EndpointCategory::GameAction)]
+    #[case::resign(Method::POST, "/api/board/game/testId/resign", EndpointCategory::GameAction)]
+    #[case::add_time(Method::POST, "/api/round/testId/add-time/15", EndpointCategory::GameAction)]
+    #[case::read_chat(Method::GET, "/api/bot/game/testId/chat", EndpointCategory::Other)]
+    #[case::write_chat(Method::POST, "/api/bot/game/testId/chat", EndpointCategory::Other)]
+    #[case::broadcast_push(
+        Method::POST, "/api/broadcast/round/testId/push", EndpointCategory::Other)]
+    #[case::export_games(Method::POST, "/api/games/export/_ids", EndpointCategory::Other)]
+    #[case::account(Method::GET, "/api/account", EndpointCategory::Other)]
+    fn endpoint_category_is_derived_from_path(#[case] method: Method, #[case] path: &str,
+            #[case] expected: EndpointCategory) {
+        assert_that!(EndpointCategory::of(&method, path)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn default_timeouts_only_limit_game_actions() {
+        let timeouts = RequestTimeouts::default();
+
+        assert_that!(timeouts.timeout(EndpointCategory::GameAction))
+            .contains(DEFAULT_GAME_ACTION_TIMEOUT);
+        assert_that!(timeouts.timeout(EndpointCategory::Stream)).is_none();
+        assert_that!(timeouts.timeout(EndpointCategory::Other)).is_none();
+    }
+
+    #[test]
+    fn with_timeout_overrides_single_category() {
+        let timeouts = RequestTimeouts::new()
+            .with_timeout(EndpointCategory::GameAction, None)
+            .with_timeout(EndpointCategory::Other, Some(Duration::from_secs(30)));
+
+        assert_that!(timeouts.timeout(EndpointCategory::GameAction)).is_none();
+        assert_that!(timeouts.timeout(EndpointCategory::Stream)).is_none();
+        assert_that!(timeouts.timeout(EndpointCategory::Other)).contains(Duration::from_secs(30));
+    }
+}
@@ -1,12 +1,63 @@
+use std::future::Future;
 use std::ops::Deref;
-use crate::model::game::{Color, GameInfo};
-use crate::model::user::UserId;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+
+use crate::by_address::ByAddress;
+use crate::chess::eco;
+use crate::chess::position::{Pockets, Position};
+use crate::client::{BotClient, MoveOutcome};
+use crate::error::{LibotRequestError, LibotResult};
+use crate::model::{Move, Url};
+use crate::model::game::{Color, GameInfo, Variant};
+use crate::model::game::event::GameStateEvent;
+use crate::model::game::opening::Opening;
+use crate::model::user::{PlayerRef, UserId, UserProfile};
+use crate::runner::cancellation::GameCancellation;
+use crate::runner::move_confirmation::MoveWatcher;
+use crate::runner::position_tracker::PositionTracker;
+use crate::runner::snapshot::SessionLog;
+use crate::runner::spectators::{Spectators, SpectatorTracker};
+use crate::runner::telemetry::MoveTimer;
+use crate::stats::OpponentStats;
+
+/// The number of times [GameContext::submit_move] submits a move whose registration is unclear.
+const MOVE_SUBMISSION_ATTEMPTS: u32 = 2;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct BotContext {
 
     /// The [UserId] of this bot's user.
-    pub bot_id: UserId
+    pub bot_id: UserId,
+
+    pub(crate) profile: ByAddress<RwLock<Option<UserProfile>>>
+}
+
+impl BotContext {
+
+    /// Gets the most recently fetched [UserProfile] of this bot's user, including its ratings and
+    /// title. The profile is fetched when the runner starts and updated by
+    /// [BotContext::refresh_profile]. It is [None] if the bot is not connected to Lichess, e.g.
+    /// while replaying a recorded session.
+    pub fn profile(&self) -> Option<UserProfile> {
+        self.profile.0.read().unwrap().clone()
+    }
+
+    /// Fetches the current [UserProfile] of this bot's user with the given client and stores it
+    /// in this context, so that it is returned by [BotContext::profile] in all handlers of this
+    /// session afterwards. The cached profile is left unchanged if the request fails.
+    ///
+    /// # Errors
+    ///
+    /// Any error which occurs while fetching the profile (see [BotClient::get_my_profile]).
+    pub async fn refresh_profile(&self, client: &BotClient) -> LibotResult<UserProfile> {
+        let profile = client.get_my_profile().await?;
+        *self.profile.0.write().unwrap() = Some(profile.clone());
+
+        Ok(profile)
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -18,7 +69,361 @@ pub struct GameContext {
     /// The [Color] as which this bot plays, or [None] if it is not a participant.
     pub bot_color: Option<Color>,
 
-    pub(crate) info: GameInfo
+    pub(crate) info: GameInfo,
+
+    pub(crate) opponent_stats: Option<OpponentStats>,
+
+    pub(crate) move_timer: Option<ByAddress<MoveTimer>>,
+
+    pub(crate) position_tracker: Option<ByAddress<PositionTracker>>,
+
+    pub(crate) move_watcher: Option<ByAddress<MoveWatcher>>,
+
+    pub(crate) game_cancellation: Option<ByAddress<GameCancellation>>,
+
+    pub(crate) session_log: Option<ByAddress<SessionLog>>,
+
+    pub(crate) spectators: Option<ByAddress<SpectatorTracker>>
+}
+
+impl GameContext {
+
+    /// Gets the [OpponentStats] of the bot against its opponent in this game. These are only
+    /// available if enabled with
+    /// [BotRunner::with_opponent_stats](crate::runner::BotRunner::with_opponent_stats) and the
+    /// opponent is a Lichess user.
+    pub fn opponent_stats(&self) -> Option<&OpponentStats> {
+        self.opponent_stats.as_ref()
+    }
+
+    /// Gets the preferred language of the opponent in this game, e.g. to localize chat messages
+    /// with `BotClient::send_localized_chat_message`. This is only known if
+    /// [GameContext::opponent_stats] are available and the opponent's profile states it.
+    pub fn opponent_language(&self) -> Option<&str> {
+        self.opponent_stats.as_ref()?.language.as_deref()
+    }
+
+    /// Gets the player as which the bot plays in this game, or [None] if it is not a participant.
+    pub fn me(&self) -> Option<PlayerRef> {
+        Some(self.info.player_ref(self.bot_color?))
+    }
+
+    /// Gets the opponent of the bot in this game, or [None] if the bot is not a participant.
+    pub fn opponent(&self) -> Option<PlayerRef> {
+        Some(self.info.player_ref(self.bot_color?.opposite()))
+    }
+
+    /// Determines the [Color] to move in the given game state of this game from the number of
+    /// moves played and the side to move in the initial position.
+    pub fn color_to_move(&self, state: &GameStateEvent) -> Color {
+        let plies = state.moves.split_whitespace().count();
+
+        if plies.is_multiple_of(2) {
+            self.starting_color()
+        }
+        else {
+            self.starting_color().opposite()
+        }
+    }
+
+    /// Determines whether the bot has to move in the given game state of this game, i.e. the game
+    /// is still running and it is the turn of the bot's color. Always `false` if the bot is not a
+    /// participant of this game.
+    pub fn is_my_turn(&self, state: &GameStateEvent) -> bool {
+        state.status.is_running() && self.bot_color == Some(self.color_to_move(state))
+    }
+
+    /// The URL under which spectators can watch this game on Lichess, with the board oriented
+    /// towards the bot if it is a participant.
+    pub fn spectator_url(&self) -> Url {
+        match self.bot_color {
+            Some(color) => self.info.url_for(color),
+            None => self.info.url()
+        }
+    }
+
+    /// Gets the [Color] to move in the initial position of this game.
+    pub(crate) fn starting_color(&self) -> Color {
+        match self.info.initial_fen.split_whitespace().nth(1) {
+            Some("b") => Color::Black,
+            _ => Color::White
+        }
+    }
+
+    /// Gets the time the bot needed for its most recent move in this game, measured from the
+    /// runner receiving the game state in which it became the bot's turn until the move was
+    /// submitted with [GameContext::make_move]. Returns [None]
+    /// if the bot has not submitted a move yet. Comparing this with the bot's increment shows
+    /// whether the bot uses more time per move than it gains.
+    pub fn last_move_latency(&self) -> Option<Duration> {
+        self.move_timer.as_ref()?.0.last_move_latency(&self.info.id)
+    }
+
+    /// Indicates whether the bot sent a chat message in this game, e.g. to greet the opponent only
+    /// once. This is only tracked for bots run by a [BotRunner](crate::runner::BotRunner), which
+    /// registers the bot's messages as they appear in the chat of the game, and, if the runner
+    /// saves state snapshots (see
+    /// [BotRunner::with_state_snapshots](crate::runner::BotRunner::with_state_snapshots)),
+    /// includes messages sent before the process was restarted.
+    pub fn has_sent_chat_message(&self) -> bool {
+        self.session_log.as_ref()
+            .is_some_and(|session_log| session_log.0.has_chatted(&self.info.id))
+    }
+
+    /// Runs the given request concerning this game, cancelling it if the game ends before it
+    /// completes, e.g. so a delayed chat message or draw offer does not hold up the bot after the
+    /// game. Only games run by a [BotRunner](crate::runner::BotRunner) are known to end, for all
+    /// others the request is run to completion.
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::GameEnded] if the game ended before the request completed.
+    /// * Any error returned by the request.
+    pub async fn run_for_game<T>(&self, request: impl Future<Output = LibotResult<T>>)
+            -> LibotResult<T> {
+        match &self.game_cancellation {
+            Some(game_cancellation) => game_cancellation.0.run(&self.info.id, request).await
+                .unwrap_or_else(|| Err(LibotRequestError::GameEnded(self.info.id.clone()))),
+            None => request.await
+        }
+    }
+
+    /// Makes the given move in this game with the given client, like
+    /// [BotClient::make_move]. If the game is run by a [BotRunner](crate::runner::BotRunner), the
+    /// move is validated against the tracked position of the game before it is submitted,
+    /// following the rules of the game's variant, the request is cancelled if the game ends (see
+    /// [GameContext::run_for_game]) and the time of the submission is recorded for
+    /// [GameContext::last_move_latency].
+    ///
+    /// # Arguments
+    ///
+    /// * `client`: The client with which to submit the move.
+    /// * `mov`: The move to play.
+    /// * `offer_draw`: If `true`, the bot will offer a draw or accept a pending draw offer.
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::InvalidMove] if the move is not valid UCI notation or illegal in the
+    ///   tracked position. In this case, Lichess is not contacted.
+    /// * [LibotRequestError::GameEnded] if the game ended before the move was submitted.
+    /// * Any other [LibotRequestError] that occurs while sending the request.
+    pub async fn make_move(&self, client: &BotClient, mov: Move, offer_draw: bool)
+            -> LibotResult<()> {
+        if let Some(position_tracker) = &self.position_tracker {
+            position_tracker.0.validate_move(&self.info.id, &mov)?;
+        }
+
+        let submitted_at = Instant::now();
+
+        self.run_for_game(client.make_move(self.info.id.clone(), mov, offer_draw)).await?;
+
+        if let Some(move_timer) = &self.move_timer {
+            move_timer.0.move_submitted(&self.info.id, submitted_at);
+        }
+
+        Ok(())
+    }
+
+    /// Makes the given move in this game, like [GameContext::make_move], and then waits until a
+    /// game state of the game registers the move. This allows bots to resubmit a move instead of
+    /// silently flagging if Lichess did not register it. Confirmation is only available for games
+    /// run by a [BotRunner](crate::runner::BotRunner).
+    ///
+    /// # Arguments
+    ///
+    /// * `client`: The client with which to submit the move.
+    /// * `mov`: The move to play.
+    /// * `timeout`: The maximum time to wait for a game state to confirm the move after it was
+    ///   submitted.
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::MoveConfirmationUnavailable] if the game is not run by a
+    ///   [BotRunner](crate::runner::BotRunner) or no game state of it was received yet. In this
+    ///   case, the move is not submitted.
+    /// * [LibotRequestError::MoveNotConfirmed] if no game state registered the move within the
+    ///   timeout, a different move was registered or the game finished.
+    /// * Any error returned by [GameContext::make_move].
+    pub async fn make_move_confirmed(&self, client: &BotClient, mov: Move, timeout: Duration)
+            -> LibotResult<()> {
+        let subscription = self.move_watcher.as_ref()
+            .and_then(|move_watcher| move_watcher.0.subscribe(&self.info.id))
+            .ok_or_else(|| LibotRequestError::MoveConfirmationUnavailable(self.info.id.clone()))?;
+
+        self.make_move(client, mov.clone(), false).await?;
+
+        if subscription.wait_for(&mov, timeout).await {
+            Ok(())
+        }
+        else {
+            Err(LibotRequestError::MoveNotConfirmed {
+                game_id: self.info.id.clone(),
+                mov,
+                timeout
+            })
+        }
+    }
+
+    /// Submits the given move in this game, like [GameContext::make_move], but safely handles
+    /// failed requests for which it is unclear whether Lichess registered the move. After a
+    /// request fails, the tracked game state is cross-checked, waiting up to the given timeout for
+    /// a game state registering a move. If that is the submitted move, it was already applied. If
+    /// no move is registered and the request timed out, failed to connect or caused a server
+    /// error, the move is resubmitted once. Cross-checking is only available for games run by a
+    /// [BotRunner](crate::runner::BotRunner).
+    ///
+    /// # Arguments
+    ///
+    /// * `client`: The client with which to submit the move.
+    /// * `mov`: The move to play.
+    /// * `offer_draw`: If `true`, the bot will offer a draw or accept a pending draw offer.
+    /// * `timeout`: The maximum time to wait for a game state after a request failed.
+    ///
+    /// # Returns
+    ///
+    /// The [MoveOutcome] of the submission. Moves which are invalid according to the tracked
+    /// position (see [GameContext::make_move]) or which Lichess refused while no game state
+    /// registered them are [MoveOutcome::Rejected].
+    ///
+    /// # Errors
+    ///
+    /// * [LibotRequestError::MoveConfirmationUnavailable] if the game is not run by a
+    ///   [BotRunner](crate::runner::BotRunner) or no game state of it was received yet. In this
+    ///   case, the move is not submitted.
+    /// * Any other error returned by [GameContext::make_move] if it does not indicate a rejection
+    ///   and the move could not be submitted after resubmitting it.
+    pub async fn submit_move(&self, client: &BotClient, mov: Move, offer_draw: bool,
+            timeout: Duration) -> LibotResult<MoveOutcome> {
+        let mut subscription = self.move_watcher.as_ref()
+            .and_then(|move_watcher| move_watcher.0.subscribe(&self.info.id))
+            .ok_or_else(|| LibotRequestError::MoveConfirmationUnavailable(self.info.id.clone()))?;
+
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let error = match self.make_move(client, mov.clone(), offer_draw).await {
+                Ok(()) => return Ok(MoveOutcome::Applied),
+                Err(LibotRequestError::InvalidMove(_)) => return Ok(MoveOutcome::Rejected),
+                Err(error) => error
+            };
+            let retryable = match &error {
+                LibotRequestError::ReqwestError(_) => true,
+                LibotRequestError::ApiError { status, .. } if status.is_server_error() => true,
+                LibotRequestError::ApiError { status: StatusCode::BAD_REQUEST, .. } => false,
+                _ => return Err(error)
+            };
+
+            match subscription.registered_move(timeout).await {
+                Some(registered) if registered == mov => return Ok(MoveOutcome::AlreadyApplied),
+                Some(_) => return Ok(MoveOutcome::Rejected),
+                None if !retryable => return Ok(MoveOutcome::Rejected),
+                None if attempts == MOVE_SUBMISSION_ATTEMPTS => return Err(error),
+                None => { }
+            }
+        }
+    }
+
+    /// Gets the current [Position] of this game, as tracked by the runner from the moves of the
+    /// most recent game state. Returns [None] if the game is not tracked, e.g. because its
+    /// initial FEN could not be parsed.
+    pub fn position(&self) -> Option<Position> {
+        self.position_tracker.as_ref()?.0.position(&self.info.id)
+    }
+
+    /// Gets the current [Spectators] of this game, which are refreshed periodically by the runner,
+    /// e.g. to switch to more entertaining behavior while the game is being watched. Returns
+    /// [None] unless enabled with
+    /// [BotRunner::with_spectator_tracking](crate::runner::BotRunner::with_spectator_tracking).
+    pub fn spectators(&self) -> Option<Spectators> {
+        Some(self.spectators.as_ref()?.0.spectators(&self.info.id))
+    }
+
+    /// Gets the opening of this game, as classified offline from the moves played so far with the
+    /// bundled ECO table (see [eco::classify]), which also recognizes transpositions. The
+    /// classification becomes more specific as more moves are played and stays at the last
+    /// identified opening once the game leaves the table. If the
+    /// moves cannot be classified, e.g. because the game did not start from the standard starting
+    /// position or is not tracked (see [GameContext::position]), the opening included in the game
+    /// data by Lichess is returned, if any.
+    pub fn opening(&self) -> Option<Opening> {
+        let is_standard = self.variant_or_standard() == Variant::Standard
+            && self.info.initial_fen == "startpos";
+        let classified = self.position_tracker.as_ref()
+            .filter(|_| is_standard)
+            .and_then(|tracker| tracker.0.with_moves(&self.info.id, |moves| moves.join(" ")))
+            .and_then(|moves| eco::classify(&moves));
+
+        classified.or_else(|| self.info.opening.clone())
+    }
+
+    /// Gets the current [Pockets] of both players in this game, i.e. the captured pieces they can
+    /// drop onto the board. Returns [None] if this is not a Crazyhouse game or the game is not
+    /// tracked (see [GameContext::position]).
+    pub fn pockets(&self) -> Option<Pockets> {
+        if self.info.variant != Some(Variant::Crazyhouse) {
+            return None;
+        }
+
+        Some(self.position()?.pockets())
+    }
+
+    /// Gets the number of checks the given color has given so far in this game. Returns [None] if
+    /// this is not a Three-check game or the game is not tracked (see [GameContext::position]).
+    pub fn checks_given(&self, color: Color) -> Option<u8> {
+        self.with_variant_position(Variant::ThreeCheck,
+            |position| position.checks_given(color))
+    }
+
+    /// Gets the number of checks the given color still has to give to win this game. Returns
+    /// [None] if this is not a Three-check game or the game is not tracked (see
+    /// [GameContext::position]).
+    pub fn remaining_checks(&self, color: Color) -> Option<u8> {
+        self.with_variant_position(Variant::ThreeCheck,
+            |position| position.remaining_checks(color))
+    }
+
+    /// Gets the number of king moves the king of the given color needs at least to reach the
+    /// center and win this game, where 0 means the king is on the center. Returns [None] if this
+    /// is not a King of the Hill game or the game is not tracked (see [GameContext::position]).
+    pub fn king_center_distance(&self, color: Color) -> Option<u8> {
+        self.with_variant_position(Variant::KingOfTheHill,
+            |position| position.king_center_distance(color)).flatten()
+    }
+
+    /// Gets the winner of this game if it has been decided by a variant-specific win condition,
+    /// such as a third check or a king reaching the center (see [Position::variant_winner]).
+    /// Returns [None] if there is no such winner or the game is not tracked.
+    pub fn variant_winner(&self) -> Option<Color> {
+        self.with_position(Position::variant_winner).flatten()
+    }
+
+    /// Indicates whether the material on the board of this game is trivially drawn, i.e. neither
+    /// player has enough material to checkmate (see [Position::has_mating_material]). This
+    /// includes dead positions (see [Position::is_insufficient_material]) as well as positions in
+    /// which a mate is only possible with a blunder of the opponent, such as a knight against a
+    /// knight. Bots can use this to offer a draw or stop spending time on their moves. Always
+    /// `false` in variants other than Standard, Chess960 and From Position, or if the game is not
+    /// tracked (see [GameContext::position]).
+    pub fn is_drawish_material(&self) -> bool {
+        self.with_position(|position| !position.has_mating_material(Color::White)
+            && !position.has_mating_material(Color::Black))
+            .unwrap_or(false)
+    }
+
+    fn with_position<T>(&self, f: impl FnOnce(&Position) -> T) -> Option<T> {
+        self.position_tracker.as_ref()?.0.with_position(&self.info.id, f)
+    }
+
+    fn with_variant_position<T>(&self, variant: Variant, f: impl FnOnce(&Position) -> T)
+            -> Option<T> {
+        if self.info.variant != Some(variant) {
+            return None;
+        }
+
+        self.with_position(f)
+    }
 }
 
 impl Deref for GameContext {
@@ -29,3 +434,464 @@ impl Deref for GameContext {
         &self.info
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{method, path};
+
+    use crate::chess::ChessError;
+    use crate::client::tests as client_tests;
+    use crate::model::game::GameStatus;
+    use crate::model::game::chat::ChatRoom;
+    use crate::runner::cancellation::GameCancellation;
+    use crate::runner::move_confirmation::MoveWatcher;
+    use crate::runner::position_tracker::PositionTracker;
+    use crate::runner::snapshot::SessionLog;
+    use crate::store::tests as store_tests;
+    use crate::test_util;
+
+    use super::*;
+
+    fn test_context(bot_color: Option<Color>, initial_fen: &str) -> GameContext {
+        GameContext {
+            bot_id: "testBotId".to_owned(),
+            bot_color,
+            info: GameInfo {
+                initial_fen: initial_fen.to_owned(),
+                ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    fn test_state(moves: &str, status: GameStatus) -> GameStateEvent {
+        GameStateEvent {
+            moves: moves.to_owned(),
+            white_time: 0,
+            black_time: 0,
+            white_increment: 0,
+            black_increment: 0,
+            status,
+            winner: None,
+            white_draw_offer: false,
+            black_draw_offer: false,
+            white_take_back_proposal: false,
+            black_take_back_proposal: false,
+            skipped_states: 0
+        }
+    }
+
+    #[rstest]
+    #[case::start("startpos", "", Color::White)]
+    #[case::after_one_move("startpos", "e2e4", Color::Black)]
+    #[case::after_two_moves("startpos", "e2e4 e7e5", Color::White)]
+    #[case::black_to_start(
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", "", Color::Black)]
+    #[case::black_to_start_after_one_move(
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", "e7e5", Color::White)]
+    fn color_to_move_works(#[case] initial_fen: &str, #[case] moves: &str,
+            #[case] expected: Color) {
+        let context = test_context(Some(Color::White), initial_fen);
+
+        assert_that!(context.color_to_move(&test_state(moves, GameStatus::Started)))
+            .is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::white_to_move(Some(Color::White), "", GameStatus::Started, true)]
+    #[case::black_to_move(Some(Color::White), "e2e4", GameStatus::Started, false)]
+    #[case::black_bot(Some(Color::Black), "e2e4", GameStatus::Started, true)]
+    #[case::game_over(Some(Color::White), "", GameStatus::Resign, false)]
+    #[case::not_participant(None, "", GameStatus::Started, false)]
+    fn is_my_turn_works(#[case] bot_color: Option<Color>, #[case] moves: &str,
+            #[case] status: GameStatus, #[case] expected: bool) {
+        let context = test_context(bot_color, "startpos");
+
+        assert_that!(context.is_my_turn(&test_state(moves, status))).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::white(Some(Color::White), Some("testBotId"), Some("opponent"))]
+    #[case::black(Some(Color::Black), Some("opponent"), Some("testBotId"))]
+    #[case::not_participant(None, None, None)]
+    fn me_and_opponent_are_selected_by_bot_color(#[case] bot_color: Option<Color>,
+            #[case] expected_me: Option<&str>, #[case] expected_opponent: Option<&str>) {
+        let context = test_context(bot_color, "startpos");
+
+        assert_that!(context.me().as_ref().and_then(PlayerRef::id)).is_equal_to(expected_me);
+        assert_that!(context.opponent().as_ref().and_then(PlayerRef::id))
+            .is_equal_to(expected_opponent);
+    }
+
+    #[rstest]
+    #[case::white("testBotId", Some(Color::White))]
+    #[case::black("opponent", Some(Color::Black))]
+    #[case::not_participant("spectator", None)]
+    fn color_of_user_is_determined_from_players(#[case] user_id: &str,
+            #[case] expected: Option<Color>) {
+        let context = test_context(None, "startpos");
+
+        assert_that!(context.color_of(user_id)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn game_info_accessors_work() {
+        let mut context = test_context(Some(Color::White), "startpos");
+        context.info.variant = None;
+
+        assert_that!(context.is_rated()).is_true();
+        assert_that!(context.clock_initial()).contains(180);
+        assert_that!(context.variant_or_standard()).is_equal_to(Variant::Standard);
+    }
+
+    #[rstest]
+    #[case::not_started("", None)]
+    #[case::classified("e2e4 c7c5 c2c3", Some("Sicilian Defense: Alapin Variation"))]
+    #[case::left_table("e2e4 c7c5 c2c3 d7d5 e4d5", Some("Sicilian Defense: Alapin Variation"))]
+    fn opening_is_classified_from_tracked_moves(#[case] moves: &str,
+            #[case] expected: Option<&str>) {
+        let tracker = Arc::new(PositionTracker::default());
+        let mut context = test_context(Some(Color::White), "startpos");
+        context.position_tracker = Some(ByAddress(Arc::clone(&tracker)));
+
+        tracker.game_started(&context.info, moves);
+
+        assert_that!(context.opening().map(|opening| opening.name))
+            .is_equal_to(expected.map(str::to_owned));
+    }
+
+    #[rstest]
+    #[case::start("startpos", "", false)]
+    #[case::knight_each("4kn2/8/8/8/8/8/8/4KN2 w - - 0 1", "", true)]
+    #[case::pawn_captured("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1", "e1d2", true)]
+    #[case::rook("4k3/8/8/8/8/8/8/4K2R w - - 0 1", "", false)]
+    fn drawish_material_is_detected_in_tracked_position(#[case] initial_fen: &str,
+            #[case] moves: &str, #[case] expected: bool) {
+        let tracker = Arc::new(PositionTracker::default());
+        let mut context = test_context(Some(Color::White), initial_fen);
+        context.position_tracker = Some(ByAddress(Arc::clone(&tracker)));
+
+        tracker.game_started(&context.info, moves);
+
+        assert_that!(context.is_drawish_material()).is_equal_to(expected);
+    }
+
+    #[test]
+    fn drawish_material_is_not_detected_in_untracked_game() {
+        let context = test_context(Some(Color::White), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert_that!(context.is_drawish_material()).is_false();
+    }
+
+    #[test]
+    fn opening_falls_back_to_game_data_if_not_classified() {
+        let opening = Opening {
+            eco: "A00".to_owned(),
+            name: "Custom Opening".to_owned(),
+            ply: 0
+        };
+        let mut context = test_context(Some(Color::White), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        context.info.opening = Some(opening.clone());
+
+        assert_that!(context.opening()).contains(opening);
+    }
+
+    #[test]
+    fn refreshed_profile_is_shared_between_bot_contexts() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/account"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(client_tests::get_test_user_json()))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let context = BotContext {
+                bot_id: "testId".to_owned(),
+                profile: ByAddress::default()
+            };
+            let cloned_context = context.clone();
+
+            assert_that!(context.profile()).is_none();
+
+            let result = context.refresh_profile(&client).await;
+
+            assert_that!(result).contains_value(client_tests::get_test_user());
+            assert_that!(cloned_context.profile()).contains(client_tests::get_test_user());
+        })
+    }
+
+    #[test]
+    fn failed_profile_refresh_keeps_cached_profile() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("GET"))
+                .and(path("/account"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&server)
+                .await;
+
+            let context = BotContext {
+                bot_id: "testId".to_owned(),
+                profile: ByAddress(Arc::new(RwLock::new(Some(client_tests::get_test_user()))))
+            };
+
+            let result = context.refresh_profile(&client).await;
+
+            assert_that!(result).is_err();
+            assert_that!(context.profile()).contains(client_tests::get_test_user());
+        })
+    }
+
+    fn tracked_context(position_tracker: &Arc<PositionTracker>,
+            move_watcher: &Arc<MoveWatcher>) -> GameContext {
+        GameContext {
+            position_tracker: Some(ByAddress(Arc::clone(position_tracker))),
+            move_watcher: Some(ByAddress(Arc::clone(move_watcher))),
+            ..test_context(Some(Color::Black), "startpos")
+        }
+    }
+
+    #[test]
+    fn make_move_rejects_illegal_move_in_tracked_game() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let position_tracker = Arc::new(PositionTracker::default());
+            let mut context = tracked_context(&position_tracker, &Arc::default());
+            context.info.variant = Some(Variant::Crazyhouse);
+            position_tracker.game_started(&context.info, "e2e4 d7d5 e4d5");
+
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let illegal_result = context.make_move(&client, "N@e6".to_owned(), false).await;
+            let legal_result = context.make_move(&client, "d8d5".to_owned(), false).await;
+
+            assert!(matches!(illegal_result,
+                Err(LibotRequestError::InvalidMove(ChessError::IllegalMove(_)))));
+            assert_that!(legal_result).is_ok();
+        });
+    }
+
+    #[test]
+    fn make_move_confirmed_fails_without_move_watcher() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let context = test_context(Some(Color::Black), "startpos");
+
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(0)
+                .mount(&server)
+                .await;
+
+            let result = context
+                .make_move_confirmed(&client, "e2e4".to_owned(), Duration::from_secs(1)).await;
+
+            assert!(matches!(result,
+                Err(LibotRequestError::MoveConfirmationUnavailable(game_id))
+                    if game_id == "testGameId"));
+        })
+    }
+
+    #[test]
+    fn make_move_confirmed_fails_if_move_is_not_registered_in_time() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let move_watcher = Arc::new(MoveWatcher::default());
+            move_watcher.update(&"testGameId".to_owned(), "e2e4");
+            let context = tracked_context(&Arc::default(), &move_watcher);
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e7e5"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = context
+                .make_move_confirmed(&client, "e7e5".to_owned(), Duration::from_millis(20)).await;
+
+            assert!(matches!(result, Err(LibotRequestError::MoveNotConfirmed { mov, .. })
+                if mov == "e7e5"));
+        })
+    }
+
+    async fn setup_move_submission_test(status: u16, expected_requests: u64)
+            -> (GameContext, BotClient, MockServer, Arc<MoveWatcher>) {
+        let (client, server) = test_util::setup_wiremock_test().await;
+        let move_watcher = Arc::new(MoveWatcher::default());
+        move_watcher.update(&"testGameId".to_owned(), "e2e4");
+        let context = tracked_context(&Arc::default(), &move_watcher);
+
+        Mock::given(method("POST"))
+            .and(path("/bot/game/testGameId/move/e7e5"))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(expected_requests)
+            .mount(&server)
+            .await;
+
+        (context, client, server, move_watcher)
+    }
+
+    #[rstest]
+    #[case::accepted(200, 1, MoveOutcome::Applied)]
+    #[case::refused(400, 1, MoveOutcome::Rejected)]
+    fn submit_move_without_registered_move(#[case] status: u16, #[case] expected_requests: u64,
+            #[case] expected_outcome: MoveOutcome) {
+        tokio_test::block_on(async {
+            let (context, client, _server, _) =
+                setup_move_submission_test(status, expected_requests).await;
+
+            let result = context
+                .submit_move(&client, "e7e5".to_owned(), false, Duration::from_millis(20)).await;
+
+            assert_that!(result).contains_value(expected_outcome);
+        })
+    }
+
+    #[rstest]
+    #[case::submitted_move("e7e5", MoveOutcome::AlreadyApplied)]
+    #[case::other_move("c7c5", MoveOutcome::Rejected)]
+    fn submit_move_cross_checks_game_state_after_failure(#[case] registered_move: &'static str,
+            #[case] expected_outcome: MoveOutcome) {
+        tokio_test::block_on(async {
+            let (context, client, _server, move_watcher) =
+                setup_move_submission_test(500, 1).await;
+
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                move_watcher.update(&"testGameId".to_owned(), &format!("e2e4 {registered_move}"));
+            });
+
+            let result = context
+                .submit_move(&client, "e7e5".to_owned(), false, Duration::from_secs(1)).await;
+
+            assert_that!(result).contains_value(expected_outcome);
+        })
+    }
+
+    #[test]
+    fn submit_move_resubmits_once_after_server_error() {
+        tokio_test::block_on(async {
+            let (context, client, _server, _) = setup_move_submission_test(500, 2).await;
+
+            let result = context
+                .submit_move(&client, "e7e5".to_owned(), false, Duration::from_millis(20)).await;
+
+            assert!(matches!(result, Err(LibotRequestError::ApiError { status, .. })
+                if status == StatusCode::INTERNAL_SERVER_ERROR));
+        })
+    }
+
+    #[test]
+    fn submit_move_fails_without_move_watcher() {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let context = test_context(Some(Color::Black), "startpos");
+
+            let result = context
+                .submit_move(&client, "e7e5".to_owned(), false, Duration::from_secs(1)).await;
+
+            assert!(matches!(result, Err(LibotRequestError::MoveConfirmationUnavailable(_))));
+        })
+    }
+
+    #[test]
+    fn pending_game_request_is_cancelled_when_game_finishes() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let game_cancellation = Arc::new(GameCancellation::default());
+            let context = GameContext {
+                game_cancellation: Some(ByAddress(Arc::clone(&game_cancellation))),
+                ..test_context(Some(Color::White), "startpos")
+            };
+            let game_id = "testGameId".to_owned();
+
+            Mock::given(method("POST"))
+                .and(path("/round/testGameId/add-time/240"))
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+                .mount(&server)
+                .await;
+
+            game_cancellation.game_started(&game_id);
+
+            let request = context.run_for_game(client.add_time(game_id.clone(), 240));
+            let finish = async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                game_cancellation.game_finished(&game_id);
+            };
+            let (result, _) = tokio::join!(request, finish);
+
+            assert!(matches!(result, Err(LibotRequestError::GameEnded(id)) if id == game_id));
+        });
+    }
+
+    #[test]
+    fn game_request_after_game_finished_is_sent() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let game_cancellation = Arc::new(GameCancellation::default());
+            let context = GameContext {
+                game_cancellation: Some(ByAddress(Arc::clone(&game_cancellation))),
+                ..test_context(Some(Color::White), "startpos")
+            };
+            let game_id = "testGameId".to_owned();
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/chat"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            game_cancellation.game_started(&game_id);
+            game_cancellation.game_finished(&game_id);
+
+            let result = context
+                .run_for_game(client.send_chat_message(game_id, ChatRoom::Player, "gg")).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+
+    #[test]
+    fn sent_chat_message_is_read_from_session_log() {
+        let session_log = Arc::new(SessionLog::default());
+        let context = GameContext {
+            session_log: Some(ByAddress(Arc::clone(&session_log))),
+            ..test_context(Some(Color::White), "startpos")
+        };
+
+        assert_that!(context.has_sent_chat_message()).is_false();
+
+        session_log.chat_sent(&"otherGameId".to_owned());
+
+        assert_that!(context.has_sent_chat_message()).is_false();
+
+        session_log.chat_sent(&"testGameId".to_owned());
+
+        assert_that!(context.has_sent_chat_message()).is_true();
+    }
+}
@@ -0,0 +1,159 @@
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::connection::EndpointCategory;
+
+/// Configures how many requests a [BotClient](crate::client::BotClient) sends concurrently and how
+/// many of them are reserved for game actions, such as moves, so that they are not delayed by a
+/// burst of other requests, e.g. chat messages or profile queries, when many games are active.
+/// Streams are long-lived and therefore not limited. Set using
+/// [BotClientBuilder::with_dispatch](crate::client::BotClientBuilder::with_dispatch).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DispatchConfig {
+    max_concurrent_requests: usize,
+    reserved_for_game_actions: usize
+}
+
+impl DispatchConfig {
+
+    /// Creates a new config with default values, i.e. at most 8 concurrent requests, 2 of which
+    /// are reserved for game actions.
+    pub fn new() -> DispatchConfig {
+        DispatchConfig {
+            max_concurrent_requests: 8,
+            reserved_for_game_actions: 2
+        }
+    }
+
+    /// Sets the maximum number of requests which are sent concurrently, including those reserved
+    /// for game actions. Further requests wait until an earlier one has received its response.
+    /// The config is returned for chaining.
+    pub fn with_max_concurrent_requests(mut self, max_concurrent_requests: usize)
+            -> DispatchConfig {
+        self.max_concurrent_requests = max_concurrent_requests;
+        self
+    }
+
+    /// Sets the number of concurrent requests which only game actions may use, i.e. requests in
+    /// the [EndpointCategory::GameAction] category. Game actions may use the other requests as
+    /// well. The number is capped at the maximum number of concurrent requests. The config is
+    /// returned for chaining.
+    pub fn with_reserved_for_game_actions(mut self, reserved_for_game_actions: usize)
+            -> DispatchConfig {
+        self.reserved_for_game_actions = reserved_for_game_actions;
+        self
+    }
+}
+
+impl Default for DispatchConfig {
+    fn default() -> DispatchConfig {
+        DispatchConfig::new()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Dispatcher {
+    shared: Semaphore,
+    reserved: Semaphore
+}
+
+impl Dispatcher {
+
+    pub(crate) fn new(config: DispatchConfig) -> Dispatcher {
+        let reserved = config.reserved_for_game_actions.min(config.max_concurrent_requests);
+
+        Dispatcher {
+            shared: Semaphore::new(config.max_concurrent_requests - reserved),
+            reserved: Semaphore::new(reserved)
+        }
+    }
+
+    /// Waits until a request of the given category may be sent. The request must hold the
+    /// returned permit, if any, until it has received its response. Streams are sent immediately
+    /// and do not receive a permit.
+    pub(crate) async fn acquire(&self, category: EndpointCategory) -> Option<SemaphorePermit<'_>> {
+        match category {
+            EndpointCategory::Stream => None,
+            EndpointCategory::GameAction => {
+                if let Ok(permit) = self.reserved.try_acquire() {
+                    return Some(permit);
+                }
+
+                tokio::select! {
+                    biased;
+                    permit = self.reserved.acquire() => permit.ok(),
+                    permit = self.shared.acquire() => permit.ok()
+                }
+            },
+            EndpointCategory::Other => self.shared.acquire().await.ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn dispatcher(max_concurrent_requests: usize, reserved_for_game_actions: usize) -> Dispatcher {
+        Dispatcher::new(DispatchConfig::new()
+            .with_max_concurrent_requests(max_concurrent_requests)
+            .with_reserved_for_game_actions(reserved_for_game_actions))
+    }
+
+    async fn is_blocked(dispatcher: &Dispatcher, category: EndpointCategory) -> bool {
+        tokio::time::timeout(Duration::from_millis(20), dispatcher.acquire(category)).await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn game_actions_use_reserved_permits_while_others_are_exhausted() {
+        let dispatcher = dispatcher(3, 1);
+        let _first = dispatcher.acquire(EndpointCategory::Other).await;
+        let _second = dispatcher.acquire(EndpointCategory::Other).await;
+
+        assert_that!(is_blocked(&dispatcher, EndpointCategory::Other).await).is_true();
+        assert_that!(is_blocked(&dispatcher, EndpointCategory::GameAction).await).is_false();
+    }
+
+    #[tokio::test]
+    async fn game_actions_use_shared_permits_if_reserved_are_exhausted() {
+        let dispatcher = dispatcher(2, 1);
+        let _first = dispatcher.acquire(EndpointCategory::GameAction).await;
+        let _second = dispatcher.acquire(EndpointCategory::GameAction).await;
+
+        assert_that!(is_blocked(&dispatcher, EndpointCategory::GameAction).await).is_true();
+        assert_that!(is_blocked(&dispatcher, EndpointCategory::Other).await).is_true();
+    }
+
+    #[tokio::test]
+    async fn released_permit_admits_waiting_request() {
+        let dispatcher = dispatcher(1, 0);
+        let permit = dispatcher.acquire(EndpointCategory::Other).await;
+
+        assert_that!(is_blocked(&dispatcher, EndpointCategory::Other).await).is_true();
+
+        drop(permit);
+
+        assert_that!(is_blocked(&dispatcher, EndpointCategory::Other).await).is_false();
+    }
+
+    #[tokio::test]
+    async fn streams_are_never_blocked() {
+        let dispatcher = dispatcher(1, 1);
+        let _permit = dispatcher.acquire(EndpointCategory::GameAction).await;
+
+        assert_that!(dispatcher.acquire(EndpointCategory::Stream).await).is_none();
+    }
+
+    #[test]
+    fn reserved_permits_are_capped_at_maximum() {
+        let dispatcher = dispatcher(2, 5);
+
+        assert_that!(dispatcher.shared.available_permits()).is_equal_to(0);
+        assert_that!(dispatcher.reserved.available_permits()).is_equal_to(2);
+    }
+}
@@ -0,0 +1,222 @@
+/// The z-score of the two-sided 95% confidence interval of an [EloEstimate].
+const Z_95: f64 = 1.959964;
+
+fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// The mean score per game and the variance of the score of a single game, given the numbers of
+/// wins, draws, and losses.
+fn score_statistics(wins: u32, draws: u32, losses: u32) -> Option<(f64, f64)> {
+    let games = (wins + draws + losses) as f64;
+
+    if games == 0.0 {
+        return None;
+    }
+
+    let score = (wins as f64 + draws as f64 * 0.5) / games;
+    let variance = (wins as f64 * (1.0 - score).powi(2)
+        + draws as f64 * (0.5 - score).powi(2)
+        + losses as f64 * score.powi(2)) / games;
+
+    Some((score, variance))
+}
+
+/// An estimate of the Elo difference between the bot and its opponent from the results of a
+/// number of games, with a 95% confidence interval. Positive values mean the bot is stronger. If
+/// the bot won or lost all games, the estimate is infinite.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EloEstimate {
+
+    /// The estimated Elo difference.
+    pub elo: f64,
+
+    /// The lower bound of the 95% confidence interval of the Elo difference.
+    pub lower: f64,
+
+    /// The upper bound of the 95% confidence interval of the Elo difference.
+    pub upper: f64
+}
+
+impl EloEstimate {
+
+    /// Estimates the Elo difference from the given numbers of games won, drawn, and lost by the
+    /// bot.
+    ///
+    /// # Returns
+    ///
+    /// The estimate, or [None] if no games were played.
+    pub fn from_results(wins: u32, draws: u32, losses: u32) -> Option<EloEstimate> {
+        let (score, variance) = score_statistics(wins, draws, losses)?;
+        let games = (wins + draws + losses) as f64;
+        let margin = Z_95 * (variance / games).sqrt();
+
+        Some(EloEstimate {
+            elo: score_to_elo(score),
+            lower: score_to_elo((score - margin).max(0.0)),
+            upper: score_to_elo((score + margin).min(1.0))
+        })
+    }
+
+    /// Half the width of the confidence interval, i.e. the error bar of the estimate.
+    pub fn error_margin(&self) -> f64 {
+        (self.upper - self.lower) / 2.0
+    }
+}
+
+/// One of the two hypotheses between which an [Sprt] decides.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SprtHypothesis {
+
+    /// The null hypothesis, i.e. the Elo difference is at most the lower bound of the test.
+    Null,
+
+    /// The alternative hypothesis, i.e. the Elo difference is at least the upper bound of the
+    /// test.
+    Alternative
+}
+
+/// A sequential probability ratio test which decides whether the bot is stronger than its
+/// opponent by at least a given Elo difference, e.g. to validate an engine change with as few
+/// games as possible. The log-likelihood ratio is approximated from the mean and variance of the
+/// game scores, which accounts for draws.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    elo0: f64,
+    elo1: f64,
+    lower_bound: f64,
+    upper_bound: f64
+}
+
+impl Sprt {
+
+    /// Creates a new test of the null hypothesis that the Elo difference is at most `elo0`
+    /// against the alternative that it is at least `elo1`, with false positive and false negative
+    /// rates of 5%.
+    pub fn new(elo0: f64, elo1: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            lower_bound: 0.0,
+            upper_bound: 0.0
+        }.with_error_rates(0.05, 0.05)
+    }
+
+    /// Sets the probability `alpha` of accepting the alternative hypothesis although the null
+    /// hypothesis holds and the probability `beta` of accepting the null hypothesis although the
+    /// alternative holds. Both must be in the open interval `(0, 1)`. The test is returned for
+    /// chaining.
+    pub fn with_error_rates(mut self, alpha: f64, beta: f64) -> Sprt {
+        self.lower_bound = (beta / (1.0 - alpha)).ln();
+        self.upper_bound = ((1.0 - beta) / alpha).ln();
+        self
+    }
+
+    /// Computes the log-likelihood ratio of the alternative against the null hypothesis given the
+    /// numbers of games won, drawn, and lost by the bot.
+    ///
+    /// # Returns
+    ///
+    /// The log-likelihood ratio, or [None] if it cannot be computed since no games were played
+    /// or all games had the same result.
+    pub fn llr(&self, wins: u32, draws: u32, losses: u32) -> Option<f64> {
+        let (score, variance) = score_statistics(wins, draws, losses)?;
+
+        if variance == 0.0 {
+            return None;
+        }
+
+        let games = (wins + draws + losses) as f64;
+        let score0 = elo_to_score(self.elo0);
+        let score1 = elo_to_score(self.elo1);
+
+        Some(games * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance))
+    }
+
+    /// Decides the test given the numbers of games won, drawn, and lost by the bot.
+    ///
+    /// # Returns
+    ///
+    /// The accepted hypothesis, or [None] if more games are needed.
+    pub fn decide(&self, wins: u32, draws: u32, losses: u32) -> Option<SprtHypothesis> {
+        let llr = self.llr(wins, draws, losses)?;
+
+        if llr >= self.upper_bound {
+            Some(SprtHypothesis::Alternative)
+        }
+        else if llr <= self.lower_bound {
+            Some(SprtHypothesis::Null)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn even_score_is_estimated_as_zero_elo() {
+        let estimate = EloEstimate::from_results(30, 40, 30).unwrap();
+
+        assert_that!(estimate.elo).is_close_to(0.0, 1e-9);
+        assert_that!(estimate.lower).is_less_than(0.0);
+        assert_that!(estimate.upper).is_greater_than(0.0);
+        assert_that!(estimate.error_margin()).is_close_to(estimate.upper, 1e-9);
+    }
+
+    #[test]
+    fn higher_score_is_estimated_as_positive_elo() {
+        // 75% score corresponds to about 191 Elo.
+        let estimate = EloEstimate::from_results(60, 30, 10).unwrap();
+
+        assert_that!(estimate.elo).is_close_to(190.85, 0.01);
+        assert_that!(estimate.lower).is_less_than(estimate.elo);
+        assert_that!(estimate.upper).is_greater_than(estimate.elo);
+    }
+
+    #[test]
+    fn error_margin_shrinks_with_more_games() {
+        let few_games = EloEstimate::from_results(6, 3, 1).unwrap();
+        let many_games = EloEstimate::from_results(600, 300, 100).unwrap();
+
+        assert_that!(many_games.error_margin()).is_less_than(few_games.error_margin());
+    }
+
+    #[test]
+    fn no_games_have_no_estimate() {
+        assert_that!(EloEstimate::from_results(0, 0, 0)).is_none();
+    }
+
+    #[rstest]
+    #[case::strong(700, 200, 100, Some(SprtHypothesis::Alternative))]
+    #[case::weak(100, 200, 700, Some(SprtHypothesis::Null))]
+    #[case::undecided(3, 4, 3, None)]
+    #[case::identical_results(0, 10, 0, None)]
+    fn sprt_decides_between_hypotheses(#[case] wins: u32, #[case] draws: u32,
+            #[case] losses: u32, #[case] expected: Option<SprtHypothesis>) {
+        let sprt = Sprt::new(0.0, 10.0);
+
+        assert_that!(sprt.decide(wins, draws, losses)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn stricter_error_rates_need_more_evidence() {
+        let lenient = Sprt::new(0.0, 50.0).with_error_rates(0.2, 0.2);
+        let strict = Sprt::new(0.0, 50.0).with_error_rates(0.001, 0.001);
+
+        assert_that!(lenient.decide(22, 10, 8)).contains(SprtHypothesis::Alternative);
+        assert_that!(strict.decide(22, 10, 8)).is_none();
+    }
+}
@@ -1,3 +1,9 @@
+use std::env::VarError;
+use std::error::Error as StdError;
+use std::io::Error as IoError;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use reqwest::{Error as ReqwestError, StatusCode, Url};
 use reqwest::header::InvalidHeaderValue;
 
@@ -5,7 +11,10 @@ use serde_json::Error as JsonError;
 
 use thiserror::Error;
 
+use crate::chess::ChessError;
 use crate::client::BotClient;
+use crate::model::{Days, Move, Seconds};
+use crate::model::game::{GameId, Variant};
 
 #[derive(Debug, Error)]
 pub enum LibotRequestError {
@@ -13,6 +22,11 @@ pub enum LibotRequestError {
     #[error("networking error: {0}")]
     ReqwestError(#[from] ReqwestError),
 
+    /// An error of a custom [HttpTransport](crate::transport::HttpTransport) which does not send
+    /// requests with `reqwest`.
+    #[error("transport error: {0}")]
+    TransportError(Box<dyn StdError + Send + Sync>),
+
     #[error("error serializing JSON body or deserializing JSON response: {0}")]
     JsonError(#[from] JsonError),
 
@@ -21,11 +35,69 @@ pub enum LibotRequestError {
         status: StatusCode,
         body: Option<String>,
         url: Url
-    }
+    },
+
+    #[error("move rejected before submitting: {0}")]
+    InvalidMove(#[from] ChessError),
+
+    #[error("chat message rejected by throttle, retry after {retry_after:?}")]
+    ChatThrottled {
+        retry_after: Duration
+    },
+
+    #[error("no chat message with key {key:?} in the catalogs of the chat localizer")]
+    MissingChatMessage {
+        key: String
+    },
+
+    #[error("move {mov} in game {game_id} was not confirmed within {timeout:?}")]
+    MoveNotConfirmed {
+        game_id: GameId,
+        mov: Move,
+        timeout: Duration
+    },
+
+    #[error("moves of game {0} are not tracked, so moves cannot be confirmed")]
+    MoveConfirmationUnavailable(GameId),
+
+    /// The request concerned a game which ended before the request completed, so it was
+    /// cancelled. The request may still have reached Lichess.
+    #[error("request concerning game {0} was cancelled because the game ended")]
+    GameEnded(GameId),
+
+    #[error("error accepting challenge: {0}")]
+    ChallengeAcceptError(#[from] ChallengeAcceptError),
+
+    #[error("stream of challenge {challenge_id:?} ended before the challenge was answered")]
+    ChallengeStreamEnded {
+        challenge_id: Option<GameId>
+    },
+
+    /// An error reading local tablebase files, e.g. because a table file is corrupted.
+    #[error("error probing tablebase: {0}")]
+    TablebaseError(Box<dyn StdError + Send + Sync>)
 }
 
 pub type LibotResult<T> = Result<T, LibotRequestError>;
 
+/// An enumeration of the known reasons why Lichess refuses to accept a challenge, as returned by
+/// [BotClient::accept_challenge]. In each case, the bot can move on to the next challenge.
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ChallengeAcceptError {
+
+    /// The challenge does not exist (anymore), e.g. because it was cancelled shortly before.
+    #[error("challenge not found")]
+    NotFound,
+
+    /// The challenge was already accepted.
+    #[error("challenge was already accepted")]
+    AlreadyAccepted,
+
+    /// Too many requests were sent to Lichess in a short period of time.
+    #[error("too many requests")]
+    RateLimited
+}
+
 #[derive(Debug, Error)]
 pub enum BotClientBuilderError {
     #[error("no token specified")]
@@ -35,7 +107,55 @@ pub enum BotClientBuilderError {
     InvalidToken(#[from] InvalidHeaderValue),
 
     #[error("error initializing client: {0}")]
-    ClientError(#[from] ReqwestError)
+    ClientError(#[from] ReqwestError),
+
+    #[error("error reading token from environment variable {name}: {source}")]
+    TokenEnvironmentVariable {
+        name: String,
+        source: VarError
+    },
+
+    #[error("error reading token from file {path:?}: {source}")]
+    TokenFile {
+        path: PathBuf,
+        source: IoError
+    }
 }
 
 pub type BotClientBuilderResult = Result<BotClient, BotClientBuilderError>;
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ChallengeValidationError {
+
+    #[error("a challenge cannot have both a clock and days per turn")]
+    ClockAndDays,
+
+    #[error("clock limit must be between 0 and 10800 seconds, but was {0}")]
+    InvalidClockLimit(Seconds),
+
+    #[error("clock increment must be between 0 and 60 seconds, but was {0}")]
+    InvalidClockIncrement(Seconds),
+
+    #[error("clock limit and increment cannot both be 0")]
+    ZeroClock,
+
+    #[error("days per turn must be one of 1, 2, 3, 5, 7, 10, or 14, but was {0}")]
+    InvalidDays(Days),
+
+    #[error("bots cannot play ultrabullet, but the clock was {limit}+{increment}")]
+    UltraBullet {
+        limit: Seconds,
+        increment: Seconds
+    },
+
+    #[error("games from a custom initial position cannot be rated")]
+    RatedFromPosition,
+
+    #[error("variant {0:?} does not support a custom initial position")]
+    UnsupportedFen(Variant),
+
+    #[error("variant FromPosition requires an initial FEN")]
+    MissingFen
+}
+
+pub type ChallengeValidationResult<T> = Result<T, ChallengeValidationError>;
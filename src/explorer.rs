@@ -0,0 +1,387 @@
+use std::sync::Arc;
+
+use reqwest::Client;
+
+use serde::{Deserialize, Serialize};
+
+use crate::book::BookEntry;
+use crate::cache::ResponseCache;
+use crate::chess::position::Position;
+use crate::chess::uci::UciMove;
+use crate::client::{handle_error, join_url};
+use crate::error::LibotResult;
+use crate::model::game::Color;
+
+/// The base URL of the public Lichess opening explorer.
+pub const DEFAULT_EXPLORER_URL: &str = "https://explorer.lichess.ovh";
+
+/// The database of games queried by an [OnlineExplorer].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ExplorerDatabase {
+
+    /// Over-the-board games of masters.
+    Masters,
+
+    /// Rated games played on Lichess.
+    #[default]
+    Lichess
+}
+
+impl ExplorerDatabase {
+
+    fn path(self) -> &'static str {
+        match self {
+            ExplorerDatabase::Masters => "/masters",
+            ExplorerDatabase::Lichess => "/lichess"
+        }
+    }
+}
+
+/// The opening to which a position queried in an [OpeningExplorer] belongs.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ExplorerOpening {
+
+    /// The ECO code of the opening, e.g. `"B20"`.
+    pub eco: String,
+
+    /// The name of the opening, e.g. `"Sicilian Defense"`.
+    pub name: String
+}
+
+/// A move played from a position queried in an [OpeningExplorer], together with the results of
+/// the games in which it was played.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplorerMove {
+
+    /// The move in UCI notation.
+    pub uci: UciMove,
+
+    /// The move in standard algebraic notation.
+    pub san: String,
+
+    /// The number of games won by white after this move.
+    pub white: u64,
+
+    /// The number of games drawn after this move.
+    pub draws: u64,
+
+    /// The number of games won by black after this move.
+    pub black: u64,
+
+    /// The average rating of the players who played this move, if known.
+    #[serde(default)]
+    pub average_rating: Option<u32>
+}
+
+impl ExplorerMove {
+
+    /// The total number of games in which this move was played.
+    pub fn games(&self) -> u64 {
+        self.white + self.draws + self.black
+    }
+}
+
+/// The response of an [OpeningExplorer] for a position.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ExplorerResult {
+
+    /// The number of games reaching the position which white won.
+    pub white: u64,
+
+    /// The number of games reaching the position which were drawn.
+    pub draws: u64,
+
+    /// The number of games reaching the position which black won.
+    pub black: u64,
+
+    /// The moves played from the position, most popular first.
+    #[serde(default)]
+    pub moves: Vec<ExplorerMove>,
+
+    /// The opening to which the position belongs, if it is known.
+    #[serde(default)]
+    pub opening: Option<ExplorerOpening>
+}
+
+impl ExplorerResult {
+
+    /// Converts the moves of this result into [BookEntry]s weighted by the number of games, e.g.
+    /// to select one of them with the same strategies as an
+    /// [OpeningBook](crate::book::OpeningBook).
+    ///
+    /// # Arguments
+    ///
+    /// * `side_to_move`: The side to move in the queried position, from whose perspective wins
+    ///   and losses are counted.
+    pub fn book_entries(&self, side_to_move: Color) -> Vec<BookEntry> {
+        let saturate = |count: u64| count.min(u32::MAX as u64) as u32;
+
+        self.moves.iter()
+            .map(|mov| {
+                let (wins, losses) = match side_to_move {
+                    Color::White => (mov.white, mov.black),
+                    Color::Black => (mov.black, mov.white)
+                };
+
+                BookEntry {
+                    mov: mov.uci,
+                    weight: saturate(mov.games()),
+                    wins: saturate(wins),
+                    draws: saturate(mov.draws),
+                    losses: saturate(losses)
+                }
+            })
+            .collect()
+    }
+}
+
+/// A source of statistics on the moves played in given positions, such as the Lichess opening
+/// explorer.
+#[async_trait::async_trait]
+pub trait OpeningExplorer : Send + Sync {
+
+    /// Queries the given position.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: The position to query.
+    ///
+    /// # Returns
+    ///
+    /// The [ExplorerResult] for the given position. Its list of moves is empty if the position
+    /// does not occur in the database.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError](crate::error::LibotRequestError) if querying the explorer fails.
+    async fn query(&self, position: &Position) -> LibotResult<ExplorerResult>;
+}
+
+/// An [OpeningExplorer] which queries the Lichess opening explorer.
+#[derive(Clone, Debug)]
+pub struct OnlineExplorer {
+    client: Client,
+    base_url: Arc<str>,
+    database: ExplorerDatabase
+}
+
+impl OnlineExplorer {
+
+    /// Creates a new online explorer client for the given database using the
+    /// [DEFAULT_EXPLORER_URL].
+    pub fn new(database: ExplorerDatabase) -> OnlineExplorer {
+        OnlineExplorer {
+            client: Client::new(),
+            base_url: DEFAULT_EXPLORER_URL.into(),
+            database
+        }
+    }
+
+    /// Overrides the base URL of the explorer server to query. The client is returned for
+    /// chaining.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> OnlineExplorer {
+        self.base_url = base_url.into().into();
+        self
+    }
+
+    /// The database of games this client queries.
+    pub fn database(&self) -> ExplorerDatabase {
+        self.database
+    }
+}
+
+impl Default for OnlineExplorer {
+    fn default() -> OnlineExplorer {
+        OnlineExplorer::new(ExplorerDatabase::default())
+    }
+}
+
+#[async_trait::async_trait]
+impl OpeningExplorer for OnlineExplorer {
+    async fn query(&self, position: &Position) -> LibotResult<ExplorerResult> {
+        #[derive(Serialize)]
+        struct Query {
+            fen: String
+        }
+
+        let url = join_url(&self.base_url, self.database.path());
+        let query = Query {
+            fen: position.to_fen()
+        };
+        let response = handle_error(self.client.get(url).query(&query).send().await).await?;
+
+        Ok(response.json().await?)
+    }
+}
+
+/// An [OpeningExplorer] which caches the results of another explorer, such as the rate-limited
+/// [OnlineExplorer], in a [ResponseCache]. Failed queries are not cached. Keep another reference
+/// to the cache to read its [CacheStats](crate::cache::CacheStats) or save it. Use a separate
+/// cache per database, since positions are the only cache key.
+pub struct CachedExplorer<E> {
+    explorer: E,
+    cache: Arc<ResponseCache<ExplorerResult>>
+}
+
+impl<E: OpeningExplorer> CachedExplorer<E> {
+
+    /// Creates a new cached explorer.
+    ///
+    /// # Arguments
+    ///
+    /// * `explorer`: The explorer to query for positions which are not cached.
+    /// * `cache`: The cache in which to store the results.
+    pub fn new(explorer: E, cache: Arc<ResponseCache<ExplorerResult>>) -> CachedExplorer<E> {
+        CachedExplorer {
+            explorer,
+            cache
+        }
+    }
+
+    /// The cache in which the results are stored.
+    pub fn cache(&self) -> &Arc<ResponseCache<ExplorerResult>> {
+        &self.cache
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: OpeningExplorer> OpeningExplorer for CachedExplorer<E> {
+    async fn query(&self, position: &Position) -> LibotResult<ExplorerResult> {
+        self.cache.get_or_fetch(&position.to_fen(), self.explorer.query(position)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use serde_json::json;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{method, path, query_param};
+
+    use crate::chess::position::STANDARD_FEN;
+
+    use super::*;
+
+    fn explorer_response() -> serde_json::Value {
+        json!({
+            "white": 120,
+            "draws": 60,
+            "black": 70,
+            "moves": [
+                {
+                    "uci": "e2e4",
+                    "san": "e4",
+                    "averageRating": 2400,
+                    "white": 80,
+                    "draws": 40,
+                    "black": 30,
+                    "game": null
+                },
+                {
+                    "uci": "d2d4",
+                    "san": "d4",
+                    "averageRating": 2410,
+                    "white": 40,
+                    "draws": 20,
+                    "black": 40,
+                    "game": null
+                }
+            ],
+            "topGames": [],
+            "opening": null
+        })
+    }
+
+    async fn mount_explorer(server: &MockServer, database: &str, expected_requests: u64) {
+        Mock::given(method("GET"))
+            .and(path(format!("/{database}")))
+            .and(query_param("fen", STANDARD_FEN))
+            .respond_with(ResponseTemplate::new(200).set_body_json(explorer_response()))
+            .expect(expected_requests)
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn query_parses_response() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let explorer = OnlineExplorer::new(ExplorerDatabase::Masters)
+                .with_base_url(server.uri());
+
+            mount_explorer(&server, "masters", 1).await;
+
+            let result = explorer.query(&Position::standard()).await.unwrap();
+
+            assert_that!(result.white).is_equal_to(120);
+            assert_that!(result.moves.len()).is_equal_to(2);
+            assert_that!(result.moves[0].uci).is_equal_to("e2e4".parse::<UciMove>().unwrap());
+            assert_that!(result.moves[0].average_rating).contains(2400);
+            assert_that!(result.moves[0].games()).is_equal_to(150);
+        });
+    }
+
+    #[test]
+    fn query_fails_on_server_error() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let explorer = OnlineExplorer::default().with_base_url(server.uri());
+
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(429))
+                .mount(&server)
+                .await;
+
+            assert_that!(explorer.query(&Position::standard()).await).is_err();
+        });
+    }
+
+    #[test]
+    fn cached_explorer_queries_each_position_once() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+            let cache = Arc::new(ResponseCache::new(Duration::from_secs(60)));
+            let explorer = CachedExplorer::new(
+                OnlineExplorer::default().with_base_url(server.uri()), Arc::clone(&cache));
+
+            mount_explorer(&server, "lichess", 1).await;
+
+            let first = explorer.query(&Position::standard()).await.unwrap();
+            let second = explorer.query(&Position::standard()).await.unwrap();
+
+            assert_that!(first).is_equal_to(second);
+            assert_that!(cache.stats().hits).is_equal_to(1);
+            assert_that!(cache.stats().misses).is_equal_to(1);
+        });
+    }
+
+    #[test]
+    fn book_entries_count_results_from_perspective_of_side_to_move() {
+        let result: ExplorerResult = serde_json::from_value(explorer_response()).unwrap();
+
+        let entries = result.book_entries(Color::Black);
+
+        assert_that!(entries).contains_exactly_in_given_order([
+            BookEntry {
+                mov: "e2e4".parse().unwrap(),
+                weight: 150,
+                wins: 30,
+                draws: 40,
+                losses: 80
+            },
+            BookEntry {
+                mov: "d2d4".parse().unwrap(),
+                weight: 100,
+                wins: 40,
+                draws: 20,
+                losses: 40
+            }
+        ]);
+    }
+}
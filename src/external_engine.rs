@@ -0,0 +1,313 @@
+use std::io;
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+
+use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+use ndjson_stream::fallible::FallibleNdjsonError;
+
+use reqwest::{Body, Client, StatusCode};
+
+use crate::client::{handle_error, join_url};
+use crate::error::{LibotRequestError, LibotResult};
+use crate::model::external_engine::{
+    AcquireWorkRequest,
+    AnalyseRequest,
+    AnalysisUpdate,
+    EngineWorkRequest,
+    ExternalEngineWork
+};
+
+/// The base URL of the Lichess server which relays analysis between clients and external engine
+/// providers.
+pub const DEFAULT_ENGINE_URL: &str = "https://engine.lichess.ovh/api";
+
+fn to_libot_error(error: FallibleNdjsonError<reqwest::Error>) -> LibotRequestError {
+    match error {
+        FallibleNdjsonError::InputError(error) => LibotRequestError::ReqwestError(error),
+        FallibleNdjsonError::JsonError(error) => LibotRequestError::JsonError(error)
+    }
+}
+
+/// A client for the analysis endpoints of the Lichess external engine API. Engines are registered
+/// and managed with the [BotClient](crate::client::BotClient), e.g. using
+/// [BotClient::create_external_engine](crate::client::BotClient::create_external_engine). This
+/// client can then be used to request analysis from a registered engine, and by the engine
+/// provider to acquire analysis requests and submit the engine output. Neither requires an OAuth
+/// token, since requests are authenticated with the engine's client or provider secret.
+#[derive(Clone, Debug)]
+pub struct ExternalEngineClient {
+    client: Client,
+    base_url: Arc<str>
+}
+
+impl ExternalEngineClient {
+
+    /// Creates a new external engine client using the [DEFAULT_ENGINE_URL].
+    pub fn new() -> ExternalEngineClient {
+        ExternalEngineClient {
+            client: Client::new(),
+            base_url: DEFAULT_ENGINE_URL.into()
+        }
+    }
+
+    /// Overrides the base URL of the engine server. The client is returned for chaining.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> ExternalEngineClient {
+        self.base_url = base_url.into().into();
+        self
+    }
+
+    /// Requests analysis of a position from an external engine. The engine's output is streamed
+    /// as a sequence of [AnalysisUpdate]s, which ends when the analysis is finished or the engine
+    /// provider stops. Dropping the stream stops the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine_id`: The ID of the external engine to use.
+    /// * `client_secret`: The client secret of the engine, as provided in the `client_secret` of
+    ///   its [ExternalEngine](crate::model::external_engine::ExternalEngine).
+    /// * `work`: The position to analyse and the resources to use.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError] that occurs while sending the request. Errors while reading the
+    /// stream are reported as items of the stream.
+    pub async fn analyse(&self, engine_id: &str, client_secret: &str, work: &ExternalEngineWork)
+            -> LibotResult<impl Stream<Item = LibotResult<AnalysisUpdate>>> {
+        let url = join_url(&self.base_url, &format!("/external-engine/{engine_id}/analyse"));
+        let body = AnalyseRequest {
+            client_secret,
+            work
+        };
+        let response = handle_error(self.client.post(url).json(&body).send().await).await?;
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+        let stream = ndjson_stream::from_fallible_stream_with_config::<AnalysisUpdate, _>(
+            response.bytes_stream(), config);
+
+        Ok(stream.map(|update| update.map_err(to_libot_error)))
+    }
+
+    /// Waits for the next analysis request for any engine registered with the given provider
+    /// secret. Lichess holds the request open for a while if no work is available.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider_secret`: The provider secret with which the engines were registered.
+    ///
+    /// # Returns
+    ///
+    /// The acquired [EngineWorkRequest], or [None] if no work became available before the
+    /// request timed out, in which case the provider should simply try again.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError] that occurs while sending the request or parsing the response.
+    pub async fn acquire_work(&self, provider_secret: &str)
+            -> LibotResult<Option<EngineWorkRequest>> {
+        let url = join_url(&self.base_url, "/external-engine/work");
+        let body = AcquireWorkRequest {
+            provider_secret
+        };
+        let response = handle_error(self.client.post(url).json(&body).send().await).await?;
+
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+
+        Ok(Some(response.json().await?))
+    }
+
+    /// Submits the output of the engine for an acquired [EngineWorkRequest]. The output is
+    /// streamed to Lichess line by line as it is produced, so the stream should yield the raw UCI
+    /// output lines of the engine, such as `info` and `bestmove` lines, and end once the analysis
+    /// is finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_id`: The [EngineWorkRequest::id] of the acquired work.
+    /// * `uci_output`: The output lines of the engine, without line terminators.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError] that occurs while sending the output.
+    pub async fn submit_work(&self, work_id: &str,
+            uci_output: impl Stream<Item = String> + Send + Sync + 'static) -> LibotResult<()> {
+        let url = join_url(&self.base_url, &format!("/external-engine/work/{work_id}"));
+        let body = Body::wrap_stream(uci_output.map(|line| Ok::<_, io::Error>(line + "\n")));
+
+        handle_error(self.client.post(url).body(body).send().await).await?;
+
+        Ok(())
+    }
+}
+
+impl Default for ExternalEngineClient {
+    fn default() -> ExternalEngineClient {
+        ExternalEngineClient::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use futures::stream;
+
+    use kernal::prelude::*;
+
+    use serde_json::json;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{body_json, body_string, method, path};
+
+    use crate::model::external_engine::UciVariant;
+
+    use super::*;
+
+    fn test_work() -> ExternalEngineWork {
+        ExternalEngineWork {
+            session_id: "testSessionId".to_owned(),
+            threads: 2,
+            hash: 256,
+            infinite: false,
+            multi_pv: 1,
+            variant: UciVariant::Chess,
+            initial_fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_owned(),
+            moves: vec!["e2e4".to_owned()]
+        }
+    }
+
+    async fn setup() -> (ExternalEngineClient, MockServer) {
+        let server = MockServer::start().await;
+        let client = ExternalEngineClient::new().with_base_url(server.uri());
+
+        (client, server)
+    }
+
+    #[test]
+    fn analyse_streams_updates() {
+        tokio_test::block_on(async {
+            let (client, server) = setup().await;
+            let body = concat!(
+                r#"{"time":100,"depth":1,"nodes":20,"pvs":[{"depth":1,"cp":30,"moves":["e7e5"]}]}"#,
+                "\n\n",
+                r#"{"time":200,"depth":2,"nodes":80,"pvs":[{"depth":2,"cp":25,"moves":["c7c5"]}]}"#,
+                "\n");
+
+            Mock::given(method("POST"))
+                .and(path("/external-engine/testEngineId/analyse"))
+                .and(body_json(json!({
+                    "clientSecret": "testClientSecret",
+                    "work": {
+                        "sessionId": "testSessionId",
+                        "threads": 2,
+                        "hash": 256,
+                        "infinite": false,
+                        "multiPv": 1,
+                        "variant": "chess",
+                        "initialFen": test_work().initial_fen,
+                        "moves": ["e2e4"]
+                    }
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let updates = client.analyse("testEngineId", "testClientSecret", &test_work())
+                .await
+                .unwrap()
+                .collect::<Vec<_>>()
+                .await;
+            let depths = updates.into_iter()
+                .map(|update| update.unwrap().depth)
+                .collect::<Vec<_>>();
+
+            assert_that!(depths).contains_exactly_in_given_order([1, 2]);
+        });
+    }
+
+    #[test]
+    fn acquire_work_returns_none_without_content() {
+        tokio_test::block_on(async {
+            let (client, server) = setup().await;
+
+            Mock::given(method("POST"))
+                .and(path("/external-engine/work"))
+                .and(body_json(json!({ "providerSecret": "testProviderSecret" })))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let work = client.acquire_work("testProviderSecret").await;
+
+            assert_that!(work).contains_value(None);
+        });
+    }
+
+    #[test]
+    fn acquire_work_parses_work_request() {
+        tokio_test::block_on(async {
+            let (client, server) = setup().await;
+            let response = json!({
+                "id": "testWorkId",
+                "work": {
+                    "sessionId": "testSessionId",
+                    "threads": 2,
+                    "hash": 256,
+                    "multiPv": 1,
+                    "variant": "chess",
+                    "initialFen": test_work().initial_fen,
+                    "moves": ["e2e4"]
+                },
+                "engine": {
+                    "id": "testEngineId",
+                    "name": "Engine",
+                    "clientSecret": "testClientSecret",
+                    "userId": "testUserId",
+                    "maxThreads": 8,
+                    "maxHash": 1024,
+                    "variants": ["chess"],
+                    "providerData": "testProviderData"
+                }
+            });
+
+            Mock::given(method("POST"))
+                .and(path("/external-engine/work"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(response))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let work = client.acquire_work("testProviderSecret").await.unwrap().unwrap();
+
+            assert_that!(work.id.as_str()).is_equal_to("testWorkId");
+            assert_that!(work.work).is_equal_to(test_work());
+            assert_that!(work.engine.provider_data).contains("testProviderData".to_owned());
+        });
+    }
+
+    #[test]
+    fn submit_work_sends_output_lines() {
+        tokio_test::block_on(async {
+            let (client, server) = setup().await;
+
+            Mock::given(method("POST"))
+                .and(path("/external-engine/work/testWorkId"))
+                .and(body_string("info depth 1 score cp 30 pv e7e5\nbestmove e7e5\n"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let output = stream::iter([
+                "info depth 1 score cp 30 pv e7e5".to_owned(),
+                "bestmove e7e5".to_owned()
+            ]);
+            let result = client.submit_work("testWorkId", output).await;
+
+            assert_that!(result).is_ok();
+        });
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use tokio::sync::watch;
+
+use crate::Bot;
+use crate::client::BotClient;
+use crate::context::{BotContext, GameContext};
+use crate::error::LibotRequestError;
+use crate::model::Move;
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::GameId;
+use crate::model::game::event::GameStateEvent;
+use crate::model::game::result::GameResult;
+
+/// A source of moves entered outside of libot, such as a physical (e.g. DGT) board or a GUI,
+/// which lets a human play through the bot account. Wrap it in a [MoveSourceBot] to feed its
+/// moves into games run by a [BotRunner](crate::runner::BotRunner).
+#[async_trait::async_trait]
+pub trait MoveSource : Send + Sync {
+
+    /// Called whenever the moves of a game change, i.e. when the game starts, after every move of
+    /// either player, and after takebacks. Front-ends should update the displayed position, in
+    /// particular to show incoming opponent moves, which the user may have to replicate on a
+    /// physical board before entering their own move.
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The context of the game.
+    /// * `moves`: All moves of the game so far in UCI notation.
+    async fn sync(&self, _context: &GameContext, _moves: &[Move]) { }
+
+    /// Waits until the user entered the next move of the bot in the given game. If the turn ends
+    /// before, e.g. because the game ended or a takeback was accepted, the returned future is
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The context of the game.
+    /// * `moves`: All moves of the game so far in UCI notation.
+    ///
+    /// # Returns
+    ///
+    /// The move to submit in UCI notation, or [None] to not move in this turn.
+    async fn next_move(&self, context: &GameContext, moves: &[Move]) -> Option<Move>;
+
+    /// Called when a move entered by the user was rejected, e.g. because it is illegal. Afterwards,
+    /// [MoveSource::next_move] is called again for the same turn.
+    async fn move_rejected(&self, _context: &GameContext, _mov: &Move,
+        _error: LibotRequestError) { }
+}
+
+/// A [Bot] which plays the moves of a [MoveSource] and keeps it synchronized with the moves of
+/// the game. Combine it with other bots using the [combinators](crate::combinator), e.g. to
+/// handle challenges.
+pub struct MoveSourceBot<S> {
+    source: S,
+    turns: Mutex<HashMap<GameId, watch::Sender<Option<usize>>>>,
+    awaiting_input: Mutex<HashSet<GameId>>
+}
+
+impl<S: MoveSource> MoveSourceBot<S> {
+
+    /// Creates a new bot which plays the moves of the given source.
+    pub fn new(source: S) -> MoveSourceBot<S> {
+        MoveSourceBot {
+            source,
+            turns: Mutex::new(HashMap::new()),
+            awaiting_input: Mutex::new(HashSet::new())
+        }
+    }
+
+    /// The source of moves played by this bot.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Records the number of plies of the game with the given ID, or [None] if it is over.
+    ///
+    /// # Returns
+    ///
+    /// `true` if and only if the plies changed.
+    fn update_turn(&self, game_id: &GameId, plies: Option<usize>) -> bool {
+        let mut turns = self.turns.lock().unwrap();
+
+        match turns.get(game_id) {
+            Some(turn) => turn.send_replace(plies) != plies,
+            None => {
+                turns.insert(game_id.clone(), watch::channel(plies).0);
+                true
+            }
+        }
+    }
+
+    fn subscribe(&self, game_id: &GameId, plies: usize) -> watch::Receiver<Option<usize>> {
+        self.turns.lock().unwrap().entry(game_id.clone())
+            .or_insert_with(|| watch::channel(Some(plies)).0)
+            .subscribe()
+    }
+
+    async fn play_turn(&self, context: &GameContext, moves: &[Move], client: &BotClient) {
+        let plies = moves.len();
+        let mut turn = self.subscribe(&context.id, plies);
+
+        loop {
+            let mov = tokio::select! {
+                mov = self.source.next_move(context, moves) => mov,
+                _ = turn.wait_for(|current| *current != Some(plies)) => return
+            };
+            let Some(mov) = mov
+            else {
+                return;
+            };
+
+            match context.make_move(client, mov.clone(), false).await {
+                Ok(()) => return,
+                Err(error) => self.source.move_rejected(context, &mov, error).await
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: MoveSource> Bot for MoveSourceBot<S> {
+
+    async fn on_game_finish(&self, _context: &BotContext, game: GameStartFinish,
+            _result: GameResult, _client: &BotClient) {
+        if let Some(game_id) = &game.id {
+            self.turns.lock().unwrap().remove(game_id);
+        }
+    }
+
+    async fn on_game_state(&self, context: &GameContext, state: GameStateEvent,
+            _client: &BotClient) {
+        let moves = state.moves.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+        let plies = Some(moves.len()).filter(|_| state.status.is_running());
+
+        if self.update_turn(&context.id, plies) && plies.is_some() {
+            self.source.sync(context, &moves).await;
+        }
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, state: GameStateEvent, client: &BotClient) {
+        // Correspondence reminders call this again while the user is still entering their move.
+        if !self.awaiting_input.lock().unwrap().insert(context.id.clone()) {
+            return;
+        }
+
+        let moves = state.moves.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+
+        self.play_turn(context, &moves, client).await;
+        self.awaiting_input.lock().unwrap().remove(&context.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    use futures::future;
+
+    use kernal::prelude::*;
+
+    use wiremock::{Mock, ResponseTemplate};
+    use wiremock::matchers::{method, path};
+
+    use crate::model::game::{Color, GameStatus};
+    use crate::store::tests as store_tests;
+    use crate::test_util;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct QueuedMoveSource {
+        moves: Mutex<VecDeque<Move>>,
+        synced: Mutex<Vec<Vec<Move>>>,
+        rejected: Mutex<Vec<Move>>
+    }
+
+    impl QueuedMoveSource {
+        fn new(moves: &[&str]) -> QueuedMoveSource {
+            QueuedMoveSource {
+                moves: Mutex::new(moves.iter().map(|&mov| mov.to_owned()).collect()),
+                ..QueuedMoveSource::default()
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MoveSource for QueuedMoveSource {
+        async fn sync(&self, _: &GameContext, moves: &[Move]) {
+            self.synced.lock().unwrap().push(moves.to_vec());
+        }
+
+        async fn next_move(&self, _: &GameContext, _: &[Move]) -> Option<Move> {
+            let mov = self.moves.lock().unwrap().pop_front();
+
+            match mov {
+                Some(mov) => Some(mov),
+                None => future::pending().await
+            }
+        }
+
+        async fn move_rejected(&self, _: &GameContext, mov: &Move, _: LibotRequestError) {
+            self.rejected.lock().unwrap().push(mov.clone());
+        }
+    }
+
+    fn test_context() -> GameContext {
+        GameContext {
+            bot_id: "testBotId".to_owned(),
+            bot_color: Some(Color::White),
+            info: store_tests::test_game_info("testGameId", "testBotId", "opponent"),
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    fn test_state(moves: &str, status: GameStatus) -> GameStateEvent {
+        GameStateEvent {
+            moves: moves.to_owned(),
+            white_time: 0,
+            black_time: 0,
+            white_increment: 0,
+            black_increment: 0,
+            status,
+            winner: None,
+            white_draw_offer: false,
+            black_draw_offer: false,
+            white_take_back_proposal: false,
+            black_take_back_proposal: false,
+            skipped_states: 0
+        }
+    }
+
+    fn moves(moves: &str) -> Vec<Move> {
+        moves.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn moves_of_source_are_submitted_and_game_is_synchronized() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e2e4"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let bot = MoveSourceBot::new(QueuedMoveSource::new(&["e2e4"]));
+            let context = test_context();
+
+            bot.on_game_state(&context, test_state("", GameStatus::Started), &client).await;
+            bot.on_my_turn(&context, test_state("", GameStatus::Started), &client).await;
+            bot.on_game_state(&context, test_state("e2e4", GameStatus::Started), &client).await;
+            bot.on_game_state(&context, test_state("e2e4", GameStatus::Started), &client).await;
+            bot.on_game_state(&context, test_state("e2e4 e7e5", GameStatus::Started), &client)
+                .await;
+
+            assert_that!(bot.source().synced.lock().unwrap().clone())
+                .contains_exactly_in_given_order([moves(""), moves("e2e4"), moves("e2e4 e7e5")]);
+        });
+    }
+
+    #[test]
+    fn rejected_move_is_reported_and_next_move_is_requested() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e2e5"))
+                .respond_with(ResponseTemplate::new(400))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e2e4"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let bot = MoveSourceBot::new(QueuedMoveSource::new(&["e2e5", "e2e4"]));
+            let context = test_context();
+
+            bot.on_my_turn(&context, test_state("", GameStatus::Started), &client).await;
+
+            assert_that!(bot.source().rejected.lock().unwrap().clone())
+                .contains_exactly_in_given_order(["e2e5".to_owned()]);
+        });
+    }
+
+    #[test]
+    fn pending_input_is_cancelled_when_game_ends() {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let bot = MoveSourceBot::new(QueuedMoveSource::default());
+            let context = test_context();
+
+            bot.on_game_state(&context, test_state("", GameStatus::Started), &client).await;
+
+            let turn = bot.on_my_turn(&context, test_state("", GameStatus::Started), &client);
+            let resignation = async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                bot.on_game_state(&context, test_state("", GameStatus::Resign), &client).await;
+            };
+            let result = tokio::time::timeout(
+                Duration::from_secs(1), future::join(turn, resignation)).await;
+
+            assert_that!(result).is_ok();
+            assert_that!(bot.awaiting_input.lock().unwrap().is_empty()).is_true();
+        });
+    }
+
+    #[test]
+    fn repeated_turn_does_not_request_second_move() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e2e4"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(0)
+                .mount(&server)
+                .await;
+            let bot = MoveSourceBot::new(QueuedMoveSource::default());
+            let context = test_context();
+            bot.awaiting_input.lock().unwrap().insert("testGameId".to_owned());
+            bot.source().moves.lock().unwrap().push_back("e2e4".to_owned());
+
+            bot.on_my_turn(&context, test_state("", GameStatus::Started), &client).await;
+
+            assert_that!(bot.source().moves.lock().unwrap().len()).is_equal_to(1);
+        });
+    }
+}
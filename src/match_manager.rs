@@ -0,0 +1,461 @@
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use crate::client::BotClient;
+use crate::elo::{EloEstimate, Sprt, SprtHypothesis};
+use crate::error::{ChallengeValidationResult, LibotResult};
+use crate::model::challenge::{
+    ChallengeBuilder,
+    ChallengeColor,
+    ChallengeOutcome,
+    ChallengeRequest,
+    DeclineReason
+};
+use crate::model::game::{Color, GameId, GameStatus};
+use crate::model::game::event::GameEvent;
+use crate::model::game::result::{self, GameOutcome};
+
+/// A single game of a match played by a [MatchManager] or a
+/// [SelfPlay](crate::self_play::SelfPlay) match.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MatchGame {
+
+    /// The ID of the game on Lichess, or a generated ID for a game played in-process.
+    pub game_id: GameId,
+
+    /// The color played by the bot in this game.
+    pub bot_color: Color,
+
+    /// The [GameOutcome] from the perspective of the bot.
+    pub outcome: GameOutcome,
+
+    /// The status with which the game ended.
+    pub status: GameStatus
+}
+
+/// Describes whether a match played by a [MatchManager] or a
+/// [SelfPlay](crate::self_play::SelfPlay) match was completed or why it stopped early.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MatchStatus {
+
+    /// All games of the match were played.
+    Completed,
+
+    /// The opponent declined the challenge to the next game for the given reason.
+    Declined(DeclineReason),
+
+    /// The challenge to the next game was canceled, e.g. because the opponent went offline.
+    Canceled,
+
+    /// The [Sprt] configured with [MatchManager::with_sprt] or
+    /// [SelfPlay::with_sprt](crate::self_play::SelfPlay::with_sprt) accepted the given hypothesis
+    /// before all games were played.
+    Concluded(SprtHypothesis)
+}
+
+/// The result of a match played by a [MatchManager] or a [SelfPlay](crate::self_play::SelfPlay)
+/// match. Its [Display] implementation reports the score in the form `+wins =draws -losses`,
+/// followed by the points of the bot.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MatchResult {
+
+    /// The name of the opponent against which the match was played.
+    pub opponent: String,
+
+    /// The games of the match in the order in which they were played.
+    pub games: Vec<MatchGame>,
+
+    /// Whether all games were played or why the match stopped early.
+    pub status: MatchStatus
+}
+
+impl MatchResult {
+
+    fn count(&self, outcome: GameOutcome) -> u32 {
+        self.games.iter().filter(|game| game.outcome == outcome).count() as u32
+    }
+
+    /// The number of games won by the bot.
+    pub fn wins(&self) -> u32 {
+        self.count(GameOutcome::Win)
+    }
+
+    /// The number of drawn games.
+    pub fn draws(&self) -> u32 {
+        self.count(GameOutcome::Draw)
+    }
+
+    /// The number of games lost by the bot.
+    pub fn losses(&self) -> u32 {
+        self.count(GameOutcome::Loss)
+    }
+
+    /// The number of games which were won, drawn, or lost, i.e. not aborted and with a known
+    /// outcome.
+    pub fn decided_games(&self) -> u32 {
+        self.wins() + self.draws() + self.losses()
+    }
+
+    /// The points scored by the bot, where wins count 1 and draws count 0.5.
+    pub fn score(&self) -> f64 {
+        self.wins() as f64 + self.draws() as f64 * 0.5
+    }
+
+    /// Estimates the Elo difference between the bot and the opponent from the decided games.
+    ///
+    /// # Returns
+    ///
+    /// The estimate, or [None] if no game was decided.
+    pub fn elo_estimate(&self) -> Option<EloEstimate> {
+        EloEstimate::from_results(self.wins(), self.draws(), self.losses())
+    }
+}
+
+impl Display for MatchResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} ={} -{} ({}/{}) against {}", self.wins(), self.draws(), self.losses(),
+            self.score(), self.decided_games(), self.opponent)?;
+
+        match self.status {
+            MatchStatus::Completed => Ok(()),
+            MatchStatus::Declined(reason) => write!(f, ", stopped because declined ({reason:?})"),
+            MatchStatus::Canceled => write!(f, ", stopped because canceled"),
+            MatchStatus::Concluded(hypothesis) =>
+                write!(f, ", stopped because SPRT accepted {hypothesis:?} hypothesis")
+        }
+    }
+}
+
+/// Plays a match of a fixed number of games between the bot and a named opponent on Lichess, for
+/// example to compare two versions of an engine. The bot challenges the opponent to every game,
+/// alternating colors, and waits for each game to finish before challenging to the next one. All
+/// games use the same settings, such as the time control.
+///
+/// The manager only sends the challenges and observes the games, it does not play them. The moves
+/// must be made by a [BotRunner](crate::runner::BotRunner) running with the same account at the
+/// same time.
+#[derive(Clone, Debug)]
+pub struct MatchManager {
+    client: BotClient,
+    opponent: String,
+    games: u32,
+    white_request: ChallengeRequest,
+    black_request: ChallengeRequest,
+    first_color: Color,
+    pause: Duration,
+    sprt: Option<Sprt>
+}
+
+impl MatchManager {
+
+    /// Creates a new match manager in which the bot plays White in the first game and which
+    /// pauses for 5 seconds between games.
+    ///
+    /// # Arguments
+    ///
+    /// * `client`: The client used to challenge the opponent and observe the games.
+    /// * `opponent`: The name of the user to play against.
+    /// * `games`: The number of games to play.
+    /// * `settings`: The settings of every game, such as the time control. The color set in the
+    ///   builder is ignored, since colors alternate.
+    ///
+    /// # Errors
+    ///
+    /// Any [ChallengeValidationError](crate::error::ChallengeValidationError) of the given
+    /// settings.
+    pub fn new(client: BotClient, opponent: impl Into<String>, games: u32,
+            settings: ChallengeBuilder) -> ChallengeValidationResult<MatchManager> {
+        Ok(MatchManager {
+            client,
+            opponent: opponent.into(),
+            games,
+            white_request: settings.clone().with_color(ChallengeColor::White).build()?,
+            black_request: settings.with_color(ChallengeColor::Black).build()?,
+            first_color: Color::White,
+            pause: Duration::from_secs(5),
+            sprt: None
+        })
+    }
+
+    /// Sets the color played by the bot in the first game. The manager is returned for chaining.
+    pub fn with_first_color(mut self, first_color: Color) -> MatchManager {
+        self.first_color = first_color;
+        self
+    }
+
+    /// Sets the time to wait after a game finished before challenging the opponent to the next
+    /// one, which gives both bots time to clean up. The manager is returned for chaining.
+    pub fn with_pause(mut self, pause: Duration) -> MatchManager {
+        self.pause = pause;
+        self
+    }
+
+    /// Sets a sequential probability ratio test which is evaluated after every game. Once it
+    /// accepts one of its hypotheses, the match stops early, so the number of games passed to
+    /// [MatchManager::new] acts as the maximum. The manager is returned for chaining.
+    pub fn with_sprt(mut self, sprt: Sprt) -> MatchManager {
+        self.sprt = Some(sprt);
+        self
+    }
+
+    fn color_of_game(&self, index: u32) -> Color {
+        if index.is_multiple_of(2) {
+            self.first_color
+        }
+        else {
+            self.first_color.opposite()
+        }
+    }
+
+    /// Waits until the game with the given ID is over and determines its outcome.
+    async fn await_game(&self, game_id: GameId, bot_color: Color) -> LibotResult<MatchGame> {
+        let mut events = Box::pin(self.client.stream_game_events(game_id.clone()).await?);
+        let mut end = None;
+
+        while let Some(event) = events.next().await {
+            let state = match event {
+                Ok(GameEvent::GameFull(game_full)) => game_full.state,
+                Ok(GameEvent::GameState(state)) => state,
+                _ => continue
+            };
+
+            if !state.status.is_running() {
+                end = Some((state.status, state.winner));
+                break;
+            }
+        }
+
+        let (status, winner) = match end {
+            Some(end) => end,
+            None => {
+                let game = self.client.export_game(game_id.clone()).await?;
+
+                (game.status, game.winner)
+            }
+        };
+
+        Ok(MatchGame {
+            game_id,
+            bot_color,
+            outcome: result::outcome(Some(status), winner, Some(bot_color)),
+            status
+        })
+    }
+
+    /// Plays the match by challenging the opponent to one game after the other. Aborted games
+    /// count towards the number of games, but not towards the score. If a challenge is declined
+    /// or canceled, or the [Sprt] set with [MatchManager::with_sprt] accepts a hypothesis, the
+    /// match stops early, which is reported in the [MatchResult::status].
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError](crate::error::LibotRequestError) that occurs while challenging the
+    /// opponent or observing a game.
+    pub async fn play(&self) -> LibotResult<MatchResult> {
+        let mut match_result = MatchResult {
+            opponent: self.opponent.clone(),
+            games: Vec::new(),
+            status: MatchStatus::Completed
+        };
+
+        for index in 0..self.games {
+            if index > 0 {
+                tokio::time::sleep(self.pause).await;
+            }
+
+            let bot_color = self.color_of_game(index);
+            let request = match bot_color {
+                Color::White => &self.white_request,
+                Color::Black => &self.black_request
+            };
+            let challenge = self.client.create_challenge_kept_alive(&self.opponent, request)
+                .await?;
+
+            let game_id = match challenge.outcome().await? {
+                ChallengeOutcome::Accepted(game_id) => game_id,
+                ChallengeOutcome::Declined(reason) => {
+                    match_result.status = MatchStatus::Declined(reason);
+                    break;
+                },
+                ChallengeOutcome::Canceled => {
+                    match_result.status = MatchStatus::Canceled;
+                    break;
+                }
+            };
+
+            match_result.games.push(self.await_game(game_id, bot_color).await?);
+
+            let decision = self.sprt.as_ref().and_then(|sprt|
+                sprt.decide(match_result.wins(), match_result.draws(), match_result.losses()));
+
+            if let Some(hypothesis) = decision {
+                match_result.status = MatchStatus::Concluded(hypothesis);
+                break;
+            }
+        }
+
+        Ok(match_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{body_string_contains, method, path};
+
+    use crate::test_util;
+
+    use super::*;
+
+    fn challenge_line(challenge_id: &str) -> String {
+        format!(concat!(
+            r#"{{"id":"{}","url":"testUrl","status":"created","#,
+            r#""challenger":{{"id":"testbot","name":"testbot"}},"variant":{{}},"#,
+            r#""rated":false,"speed":"blitz","timeControl":{{"type":"unlimited"}},"#,
+            r#""color":"random","perf":{{}}}}"#), challenge_id)
+    }
+
+    fn final_state_line(status: &str, winner: Option<&str>) -> String {
+        let winner = winner.map(|winner| format!(r#","winner":"{winner}""#)).unwrap_or_default();
+
+        format!(concat!(
+            r#"{{"type":"gameState","moves":"e2e4","wtime":180000,"btime":180000,"winc":2000,"#,
+            r#""binc":2000,"status":"{}"{}}}"#), status, winner)
+    }
+
+    async fn mount_game(server: &MockServer, game_id: &str, color: &str, done: &str,
+            game_stream: String) {
+        Mock::given(method("POST"))
+            .and(path("/challenge/opponent"))
+            .and(body_string_contains(format!("color={color}")))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_string(format!("{}\n{{\"done\":\"{done}\"}}\n", challenge_line(game_id))))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/bot/game/stream/{game_id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_string(game_stream))
+            .mount(server)
+            .await;
+    }
+
+    fn match_manager(client: BotClient, games: u32) -> MatchManager {
+        MatchManager::new(client, "opponent", games, ChallengeBuilder::new().with_clock(180, 2))
+            .unwrap()
+            .with_pause(Duration::ZERO)
+    }
+
+    fn match_game(game_id: &str, bot_color: Color, outcome: GameOutcome, status: GameStatus)
+            -> MatchGame {
+        MatchGame {
+            game_id: game_id.to_owned(),
+            bot_color,
+            outcome,
+            status
+        }
+    }
+
+    #[test]
+    fn match_alternates_colors_and_aggregates_score() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            mount_game(&server, "game1", "white", "accepted",
+                format!("{}\n", final_state_line("mate", Some("white")))).await;
+            mount_game(&server, "game2", "black", "accepted",
+                format!("{}\n", final_state_line("draw", None))).await;
+            mount_game(&server, "game3", "white", "accepted",
+                format!("{}\n", final_state_line("resign", Some("black")))).await;
+
+            let result = match_manager(client, 3).play().await.unwrap();
+
+            assert_that!(&result.games).contains_exactly_in_given_order([
+                match_game("game1", Color::White, GameOutcome::Win, GameStatus::Mate),
+                match_game("game2", Color::Black, GameOutcome::Draw, GameStatus::Draw),
+                match_game("game3", Color::White, GameOutcome::Loss, GameStatus::Resign)
+            ]);
+            assert_that!(result.status).is_equal_to(MatchStatus::Completed);
+            assert_that!(result.score()).is_equal_to(1.5);
+            assert_that!(result.to_string())
+                .is_equal_to("+1 =1 -1 (1.5/3) against opponent".to_owned());
+        });
+    }
+
+    #[rstest]
+    #[case::declined("declined", MatchStatus::Declined(DeclineReason::Generic))]
+    #[case::canceled("canceled", MatchStatus::Canceled)]
+    fn match_stops_if_challenge_is_not_accepted(#[case] done: &str,
+            #[case] expected_status: MatchStatus) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            mount_game(&server, "game1", "black", "accepted",
+                format!("{}\n", final_state_line("outoftime", Some("black")))).await;
+            mount_game(&server, "game2", "white", done, String::new()).await;
+
+            let result = match_manager(client, 5)
+                .with_first_color(Color::Black)
+                .play()
+                .await
+                .unwrap();
+
+            assert_that!(&result.games).contains_exactly_in_given_order([
+                match_game("game1", Color::Black, GameOutcome::Win, GameStatus::OutOfTime)
+            ]);
+            assert_that!(result.status).is_equal_to(expected_status);
+        });
+    }
+
+    #[test]
+    fn match_stops_once_sprt_concludes() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            mount_game(&server, "game1", "white", "accepted",
+                format!("{}\n", final_state_line("mate", Some("white")))).await;
+            mount_game(&server, "game2", "black", "accepted",
+                format!("{}\n", final_state_line("draw", None))).await;
+
+            let sprt = Sprt::new(0.0, 200.0).with_error_rates(0.4, 0.4);
+            let result = match_manager(client, 10).with_sprt(sprt).play().await.unwrap();
+
+            assert_that!(&result.games).has_length(2);
+            assert_that!(result.status)
+                .is_equal_to(MatchStatus::Concluded(SprtHypothesis::Alternative));
+            assert_that!(result.elo_estimate().unwrap().elo).is_greater_than(0.0);
+        });
+    }
+
+    #[test]
+    fn outcome_is_exported_if_game_stream_ends_early() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            mount_game(&server, "game1", "white", "accepted", String::new()).await;
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                    r#"{"id":"game1","rated":false,"speed":"blitz","createdAt":1234,"#,
+                    r#""status":"aborted","players":{"white":{},"black":{}}}"#)))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = match_manager(client, 1).play().await.unwrap();
+
+            assert_that!(&result.games).contains_exactly_in_given_order([
+                match_game("game1", Color::White, GameOutcome::Aborted, GameStatus::Aborted)
+            ]);
+            assert_that!(result.decided_games()).is_equal_to(0);
+        });
+    }
+}
@@ -0,0 +1,454 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::model::Move;
+use crate::model::cloud_eval::CloudEvaluation;
+use crate::model::external_engine::{AnalysisLine, AnalysisUpdate};
+use crate::model::game::Color;
+
+/// An evaluation of a position by an engine.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Score {
+
+    /// An evaluation in centipawns, i.e. hundredths of a pawn.
+    Centipawns(i32),
+
+    /// A forced mate in the given number of moves. Negative values mean that the side from whose
+    /// perspective the score is given gets mated.
+    Mate(i32)
+}
+
+impl Score {
+
+    /// The same score from the perspective of the other side.
+    pub fn negate(self) -> Score {
+        match self {
+            Score::Centipawns(centipawns) => Score::Centipawns(-centipawns),
+            Score::Mate(moves) => Score::Mate(-moves)
+        }
+    }
+
+    /// Converts a score from White's perspective to the perspective of the given color. Since
+    /// this is symmetric, it also converts a score from the perspective of the given color to
+    /// White's perspective.
+    pub fn relative_to(self, color: Color) -> Score {
+        match color {
+            Color::White => self,
+            Color::Black => self.negate()
+        }
+    }
+
+    /// Combines the `cp` and `mate` fields used by the Lichess API into a score, preferring the
+    /// mate if both are present.
+    ///
+    /// # Returns
+    ///
+    /// The score, or [None] if neither is present.
+    pub fn from_cp_or_mate(cp: Option<i32>, mate: Option<i32>) -> Option<Score> {
+        mate.map(Score::Mate).or(cp.map(Score::Centipawns))
+    }
+}
+
+/// The backend which produced an [Evaluation].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EvaluationSource {
+    CloudEval,
+    ExternalEngine,
+    LocalEngine
+}
+
+/// A principal variation of an [Evaluation].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PrincipalVariation {
+
+    /// The evaluation at the end of this line from White's perspective.
+    pub score: Score,
+
+    /// The moves of the line in UCI notation.
+    pub moves: Vec<Move>
+}
+
+impl PrincipalVariation {
+
+    fn from_analysis_line(line: AnalysisLine, side_to_move: Color) -> Option<PrincipalVariation> {
+        Some(PrincipalVariation {
+            score: Score::from_cp_or_mate(line.cp, line.mate)?.relative_to(side_to_move),
+            moves: line.moves
+        })
+    }
+}
+
+/// An evaluation of a position with one or more principal variations, independent of whether it
+/// was obtained from the cloud evaluation database, an external engine, or a local UCI engine.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Evaluation {
+    pub source: EvaluationSource,
+
+    /// The search depth reached in half-moves.
+    pub depth: u32,
+
+    /// The principal variations, best first.
+    pub lines: Vec<PrincipalVariation>
+}
+
+impl Evaluation {
+
+    /// The score of the best line from White's perspective, if any line was found.
+    pub fn score(&self) -> Option<Score> {
+        self.lines.first().map(|line| line.score)
+    }
+
+    /// The first move of the best line, if any line was found.
+    pub fn best_move(&self) -> Option<&Move> {
+        self.lines.first()?.moves.first()
+    }
+
+    /// Converts an update of an external engine analysis, whose scores are given from the
+    /// perspective of the side to move. Lines without a score are skipped.
+    pub fn from_analysis_update(update: AnalysisUpdate, side_to_move: Color) -> Evaluation {
+        Evaluation {
+            source: EvaluationSource::ExternalEngine,
+            depth: update.depth,
+            lines: update.pvs.into_iter()
+                .filter_map(|line| PrincipalVariation::from_analysis_line(line, side_to_move))
+                .collect()
+        }
+    }
+
+    /// Converts the UCI `info` lines of a local engine, whose scores are given from the
+    /// perspective of the side to move. If multiple infos are given for the same principal
+    /// variation, the last one is used. The depth of the evaluation is the maximum depth of all
+    /// infos.
+    ///
+    /// # Returns
+    ///
+    /// The evaluation with lines ordered by their [UciInfo::multi_pv] index, or [None] if no
+    /// infos are given.
+    pub fn from_uci_infos(infos: impl IntoIterator<Item = UciInfo>, side_to_move: Color)
+            -> Option<Evaluation> {
+        let mut latest: Vec<UciInfo> = Vec::new();
+
+        for info in infos {
+            latest.retain(|latest| latest.multi_pv != info.multi_pv);
+            latest.push(info);
+        }
+
+        latest.sort_by_key(|info| info.multi_pv);
+        let depth = latest.iter().map(|info| info.depth).max()?;
+
+        Some(Evaluation {
+            source: EvaluationSource::LocalEngine,
+            depth,
+            lines: latest.into_iter()
+                .map(|info| PrincipalVariation {
+                    score: info.score.relative_to(side_to_move),
+                    moves: info.pv
+                })
+                .collect()
+        })
+    }
+
+    /// Converts this evaluation into UCI `info` lines, one per principal variation with
+    /// consecutive [UciInfo::multi_pv] indices, e.g. to submit it as the output of an external
+    /// engine. Scores are converted to the perspective of the given side to move.
+    pub fn to_uci_infos(&self, side_to_move: Color) -> Vec<UciInfo> {
+        self.lines.iter()
+            .zip(1..)
+            .map(|(line, multi_pv)| UciInfo {
+                depth: self.depth,
+                multi_pv,
+                score: line.score.relative_to(side_to_move),
+                nodes: None,
+                pv: line.moves.clone()
+            })
+            .collect()
+    }
+}
+
+impl From<CloudEvaluation> for Evaluation {
+    fn from(cloud_eval: CloudEvaluation) -> Evaluation {
+        let lines = cloud_eval.pvs.into_iter()
+            .filter_map(|line| Some(PrincipalVariation {
+                score: Score::from_cp_or_mate(line.cp, line.mate)?,
+                moves: line.moves.split_whitespace().map(str::to_owned).collect()
+            }))
+            .collect();
+
+        Evaluation {
+            source: EvaluationSource::CloudEval,
+            depth: cloud_eval.depth,
+            lines
+        }
+    }
+}
+
+/// The information of a UCI `info` line that reports a principal variation.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UciInfo {
+    pub depth: u32,
+
+    /// The 1-based index of the principal variation, which is 1 if the engine does not report
+    /// multiple variations.
+    pub multi_pv: u32,
+
+    /// The score from the perspective of the side to move.
+    pub score: Score,
+    pub nodes: Option<u64>,
+
+    /// The moves of the principal variation in UCI notation.
+    pub pv: Vec<Move>
+}
+
+impl UciInfo {
+
+    /// Parses a UCI `info` line. Lines without a depth, score, or principal variation, such as
+    /// `info string` or `info currmove` lines, yield [None].
+    ///
+    /// # Arguments
+    ///
+    /// * `line`: The line output by the engine.
+    pub fn parse(line: &str) -> Option<UciInfo> {
+        let mut tokens = line.split_whitespace();
+
+        if tokens.next() != Some("info") {
+            return None;
+        }
+
+        let mut depth = None;
+        let mut multi_pv = 1;
+        let mut score = None;
+        let mut nodes = None;
+        let mut pv = Vec::new();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => depth = tokens.next()?.parse().ok(),
+                "multipv" => multi_pv = tokens.next()?.parse().ok()?,
+                "nodes" => nodes = tokens.next()?.parse().ok(),
+                "score" => score = match (tokens.next()?, tokens.next()?.parse().ok()?) {
+                    ("cp", centipawns) => Some(Score::Centipawns(centipawns)),
+                    ("mate", moves) => Some(Score::Mate(moves)),
+                    _ => None
+                },
+                "pv" => {
+                    pv = tokens.by_ref().map(str::to_owned).collect();
+                },
+                "string" => return None,
+                _ => { }
+            }
+        }
+
+        if pv.is_empty() {
+            return None;
+        }
+
+        Some(UciInfo {
+            depth: depth?,
+            multi_pv,
+            score: score?,
+            nodes,
+            pv
+        })
+    }
+}
+
+/// Formats the info as a UCI `info` line which can be parsed with [UciInfo::parse].
+impl Display for UciInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "info depth {} multipv {} score ", self.depth, self.multi_pv)?;
+
+        match self.score {
+            Score::Centipawns(centipawns) => write!(f, "cp {centipawns}")?,
+            Score::Mate(moves) => write!(f, "mate {moves}")?
+        }
+
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {nodes}")?;
+        }
+
+        write!(f, " pv {}", self.pv.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::cloud_eval::CloudEvaluationLine;
+
+    use super::*;
+
+    fn moves(moves: &str) -> Vec<Move> {
+        moves.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn score_negate() {
+        assert_that!(Score::Centipawns(35).negate()).is_equal_to(Score::Centipawns(-35));
+        assert_that!(Score::Mate(-2).negate()).is_equal_to(Score::Mate(2));
+    }
+
+    #[rstest]
+    #[case::white(Color::White, Score::Centipawns(35))]
+    #[case::black(Color::Black, Score::Centipawns(-35))]
+    fn score_relative_to(#[case] color: Color, #[case] expected: Score) {
+        assert_that!(Score::Centipawns(35).relative_to(color)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::cp(Some(20), None, Some(Score::Centipawns(20)))]
+    #[case::mate(None, Some(-3), Some(Score::Mate(-3)))]
+    #[case::both(Some(20), Some(3), Some(Score::Mate(3)))]
+    #[case::neither(None, None, None)]
+    fn score_from_cp_or_mate(#[case] cp: Option<i32>, #[case] mate: Option<i32>,
+            #[case] expected: Option<Score>) {
+        assert_that!(Score::from_cp_or_mate(cp, mate)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::centipawns(
+        "info depth 12 seldepth 15 multipv 2 score cp -35 nodes 1234 nps 100 pv e2e4 e7e5",
+        Some(UciInfo {
+            depth: 12,
+            multi_pv: 2,
+            score: Score::Centipawns(-35),
+            nodes: Some(1234),
+            pv: vec!["e2e4".to_owned(), "e7e5".to_owned()]
+        }))]
+    #[case::mate("info depth 5 score mate 3 pv h5f7",
+        Some(UciInfo {
+            depth: 5,
+            multi_pv: 1,
+            score: Score::Mate(3),
+            nodes: None,
+            pv: vec!["h5f7".to_owned()]
+        }))]
+    #[case::string("info string NNUE evaluation enabled", None)]
+    #[case::currmove("info depth 5 currmove e2e4 currmovenumber 1", None)]
+    #[case::not_info("bestmove e2e4", None)]
+    fn parse_info(#[case] line: &str, #[case] expected: Option<UciInfo>) {
+        assert_that!(UciInfo::parse(line)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::centipawns("info depth 12 multipv 2 score cp -35 nodes 1234 pv e2e4 e7e5")]
+    #[case::mate("info depth 5 multipv 1 score mate 3 pv h5f7")]
+    fn format_info_round_trip(#[case] line: &str) {
+        let info = UciInfo::parse(line).unwrap();
+
+        assert_that!(info.to_string()).is_equal_to(line.to_owned());
+    }
+
+    #[test]
+    fn evaluation_from_cloud_eval() {
+        let cloud_eval = CloudEvaluation {
+            fen: "testFen".to_owned(),
+            knodes: 100,
+            depth: 30,
+            pvs: vec![
+                CloudEvaluationLine {
+                    moves: "e7e5 g1f3".to_owned(),
+                    cp: Some(20),
+                    mate: None
+                },
+                CloudEvaluationLine {
+                    moves: "c7c5".to_owned(),
+                    cp: None,
+                    mate: None
+                }
+            ]
+        };
+
+        let evaluation = Evaluation::from(cloud_eval);
+
+        assert_that!(evaluation).is_equal_to(Evaluation {
+            source: EvaluationSource::CloudEval,
+            depth: 30,
+            lines: vec![PrincipalVariation {
+                score: Score::Centipawns(20),
+                moves: moves("e7e5 g1f3")
+            }]
+        });
+    }
+
+    #[test]
+    fn evaluation_from_analysis_update_uses_white_perspective() {
+        let update = AnalysisUpdate {
+            time: 20,
+            depth: 10,
+            nodes: 200,
+            pvs: vec![AnalysisLine {
+                depth: 10,
+                cp: None,
+                mate: Some(2),
+                moves: moves("d8h4")
+            }]
+        };
+
+        let evaluation = Evaluation::from_analysis_update(update, Color::Black);
+
+        assert_that!(evaluation.source).is_equal_to(EvaluationSource::ExternalEngine);
+        assert_that!(evaluation.score()).contains(Score::Mate(-2));
+        assert_that!(evaluation.best_move()).contains(&"d8h4".to_owned());
+    }
+
+    #[test]
+    fn evaluation_from_uci_infos_keeps_latest_info_per_variation() {
+        let infos = [
+            "info depth 1 multipv 1 score cp 10 pv e2e4",
+            "info depth 1 multipv 2 score cp 5 pv d2d4",
+            "info depth 2 multipv 1 score cp 30 pv e2e4 e7e5"
+        ].into_iter().filter_map(UciInfo::parse);
+
+        let evaluation = Evaluation::from_uci_infos(infos, Color::Black).unwrap();
+
+        assert_that!(evaluation).is_equal_to(Evaluation {
+            source: EvaluationSource::LocalEngine,
+            depth: 2,
+            lines: vec![
+                PrincipalVariation {
+                    score: Score::Centipawns(-30),
+                    moves: moves("e2e4 e7e5")
+                },
+                PrincipalVariation {
+                    score: Score::Centipawns(-5),
+                    moves: moves("d2d4")
+                }
+            ]
+        });
+    }
+
+    #[test]
+    fn evaluation_from_no_uci_infos_is_none() {
+        assert_that!(Evaluation::from_uci_infos([], Color::White)).is_none();
+    }
+
+    #[test]
+    fn evaluation_to_uci_infos_uses_side_to_move_perspective() {
+        let evaluation = Evaluation {
+            source: EvaluationSource::CloudEval,
+            depth: 20,
+            lines: vec![
+                PrincipalVariation {
+                    score: Score::Mate(-4),
+                    moves: moves("c7c5 g1f3")
+                },
+                PrincipalVariation {
+                    score: Score::Centipawns(15),
+                    moves: moves("e7e5")
+                }
+            ]
+        };
+
+        let lines = evaluation.to_uci_infos(Color::Black).iter()
+            .map(UciInfo::to_string)
+            .collect::<Vec<_>>();
+
+        assert_that!(lines).contains_exactly_in_given_order([
+            "info depth 20 multipv 1 score mate 4 pv c7c5 g1f3".to_owned(),
+            "info depth 20 multipv 2 score cp -15 pv e7e5".to_owned()
+        ]);
+    }
+}
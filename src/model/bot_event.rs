@@ -6,8 +6,11 @@ use crate::model::game::event::GameEventSource;
 use crate::model::game::{
     Color,
     deserialize_game_status_from_object,
+    deserialize_optional_variant,
     GameId,
-    GameStatus
+    GameStatus,
+    Speed,
+    Variant
 };
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
@@ -19,6 +22,14 @@ pub struct GameStartFinish {
     #[serde(default, deserialize_with = "deserialize_game_status_from_object")]
     pub status: Option<GameStatus>,
     pub winner: Option<Color>,
+
+    /// The color played by the bot in this game.
+    pub color: Option<Color>,
+    pub rated: Option<bool>,
+    pub speed: Option<Speed>,
+
+    #[serde(default, deserialize_with = "deserialize_optional_variant")]
+    pub variant: Option<Variant>,
     pub compat: Option<Compat>
 }
 
@@ -76,6 +87,7 @@ mod tests {
         ChallengeDirection,
         ChallengePerf,
         ChallengeStatus,
+        DeclineInfo,
         DeclineReason
     };
     use crate::model::game::{Clock, Speed, Variant};
@@ -106,6 +118,10 @@ mod tests {
             source: None,
             status: None,
             winner: None,
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
             compat: None
         })
     )]
@@ -121,6 +137,10 @@ mod tests {
             source: None,
             status: None,
             winner: None,
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
             compat: None
         })
     )]
@@ -136,6 +156,10 @@ mod tests {
             source: Some(GameEventSource::Friend),
             status: None,
             winner: None,
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
             compat: None
         })
     )]
@@ -154,6 +178,10 @@ mod tests {
             source: None,
             status: Some(GameStatus::Created),
             winner: None,
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
             compat: None
         })
     )]
@@ -169,6 +197,53 @@ mod tests {
             source: None,
             status: None,
             winner: Some(Color::White),
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
+            compat: None
+        })
+    )]
+    #[case::game_finish_with_color_and_rated(
+        r#"{
+            "type": "gameFinish",
+            "game": {
+                "color": "black",
+                "rated": true
+            }
+        }"#,
+        BotEvent::GameFinish(GameStartFinish {
+            id: None,
+            source: None,
+            status: None,
+            winner: None,
+            color: Some(Color::Black),
+            rated: Some(true),
+            speed: None,
+            variant: None,
+            compat: None
+        })
+    )]
+    #[case::game_start_with_speed_and_variant(
+        r#"{
+            "type": "gameStart",
+            "game": {
+                "speed": "bullet",
+                "variant": {
+                    "key": "atomic",
+                    "name": "Atomic"
+                }
+            }
+        }"#,
+        BotEvent::GameStart(GameStartFinish {
+            id: None,
+            source: None,
+            status: None,
+            winner: None,
+            color: None,
+            rated: None,
+            speed: Some(Speed::Bullet),
+            variant: Some(Variant::Atomic),
             compat: None
         })
     )]
@@ -184,6 +259,10 @@ mod tests {
             source: None,
             status: None,
             winner: None,
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
             compat: Some(Compat {
                 board: None,
                 bot: None
@@ -205,6 +284,10 @@ mod tests {
             source: None,
             status: None,
             winner: None,
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
             compat: Some(Compat {
                 board: Some(true),
                 bot: Some(false)
@@ -258,8 +341,8 @@ mod tests {
             },
             direction: None,
             initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
+            rematch_of: None,
+            decline: None
         })
     )]
     #[case::challenge_with_full_challenger(
@@ -314,8 +397,8 @@ mod tests {
             },
             direction: None,
             initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
+            rematch_of: None,
+            decline: None
         })
     )]
     #[case::challenge_with_dest_user(
@@ -376,8 +459,8 @@ mod tests {
             },
             direction: None,
             initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
+            rematch_of: None,
+            decline: None
         })
     )]
     #[case::challenge_with_full_variant(
@@ -422,8 +505,8 @@ mod tests {
             },
             direction: None,
             initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
+            rematch_of: None,
+            decline: None
         })
     )]
     #[case::challenge_with_filled_perf(
@@ -467,8 +550,8 @@ mod tests {
             },
             direction: None,
             initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
+            rematch_of: None,
+            decline: None
         })
     )]
     #[case::challenge_canceled_with_remaining_optional_strings(
@@ -513,8 +596,11 @@ mod tests {
             },
             direction: Some(ChallengeDirection::In),
             initial_fen: Some("testFen".to_owned()),
-            decline_reason: Some("testDeclineReason".to_owned()),
-            decline_reason_key: Some(DeclineReason::NoBot)
+            rematch_of: None,
+            decline: Some(DeclineInfo {
+                key: DeclineReason::NoBot,
+                localized_text: Some("testDeclineReason".to_owned())
+            })
         })
     )]
     #[case::challenge_canceled_with_clock_time_control(
@@ -550,10 +636,13 @@ mod tests {
             variant: None,
             rated: true,
             speed: Speed::Blitz,
-            time_control: TimeControl::Clock(Clock {
-                limit: Some(300),
-                increment: Some(3)
-            }),
+            time_control: TimeControl::Clock {
+                clock: Clock {
+                    limit: Some(300),
+                    increment: Some(3)
+                },
+                show: Some("5+3".to_owned())
+            },
             color: ChallengeColor::Black,
             perf: ChallengePerf {
                 icon: None,
@@ -561,8 +650,8 @@ mod tests {
             },
             direction: None,
             initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
+            rematch_of: None,
+            decline: None
         })
     )]
     #[case::challenge_canceled_with_clock_time_control(
@@ -606,8 +695,8 @@ mod tests {
             },
             direction: None,
             initial_fen: None,
-            decline_reason: None,
-            decline_reason_key: None
+            rematch_of: None,
+            decline: None
         })
     )]
     #[case::challenge_declined(
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// The outcome of pushing a single game to a broadcast round, see [BroadcastPush].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct BroadcastPushGame {
+
+    /// The PGN tags of the game as parsed by Lichess, such as `White` or `Result`.
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+
+    /// The number of moves of the game Lichess parsed, if the game was accepted.
+    pub moves: Option<u32>,
+
+    /// The reason why the game was rejected, if any.
+    pub error: Option<String>
+}
+
+impl BroadcastPushGame {
+
+    /// Indicates whether Lichess accepted this game into the broadcast round.
+    pub fn is_accepted(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The response of Lichess to PGN pushed to a broadcast round using
+/// [BotClient::push_broadcast_pgn](crate::client::BotClient::push_broadcast_pgn).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct BroadcastPush {
+
+    /// The outcome for every game contained in the pushed PGN, in the same order.
+    pub games: Vec<BroadcastPushGame>
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_broadcast_push() {
+        let json = r#"{
+            "games": [
+                { "tags": { "White": "TestBot", "Black": "Opponent" }, "moves": 12 },
+                { "tags": { "White": "?" }, "error": "No moves found" }
+            ]
+        }"#;
+
+        let push = serde_json::from_str::<BroadcastPush>(json).unwrap();
+
+        assert_that!(&push.games).has_length(2);
+        assert_that!(push.games[0].tags.get("White").map(String::as_str)).contains("TestBot");
+        assert_that!(push.games[0].moves).contains(12);
+        assert_that!(push.games[0].is_accepted()).is_true();
+        assert_that!(push.games[1].error.as_deref()).contains("No moves found");
+        assert_that!(push.games[1].is_accepted()).is_false();
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::model::game::{deserialize_optional_variant, Fen, GameId, Speed, Variant};
-use crate::model::{TimeControl, Url};
+use crate::chat_i18n;
+use crate::error::{ChallengeValidationError, ChallengeValidationResult};
+use crate::model::game::{deserialize_optional_variant, Clock, Fen, GameId, Speed, Variant};
+use crate::model::{Days, Seconds, TimeControl, Url};
 use crate::model::user::User;
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
@@ -15,7 +17,7 @@ pub enum ChallengeStatus {
 }
 
 // TODO replace with Option<Player>?
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum ChallengeColor {
     White,
@@ -38,6 +40,8 @@ pub enum ChallengeDirection {
 
 /// An enumeration of the different reasons a bot can give why it rejected a challenge. This is
 /// displayed to the challenger so they can potentially formulate a more conforming challenge.
+/// Lichess reports the reason of a declined challenge in lower case, e.g. `nobot`, which is
+/// accepted as well.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum DeclineReason {
@@ -49,12 +53,15 @@ pub enum DeclineReason {
     Later,
 
     /// Indicates that the time control is too fast for the bot.
+    #[serde(alias = "toofast")]
     TooFast,
 
     /// Indicates that the time control is too slow for the bot.
+    #[serde(alias = "tooslow")]
     TooSlow,
 
     /// Indicates that the bot does not accept challenges with the given time control.
+    #[serde(alias = "timecontrol")]
     TimeControl,
 
     /// Indicates that the bot wants a rated challenge.
@@ -70,12 +77,146 @@ pub enum DeclineReason {
     Variant,
 
     /// Indicates that the bot does not accepts challenges from other bots.
+    #[serde(alias = "nobot")]
     NoBot,
 
     /// Indicates that the bot only accepts challenges from other bots.
+    #[serde(alias = "onlybot")]
     OnlyBot
 }
 
+/// The languages for which [DeclineReason::text] provides bundled translations.
+pub const DECLINE_REASON_LANGUAGES: [&str; 4] = ["en", "de", "fr", "es"];
+
+impl DeclineReason {
+
+    /// All reasons which a bot can give.
+    pub const ALL: [DeclineReason; 11] = [
+        DeclineReason::Generic,
+        DeclineReason::Later,
+        DeclineReason::TooFast,
+        DeclineReason::TooSlow,
+        DeclineReason::TimeControl,
+        DeclineReason::Rated,
+        DeclineReason::Casual,
+        DeclineReason::Standard,
+        DeclineReason::Variant,
+        DeclineReason::NoBot,
+        DeclineReason::OnlyBot
+    ];
+
+    /// The texts of this reason in the languages of [DECLINE_REASON_LANGUAGES], in that order.
+    fn texts(self) -> [&'static str; 4] {
+        match self {
+            DeclineReason::Generic => [
+                "I'm not accepting challenges at the moment.",
+                "Ich nehme im Moment keine Herausforderungen an.",
+                "Je n'accepte pas de défis pour le moment.",
+                "No estoy aceptando desafíos en este momento."
+            ],
+            DeclineReason::Later => [
+                "This is not the right time for me, please ask again later.",
+                "Jetzt passt es mir gerade nicht, bitte frag später noch einmal.",
+                "Ce n'est pas le bon moment pour moi, veuillez réessayer plus tard.",
+                "Este no es un buen momento para mí, por favor pregunta de nuevo más tarde."
+            ],
+            DeclineReason::TooFast => [
+                "This time control is too fast for me, please challenge again with a slower game.",
+                "Diese Bedenkzeit ist mir zu schnell, bitte fordere mich mit einer langsameren \
+                    Partie heraus.",
+                "Cette cadence est trop rapide pour moi, veuillez me défier avec une partie plus \
+                    lente.",
+                "Este control de tiempo es demasiado rápido para mí, por favor desafíame con una \
+                    partida más lenta."
+            ],
+            DeclineReason::TooSlow => [
+                "This time control is too slow for me, please challenge again with a faster game.",
+                "Diese Bedenkzeit ist mir zu langsam, bitte fordere mich mit einer schnelleren \
+                    Partie heraus.",
+                "Cette cadence est trop lente pour moi, veuillez me défier avec une partie plus \
+                    rapide.",
+                "Este control de tiempo es demasiado lento para mí, por favor desafíame con una \
+                    partida más rápida."
+            ],
+            DeclineReason::TimeControl => [
+                "I'm not accepting challenges with this time control.",
+                "Ich nehme keine Herausforderungen mit dieser Bedenkzeit an.",
+                "Je n'accepte pas de défis avec cette cadence.",
+                "No estoy aceptando desafíos con este control de tiempo."
+            ],
+            DeclineReason::Rated => [
+                "Please send me a rated challenge instead.",
+                "Bitte sende mir stattdessen eine gewertete Herausforderung.",
+                "Veuillez plutôt m'envoyer un défi classé.",
+                "Por favor, envíame un desafío por puntos en su lugar."
+            ],
+            DeclineReason::Casual => [
+                "Please send me a casual challenge instead.",
+                "Bitte sende mir stattdessen eine ungewertete Herausforderung.",
+                "Veuillez plutôt m'envoyer un défi amical.",
+                "Por favor, envíame un desafío amistoso en su lugar."
+            ],
+            DeclineReason::Standard => [
+                "I'm not accepting variant challenges right now.",
+                "Ich nehme derzeit keine Herausforderungen für Varianten an.",
+                "Je n'accepte pas de défis de variantes pour le moment.",
+                "No estoy aceptando desafíos de variantes en este momento."
+            ],
+            DeclineReason::Variant => [
+                "I'm not willing to play this variant right now.",
+                "Ich möchte diese Variante derzeit nicht spielen.",
+                "Je ne souhaite pas jouer cette variante pour le moment.",
+                "No quiero jugar esta variante en este momento."
+            ],
+            DeclineReason::NoBot => [
+                "I'm not accepting challenges from bots.",
+                "Ich nehme keine Herausforderungen von Bots an.",
+                "Je n'accepte pas de défis de la part de bots.",
+                "No estoy aceptando desafíos de bots."
+            ],
+            DeclineReason::OnlyBot => [
+                "I'm only accepting challenges from bots.",
+                "Ich nehme nur Herausforderungen von Bots an.",
+                "J'accepte uniquement les défis de bots.",
+                "Solo estoy aceptando desafíos de bots."
+            ]
+        }
+    }
+
+    /// Gets a human-readable text explaining this reason to the challenger, e.g. to mention it
+    /// in a chat message or notification. The English texts are those shown by Lichess, while
+    /// the texts in the other [DECLINE_REASON_LANGUAGES] are bundled translations which may
+    /// differ slightly from the Lichess translations. When Lichess reports a declined challenge,
+    /// the text it displayed is available as [DeclineInfo::localized_text].
+    ///
+    /// # Arguments
+    ///
+    /// * `language`: The language in which to get the text, such as `de` or `fr-CA`. If it is
+    ///   unknown or not supported, English is used.
+    pub fn text(self, language: Option<&str>) -> &'static str {
+        let language_index = language
+            .map(|language| chat_i18n::primary_language(language).to_lowercase())
+            .and_then(|language| DECLINE_REASON_LANGUAGES.iter()
+                .position(|&supported| supported == language))
+            .unwrap_or(0);
+
+        self.texts()[language_index]
+    }
+}
+
+/// The reason for which a [Challenge] was declined, as reported by Lichess.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct DeclineInfo {
+
+    /// The [DeclineReason] given by the declining user.
+    #[serde(rename = "declineReasonKey")]
+    pub key: DeclineReason,
+
+    /// The reason as displayed to the challenger, translated by Lichess, if it was reported.
+    #[serde(default, rename = "declineReason")]
+    pub localized_text: Option<String>
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Challenge {
@@ -95,8 +236,30 @@ pub struct Challenge {
     pub perf: ChallengePerf,
     pub direction: Option<ChallengeDirection>,
     pub initial_fen: Option<Fen>,
-    pub decline_reason: Option<String>, // TODO unify with key?
-    pub decline_reason_key: Option<DeclineReason>
+
+    /// The ID of the game of which this challenge is a rematch, if it is one.
+    pub rematch_of: Option<GameId>,
+
+    /// Information on why the challenge was declined, if it was.
+    #[serde(flatten)]
+    pub decline: Option<DeclineInfo>
+}
+
+/// The final outcome of a challenge created by the bot with
+/// [BotClient::create_challenge_kept_alive](crate::client::BotClient::create_challenge_kept_alive).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ChallengeOutcome {
+
+    /// The challenge was accepted and the game with the given ID, which is the ID of the
+    /// challenge, has started.
+    Accepted(GameId),
+
+    /// The challenge was declined for the given reason. If Lichess does not report a reason,
+    /// [DeclineReason::Generic] is used.
+    Declined(DeclineReason),
+
+    /// The challenge was canceled, e.g. because the challenged user went offline.
+    Canceled
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
@@ -113,3 +276,377 @@ pub struct Challenges {
     #[serde(default, rename = "out")]
     pub outgoing: Vec<Challenge>
 }
+
+const MAX_CLOCK_LIMIT: Seconds = 10800;
+const MAX_CLOCK_INCREMENT: Seconds = 60;
+const VALID_DAYS: [Days; 7] = [1, 2, 3, 5, 7, 10, 14];
+
+/// The body of a request to create a challenge, as sent by
+/// [BotClient::create_challenge](crate::client::BotClient::create_challenge). It can only be
+/// obtained from a [ChallengeBuilder], which ensures that the combination of settings is valid.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+pub struct ChallengeRequest {
+    rated: bool,
+
+    #[serde(rename = "clock.limit", skip_serializing_if = "Option::is_none")]
+    clock_limit: Option<Seconds>,
+
+    #[serde(rename = "clock.increment", skip_serializing_if = "Option::is_none")]
+    clock_increment: Option<Seconds>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    days: Option<Days>,
+    color: ChallengeColor,
+    variant: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fen: Option<Fen>
+}
+
+/// A builder for [ChallengeRequest]s, which validates the combination of settings before the
+/// challenge is sent to Lichess. By default, the challenge is a casual, standard game with
+/// unlimited time and a random color.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ChallengeBuilder {
+    rated: bool,
+    clock: Option<(Seconds, Seconds)>,
+    days: Option<Days>,
+    color: ChallengeColor,
+    variant: Variant,
+    fen: Option<Fen>
+}
+
+impl ChallengeBuilder {
+
+    /// Creates a new builder for a casual, standard game with unlimited time and a random color.
+    pub fn new() -> ChallengeBuilder {
+        ChallengeBuilder {
+            rated: false,
+            clock: None,
+            days: None,
+            color: ChallengeColor::Random,
+            variant: Variant::Standard,
+            fen: None
+        }
+    }
+
+    /// Sets whether the game should be rated. The builder is returned for chaining.
+    pub fn with_rated(mut self, rated: bool) -> ChallengeBuilder {
+        self.rated = rated;
+        self
+    }
+
+    /// Sets a real-time clock for the game. The builder is returned for chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: The initial time of each player in seconds.
+    /// * `increment`: The time added to a player's clock after each of their moves in seconds.
+    pub fn with_clock(mut self, limit: Seconds, increment: Seconds) -> ChallengeBuilder {
+        self.clock = Some((limit, increment));
+        self
+    }
+
+    /// Makes the game a correspondence game with the given number of days per turn. The builder
+    /// is returned for chaining.
+    pub fn with_days(mut self, days: Days) -> ChallengeBuilder {
+        self.days = Some(days);
+        self
+    }
+
+    /// Sets the color the bot should play. The builder is returned for chaining.
+    pub fn with_color(mut self, color: ChallengeColor) -> ChallengeBuilder {
+        self.color = color;
+        self
+    }
+
+    /// Sets the variant of the game. The builder is returned for chaining.
+    pub fn with_variant(mut self, variant: Variant) -> ChallengeBuilder {
+        self.variant = variant;
+        self
+    }
+
+    /// Sets a custom initial position for the game. This is only supported for
+    /// [Variant::Standard], [Variant::Chess960], and [Variant::FromPosition], and only for casual
+    /// games. The builder is returned for chaining.
+    pub fn with_fen(mut self, fen: impl Into<Fen>) -> ChallengeBuilder {
+        self.fen = Some(fen.into());
+        self
+    }
+
+    fn validate_clock(limit: Seconds, increment: Seconds) -> ChallengeValidationResult<()> {
+        if !(0..=MAX_CLOCK_LIMIT).contains(&limit) {
+            return Err(ChallengeValidationError::InvalidClockLimit(limit));
+        }
+
+        if !(0..=MAX_CLOCK_INCREMENT).contains(&increment) {
+            return Err(ChallengeValidationError::InvalidClockIncrement(increment));
+        }
+
+        if limit == 0 && increment == 0 {
+            return Err(ChallengeValidationError::ZeroClock);
+        }
+
+        let clock = Clock {
+            limit: Some(limit),
+            increment: Some(increment)
+        };
+
+        if clock.speed() == Speed::UltraBullet {
+            return Err(ChallengeValidationError::UltraBullet {
+                limit,
+                increment
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_position(&self) -> ChallengeValidationResult<()> {
+        match (self.variant, &self.fen) {
+            (Variant::FromPosition, None) => return Err(ChallengeValidationError::MissingFen),
+            (Variant::Standard | Variant::Chess960 | Variant::FromPosition, _) | (_, None) => { },
+            (variant, Some(_)) => return Err(ChallengeValidationError::UnsupportedFen(variant))
+        }
+
+        if self.rated && (self.fen.is_some() || self.variant == Variant::FromPosition) {
+            return Err(ChallengeValidationError::RatedFromPosition);
+        }
+
+        Ok(())
+    }
+
+    /// Validates the settings and builds the [ChallengeRequest].
+    ///
+    /// # Errors
+    ///
+    /// * [ChallengeValidationError::ClockAndDays] if both a clock and days per turn were set.
+    /// * [ChallengeValidationError::InvalidClockLimit],
+    ///   [ChallengeValidationError::InvalidClockIncrement], or
+    ///   [ChallengeValidationError::ZeroClock] if the clock is outside the range Lichess accepts.
+    /// * [ChallengeValidationError::UltraBullet] if the clock is so fast that the game would be
+    ///   UltraBullet, which bots cannot play.
+    /// * [ChallengeValidationError::InvalidDays] if the days per turn are not offered by Lichess.
+    /// * [ChallengeValidationError::UnsupportedFen] if an initial position was set for a variant
+    ///   which does not support it.
+    /// * [ChallengeValidationError::MissingFen] if the variant is [Variant::FromPosition], but no
+    ///   initial position was set.
+    /// * [ChallengeValidationError::RatedFromPosition] if a rated game from a custom initial
+    ///   position was requested.
+    pub fn build(self) -> ChallengeValidationResult<ChallengeRequest> {
+        if self.clock.is_some() && self.days.is_some() {
+            return Err(ChallengeValidationError::ClockAndDays);
+        }
+
+        if let Some((limit, increment)) = self.clock {
+            ChallengeBuilder::validate_clock(limit, increment)?;
+        }
+
+        if let Some(days) = self.days {
+            if !VALID_DAYS.contains(&days) {
+                return Err(ChallengeValidationError::InvalidDays(days));
+            }
+        }
+
+        self.validate_position()?;
+
+        Ok(ChallengeRequest {
+            rated: self.rated,
+            clock_limit: self.clock.map(|(limit, _)| limit),
+            clock_increment: self.clock.map(|(_, increment)| increment),
+            days: self.days,
+            color: self.color,
+            variant: self.variant.key(),
+            fen: self.fen
+        })
+    }
+}
+
+impl Default for ChallengeBuilder {
+    fn default() -> ChallengeBuilder {
+        ChallengeBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use serde_json::json;
+
+    use super::*;
+
+    const TEST_FEN: &str = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+
+    #[rstest]
+    #[case::english(Some("en"), DeclineReason::NoBot, "I'm not accepting challenges from bots.")]
+    #[case::region(Some("de-CH"), DeclineReason::Rated,
+        "Bitte sende mir stattdessen eine gewertete Herausforderung.")]
+    #[case::upper_case(Some("FR"), DeclineReason::Later,
+        "Ce n'est pas le bon moment pour moi, veuillez réessayer plus tard.")]
+    #[case::unsupported(Some("ja"), DeclineReason::OnlyBot,
+        "I'm only accepting challenges from bots.")]
+    #[case::unknown(None, DeclineReason::Generic, "I'm not accepting challenges at the moment.")]
+    fn decline_reason_text_is_localized(#[case] language: Option<&str>,
+            #[case] reason: DeclineReason, #[case] expected: &str) {
+        assert_that!(reason.text(language)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn decline_reason_texts_exist_for_all_reasons() {
+        for language in DECLINE_REASON_LANGUAGES {
+            let texts = DeclineReason::ALL.iter()
+                .map(|reason| reason.text(Some(language)))
+                .collect::<HashSet<_>>();
+
+            assert_that!(texts).has_length(DeclineReason::ALL.len());
+
+            for reason in DeclineReason::ALL {
+                let text = reason.text(Some(language));
+
+                assert_that!(text).is_not_empty();
+
+                if language != "en" {
+                    assert_that!(text).is_not_equal_to(reason.text(None));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn default_challenge_is_casual_standard_unlimited() {
+        let request = ChallengeBuilder::new().build().unwrap();
+        let serialized = serde_json::to_value(&request).unwrap();
+
+        assert_that!(serialized).is_equal_to(json!({
+            "rated": false,
+            "color": "random",
+            "variant": "standard"
+        }));
+    }
+
+    #[test]
+    fn real_time_challenge_serializes_clock() {
+        let request = ChallengeBuilder::new()
+            .with_rated(true)
+            .with_clock(180, 2)
+            .with_color(ChallengeColor::White)
+            .with_variant(Variant::KingOfTheHill)
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_value(&request).unwrap();
+
+        assert_that!(serialized).is_equal_to(json!({
+            "rated": true,
+            "clock.limit": 180,
+            "clock.increment": 2,
+            "color": "white",
+            "variant": "kingOfTheHill"
+        }));
+    }
+
+    #[test]
+    fn casual_challenge_from_position_serializes_fen() {
+        let request = ChallengeBuilder::new()
+            .with_days(3)
+            .with_variant(Variant::FromPosition)
+            .with_fen(TEST_FEN)
+            .build()
+            .unwrap();
+
+        assert_that!(request.days).contains(3);
+        assert_that!(request.fen).contains(TEST_FEN.to_owned());
+    }
+
+    #[rstest]
+    #[case::clock_and_days(
+        ChallengeBuilder::new().with_clock(60, 0).with_days(1),
+        ChallengeValidationError::ClockAndDays)]
+    #[case::negative_limit(
+        ChallengeBuilder::new().with_clock(-1, 5),
+        ChallengeValidationError::InvalidClockLimit(-1))]
+    #[case::too_long_limit(
+        ChallengeBuilder::new().with_clock(10860, 0),
+        ChallengeValidationError::InvalidClockLimit(10860))]
+    #[case::too_large_increment(
+        ChallengeBuilder::new().with_clock(60, 61),
+        ChallengeValidationError::InvalidClockIncrement(61))]
+    #[case::zero_clock(
+        ChallengeBuilder::new().with_clock(0, 0),
+        ChallengeValidationError::ZeroClock)]
+    #[case::ultra_bullet(
+        ChallengeBuilder::new().with_clock(15, 0),
+        ChallengeValidationError::UltraBullet { limit: 15, increment: 0 })]
+    #[case::invalid_days(
+        ChallengeBuilder::new().with_days(4),
+        ChallengeValidationError::InvalidDays(4))]
+    #[case::rated_with_fen(
+        ChallengeBuilder::new().with_rated(true).with_fen(TEST_FEN),
+        ChallengeValidationError::RatedFromPosition)]
+    #[case::fen_in_unsupported_variant(
+        ChallengeBuilder::new().with_variant(Variant::Atomic).with_fen(TEST_FEN),
+        ChallengeValidationError::UnsupportedFen(Variant::Atomic))]
+    #[case::from_position_without_fen(
+        ChallengeBuilder::new().with_variant(Variant::FromPosition),
+        ChallengeValidationError::MissingFen)]
+    fn invalid_challenge_is_rejected(
+        #[case] builder: ChallengeBuilder, #[case] expected_error: ChallengeValidationError) {
+        assert_that!(builder.build()).contains_error(expected_error);
+    }
+
+    #[rstest]
+    #[case::bullet(30, 0)]
+    #[case::bullet_with_increment(0, 1)]
+    #[case::classical(10800, 60)]
+    fn boundary_clocks_are_accepted(#[case] limit: Seconds, #[case] increment: Seconds) {
+        let result = ChallengeBuilder::new().with_clock(limit, increment).build();
+
+        assert_that!(result).is_ok();
+    }
+
+    #[rstest]
+    #[case::key_and_text(
+        r#""declineReason": "Not right now.", "declineReasonKey": "later","#,
+        Some(DeclineInfo {
+            key: DeclineReason::Later,
+            localized_text: Some("Not right now.".to_owned())
+        }))]
+    #[case::key_only(
+        r#""declineReasonKey": "tooFast","#,
+        Some(DeclineInfo {
+            key: DeclineReason::TooFast,
+            localized_text: None
+        }))]
+    #[case::not_declined("", None)]
+    fn decline_info_is_deserialized(#[case] decline_fields: &str,
+            #[case] expected: Option<DeclineInfo>) {
+        let json = format!(r#"{{
+            "id": "testId",
+            "url": "testUrl",
+            "status": "declined",
+            "challenger": {{
+                "id": "testChallengerId",
+                "name": "testChallengerName"
+            }},
+            "variant": {{ }},
+            "rated": false,
+            "speed": "correspondence",
+            "timeControl": {{
+                "type": "unlimited"
+            }},
+            "color": "random",
+            "perf": {{ }},
+            {decline_fields}
+            "direction": "out"
+        }}"#);
+
+        let challenge = serde_json::from_str::<Challenge>(&json).unwrap();
+
+        assert_that!(challenge.decline).is_equal_to(expected);
+    }
+}
@@ -0,0 +1,58 @@
+use serde::Deserialize;
+
+use crate::model::Moves;
+
+/// A principal variation of a [CloudEvaluation].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct CloudEvaluationLine {
+
+    /// The moves of the variation in UCI notation, separated by spaces.
+    pub moves: Moves,
+
+    /// The evaluation in centipawns from White's perspective, if no mate was found.
+    pub cp: Option<i32>,
+
+    /// The number of moves until mate, positive if White mates, if a mate was found.
+    pub mate: Option<i32>
+}
+
+/// A cached evaluation of a position from the Lichess cloud evaluation database.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct CloudEvaluation {
+    pub fen: String,
+
+    /// The number of nodes searched in thousands.
+    pub knodes: u64,
+    pub depth: u32,
+
+    /// The principal variations, best first.
+    pub pvs: Vec<CloudEvaluationLine>
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_cloud_evaluation() {
+        let json = r#"{
+            "fen": "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "knodes": 1028,
+            "depth": 36,
+            "pvs": [
+                { "moves": "c7c5 g1f3", "cp": 26 },
+                { "moves": "e7e5 g1f3", "mate": 12 }
+            ]
+        }"#;
+
+        let evaluation = serde_json::from_str::<CloudEvaluation>(json).unwrap();
+
+        assert_that!(evaluation.depth).is_equal_to(36);
+        assert_that!(evaluation.pvs[0].cp).contains(26);
+        assert_that!(evaluation.pvs[1].mate).contains(12);
+        assert_that!(evaluation.pvs[1].cp).is_none();
+    }
+}
@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::Move;
+use crate::model::user::UserId;
+
+pub type ExternalEngineId = String;
+
+/// A chess variant as named by the UCI `UCI_Variant` option, used by the external engine API.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UciVariant {
+    Chess,
+    Crazyhouse,
+    Antichess,
+    Atomic,
+    Horde,
+    KingOfTheHill,
+    RacingKings,
+
+    #[serde(rename = "3check")]
+    ThreeCheck
+}
+
+/// An external engine registered with Lichess.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalEngine {
+    pub id: ExternalEngineId,
+    pub name: String,
+
+    /// The secret with which clients request analysis from this engine.
+    pub client_secret: String,
+    pub user_id: UserId,
+    pub max_threads: u32,
+
+    /// The maximum hash table size of the engine in MiB.
+    pub max_hash: u32,
+
+    #[serde(default)]
+    pub variants: Vec<UciVariant>,
+
+    /// Arbitrary data provided when registering the engine, if any.
+    pub provider_data: Option<String>
+}
+
+/// The data with which an external engine is registered or updated.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalEngineRegistration {
+    pub name: String,
+    pub max_threads: u32,
+
+    /// The maximum hash table size of the engine in MiB.
+    pub max_hash: u32,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<UciVariant>,
+
+    /// The secret with which the provider acquires analysis requests for the engine. Should be
+    /// random and at least 16 characters long.
+    pub provider_secret: String,
+
+    /// Arbitrary data which is returned with the engine, e.g. to identify it on the provider side.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_data: Option<String>
+}
+
+/// A unit of analysis work for an external engine, i.e. a position to analyse with certain
+/// resource limits.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalEngineWork {
+
+    /// An arbitrary identifier of the analysis session. Requests with a new session ID stop any
+    /// running analysis of the previous session.
+    pub session_id: String,
+    pub threads: u32,
+
+    /// The hash table size to use in MiB.
+    pub hash: u32,
+
+    /// If `true`, the engine analyses until the request is stopped rather than until a default
+    /// depth is reached.
+    #[serde(default)]
+    pub infinite: bool,
+    pub multi_pv: u32,
+    pub variant: UciVariant,
+    pub initial_fen: String,
+
+    /// The moves played from the initial position in UCI notation.
+    #[serde(default)]
+    pub moves: Vec<Move>
+}
+
+/// A principal variation reported by an external engine.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct AnalysisLine {
+    pub depth: u32,
+
+    /// The evaluation in centipawns from the perspective of the side to move, if no mate was
+    /// found.
+    pub cp: Option<i32>,
+
+    /// The number of moves until mate from the perspective of the side to move, if a mate was
+    /// found.
+    pub mate: Option<i32>,
+
+    /// The moves of the variation in UCI notation.
+    pub moves: Vec<Move>
+}
+
+/// One update of a running analysis by an external engine.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct AnalysisUpdate {
+
+    /// The time spent on the analysis so far in milliseconds.
+    pub time: u64,
+    pub depth: u32,
+    pub nodes: u64,
+
+    /// The principal variations, best first.
+    pub pvs: Vec<AnalysisLine>
+}
+
+/// An analysis request acquired by an engine provider, which it should answer by running the
+/// engine on the given work.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct EngineWorkRequest {
+
+    /// The ID with which the engine output is submitted.
+    pub id: String,
+    pub work: ExternalEngineWork,
+    pub engine: ExternalEngine
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AnalyseRequest<'a> {
+    pub(crate) client_secret: &'a str,
+    pub(crate) work: &'a ExternalEngineWork
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AcquireWorkRequest<'a> {
+    pub(crate) provider_secret: &'a str
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[rstest]
+    #[case::chess(UciVariant::Chess, "\"chess\"")]
+    #[case::king_of_the_hill(UciVariant::KingOfTheHill, "\"kingofthehill\"")]
+    #[case::racing_kings(UciVariant::RacingKings, "\"racingkings\"")]
+    #[case::three_check(UciVariant::ThreeCheck, "\"3check\"")]
+    fn uci_variant_round_trip(#[case] variant: UciVariant, #[case] expected_json: &str) {
+        let serialized = serde_json::to_string(&variant).unwrap();
+        let deserialized = serde_json::from_str::<UciVariant>(&serialized).unwrap();
+
+        assert_that!(serialized.as_str()).is_equal_to(expected_json);
+        assert_that!(deserialized).is_equal_to(variant);
+    }
+
+    #[test]
+    fn parse_external_engine() {
+        let json = r#"{
+            "id": "eei_aTKImBJOnv6j",
+            "name": "Stockfish 15",
+            "clientSecret": "ees_mdF2hK0hlKGSPeC6",
+            "userId": "thibault",
+            "maxThreads": 8,
+            "maxHash": 2048,
+            "defaultDepth": 24,
+            "variants": ["chess", "3check"],
+            "providerData": null
+        }"#;
+
+        let engine = serde_json::from_str::<ExternalEngine>(json).unwrap();
+
+        assert_that!(engine.client_secret.as_str()).is_equal_to("ees_mdF2hK0hlKGSPeC6");
+        assert_that!(engine.max_hash).is_equal_to(2048);
+        assert_that!(engine.variants)
+            .contains_exactly_in_given_order([UciVariant::Chess, UciVariant::ThreeCheck]);
+        assert_that!(engine.provider_data).is_none();
+    }
+
+    #[test]
+    fn serialize_registration_omits_optional_fields() {
+        let registration = ExternalEngineRegistration {
+            name: "Engine".to_owned(),
+            max_threads: 4,
+            max_hash: 512,
+            variants: Vec::new(),
+            provider_secret: "testProviderSecret".to_owned(),
+            provider_data: None
+        };
+
+        let serialized = serde_json::to_value(&registration).unwrap();
+
+        assert_that!(serialized).is_equal_to(json!({
+            "name": "Engine",
+            "maxThreads": 4,
+            "maxHash": 512,
+            "providerSecret": "testProviderSecret"
+        }));
+    }
+
+    #[test]
+    fn parse_analysis_update() {
+        let json = r#"{
+            "time": 1500,
+            "depth": 20,
+            "nodes": 123456,
+            "pvs": [
+                { "depth": 20, "cp": 35, "moves": ["e2e4", "e7e5"] },
+                { "depth": 19, "mate": -3, "moves": ["f2f3"] }
+            ]
+        }"#;
+
+        let update = serde_json::from_str::<AnalysisUpdate>(json).unwrap();
+
+        assert_that!(update.pvs.len()).is_equal_to(2);
+        assert_that!(update.pvs[0].cp).contains(35);
+        assert_that!(update.pvs[1].mate).contains(-3);
+        assert_that!(update.pvs[1].cp).is_none();
+    }
+}
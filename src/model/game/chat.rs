@@ -1,16 +1,165 @@
+use std::ops::Deref;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+/// A chat room of a game. The chats of arena tournaments are not accessible via the Lichess API,
+/// so bots playing in a tournament can only greet their opponents in the chats of the tournament
+/// games. Teams can be messaged with
+/// [BotClient::send_team_message](crate::client::BotClient::send_team_message).
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum ChatRoom {
+    #[default]
     Player,
     Spectator
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct ChatLine {
+
+    /// The [ChatRoom] in which this line was posted. Lines without a room, such as those in the
+    /// chat history provided by Lichess, are attributed to the [ChatRoom::Player] room.
+    #[serde(default)]
+    pub room: ChatRoom,
+
     pub username: String,
     pub text: String
 }
 
-pub type ChatHistory = Vec<ChatLine>;
+/// Marks a point in the [ChatHistory] of a game, so that only the lines posted after it can be
+/// retrieved later. Obtained from [ChatHistory::marker].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChatMarker(usize);
+
+/// The chat lines of a game in the order in which they were posted. Dereferences to a slice of
+/// all [ChatLine]s regardless of their room.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ChatHistory {
+    lines: Vec<ChatLine>
+}
+
+impl ChatHistory {
+
+    /// Gets an iterator over all lines in this history which were posted in the given room.
+    pub fn room_lines(&self, room: ChatRoom) -> impl Iterator<Item = &ChatLine> + '_ {
+        self.lines.iter().filter(move |line| line.room == room)
+    }
+
+    /// Gets an iterator over all lines in this history which were posted in the player room.
+    pub fn player_lines(&self) -> impl Iterator<Item = &ChatLine> + '_ {
+        self.room_lines(ChatRoom::Player)
+    }
+
+    /// Gets an iterator over all lines in this history which were posted in the spectator room.
+    pub fn spectator_lines(&self) -> impl Iterator<Item = &ChatLine> + '_ {
+        self.room_lines(ChatRoom::Spectator)
+    }
+
+    /// Gets a [ChatMarker] pointing to the end of this history, i.e. after its latest line.
+    pub fn marker(&self) -> ChatMarker {
+        ChatMarker(self.lines.len())
+    }
+
+    /// Gets all lines in this history which were posted after the given marker was obtained. If
+    /// the marker was obtained from a longer history, no lines are returned.
+    pub fn lines_since(&self, marker: ChatMarker) -> &[ChatLine] {
+        &self.lines[marker.0.min(self.lines.len())..]
+    }
+
+    /// Consumes this history and returns its lines.
+    pub fn into_lines(self) -> Vec<ChatLine> {
+        self.lines
+    }
+}
+
+impl Deref for ChatHistory {
+
+    type Target = [ChatLine];
+
+    fn deref(&self) -> &[ChatLine] {
+        &self.lines
+    }
+}
+
+impl From<Vec<ChatLine>> for ChatHistory {
+    fn from(lines: Vec<ChatLine>) -> ChatHistory {
+        ChatHistory { lines }
+    }
+}
+
+/// The lines posted in a game chat since a [ChatMarker], as returned by
+/// [BotClient::get_new_chat_since](crate::client::BotClient::get_new_chat_since).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct NewChatLines {
+
+    /// The lines posted since the marker, in the order in which they were posted.
+    pub lines: Vec<ChatLine>,
+
+    /// A marker after the latest line, to be passed to the next call.
+    pub marker: ChatMarker
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn line(room: ChatRoom, text: &str) -> ChatLine {
+        ChatLine {
+            room,
+            username: "testUsername".to_owned(),
+            text: text.to_owned()
+        }
+    }
+
+    fn history() -> ChatHistory {
+        ChatHistory::from(vec![
+            line(ChatRoom::Player, "testText1"),
+            line(ChatRoom::Spectator, "testText2"),
+            line(ChatRoom::Player, "testText3")
+        ])
+    }
+
+    #[test]
+    fn filters_lines_by_room() {
+        let history = history();
+
+        assert_that!(history.player_lines().cloned().collect::<Vec<_>>())
+            .contains_exactly_in_given_order([
+                line(ChatRoom::Player, "testText1"),
+                line(ChatRoom::Player, "testText3")
+            ]);
+        assert_that!(history.spectator_lines().cloned().collect::<Vec<_>>())
+            .contains_exactly_in_given_order([line(ChatRoom::Spectator, "testText2")]);
+    }
+
+    #[test]
+    fn lines_since_returns_lines_after_marker() {
+        let old_history = ChatHistory::from(history()[..1].to_vec());
+        let marker = old_history.marker();
+
+        assert_that!(history().lines_since(marker)).contains_exactly_in_given_order([
+            line(ChatRoom::Spectator, "testText2"),
+            line(ChatRoom::Player, "testText3")
+        ]);
+        assert_that!(history().lines_since(history().marker())).is_empty();
+    }
+
+    #[test]
+    fn lines_since_marker_from_longer_history_is_empty() {
+        let marker = history().marker();
+
+        assert_that!(ChatHistory::default().lines_since(marker)).is_empty();
+    }
+
+    #[test]
+    fn line_without_room_is_attributed_to_player_room() {
+        let line = serde_json::from_str::<ChatLine>(
+            r#"{ "username": "testUsername", "text": "testText" }"#).unwrap();
+
+        assert_that!(line.room).is_equal_to(ChatRoom::Player);
+    }
+}
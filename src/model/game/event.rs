@@ -2,13 +2,15 @@ use serde::Deserialize;
 
 use crate::model::{Milliseconds, Seconds};
 use crate::model::game::{Color, GameInfo, GameStatus};
-use crate::model::game::chat::{ChatLine, ChatRoom};
+use crate::model::game::chat::ChatLine;
 use crate::model::user::{AiLevel, Rating, Title, UserId};
 
+/// A player as reported in game events. Which fields are present depends on whether the player is
+/// a Lichess user, the Lichess AI or anonymous. To distinguish these cases, convert it to a
+/// [PlayerRef](crate::model::user::PlayerRef), e.g. with [GameInfo::player_ref].
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GameEventPlayer {
-    // TODO really everything optional?
     pub ai_level: Option<AiLevel>,
     pub id: Option<UserId>,
     pub name: Option<String>,
@@ -57,7 +59,14 @@ pub struct GameStateEvent {
 
     /// True if and only if Black is proposing a take-back.
     #[serde(rename = "btakeback", default)]
-    pub black_take_back_proposal: bool
+    pub black_take_back_proposal: bool,
+
+    /// The number of game states directly preceding this one which were not delivered to the bot,
+    /// because they were replaced by this one while the bot was busy handling earlier events. See
+    /// [BackpressureConfig](crate::runner::backpressure::BackpressureConfig). This is not sent by
+    /// Lichess and always 0 unless game states are coalesced.
+    #[serde(skip)]
+    pub skipped_states: usize
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
@@ -70,8 +79,9 @@ pub struct GameFullEvent {
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
 pub struct ChatLineEvent {
-    pub room: ChatRoom,
 
+    /// The posted line, including the [ChatRoom](crate::model::game::chat::ChatRoom) in which it
+    /// was posted.
     #[serde(flatten)]
     pub chat_line: ChatLine
 }
@@ -128,6 +138,7 @@ mod tests {
     use rstest::rstest;
 
     use crate::model::game::{Clock, GamePerf, Speed, Variant};
+    use crate::model::game::chat::ChatRoom;
 
     use super::*;
 
@@ -154,7 +165,8 @@ mod tests {
             white_draw_offer: false,
             black_draw_offer: false,
             white_take_back_proposal: false,
-            black_take_back_proposal: false
+            black_take_back_proposal: false,
+            skipped_states: 0
         }
     }
 
@@ -196,6 +208,8 @@ mod tests {
                 white: empty_game_event_player(),
                 black: empty_game_event_player(),
                 initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: None,
+                opening: None,
                 tournament_id: None,
             },
             state: minimal_game_state_event()
@@ -242,6 +256,8 @@ mod tests {
                 white: empty_game_event_player(),
                 black: empty_game_event_player(),
                 initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: None,
+                opening: None,
                 tournament_id: None,
             },
             state: minimal_game_state_event()
@@ -287,6 +303,8 @@ mod tests {
                 white: empty_game_event_player(),
                 black: empty_game_event_player(),
                 initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: None,
+                opening: None,
                 tournament_id: None,
             },
             state: minimal_game_state_event()
@@ -335,6 +353,8 @@ mod tests {
                 white: empty_game_event_player(),
                 black: empty_game_event_player(),
                 initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: None,
+                opening: None,
                 tournament_id: None
             },
             state: minimal_game_state_event()
@@ -379,6 +399,8 @@ mod tests {
                 white: empty_game_event_player(),
                 black: empty_game_event_player(),
                 initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: None,
+                opening: None,
                 tournament_id: None
             },
             state: minimal_game_state_event(),
@@ -448,6 +470,8 @@ mod tests {
                     provisional: Some(false)
                 },
                 initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: None,
+                opening: None,
                 tournament_id: None
             },
             state: minimal_game_state_event()
@@ -491,11 +515,58 @@ mod tests {
                 white: empty_game_event_player(),
                 black: empty_game_event_player(),
                 initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: None,
+                opening: None,
                 tournament_id: Some("testTournamentId".to_owned())
             },
             state: minimal_game_state_event()
         })
     )]
+    #[case::game_full_with_days_per_turn(
+        r#"{
+            "type": "gameFull",
+            "id": "testId",
+            "variant": { },
+            "clock": null,
+            "daysPerTurn": 3,
+            "speed": "correspondence",
+            "perf": { },
+            "rated": false,
+            "createdAt": 1234,
+            "white": { },
+            "black": { },
+            "initialFen": "testInitialFen",
+            "state": {
+                "type": "gameState",
+                "moves": "testMoves",
+                "wtime": 100,
+                "btime": 200,
+                "winc": 1,
+                "binc": 2,
+                "status": "created"
+            }
+        }"#,
+        GameEvent::GameFull(GameFullEvent {
+            info: GameInfo {
+                id: "testId".to_owned(),
+                variant: None,
+                clock: None,
+                speed: Speed::Correspondence,
+                perf: GamePerf {
+                    name: None
+                },
+                rated: false,
+                created_at: 1234,
+                white: empty_game_event_player(),
+                black: empty_game_event_player(),
+                initial_fen: "testInitialFen".to_owned(),
+                days_per_turn: Some(3),
+                opening: None,
+                tournament_id: None
+            },
+            state: minimal_game_state_event()
+        })
+    )]
     #[case::minimal_game_state(
         r#"{
             "type": "gameState",
@@ -517,7 +588,8 @@ mod tests {
             white_draw_offer: false,
             black_draw_offer: false,
             white_take_back_proposal: false,
-            black_take_back_proposal: false
+            black_take_back_proposal: false,
+            skipped_states: 0
         })
     )]
     #[case::game_state_with_winner(
@@ -542,7 +614,8 @@ mod tests {
             white_draw_offer: false,
             black_draw_offer: false,
             white_take_back_proposal: false,
-            black_take_back_proposal: false
+            black_take_back_proposal: false,
+            skipped_states: 0
         })
     )]
     #[case::game_state_with_draw_offers(
@@ -568,7 +641,8 @@ mod tests {
             white_draw_offer: true,
             black_draw_offer: true,
             white_take_back_proposal: false,
-            black_take_back_proposal: false
+            black_take_back_proposal: false,
+            skipped_states: 0
         })
     )]
     #[case::game_state_with_draw_takebacks(
@@ -594,7 +668,8 @@ mod tests {
             white_draw_offer: false,
             black_draw_offer: false,
             white_take_back_proposal: true,
-            black_take_back_proposal: true
+            black_take_back_proposal: true,
+            skipped_states: 0
         })
     )]
     #[case::chat_line(
@@ -605,8 +680,8 @@ mod tests {
             "text": "testText"
         }"#,
         GameEvent::ChatLine(ChatLineEvent {
-            room: ChatRoom::Spectator,
             chat_line: ChatLine {
+                room: ChatRoom::Spectator,
                 username: "testUsername".to_owned(),
                 text: "testText".to_owned()
             }
@@ -0,0 +1,201 @@
+use serde::Deserialize;
+
+use crate::model::{Moves, Timestamp};
+use crate::model::game::{Color, GameId, GameStatus, Speed};
+use crate::model::game::opening::Opening;
+use crate::model::user::{AiLevel, Rating, Title, UserId};
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct ExportedUser {
+    pub id: UserId,
+    pub name: String,
+    pub title: Option<Title>
+}
+
+/// The summary of the server analysis of a game for one player, as included in exported games
+/// once Lichess analysed the game.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct ExportedPlayerAnalysis {
+    pub inaccuracy: u32,
+    pub mistake: u32,
+    pub blunder: u32,
+
+    /// The average centipawn loss of the player's moves.
+    pub acpl: u32,
+
+    /// The accuracy of the player's moves in percent, if requested.
+    pub accuracy: Option<u32>
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedPlayer {
+    pub user: Option<ExportedUser>,
+    pub rating: Option<Rating>,
+
+    /// The rating change of the player as a result of the game, if the game was rated and the
+    /// change is already known.
+    pub rating_diff: Option<Rating>,
+    pub ai_level: Option<AiLevel>,
+
+    /// The summary of the server analysis for this player, if the game was analysed and the
+    /// analysis was requested.
+    pub analysis: Option<ExportedPlayerAnalysis>
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct ExportedPlayers {
+    pub white: ExportedPlayer,
+    pub black: ExportedPlayer
+}
+
+impl ExportedPlayers {
+
+    /// Gets the player of the given color.
+    pub fn of(&self, color: Color) -> &ExportedPlayer {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black
+        }
+    }
+}
+
+/// A game as returned by the game export endpoints.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedGame {
+    pub id: GameId,
+    pub rated: bool,
+    pub speed: Speed,
+    pub created_at: Timestamp,
+    pub status: GameStatus,
+    pub players: ExportedPlayers,
+
+    /// Color of the winner, if any.
+    pub winner: Option<Color>,
+
+    /// The opening of the game, if requested and the game started from the standard position.
+    pub opening: Option<Opening>,
+
+    /// The moves of the game in SAN, separated by spaces.
+    #[serde(default)]
+    pub moves: Moves
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_exported_game() {
+        let json = r#"{
+            "id": "q7ZvsdUF",
+            "rated": true,
+            "variant": "standard",
+            "speed": "blitz",
+            "perf": "blitz",
+            "createdAt": 1514505150384,
+            "lastMoveAt": 1514505592843,
+            "status": "resign",
+            "players": {
+                "white": {
+                    "user": {
+                        "name": "Lance5500",
+                        "title": "LM",
+                        "id": "lance5500"
+                    },
+                    "rating": 2389,
+                    "ratingDiff": 4
+                },
+                "black": {
+                    "user": {
+                        "name": "TryingHard87",
+                        "id": "tryinghard87"
+                    },
+                    "rating": 2498,
+                    "ratingDiff": -4
+                }
+            },
+            "winner": "white",
+            "moves": "d4 d5 c4 c6"
+        }"#;
+
+        let game = serde_json::from_str::<ExportedGame>(json).unwrap();
+
+        assert_that!(game.status).is_equal_to(GameStatus::Resign);
+        assert_that!(game.winner).contains(Color::White);
+        assert_that!(game.players.of(Color::White).rating_diff).contains(4);
+        assert_that!(game.players.of(Color::Black).rating_diff).contains(-4);
+        assert_that!(game.players.of(Color::White).user.as_ref().unwrap().title.clone())
+            .contains(Title::Lm);
+        assert_that!(game.moves).is_equal_to("d4 d5 c4 c6".to_owned());
+    }
+
+    #[test]
+    fn parse_exported_game_against_ai() {
+        let json = r#"{
+            "id": "abcdefgh",
+            "rated": false,
+            "speed": "correspondence",
+            "createdAt": 1514505150384,
+            "status": "started",
+            "players": {
+                "white": {
+                    "aiLevel": 3
+                },
+                "black": {
+                    "user": {
+                        "name": "Bot",
+                        "id": "bot"
+                    },
+                    "rating": 1500
+                }
+            }
+        }"#;
+
+        let game = serde_json::from_str::<ExportedGame>(json).unwrap();
+
+        assert_that!(game.players.white.ai_level).contains(3);
+        assert_that!(game.players.white.user).is_none();
+        assert_that!(game.players.black.rating_diff).is_none();
+        assert_that!(game.winner).is_none();
+        assert_that!(game.moves).is_empty();
+    }
+
+    #[test]
+    fn parse_exported_game_with_analysis() {
+        let json = r#"{
+            "id": "abcdefgh",
+            "rated": true,
+            "speed": "blitz",
+            "createdAt": 1514505150384,
+            "status": "mate",
+            "players": {
+                "white": {
+                    "user": { "name": "Bot", "id": "bot" },
+                    "analysis": { "inaccuracy": 1, "mistake": 0, "blunder": 2, "acpl": 45,
+                        "accuracy": 81 }
+                },
+                "black": {
+                    "user": { "name": "Opponent", "id": "opponent" },
+                    "analysis": { "inaccuracy": 3, "mistake": 1, "blunder": 0, "acpl": 30 }
+                }
+            }
+        }"#;
+
+        let game = serde_json::from_str::<ExportedGame>(json).unwrap();
+
+        assert_that!(game.players.white.analysis).contains(ExportedPlayerAnalysis {
+            inaccuracy: 1,
+            mistake: 0,
+            blunder: 2,
+            acpl: 45,
+            accuracy: Some(81)
+        });
+        assert_that!(game.players.black.analysis.and_then(|analysis| analysis.accuracy))
+            .is_none();
+    }
+}
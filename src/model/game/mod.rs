@@ -1,18 +1,42 @@
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde::de::Error as DeserializeError;
 
 use thiserror::Error;
 
-use crate::model::{Seconds, Timestamp};
+use crate::chess::ChessResult;
+use crate::chess::position::{HORDE_FEN, Position, RACING_KINGS_FEN, STANDARD_FEN};
+use crate::chess::uci;
+use crate::model::{Days, Milliseconds, Seconds, Timestamp, Url};
 use crate::model::game::event::GameEventPlayer;
+use crate::model::game::opening::Opening;
+use crate::model::user::PlayerRef;
 
 pub mod chat;
 pub mod event;
+pub mod export;
+pub mod ongoing;
+pub mod opening;
+pub mod result;
 
 pub type GameId = String;
 pub type TournamentId = String;
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+/// The URL of the Lichess website, under which games can be viewed and analyzed.
+pub const SITE_URL: &str = "https://lichess.org";
+
+/// The URL of the Lichess server which renders games as animated GIFs.
+pub const GIF_URL: &str = "https://lichess1.org";
+
+/// Gets the path of the GIF of the game with the given ID relative to [GIF_URL], rendered from the
+/// perspective of the given color, or White if none is given.
+pub(crate) fn gif_path(game_id: &str, color: Option<Color>) -> String {
+    match color {
+        Some(color) => format!("/game/export/gif/{}/{game_id}.gif", color.key()),
+        None => format!("/game/export/gif/{game_id}.gif")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum GameStatus {
     Created,
@@ -151,6 +175,8 @@ pub struct GameInfo {
     // TODO really optional?
     #[serde(deserialize_with = "deserialize_optional_variant")]
     pub variant: Option<Variant>,
+
+    #[serde(default, deserialize_with = "deserialize_game_clock")]
     pub clock: Option<Clock>,
     pub speed: Speed,
     pub perf: GamePerf,
@@ -159,20 +185,163 @@ pub struct GameInfo {
     pub white: GameEventPlayer,
     pub black: GameEventPlayer,
     pub initial_fen: Fen,
+
+    /// The number of days per move in correspondence games.
+    #[serde(default)]
+    pub days_per_turn: Option<Days>,
+
+    /// The opening of the game as classified by Lichess, if included in the game data. See
+    /// [GameContext::opening](crate::context::GameContext::opening) for the opening of the current
+    /// position.
+    #[serde(default)]
+    pub opening: Option<Opening>,
     pub tournament_id: Option<TournamentId>
 }
 
+impl GameInfo {
+
+    /// Gets the player of the given color.
+    pub fn player(&self, color: Color) -> &GameEventPlayer {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black
+        }
+    }
+
+    /// Gets the player of the given color as a [PlayerRef].
+    pub fn player_ref(&self, color: Color) -> PlayerRef {
+        PlayerRef::from(self.player(color))
+    }
+
+    /// Gets the [Color] as which the Lichess user with the given ID plays in this game, or [None]
+    /// if the user is not a participant.
+    pub fn color_of(&self, user_id: &str) -> Option<Color> {
+        [Color::White, Color::Black].into_iter()
+            .find(|&color| self.player_ref(color).is_user(user_id))
+    }
+
+    /// Indicates whether this game is rated.
+    pub fn is_rated(&self) -> bool {
+        self.rated
+    }
+
+    /// The initial time of each player in seconds, or [None] if this game has no real-time clock.
+    pub fn clock_initial(&self) -> Option<Seconds> {
+        self.clock?.limit
+    }
+
+    /// The variant of this game, where an unknown variant is assumed to be [Variant::Standard].
+    pub fn variant_or_standard(&self) -> Variant {
+        self.variant.unwrap_or(Variant::Standard)
+    }
+
+    /// The URL under which this game can be viewed on Lichess.
+    pub fn url(&self) -> Url {
+        format!("{SITE_URL}/{}", self.id)
+    }
+
+    /// The URL under which this game can be viewed on Lichess with the board oriented towards the
+    /// given color.
+    pub fn url_for(&self, color: Color) -> Url {
+        format!("{SITE_URL}/{}/{}", self.id, color.key())
+    }
+
+    /// The URL of an animated GIF of this game, rendered from the perspective of the given color,
+    /// or White if none is given. The GIF can also be downloaded with
+    /// [BotClient::get_game_gif](crate::client::BotClient::get_game_gif).
+    pub fn gif_url(&self, color: Option<Color>) -> Url {
+        format!("{GIF_URL}{}", gif_path(&self.id, color))
+    }
+
+    /// The URL of the Lichess analysis board for this game after the given moves. For standard
+    /// games from the initial position, the moves are preloaded, so they can be stepped through.
+    /// Lichess cannot preload moves of other games from a URL, so the analysis board starts at the
+    /// position after the given moves instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `moves`: The moves played so far in UCI notation, separated by spaces.
+    ///
+    /// # Errors
+    ///
+    /// Any [ChessError](crate::chess::ChessError) that occurs while constructing the initial
+    /// position of this game or playing the given moves.
+    pub fn analysis_url(&self, moves: &str) -> ChessResult<Url> {
+        let mut position = Position::from_game_info(self)?;
+        let is_standard = self.initial_fen == "startpos"
+            && matches!(self.variant, None | Some(Variant::Standard));
+
+        if !is_standard {
+            position.play_uci_moves(moves)?;
+            let fen = position.to_fen().replace(' ', "_");
+
+            return Ok(format!("{SITE_URL}/analysis/{}/{fen}", position.variant().key()));
+        }
+
+        let mut sans = Vec::new();
+
+        for mov in uci::parse_uci_moves(moves)? {
+            sans.push(position.to_san(&mov)?.replace('#', "%23"));
+            position.play(&mov)?;
+        }
+
+        if sans.is_empty() {
+            Ok(format!("{SITE_URL}/analysis"))
+        }
+        else {
+            Ok(format!("{SITE_URL}/analysis/pgn/{}", sans.join("_")))
+        }
+    }
+}
+
 // TODO avoid expensive clone with IDs?
 pub type Fen = String;
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Color {
     White,
     Black
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+impl Color {
+
+    /// The key which identifies this color in the Lichess API and URLs, i.e. `"white"` or
+    /// `"black"`.
+    pub fn key(self) -> &'static str {
+        match self {
+            Color::White => "white",
+            Color::Black => "black"
+        }
+    }
+
+    /// The other color, i.e. [Color::Black] for [Color::White] and vice versa.
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White
+        }
+    }
+}
+
+/// Metadata of a [Variant] as displayed by Lichess, obtained with [Variant::info]. Lichess sends
+/// the name and abbreviation along with the key of a variant, but they are not kept when parsing
+/// a [Variant], since they are fully determined by it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct VariantInfo {
+
+    /// The display name of the variant, e.g. `"King of the Hill"`.
+    pub name: &'static str,
+
+    /// The abbreviation of the variant, e.g. `"KotH"`.
+    pub short: &'static str,
+
+    /// The FEN of the starting position of the variant, or [None] for [Variant::Chess960] and
+    /// [Variant::FromPosition], whose starting position is not fixed.
+    pub initial_fen: Option<&'static str>
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(tag = "key", rename_all = "camelCase")]
 pub enum Variant {
     Standard,
@@ -187,6 +356,85 @@ pub enum Variant {
     FromPosition
 }
 
+impl Variant {
+
+    /// All variants supported by Lichess.
+    pub const ALL: [Variant; 10] = [
+        Variant::Standard,
+        Variant::Chess960,
+        Variant::Crazyhouse,
+        Variant::Antichess,
+        Variant::Atomic,
+        Variant::Horde,
+        Variant::KingOfTheHill,
+        Variant::RacingKings,
+        Variant::ThreeCheck,
+        Variant::FromPosition
+    ];
+
+    /// Gets the variant identified by the given key in the Lichess API (see [Variant::key]), or
+    /// [None] if there is no such variant.
+    pub fn from_key(key: &str) -> Option<Variant> {
+        Variant::ALL.into_iter().find(|variant| variant.key() == key)
+    }
+
+    /// The key which identifies this variant in the Lichess API, e.g. `"kingOfTheHill"` for
+    /// [Variant::KingOfTheHill].
+    pub fn key(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Chess960 => "chess960",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Antichess => "antichess",
+            Variant::Atomic => "atomic",
+            Variant::Horde => "horde",
+            Variant::KingOfTheHill => "kingOfTheHill",
+            Variant::RacingKings => "racingKings",
+            Variant::ThreeCheck => "threeCheck",
+            Variant::FromPosition => "fromPosition"
+        }
+    }
+
+    /// The [VariantInfo] of this variant, containing its display name, abbreviation and starting
+    /// position.
+    pub fn info(self) -> VariantInfo {
+        let (name, short, initial_fen) = match self {
+            Variant::Standard => ("Standard", "Std", Some(STANDARD_FEN)),
+            Variant::Chess960 => ("Chess960", "960", None),
+            Variant::Crazyhouse => ("Crazyhouse", "Crazy", Some(STANDARD_FEN)),
+            Variant::Antichess => ("Antichess", "Anti", Some(STANDARD_FEN)),
+            Variant::Atomic => ("Atomic", "Atom", Some(STANDARD_FEN)),
+            Variant::Horde => ("Horde", "Horde", Some(HORDE_FEN)),
+            Variant::KingOfTheHill => ("King of the Hill", "KotH", Some(STANDARD_FEN)),
+            Variant::RacingKings => ("Racing Kings", "Race", Some(RACING_KINGS_FEN)),
+            Variant::ThreeCheck => ("Three-check", "3check", Some(STANDARD_FEN)),
+            Variant::FromPosition => ("From Position", "FEN", None)
+        };
+
+        VariantInfo {
+            name,
+            short,
+            initial_fen
+        }
+    }
+
+    /// The display name of this variant, e.g. `"King of the Hill"` for [Variant::KingOfTheHill].
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    /// The abbreviation of this variant, e.g. `"KotH"` for [Variant::KingOfTheHill].
+    pub fn short(self) -> &'static str {
+        self.info().short
+    }
+
+    /// The FEN of the starting position of this variant, or [None] for [Variant::Chess960] and
+    /// [Variant::FromPosition], whose starting position is not fixed.
+    pub fn initial_fen(self) -> Option<&'static str> {
+        self.info().initial_fen
+    }
+}
+
 pub(crate) fn deserialize_optional_variant<'de, D>(deserializer: D) -> Result<Option<Variant>, D::Error>
 where
     D: Deserializer<'de>
@@ -206,7 +454,7 @@ where
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Speed {
     UltraBullet,
@@ -217,13 +465,82 @@ pub enum Speed {
     Correspondence
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+/// The upper bounds (exclusive) of the estimated game duration in seconds for every real-time
+/// [Speed] except [Speed::Classical], as used by Lichess.
+const SPEED_LIMITS: [(Seconds, Speed); 4] = [
+    (30, Speed::UltraBullet),
+    (180, Speed::Bullet),
+    (480, Speed::Blitz),
+    (1500, Speed::Rapid)
+];
+
+impl Speed {
+
+    /// Classifies a game with the given real-time clock like Lichess does, i.e. by its
+    /// [Clock::estimated_game_duration].
+    pub fn from_clock(clock: &Clock) -> Speed {
+        let duration = clock.estimated_game_duration();
+
+        SPEED_LIMITS.into_iter()
+            .find(|&(limit, _)| duration < limit)
+            .map_or(Speed::Classical, |(_, speed)| speed)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Clock {
     // TODO really optional?
     pub limit: Option<Seconds>,
     pub increment: Option<Seconds>
 }
 
+impl Clock {
+
+    /// The estimated duration of a game with this clock in seconds, which Lichess computes as the
+    /// initial time plus 40 times the increment. Missing values are treated as zero.
+    pub fn estimated_game_duration(&self) -> Seconds {
+        self.limit.unwrap_or(0) + 40 * self.increment.unwrap_or(0)
+    }
+
+    /// The [Speed] of a game with this clock (see [Speed::from_clock]).
+    pub fn speed(&self) -> Speed {
+        Speed::from_clock(self)
+    }
+}
+
+/// Deserializes the clock of a game, which Lichess sends as `initial` and `increment` in
+/// milliseconds in `gameFull` events, into a [Clock] in seconds. Clocks which already specify a
+/// `limit` in seconds are taken as they are.
+pub(crate) fn deserialize_game_clock<'de, D>(deserializer: D) -> Result<Option<Clock>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    #[derive(Deserialize)]
+    struct GameClockObject {
+        initial: Option<Milliseconds>,
+        limit: Option<Seconds>,
+        increment: Option<Milliseconds>
+    }
+
+    let Some(clock) = Option::<GameClockObject>::deserialize(deserializer)?
+    else {
+        return Ok(None);
+    };
+
+    let clock = match clock.initial {
+        Some(initial) => Clock {
+            limit: Some((initial / 1000) as Seconds),
+            increment: clock.increment.map(|increment| (increment / 1000) as Seconds)
+        },
+        None => Clock {
+            limit: clock.limit,
+            increment: clock.increment.map(|increment| increment as Seconds)
+        }
+    };
+
+    Ok(Some(clock))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -233,13 +550,30 @@ mod tests {
 
     use serde_json::{Deserializer as JsonDeserializer, Result as JsonResult};
 
-    use crate::model::game::{deserialize_game_status_from_object, GameStatus};
+    use crate::chess::position::{HORDE_FEN, RACING_KINGS_FEN, STANDARD_FEN};
+    use crate::model::Seconds;
+    use crate::model::game::{
+        deserialize_game_clock,
+        deserialize_game_status_from_object,
+        Clock,
+        Color,
+        GameInfo,
+        GameStatus,
+        Speed,
+        Variant
+    };
+    use crate::store::tests as store_tests;
 
     fn parse_game_status(json: &str) -> JsonResult<Option<GameStatus>> {
-        let mut deserializer = JsonDeserializer::from_str(&json);
+        let mut deserializer = JsonDeserializer::from_str(json);
         deserialize_game_status_from_object(&mut deserializer)
     }
 
+    fn parse_game_clock(json: &str) -> JsonResult<Option<Clock>> {
+        let mut deserializer = JsonDeserializer::from_str(json);
+        deserialize_game_clock(&mut deserializer)
+    }
+
     #[rstest]
     #[case::created(10, GameStatus::Created)]
     #[case::started(20, GameStatus::Started)]
@@ -314,7 +648,7 @@ mod tests {
     #[case::unknown_name("{\"name\":\"help\"}")]
     #[case::mismatch("{\"id\":10,\"name\":\"aborted\"}")]
     fn parse_game_status_fails(#[case] json: &str) {
-        let status = parse_game_status(&json);
+        let status = parse_game_status(json);
 
         assert_that!(status).is_err();
     }
@@ -326,7 +660,7 @@ mod tests {
     #[case::null_name("{\"name\":null}")]
     #[case::null_id_and_name("{\"id\":null,\"name\":null}")]
     fn parse_game_status_is_none(#[case] json: &str) {
-        let status = parse_game_status(&json).unwrap();
+        let status = parse_game_status(json).unwrap();
 
         assert_that!(status).is_none();
     }
@@ -348,4 +682,125 @@ mod tests {
     fn game_status_is_running(#[case] game_status: GameStatus, #[case] expected_is_running: bool) {
         assert_that!(game_status.is_running()).is_equal_to(expected_is_running);
     }
+
+    #[rstest]
+    #[case::standard(Variant::Standard)]
+    #[case::chess960(Variant::Chess960)]
+    #[case::king_of_the_hill(Variant::KingOfTheHill)]
+    #[case::racing_kings(Variant::RacingKings)]
+    #[case::three_check(Variant::ThreeCheck)]
+    #[case::from_position(Variant::FromPosition)]
+    fn variant_key_matches_serialized_key(#[case] variant: Variant) {
+        let serialized = serde_json::to_value(variant).unwrap();
+
+        assert_that!(serialized["key"].as_str()).contains(variant.key());
+    }
+
+    #[test]
+    fn variant_from_key_round_trips_all_variants() {
+        for variant in Variant::ALL {
+            assert_that!(Variant::from_key(variant.key())).contains(variant);
+        }
+
+        assert_that!(Variant::from_key("invalid")).is_none();
+    }
+
+    #[rstest]
+    #[case::standard(Variant::Standard, "Standard", "Std", Some(STANDARD_FEN))]
+    #[case::chess960(Variant::Chess960, "Chess960", "960", None)]
+    #[case::horde(Variant::Horde, "Horde", "Horde", Some(HORDE_FEN))]
+    #[case::king_of_the_hill(Variant::KingOfTheHill, "King of the Hill", "KotH",
+        Some(STANDARD_FEN))]
+    #[case::racing_kings(Variant::RacingKings, "Racing Kings", "Race", Some(RACING_KINGS_FEN))]
+    #[case::from_position(Variant::FromPosition, "From Position", "FEN", None)]
+    fn variant_info(#[case] variant: Variant, #[case] name: &str, #[case] short: &str,
+            #[case] initial_fen: Option<&str>) {
+        assert_that!(variant.name()).is_equal_to(name);
+        assert_that!(variant.short()).is_equal_to(short);
+        assert_that!(variant.initial_fen()).is_equal_to(initial_fen);
+    }
+
+    #[test]
+    fn variant_info_matches_lichess_json() {
+        let json = r#"{"key":"threeCheck","name":"Three-check","short":"3check"}"#;
+        let variant = serde_json::from_str::<Variant>(json).unwrap();
+
+        assert_that!(variant.name()).is_equal_to("Three-check");
+        assert_that!(variant.short()).is_equal_to("3check");
+    }
+
+    fn test_info(variant: Variant, initial_fen: &str) -> GameInfo {
+        GameInfo {
+            variant: Some(variant),
+            initial_fen: initial_fen.to_owned(),
+            ..store_tests::test_game_info("testGameId", "white", "black")
+        }
+    }
+
+    #[test]
+    fn game_urls() {
+        let info = test_info(Variant::Standard, "startpos");
+
+        assert_that!(info.url()).is_equal_to("https://lichess.org/testGameId".to_owned());
+        assert_that!(info.url_for(Color::Black))
+            .is_equal_to("https://lichess.org/testGameId/black".to_owned());
+        assert_that!(info.gif_url(None))
+            .is_equal_to("https://lichess1.org/game/export/gif/testGameId.gif".to_owned());
+        assert_that!(info.gif_url(Some(Color::White)))
+            .is_equal_to("https://lichess1.org/game/export/gif/white/testGameId.gif".to_owned());
+    }
+
+    #[rstest]
+    #[case::no_moves(Variant::Standard, "startpos", "", "https://lichess.org/analysis")]
+    #[case::standard_moves(Variant::Standard, "startpos", "f2f3 e7e5 g2g4 d8h4",
+        "https://lichess.org/analysis/pgn/f3_e5_g4_Qh4%23")]
+    #[case::variant(Variant::Atomic, "startpos", "e2e4",
+        "https://lichess.org/analysis/atomic/\
+            rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR_b_KQkq_-_0_1")]
+    #[case::from_position(Variant::FromPosition, "4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1",
+        "https://lichess.org/analysis/fromPosition/4k3/8/8/8/8/8/8/5RK1_b_-_-_1_1")]
+    fn analysis_url(#[case] variant: Variant, #[case] initial_fen: &str, #[case] moves: &str,
+            #[case] expected_url: &str) {
+        let info = test_info(variant, initial_fen);
+
+        assert_that!(info.analysis_url(moves).unwrap()).is_equal_to(expected_url.to_owned());
+    }
+
+    #[test]
+    fn analysis_url_fails_for_illegal_move() {
+        let info = test_info(Variant::Standard, "startpos");
+
+        assert_that!(info.analysis_url("e2e5")).is_err();
+    }
+
+    #[rstest]
+    #[case::ultra_bullet(15, 0, Speed::UltraBullet)]
+    #[case::bullet_boundary(30, 0, Speed::Bullet)]
+    #[case::bullet_with_increment(60, 1, Speed::Bullet)]
+    #[case::blitz(180, 2, Speed::Blitz)]
+    #[case::rapid(600, 5, Speed::Rapid)]
+    #[case::classical_boundary(1500, 0, Speed::Classical)]
+    #[case::classical(1800, 20, Speed::Classical)]
+    fn clock_speed_is_derived_from_estimated_duration(#[case] limit: Seconds,
+            #[case] increment: Seconds, #[case] expected: Speed) {
+        let clock = Clock {
+            limit: Some(limit),
+            increment: Some(increment)
+        };
+
+        assert_that!(Speed::from_clock(&clock)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::null("null", None)]
+    #[case::empty("{}", Some(Clock { limit: None, increment: None }))]
+    #[case::seconds(
+        r#"{"limit":180,"increment":2}"#,
+        Some(Clock { limit: Some(180), increment: Some(2) }))]
+    #[case::milliseconds(
+        r#"{"initial":180000,"increment":2000}"#,
+        Some(Clock { limit: Some(180), increment: Some(2) }))]
+    fn parse_game_clock_works(#[case] json: &str, #[case] expected: Option<Clock>) {
+        assert_that!(parse_game_clock(json)).contains_value(expected);
+    }
 }
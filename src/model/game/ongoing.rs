@@ -0,0 +1,147 @@
+use serde::Deserialize;
+
+use crate::model::{Move, Seconds};
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::{deserialize_optional_variant, Color, Fen, GameId, Speed, Variant};
+
+/// A game which the bot is currently playing, as listed by
+/// [BotClient::get_ongoing_games](crate::client::BotClient::get_ongoing_games).
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OngoingGame {
+    pub game_id: GameId,
+
+    /// The color played by the bot in this game.
+    pub color: Color,
+
+    /// The FEN of the current position.
+    pub fen: Option<Fen>,
+    pub has_moved: Option<bool>,
+    pub is_my_turn: bool,
+
+    /// The last move in UCI notation, if any move was played.
+    pub last_move: Option<Move>,
+    pub rated: Option<bool>,
+
+    /// The time the player to move has left, if the game has a time limit.
+    pub seconds_left: Option<Seconds>,
+    pub speed: Option<Speed>,
+
+    #[serde(default, deserialize_with = "deserialize_optional_variant")]
+    pub variant: Option<Variant>
+}
+
+impl OngoingGame {
+
+    /// Converts this game into the [GameStartFinish] of the `gameStart` event Lichess would send
+    /// for it, with the fields not known from the list of ongoing games left empty.
+    pub fn to_game_start(&self) -> GameStartFinish {
+        GameStartFinish {
+            id: Some(self.game_id.clone()),
+            source: None,
+            status: None,
+            winner: None,
+            color: Some(self.color),
+            rated: self.rated,
+            speed: self.speed,
+            variant: self.variant,
+            compat: None
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OngoingGames {
+    pub(crate) now_playing: Vec<OngoingGame>
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_ongoing_games() {
+        let json = r#"{
+            "nowPlaying": [
+                {
+                    "gameId": "testGameId",
+                    "fullId": "testGameIdFull",
+                    "color": "black",
+                    "fen": "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+                    "hasMoved": false,
+                    "isMyTurn": true,
+                    "lastMove": "e2e4",
+                    "opponent": { "id": "opponent", "username": "Opponent", "rating": 1500 },
+                    "perf": "correspondence",
+                    "rated": true,
+                    "secondsLeft": 172800,
+                    "source": "friend",
+                    "speed": "correspondence",
+                    "variant": { "key": "standard", "name": "Standard" }
+                },
+                {
+                    "gameId": "minimalGameId",
+                    "color": "white",
+                    "isMyTurn": false
+                }
+            ]
+        }"#;
+
+        let games = serde_json::from_str::<OngoingGames>(json).unwrap().now_playing;
+
+        assert_that!(games).contains_exactly_in_given_order([
+            OngoingGame {
+                game_id: "testGameId".to_owned(),
+                color: Color::Black,
+                fen: Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".to_owned()),
+                has_moved: Some(false),
+                is_my_turn: true,
+                last_move: Some("e2e4".to_owned()),
+                rated: Some(true),
+                seconds_left: Some(172800),
+                speed: Some(Speed::Correspondence),
+                variant: Some(Variant::Standard)
+            },
+            OngoingGame {
+                game_id: "minimalGameId".to_owned(),
+                color: Color::White,
+                fen: None,
+                has_moved: None,
+                is_my_turn: false,
+                last_move: None,
+                rated: None,
+                seconds_left: None,
+                speed: None,
+                variant: None
+            }
+        ]);
+    }
+
+    #[test]
+    fn ongoing_game_to_game_start() {
+        let game = OngoingGame {
+            game_id: "testGameId".to_owned(),
+            color: Color::Black,
+            fen: None,
+            has_moved: Some(true),
+            is_my_turn: false,
+            last_move: None,
+            rated: Some(false),
+            seconds_left: None,
+            speed: Some(Speed::Correspondence),
+            variant: Some(Variant::Chess960)
+        };
+
+        let game_start = game.to_game_start();
+
+        assert_that!(game_start.id).is_equal_to(Some("testGameId".to_owned()));
+        assert_that!(game_start.color).contains(Color::Black);
+        assert_that!(game_start.rated).contains(false);
+        assert_that!(game_start.speed).contains(Speed::Correspondence);
+        assert_that!(game_start.variant).contains(Variant::Chess960);
+    }
+}
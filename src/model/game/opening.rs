@@ -0,0 +1,37 @@
+use serde::Deserialize;
+
+/// The opening of a game, as classified by Lichess or by
+/// [chess::eco::classify](crate::chess::eco::classify).
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct Opening {
+
+    /// The code of the opening in the Encyclopaedia of Chess Openings, such as `C50`.
+    pub eco: String,
+
+    /// The name of the opening, such as `Italian Game: Giuoco Piano`.
+    pub name: String,
+
+    /// The number of half-moves after which the opening was identified.
+    pub ply: u32
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_opening() {
+        let json = r#"{ "eco": "D31", "name": "Semi-Slav Defense: Marshall Gambit", "ply": 7 }"#;
+
+        let opening = serde_json::from_str::<Opening>(json).unwrap();
+
+        assert_that!(opening).is_equal_to(Opening {
+            eco: "D31".to_owned(),
+            name: "Semi-Slav Defense: Marshall Gambit".to_owned(),
+            ply: 7
+        });
+    }
+}
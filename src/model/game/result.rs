@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::Milliseconds;
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::{Color, GameId, GameStatus};
+use crate::model::user::Rating;
+
+/// The outcome of a finished game from the perspective of the bot.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GameOutcome {
+    Win,
+    Loss,
+    Draw,
+
+    /// The game was aborted or never started, so it has no result.
+    Aborted,
+
+    /// The outcome could not be determined, e.g. because the bot's color is unknown.
+    Unknown
+}
+
+/// A summary of a finished game from the perspective of the bot, provided to
+/// [Bot::on_game_finish](crate::Bot::on_game_finish).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct GameResult {
+
+    /// The ID of the finished game, if known.
+    pub game_id: Option<GameId>,
+
+    /// The [GameOutcome] from the perspective of the bot.
+    pub outcome: GameOutcome,
+
+    /// The status with which the game ended, describing how it was terminated.
+    pub status: Option<GameStatus>,
+
+    /// The color played by the bot, if known.
+    pub bot_color: Option<Color>,
+
+    /// The time remaining on White's clock in the last observed game state, if known.
+    pub white_time: Option<Milliseconds>,
+
+    /// The time remaining on Black's clock in the last observed game state, if known.
+    pub black_time: Option<Milliseconds>,
+
+    /// The rating change of the bot as a result of this game, if the game was rated and the
+    /// change could be fetched.
+    pub rating_diff: Option<Rating>
+}
+
+pub(crate) fn outcome(status: Option<GameStatus>, winner: Option<Color>,
+        bot_color: Option<Color>) -> GameOutcome {
+    match (status, winner, bot_color) {
+        (Some(GameStatus::Aborted | GameStatus::NoStart), _, _) => GameOutcome::Aborted,
+        (_, Some(winner), Some(bot_color)) if winner == bot_color => GameOutcome::Win,
+        (_, Some(_), Some(_)) => GameOutcome::Loss,
+        (_, Some(_), None) => GameOutcome::Unknown,
+        (Some(status), None, _) if !status.is_running() && status != GameStatus::UnknownFinish =>
+            GameOutcome::Draw,
+        _ => GameOutcome::Unknown
+    }
+}
+
+impl GameResult {
+
+    pub(crate) fn new(game: &GameStartFinish, clock_times: Option<(Milliseconds, Milliseconds)>,
+            rating_diff: Option<Rating>) -> GameResult {
+        GameResult {
+            game_id: game.id.clone(),
+            outcome: outcome(game.status, game.winner, game.color),
+            status: game.status,
+            bot_color: game.color,
+            white_time: clock_times.map(|(white_time, _)| white_time),
+            black_time: clock_times.map(|(_, black_time)| black_time),
+            rating_diff
+        }
+    }
+
+    /// The time remaining on the bot's clock in the last observed game state, if known.
+    pub fn bot_time(&self) -> Option<Milliseconds> {
+        match self.bot_color? {
+            Color::White => self.white_time,
+            Color::Black => self.black_time
+        }
+    }
+
+    /// The time remaining on the opponent's clock in the last observed game state, if known.
+    pub fn opponent_time(&self) -> Option<Milliseconds> {
+        match self.bot_color? {
+            Color::White => self.black_time,
+            Color::Black => self.white_time
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn finished_game(status: Option<GameStatus>, winner: Option<Color>, color: Option<Color>)
+            -> GameStartFinish {
+        GameStartFinish {
+            id: Some("testGameId".to_owned()),
+            source: None,
+            status,
+            winner,
+            color,
+            rated: None,
+            speed: None,
+            variant: None,
+            compat: None
+        }
+    }
+
+    #[rstest]
+    #[case::win(Some(GameStatus::Mate), Some(Color::White), Some(Color::White), GameOutcome::Win)]
+    #[case::loss(
+        Some(GameStatus::Resign), Some(Color::White), Some(Color::Black), GameOutcome::Loss)]
+    #[case::draw(Some(GameStatus::Stalemate), None, Some(Color::Black), GameOutcome::Draw)]
+    #[case::draw_without_color(Some(GameStatus::Draw), None, None, GameOutcome::Draw)]
+    #[case::aborted(Some(GameStatus::Aborted), None, Some(Color::White), GameOutcome::Aborted)]
+    #[case::no_start(Some(GameStatus::NoStart), Some(Color::Black), None, GameOutcome::Aborted)]
+    #[case::winner_without_color(
+        Some(GameStatus::Mate), Some(Color::White), None, GameOutcome::Unknown)]
+    #[case::unknown_finish(Some(GameStatus::UnknownFinish), None, None, GameOutcome::Unknown)]
+    #[case::running(Some(GameStatus::Started), None, Some(Color::White), GameOutcome::Unknown)]
+    #[case::no_status(None, None, Some(Color::White), GameOutcome::Unknown)]
+    fn outcome_is_computed_from_bot_perspective(#[case] status: Option<GameStatus>,
+            #[case] winner: Option<Color>, #[case] color: Option<Color>,
+            #[case] expected_outcome: GameOutcome) {
+        let result = GameResult::new(&finished_game(status, winner, color), None, None);
+
+        assert_that!(result.outcome).is_equal_to(expected_outcome);
+    }
+
+    #[rstest]
+    #[case::white(Some(Color::White), Some(1000), Some(2000))]
+    #[case::black(Some(Color::Black), Some(2000), Some(1000))]
+    #[case::unknown(None, None, None)]
+    fn clock_times_from_bot_perspective(#[case] color: Option<Color>,
+            #[case] expected_bot_time: Option<Milliseconds>,
+            #[case] expected_opponent_time: Option<Milliseconds>) {
+        let game = finished_game(Some(GameStatus::Mate), Some(Color::White), color);
+
+        let result = GameResult::new(&game, Some((1000, 2000)), Some(5));
+
+        assert_that!(result.bot_time()).is_equal_to(expected_bot_time);
+        assert_that!(result.opponent_time()).is_equal_to(expected_opponent_time);
+        assert_that!(result.rating_diff).contains(5);
+    }
+}
@@ -2,12 +2,18 @@ use serde::Deserialize;
 use serde_json::Value;
 use std::hash::{Hash, Hasher};
 
-use crate::model::game::Clock;
+use crate::model::game::{Clock, Speed};
 
 pub mod user;
 pub mod game;
 pub mod challenge;
 pub mod bot_event;
+pub mod analysis;
+pub mod cloud_eval;
+pub mod external_engine;
+pub mod tournament;
+pub mod tv;
+pub mod broadcast;
 pub(crate) mod request;
 
 /// A Chess move in UCI notation.
@@ -28,10 +34,16 @@ pub struct Compat {
     pub board: Option<bool>
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum TimeControl {
-    Clock(Clock),
+    Clock {
+        #[serde(flatten)]
+        clock: Clock,
+
+        /// The time control as displayed by Lichess, e.g. `5+3`.
+        show: Option<String>
+    },
     #[serde(rename_all = "camelCase")]
     Correspondence {
         // TODO really optional?
@@ -40,6 +52,27 @@ pub enum TimeControl {
     Unlimited
 }
 
+impl TimeControl {
+
+    /// The estimated duration of a game with this time control in seconds (see
+    /// [Clock::estimated_game_duration]), or [None] if it has no real-time clock.
+    pub fn estimated_game_duration(&self) -> Option<Seconds> {
+        match self {
+            TimeControl::Clock { clock, .. } => Some(clock.estimated_game_duration()),
+            TimeControl::Correspondence { .. } | TimeControl::Unlimited => None
+        }
+    }
+
+    /// The [Speed] of a game with this time control, derived from the estimated game duration
+    /// like Lichess does. Time controls without a real-time clock are [Speed::Correspondence].
+    pub fn speed(&self) -> Speed {
+        match self {
+            TimeControl::Clock { clock, .. } => clock.speed(),
+            TimeControl::Correspondence { .. } | TimeControl::Unlimited => Speed::Correspondence
+        }
+    }
+}
+
 struct AnyRef<'reference>(&'reference Value);
 
 impl<'reference> Hash for AnyRef<'reference> {
@@ -1,4 +1,5 @@
-use crate::model::challenge::DeclineReason;
+use crate::model::Seconds;
+use crate::model::challenge::{ChallengeColor, ChallengeRequest, DeclineReason};
 use crate::model::game::chat::ChatRoom;
 
 use serde::Serialize;
@@ -10,12 +11,41 @@ pub(crate) struct DeclineRequest {
     pub(crate) reason: Option<DeclineReason>
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct KeepAliveChallengeRequest<'request> {
+
+    #[serde(flatten)]
+    pub(crate) request: &'request ChallengeRequest,
+    pub(crate) keep_alive_stream: bool
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SeekRequest {
+    pub(crate) rated: bool,
+
+    /// The initial clock time in minutes.
+    pub(crate) time: f64,
+    pub(crate) increment: Seconds,
+    pub(crate) variant: &'static str,
+    pub(crate) color: ChallengeColor,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) rating_range: Option<String>
+}
+
 #[derive(Serialize)]
 pub(crate) struct SendChatMessageRequest {
     pub(crate) room: ChatRoom,
     pub(crate) text: String
 }
 
+#[derive(Serialize)]
+pub(crate) struct SendTeamMessageRequest {
+    pub(crate) message: String
+}
+
 #[cfg(test)]
 mod tests {
 
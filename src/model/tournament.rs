@@ -0,0 +1,236 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::model::Seconds;
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::{Clock, Color, GameId, Speed, TournamentId, Variant};
+use crate::model::user::Rating;
+
+/// The participation of the bot in a [Tournament].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentParticipation {
+
+    /// The current rank of the bot in the tournament.
+    pub rank: u32,
+
+    /// Whether the bot withdrew from or was paused in the tournament, in which case it is not
+    /// paired until it joins again.
+    #[serde(default)]
+    pub withdraw: bool,
+
+    /// The ID of the game the bot currently plays in the tournament, if any.
+    pub game_id: Option<GameId>,
+
+    /// The number of seconds until the bot can join again after being paused, if any.
+    pub pause_delay: Option<Seconds>
+}
+
+/// A player of a [TournamentDuel].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct DuelPlayer {
+
+    /// The name of the player.
+    #[serde(rename = "n")]
+    pub name: String,
+
+    /// The rating of the player in the tournament's rating category.
+    #[serde(rename = "r")]
+    pub rating: Option<Rating>
+}
+
+/// A game which is currently played in a [Tournament], as listed on its page.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct TournamentDuel {
+    pub id: GameId,
+
+    /// The players of the game, White first.
+    #[serde(rename = "p")]
+    pub players: Vec<DuelPlayer>
+}
+
+/// A game of a [Tournament] for which the bot was paired, as determined by
+/// [Tournament::pairing].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TournamentPairing {
+    pub tournament_id: TournamentId,
+    pub game_id: GameId,
+
+    /// The color the bot plays, if the game is listed among the duels of the tournament.
+    pub color: Option<Color>,
+
+    /// The opponent of the bot, if the game is listed among the duels of the tournament.
+    pub opponent: Option<DuelPlayer>,
+    pub speed: Option<Speed>,
+    pub variant: Option<Variant>
+}
+
+impl TournamentPairing {
+
+    /// Converts this pairing into the [GameStartFinish] of the `gameStart` event Lichess will
+    /// send for the game, with the fields not known from the tournament left empty.
+    pub fn to_game_start(&self) -> GameStartFinish {
+        GameStartFinish {
+            id: Some(self.game_id.clone()),
+            source: None,
+            status: None,
+            winner: None,
+            color: self.color,
+            rated: None,
+            speed: self.speed,
+            variant: self.variant,
+            compat: None
+        }
+    }
+}
+
+fn deserialize_variant_key<'de, D>(deserializer: D) -> Result<Option<Variant>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    let key = Option::<String>::deserialize(deserializer)?;
+
+    Ok(key.and_then(|key| Variant::from_key(&key)))
+}
+
+/// An arena tournament on Lichess, as seen by the bot.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Tournament {
+    pub id: TournamentId,
+    pub full_name: String,
+
+    #[serde(default)]
+    pub nb_players: u32,
+
+    #[serde(default)]
+    pub is_started: bool,
+
+    #[serde(default)]
+    pub is_finished: bool,
+
+    /// The number of seconds until the tournament starts, if it has not started yet.
+    pub seconds_to_start: Option<Seconds>,
+
+    /// The number of seconds until the tournament finishes, if it is running.
+    pub seconds_to_finish: Option<Seconds>,
+
+    /// Whether players may berserk their games in this tournament, halving their clock.
+    #[serde(default)]
+    pub berserkable: bool,
+
+    pub clock: Option<Clock>,
+
+    #[serde(default, deserialize_with = "deserialize_variant_key")]
+    pub variant: Option<Variant>,
+
+    /// The games which are currently played in the tournament. Lichess only lists the top games,
+    /// so the game of the bot may be missing.
+    #[serde(default)]
+    pub duels: Vec<TournamentDuel>,
+
+    /// The participation of the bot, or [None] if it never joined the tournament.
+    pub me: Option<TournamentParticipation>
+}
+
+impl Tournament {
+
+    /// Determines whether the bot currently takes part in this tournament, i.e. it joined and
+    /// neither withdrew nor was paused.
+    pub fn is_joined(&self) -> bool {
+        self.me.as_ref().is_some_and(|me| !me.withdraw)
+    }
+
+    /// Determines the game for which the bot is currently paired in this tournament, if any. The
+    /// color and opponent of the bot are only known if the game is listed among the
+    /// [duels](Tournament::duels).
+    ///
+    /// # Arguments
+    ///
+    /// * `bot_name`: The name of the bot's user, by which it is identified in the duels.
+    pub fn pairing(&self, bot_name: &str) -> Option<TournamentPairing> {
+        let game_id = self.me.as_ref()?.game_id.clone()?;
+        let duel = self.duels.iter().find(|duel| duel.id == game_id);
+        let bot_index = duel.and_then(|duel| duel.players.iter()
+            .position(|player| player.name.eq_ignore_ascii_case(bot_name)));
+        let color = bot_index.map(|index| if index == 0 { Color::White } else { Color::Black });
+        let opponent = duel.filter(|_| bot_index.is_some())
+            .and_then(|duel| duel.players.iter()
+                .find(|player| !player.name.eq_ignore_ascii_case(bot_name)))
+            .cloned();
+
+        Some(TournamentPairing {
+            tournament_id: self.id.clone(),
+            game_id,
+            color,
+            opponent,
+            speed: self.clock.as_ref().map(Speed::from_clock),
+            variant: self.variant
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_tournament() {
+        let json = r#"{
+            "id": "testTournamentId",
+            "fullName": "Bullet Arena",
+            "nbPlayers": 42,
+            "isStarted": true,
+            "secondsToFinish": 1200,
+            "berserkable": true,
+            "me": {
+                "rank": 3,
+                "withdraw": true,
+                "gameId": "testGameId"
+            }
+        }"#;
+
+        let tournament = serde_json::from_str::<Tournament>(json).unwrap();
+
+        assert_that!(tournament.is_started).is_true();
+        assert_that!(tournament.is_finished).is_false();
+        assert_that!(tournament.seconds_to_finish).contains(1200);
+        assert_that!(tournament.me.as_ref().map(|me| me.rank)).contains(3);
+        assert_that!(tournament.is_joined()).is_false();
+    }
+
+    fn parse_tournament_with_duels(duels: &str) -> Tournament {
+        let json = format!(concat!(r#"{{"id":"testTournamentId","fullName":"Bullet Arena","#,
+            r#""variant":"atomic","me":{{"rank":3,"gameId":"testGameId"}},"duels":{}}}"#), duels);
+
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn pairing_determines_color_and_opponent_from_duels() {
+        let tournament = parse_tournament_with_duels(concat!(r#"[{"id":"otherGameId","p":[]},"#,
+            r#"{"id":"testGameId","p":[{"n":"TestBot","r":2000},{"n":"Opponent","r":1900}]}]"#));
+
+        let pairing = tournament.pairing("testbot").unwrap();
+
+        assert_that!(pairing.game_id.as_str()).is_equal_to("testGameId");
+        assert_that!(pairing.color).contains(Color::White);
+        assert_that!(pairing.opponent).contains(DuelPlayer {
+            name: "Opponent".to_owned(),
+            rating: Some(1900)
+        });
+        assert_that!(pairing.variant).contains(Variant::Atomic);
+    }
+
+    #[test]
+    fn pairing_without_listed_duel_has_unknown_color() {
+        let tournament = parse_tournament_with_duels("[]");
+
+        let pairing = tournament.pairing("testbot").unwrap();
+
+        assert_that!(pairing.color).is_none();
+        assert_that!(pairing.opponent).is_none();
+    }
+}
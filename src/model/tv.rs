@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::model::game::{Color, GameId};
+use crate::model::user::{Rating, User};
+
+/// The game currently featured on a Lichess TV channel.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TvGame {
+
+    /// The player whose game is featured.
+    pub user: User,
+
+    /// The rating of the featured player in the channel's rating category, if any.
+    pub rating: Option<Rating>,
+
+    /// The ID of the featured game.
+    pub game_id: GameId,
+
+    /// The color played by the featured player.
+    pub color: Color
+}
+
+/// The games currently featured on Lichess TV, indexed by the name of their channel, such as
+/// `bot`, `blitz`, or `best`. The same game may be featured on several channels.
+pub type TvChannels = HashMap<String, TvGame>;
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_tv_channels() {
+        let json = r#"{
+            "bot": {
+                "user": { "id": "testbot", "name": "TestBot", "title": "BOT" },
+                "rating": 2345,
+                "gameId": "testGameId",
+                "color": "black"
+            },
+            "horde": {
+                "user": { "id": "anotheruser", "name": "AnotherUser" },
+                "rating": 1500,
+                "gameId": "otherGameId",
+                "color": "white"
+            }
+        }"#;
+
+        let channels = serde_json::from_str::<TvChannels>(json).unwrap();
+        let bot_channel = channels.get("bot").unwrap();
+
+        assert_that!(channels.len()).is_equal_to(2);
+        assert_that!(bot_channel.user.id.as_str()).is_equal_to("testbot");
+        assert_that!(bot_channel.rating).contains(2345);
+        assert_that!(bot_channel.game_id.as_str()).is_equal_to("testGameId");
+        assert_that!(bot_channel.color).is_equal_to(Color::Black);
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::model::user::UserId;
+
+/// The total scores of two users in all games they played against each other.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Crosstable {
+
+    /// The points scored by each of the two users, where a draw counts half a point.
+    pub users: HashMap<UserId, f64>,
+
+    /// The number of games the two users played against each other.
+    #[serde(rename = "nbGames")]
+    pub games: u32
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_crosstable() {
+        let json = r#"{
+            "users": {
+                "neio": 201.5,
+                "thibault": 144.5
+            },
+            "nbGames": 346
+        }"#;
+
+        let crosstable = serde_json::from_str::<Crosstable>(json).unwrap();
+
+        assert_that!(crosstable.games).is_equal_to(346);
+        assert_that!(crosstable.users.get("neio").copied()).contains(201.5);
+        assert_that!(crosstable.users.get("thibault").copied()).contains(144.5);
+    }
+}
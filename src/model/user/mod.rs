@@ -3,7 +3,11 @@ use std::hash::Hash;
 use serde::Deserialize;
 
 use crate::model::{Any, Seconds, Timestamp, Url};
+use crate::model::game::{Speed, Variant};
+use crate::model::game::event::GameEventPlayer;
+use crate::model::user::preferences::Language;
 
+pub mod crosstable;
 pub mod preferences;
 
 pub type UserId = String;
@@ -74,6 +78,9 @@ pub struct Perfs {
     pub bullet: Option<Perf>,
     pub correspondence: Option<Perf>,
     pub horde: Option<Perf>,
+    pub crazyhouse: Option<Perf>,
+    pub antichess: Option<Perf>,
+    pub three_check: Option<Perf>,
     pub puzzle: Option<Perf>,
     pub classical: Option<Perf>,
     pub rapid: Option<Perf>,
@@ -82,6 +89,93 @@ pub struct Perfs {
     pub streak: Option<PuzzleModePerf>
 }
 
+impl Perfs {
+
+    /// Gets the performance of the given rating category.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: The rating category whose performance to get.
+    ///
+    /// # Returns
+    ///
+    /// The performance in the given category, or [None] if the user has none.
+    pub fn get(&self, key: PerfKey) -> Option<&Perf> {
+        match key {
+            PerfKey::UltraBullet => self.ultra_bullet.as_ref(),
+            PerfKey::Bullet => self.bullet.as_ref(),
+            PerfKey::Blitz => self.blitz.as_ref(),
+            PerfKey::Rapid => self.rapid.as_ref(),
+            PerfKey::Classical => self.classical.as_ref(),
+            PerfKey::Correspondence => self.correspondence.as_ref(),
+            PerfKey::Chess960 => self.chess960.as_ref(),
+            PerfKey::Crazyhouse => self.crazyhouse.as_ref(),
+            PerfKey::Antichess => self.antichess.as_ref(),
+            PerfKey::Atomic => self.atomic.as_ref(),
+            PerfKey::Horde => self.horde.as_ref(),
+            PerfKey::KingOfTheHill => self.king_of_the_hill.as_ref(),
+            PerfKey::RacingKings => self.racing_kings.as_ref(),
+            PerfKey::ThreeCheck => self.three_check.as_ref()
+        }
+    }
+}
+
+/// A rating category on Lichess in which games are rated, i.e. one of the [Perfs] of a user.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PerfKey {
+    UltraBullet,
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+    Correspondence,
+    Chess960,
+    Crazyhouse,
+    Antichess,
+    Atomic,
+    Horde,
+    KingOfTheHill,
+    RacingKings,
+    ThreeCheck
+}
+
+impl PerfKey {
+
+    /// Determines the rating category of a game with the given speed and variant. Standard games
+    /// are rated by speed, while games of other variants are rated per variant regardless of speed.
+    ///
+    /// # Arguments
+    ///
+    /// * `speed`: The speed of the game.
+    /// * `variant`: The variant of the game.
+    ///
+    /// # Returns
+    ///
+    /// The rating category of the game, or [None] if games from a custom position are not rated.
+    pub fn of_game(speed: Speed, variant: Variant) -> Option<PerfKey> {
+        match variant {
+            Variant::Standard => Some(match speed {
+                Speed::UltraBullet => PerfKey::UltraBullet,
+                Speed::Bullet => PerfKey::Bullet,
+                Speed::Blitz => PerfKey::Blitz,
+                Speed::Rapid => PerfKey::Rapid,
+                Speed::Classical => PerfKey::Classical,
+                Speed::Correspondence => PerfKey::Correspondence
+            }),
+            Variant::Chess960 => Some(PerfKey::Chess960),
+            Variant::Crazyhouse => Some(PerfKey::Crazyhouse),
+            Variant::Antichess => Some(PerfKey::Antichess),
+            Variant::Atomic => Some(PerfKey::Atomic),
+            Variant::Horde => Some(PerfKey::Horde),
+            Variant::KingOfTheHill => Some(PerfKey::KingOfTheHill),
+            Variant::RacingKings => Some(PerfKey::RacingKings),
+            Variant::ThreeCheck => Some(PerfKey::ThreeCheck),
+            Variant::FromPosition => None
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Profile {
@@ -167,7 +261,122 @@ pub struct UserProfile {
     pub blocking: bool,
 
     #[serde(default)]
-    pub follows_you: bool
+    pub follows_you: bool,
+
+    /// The preferred language of the user, if Lichess reports it.
+    #[serde(default)]
+    pub language: Option<Language>
+}
+
+/// A uniform view of a player, which can be obtained from the different shapes in which Lichess
+/// reports players, i.e. [GameEventPlayer], [User] and [UserProfile].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PlayerRef {
+
+    /// A Lichess user.
+    User {
+        id: UserId,
+        name: String,
+        title: Option<Title>,
+        rating: Option<Rating>,
+        provisional: bool
+    },
+
+    /// The Lichess AI on the given level.
+    Ai(AiLevel),
+
+    /// An anonymous player without an account.
+    Anonymous
+}
+
+impl PlayerRef {
+
+    /// Gets the [UserId] of this player, or [None] if it is not a Lichess user.
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            PlayerRef::User { id, .. } => Some(id),
+            _ => None
+        }
+    }
+
+    /// Gets the name of this player, or [None] if it is not a Lichess user.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            PlayerRef::User { name, .. } => Some(name),
+            _ => None
+        }
+    }
+
+    /// Gets the [Title] of this player, if it is a titled Lichess user.
+    pub fn title(&self) -> Option<&Title> {
+        match self {
+            PlayerRef::User { title, .. } => title.as_ref(),
+            _ => None
+        }
+    }
+
+    /// Gets the rating of this player, if it is a Lichess user and the rating is known.
+    pub fn rating(&self) -> Option<Rating> {
+        match self {
+            PlayerRef::User { rating, .. } => *rating,
+            _ => None
+        }
+    }
+
+    /// Indicates whether the rating of this player is provisional.
+    pub fn is_provisional(&self) -> bool {
+        matches!(self, PlayerRef::User { provisional: true, .. })
+    }
+
+    /// Indicates whether this player is the Lichess user with the given ID.
+    pub fn is_user(&self, user_id: &str) -> bool {
+        self.id() == Some(user_id)
+    }
+
+    /// Indicates whether this player is a bot account.
+    pub fn is_bot(&self) -> bool {
+        self.title() == Some(&Title::Bot)
+    }
+}
+
+impl From<&GameEventPlayer> for PlayerRef {
+    fn from(player: &GameEventPlayer) -> PlayerRef {
+        match (&player.id, player.ai_level) {
+            (Some(id), _) => PlayerRef::User {
+                id: id.clone(),
+                name: player.name.clone().unwrap_or_else(|| id.clone()),
+                title: player.title.clone(),
+                rating: player.rating,
+                provisional: player.provisional.unwrap_or(false)
+            },
+            (None, Some(ai_level)) => PlayerRef::Ai(ai_level),
+            (None, None) => PlayerRef::Anonymous
+        }
+    }
+}
+
+impl From<&User> for PlayerRef {
+    fn from(user: &User) -> PlayerRef {
+        PlayerRef::User {
+            id: user.id.clone(),
+            name: user.name.clone(),
+            title: user.title.clone(),
+            rating: user.rating,
+            provisional: user.provisional
+        }
+    }
+}
+
+impl From<&UserProfile> for PlayerRef {
+    fn from(profile: &UserProfile) -> PlayerRef {
+        PlayerRef::User {
+            id: profile.id.clone(),
+            name: profile.username.clone(),
+            title: profile.title.clone(),
+            rating: None,
+            provisional: false
+        }
+    }
 }
 
 #[cfg(test)]
@@ -177,13 +386,18 @@ mod tests {
 
     use rstest::rstest;
 
+    use crate::model::game::{Speed, Variant};
+    use crate::model::game::event::GameEventPlayer;
     use crate::model::user::{
         Perf,
+        PerfKey,
         Perfs,
         PlayTime,
+        PlayerRef,
         Profile,
         PuzzleModePerf,
         Title,
+        User,
         UserProfile,
         UserProfileStats
     };
@@ -228,6 +442,7 @@ mod tests {
             following: false,
             blocking: false,
             follows_you: false,
+            language: None,
         }
     }
 
@@ -872,4 +1087,85 @@ mod tests {
 
         assert_that!(user_profile).is_equal_to(expected_profile);
     }
+
+    #[rstest]
+    #[case::standard_by_speed(Speed::Rapid, Variant::Standard, Some(PerfKey::Rapid))]
+    #[case::correspondence(Speed::Correspondence, Variant::Standard, Some(PerfKey::Correspondence))]
+    #[case::variant_ignores_speed(Speed::Bullet, Variant::ThreeCheck, Some(PerfKey::ThreeCheck))]
+    #[case::from_position(Speed::Blitz, Variant::FromPosition, None)]
+    fn perf_key_of_game(#[case] speed: Speed, #[case] variant: Variant,
+            #[case] expected: Option<PerfKey>) {
+        assert_that!(PerfKey::of_game(speed, variant)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn perfs_get_returns_perf_of_category() {
+        let perf = Perf {
+            games: 1,
+            rating: 1500,
+            rd: 100,
+            prog: 0,
+            prov: false
+        };
+        let perfs = Perfs {
+            crazyhouse: Some(perf),
+            ..Perfs::default()
+        };
+
+        assert_that!(perfs.get(PerfKey::Crazyhouse).copied()).contains(perf);
+        assert_that!(perfs.get(PerfKey::Blitz).copied()).is_none();
+    }
+
+    fn game_event_player(id: Option<&str>, ai_level: Option<i32>) -> GameEventPlayer {
+        GameEventPlayer {
+            ai_level,
+            id: id.map(str::to_owned),
+            name: id.map(|id| format!("{id}Name")),
+            title: Some(Title::Bot),
+            rating: Some(1500),
+            provisional: Some(true)
+        }
+    }
+
+    #[rstest]
+    #[case::user(Some("testId"), None, Some("testId"), Some(1500), true)]
+    #[case::ai(None, Some(3), None, None, false)]
+    #[case::anonymous(None, None, None, None, false)]
+    fn player_ref_from_game_event_player(#[case] id: Option<&str>, #[case] ai_level: Option<i32>,
+            #[case] expected_id: Option<&str>, #[case] expected_rating: Option<i32>,
+            #[case] expected_bot: bool) {
+        let player = PlayerRef::from(&game_event_player(id, ai_level));
+
+        assert_that!(player.id()).is_equal_to(expected_id);
+        assert_that!(player.rating()).is_equal_to(expected_rating);
+        assert_that!(player.is_bot()).is_equal_to(expected_bot);
+    }
+
+    #[test]
+    fn player_ref_is_ai_for_game_event_player_with_ai_level() {
+        let player = PlayerRef::from(&game_event_player(None, Some(8)));
+
+        assert_that!(player).is_equal_to(PlayerRef::Ai(8));
+    }
+
+    #[test]
+    fn player_refs_from_user_and_profile_agree_on_identity() {
+        let user = User {
+            rating: Some(2000),
+            provisional: false,
+            online: true,
+            id: "testId".to_owned(),
+            name: "testUsername".to_owned(),
+            title: None,
+            patron: false
+        };
+        let from_user = PlayerRef::from(&user);
+        let from_profile = PlayerRef::from(&minimal_user_profile());
+
+        assert_that!(from_user.id()).is_equal_to(from_profile.id());
+        assert_that!(from_user.name()).is_equal_to(from_profile.name());
+        assert_that!(from_user.rating()).contains(2000);
+        assert_that!(from_profile.rating()).is_none();
+        assert_that!(from_user.is_user("testId")).is_true();
+    }
 }
@@ -0,0 +1,186 @@
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+const LIMIT_HEADER: &str = "x-ratelimit-limit";
+const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+const RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// The time Lichess asks clients to wait after being rate limited if the response does not state
+/// otherwise.
+pub const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Rate-limit related information parsed from the headers of a Lichess API response. The
+/// information of the most recent response which contained any is available from
+/// [BotClient::rate_limit_info](crate::client::BotClient::rate_limit_info).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RateLimitInfo {
+
+    /// The status of the response from which this information was parsed.
+    pub status: StatusCode,
+
+    /// The number of requests permitted in the current window, from the `X-RateLimit-Limit`
+    /// header.
+    pub limit: Option<u64>,
+
+    /// The number of requests remaining in the current window, from the `X-RateLimit-Remaining`
+    /// header.
+    pub remaining: Option<u64>,
+
+    /// The time until the current window resets, from the `X-RateLimit-Reset` header.
+    pub reset_after: Option<Duration>,
+
+    /// The time to wait before sending the next request, from the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+
+    /// The time at which the response was received, from which the durations are measured.
+    pub received_at: Instant
+}
+
+impl RateLimitInfo {
+
+    /// Parses the rate-limit related headers of a response with the given status.
+    ///
+    /// # Returns
+    ///
+    /// The parsed information, or [None] if the response contains no rate-limit related headers
+    /// and is not a `429 Too Many Requests` response.
+    pub fn from_response(status: StatusCode, headers: &HeaderMap) -> Option<RateLimitInfo> {
+        let parse = |name: &str| headers.get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        let info = RateLimitInfo {
+            status,
+            limit: parse(LIMIT_HEADER),
+            remaining: parse(REMAINING_HEADER),
+            reset_after: parse(RESET_HEADER).map(Duration::from_secs),
+            retry_after: parse(RETRY_AFTER.as_str()).map(Duration::from_secs),
+            received_at: Instant::now()
+        };
+        let has_headers = info.limit.is_some() || info.remaining.is_some() ||
+            info.reset_after.is_some() || info.retry_after.is_some();
+
+        if has_headers || info.is_rate_limited() {
+            Some(info)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Indicates whether the response was a `429 Too Many Requests` response.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS
+    }
+
+    /// Computes the time from now on for which no further requests should be sent. If the
+    /// response was rate limited, this is the `Retry-After` time, falling back to
+    /// [DEFAULT_RATE_LIMIT_BACKOFF]. Otherwise, if no requests remain in the current window, it
+    /// is the time until the window resets.
+    ///
+    /// # Returns
+    ///
+    /// The remaining time to wait, or [None] if requests may be sent immediately.
+    pub fn wait_time(&self) -> Option<Duration> {
+        let wait = if self.is_rate_limited() {
+            self.retry_after.unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF)
+        }
+        else if self.remaining == Some(0) {
+            self.reset_after.or(self.retry_after)?
+        }
+        else {
+            self.retry_after?
+        };
+
+        wait.checked_sub(self.received_at.elapsed()).filter(|wait| !wait.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use reqwest::header::HeaderValue;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn headers(entries: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for &(name, value) in entries {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+
+        headers
+    }
+
+    #[test]
+    fn response_without_rate_limit_headers_has_no_info() {
+        assert_that!(RateLimitInfo::from_response(StatusCode::OK, &HeaderMap::new())).is_none();
+    }
+
+    #[test]
+    fn rate_limit_headers_are_parsed() {
+        let headers = headers(&[
+            ("X-RateLimit-Limit", "30"),
+            ("X-RateLimit-Remaining", "12"),
+            ("X-RateLimit-Reset", "45"),
+            ("Retry-After", " 5 ")
+        ]);
+
+        let info = RateLimitInfo::from_response(StatusCode::OK, &headers).unwrap();
+
+        assert_that!(info.limit).contains(30);
+        assert_that!(info.remaining).contains(12);
+        assert_that!(info.reset_after).contains(Duration::from_secs(45));
+        assert_that!(info.retry_after).contains(Duration::from_secs(5));
+        assert_that!(info.is_rate_limited()).is_false();
+    }
+
+    #[test]
+    fn malformed_headers_are_ignored() {
+        let headers = headers(&[("X-RateLimit-Remaining", "many")]);
+
+        assert_that!(RateLimitInfo::from_response(StatusCode::OK, &headers)).is_none();
+    }
+
+    #[test]
+    fn too_many_requests_response_has_info_without_headers() {
+        let info = RateLimitInfo::from_response(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new())
+            .unwrap();
+
+        assert_that!(info.is_rate_limited()).is_true();
+        assert_that!(info.retry_after).is_none();
+    }
+
+    #[rstest]
+    #[case::rate_limited_with_retry_after(StatusCode::TOO_MANY_REQUESTS, &[("Retry-After", "30")],
+        Some(30))]
+    #[case::rate_limited_without_retry_after(StatusCode::TOO_MANY_REQUESTS, &[], Some(60))]
+    #[case::exhausted(StatusCode::OK,
+        &[("X-RateLimit-Remaining", "0"), ("X-RateLimit-Reset", "20")], Some(20))]
+    #[case::remaining(StatusCode::OK,
+        &[("X-RateLimit-Remaining", "3"), ("X-RateLimit-Reset", "20")], None)]
+    fn wait_time_is_derived_from_headers(#[case] status: StatusCode,
+            #[case] entries: &[(&'static str, &'static str)],
+            #[case] expected_secs_upper_bound: Option<u64>) {
+        let info = RateLimitInfo::from_response(status, &headers(entries)).unwrap();
+
+        let wait_time = info.wait_time();
+
+        match expected_secs_upper_bound {
+            Some(secs) => {
+                let wait_time = wait_time.unwrap();
+                assert_that!(wait_time).is_less_than_or_equal_to(Duration::from_secs(secs));
+                assert_that!(wait_time).is_greater_than(Duration::from_secs(secs - 1));
+            },
+            None => {
+                assert_that!(wait_time).is_none();
+            }
+        }
+    }
+}
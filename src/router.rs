@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::Bot;
+use crate::chess::position::InvalidPosition;
+use crate::client::BotClient;
+use crate::context::{BotContext, GameContext};
+use crate::model::bot_event::GameStartFinish;
+use crate::model::challenge::{Challenge, ChallengeDeclined};
+use crate::model::game::{Color, GameId, Speed, Variant};
+use crate::model::game::event::{ChatLineEvent, GameStateEvent, OpponentGoneEvent};
+use crate::model::game::result::GameResult;
+use crate::model::tournament::TournamentPairing;
+use crate::runner::error::RunnerError;
+use crate::stats::RatingUpdate;
+
+/// A condition on the speed and variant of a game or challenge, used by a [SpeedRouter] to decide
+/// which bot handles it. An empty list of speeds or variants matches any speed or variant,
+/// respectively. By default, i.e. as constructed by [Route::any], every game matches.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Route {
+    speeds: Vec<Speed>,
+    variants: Vec<Variant>
+}
+
+impl Route {
+
+    /// Creates a new route which matches every game.
+    pub fn any() -> Route {
+        Route::default()
+    }
+
+    /// Creates a new route which matches games of any of the given speeds, regardless of their
+    /// variant.
+    pub fn speeds(speeds: impl IntoIterator<Item = Speed>) -> Route {
+        Route::any().with_speeds(speeds)
+    }
+
+    /// Creates a new route which matches games of any of the given variants, regardless of their
+    /// speed.
+    pub fn variants(variants: impl IntoIterator<Item = Variant>) -> Route {
+        Route::any().with_variants(variants)
+    }
+
+    /// Restricts this route to games of any of the given speeds. The route is returned for
+    /// chaining.
+    pub fn with_speeds(mut self, speeds: impl IntoIterator<Item = Speed>) -> Route {
+        self.speeds.extend(speeds);
+        self
+    }
+
+    /// Restricts this route to games of any of the given variants. The route is returned for
+    /// chaining.
+    pub fn with_variants(mut self, variants: impl IntoIterator<Item = Variant>) -> Route {
+        self.variants.extend(variants);
+        self
+    }
+
+    /// Determines whether a game with the given speed and variant matches this route. If this
+    /// route is restricted to certain speeds or variants, an unknown speed or variant,
+    /// respectively, does not match.
+    pub fn matches(&self, speed: Option<Speed>, variant: Option<Variant>) -> bool {
+        let speed_matches = self.speeds.is_empty() ||
+            speed.is_some_and(|speed| self.speeds.contains(&speed));
+        let variant_matches = self.variants.is_empty() ||
+            variant.is_some_and(|variant| self.variants.contains(&variant));
+
+        speed_matches && variant_matches
+    }
+}
+
+/// A [Bot] which dispatches every event to one of multiple inner bots based on the speed and
+/// variant of the game or challenge it concerns. This allows running different strategies on one
+/// account, e.g. a book-only bot for ultra bullet and an engine bot for rapid games. Routes are
+/// checked in the order in which they were added and the bot of the first matching [Route]
+/// handles the event. If no route matches, the fallback bot handles it.
+///
+/// Declined challenges only carry the ID of the challenge, so they are dispatched to the bot
+/// which handled the challenge when it was created, or the fallback bot if it is unknown.
+pub struct SpeedRouter {
+    routes: Vec<(Route, Box<dyn Bot + Send>)>,
+    fallback: Box<dyn Bot + Send>,
+    challenge_routes: Mutex<HashMap<GameId, usize>>
+}
+
+impl SpeedRouter {
+
+    /// Creates a new router without routes, which dispatches every event to the given fallback
+    /// bot until routes are added with [SpeedRouter::with_route].
+    pub fn new(fallback: impl Bot + Send + 'static) -> SpeedRouter {
+        SpeedRouter {
+            routes: Vec::new(),
+            fallback: Box::new(fallback),
+            challenge_routes: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Adds a route which dispatches events concerning games that match the given [Route] to the
+    /// given bot, unless a previously added route matches as well. The router is returned for
+    /// chaining.
+    pub fn with_route(mut self, route: Route, bot: impl Bot + Send + 'static) -> SpeedRouter {
+        self.routes.push((route, Box::new(bot)));
+        self
+    }
+
+    fn route_index(&self, speed: Option<Speed>, variant: Option<Variant>) -> usize {
+        self.routes.iter()
+            .position(|(route, _)| route.matches(speed, variant))
+            .unwrap_or(self.routes.len())
+    }
+
+    fn bot(&self, index: usize) -> &dyn Bot {
+        match self.routes.get(index) {
+            Some((_, bot)) => bot.as_ref(),
+            None => self.fallback.as_ref()
+        }
+    }
+
+    fn route(&self, speed: Option<Speed>, variant: Option<Variant>) -> &dyn Bot {
+        self.bot(self.route_index(speed, variant))
+    }
+
+    fn game_route(&self, context: &GameContext) -> &dyn Bot {
+        self.route(Some(context.speed), context.variant)
+    }
+}
+
+#[async_trait::async_trait]
+impl Bot for SpeedRouter {
+
+    async fn on_game_start(&self, context: &BotContext, game: GameStartFinish,
+            client: &BotClient) {
+        // An accepted challenge becomes a game with the same ID, so it can no longer be declined.
+        if let Some(game_id) = &game.id {
+            self.challenge_routes.lock().unwrap().remove(game_id);
+        }
+
+        self.route(game.speed, game.variant).on_game_start(context, game, client).await
+    }
+
+    async fn on_game_finish(&self, context: &BotContext, game: GameStartFinish,
+            result: GameResult, client: &BotClient) {
+        self.route(game.speed, game.variant).on_game_finish(context, game, result, client).await
+    }
+
+    async fn on_rating_update(&self, context: &BotContext, game: GameStartFinish,
+            update: RatingUpdate, client: &BotClient) {
+        self.route(game.speed, game.variant).on_rating_update(context, game, update, client).await
+    }
+
+    async fn on_challenge(&self, context: &BotContext, challenge: Challenge, client: &BotClient) {
+        let index = self.route_index(Some(challenge.speed), challenge.variant);
+        self.challenge_routes.lock().unwrap().insert(challenge.id.clone(), index);
+        self.bot(index).on_challenge(context, challenge, client).await
+    }
+
+    async fn on_rematch_offer(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        let index = self.route_index(Some(challenge.speed), challenge.variant);
+        self.challenge_routes.lock().unwrap().insert(challenge.id.clone(), index);
+        self.bot(index).on_rematch_offer(context, challenge, client).await
+    }
+
+    async fn on_challenge_cancelled(&self, context: &BotContext, challenge: Challenge,
+            client: &BotClient) {
+        self.challenge_routes.lock().unwrap().remove(&challenge.id);
+        self.route(Some(challenge.speed), challenge.variant)
+            .on_challenge_cancelled(context, challenge, client).await
+    }
+
+    async fn on_challenge_declined(&self, context: &BotContext, challenge: ChallengeDeclined,
+            client: &BotClient) {
+        let index = self.challenge_routes.lock().unwrap().remove(&challenge.id)
+            .unwrap_or(self.routes.len());
+        self.bot(index).on_challenge_declined(context, challenge, client).await
+    }
+
+    async fn on_tournament_pairing(&self, context: &BotContext, pairing: TournamentPairing,
+            client: &BotClient) {
+        self.route(pairing.speed, pairing.variant)
+            .on_tournament_pairing(context, pairing, client).await
+    }
+
+    async fn on_error(&self, context: &BotContext, game_id: Option<GameId>,
+            error: RunnerError, client: &BotClient) {
+        for index in 0..=self.routes.len() {
+            self.bot(index).on_error(context, game_id.clone(), error.clone(), client).await;
+        }
+    }
+
+    async fn on_game_state(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.game_route(context).on_game_state(context, state, client).await
+    }
+
+    async fn on_chat_line(&self, context: &GameContext, chat_line: ChatLineEvent,
+            client: &BotClient) {
+        self.game_route(context).on_chat_line(context, chat_line, client).await
+    }
+
+    async fn on_opponent_gone(&self, context: &GameContext, opponent_gone: OpponentGoneEvent,
+            client: &BotClient) {
+        self.game_route(context).on_opponent_gone(context, opponent_gone, client).await
+    }
+
+    async fn on_claimable_win(&self, context: &GameContext, client: &BotClient) {
+        self.game_route(context).on_claimable_win(context, client).await
+    }
+
+    async fn on_invalid_position(&self, context: &GameContext, warning: InvalidPosition,
+            client: &BotClient) {
+        self.game_route(context).on_invalid_position(context, warning, client).await
+    }
+
+    async fn on_my_turn(&self, context: &GameContext, state: GameStateEvent, client: &BotClient) {
+        self.game_route(context).on_my_turn(context, state, client).await
+    }
+
+    async fn on_draw_offered(&self, context: &GameContext, color: Color, client: &BotClient) {
+        self.game_route(context).on_draw_offered(context, color, client).await
+    }
+
+    async fn on_takeback_proposed(&self, context: &GameContext, color: Color,
+            client: &BotClient) {
+        self.game_route(context).on_takeback_proposed(context, color, client).await
+    }
+
+    async fn on_draw_offer(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.game_route(context).on_draw_offer(context, state, client).await
+    }
+
+    async fn on_takeback_proposal(&self, context: &GameContext, state: GameStateEvent,
+            client: &BotClient) {
+        self.game_route(context).on_takeback_proposal(context, state, client).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::client::BotClientBuilder;
+    use crate::by_address::ByAddress;
+    use crate::model::game::GameInfo;
+    use crate::store::tests as store_tests;
+
+    use super::*;
+
+    type Log = Arc<Mutex<Vec<(&'static str, &'static str)>>>;
+
+    struct RecordingBot {
+        name: &'static str,
+        log: Log
+    }
+
+    impl RecordingBot {
+        fn record(&self, hook: &'static str) {
+            self.log.lock().unwrap().push((self.name, hook));
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for RecordingBot {
+        async fn on_challenge(&self, _: &BotContext, _: Challenge, _: &BotClient) {
+            self.record("challenge");
+        }
+
+        async fn on_rematch_offer(&self, _: &BotContext, _: Challenge, _: &BotClient) {
+            self.record("rematchOffer");
+        }
+
+        async fn on_challenge_declined(&self, _: &BotContext, _: ChallengeDeclined,
+                _: &BotClient) {
+            self.record("challengeDeclined");
+        }
+
+        async fn on_game_start(&self, _: &BotContext, _: GameStartFinish, _: &BotClient) {
+            self.record("gameStart");
+        }
+
+        async fn on_game_state(&self, _: &GameContext, _: GameStateEvent, _: &BotClient) {
+            self.record("gameState");
+        }
+    }
+
+    fn test_router(log: &Log) -> SpeedRouter {
+        let bot = |name| RecordingBot {
+            name,
+            log: Arc::clone(log)
+        };
+
+        SpeedRouter::new(bot("fallback"))
+            .with_route(Route::speeds([Speed::UltraBullet, Speed::Bullet]), bot("fast"))
+            .with_route(Route::variants([Variant::Atomic]).with_speeds([Speed::Blitz]),
+                bot("atomicBlitz"))
+            .with_route(Route::speeds([Speed::Rapid]), bot("rapid"))
+    }
+
+    fn bot_context() -> BotContext {
+        BotContext {
+            bot_id: "testBotId".to_owned(),
+            profile: ByAddress::default()
+        }
+    }
+
+    fn test_client() -> BotClient {
+        BotClientBuilder::new().with_token("").build().unwrap()
+    }
+
+    fn test_challenge(id: &str, speed: &str, variant: &str) -> Challenge {
+        serde_json::from_str(&format!(r#"{{
+            "id": "{id}",
+            "url": "testUrl",
+            "status": "created",
+            "challenger": {{ "id": "testChallengerId", "name": "testChallengerName" }},
+            "variant": {{ "key": "{variant}" }},
+            "rated": false,
+            "speed": "{speed}",
+            "timeControl": {{ "type": "unlimited" }},
+            "color": "random",
+            "perf": {{ }}
+        }}"#)).unwrap()
+    }
+
+    fn game_context(speed: Speed, variant: Option<Variant>) -> GameContext {
+        GameContext {
+            bot_color: None,
+            bot_id: "testBotId".to_owned(),
+            info: GameInfo {
+                speed,
+                variant,
+                ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    #[rstest]
+    #[case::empty_route(Route::any(), None, None, true)]
+    #[case::matching_speed(Route::speeds([Speed::Blitz]), Some(Speed::Blitz), None, true)]
+    #[case::other_speed(Route::speeds([Speed::Blitz]), Some(Speed::Rapid), None, false)]
+    #[case::unknown_speed(Route::speeds([Speed::Blitz]), None, None, false)]
+    #[case::matching_variant(
+        Route::variants([Variant::Horde]), Some(Speed::Rapid), Some(Variant::Horde), true)]
+    #[case::other_variant(
+        Route::variants([Variant::Horde]), None, Some(Variant::Standard), false)]
+    #[case::speed_and_variant(
+        Route::speeds([Speed::Bullet]).with_variants([Variant::Atomic]),
+        Some(Speed::Bullet),
+        Some(Variant::Standard),
+        false
+    )]
+    fn route_matches(#[case] route: Route, #[case] speed: Option<Speed>,
+            #[case] variant: Option<Variant>, #[case] expected: bool) {
+        assert_that!(route.matches(speed, variant)).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::ultra_bullet(Speed::UltraBullet, Some(Variant::Standard), "fast")]
+    #[case::bullet_atomic(Speed::Bullet, Some(Variant::Atomic), "fast")]
+    #[case::blitz_atomic(Speed::Blitz, Some(Variant::Atomic), "atomicBlitz")]
+    #[case::blitz_standard(Speed::Blitz, Some(Variant::Standard), "fallback")]
+    #[case::rapid(Speed::Rapid, None, "rapid")]
+    #[case::classical(Speed::Classical, Some(Variant::Standard), "fallback")]
+    fn game_events_are_routed_by_speed_and_variant(#[case] speed: Speed,
+            #[case] variant: Option<Variant>, #[case] expected_bot: &'static str) {
+        let log = Log::default();
+        let router = test_router(&log);
+        let state = serde_json::from_str(
+            r#"{"moves":"","wtime":0,"btime":0,"winc":0,"binc":0,"status":"started"}"#)
+            .unwrap();
+
+        tokio_test::block_on(
+            router.on_game_state(&game_context(speed, variant), state, &test_client()));
+
+        assert_that!(log.lock().unwrap().clone())
+            .contains_exactly_in_given_order([(expected_bot, "gameState")]);
+    }
+
+    #[test]
+    fn game_start_is_routed_by_speed() {
+        let log = Log::default();
+        let router = test_router(&log);
+        let game = serde_json::from_str(r#"{"id":"testGameId","speed":"rapid"}"#).unwrap();
+
+        tokio_test::block_on(router.on_game_start(&bot_context(), game, &test_client()));
+
+        assert_that!(log.lock().unwrap().clone())
+            .contains_exactly_in_given_order([("rapid", "gameStart")]);
+    }
+
+    #[test]
+    fn declined_challenge_is_routed_to_bot_which_handled_challenge() {
+        let log = Log::default();
+        let router = test_router(&log);
+        let client = test_client();
+        let declined = |id: &str| ChallengeDeclined {
+            id: id.to_owned()
+        };
+
+        tokio_test::block_on(async {
+            router.on_challenge(&bot_context(), test_challenge("fastId", "bullet", "standard"),
+                &client).await;
+            router.on_challenge_declined(&bot_context(), declined("fastId"), &client).await;
+            router.on_challenge_declined(&bot_context(), declined("unknownId"), &client).await;
+        });
+
+        assert_that!(log.lock().unwrap().clone()).contains_exactly_in_given_order([
+            ("fast", "challenge"),
+            ("fast", "challengeDeclined"),
+            ("fallback", "challengeDeclined")
+        ]);
+    }
+
+    #[test]
+    fn rematch_offer_is_routed_by_speed_and_variant() {
+        let log = Log::default();
+        let router = test_router(&log);
+        let client = test_client();
+
+        tokio_test::block_on(async {
+            router.on_rematch_offer(&bot_context(), test_challenge("atomicId", "blitz", "atomic"),
+                &client).await;
+            router.on_challenge_declined(&bot_context(), ChallengeDeclined {
+                id: "atomicId".to_owned()
+            }, &client).await;
+        });
+
+        assert_that!(log.lock().unwrap().clone()).contains_exactly_in_given_order([
+            ("atomicBlitz", "rematchOffer"),
+            ("atomicBlitz", "challengeDeclined")
+        ]);
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tokio::sync::Notify;
+
+/// Configures how the [BotRunner](crate::runner::BotRunner) limits the work it does for the events
+/// of each game, so slow bot handlers cannot make memory usage and latency grow without bounds.
+/// Set using [BotRunner::with_backpressure](crate::runner::BotRunner::with_backpressure).
+///
+/// For each game, at most the configured number of handlers run at the same time. Further events
+/// wait in a queue of the configured capacity. If the queue is full, reading from the game event
+/// stream pauses until there is space again, unless the overflow policy makes room, i.e. by
+/// replacing a queued game state with a newer one or by dropping the oldest queued chat line.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BackpressureConfig {
+    pub(crate) capacity: usize,
+    pub(crate) max_concurrent_handlers: usize,
+    pub(crate) coalesce_game_states: bool,
+    pub(crate) latest_game_state_only: bool,
+    pub(crate) drop_stale_chat: bool
+}
+
+impl BackpressureConfig {
+
+    /// Creates a new config which pauses reading events while the queue is full, without
+    /// coalescing game states or dropping chat lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: The maximum number of events of a game waiting for a handler. Values below 1
+    ///   are treated as 1.
+    /// * `max_concurrent_handlers`: The maximum number of handlers running at the same time for
+    ///   events of the same game. Values below 1 are treated as 1.
+    pub fn new(capacity: usize, max_concurrent_handlers: usize) -> BackpressureConfig {
+        BackpressureConfig {
+            capacity: capacity.max(1),
+            max_concurrent_handlers: max_concurrent_handlers.max(1),
+            coalesce_game_states: false,
+            latest_game_state_only: false,
+            drop_stale_chat: false
+        }
+    }
+
+    /// Sets whether a game state arriving while the queue is full replaces the most recent queued
+    /// game state, if that was the last queued event. Only game states which neither start a turn
+    /// of the bot nor carry new offers are replaced, so [Bot::on_my_turn](crate::Bot::on_my_turn)
+    /// and the offer policies still see every relevant state. The number of replaced states is
+    /// reported in [GameStateEvent::skipped_states](crate::model::game::event::GameStateEvent).
+    /// The config is returned for chaining.
+    pub fn with_game_state_coalescing(mut self, enabled: bool) -> BackpressureConfig {
+        self.coalesce_game_states = enabled;
+        self
+    }
+
+    /// Sets whether a game state replaces the most recent queued game state even if the queue is
+    /// not full, so that once a handler becomes available, only the newest of several game states
+    /// which arrived while all handlers were busy is delivered. This is useful for bots which think
+    /// for a long time, for which processing intermediate states one by one is pointless. The same
+    /// restrictions as for [BackpressureConfig::with_game_state_coalescing] apply. The config is
+    /// returned for chaining.
+    pub fn with_latest_game_state_only(mut self, enabled: bool) -> BackpressureConfig {
+        self.latest_game_state_only = enabled;
+        self
+    }
+
+    /// Sets whether the oldest queued chat line is dropped to make room for a new event while the
+    /// queue is full. The config is returned for chaining.
+    pub fn with_stale_chat_dropping(mut self, enabled: bool) -> BackpressureConfig {
+        self.drop_stale_chat = enabled;
+        self
+    }
+}
+
+/// The kind of an event in an [EventQueue], which determines how it is treated on overflow.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum QueuedEventKind {
+
+    /// A game state which may be replaced by a newer one.
+    GameState,
+
+    /// A chat line which may be dropped.
+    ChatLine,
+
+    /// Any other event, which is always handled.
+    Other
+}
+
+#[derive(Debug)]
+struct QueuedEvent<T> {
+    kind: QueuedEventKind,
+    skipped: usize,
+    item: T
+}
+
+#[derive(Debug)]
+struct QueueState<T> {
+    items: VecDeque<QueuedEvent<T>>,
+    closed: bool
+}
+
+/// A queue of the pending event handlers of a single game, which applies the overflow policy of a
+/// [BackpressureConfig]. Intended for one producer and one consumer.
+#[derive(Debug)]
+pub(crate) struct EventQueue<T> {
+    capacity: usize,
+    coalesce_game_states: bool,
+    latest_game_state_only: bool,
+    drop_stale_chat: bool,
+    state: Mutex<QueueState<T>>,
+    item_available: Notify,
+    space_available: Notify
+}
+
+impl<T> EventQueue<T> {
+
+    pub(crate) fn new(config: Option<&BackpressureConfig>) -> EventQueue<T> {
+        EventQueue {
+            capacity: config.map_or(usize::MAX, |config| config.capacity),
+            coalesce_game_states: config.is_some_and(|config| config.coalesce_game_states),
+            latest_game_state_only: config.is_some_and(|config| config.latest_game_state_only),
+            drop_stale_chat: config.is_some_and(|config| config.drop_stale_chat),
+            state: Mutex::new(QueueState {
+                items: VecDeque::new(),
+                closed: false
+            }),
+            item_available: Notify::new(),
+            space_available: Notify::new()
+        }
+    }
+
+    /// Tries to add the given item, applying the overflow policy if the queue is full.
+    ///
+    /// # Returns
+    ///
+    /// The item if it could not be added, because the queue is full.
+    fn try_push(&self, kind: QueuedEventKind, item: T) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        let items = &mut state.items;
+        let full = items.len() >= self.capacity;
+        let coalescable = (self.latest_game_state_only || full && self.coalesce_game_states)
+            && kind == QueuedEventKind::GameState
+            && items.back().is_some_and(|event| event.kind == QueuedEventKind::GameState);
+        let mut skipped = 0;
+
+        if coalescable {
+            skipped = items.pop_back().map_or(0, |event| event.skipped + 1);
+        }
+        else if full {
+            let stale_chat = items.iter()
+                .position(|event| event.kind == QueuedEventKind::ChatLine)
+                .filter(|_| self.drop_stale_chat);
+
+            match stale_chat {
+                Some(index) => {
+                    items.remove(index);
+                },
+                None => return Some(item)
+            }
+        }
+
+        items.push_back(QueuedEvent {
+            kind,
+            skipped,
+            item
+        });
+        drop(state);
+        self.item_available.notify_one();
+
+        None
+    }
+
+    /// Adds the given item, waiting for space if the queue is full and the overflow policy
+    /// cannot make room.
+    pub(crate) async fn push(&self, kind: QueuedEventKind, item: T) {
+        let mut item = item;
+
+        loop {
+            let space_available = self.space_available.notified();
+
+            match self.try_push(kind, item) {
+                Some(rejected) => item = rejected,
+                None => return
+            }
+
+            space_available.await;
+        }
+    }
+
+    /// Marks that no more items will be added. Items already in the queue are still returned by
+    /// [EventQueue::pop].
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.item_available.notify_one();
+    }
+
+    /// Removes the oldest item, waiting for one if the queue is empty.
+    ///
+    /// # Returns
+    ///
+    /// The oldest item together with the number of items it replaced, or [None] if the queue is
+    /// empty and closed.
+    pub(crate) async fn pop(&self) -> Option<(T, usize)> {
+        loop {
+            let item_available = self.item_available.notified();
+
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if let Some(event) = state.items.pop_front() {
+                    drop(state);
+                    self.space_available.notify_one();
+
+                    return Some((event.item, event.skipped));
+                }
+
+                if state.closed {
+                    return None;
+                }
+            }
+
+            item_available.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use futures::future;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn queue(coalesce_game_states: bool, drop_stale_chat: bool) -> EventQueue<u32> {
+        let config = BackpressureConfig::new(2, 1)
+            .with_game_state_coalescing(coalesce_game_states)
+            .with_stale_chat_dropping(drop_stale_chat);
+
+        EventQueue::new(Some(&config))
+    }
+
+    async fn drain_with_skipped(queue: &EventQueue<u32>) -> Vec<(u32, usize)> {
+        queue.close();
+
+        let mut items = Vec::new();
+
+        while let Some(item) = queue.pop().await {
+            items.push(item);
+        }
+
+        items
+    }
+
+    async fn drain(queue: &EventQueue<u32>) -> Vec<u32> {
+        drain_with_skipped(queue).await.into_iter()
+            .map(|(item, _)| item)
+            .collect()
+    }
+
+    #[test]
+    fn consecutive_game_states_are_coalesced_when_full() {
+        tokio_test::block_on(async {
+            let queue = queue(true, false);
+
+            queue.push(QueuedEventKind::Other, 1).await;
+            queue.push(QueuedEventKind::GameState, 2).await;
+            queue.push(QueuedEventKind::GameState, 3).await;
+
+            assert_that!(drain_with_skipped(&queue).await)
+                .contains_exactly_in_given_order([(1, 0), (3, 1)]);
+        });
+    }
+
+    #[test]
+    fn only_latest_game_state_is_kept_before_queue_is_full() {
+        tokio_test::block_on(async {
+            let config = BackpressureConfig::new(10, 1).with_latest_game_state_only(true);
+            let queue = EventQueue::new(Some(&config));
+
+            queue.push(QueuedEventKind::GameState, 1).await;
+            queue.push(QueuedEventKind::GameState, 2).await;
+            queue.push(QueuedEventKind::GameState, 3).await;
+            queue.push(QueuedEventKind::Other, 4).await;
+            queue.push(QueuedEventKind::GameState, 5).await;
+
+            assert_that!(drain_with_skipped(&queue).await)
+                .contains_exactly_in_given_order([(3, 2), (4, 0), (5, 0)]);
+        });
+    }
+
+    #[test]
+    fn oldest_chat_line_is_dropped_when_full() {
+        tokio_test::block_on(async {
+            let queue = queue(false, true);
+
+            queue.push(QueuedEventKind::ChatLine, 1).await;
+            queue.push(QueuedEventKind::Other, 2).await;
+            queue.push(QueuedEventKind::Other, 3).await;
+
+            assert_that!(drain(&queue).await).contains_exactly_in_given_order([2, 3]);
+        });
+    }
+
+    #[test]
+    fn push_waits_for_space_without_overflow_policy() {
+        tokio_test::block_on(async {
+            let queue = queue(false, false);
+
+            queue.push(QueuedEventKind::GameState, 1).await;
+            queue.push(QueuedEventKind::ChatLine, 2).await;
+
+            let blocked = tokio::time::timeout(
+                Duration::from_millis(50), queue.push(QueuedEventKind::GameState, 3)).await;
+
+            assert_that!(blocked).is_err();
+
+            let (popped, ()) = future::join(queue.pop(),
+                queue.push(QueuedEventKind::GameState, 4)).await;
+
+            assert_that!(popped).contains((1, 0));
+            assert_that!(drain(&queue).await).contains_exactly_in_given_order([2, 4]);
+        });
+    }
+
+    #[test]
+    fn unbounded_queue_never_waits() {
+        tokio_test::block_on(async {
+            let queue = EventQueue::new(None);
+
+            for item in 0..1000 {
+                queue.push(QueuedEventKind::Other, item).await;
+            }
+
+            assert_that!(drain(&queue).await.len()).is_equal_to(1000);
+        });
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use tokio::sync::watch::{self, Sender};
+
+use crate::model::game::GameId;
+
+/// Cancels the pending requests of a game once it ends. Shared between the runner, which reports
+/// when games start and end, and the [GameContext](crate::context::GameContext) of each game,
+/// which runs the requests concerning the running game, such as moves, with
+/// [GameCancellation::run].
+#[derive(Debug, Default)]
+pub(crate) struct GameCancellation {
+    games: Mutex<HashMap<GameId, Sender<()>>>
+}
+
+impl GameCancellation {
+
+    pub(crate) fn game_started(&self, game_id: &GameId) {
+        self.games.lock().unwrap().entry(game_id.clone()).or_insert_with(|| watch::channel(()).0);
+    }
+
+    /// Cancels all requests of the game with the given ID which are still pending. Requests which
+    /// are started afterwards, such as a message in the chat after the game, are not affected.
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.games.lock().unwrap().remove(game_id);
+    }
+
+    /// Runs the given request of the game with the given ID until it completes or the game ends.
+    ///
+    /// # Returns
+    ///
+    /// The output of the request, or [None] if the game ended before it completed. Requests of
+    /// games which are not running are always run to completion.
+    pub(crate) async fn run<F>(&self, game_id: &GameId, request: F) -> Option<F::Output>
+    where
+        F: Future
+    {
+        let receiver = self.games.lock().unwrap().get(game_id).map(Sender::subscribe);
+
+        let Some(mut receiver) = receiver
+        else {
+            return Some(request.await);
+        };
+
+        tokio::select! {
+            output = request => Some(output),
+            _ = receiver.changed() => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn game_id() -> GameId {
+        "testGameId".to_owned()
+    }
+
+    #[tokio::test]
+    async fn request_of_running_game_is_completed() {
+        let cancellation = GameCancellation::default();
+        cancellation.game_started(&game_id());
+
+        assert_that!(cancellation.run(&game_id(), async { 42 }).await).contains(42);
+    }
+
+    #[tokio::test]
+    async fn pending_request_is_cancelled_when_game_finishes() {
+        let cancellation = GameCancellation::default();
+        cancellation.game_started(&game_id());
+
+        let game_id = game_id();
+        let request = cancellation.run(&game_id, tokio::time::sleep(Duration::from_secs(10)));
+        let finish = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancellation.game_finished(&game_id);
+        };
+        let (output, _) = tokio::join!(request, finish);
+
+        assert_that!(output).is_none();
+    }
+
+    #[tokio::test]
+    async fn request_after_game_finished_is_completed() {
+        let cancellation = GameCancellation::default();
+        cancellation.game_started(&game_id());
+        cancellation.game_finished(&game_id());
+
+        assert_that!(cancellation.run(&game_id(), async { 42 }).await).contains(42);
+    }
+
+    #[tokio::test]
+    async fn request_of_other_game_is_not_cancelled() {
+        let cancellation = GameCancellation::default();
+        cancellation.game_started(&game_id());
+        cancellation.game_started(&"otherGameId".to_owned());
+
+        let game_id = game_id();
+        let request = cancellation.run(&game_id, async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            42
+        });
+        let finish = async {
+            cancellation.game_finished(&"otherGameId".to_owned());
+        };
+        let (output, _) = tokio::join!(request, finish);
+
+        assert_that!(output).contains(42);
+    }
+}
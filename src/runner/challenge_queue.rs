@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::model::challenge::{Challenge, DeclineReason};
+use crate::model::game::GameId;
+
+/// The decision a [ChallengePolicy] makes about an incoming challenge.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ChallengeDecision {
+
+    /// Puts the challenge into the challenge queue, so it is accepted once the bot has a free
+    /// game slot. Challenges with higher `priority` are accepted first if the queue uses
+    /// [QueueOrdering::Priority], otherwise the priority is ignored.
+    Enqueue {
+        priority: i32
+    },
+
+    /// Declines the challenge immediately, optionally with the given reason.
+    Decline(Option<DeclineReason>),
+
+    /// Neither queues nor declines the challenge, leaving it to
+    /// [Bot::on_challenge](crate::Bot::on_challenge).
+    Ignore
+}
+
+/// A policy which decides for every incoming challenge whether it is queued for acceptance or
+/// declined. This is implemented for all closures taking a [Challenge] reference and returning a
+/// [ChallengeDecision].
+pub trait ChallengePolicy : Send + Sync {
+
+    /// Decides what should happen to the given incoming challenge.
+    ///
+    /// # Arguments
+    ///
+    /// * `challenge`: The incoming challenge to decide on.
+    ///
+    /// # Returns
+    ///
+    /// A [ChallengeDecision] determining how the runner handles the challenge.
+    fn decide(&self, challenge: &Challenge) -> ChallengeDecision;
+}
+
+impl<F> ChallengePolicy for F
+where
+    F: Fn(&Challenge) -> ChallengeDecision + Send + Sync
+{
+    fn decide(&self, challenge: &Challenge) -> ChallengeDecision {
+        self(challenge)
+    }
+}
+
+/// The order in which queued challenges are accepted.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueOrdering {
+
+    /// Challenges are accepted in the order in which they were received.
+    #[default]
+    Fifo,
+
+    /// Challenges with the highest priority assigned by the [ChallengePolicy] are accepted first.
+    /// Challenges with equal priority are accepted in the order in which they were received.
+    Priority
+}
+
+/// Configuration of the challenge queue of a [BotRunner](crate::runner::BotRunner). Incoming
+/// challenges that the [ChallengePolicy] wants to accept are queued and only accepted once the
+/// bot plays less than the configured maximum number of concurrent games.
+#[derive(Clone)]
+pub struct ChallengeQueueConfig {
+    pub(crate) policy: Arc<dyn ChallengePolicy>,
+    pub(crate) max_concurrent_games: usize,
+    pub(crate) ordering: QueueOrdering,
+    pub(crate) expiry: Option<Duration>,
+    pub(crate) accept_timeout: Duration,
+    pub(crate) sweep_interval: Duration
+}
+
+/// The maximum number of concurrent games used by default, if no other value is provided using
+/// [ChallengeQueueConfig::with_max_concurrent_games].
+pub const DEFAULT_MAX_CONCURRENT_GAMES: usize = 1;
+
+/// The time after which an accepted challenge whose game has not started is no longer considered
+/// pending used by default, if no other value is provided using
+/// [ChallengeQueueConfig::with_accept_timeout].
+pub const DEFAULT_ACCEPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The interval in which the queue is checked for expired challenges and timed out accepts used
+/// by default, if no other value is provided using [ChallengeQueueConfig::with_sweep_interval].
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+impl ChallengeQueueConfig {
+
+    /// Creates a new challenge queue configuration using the given policy. By default, at most
+    /// [DEFAULT_MAX_CONCURRENT_GAMES] games are played concurrently, challenges are accepted in
+    /// [QueueOrdering::Fifo] order and never expire, accepts time out after
+    /// [DEFAULT_ACCEPT_TIMEOUT], and the queue is swept every [DEFAULT_SWEEP_INTERVAL].
+    ///
+    /// # Arguments
+    ///
+    /// * `policy`: The [ChallengePolicy] deciding which incoming challenges to queue.
+    pub fn new(policy: impl ChallengePolicy + 'static) -> ChallengeQueueConfig {
+        ChallengeQueueConfig {
+            policy: Arc::new(policy),
+            max_concurrent_games: DEFAULT_MAX_CONCURRENT_GAMES,
+            ordering: QueueOrdering::default(),
+            expiry: None,
+            accept_timeout: DEFAULT_ACCEPT_TIMEOUT,
+            sweep_interval: DEFAULT_SWEEP_INTERVAL
+        }
+    }
+
+    /// Sets the maximum number of games the bot plays at the same time. Queued challenges are
+    /// only accepted while fewer games are running. The config is returned for chaining.
+    pub fn with_max_concurrent_games(mut self, max_concurrent_games: usize)
+            -> ChallengeQueueConfig {
+        self.max_concurrent_games = max_concurrent_games;
+        self
+    }
+
+    /// Sets the order in which queued challenges are accepted. The config is returned for
+    /// chaining.
+    pub fn with_ordering(mut self, ordering: QueueOrdering) -> ChallengeQueueConfig {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Sets the time after which a queued challenge that has not been accepted yet is declined
+    /// with [DeclineReason::Later]. The config is returned for chaining.
+    pub fn with_expiry(mut self, expiry: Duration) -> ChallengeQueueConfig {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    /// Sets the time after which an accepted challenge whose game has not started yet no longer
+    /// occupies a game slot, e.g. because Lichess never sent the start event. The config is
+    /// returned for chaining.
+    pub fn with_accept_timeout(mut self, accept_timeout: Duration) -> ChallengeQueueConfig {
+        self.accept_timeout = accept_timeout;
+        self
+    }
+
+    /// Sets the interval in which the runner declines expired challenges, frees the slots of
+    /// timed out accepts, and accepts queued challenges for which slots became free, independent
+    /// of incoming events. The config is returned for chaining.
+    pub fn with_sweep_interval(mut self, sweep_interval: Duration) -> ChallengeQueueConfig {
+        self.sweep_interval = sweep_interval;
+        self
+    }
+}
+
+struct QueuedChallenge {
+    challenge: Challenge,
+    priority: i32,
+    queued_at: Instant,
+    sequence: u64
+}
+
+pub(crate) struct ChallengeQueue {
+    config: ChallengeQueueConfig,
+    queued: Vec<QueuedChallenge>,
+    pending_accepts: HashMap<GameId, Instant>,
+    next_sequence: u64
+}
+
+impl ChallengeQueue {
+
+    pub(crate) fn new(config: ChallengeQueueConfig) -> ChallengeQueue {
+        ChallengeQueue {
+            config,
+            queued: Vec::new(),
+            pending_accepts: HashMap::new(),
+            next_sequence: 0
+        }
+    }
+
+    pub(crate) fn decide(&self, challenge: &Challenge) -> ChallengeDecision {
+        self.config.policy.decide(challenge)
+    }
+
+    pub(crate) fn enqueue(&mut self, challenge: Challenge, priority: i32, now: Instant) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.queued.push(QueuedChallenge {
+            challenge,
+            priority,
+            queued_at: now,
+            sequence
+        });
+    }
+
+    /// Removes the challenge with the given ID from the queue, e.g. because it was cancelled by
+    /// the challenger.
+    pub(crate) fn remove(&mut self, challenge_id: &GameId) {
+        self.queued.retain(|queued| &queued.challenge.id != challenge_id);
+    }
+
+    /// Removes and returns all challenges which have been queued for longer than the configured
+    /// expiry.
+    pub(crate) fn remove_expired(&mut self, now: Instant) -> Vec<Challenge> {
+        let Some(expiry) = self.config.expiry
+        else {
+            return Vec::new();
+        };
+
+        let (expired, queued) = self.queued.drain(..)
+            .partition(|queued| now.saturating_duration_since(queued.queued_at) >= expiry);
+        self.queued = queued;
+
+        expired.into_iter()
+            .map(|expired: QueuedChallenge| expired.challenge)
+            .collect()
+    }
+
+    /// Releases the slots of all accepted challenges which have been pending for at least the
+    /// configured accept timeout, returning their IDs.
+    pub(crate) fn release_timed_out(&mut self, now: Instant) -> Vec<GameId> {
+        let accept_timeout = self.config.accept_timeout;
+        let timed_out = self.pending_accepts.iter()
+            .filter(|(_, accepted_at)|
+                now.saturating_duration_since(**accepted_at) >= accept_timeout)
+            .map(|(challenge_id, _)| challenge_id.clone())
+            .collect::<Vec<_>>();
+
+        for challenge_id in &timed_out {
+            self.pending_accepts.remove(challenge_id);
+        }
+
+        timed_out
+    }
+
+    pub(crate) fn sweep_interval(&self) -> Duration {
+        self.config.sweep_interval
+    }
+
+    fn next_index(&self) -> Option<usize> {
+        let ordering = self.config.ordering;
+
+        self.queued.iter()
+            .enumerate()
+            .min_by_key(|(_, queued)| match ordering {
+                QueueOrdering::Fifo => (0, queued.sequence),
+                QueueOrdering::Priority => (-(queued.priority as i64), queued.sequence)
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Removes and returns as many challenges as can be accepted given the number of currently
+    /// active games. The returned challenges are considered pending until either
+    /// [ChallengeQueue::mark_started] or [ChallengeQueue::release] is called for them, or until
+    /// they time out (see [ChallengeQueue::release_timed_out]).
+    pub(crate) fn pop_acceptable(&mut self, active_games: usize, now: Instant) -> Vec<Challenge> {
+        let mut acceptable = Vec::new();
+
+        while active_games + self.pending_accepts.len() < self.config.max_concurrent_games {
+            let Some(index) = self.next_index()
+            else {
+                break;
+            };
+
+            let challenge = self.queued.remove(index).challenge;
+            self.pending_accepts.insert(challenge.id.clone(), now);
+            acceptable.push(challenge);
+        }
+
+        acceptable
+    }
+
+    /// Marks a previously accepted challenge as started, so it no longer counts as pending.
+    pub(crate) fn mark_started(&mut self, game_id: &GameId) {
+        self.pending_accepts.remove(game_id);
+    }
+
+    /// Releases the slot of a challenge whose acceptance failed.
+    pub(crate) fn release(&mut self, challenge_id: &GameId) {
+        self.pending_accepts.remove(challenge_id);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.queued.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::challenge::{ChallengeColor, ChallengePerf, ChallengeStatus};
+    use crate::model::game::Speed;
+    use crate::model::TimeControl;
+    use crate::model::user::User;
+
+    use super::*;
+
+    fn test_challenge(id: &str) -> Challenge {
+        Challenge {
+            id: id.to_owned(),
+            url: "testUrl".to_owned(),
+            status: ChallengeStatus::Created,
+            challenger: User {
+                rating: None,
+                provisional: false,
+                online: false,
+                id: "testChallengerId".to_owned(),
+                name: "testChallengerName".to_owned(),
+                title: None,
+                patron: false
+            },
+            dest_user: None,
+            variant: None,
+            rated: false,
+            speed: Speed::Blitz,
+            time_control: TimeControl::Unlimited,
+            color: ChallengeColor::Random,
+            perf: ChallengePerf {
+                icon: None,
+                name: None
+            },
+            direction: None,
+            initial_fen: None,
+            rematch_of: None,
+            decline: None
+        }
+    }
+
+    fn test_queue(max_concurrent_games: usize, ordering: QueueOrdering) -> ChallengeQueue {
+        let config = ChallengeQueueConfig::new(|_: &Challenge| ChallengeDecision::Ignore)
+            .with_max_concurrent_games(max_concurrent_games)
+            .with_ordering(ordering)
+            .with_expiry(Duration::from_secs(10));
+
+        ChallengeQueue::new(config)
+    }
+
+    fn ids(challenges: Vec<Challenge>) -> Vec<GameId> {
+        challenges.into_iter().map(|challenge| challenge.id).collect()
+    }
+
+    #[rstest]
+    #[case::fifo(QueueOrdering::Fifo, vec!["first", "second", "third"])]
+    #[case::priority(QueueOrdering::Priority, vec!["second", "third", "first"])]
+    fn pop_acceptable_respects_ordering(#[case] ordering: QueueOrdering,
+            #[case] expected_ids: Vec<&str>) {
+        let now = Instant::now();
+        let mut queue = test_queue(3, ordering);
+        queue.enqueue(test_challenge("first"), 0, now);
+        queue.enqueue(test_challenge("second"), 2, now);
+        queue.enqueue(test_challenge("third"), 2, now);
+
+        let accepted = queue.pop_acceptable(0, now);
+        let expected_ids = expected_ids.into_iter().map(str::to_owned).collect::<Vec<_>>();
+
+        assert_that!(ids(accepted)).contains_exactly_in_given_order(expected_ids);
+    }
+
+    #[test]
+    fn pop_acceptable_respects_concurrent_game_limit() {
+        let now = Instant::now();
+        let mut queue = test_queue(2, QueueOrdering::Fifo);
+        queue.enqueue(test_challenge("first"), 0, now);
+        queue.enqueue(test_challenge("second"), 0, now);
+
+        let accepted = queue.pop_acceptable(1, now);
+
+        assert_that!(ids(accepted)).contains_exactly_in_given_order(["first".to_owned()]);
+        assert_that!(queue.len()).is_equal_to(1);
+    }
+
+    #[test]
+    fn pending_accepts_occupy_slots_until_started_or_released() {
+        let now = Instant::now();
+        let mut queue = test_queue(1, QueueOrdering::Fifo);
+        queue.enqueue(test_challenge("first"), 0, now);
+        queue.enqueue(test_challenge("second"), 0, now);
+        queue.enqueue(test_challenge("third"), 0, now);
+
+        assert_that!(queue.pop_acceptable(0, now)).has_length(1);
+        assert_that!(queue.pop_acceptable(0, now)).is_empty();
+
+        queue.release(&"first".to_owned());
+
+        assert_that!(ids(queue.pop_acceptable(0, now)))
+            .contains_exactly_in_given_order(["second".to_owned()]);
+
+        queue.mark_started(&"second".to_owned());
+
+        assert_that!(queue.pop_acceptable(1, now)).is_empty();
+        assert_that!(ids(queue.pop_acceptable(0, now)))
+            .contains_exactly_in_given_order(["third".to_owned()]);
+    }
+
+    #[test]
+    fn removed_challenges_are_not_accepted() {
+        let now = Instant::now();
+        let mut queue = test_queue(2, QueueOrdering::Fifo);
+        queue.enqueue(test_challenge("first"), 0, now);
+        queue.enqueue(test_challenge("second"), 0, now);
+
+        queue.remove(&"first".to_owned());
+
+        assert_that!(ids(queue.pop_acceptable(0, now)))
+            .contains_exactly_in_given_order(["second".to_owned()]);
+    }
+
+    #[test]
+    fn expired_challenges_are_removed() {
+        let now = Instant::now();
+        let mut queue = test_queue(1, QueueOrdering::Fifo);
+        queue.enqueue(test_challenge("old"), 0, now);
+        queue.enqueue(test_challenge("new"), 0, now + Duration::from_secs(5));
+
+        let expired = queue.remove_expired(now + Duration::from_secs(12));
+
+        assert_that!(ids(expired)).contains_exactly_in_given_order(["old".to_owned()]);
+        assert_that!(queue.len()).is_equal_to(1);
+    }
+
+    #[test]
+    fn timed_out_accepts_release_their_slots() {
+        let now = Instant::now();
+        let mut queue = ChallengeQueue::new(
+            ChallengeQueueConfig::new(|_: &Challenge| ChallengeDecision::Ignore)
+                .with_max_concurrent_games(2)
+                .with_accept_timeout(Duration::from_secs(30)));
+        queue.enqueue(test_challenge("first"), 0, now);
+        queue.enqueue(test_challenge("second"), 0, now);
+        queue.enqueue(test_challenge("third"), 0, now);
+        queue.pop_acceptable(1, now);
+        queue.pop_acceptable(0, now + Duration::from_secs(10));
+
+        assert_that!(queue.release_timed_out(now + Duration::from_secs(29))).is_empty();
+        assert_that!(queue.release_timed_out(now + Duration::from_secs(30)))
+            .contains_exactly_in_given_order(["first".to_owned()]);
+        assert_that!(ids(queue.pop_acceptable(0, now + Duration::from_secs(30))))
+            .contains_exactly_in_given_order(["third".to_owned()]);
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::model::game::{GameId, Speed};
+use crate::runner::parsing::StreamParseError;
+
+/// An error that is reported to [Bot::on_error](crate::Bot::on_error) by a
+/// [BotRunner](crate::runner::BotRunner) without stopping it.
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum RunnerError {
+
+    /// A line received from one of the Lichess event streams could not be parsed and was skipped.
+    /// This is only reported if the runner is configured to skip such lines (see
+    /// [ParsingConfig::with_invalid_line_skipping](crate::runner::parsing::ParsingConfig)).
+    #[error(transparent)]
+    Parse(#[from] StreamParseError),
+
+    /// An event handler panicked, which is only reported if the runner is configured to write
+    /// crash dumps (see [BotRunner::with_crash_dumps](crate::runner::BotRunner::with_crash_dumps)).
+    /// The panic is resumed after this is reported.
+    #[error("event handler panicked: {message}")]
+    HandlerPanicked {
+
+        /// The panic message.
+        message: String,
+
+        /// The path of the written [CrashDump](crate::runner::events::CrashDump), or [None] if it
+        /// could not be written.
+        dump: Option<PathBuf>
+    },
+
+    /// A challenge whose reported speed is inconsistent with its time control, which may indicate
+    /// a change in the Lichess API. This is only reported if the runner is configured to validate
+    /// speeds (see
+    /// [BotRunner::with_speed_validation](crate::runner::BotRunner::with_speed_validation)). The
+    /// challenge is handled as usual.
+    #[error("challenge {challenge_id} reports speed {reported:?}, but its time control is \
+        {expected:?}")]
+    InconsistentSpeed {
+
+        /// The ID of the challenge.
+        challenge_id: GameId,
+
+        /// The speed reported by Lichess.
+        reported: Speed,
+
+        /// The speed derived from the time control of the challenge (see
+        /// [TimeControl::speed](crate::model::TimeControl::speed)).
+        expected: Speed
+    }
+}
@@ -0,0 +1,621 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Debug, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::Stream;
+use futures::stream::{self, StreamExt};
+
+use serde::{Deserialize, Serialize};
+
+use serde_json::Error as JsonError;
+
+use thiserror::Error;
+
+use crate::model::{Milliseconds, Moves, Timestamp};
+use crate::model::bot_event::BotEvent;
+use crate::model::game::GameId;
+use crate::model::game::event::GameEvent;
+use crate::runner::parsing::LineBuffer;
+
+/// An error that occurs when recording or loading an event recording.
+#[derive(Debug, Error)]
+pub enum EventRecordError {
+
+    #[error("error accessing event recording: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("error serializing or deserializing recorded line: {0}")]
+    Json(#[from] JsonError)
+}
+
+pub type EventRecordResult<T> = Result<T, EventRecordError>;
+
+/// A single line of NDJSON received from one of the Lichess event streams, as written by an
+/// [EventRecorder].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedLine {
+
+    /// The time at which the line was received, in milliseconds since the Unix epoch.
+    pub timestamp: Timestamp,
+
+    /// The ID of the game whose game event stream the line was received from, or [None] if it was
+    /// received from the bot's main event stream.
+    pub game_id: Option<GameId>,
+
+    /// The raw line of JSON as it was received.
+    pub line: String
+}
+
+impl RecordedLine {
+
+    fn received_now(game_id: Option<&GameId>, line: &str) -> RecordedLine {
+        RecordedLine {
+            timestamp: now(),
+            game_id: game_id.cloned(),
+            line: line.to_owned()
+        }
+    }
+}
+
+fn now() -> Timestamp {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as Timestamp)
+        .unwrap_or(0)
+}
+
+/// Writes every line received from the event streams of a
+/// [BotRunner](crate::runner::BotRunner) as one [RecordedLine] in JSON to a writer, such as a
+/// file. Register using
+/// [BotRunner::with_event_recorder](crate::runner::BotRunner::with_event_recorder). The recording
+/// can later be loaded as an [EventReplay] for debugging.
+pub struct EventRecorder {
+    writer: Mutex<Box<dyn Write + Send>>
+}
+
+impl EventRecorder {
+
+    /// Creates a new recorder which writes recorded lines to the given writer.
+    pub fn new(writer: impl Write + Send + 'static) -> EventRecorder {
+        EventRecorder {
+            writer: Mutex::new(Box::new(writer))
+        }
+    }
+
+    /// Creates a new recorder which writes recorded lines to the file at the given path. If the
+    /// file already exists, it is overwritten.
+    ///
+    /// # Errors
+    ///
+    /// [EventRecordError::Io] if the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> EventRecordResult<EventRecorder> {
+        Ok(EventRecorder::new(File::create(path)?))
+    }
+
+    /// Records the given line as received now from the event stream of the game with the given
+    /// ID, or the main event stream if the ID is [None].
+    ///
+    /// # Errors
+    ///
+    /// Any [EventRecordError] if the line cannot be written.
+    pub fn record(&self, game_id: Option<&GameId>, line: &str) -> EventRecordResult<()> {
+        let recorded_line = RecordedLine::received_now(game_id, line);
+        let mut json = serde_json::to_string(&recorded_line)?;
+        json.push('\n');
+
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(json.as_bytes())?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Debug for EventRecorder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventRecorder").finish_non_exhaustive()
+    }
+}
+
+/// Wraps the given stream of bytes received from an event stream, such that every complete
+/// non-empty line is recorded by the given recorder and crash dumper, if any. The bytes are passed
+/// on unchanged.
+pub(crate) fn record_stream<B, E>(stream: impl Stream<Item = Result<B, E>>,
+    recorder: Option<Arc<EventRecorder>>, crash_dumper: Option<Arc<CrashDumper>>,
+    game_id: Option<GameId>) -> impl Stream<Item = Result<B, E>>
+where
+    B: AsRef<[u8]>
+{
+    let mut line_buffer = LineBuffer::default();
+    let recording = recorder.is_some() || crash_dumper.is_some();
+
+    stream.map(move |chunk| {
+        if let (true, Ok(bytes)) = (recording, &chunk) {
+            for line in line_buffer.push(bytes.as_ref()).into_iter().flatten() {
+                if let Some(recorder) = &recorder {
+                    // TODO enable error handling
+                    let _ = recorder.record(game_id.as_ref(), &line);
+                }
+
+                if let Some(crash_dumper) = &crash_dumper {
+                    crash_dumper.record(game_id.as_ref(), &line);
+                }
+            }
+        }
+
+        chunk
+    })
+}
+
+/// The state of a game tracked by the [BotRunner](crate::runner::BotRunner) at the time a
+/// [CrashDump] was written.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashDumpGame {
+    pub game_id: GameId,
+
+    /// The moves played in the game so far, in UCI notation separated by spaces.
+    pub moves: Moves,
+
+    /// The remaining time of White, in milliseconds.
+    pub white_time: Milliseconds,
+
+    /// The remaining time of Black, in milliseconds.
+    pub black_time: Milliseconds
+}
+
+/// A debug bundle written by a [CrashDumper] when an event handler of the bot panics. It contains
+/// the last received lines of every event stream and the tracked state of all running games.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashDump {
+
+    /// The time at which the dump was written, in milliseconds since the Unix epoch.
+    pub timestamp: Timestamp,
+
+    /// A description of the crash, such as the panic message.
+    pub reason: String,
+
+    /// The ID of the game whose handler crashed, or [None] if the crash was not associated with a
+    /// game.
+    pub game_id: Option<GameId>,
+
+    /// The tracked state of all games running at the time of the crash.
+    pub games: Vec<CrashDumpGame>,
+
+    /// The last lines received from every event stream, ordered by the time they were received.
+    pub lines: Vec<RecordedLine>
+}
+
+impl CrashDump {
+
+    /// Loads a dump from the file at the given path, which was written by a [CrashDumper].
+    ///
+    /// # Errors
+    ///
+    /// * [EventRecordError::Io] if the file cannot be read.
+    /// * [EventRecordError::Json] if the file is not a valid dump.
+    pub fn open(path: impl AsRef<Path>) -> EventRecordResult<CrashDump> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Creates an [EventReplay] of the lines in this dump, e.g. to reproduce the crash with
+    /// [BotRunner::replay](crate::runner::BotRunner::replay).
+    pub fn replay(&self) -> EventReplay {
+        EventReplay::new(self.lines.clone())
+    }
+}
+
+/// Keeps the last lines received from each event stream of a
+/// [BotRunner](crate::runner::BotRunner) in memory and writes them as a [CrashDump] into a
+/// directory when an event handler panics, to make hard-to-reproduce issues actionable. The path
+/// of the dump is reported to [Bot::on_error](crate::Bot::on_error) as
+/// [RunnerError::HandlerPanicked](crate::runner::error::RunnerError::HandlerPanicked). Register
+/// using
+/// [BotRunner::with_crash_dumps](crate::runner::BotRunner::with_crash_dumps).
+#[derive(Debug)]
+pub struct CrashDumper {
+    directory: PathBuf,
+    lines_per_stream: usize,
+    lines: Mutex<HashMap<Option<GameId>, VecDeque<RecordedLine>>>,
+    dumps: AtomicU64
+}
+
+impl CrashDumper {
+
+    /// Creates a new crash dumper.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory`: The directory into which dumps are written. It is created when the first
+    ///   dump is written.
+    /// * `lines_per_stream`: The number of most recently received lines kept for the main event
+    ///   stream and for the event stream of every game.
+    pub fn new(directory: impl Into<PathBuf>, lines_per_stream: usize) -> CrashDumper {
+        CrashDumper {
+            directory: directory.into(),
+            lines_per_stream,
+            lines: Mutex::new(HashMap::new()),
+            dumps: AtomicU64::new(0)
+        }
+    }
+
+    /// The directory into which dumps are written.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Keeps the given line as received now from the event stream of the game with the given ID,
+    /// or the main event stream if the ID is [None], discarding the oldest line of that stream if
+    /// the limit is exceeded.
+    pub fn record(&self, game_id: Option<&GameId>, line: &str) {
+        if self.lines_per_stream == 0 {
+            return;
+        }
+
+        let mut lines = self.lines.lock().unwrap();
+        let stream_lines = lines.entry(game_id.cloned()).or_default();
+
+        if stream_lines.len() >= self.lines_per_stream {
+            stream_lines.pop_front();
+        }
+
+        stream_lines.push_back(RecordedLine::received_now(game_id, line));
+    }
+
+    /// Discards the lines kept for the game with the given ID, once it is finished.
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.lines.lock().unwrap().remove(&Some(game_id.clone()));
+    }
+
+    /// Writes a [CrashDump] with all kept lines and the given game states into a new file in the
+    /// dump directory.
+    ///
+    /// # Returns
+    ///
+    /// The path of the written file.
+    ///
+    /// # Errors
+    ///
+    /// * [EventRecordError::Io] if the directory or file cannot be created.
+    /// * [EventRecordError::Json] if the dump cannot be serialized.
+    pub(crate) fn dump(&self, reason: String, game_id: Option<GameId>, games: Vec<CrashDumpGame>)
+            -> EventRecordResult<PathBuf> {
+        let mut lines = self.lines.lock().unwrap().values()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|line| line.timestamp);
+
+        let dump = CrashDump {
+            timestamp: now(),
+            reason,
+            game_id,
+            games,
+            lines
+        };
+        let index = self.dumps.fetch_add(1, Ordering::Relaxed);
+        let path = self.directory.join(format!("crash-{}-{index}.json", dump.timestamp));
+
+        fs::create_dir_all(&self.directory)?;
+        fs::write(&path, serde_json::to_string_pretty(&dump)?)?;
+
+        Ok(path)
+    }
+}
+
+/// Determines how an [EventReplay] spaces out the events it feeds back through a bot.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReplayTiming {
+
+    /// All events are replayed as fast as possible, ignoring the recorded timestamps.
+    #[default]
+    Instant,
+
+    /// Every event is replayed after the same delay since the start of the replay as it was
+    /// received after the first recorded line, reproducing the original timing of the session.
+    Recorded,
+
+    /// Like [ReplayTiming::Recorded], but all delays are divided by the given speed factor, i.e.
+    /// `Scaled(2.0)` replays twice as fast as recorded. If the factor is not positive and finite,
+    /// events are replayed instantly.
+    Scaled(f64)
+}
+
+impl ReplayTiming {
+
+    /// Computes the delay after the start of the replay at which a line with the given timestamp
+    /// is replayed, where `first_timestamp` is the timestamp of the first recorded line.
+    fn delay(self, first_timestamp: Timestamp, timestamp: Timestamp) -> Duration {
+        let recorded_delay =
+            Duration::from_millis(timestamp.saturating_sub(first_timestamp).max(0) as u64);
+
+        match self {
+            ReplayTiming::Instant => Duration::ZERO,
+            ReplayTiming::Recorded => recorded_delay,
+            ReplayTiming::Scaled(speed) if speed.is_finite() && speed > 0.0 =>
+                recorded_delay.div_f64(speed),
+            ReplayTiming::Scaled(_) => Duration::ZERO
+        }
+    }
+}
+
+/// A recorded session of event stream lines, as written by an [EventRecorder], which can be fed
+/// back through a bot using [BotRunner::replay](crate::runner::BotRunner::replay) to reproduce
+/// its behavior deterministically. By default, events are replayed instantly. Use
+/// [EventReplay::with_timing] to reproduce the recorded delays between events instead, e.g. to
+/// debug time management.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventReplay {
+    lines: Vec<RecordedLine>,
+    timing: ReplayTiming
+}
+
+impl EventReplay {
+
+    /// Creates a new replay of the given lines.
+    pub fn new(lines: Vec<RecordedLine>) -> EventReplay {
+        EventReplay {
+            lines,
+            timing: ReplayTiming::default()
+        }
+    }
+
+    /// Sets the [ReplayTiming] which determines the delays between replayed events. The replay
+    /// is returned for chaining.
+    pub fn with_timing(mut self, timing: ReplayTiming) -> EventReplay {
+        self.timing = timing;
+        self
+    }
+
+    /// The [ReplayTiming] which determines the delays between replayed events.
+    pub fn timing(&self) -> ReplayTiming {
+        self.timing
+    }
+
+    /// Loads a replay from the file at the given path, which was written by an [EventRecorder].
+    ///
+    /// # Errors
+    ///
+    /// * [EventRecordError::Io] if the file cannot be read.
+    /// * [EventRecordError::Json] if any line of the file is not a valid [RecordedLine].
+    pub fn open(path: impl AsRef<Path>) -> EventRecordResult<EventReplay> {
+        EventReplay::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses a replay from the given content of a recording written by an [EventRecorder].
+    ///
+    /// # Errors
+    ///
+    /// [EventRecordError::Json] if any line is not a valid [RecordedLine].
+    pub fn parse(content: &str) -> EventRecordResult<EventReplay> {
+        let lines = content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(EventReplay::new(lines))
+    }
+
+    /// All recorded lines in the order in which they were received.
+    pub fn lines(&self) -> &[RecordedLine] {
+        &self.lines
+    }
+
+}
+
+/// An [EventReplay] in progress. All event streams of the replay are timed relative to the same
+/// start, so the order of events across streams is preserved when replaying with delays.
+#[derive(Debug)]
+pub(crate) struct ReplaySession {
+    replay: EventReplay,
+    start: Instant
+}
+
+impl ReplaySession {
+
+    pub(crate) fn new(replay: EventReplay, start: Instant) -> ReplaySession {
+        ReplaySession {
+            replay,
+            start
+        }
+    }
+
+    fn events<T>(&self, game_id: Option<&GameId>)
+            -> impl Stream<Item = Result<T, JsonError>> + Send + 'static
+    where
+        T: for<'de> Deserialize<'de> + Send + 'static
+    {
+        let timing = self.replay.timing;
+        let first_timestamp = self.replay.lines.first().map_or(0, |line| line.timestamp);
+        let start = self.start;
+        let events = self.replay.lines.iter()
+            .filter(|line| line.game_id.as_ref() == game_id)
+            .map(|line| {
+                let due = start + timing.delay(first_timestamp, line.timestamp);
+
+                (due, serde_json::from_str(&line.line))
+            })
+            .collect::<Vec<_>>();
+
+        stream::iter(events).then(move |(due, event)| async move {
+            if timing != ReplayTiming::Instant {
+                tokio::time::sleep_until(due.into()).await;
+            }
+
+            event
+        })
+    }
+
+    pub(crate) fn bot_events(&self)
+            -> impl Stream<Item = Result<BotEvent, JsonError>> + Send + 'static {
+        self.events(None)
+    }
+
+    pub(crate) fn game_events(&self, game_id: &GameId)
+            -> impl Stream<Item = Result<GameEvent, JsonError>> + Send + 'static {
+        self.events(Some(game_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedWriter {
+        fn content(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn recorded_stream_passes_bytes_through_and_records_lines() {
+        let writer = SharedWriter::default();
+        let recorder = Arc::new(EventRecorder::new(writer.clone()));
+        let chunks: Vec<Result<&[u8], ()>> = vec![Ok(b"{\"type\":"), Ok(b"\"x\"}\n\n")];
+        let stream = record_stream(stream::iter(chunks.clone()), Some(recorder), None,
+            Some("testGameId".to_owned()));
+
+        let passed_through = tokio_test::block_on(stream.collect::<Vec<_>>());
+        let replay = EventReplay::parse(&writer.content()).unwrap();
+
+        assert_that!(passed_through).contains_exactly_in_given_order(chunks);
+        assert_that!(replay.lines()).has_length(1);
+        assert_that!(replay.lines()[0].game_id.clone()).contains("testGameId".to_owned());
+        assert_that!(replay.lines()[0].line.as_str()).is_equal_to("{\"type\":\"x\"}");
+    }
+
+    #[test]
+    fn crash_dump_contains_last_lines_of_each_stream() {
+        let directory = std::env::temp_dir()
+            .join(format!("libot-crash-dumps-{}", std::process::id()));
+        let dumper = Arc::new(CrashDumper::new(&directory, 2));
+        let game_id = "testGameId".to_owned();
+        let chunks: Vec<Result<&[u8], ()>> = vec![Ok(b"{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n")];
+
+        tokio_test::block_on(record_stream(stream::iter(chunks), None, Some(Arc::clone(&dumper)),
+            Some(game_id.clone())).collect::<Vec<_>>());
+        dumper.record(None, "{\"n\":4}");
+
+        let game = CrashDumpGame {
+            game_id: game_id.clone(),
+            moves: "e2e4".to_owned(),
+            white_time: 1000,
+            black_time: 2000
+        };
+        let path = dumper.dump("test panic".to_owned(), Some(game_id.clone()), vec![game.clone()])
+            .unwrap();
+        let dump = CrashDump::open(&path).unwrap();
+        let lines = dump.lines.iter()
+            .map(|line| (line.game_id.clone(), line.line.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_that!(dump.reason.as_str()).is_equal_to("test panic");
+        assert_that!(dump.game_id.clone()).contains(game_id.clone());
+        assert_that!(dump.games.clone()).contains_exactly_in_given_order([game]);
+        assert_that!(lines).contains_exactly_in_any_order([
+            (Some(game_id.clone()), "{\"n\":2}"),
+            (Some(game_id.clone()), "{\"n\":3}"),
+            (None, "{\"n\":4}")
+        ]);
+        assert_that!(dump.replay().lines()).has_length(3);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn replay_separates_bot_and_game_events() {
+        let replay = EventReplay::new(vec![
+            RecordedLine {
+                timestamp: 1,
+                game_id: None,
+                line: r#"{"type":"challengeDeclined","challenge":{"id":"testId"}}"#.to_owned()
+            },
+            RecordedLine {
+                timestamp: 2,
+                game_id: Some("testGameId".to_owned()),
+                line: r#"{"type":"opponentGone","gone":true}"#.to_owned()
+            },
+            RecordedLine {
+                timestamp: 3,
+                game_id: Some("otherGameId".to_owned()),
+                line: r#"{"type":"opponentGone","gone":false}"#.to_owned()
+            }
+        ]);
+
+        let session = ReplaySession::new(replay, Instant::now());
+
+        let bot_events = tokio_test::block_on(session.bot_events().collect::<Vec<_>>());
+        let game_events = tokio_test::block_on(
+            session.game_events(&"testGameId".to_owned()).collect::<Vec<_>>());
+
+        assert!(matches!(bot_events.as_slice(), [Ok(BotEvent::ChallengeDeclined(_))]));
+        assert!(matches!(game_events.as_slice(),
+            [Ok(GameEvent::OpponentGone(opponent_gone))] if opponent_gone.gone));
+    }
+
+    #[rstest]
+    #[case::instant(ReplayTiming::Instant, 1500, 0)]
+    #[case::recorded(ReplayTiming::Recorded, 1500, 500)]
+    #[case::faster(ReplayTiming::Scaled(2.0), 1500, 250)]
+    #[case::slower(ReplayTiming::Scaled(0.5), 1500, 1000)]
+    #[case::zero_speed(ReplayTiming::Scaled(0.0), 1500, 0)]
+    #[case::infinite_speed(ReplayTiming::Scaled(f64::INFINITY), 1500, 0)]
+    #[case::before_first_line(ReplayTiming::Recorded, 500, 0)]
+    fn replay_timing_delay(#[case] timing: ReplayTiming, #[case] timestamp: Timestamp,
+            #[case] expected_millis: u64) {
+        assert_that!(timing.delay(1000, timestamp))
+            .is_equal_to(Duration::from_millis(expected_millis));
+    }
+
+    #[test]
+    fn timed_replay_respects_scaled_delays() {
+        let replay = EventReplay::new(vec![
+            RecordedLine {
+                timestamp: 1000,
+                game_id: None,
+                line: r#"{"type":"challengeDeclined","challenge":{"id":"firstId"}}"#.to_owned()
+            },
+            RecordedLine {
+                timestamp: 1500,
+                game_id: None,
+                line: r#"{"type":"challengeDeclined","challenge":{"id":"secondId"}}"#.to_owned()
+            }
+        ]).with_timing(ReplayTiming::Scaled(10.0));
+        let start = Instant::now();
+        let session = ReplaySession::new(replay, start);
+
+        let events = tokio_test::block_on(session.bot_events().collect::<Vec<_>>());
+
+        assert_that!(events).has_length(2);
+        assert_that!(start.elapsed()).is_greater_than_or_equal_to(Duration::from_millis(50));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_lines() {
+        assert_that!(EventReplay::parse("not json")).is_err();
+    }
+}
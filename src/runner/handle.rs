@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::Bot;
+use crate::client::BotClient;
+use crate::context::{BotContext, GameContext};
+use crate::error::LibotResult;
+use crate::model::Move;
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::GameId;
+use crate::model::game::chat::ChatRoom;
+use crate::model::game::event::{GameEvent, GameFullEvent, GameStateEvent};
+
+/// A handle to a single game of the bot, which allows playing it in an imperative style, e.g. in
+/// a task per game which loops over [GameHandle::next_event], instead of implementing the
+/// callbacks of the [Bot] trait. Handles are obtained from the [GameHandles] of a runner created
+/// with [BotRunner::games](crate::runner::BotRunner::games).
+#[derive(Debug)]
+pub struct GameHandle {
+    game_id: GameId,
+    game: GameStartFinish,
+    client: BotClient,
+    context: Arc<OnceLock<GameContext>>,
+    events: UnboundedReceiver<GameEvent>
+}
+
+impl GameHandle {
+
+    /// The ID of the game this handle is bound to.
+    pub fn id(&self) -> &GameId {
+        &self.game_id
+    }
+
+    /// The information about the game which was sent when it started, such as the color of the
+    /// bot and the opponent.
+    pub fn game(&self) -> &GameStartFinish {
+        &self.game
+    }
+
+    /// The client used by the runner, e.g. to send requests which are not bound to this game.
+    pub fn client(&self) -> &BotClient {
+        &self.client
+    }
+
+    /// The [GameContext] of this game, e.g. to query the tracked position or to make moves which
+    /// are confirmed by the game state (see [GameContext::make_move_confirmed]). It is available
+    /// once the runner received the full information about the game, i.e. at the latest when
+    /// [GameHandle::next_event] returned the [GameEvent::GameFull].
+    pub fn context(&self) -> Option<&GameContext> {
+        self.context.get()
+    }
+
+    /// Waits for the next event of the game. The first event is always a
+    /// [GameEvent::GameFull] with the full information about the game and its initial state,
+    /// which is followed by the events of the game event stream.
+    ///
+    /// # Returns
+    ///
+    /// The next event, or [None] once the event stream of the game ended and all its events were
+    /// returned.
+    pub async fn next_event(&mut self) -> Option<GameEvent> {
+        self.events.recv().await
+    }
+
+    /// Makes the given move in this game (see [GameContext::make_move]). If the
+    /// [GameHandle::context] is not available yet, the move is submitted without validation (see
+    /// [BotClient::make_move]).
+    ///
+    /// # Arguments
+    ///
+    /// * `mov`: The move to play in UCI notation.
+    /// * `offer_draw`: If `true`, the bot will offer a draw or accept a pending draw offer.
+    pub async fn make_move(&self, mov: impl Into<Move>, offer_draw: bool) -> LibotResult<()> {
+        match self.context.get() {
+            Some(context) => context.make_move(&self.client, mov.into(), offer_draw).await,
+            None => self.client.make_move(self.game_id.clone(), mov.into(), offer_draw).await
+        }
+    }
+
+    /// Sends a message to the chat of this game (see [BotClient::send_chat_message]).
+    ///
+    /// # Arguments
+    ///
+    /// * `room`: The chat room (player/spectator) in which to post the message.
+    /// * `text`: The text of the chat message to send.
+    pub async fn chat(&self, room: ChatRoom, text: impl Into<String>) -> LibotResult<()> {
+        self.client.send_chat_message(self.game_id.clone(), room, text).await
+    }
+
+    /// Resigns this game (see [BotClient::resign_game]).
+    pub async fn resign(&self) -> LibotResult<()> {
+        self.client.resign_game(self.game_id.clone()).await
+    }
+
+    /// Aborts this game (see [BotClient::abort_game]).
+    pub async fn abort(&self) -> LibotResult<()> {
+        self.client.abort_game(self.game_id.clone()).await
+    }
+}
+
+/// Receives a [GameHandle] for every game the bot starts while the runner created with
+/// [BotRunner::games](crate::runner::BotRunner::games) is running.
+#[derive(Debug)]
+pub struct GameHandles {
+    handles: UnboundedReceiver<GameHandle>
+}
+
+impl GameHandles {
+
+    /// Waits for the next game of the bot to start.
+    ///
+    /// # Returns
+    ///
+    /// A handle to the started game, or [None] once the runner has stopped.
+    pub async fn next_game(&mut self) -> Option<GameHandle> {
+        self.handles.recv().await
+    }
+}
+
+#[derive(Debug)]
+struct GameEventSender {
+    sender: UnboundedSender<GameEvent>,
+    context: Arc<OnceLock<GameContext>>
+}
+
+/// Forwards the events of each game to its [GameHandle]. The runner passes the events to it from
+/// the reader of the game event stream, so they arrive in stream order, independent of how long
+/// concurrently running hooks take.
+#[derive(Debug, Default)]
+pub(crate) struct GameEventForwarder {
+    games: Mutex<HashMap<GameId, GameEventSender>>
+}
+
+impl GameEventForwarder {
+
+    fn register(&self, game_id: GameId, sender: GameEventSender) {
+        self.games.lock().unwrap().insert(game_id, sender);
+    }
+
+    fn send(&self, game_id: &GameId, event: impl FnOnce(&GameEventSender) -> GameEvent) {
+        let mut games = self.games.lock().unwrap();
+
+        let Some(game) = games.get(game_id)
+        else {
+            return;
+        };
+
+        let event = event(game);
+        let finished = matches!(&event,
+            GameEvent::GameState(state) if !state.status.is_running());
+
+        if game.sender.send(event).is_err() || finished {
+            games.remove(game_id);
+        }
+    }
+
+    /// Sends the synthesized [GameEvent::GameFull] with the initial state of the game with the
+    /// given context and makes the context available to its handle.
+    pub(crate) fn game_full(&self, context: &GameContext, state: &GameStateEvent) {
+        self.send(&context.id, |game| {
+            game.context.get_or_init(|| context.clone());
+
+            GameEvent::GameFull(GameFullEvent {
+                info: context.info.clone(),
+                state: state.clone()
+            })
+        });
+    }
+
+    /// Sends an event which followed the [GameEvent::GameFull] on the stream of the given game.
+    pub(crate) fn event(&self, game_id: &GameId, event: &GameEvent) {
+        self.send(game_id, |_| event.clone());
+    }
+
+    /// Closes the handle of the given game once its event stream ended, after all its events were
+    /// sent.
+    pub(crate) fn game_stream_closed(&self, game_id: &GameId) {
+        self.games.lock().unwrap().remove(game_id);
+    }
+}
+
+/// The [Bot] run by a runner created with [BotRunner::games](crate::runner::BotRunner::games),
+/// which creates a [GameHandle] for each game. The runner forwards the events of the game to the
+/// handle. Challenges are not handled, so they should be accepted by a challenge queue (see
+/// [BotRunner::with_challenge_queue](crate::runner::BotRunner::with_challenge_queue)) or
+/// elsewhere using the client.
+#[derive(Debug)]
+pub struct GameHandleBot {
+    games: Arc<GameEventForwarder>,
+    handles: UnboundedSender<GameHandle>
+}
+
+impl GameHandleBot {
+
+    pub(crate) fn new() -> (GameHandleBot, GameHandles) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let bot = GameHandleBot {
+            games: Arc::new(GameEventForwarder::default()),
+            handles: sender
+        };
+
+        (bot, GameHandles { handles: receiver })
+    }
+
+    pub(crate) fn game_events(&self) -> Arc<GameEventForwarder> {
+        Arc::clone(&self.games)
+    }
+}
+
+#[async_trait::async_trait]
+impl Bot for GameHandleBot {
+
+    async fn on_game_start(&self, _context: &BotContext, game: GameStartFinish,
+            client: &BotClient) {
+        let Some(game_id) = game.id.clone()
+        else {
+            return;
+        };
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let context = Arc::new(OnceLock::new());
+        let handle = GameHandle {
+            game_id: game_id.clone(),
+            game,
+            client: client.clone(),
+            context: Arc::clone(&context),
+            events: receiver
+        };
+
+        if self.handles.send(handle).is_ok() {
+            self.games.register(game_id, GameEventSender {
+                sender,
+                context
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use futures::stream;
+
+    use kernal::prelude::*;
+
+    use wiremock::{Mock, ResponseTemplate};
+    use wiremock::matchers::{method, path};
+
+    use crate::by_address::ByAddress;
+    use crate::model::bot_event::BotEvent;
+    use crate::model::game::GameStatus;
+    use crate::runner::{self, BotRunner};
+    use crate::test_util;
+
+    use super::*;
+
+    const GAME_FULL_LINE: &str = concat!(
+        r#"{"type":"gameFull","id":"testId","variant":{},"speed":"blitz","perf":{},"#,
+        r#""rated":false,"createdAt":1234,"white":{"id":"testbot"},"black":{},"#,
+        r#""initialFen":"startpos","state":{"type":"gameState","moves":"","wtime":120000,"#,
+        r#""btime":120000,"winc":0,"binc":0,"status":"started"}}"#);
+    const CHAT_LINE: &str = r#"{"type":"chatLine","room":"player","username":"a","text":"hi"}"#;
+    const FINAL_STATE_LINE: &str = concat!(
+        r#"{"type":"gameState","moves":"e2e4","wtime":120000,"btime":120000,"winc":0,"#,
+        r#""binc":0,"status":"resign","winner":"white"}"#);
+
+    fn bot_context() -> BotContext {
+        BotContext {
+            bot_id: "testbot".to_owned(),
+            profile: ByAddress::default()
+        }
+    }
+
+    fn game_start(game_id: &str) -> GameStartFinish {
+        serde_json::from_value(serde_json::json!({ "id": game_id })).unwrap()
+    }
+
+    #[test]
+    fn events_of_started_game_are_forwarded_to_handle() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (runner, mut handles) = BotRunner::games(client);
+            let (bot, client, state) = runner.into_parts();
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    format!("{GAME_FULL_LINE}\n{CHAT_LINE}\n{FINAL_STATE_LINE}\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let stream =
+                stream::iter([Ok::<_, &str>(BotEvent::GameStart(game_start("testId")))]);
+
+            runner::run_with_event_stream(bot, stream, client, "testbot".to_owned(),
+                Arc::new(state)).await;
+
+            let mut handle = handles.next_game().await.unwrap();
+            let mut events = Vec::new();
+
+            while let Some(event) = handle.next_event().await {
+                events.push(event);
+            }
+
+            assert_that!(handle.id().as_str()).is_equal_to("testId");
+            assert_that!(&events).has_length(3);
+            assert!(matches!(&events[0], GameEvent::GameFull(game_full)
+                if game_full.info.id == "testId" && game_full.state.moves.is_empty()));
+            assert!(matches!(&events[1], GameEvent::ChatLine(chat_line)
+                if chat_line.chat_line.text == "hi"));
+            assert!(matches!(&events[2], GameEvent::GameState(state)
+                if state.status == GameStatus::Resign));
+            assert_that!(handles.next_game().await).is_none();
+        });
+    }
+
+    #[test]
+    fn handle_sends_requests_for_its_game() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, mut handles) = GameHandleBot::new();
+
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testId/move/e2e4"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testId/resign"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            bot.on_game_start(&bot_context(), game_start("testId"), &client).await;
+
+            let handle = handles.next_game().await.unwrap();
+
+            assert_that!(handle.make_move("e2e4", false).await).is_ok();
+            assert_that!(handle.resign().await).is_ok();
+        });
+    }
+
+    #[test]
+    fn handle_is_closed_when_game_stream_ends() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (runner, mut handles) = BotRunner::games(client);
+            let (bot, client, state) = runner.into_parts();
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    format!("{GAME_FULL_LINE}\n{CHAT_LINE}\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let stream =
+                stream::iter([Ok::<_, &str>(BotEvent::GameStart(game_start("testId")))]);
+
+            runner::run_with_event_stream(bot, stream, client, "testbot".to_owned(),
+                Arc::new(state)).await;
+
+            let mut handle = handles.next_game().await.unwrap();
+
+            assert!(matches!(handle.next_event().await, Some(GameEvent::GameFull(_))));
+            assert!(matches!(handle.next_event().await, Some(GameEvent::ChatLine(_))));
+            assert_that!(handle.next_event().await).is_none();
+        });
+    }
+
+    #[test]
+    fn forwarder_closes_handle_after_final_state() {
+        let forwarder = GameEventForwarder::default();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let context = Arc::new(OnceLock::new());
+        let game_full: GameFullEvent = serde_json::from_str(GAME_FULL_LINE).unwrap();
+        let game_context = GameContext {
+            bot_color: None,
+            bot_id: "testbot".to_owned(),
+            info: game_full.info,
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        };
+        let chat_line: GameEvent = serde_json::from_str(CHAT_LINE).unwrap();
+        let final_state: GameEvent = serde_json::from_str(FINAL_STATE_LINE).unwrap();
+
+        forwarder.register("testId".to_owned(), GameEventSender {
+            sender,
+            context: Arc::clone(&context)
+        });
+        forwarder.game_full(&game_context, &game_full.state);
+        forwarder.event(&game_context.id, &chat_line);
+        forwarder.event(&game_context.id, &final_state);
+        forwarder.event(&game_context.id, &chat_line);
+
+        assert_that!(context.get()).is_some();
+        assert!(matches!(receiver.try_recv(), Ok(GameEvent::GameFull(_))));
+        assert_that!(receiver.try_recv().ok()).contains(chat_line);
+        assert_that!(receiver.try_recv().ok()).contains(final_state);
+        assert_that!(receiver.try_recv().is_err()).is_true();
+    }
+}
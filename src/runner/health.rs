@@ -0,0 +1,409 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::{Stream, StreamExt};
+
+use serde::Serialize;
+
+use crate::cache::{CacheStats, CacheStatsSource, ResponseCache};
+use crate::model::Timestamp;
+
+#[cfg(feature = "health")]
+use std::future::Future;
+#[cfg(feature = "health")]
+use std::io;
+
+#[cfg(feature = "health")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "health")]
+use tokio::net::{TcpListener, TcpStream};
+
+/// The version of this library, as reported by [HealthReport::version].
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+fn timestamp(time: SystemTime) -> Timestamp {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as Timestamp
+}
+
+/// A snapshot of the health of a running bot, as produced by [HealthMonitor::report].
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+pub struct HealthReport {
+
+    /// Indicates whether the runner received any data from Lichess, including keep-alive
+    /// messages, within the liveness timeout. If this is `false`, the bot is likely wedged and
+    /// should be restarted.
+    pub live: bool,
+
+    /// Indicates whether the event stream of the bot is currently connected.
+    pub ready: bool,
+
+    /// The number of games the bot is currently playing.
+    pub active_games: usize,
+
+    /// The time at which the runner last received data from Lichess, if it received any.
+    pub last_event: Option<Timestamp>,
+
+    /// The time at which the monitor was created.
+    pub started_at: Timestamp,
+
+    /// The version of this library.
+    pub version: &'static str,
+
+    /// The [CacheStats] of every cache registered with [HealthMonitor::with_cache], by name.
+    pub caches: BTreeMap<String, CacheStats>
+}
+
+/// Observes a [BotRunner](crate::runner::BotRunner) to determine whether the bot is healthy, e.g.
+/// to let container orchestrators restart a wedged bot. Register it with
+/// [BotRunner::with_health_monitor](crate::runner::BotRunner::with_health_monitor) and query it
+/// with [HealthMonitor::report]. With the `health` feature, the report can be exposed over HTTP
+/// using `serve`.
+pub struct HealthMonitor {
+    liveness_timeout: Duration,
+    started_at: SystemTime,
+    last_event: Mutex<Option<SystemTime>>,
+    connected: AtomicBool,
+    active_games: AtomicUsize,
+    caches: Vec<(String, Arc<dyn CacheStatsSource>)>
+}
+
+impl HealthMonitor {
+
+    /// Creates a new monitor.
+    ///
+    /// # Arguments
+    ///
+    /// * `liveness_timeout`: The time without receiving any data from Lichess after which the bot
+    ///   is considered not live. Lichess sends keep-alive messages on the event stream every few
+    ///   seconds, so this can be fairly short, e.g. a minute.
+    pub fn new(liveness_timeout: Duration) -> HealthMonitor {
+        HealthMonitor {
+            liveness_timeout,
+            started_at: SystemTime::now(),
+            last_event: Mutex::new(None),
+            connected: AtomicBool::new(false),
+            active_games: AtomicUsize::new(0),
+            caches: Vec::new()
+        }
+    }
+
+    /// Includes the [CacheStats] of the given cache, e.g. of a
+    /// [CachedTablebase](crate::tablebase::CachedTablebase) or
+    /// [CachedExplorer](crate::explorer::CachedExplorer), in every [HealthReport] under the given
+    /// name. The monitor is returned for chaining.
+    pub fn with_cache<V>(mut self, name: impl Into<String>, cache: Arc<ResponseCache<V>>)
+        -> HealthMonitor
+    where
+        V: Clone + Send + 'static
+    {
+        self.caches.push((name.into(), cache));
+        self
+    }
+
+    /// Creates a [HealthReport] on the current health of the bot.
+    pub fn report(&self) -> HealthReport {
+        self.report_at(SystemTime::now())
+    }
+
+    fn report_at(&self, now: SystemTime) -> HealthReport {
+        let last_event = *self.last_event.lock().unwrap();
+        let last_activity = last_event.unwrap_or(self.started_at);
+        let inactive_for = now.duration_since(last_activity).unwrap_or_default();
+
+        HealthReport {
+            live: inactive_for <= self.liveness_timeout,
+            ready: self.connected.load(Ordering::Relaxed),
+            active_games: self.active_games.load(Ordering::Relaxed),
+            last_event: last_event.map(timestamp),
+            started_at: timestamp(self.started_at),
+            version: VERSION,
+            caches: self.caches.iter()
+                .map(|(name, cache)| (name.clone(), cache.stats()))
+                .collect()
+        }
+    }
+
+    pub(crate) fn event_received(&self) {
+        *self.last_event.lock().unwrap() = Some(SystemTime::now());
+    }
+
+    pub(crate) fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_active_games(&self, active_games: usize) {
+        self.active_games.store(active_games, Ordering::Relaxed);
+    }
+}
+
+impl Debug for HealthMonitor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let caches = self.caches.iter().map(|(name, _)| name).collect::<Vec<_>>();
+
+        f.debug_struct("HealthMonitor")
+            .field("liveness_timeout", &self.liveness_timeout)
+            .field("started_at", &self.started_at)
+            .field("last_event", &self.last_event)
+            .field("connected", &self.connected)
+            .field("active_games", &self.active_games)
+            .field("caches", &caches)
+            .finish()
+    }
+}
+
+/// Wraps the given stream of bytes received from an event stream, such that every chunk is
+/// reported to the given monitor, if any. The chunks are passed on unchanged.
+pub(crate) fn monitor_stream<B, E>(stream: impl Stream<Item = Result<B, E>>,
+    monitor: Option<Arc<HealthMonitor>>) -> impl Stream<Item = Result<B, E>>
+{
+    stream.inspect(move |_| {
+        if let Some(monitor) = &monitor {
+            monitor.event_received();
+        }
+    })
+}
+
+#[cfg(feature = "health")]
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// The time within which a client must send its request and receive the response before its
+/// connection is closed, so idle connections do not accumulate.
+#[cfg(feature = "health")]
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[cfg(feature = "health")]
+fn response(path: &str, report: &HealthReport) -> (u16, String) {
+    let healthy = match path {
+        "/health" | "/health/live" => report.live,
+        "/health/ready" => report.ready,
+        _ => return (404, "{\"error\":\"not found\"}".to_owned())
+    };
+    let status = if healthy { 200 } else { 503 };
+
+    // Serializing a report cannot fail, since its only map has string keys and there are no custom
+    // serializers.
+    (status, serde_json::to_string(report).unwrap_or_default())
+}
+
+/// Reads the head of an HTTP request from the given connection.
+///
+/// # Returns
+///
+/// The bytes of the request head, or [None] if the connection was closed or the head exceeds
+/// [MAX_REQUEST_SIZE] bytes.
+#[cfg(feature = "health")]
+async fn read_request(connection: &mut TcpStream) -> io::Result<Option<Vec<u8>>> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let capacity = buffer.len().min(MAX_REQUEST_SIZE - request.len());
+
+        if capacity == 0 {
+            return Ok(None);
+        }
+
+        let read = connection.read(&mut buffer[..capacity]).await?;
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    Ok(Some(request))
+}
+
+#[cfg(feature = "health")]
+async fn with_timeout<T>(timeout: Duration, operation: impl Future<Output = io::Result<T>>)
+        -> io::Result<T> {
+    tokio::time::timeout(timeout, operation).await
+        .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+}
+
+#[cfg(feature = "health")]
+async fn handle_connection(mut connection: TcpStream, monitor: &HealthMonitor, timeout: Duration)
+        -> io::Result<()> {
+    let Some(request) = with_timeout(timeout, read_request(&mut connection)).await?
+    else {
+        return Ok(());
+    };
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    let (status, body) = if method == "GET" {
+        response(path, &monitor.report())
+    }
+    else {
+        (405, "{\"error\":\"method not allowed\"}".to_owned())
+    };
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable"
+    };
+    let response = format!("HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\n\
+        Content-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+
+    with_timeout(timeout, async {
+        connection.write_all(response.as_bytes()).await?;
+        connection.shutdown().await
+    }).await
+}
+
+/// Serves the [HealthReport] of the given monitor over HTTP on the given listener until an error
+/// occurs accepting a connection. Intended to be spawned as a separate task next to the runner.
+/// The following endpoints respond to `GET` requests with the report as JSON:
+///
+/// * `/health/live`: Status 200 if the bot is [live](HealthReport::live), otherwise 503.
+/// * `/health/ready`: Status 200 if the bot is [ready](HealthReport::ready), otherwise 503.
+/// * `/health`: The same as `/health/live`.
+///
+/// Only available with the `health` feature.
+///
+/// # Arguments
+///
+/// * `listener`: The [TcpListener] on which to accept connections.
+/// * `monitor`: The [HealthMonitor] whose report to serve.
+///
+/// # Errors
+///
+/// Any I/O error that occurs while accepting a connection. Errors on individual connections are
+/// ignored. Connections which do not send a complete request within five seconds, or whose request
+/// exceeds 8 KiB, are closed.
+#[cfg(feature = "health")]
+pub async fn serve(listener: TcpListener, monitor: Arc<HealthMonitor>) -> io::Result<()> {
+    loop {
+        let (connection, _) = listener.accept().await?;
+        let monitor = Arc::clone(&monitor);
+
+        tokio::spawn(async move {
+            // TODO enable error handling
+            let _ = handle_connection(connection, &monitor, CONNECTION_TIMEOUT).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn new_monitor_is_live_but_not_ready() {
+        let monitor = HealthMonitor::new(Duration::from_secs(60));
+        let report = monitor.report();
+
+        assert_that!(report.live).is_true();
+        assert_that!(report.ready).is_false();
+        assert_that!(report.last_event).is_none();
+        assert_that!(report.version).is_equal_to(VERSION);
+    }
+
+    #[test]
+    fn monitor_is_not_live_after_timeout_without_events() {
+        let monitor = HealthMonitor::new(Duration::from_secs(60));
+        monitor.event_received();
+        monitor.set_connected(true);
+        monitor.set_active_games(2);
+
+        let later = SystemTime::now() + Duration::from_secs(61);
+        let report = monitor.report_at(later);
+
+        assert_that!(report.live).is_false();
+        assert_that!(report.ready).is_true();
+        assert_that!(report.active_games).is_equal_to(2);
+        assert_that!(report.last_event).is_some();
+    }
+
+    #[test]
+    fn report_contains_stats_of_registered_caches() {
+        let cache = Arc::new(ResponseCache::<u32>::new(Duration::from_secs(60)));
+        let monitor = HealthMonitor::new(Duration::from_secs(60))
+            .with_cache("tablebase", Arc::clone(&cache));
+
+        cache.get("8/8/8/8/8/8/8/8 w - - 0 1");
+
+        let report = monitor.report();
+
+        assert_that!(report.caches.len()).is_equal_to(1);
+        assert_that!(report.caches.get("tablebase").copied()).contains(CacheStats {
+            hits: 0,
+            misses: 1,
+            entries: 0
+        });
+    }
+
+    #[cfg(feature = "health")]
+    #[test]
+    fn endpoints_report_health_over_http() {
+        tokio_test::block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let monitor = Arc::new(HealthMonitor::new(Duration::from_secs(60)));
+            let server = tokio::spawn(serve(listener, Arc::clone(&monitor)));
+            let get = |path: &str| reqwest::get(format!("http://{address}{path}"));
+
+            let live = get("/health/live").await.unwrap();
+            let ready = get("/health/ready").await.unwrap();
+            let unknown = get("/unknown").await.unwrap();
+
+            assert_that!(live.status().as_u16()).is_equal_to(200);
+            assert_that!(ready.status().as_u16()).is_equal_to(503);
+            assert_that!(unknown.status().as_u16()).is_equal_to(404);
+
+            let report = live.json::<serde_json::Value>().await.unwrap();
+
+            assert_that!(report["version"].as_str()).contains(VERSION);
+            assert_that!(report["active_games"].as_u64()).contains(0);
+
+            server.abort();
+        });
+    }
+
+    #[cfg(feature = "health")]
+    #[test]
+    fn idle_connection_times_out() {
+        tokio_test::block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let _client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+            let (connection, _) = listener.accept().await.unwrap();
+            let monitor = HealthMonitor::new(Duration::from_secs(60));
+
+            let result = tokio::time::timeout(Duration::from_secs(5),
+                handle_connection(connection, &monitor, Duration::from_millis(100))).await;
+
+            assert_that!(result.unwrap().map_err(|error| error.kind()))
+                .contains_error(io::ErrorKind::TimedOut);
+        });
+    }
+
+    #[cfg(feature = "health")]
+    #[test]
+    fn oversized_request_is_rejected_without_response() {
+        tokio_test::block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+            let (connection, _) = listener.accept().await.unwrap();
+            let monitor = HealthMonitor::new(Duration::from_secs(60));
+
+            client.write_all(&[b'a'; MAX_REQUEST_SIZE + 1]).await.unwrap();
+            handle_connection(connection, &monitor, Duration::from_secs(5)).await.unwrap();
+
+            // The connection may also be reset, since the request was not read completely.
+            let mut response = Vec::new();
+            let _ = client.read_to_end(&mut response).await;
+
+            assert_that!(response).is_empty();
+        });
+    }
+}
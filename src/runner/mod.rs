@@ -0,0 +1,4709 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::pin::pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::{future, stream, Stream};
+use futures::future::BoxFuture;
+use futures::stream::StreamExt;
+
+use reqwest::Method;
+
+use tokio::sync::watch;
+use tokio::task;
+
+use crate::Bot;
+use crate::chess::position::InvalidPosition;
+use crate::client::BotClient;
+use crate::config::BotConfig;
+use crate::by_address::ByAddress;
+use crate::context::{BotContext, GameContext};
+use crate::error::{ChallengeAcceptError, LibotRequestError, LibotResult};
+use crate::model::bot_event::BotEvent;
+use crate::model::challenge::{Challenge, ChallengeRequest, DeclineReason};
+use crate::model::{Milliseconds, Moves, Timestamp};
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::{Color, GameId, GameInfo};
+use crate::model::game::event::{GameEvent, GameStateEvent};
+use crate::model::game::result::GameResult;
+use crate::model::user::{PerfKey, Rating};
+use crate::model::user::{UserId, UserProfile};
+use crate::runner::backpressure::{BackpressureConfig, EventQueue, QueuedEventKind};
+use crate::runner::cancellation::GameCancellation;
+use crate::runner::challenge_queue::{ChallengeDecision, ChallengeQueue, ChallengeQueueConfig};
+use crate::runner::events::{CrashDumpGame, CrashDumper, EventRecorder, EventReplay, ReplaySession};
+use crate::runner::handle::{GameEventForwarder, GameHandleBot, GameHandles};
+use crate::runner::health::HealthMonitor;
+use crate::runner::offer_policy::{
+    OfferKind,
+    OfferPolicies,
+    OfferPolicy,
+    OfferResponse,
+    OfferTracker
+};
+use crate::runner::move_confirmation::MoveWatcher;
+use crate::runner::error::RunnerError;
+use crate::runner::parsing::{ParsingConfig, StreamError};
+use crate::runner::position_tracker::PositionTracker;
+use crate::runner::post_game::PostGameAnalysisConfig;
+use crate::runner::rematch::{RematchConfig, RematchDecision, RematchTracker};
+use crate::runner::schedule::ChallengeSchedule;
+use crate::runner::snapshot::SessionLog;
+use crate::runner::spam_protection::{ChallengeRateTracker, ChallengerLists, SpamProtectionConfig};
+use crate::runner::prefetch::CloudEvalPrefetcher;
+use crate::runner::spectators::SpectatorTracker;
+use crate::runner::stream_pacing::{GameStreamPacer, GameStreamPacing};
+use crate::runner::systemd::SystemdNotifier;
+use crate::runner::telemetry::{MoveTelemetry, MoveTimer};
+use crate::runner::tournament::{TournamentAction, TournamentConfig, TournamentMode};
+use crate::runner::turn::TurnTracker;
+use crate::stats::{self, OpponentStats, PerformanceStats, RatingHistory, RatingUpdate};
+use crate::store::{GameRecord, GameStore, RunnerSnapshot, StoreResult};
+use crate::store::pgn_archive::PgnArchive;
+
+pub mod backpressure;
+pub(crate) mod cancellation;
+pub mod challenge_queue;
+pub mod error;
+pub mod events;
+pub mod handle;
+pub mod health;
+pub mod offer_policy;
+pub mod parsing;
+pub(crate) mod move_confirmation;
+pub(crate) mod position_tracker;
+pub mod post_game;
+pub mod prefetch;
+pub mod rematch;
+pub mod schedule;
+pub(crate) mod snapshot;
+pub mod spam_protection;
+pub mod spectators;
+pub mod stream_pacing;
+pub mod systemd;
+pub mod telemetry;
+pub mod tournament;
+pub(crate) mod turn;
+
+const EVENT_PATH: &str = "/stream/event";
+const MAX_ONGOING_GAMES: u32 = 50;
+
+/// The time after which collecting [OpponentStats] at the start of a game is given up, so it does
+/// not delay the first move of the bot.
+const OPPONENT_STATS_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn game_event_path(client: &BotClient, game_id: &GameId) -> String {
+    client.game_path(&format!("/game/stream/{}", game_id))
+}
+
+/// Runs a [Bot] with additional, optional features that manage parts of the bot's behavior, such
+/// as a challenge queue. Use [crate::run] to run a bot without any additional features.
+pub struct BotRunner<B> {
+    bot: B,
+    client: BotClient,
+    challenge_queue: Option<ChallengeQueueConfig>,
+    spam_protection: Option<SpamProtectionConfig>,
+    schedule: Option<ChallengeSchedule>,
+    tournament: Option<TournamentConfig>,
+    challenger_lists: Arc<ChallengerLists>,
+    game_store: Option<Arc<dyn GameStore>>,
+    opponent_stats: bool,
+    offer_policies: OfferPolicies,
+    move_telemetry: Option<Arc<MoveTelemetry>>,
+    rating_history: Option<Arc<RatingHistory>>,
+    performance_stats: Option<Arc<PerformanceStats>>,
+    pgn_archive: Option<(Arc<PgnArchive>, Duration)>,
+    event_recorder: Option<Arc<EventRecorder>>,
+    crash_dumper: Option<Arc<CrashDumper>>,
+    stale_game_timeout: Option<Duration>,
+    automatic_win_claim: bool,
+    resume_ongoing_games: bool,
+    state_snapshots: Option<Duration>,
+    correspondence_reminder: Option<Duration>,
+    speed_validation: bool,
+    parsing: ParsingConfig,
+    backpressure: Option<BackpressureConfig>,
+    game_stream_pacing: Option<GameStreamPacing>,
+    health_monitor: Option<Arc<HealthMonitor>>,
+    post_game_analysis: Option<PostGameAnalysisConfig>,
+    rematches: Option<RematchConfig>,
+    spectator_tracking: Option<Duration>,
+    cloud_eval_prefetcher: Option<Arc<CloudEvalPrefetcher>>,
+    systemd_notifier: Option<SystemdNotifier>,
+    watchdog_timeout: Option<Duration>,
+    game_events: Option<Arc<GameEventForwarder>>
+}
+
+impl BotRunner<GameHandleBot> {
+
+    /// Creates a new runner using the given client which, instead of calling the handlers of a
+    /// [Bot], passes each game the bot starts to the returned [GameHandles] as a
+    /// [GameHandle](handle::GameHandle). This allows playing every game in its own task in an
+    /// imperative style, awaiting its events and sending requests bound to it. The runner must be
+    /// run, e.g. in a spawned task, for games to be received. Challenges are not handled, so the
+    /// runner should usually be configured with [BotRunner::with_challenge_queue]. All other
+    /// features can be configured as usual.
+    pub fn games(client: BotClient) -> (BotRunner<GameHandleBot>, GameHandles) {
+        let (bot, handles) = GameHandleBot::new();
+        let mut runner = BotRunner::new(bot, client);
+        runner.game_events = Some(runner.bot.game_events());
+
+        (runner, handles)
+    }
+}
+
+impl<B: Bot + Send + 'static> BotRunner<B> {
+
+    /// Creates a new runner for the given bot using the given client. By default, no additional
+    /// features are enabled.
+    pub fn new(bot: B, client: BotClient) -> BotRunner<B> {
+        BotRunner {
+            bot,
+            client,
+            challenge_queue: None,
+            spam_protection: None,
+            schedule: None,
+            tournament: None,
+            challenger_lists: Arc::new(ChallengerLists::default()),
+            game_store: None,
+            opponent_stats: false,
+            offer_policies: OfferPolicies::default(),
+            move_telemetry: None,
+            rating_history: None,
+            performance_stats: None,
+            pgn_archive: None,
+            event_recorder: None,
+            crash_dumper: None,
+            stale_game_timeout: None,
+            automatic_win_claim: false,
+            resume_ongoing_games: false,
+            state_snapshots: None,
+            correspondence_reminder: None,
+            speed_validation: false,
+            parsing: ParsingConfig::default(),
+            backpressure: None,
+            game_stream_pacing: None,
+            health_monitor: None,
+            post_game_analysis: None,
+            rematches: None,
+            spectator_tracking: None,
+            cloud_eval_prefetcher: None,
+            systemd_notifier: None,
+            watchdog_timeout: None,
+            game_events: None
+        }
+    }
+
+    /// Enables the challenge queue with the given configuration. Incoming challenges are then
+    /// handled according to the configured [ChallengePolicy](challenge_queue::ChallengePolicy)
+    /// and accepted only while the bot plays fewer games than the configured limit. Note that
+    /// [Bot::on_challenge] is still called for every challenge, so it should not accept
+    /// challenges itself. The runner is returned for chaining.
+    pub fn with_challenge_queue(mut self, config: ChallengeQueueConfig) -> BotRunner<B> {
+        self.challenge_queue = Some(config);
+        self
+    }
+
+    /// Declines challenges of users which challenge the bot more often than allowed by the given
+    /// [SpamProtectionConfig] with [DeclineReason::Later], without passing them to the challenge
+    /// queue or [Bot::on_challenge]. Users on the allowlist (see [BotRunner::allow_user]) are
+    /// exempt. The runner is returned for chaining.
+    pub fn with_spam_protection(mut self, config: SpamProtectionConfig) -> BotRunner<B> {
+        self.spam_protection = Some(config);
+        self
+    }
+
+    /// Declines all challenges with [DeclineReason::Later] while the given [ChallengeSchedule] is
+    /// inactive, i.e. outside its active hours or during a maintenance window, without passing
+    /// them to the challenge queue or [Bot::on_challenge]. Ongoing games are still played until
+    /// they finish. The runner is returned for chaining.
+    pub fn with_schedule(mut self, schedule: ChallengeSchedule) -> BotRunner<B> {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Enables the tournament mode with the given [TournamentConfig]. The runner then joins the
+    /// configured arena tournament when it starts, joins it again whenever the bot was paused,
+    /// goes berserk in tournament games according to the configured
+    /// [BerserkPolicy](tournament::BerserkPolicy) and withdraws at the configured time. Until it
+    /// withdrew or the tournament finished, all challenges are declined with
+    /// [DeclineReason::Later] without passing them to the challenge queue or [Bot::on_challenge].
+    /// The runner is returned for chaining.
+    pub fn with_tournament(mut self, config: TournamentConfig) -> BotRunner<B> {
+        self.tournament = Some(config);
+        self
+    }
+
+    /// Adds the user with the given ID to the blocklist of this runner, so all their challenges
+    /// are declined with [DeclineReason::Later] without passing them to the challenge queue or
+    /// [Bot::on_challenge]. The lists are persisted in the [GameStore] of this runner, if one is
+    /// registered with [BotRunner::with_game_store]. Use [BotRunner::challenger_lists] to change
+    /// the lists while the bot is running.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError](crate::store::StoreError) if the lists cannot be saved. The user is
+    /// blocked regardless.
+    pub fn block_user(&self, user_id: impl Into<UserId>) -> StoreResult<()> {
+        self.challenger_lists.block_user(user_id)
+    }
+
+    /// Adds the user with the given ID to the allowlist of this runner, which exempts them from
+    /// the spam protection configured with [BotRunner::with_spam_protection] and removes them from
+    /// the blocklist. The lists are persisted like with [BotRunner::block_user].
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError](crate::store::StoreError) if the lists cannot be saved. The user is
+    /// allowed regardless.
+    pub fn allow_user(&self, user_id: impl Into<UserId>) -> StoreResult<()> {
+        self.challenger_lists.allow_user(user_id)
+    }
+
+    /// Gets the [ChallengerLists] of this runner, which can be kept to block or allow users while
+    /// the bot is running.
+    pub fn challenger_lists(&self) -> Arc<ChallengerLists> {
+        Arc::clone(&self.challenger_lists)
+    }
+
+    /// Records every finished game in the given [GameStore]. The game is stored before
+    /// [Bot::on_game_finish] is called, so the bot can query it from there if it holds another
+    /// reference to the same store. The [ChallengerLists] of the runner are loaded from and saved
+    /// to the store as well.
+    ///
+    /// # Returns
+    ///
+    /// The runner for chaining.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError](crate::store::StoreError) if the persisted [ChallengerLists] cannot be
+    /// loaded from the store or the merged lists cannot be saved to it, since the bot would
+    /// otherwise run without the users blocked in a previous session.
+    pub fn with_game_store(mut self, game_store: Arc<dyn GameStore>)
+            -> StoreResult<BotRunner<B>> {
+        self.challenger_lists.attach_store(Arc::clone(&game_store))?;
+        self.game_store = Some(game_store);
+        Ok(self)
+    }
+
+    /// Collects [OpponentStats] at the start of every game, which are then available via
+    /// [GameContext::opponent_stats]. The statistics are aggregated from the games recorded in the
+    /// [GameStore], if one is registered with [BotRunner::with_game_store], and the Lichess
+    /// crosstable. If they cannot be collected within two seconds, e.g. because Lichess is slow to
+    /// respond, the game starts without them. The runner is returned for chaining.
+    pub fn with_opponent_stats(mut self) -> BotRunner<B> {
+        self.opponent_stats = true;
+        self
+    }
+
+    /// Responds to draw offers of the opponent according to the given [OfferPolicy]. Offers for
+    /// which the policy returns [OfferResponse::Delegate] are passed to [Bot::on_draw_offer]. The
+    /// runner is returned for chaining.
+    pub fn with_draw_offer_policy(mut self, policy: impl OfferPolicy + 'static) -> BotRunner<B> {
+        self.offer_policies.draw = Some(Arc::new(policy));
+        self
+    }
+
+    /// Responds to takeback proposals of the opponent according to the given [OfferPolicy].
+    /// Proposals for which the policy returns [OfferResponse::Delegate] are passed to
+    /// [Bot::on_takeback_proposal]. The runner is returned for chaining.
+    pub fn with_takeback_policy(mut self, policy: impl OfferPolicy + 'static) -> BotRunner<B> {
+        self.offer_policies.takeback = Some(Arc::new(policy));
+        self
+    }
+
+    /// Records the latency of every move of the bot in the given [MoveTelemetry], i.e. the time
+    /// between the runner receiving the game state in which it became the bot's turn and the bot
+    /// submitting its move with [GameContext::make_move]. Keep another reference to the telemetry
+    /// to read the metrics. The runner is returned for chaining.
+    pub fn with_move_telemetry(mut self, move_telemetry: Arc<MoveTelemetry>) -> BotRunner<B> {
+        self.move_telemetry = Some(move_telemetry);
+        self
+    }
+
+    /// Fetches the rating of the bot after every rated game and records the change in the given
+    /// [RatingHistory], in addition to reporting it to [Bot::on_rating_update]. Without a rating
+    /// history, the profile of the bot is not fetched after games. Keep another reference to the
+    /// history to read the recorded updates. The runner is returned for chaining.
+    pub fn with_rating_history(mut self, rating_history: Arc<RatingHistory>) -> BotRunner<B> {
+        self.rating_history = Some(rating_history);
+        self
+    }
+
+    /// Records the outcome of every finished game in the given [PerformanceStats], which computes
+    /// rolling win rates by speed, variant, and rating band of the opponent. Keep another
+    /// reference to the statistics to read the rates. The runner is returned for chaining.
+    pub fn with_performance_stats(mut self, performance_stats: Arc<PerformanceStats>)
+            -> BotRunner<B> {
+        self.performance_stats = Some(performance_stats);
+        self
+    }
+
+    /// Periodically exports the finished games of the bot from Lichess into the given
+    /// [PgnArchive], which skips games that are already archived. The first export happens when
+    /// the runner starts and includes all past games of the bot. The runner is returned for
+    /// chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive`: The archive into which to export games.
+    /// * `interval`: The time between two exports.
+    pub fn with_pgn_archive(mut self, archive: Arc<PgnArchive>, interval: Duration)
+            -> BotRunner<B> {
+        self.pgn_archive = Some((archive, interval));
+        self
+    }
+
+    /// Applies the given [BotConfig] to this runner. If the config contains a
+    /// [ChallengeConfig](crate::config::ChallengeConfig), the challenge queue is enabled with it
+    /// as its policy (see [BotRunner::with_challenge_queue]). The runner is returned for chaining.
+    pub fn with_config(mut self, config: &BotConfig) -> BotRunner<B> {
+        if let Some(challenge_config) = &config.challenge {
+            self = self.with_challenge_queue(challenge_config.queue_config());
+        }
+
+        self
+    }
+
+    /// Records every line received from the bot's event stream and the game event streams with
+    /// the given [EventRecorder]. The recording can be loaded as an [EventReplay] and fed back
+    /// through the bot using [BotRunner::replay]. The runner is returned for chaining.
+    pub fn with_event_recorder(mut self, event_recorder: Arc<EventRecorder>) -> BotRunner<B> {
+        self.event_recorder = Some(event_recorder);
+        self
+    }
+
+    /// Automatically aborts games in which the opponent does not make their first move within the
+    /// given timeout after the game was created, which frees up the slot of the game in the
+    /// challenge queue. Games are also aborted as soon as Lichess reports that the opponent left
+    /// before making their first move. The runner is returned for chaining.
+    pub fn with_stale_game_abort(mut self, timeout: Duration) -> BotRunner<B> {
+        self.stale_game_timeout = Some(timeout);
+        self
+    }
+
+    /// Claims victory with [BotClient::claim_victory] as soon as the time announced by an
+    /// [OpponentGoneEvent](crate::model::game::event::OpponentGoneEvent) has elapsed without the
+    /// opponent returning. [Bot::on_claimable_win] is called afterwards. The runner is returned
+    /// for chaining.
+    pub fn with_automatic_win_claim(mut self) -> BotRunner<B> {
+        self.automatic_win_claim = true;
+        self
+    }
+
+    /// Queries the games the bot is playing with [BotClient::get_ongoing_games] when the runner
+    /// starts and resumes them as if their start events were received, so games, in particular
+    /// correspondence games, continue to be played after the process was restarted. Games which
+    /// Lichess also reports on the event stream are only handled once. The runner is returned for
+    /// chaining.
+    pub fn with_ongoing_game_resume(mut self) -> BotRunner<B> {
+        self.resume_ongoing_games = true;
+        self
+    }
+
+    /// Saves a [RunnerSnapshot] of the active games, the accepted challenges and the games in
+    /// which the bot sent chat messages to the [GameStore] registered with
+    /// [BotRunner::with_game_store] in the given interval. When the runner starts and the store
+    /// contains a snapshot, it is reconciled with the games Lichess reports as ongoing: the games
+    /// of the snapshot which are still running are resumed as with
+    /// [BotRunner::with_ongoing_game_resume], and [GameContext::has_sent_chat_message] reports the
+    /// messages sent before the restart, so bots do not greet their opponents twice. Without a
+    /// game store, this has no effect. The runner is returned for chaining.
+    pub fn with_state_snapshots(mut self, interval: Duration) -> BotRunner<B> {
+        self.state_snapshots = Some(interval);
+        self
+    }
+
+    /// Reminds the bot of its turn in correspondence games by calling [Bot::on_my_turn] again the
+    /// given time before the turn runs out, unless the bot moved before. The end of the turn is
+    /// determined from the days per turn of the game and the remaining clock of the bot. The
+    /// runner is returned for chaining.
+    pub fn with_correspondence_reminders(mut self, remind_before: Duration) -> BotRunner<B> {
+        self.correspondence_reminder = Some(remind_before);
+        self
+    }
+
+    /// Checks for every incoming challenge whether the [Speed](crate::model::game::Speed) reported
+    /// by Lichess matches the one derived from its time control (see
+    /// [TimeControl::speed](crate::model::TimeControl::speed)) and reports mismatches to
+    /// [Bot::on_error] as [RunnerError::InconsistentSpeed]. This helps to detect changes in
+    /// the Lichess API. The runner is returned for chaining.
+    pub fn with_speed_validation(mut self) -> BotRunner<B> {
+        self.speed_validation = true;
+        self
+    }
+
+    /// Parses the event streams of Lichess according to the given [ParsingConfig], e.g. to skip
+    /// unparsable lines instead of panicking. The runner is returned for chaining.
+    pub fn with_parsing_config(mut self, config: ParsingConfig) -> BotRunner<B> {
+        self.parsing = config;
+        self
+    }
+
+    /// Bounds the number of pending and running event handlers of each game according to the
+    /// given [BackpressureConfig], so events cannot pile up when the bot handles them slower than
+    /// they arrive. By default, all events are handled concurrently as soon as they arrive. The
+    /// runner is returned for chaining.
+    pub fn with_backpressure(mut self, config: BackpressureConfig) -> BotRunner<B> {
+        self.backpressure = Some(config);
+        self
+    }
+
+    /// Paces opening the event streams of games as configured by the given [GameStreamPacing],
+    /// i.e. spaces them out, limits how many are opened at the same time, and retries them with a
+    /// backoff if Lichess rate limits them. This avoids losing games to rate limiting when the bot
+    /// restarts with many ongoing games (see [BotRunner::with_ongoing_game_resume]). The runner
+    /// is returned for chaining.
+    pub fn with_game_stream_pacing(mut self, config: GameStreamPacing) -> BotRunner<B> {
+        self.game_stream_pacing = Some(config);
+        self
+    }
+
+    /// Keeps the last lines received from each event stream in the given [CrashDumper]. When an
+    /// event handler panics, a [CrashDump](events::CrashDump) with these lines and the tracked
+    /// moves and clocks of all running games is written, and its path is reported to
+    /// [Bot::on_error] as [RunnerError::HandlerPanicked] before the panic is resumed. The
+    /// runner is returned for chaining.
+    pub fn with_crash_dumps(mut self, crash_dumper: Arc<CrashDumper>) -> BotRunner<B> {
+        self.crash_dumper = Some(crash_dumper);
+        self
+    }
+
+    /// Reports the connection state, received data, and number of active games to the given
+    /// [HealthMonitor], e.g. to serve it to container orchestrators using `health::serve` with the
+    /// `health` feature. The runner is returned for chaining.
+    pub fn with_health_monitor(mut self, monitor: Arc<HealthMonitor>) -> BotRunner<B> {
+        self.health_monitor = Some(monitor);
+        self
+    }
+
+    /// Posts a summary of the analysis of every finished game of the bot to its chat, as
+    /// configured by the given [PostGameAnalysisConfig]. The runner is returned for chaining.
+    pub fn with_post_game_analysis(mut self, config: PostGameAnalysisConfig) -> BotRunner<B> {
+        self.post_game_analysis = Some(config);
+        self
+    }
+
+    /// Offers and accepts rematches after finished games against Lichess users, as configured by
+    /// the given [RematchConfig]. Rematches are only offered while the bot is not suspended by its
+    /// schedule or tournament mode. The runner is returned for chaining.
+    pub fn with_rematches(mut self, config: RematchConfig) -> BotRunner<B> {
+        self.rematches = Some(config);
+        self
+    }
+
+    /// Tracks the [Spectators](spectators::Spectators) of every running game, which are available
+    /// through [GameContext::spectators]. While the bot plays, the games featured on Lichess TV are
+    /// queried in the given interval. The runner is returned for chaining.
+    pub fn with_spectator_tracking(mut self, refresh_interval: Duration) -> BotRunner<B> {
+        self.spectator_tracking = Some(refresh_interval);
+        self
+    }
+
+    /// Prefetches cloud evaluations with the given [CloudEvalPrefetcher] whenever it is the
+    /// opponent's turn in a game, so the evaluations after its most likely replies are cached by
+    /// the time the bot has to move. Keep another reference to the prefetcher to query the
+    /// evaluations. The runner is returned for chaining.
+    pub fn with_cloud_eval_prefetch(mut self, prefetcher: Arc<CloudEvalPrefetcher>)
+            -> BotRunner<B> {
+        self.cloud_eval_prefetcher = Some(prefetcher);
+        self
+    }
+
+    /// Integrates the runner with systemd, if the bot runs as a systemd service with
+    /// `Type=notify`, as indicated by the `NOTIFY_SOCKET` environment variable. Otherwise, this
+    /// has no effect. The runner then notifies systemd once the event stream is connected and when
+    /// it ends. If the watchdog is enabled with `WatchdogSec=` in the unit file, the runner also
+    /// pings it every [BotRunner::watchdog_interval], but only as long as the bot is
+    /// [live](health::HealthReport::live) according to its [HealthMonitor]. If no monitor is
+    /// registered, one with the watchdog timeout as liveness timeout is used. This way, systemd
+    /// restarts the bot if its event stream stalls. The runner is returned for chaining.
+    pub fn with_systemd_notify(self) -> BotRunner<B> {
+        match SystemdNotifier::from_env() {
+            Some(notifier) => self.with_systemd_notifier(notifier, systemd::watchdog_timeout()),
+            None => self
+        }
+    }
+
+    /// Like [BotRunner::with_systemd_notify], but sends the notifications using the given
+    /// [SystemdNotifier] and uses the given watchdog timeout instead of taking them from the
+    /// environment. The runner is returned for chaining.
+    pub fn with_systemd_notifier(mut self, notifier: SystemdNotifier,
+            watchdog_timeout: Option<Duration>) -> BotRunner<B> {
+        self.systemd_notifier = Some(notifier);
+        self.watchdog_timeout = watchdog_timeout;
+        self
+    }
+
+    /// The interval in which the runner pings the systemd watchdog, which is half the watchdog
+    /// timeout configured with `WatchdogSec=`, as recommended by systemd.
+    ///
+    /// # Returns
+    ///
+    /// The watchdog interval, or [None] if systemd integration is not enabled using
+    /// [BotRunner::with_systemd_notify] or the watchdog is not enabled for this service.
+    pub fn watchdog_interval(&self) -> Option<Duration> {
+        self.systemd_notifier.as_ref()?;
+
+        self.watchdog_timeout.map(|timeout| timeout / 2)
+    }
+
+    /// Runs the bot until the event stream ends.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError] that occurs while querying the bot's profile or ongoing games, or
+    /// opening the event stream.
+    pub async fn run(mut self) -> LibotResult<()> {
+        let watchdog_interval = self.watchdog_interval();
+
+        if let (Some(timeout), None) = (self.watchdog_timeout, &self.health_monitor) {
+            if watchdog_interval.is_some() {
+                self.health_monitor = Some(Arc::new(HealthMonitor::new(timeout)));
+            }
+        }
+
+        let profile = self.client.get_my_profile().await?;
+        let bot_id = profile.id.clone();
+        let snapshot = self.load_snapshot();
+        let mut ongoing_games = if self.resume_ongoing_games || snapshot.is_some() {
+            self.client.get_ongoing_games(MAX_ONGOING_GAMES).await?
+        }
+        else {
+            Vec::new()
+        };
+        let snapshot = snapshot.map(|snapshot| {
+            let ongoing_ids = ongoing_games.iter().map(|game| game.game_id.clone()).collect();
+
+            snapshot::reconcile(snapshot, &ongoing_ids)
+        });
+
+        if let (false, Some(snapshot)) = (self.resume_ongoing_games, &snapshot) {
+            ongoing_games.retain(|game| snapshot.active_games.contains(&game.game_id) ||
+                snapshot.accepted_challenges.contains(&game.game_id));
+        }
+
+        let response = self.client.send_request(Method::GET, EVENT_PATH).await?;
+        let bytes_stream =
+            health::monitor_stream(response.bytes_stream(), self.health_monitor.clone());
+        let bytes_stream =
+            events::record_stream(bytes_stream, self.event_recorder.clone(),
+                self.crash_dumper.clone(), None);
+        let resumed_games = ongoing_games.into_iter()
+            .map(|game| Ok(BotEvent::GameStart(game.to_game_start())));
+        let stream = stream::iter(resumed_games)
+            .chain(parsing::parse_stream::<BotEvent, _, _>(bytes_stream, &self.parsing));
+        let systemd_notifier = self.systemd_notifier.clone();
+        let (bot, client, state) = self.into_parts();
+        let state = state.with_profile(profile);
+
+        if let Some(snapshot) = &snapshot {
+            state.session_log.restore(snapshot);
+        }
+
+        let context = BotContext {
+            bot_id: bot_id.clone(),
+            profile: state.profile.clone()
+        };
+        let stream = skip_invalid_lines(
+            stream, Arc::clone(&bot), client.clone(), context, None, &state.parsing);
+
+        let health_monitor = state.health_monitor.clone();
+
+        if let Some(health_monitor) = &health_monitor {
+            health_monitor.set_connected(true);
+        }
+
+        if let Some(notifier) = &systemd_notifier {
+            // TODO enable error handling
+            let _ = notifier.ready();
+        }
+
+        let running = run_with_event_stream(bot, stream, client, bot_id, Arc::new(state));
+        let watchdog = ping_watchdog(
+            systemd_notifier.as_ref().zip(watchdog_interval), health_monitor.as_deref());
+
+        future::select(pin!(running), pin!(watchdog)).await;
+
+        if let Some(health_monitor) = &health_monitor {
+            health_monitor.set_connected(false);
+        }
+
+        if let Some(notifier) = &systemd_notifier {
+            // TODO enable error handling
+            let _ = notifier.stopping();
+        }
+
+        Ok(())
+    }
+
+    /// Feeds the events of a recorded session back through the bot, instead of opening the event
+    /// streams of Lichess, with all features configured on this runner. Game event streams are
+    /// also taken from the recording. Events are spaced out according to the
+    /// [ReplayTiming](events::ReplayTiming) of the replay. Note that requests made by the bot,
+    /// such as submitting moves, are still sent using the runner's client, so it should usually
+    /// point to a test server.
+    ///
+    /// # Arguments
+    ///
+    /// * `replay`: The recorded session to replay.
+    /// * `bot_id`: The [UserId] of the bot's user at the time of the recording.
+    pub async fn replay(self, replay: EventReplay, bot_id: UserId) {
+        let (bot, client, state) = self.into_parts();
+        let session = Arc::new(ReplaySession::new(replay, Instant::now()));
+        let stream = session.bot_events();
+        let state = state.with_replay_session(session);
+
+        run_with_event_stream(bot, stream, client, bot_id, Arc::new(state)).await;
+    }
+
+    /// Loads the last saved [RunnerSnapshot], if state snapshots are enabled and one exists.
+    fn load_snapshot(&self) -> Option<RunnerSnapshot> {
+        self.state_snapshots?;
+
+        // TODO enable error handling
+        self.game_store.as_ref()?.snapshot().ok().flatten()
+    }
+
+    fn into_parts(self) -> (Arc<B>, BotClient, RunnerState) {
+        let mut state = RunnerState::new(self.challenge_queue)
+            .with_challenger_lists(self.challenger_lists)
+            .with_offer_policies(self.offer_policies)
+            .with_parsing_config(self.parsing);
+
+        if let Some(spam_protection) = self.spam_protection {
+            state = state.with_spam_protection(spam_protection);
+        }
+
+        if let Some(schedule) = self.schedule {
+            state = state.with_schedule(schedule);
+        }
+
+        if let Some(tournament) = self.tournament {
+            state = state.with_tournament(tournament);
+        }
+
+        if let Some(move_telemetry) = self.move_telemetry {
+            state = state.with_move_telemetry(move_telemetry);
+        }
+
+        if let Some(rating_history) = self.rating_history {
+            state = state.with_rating_history(rating_history);
+        }
+
+        if let Some(performance_stats) = self.performance_stats {
+            state = state.with_performance_stats(performance_stats);
+        }
+
+        if let Some((archive, interval)) = self.pgn_archive {
+            state = state.with_pgn_archive(archive, interval);
+        }
+
+        if let Some(game_store) = self.game_store {
+            state = state.with_game_store(game_store);
+        }
+
+        if let Some(interval) = self.state_snapshots {
+            state = state.with_state_snapshots(interval);
+        }
+
+        if self.opponent_stats {
+            state = state.with_opponent_stats();
+        }
+
+        if let Some(event_recorder) = self.event_recorder {
+            state = state.with_event_recorder(event_recorder);
+        }
+
+        if let Some(timeout) = self.stale_game_timeout {
+            state = state.with_stale_game_timeout(timeout);
+        }
+
+        if self.automatic_win_claim {
+            state = state.with_automatic_win_claim();
+        }
+
+        if let Some(remind_before) = self.correspondence_reminder {
+            state = state.with_correspondence_reminder(remind_before);
+        }
+
+        if self.speed_validation {
+            state = state.with_speed_validation();
+        }
+
+        if let Some(backpressure) = self.backpressure {
+            state = state.with_backpressure(backpressure);
+        }
+
+        if let Some(game_stream_pacing) = self.game_stream_pacing {
+            state = state.with_game_stream_pacing(game_stream_pacing);
+        }
+
+        if let Some(health_monitor) = self.health_monitor {
+            state = state.with_health_monitor(health_monitor);
+        }
+
+        if let Some(crash_dumper) = self.crash_dumper {
+            state = state.with_crash_dumper(crash_dumper);
+        }
+
+        if let Some(post_game_analysis) = self.post_game_analysis {
+            state = state.with_post_game_analysis(post_game_analysis);
+        }
+
+        if let Some(rematches) = self.rematches {
+            state = state.with_rematches(rematches);
+        }
+
+        if let Some(refresh_interval) = self.spectator_tracking {
+            state = state.with_spectator_tracking(refresh_interval);
+        }
+
+        if let Some(prefetcher) = self.cloud_eval_prefetcher {
+            state = state.with_cloud_eval_prefetcher(prefetcher);
+        }
+
+        if let Some(game_events) = self.game_events {
+            state = state.with_game_events(game_events);
+        }
+
+        (Arc::new(self.bot), self.client, state)
+    }
+}
+
+struct TrackedGame {
+    info: GameInfo,
+    moves: Moves,
+    white_time: Milliseconds,
+    black_time: Milliseconds
+}
+
+pub(crate) struct RunnerState {
+    active_games: Mutex<HashSet<GameId>>,
+    streamed_games: Mutex<HashSet<GameId>>,
+    tracked_games: Mutex<HashMap<GameId, TrackedGame>>,
+    challenge_queue: Option<Mutex<ChallengeQueue>>,
+    spam_protection: Option<Mutex<ChallengeRateTracker>>,
+    schedule: Option<ChallengeSchedule>,
+    tournament: Option<Arc<TournamentMode>>,
+    challenger_lists: Arc<ChallengerLists>,
+    game_store: Option<Arc<dyn GameStore>>,
+    opponent_stats: bool,
+    offer_policies: OfferPolicies,
+    rating_history: Option<Arc<RatingHistory>>,
+    performance_stats: Option<Arc<PerformanceStats>>,
+    pgn_archive: Option<(Arc<PgnArchive>, Duration)>,
+    move_timer: Arc<MoveTimer>,
+    position_tracker: Arc<PositionTracker>,
+    move_watcher: Arc<MoveWatcher>,
+    game_cancellation: Arc<GameCancellation>,
+    session_log: Arc<SessionLog>,
+    snapshot_interval: Option<Duration>,
+    event_recorder: Option<Arc<EventRecorder>>,
+    crash_dumper: Option<Arc<CrashDumper>>,
+    replay_session: Option<Arc<ReplaySession>>,
+    stale_game_timeout: Option<Duration>,
+    automatic_win_claim: bool,
+    correspondence_reminder: Option<Duration>,
+    speed_validation: bool,
+    parsing: ParsingConfig,
+    backpressure: Option<BackpressureConfig>,
+    game_stream_pacer: Option<GameStreamPacer>,
+    health_monitor: Option<Arc<HealthMonitor>>,
+    post_game_analysis: Option<PostGameAnalysisConfig>,
+    rematch_tracker: Option<RematchTracker>,
+    spectator_tracker: Option<Arc<SpectatorTracker>>,
+    cloud_eval_prefetcher: Option<Arc<CloudEvalPrefetcher>>,
+    game_events: Option<Arc<GameEventForwarder>>,
+    profile: ByAddress<RwLock<Option<UserProfile>>>
+}
+
+impl RunnerState {
+
+    pub(crate) fn new(challenge_queue: Option<ChallengeQueueConfig>) -> RunnerState {
+        RunnerState {
+            active_games: Mutex::new(HashSet::new()),
+            streamed_games: Mutex::new(HashSet::new()),
+            tracked_games: Mutex::new(HashMap::new()),
+            challenge_queue: challenge_queue.map(|config| Mutex::new(ChallengeQueue::new(config))),
+            spam_protection: None,
+            schedule: None,
+            tournament: None,
+            challenger_lists: Arc::new(ChallengerLists::default()),
+            game_store: None,
+            opponent_stats: false,
+            offer_policies: OfferPolicies::default(),
+            rating_history: None,
+            performance_stats: None,
+            pgn_archive: None,
+            move_timer: Arc::new(MoveTimer::default()),
+            position_tracker: Arc::new(PositionTracker::default()),
+            move_watcher: Arc::new(MoveWatcher::default()),
+            game_cancellation: Arc::new(GameCancellation::default()),
+            session_log: Arc::new(SessionLog::default()),
+            snapshot_interval: None,
+            event_recorder: None,
+            crash_dumper: None,
+            replay_session: None,
+            stale_game_timeout: None,
+            automatic_win_claim: false,
+            correspondence_reminder: None,
+            speed_validation: false,
+            parsing: ParsingConfig::default(),
+            backpressure: None,
+            game_stream_pacer: None,
+            health_monitor: None,
+            post_game_analysis: None,
+            rematch_tracker: None,
+            spectator_tracker: None,
+            cloud_eval_prefetcher: None,
+            game_events: None,
+            profile: ByAddress::default()
+        }
+    }
+
+    pub(crate) fn with_spam_protection(mut self, config: SpamProtectionConfig) -> RunnerState {
+        self.spam_protection = Some(Mutex::new(ChallengeRateTracker::new(config)));
+        self
+    }
+
+    pub(crate) fn with_schedule(mut self, schedule: ChallengeSchedule) -> RunnerState {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    pub(crate) fn with_tournament(mut self, config: TournamentConfig) -> RunnerState {
+        self.tournament = Some(Arc::new(TournamentMode::new(config)));
+        self
+    }
+
+    pub(crate) fn with_challenger_lists(mut self, challenger_lists: Arc<ChallengerLists>)
+            -> RunnerState {
+        self.challenger_lists = challenger_lists;
+        self
+    }
+
+    pub(crate) fn with_game_store(mut self, game_store: Arc<dyn GameStore>) -> RunnerState {
+        self.game_store = Some(game_store);
+        self
+    }
+
+    pub(crate) fn with_opponent_stats(mut self) -> RunnerState {
+        self.opponent_stats = true;
+        self
+    }
+
+    pub(crate) fn with_offer_policies(mut self, offer_policies: OfferPolicies) -> RunnerState {
+        self.offer_policies = offer_policies;
+        self
+    }
+
+    pub(crate) fn with_move_telemetry(mut self, move_telemetry: Arc<MoveTelemetry>)
+            -> RunnerState {
+        self.move_timer = Arc::new(MoveTimer::new(Some(move_telemetry)));
+        self
+    }
+
+    pub(crate) fn with_rating_history(mut self, rating_history: Arc<RatingHistory>)
+            -> RunnerState {
+        self.rating_history = Some(rating_history);
+        self
+    }
+
+    pub(crate) fn with_performance_stats(mut self, performance_stats: Arc<PerformanceStats>)
+            -> RunnerState {
+        self.performance_stats = Some(performance_stats);
+        self
+    }
+
+    pub(crate) fn with_pgn_archive(mut self, archive: Arc<PgnArchive>, interval: Duration)
+            -> RunnerState {
+        self.pgn_archive = Some((archive, interval));
+        self
+    }
+
+    pub(crate) fn with_event_recorder(mut self, event_recorder: Arc<EventRecorder>)
+            -> RunnerState {
+        self.event_recorder = Some(event_recorder);
+        self
+    }
+
+    pub(crate) fn with_replay_session(mut self, replay_session: Arc<ReplaySession>)
+            -> RunnerState {
+        self.replay_session = Some(replay_session);
+        self
+    }
+
+    pub(crate) fn with_stale_game_timeout(mut self, timeout: Duration) -> RunnerState {
+        self.stale_game_timeout = Some(timeout);
+        self
+    }
+
+    pub(crate) fn with_automatic_win_claim(mut self) -> RunnerState {
+        self.automatic_win_claim = true;
+        self
+    }
+
+    pub(crate) fn with_correspondence_reminder(mut self, remind_before: Duration)
+            -> RunnerState {
+        self.correspondence_reminder = Some(remind_before);
+        self
+    }
+
+    pub(crate) fn with_speed_validation(mut self) -> RunnerState {
+        self.speed_validation = true;
+        self
+    }
+
+    pub(crate) fn with_state_snapshots(mut self, interval: Duration) -> RunnerState {
+        self.snapshot_interval = Some(interval);
+        self
+    }
+
+    pub(crate) fn with_profile(mut self, profile: UserProfile) -> RunnerState {
+        self.profile = ByAddress(Arc::new(RwLock::new(Some(profile))));
+        self
+    }
+
+    pub(crate) fn with_parsing_config(mut self, parsing: ParsingConfig) -> RunnerState {
+        self.parsing = parsing;
+        self
+    }
+
+    pub(crate) fn with_backpressure(mut self, backpressure: BackpressureConfig) -> RunnerState {
+        self.backpressure = Some(backpressure);
+        self
+    }
+
+    pub(crate) fn with_game_stream_pacing(mut self, pacing: GameStreamPacing) -> RunnerState {
+        self.game_stream_pacer = Some(GameStreamPacer::new(pacing));
+        self
+    }
+
+    pub(crate) fn with_crash_dumper(mut self, crash_dumper: Arc<CrashDumper>) -> RunnerState {
+        self.crash_dumper = Some(crash_dumper);
+        self
+    }
+
+    pub(crate) fn with_post_game_analysis(mut self, config: PostGameAnalysisConfig)
+            -> RunnerState {
+        self.post_game_analysis = Some(config);
+        self
+    }
+
+    pub(crate) fn with_rematches(mut self, config: RematchConfig) -> RunnerState {
+        self.rematch_tracker = Some(RematchTracker::new(config));
+        self
+    }
+
+    pub(crate) fn with_spectator_tracking(mut self, refresh_interval: Duration) -> RunnerState {
+        self.spectator_tracker = Some(Arc::new(SpectatorTracker::new(refresh_interval)));
+        self
+    }
+
+    pub(crate) fn with_cloud_eval_prefetcher(mut self, prefetcher: Arc<CloudEvalPrefetcher>)
+            -> RunnerState {
+        self.cloud_eval_prefetcher = Some(prefetcher);
+        self
+    }
+
+    pub(crate) fn with_game_events(mut self, game_events: Arc<GameEventForwarder>)
+            -> RunnerState {
+        self.game_events = Some(game_events);
+        self
+    }
+
+    pub(crate) fn with_health_monitor(mut self, monitor: Arc<HealthMonitor>) -> RunnerState {
+        self.health_monitor = Some(monitor);
+        self
+    }
+
+    /// Marks the game with the given ID as active. Lichess may re-send the start event of a
+    /// running game, for example after reconnecting, in which case nothing happens.
+    fn game_started(&self, game_id: &GameId) {
+        let mut active_games = self.active_games.lock().unwrap();
+
+        if !active_games.insert(game_id.clone()) {
+            return;
+        }
+
+        self.report_active_games(active_games.len());
+        drop(active_games);
+
+        self.game_cancellation.game_started(game_id);
+
+        if let Some(challenge_queue) = &self.challenge_queue {
+            challenge_queue.lock().unwrap().mark_started(game_id);
+        }
+    }
+
+    /// Marks the event stream of the game with the given ID as open, returning `false` if it
+    /// already was. A start event which Lichess re-sends while the game is still streamed must not
+    /// cause it to be streamed and handled twice, while one re-sent after the stream was closed,
+    /// e.g. because the connection dropped, should open it again.
+    fn game_stream_opened(&self, game_id: &GameId) -> bool {
+        self.streamed_games.lock().unwrap().insert(game_id.clone())
+    }
+
+    fn game_stream_closed(&self, game_id: &GameId) {
+        self.streamed_games.lock().unwrap().remove(game_id);
+    }
+
+    fn track_game(&self, info: &GameInfo, state: &GameStateEvent) {
+        let tracked_game = TrackedGame {
+            info: info.clone(),
+            moves: state.moves.clone(),
+            white_time: state.white_time,
+            black_time: state.black_time
+        };
+
+        self.tracked_games.lock().unwrap().insert(info.id.clone(), tracked_game);
+    }
+
+    fn update_tracked_game(&self, game_id: &GameId, state: &GameStateEvent) {
+        if let Some(tracked_game) = self.tracked_games.lock().unwrap().get_mut(game_id) {
+            tracked_game.moves.clone_from(&state.moves);
+            tracked_game.white_time = state.white_time;
+            tracked_game.black_time = state.black_time;
+        }
+    }
+
+    fn tracked_moves(&self, game_id: &GameId) -> Option<Moves> {
+        self.tracked_games.lock().unwrap().get(game_id)
+            .map(|tracked_game| tracked_game.moves.clone())
+    }
+
+    fn game_finished(&self, game_id: &GameId) -> Option<TrackedGame> {
+        let mut active_games = self.active_games.lock().unwrap();
+        active_games.remove(game_id);
+        self.report_active_games(active_games.len());
+        drop(active_games);
+
+        self.session_log.game_finished(game_id);
+        self.tracked_games.lock().unwrap().remove(game_id)
+    }
+
+    fn report_active_games(&self, active_games: usize) {
+        if let Some(health_monitor) = &self.health_monitor {
+            health_monitor.set_active_games(active_games);
+        }
+    }
+}
+
+async fn process_game_event(event: GameEvent, game_context: &GameContext, bot: &impl Bot,
+        client: &BotClient) {
+    // TODO enable error handling
+    match event {
+        GameEvent::GameFull(_) => panic!(), // TODO proper error handling
+        GameEvent::GameState(state) =>
+            bot.on_game_state(game_context, state, client).await,
+        GameEvent::ChatLine(chat_line) =>
+            bot.on_chat_line(game_context, chat_line, client).await,
+        GameEvent::OpponentGone(opponent_gone) =>
+            bot.on_opponent_gone(game_context, opponent_gone, client).await,
+    }
+}
+
+/// Determines whether the game with the given context is stale, i.e. still running with the given
+/// moves, of which none was made by the opponent. Games in which the color of the bot is unknown
+/// are never considered stale.
+fn is_stale(game_context: &GameContext, moves: &str) -> bool {
+    let Some(bot_color) = game_context.bot_color
+    else {
+        return false;
+    };
+    let opponent_first_ply = if game_context.starting_color() == bot_color { 1 } else { 0 };
+
+    moves.split_whitespace().count() <= opponent_first_ply
+}
+
+async fn abort_if_stale(game_context: &GameContext, state: &RunnerState, client: &BotClient) {
+    let Some(moves) = state.tracked_moves(&game_context.id)
+    else {
+        return;
+    };
+
+    if is_stale(game_context, &moves) {
+        // TODO enable error handling
+        let _ = client.abort_game(game_context.id.clone()).await;
+    }
+}
+
+/// Waits until the stale game timeout has elapsed since the creation of the game with the given
+/// context and then aborts it if the opponent has not moved yet. Never completes, so it can be
+/// raced against the game event stream.
+async fn watch_stale_game(game_context: &GameContext, state: &RunnerState, client: &BotClient) {
+    if let Some(timeout) = state.stale_game_timeout {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let created_at = Duration::from_millis(game_context.created_at.max(0) as u64);
+        let age = now.saturating_sub(created_at);
+
+        tokio::time::sleep(timeout.saturating_sub(age)).await;
+        abort_if_stale(game_context, state, client).await;
+    }
+
+    future::pending().await
+}
+
+/// Waits until victory can be claimed at the instant sent through the given receiver whenever an
+/// [OpponentGoneEvent](crate::model::game::event::OpponentGoneEvent) arrives. At that instant,
+/// victory is claimed if the runner is configured to do so and [Bot::on_claimable_win] is called.
+/// If the opponent returns before, the claim is cancelled. Never completes, so it can be raced
+/// against the game event stream.
+async fn schedule_win_claims(mut claimable_at: watch::Receiver<Option<Instant>>,
+        game_context: &GameContext, bot: &impl Bot, state: &RunnerState, client: &BotClient) {
+    loop {
+        let deadline = *claimable_at.borrow_and_update();
+        let changed = match deadline {
+            Some(deadline) => tokio::select! {
+                changed = claimable_at.changed() => changed,
+                _ = tokio::time::sleep_until(deadline.into()) => {
+                    if state.automatic_win_claim {
+                        // TODO enable error handling
+                        let _ = client.claim_victory(game_context.id.clone()).await;
+                    }
+
+                    bot.on_claimable_win(game_context, client).await;
+                    claimable_at.changed().await
+                }
+            },
+            None => claimable_at.changed().await
+        };
+
+        if changed.is_err() {
+            return future::pending().await;
+        }
+    }
+}
+
+fn turn_reminder(game_context: &GameContext, game_state: &GameStateEvent, state: &RunnerState)
+        -> Option<(Instant, GameStateEvent)> {
+    let remind_before = state.correspondence_reminder?;
+    let reminder_at = turn::reminder_at(game_context, game_state, remind_before, Instant::now())?;
+
+    Some((reminder_at, game_state.clone()))
+}
+
+/// Waits until the instant sent through the given receiver whenever a turn of the bot starts in a
+/// correspondence game and then reminds the bot of its turn by calling [Bot::on_my_turn] again
+/// with the game state which started the turn. If the turn ends before, the reminder is
+/// cancelled. Never completes, so it can be raced against the game event stream.
+async fn schedule_turn_reminders(
+        mut reminder: watch::Receiver<Option<(Instant, GameStateEvent)>>,
+        game_context: &GameContext, bot: &impl Bot, client: &BotClient) {
+    loop {
+        let next_reminder = reminder.borrow_and_update().clone();
+        let changed = match next_reminder {
+            Some((deadline, game_state)) => tokio::select! {
+                changed = reminder.changed() => changed,
+                _ = tokio::time::sleep_until(deadline.into()) => {
+                    bot.on_my_turn(game_context, game_state, client).await;
+                    reminder.changed().await
+                }
+            },
+            None => reminder.changed().await
+        };
+
+        if changed.is_err() {
+            return future::pending().await;
+        }
+    }
+}
+
+fn update_move_timer(game_context: &GameContext, state: &GameStateEvent, move_timer: &MoveTimer) {
+    if game_context.is_my_turn(state) {
+        let increment = match game_context.bot_color {
+            Some(Color::Black) => state.black_increment,
+            _ => state.white_increment
+        };
+        let plies = state.moves.split_whitespace().count();
+
+        move_timer.turn_started(&game_context.id, plies, increment, Instant::now());
+    }
+    else {
+        move_timer.turn_ended(&game_context.id);
+    }
+}
+
+async fn process_offers(offers: Vec<(OfferKind, Color)>, state: GameStateEvent,
+        offer_policies: &OfferPolicies, game_context: &GameContext, bot: &impl Bot,
+        client: &BotClient) {
+    for (offer, color) in offers {
+        match offer {
+            OfferKind::Draw => bot.on_draw_offered(game_context, color, client).await,
+            OfferKind::Takeback => bot.on_takeback_proposed(game_context, color, client).await
+        }
+
+        if game_context.bot_color != Some(color.opposite()) {
+            continue;
+        }
+
+        let game_id = game_context.id.clone();
+
+        let result = match (offer, offer_policies.respond(offer, game_context, &state)) {
+            (OfferKind::Draw, OfferResponse::Accept) => client.offer_or_accept_draw(game_id).await,
+            (OfferKind::Draw, OfferResponse::Decline) => client.decline_draw(game_id).await,
+            (OfferKind::Takeback, OfferResponse::Accept) => client.accept_takeback(game_id).await,
+            (OfferKind::Takeback, OfferResponse::Decline) =>
+                client.decline_takeback(game_id).await,
+            (OfferKind::Draw, OfferResponse::Delegate) => {
+                bot.on_draw_offer(game_context, state.clone(), client).await;
+                Ok(())
+            },
+            (OfferKind::Takeback, OfferResponse::Delegate) => {
+                bot.on_takeback_proposal(game_context, state.clone(), client).await;
+                Ok(())
+            }
+        };
+
+        // TODO enable error handling
+        let _ = result;
+    }
+}
+
+async fn opponent_stats(info: &GameInfo, bot_color: Option<Color>, bot_id: &str,
+        state: &RunnerState, client: &BotClient) -> Option<OpponentStats> {
+    if !state.opponent_stats {
+        return None;
+    }
+
+    let opponent = info.player_ref(bot_color?.opposite());
+    let opponent_id = opponent.id()?;
+    let game_store = state.game_store.as_deref();
+
+    let stats = stats::collect_opponent_stats(game_store, client, bot_id, opponent_id);
+
+    tokio::time::timeout(OPPONENT_STATS_TIMEOUT, stats).await.ok()
+}
+
+async fn run_with_game_event_stream<E>(bot: Arc<impl Bot + Send + 'static>,
+    event_stream: impl Stream<Item = Result<GameEvent, E>>, client: BotClient, bot_id: UserId,
+    state: &RunnerState)
+where
+    E: Debug + Send + 'static
+{
+    let game_context;
+    let mut offer_tracker = OfferTracker::default();
+    let mut turn_tracker = TurnTracker::default();
+    let (reminder_sender, reminder_receiver) = watch::channel(None);
+    let mut event_stream = pin!(event_stream);
+
+    let (initial_state, initial_offers, initial_turn) = match event_stream.next().await {
+        Some(Ok(GameEvent::GameFull(game_full))) => {
+            let bot_color = game_full.info.color_of(&bot_id);
+            let opponent_stats =
+                opponent_stats(&game_full.info, bot_color, &bot_id, state, &client).await;
+
+            game_context = GameContext {
+                bot_color,
+                bot_id: bot_id.clone(),
+                info: game_full.info,
+                opponent_stats,
+                move_timer: Some(ByAddress(Arc::clone(&state.move_timer))),
+                position_tracker: Some(ByAddress(Arc::clone(&state.position_tracker))),
+                move_watcher: Some(ByAddress(Arc::clone(&state.move_watcher))),
+                game_cancellation:
+                    Some(ByAddress(Arc::clone(&state.game_cancellation))),
+                session_log: Some(ByAddress(Arc::clone(&state.session_log))),
+                spectators: state.spectator_tracker.clone().map(ByAddress)
+            };
+
+            state.track_game(&game_context.info, &game_full.state);
+
+            if let Some(game_events) = &state.game_events {
+                game_events.game_full(&game_context, &game_full.state);
+            }
+
+            state.position_tracker.game_started(&game_context.info, &game_full.state.moves);
+            state.move_watcher.update(&game_context.info.id, &game_full.state.moves);
+            update_move_timer(&game_context, &game_full.state, &state.move_timer);
+
+            if let Some(prefetcher) = &state.cloud_eval_prefetcher {
+                prefetcher.game_state(&client, &game_context, &game_full.state);
+            }
+
+            let offers = offer_tracker.update(&game_full.state);
+            let new_turn = turn_tracker.update(&game_context, &game_full.state);
+
+            if new_turn {
+                reminder_sender.send_replace(
+                    turn_reminder(&game_context, &game_full.state, state));
+            }
+
+            (game_full.state, offers, new_turn)
+        },
+        Some(_) => panic!(), // TODO proper error handling
+        None => return
+    };
+
+    let game_context = Arc::new(game_context);
+
+    // The initial game state is processed concurrently with the following events, so the bot can
+    // wait for its first move to be confirmed by a later game state.
+    let initial = async {
+        let game_state = initial_state.clone();
+
+        if let Some(tournament) = &state.tournament {
+            let plies = game_state.moves.split_whitespace().count();
+            let bot_moved = match game_context.bot_color {
+                Some(Color::White) => plies > 0,
+                Some(Color::Black) => plies > 1,
+                None => true
+            };
+
+            if !bot_moved && tournament.berserk(&game_context) {
+                let berserk = client.berserk(game_context.info.id.clone());
+
+                // TODO enable error handling
+                let _ = game_context.run_for_game(berserk).await;
+            }
+        }
+
+        if let Some(warning) = InvalidPosition::detect(&game_context.info) {
+            bot.on_invalid_position(&game_context, warning, &client).await;
+        }
+
+        bot.on_game_state(&game_context, initial_state, &client).await;
+
+        if initial_turn {
+            bot.on_my_turn(&game_context, game_state.clone(), &client).await;
+        }
+
+        process_offers(initial_offers, game_state, &state.offer_policies, &game_context,
+            bot.as_ref(), &client).await
+    };
+
+    let (claimable_at_sender, claimable_at_receiver) = watch::channel(None);
+    let queue = EventQueue::new(state.backpressure.as_ref());
+    let reader = event_stream.map(|record| {
+        let bot = Arc::clone(&bot);
+        let client = client.clone();
+        let game_context = Arc::clone(&game_context);
+        let offer_policies = state.offer_policies.clone();
+        let mut offers = None;
+        let mut new_turn = None;
+        let mut abort = false;
+
+        if let (Ok(event), Some(game_events)) = (&record, &state.game_events) {
+            game_events.event(&game_context.id, event);
+        }
+
+        if let Ok(GameEvent::GameState(game_state)) = &record {
+            state.update_tracked_game(&game_context.id, game_state);
+            state.position_tracker.update(&game_context.id, &game_state.moves);
+            state.move_watcher.update(&game_context.id, &game_state.moves);
+            update_move_timer(&game_context, game_state, &state.move_timer);
+
+            if let Some(prefetcher) = &state.cloud_eval_prefetcher {
+                prefetcher.game_state(&client, &game_context, game_state);
+            }
+
+            let new_offers = offer_tracker.update(game_state);
+
+            if !new_offers.is_empty() {
+                offers = Some((new_offers, game_state.clone()));
+            }
+
+            if turn_tracker.update(&game_context, game_state) {
+                reminder_sender.send_replace(turn_reminder(&game_context, game_state, state));
+                new_turn = Some(game_state.clone());
+            }
+            else if !game_context.is_my_turn(game_state) {
+                reminder_sender.send_replace(None);
+            }
+        }
+
+        if let (Ok(GameEvent::ChatLine(chat_line)), Some(spectator_tracker)) =
+                (&record, &state.spectator_tracker) {
+            spectator_tracker.chat_line(&game_context.id, &chat_line.chat_line);
+        }
+
+        if matches!(&record, Ok(GameEvent::ChatLine(chat_line))
+                if chat_line.chat_line.username.eq_ignore_ascii_case(&game_context.bot_id)) {
+            state.session_log.chat_sent(&game_context.id);
+        }
+
+        if let Ok(GameEvent::OpponentGone(opponent_gone)) = &record {
+            abort = opponent_gone.gone && state.stale_game_timeout.is_some() &&
+                state.tracked_moves(&game_context.id)
+                    .is_some_and(|moves| is_stale(&game_context, &moves));
+
+            let claimable_at = opponent_gone.claim_win_in_seconds
+                .filter(|_| opponent_gone.gone)
+                .map(|seconds| Instant::now() + Duration::from_secs(seconds.max(0) as u64));
+
+            claimable_at_sender.send_replace(claimable_at);
+        }
+
+        let kind = match &record {
+            Ok(GameEvent::GameState(_)) if offers.is_none() && new_turn.is_none() =>
+                QueuedEventKind::GameState,
+            Ok(GameEvent::ChatLine(_)) => QueuedEventKind::ChatLine,
+            _ => QueuedEventKind::Other
+        };
+
+        let handler: GameEventHandler = Box::new(move |skipped_states| Box::pin(async move {
+            let mut event = record.unwrap();
+
+            if let GameEvent::GameState(game_state) = &mut event {
+                game_state.skipped_states = skipped_states;
+            }
+
+            if abort {
+                // TODO enable error handling
+                let _ = client.abort_game(game_context.id.clone()).await;
+            }
+
+            process_game_event(event, game_context.as_ref(), bot.as_ref(), &client).await;
+
+            if let Some(game_state) = new_turn {
+                bot.on_my_turn(game_context.as_ref(), game_state, &client).await;
+            }
+
+            if let Some((offers, game_state)) = offers {
+                process_offers(offers, game_state, &offer_policies, game_context.as_ref(),
+                    bot.as_ref(), &client).await;
+            }
+        }));
+
+        (kind, handler)
+    }).for_each(|(kind, handler)| queue.push(kind, handler));
+    let reader = async {
+        reader.await;
+        queue.close();
+    };
+    let max_concurrent_handlers =
+        state.backpressure.as_ref().map(|backpressure| backpressure.max_concurrent_handlers);
+    let handlers = run_game_event_handlers(&queue, max_concurrent_handlers);
+    let remaining = pin!(future::join(reader, handlers));
+    let watchdog = watch_stale_game(&game_context, state, &client);
+    let win_claims =
+        schedule_win_claims(claimable_at_receiver, &game_context, bot.as_ref(), state, &client);
+    let reminders =
+        schedule_turn_reminders(reminder_receiver, &game_context, bot.as_ref(), &client);
+    let background = pin!(future::join3(watchdog, win_claims, reminders));
+
+    futures::join!(initial, future::select(remaining, background));
+}
+
+/// Pings the systemd watchdog in the given interval as long as the bot is live according to the
+/// given monitor. Never returns, so it is intended to be raced against the event stream.
+async fn ping_watchdog(watchdog: Option<(&SystemdNotifier, Duration)>,
+        health_monitor: Option<&HealthMonitor>) {
+    let Some((notifier, interval)) = watchdog
+    else {
+        return future::pending().await;
+    };
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        if health_monitor.is_none_or(|health_monitor| health_monitor.report().live) {
+            // TODO enable error handling
+            let _ = notifier.watchdog();
+        }
+    }
+}
+
+/// Creates the future which handles an event of a game, given the number of game states it
+/// replaced in the [EventQueue].
+type GameEventHandler = Box<dyn FnOnce(usize) -> BoxFuture<'static, ()> + Send>;
+
+async fn run_game_event_handlers(queue: &EventQueue<GameEventHandler>,
+        max_concurrent_handlers: Option<usize>) {
+    stream::unfold(queue, |queue| async move {
+        queue.pop().await.map(|handler| (handler, queue))
+    }).for_each_concurrent(max_concurrent_handlers, |(handler, skipped_states)| async move {
+        task::spawn(handler(skipped_states)).await.unwrap()
+    }).await
+}
+
+async fn accept_queued_challenges(state: &RunnerState, client: &BotClient) {
+    let Some(challenge_queue) = &state.challenge_queue
+    else {
+        return;
+    };
+
+    let (expired, acceptable) = {
+        let now = Instant::now();
+        let mut challenge_queue = challenge_queue.lock().unwrap();
+        let expired = challenge_queue.remove_expired(now);
+        challenge_queue.release_timed_out(now);
+        let active_games = state.active_games.lock().unwrap().len();
+        let acceptable = challenge_queue.pop_acceptable(active_games, now);
+
+        (expired, acceptable)
+    };
+
+    for challenge in expired {
+        // TODO enable error handling
+        let _ = client.decline_challenge(challenge.id, Some(DeclineReason::Later)).await;
+    }
+
+    let mut acceptable = acceptable;
+
+    while !acceptable.is_empty() {
+        let mut vacated = false;
+
+        for challenge in acceptable {
+            match client.accept_challenge(challenge.id.clone()).await {
+                Ok(()) => state.session_log.challenge_accepted(&challenge.id),
+
+                // The game starts regardless, which marks the challenge as started.
+                Err(LibotRequestError::ChallengeAcceptError(
+                    ChallengeAcceptError::AlreadyAccepted)) =>
+                    state.session_log.challenge_accepted(&challenge.id),
+                Err(LibotRequestError::ChallengeAcceptError(ChallengeAcceptError::NotFound)) => {
+                    challenge_queue.lock().unwrap().release(&challenge.id);
+                    vacated = true;
+                },
+
+                // TODO enable error handling
+                Err(_) => challenge_queue.lock().unwrap().release(&challenge.id)
+            }
+        }
+
+        if !vacated {
+            break;
+        }
+
+        let active_games = state.active_games.lock().unwrap().len();
+        acceptable =
+            challenge_queue.lock().unwrap().pop_acceptable(active_games, Instant::now());
+    }
+}
+
+/// Declines the given challenge with [DeclineReason::Later] if the challenger is blocked or
+/// exceeds the rate allowed by the spam protection. Allowed users and the bot itself are exempt.
+/// Returns whether the challenge was declined.
+async fn decline_spam(challenge: &Challenge, state: &RunnerState, client: &BotClient,
+        context: &BotContext) -> bool {
+    let challenger = &challenge.challenger.id;
+
+    if challenger == &context.bot_id || state.challenger_lists.is_allowed(challenger) {
+        return false;
+    }
+
+    let spam = state.challenger_lists.is_blocked(challenger) ||
+        state.spam_protection.as_ref().is_some_and(|spam_protection|
+            spam_protection.lock().unwrap().record(challenger, Instant::now()));
+
+    if spam {
+        // TODO enable error handling
+        let _ = client.decline_challenge(challenge.id.clone(), Some(DeclineReason::Later)).await;
+    }
+
+    spam
+}
+
+async fn decline_while_suspended(challenge: &Challenge, state: &RunnerState, client: &BotClient,
+        context: &BotContext) -> bool {
+    if challenge.challenger.id == context.bot_id {
+        return false;
+    }
+
+    let inactive = state.schedule.as_ref().is_some_and(|schedule| !schedule.is_active()) ||
+        state.tournament.as_ref().is_some_and(|tournament| tournament.is_playing());
+
+    if inactive {
+        // TODO enable error handling
+        let _ = client.decline_challenge(challenge.id.clone(), Some(DeclineReason::Later)).await;
+    }
+
+    inactive
+}
+
+/// Accepts or declines the given challenge if it is a rematch which the rematch configuration of
+/// the runner handles automatically.
+///
+/// # Returns
+///
+/// `true` if and only if the challenge was accepted or declined.
+async fn answer_rematch(challenge: &Challenge, state: &RunnerState, client: &BotClient,
+        context: &BotContext) -> bool {
+    let (Some(rematch_tracker), Some(rematch_of)) = (&state.rematch_tracker, &challenge.rematch_of)
+    else {
+        return false;
+    };
+
+    if challenge.challenger.id == context.bot_id {
+        return false;
+    }
+
+    match rematch_tracker.rematch_offered(&challenge.challenger.id, rematch_of) {
+        RematchDecision::Accept => {
+            // TODO enable error handling
+            if client.accept_challenge(challenge.id.clone()).await.is_ok() {
+                state.session_log.challenge_accepted(&challenge.id);
+            }
+        },
+        RematchDecision::Decline => {
+            // TODO enable error handling
+            let _ = client.decline_challenge(challenge.id.clone(), Some(DeclineReason::Later))
+                .await;
+        },
+        RematchDecision::Pass => return false
+    }
+
+    true
+}
+
+/// Records the given finished game in the rematch tracker of the runner, if any.
+///
+/// # Returns
+///
+/// The opponent to which to offer a rematch, the ID of the finished game, and the challenge to
+/// send, if a rematch should be offered after the offer delay.
+fn prepare_rematch(info: &GameInfo, state: &RunnerState, context: &BotContext)
+        -> Option<(UserId, GameId, ChallengeRequest)> {
+    let rematch_tracker = state.rematch_tracker.as_ref()?;
+    let (color, opponent) = rematch::opponent(info, &context.bot_id)?;
+
+    if !rematch_tracker.game_finished(&opponent, &info.id, Instant::now()) {
+        return None;
+    }
+
+    let request = rematch::rematch_request(info, color)?;
+
+    Some((opponent, info.id.clone(), request))
+}
+
+async fn offer_rematch(opponent: &UserId, game_id: &GameId, request: &ChallengeRequest,
+        state: &RunnerState, client: &BotClient) {
+    let Some(rematch_tracker) = &state.rematch_tracker
+    else {
+        return;
+    };
+
+    tokio::time::sleep(rematch_tracker.offer_delay()).await;
+
+    let suspended = state.schedule.as_ref().is_some_and(|schedule| !schedule.is_active()) ||
+        state.tournament.as_ref().is_some_and(|tournament| tournament.is_playing());
+
+    if !suspended && rematch_tracker.should_offer(opponent, game_id) {
+        // TODO enable error handling
+        let _ = client.create_challenge(opponent, request).await;
+    }
+}
+
+async fn queue_challenge(challenge: &Challenge, state: &RunnerState, client: &BotClient,
+        context: &BotContext) {
+    let Some(challenge_queue) = &state.challenge_queue
+    else {
+        return;
+    };
+
+    if challenge.challenger.id == context.bot_id {
+        return;
+    }
+
+    let decision = challenge_queue.lock().unwrap().decide(challenge);
+
+    match decision {
+        ChallengeDecision::Enqueue { priority } => {
+            challenge_queue.lock().unwrap()
+                .enqueue(challenge.clone(), priority, Instant::now());
+            accept_queued_challenges(state, client).await;
+        },
+        ChallengeDecision::Decline(reason) => {
+            // TODO enable error handling
+            let _ = client.decline_challenge(challenge.id.clone(), reason).await;
+        },
+        ChallengeDecision::Ignore => { }
+    }
+}
+
+async fn fetch_rating_diff(game: &GameStartFinish, client: &BotClient) -> Option<Rating> {
+    if game.rated != Some(true) {
+        return None;
+    }
+
+    let color = game.color?;
+
+    // TODO enable error handling
+    let exported_game = client.export_game(game.id.clone()?).await.ok()?;
+
+    exported_game.players.of(color).rating_diff
+}
+
+/// Fetches the rating of the bot in the rating category of the given finished game, if it was
+/// rated. The rating before the game is taken from the start of the tracked game if available, and
+/// otherwise derived from the rating change reported by Lichess.
+async fn fetch_rating_update(game: &GameStartFinish, tracked_game: Option<&TrackedGame>,
+        rating_diff: Option<Rating>, client: &BotClient) -> Option<RatingUpdate> {
+    if game.rated != Some(true) {
+        return None;
+    }
+
+    let perf = PerfKey::of_game(game.speed?, game.variant?)?;
+
+    // TODO enable error handling
+    let new = client.get_my_profile().await.ok()?.perfs.get(perf)?.rating;
+    let old_rating = tracked_game.zip(game.color)
+        .and_then(|(tracked_game, color)| tracked_game.info.player_ref(color).rating());
+    let old = old_rating.or_else(|| rating_diff.map(|rating_diff| new - rating_diff))?;
+
+    Some(RatingUpdate {
+        game_id: game.id.clone(),
+        perf,
+        old,
+        new
+    })
+}
+
+/// Opens the event stream of the game with the given ID and handles its events until the stream
+/// ends. Nothing happens if the stream cannot be opened.
+async fn stream_game(game_id: &GameId, bot: Arc<impl Bot + Send + 'static>, client: BotClient,
+        context: &BotContext, state: &RunnerState) {
+    if let Some(replay_session) = &state.replay_session {
+        let stream = replay_session.game_events(game_id);
+
+        return run_with_game_event_stream(bot, stream, client, context.bot_id.clone(), state)
+            .await;
+    }
+
+    let event_path = game_event_path(&client, game_id);
+    let response = match &state.game_stream_pacer {
+        Some(pacer) => pacer.open(&client, &event_path).await,
+        None => client.send_request(Method::GET, &event_path).await
+    };
+
+    // TODO enable error handling
+    if let Ok(response) = response {
+        let bytes_stream = health::monitor_stream(
+            response.bytes_stream(), state.health_monitor.clone());
+        let bytes_stream = events::record_stream(bytes_stream, state.event_recorder.clone(),
+            state.crash_dumper.clone(), Some(game_id.clone()));
+        let stream = parsing::parse_stream::<GameEvent, _, _>(bytes_stream, &state.parsing);
+        let stream = skip_invalid_lines(stream, Arc::clone(&bot), client.clone(),
+            context.clone(), Some(game_id.clone()), &state.parsing);
+
+        run_with_game_event_stream(bot, stream, client, context.bot_id.clone(), state).await
+    }
+}
+
+async fn process_bot_event(event: BotEvent, bot: Arc<impl Bot + Send + 'static>,
+        client: BotClient, context: &BotContext, state: &RunnerState) {
+    // TODO enable error handling
+    match event {
+        BotEvent::GameStart(game) => {
+            let game_id = game.id.clone();
+
+            if let Some(game_id) = &game_id {
+                state.game_started(game_id);
+
+                if !state.game_stream_opened(game_id) {
+                    return;
+                }
+            }
+
+            bot.as_ref().on_game_start(context, game, &client).await;
+
+            if let Some(game_id) = game_id {
+                stream_game(&game_id, bot, client, context, state).await;
+                state.game_stream_closed(&game_id);
+
+                if let Some(game_events) = &state.game_events {
+                    game_events.game_stream_closed(&game_id);
+                }
+            }
+        },
+        BotEvent::GameFinish(game) => {
+            if let Some(game_id) = &game.id {
+                state.move_timer.game_finished(game_id);
+                state.position_tracker.game_finished(game_id);
+                state.move_watcher.game_finished(game_id);
+                state.game_cancellation.game_finished(game_id);
+                client.chat_game_finished(game_id);
+
+                if let Some(crash_dumper) = &state.crash_dumper {
+                    crash_dumper.game_finished(game_id);
+                }
+
+                if let Some(spectator_tracker) = &state.spectator_tracker {
+                    spectator_tracker.game_finished(game_id);
+                }
+
+                if let Some(prefetcher) = &state.cloud_eval_prefetcher {
+                    prefetcher.game_finished(game_id);
+                }
+            }
+
+            let tracked_game = game.id.as_ref().and_then(|game_id| state.game_finished(game_id));
+            let rematch = tracked_game.as_ref()
+                .and_then(|tracked_game| prepare_rematch(&tracked_game.info, state, context));
+            let clock_times = tracked_game.as_ref()
+                .map(|tracked_game| (tracked_game.white_time, tracked_game.black_time));
+
+            accept_queued_challenges(state, &client).await;
+
+            let rating_diff = fetch_rating_diff(&game, &client).await;
+            let result = GameResult::new(&game, clock_times, rating_diff);
+
+            let record = tracked_game.as_ref()
+                .filter(|_| state.game_store.is_some() || state.performance_stats.is_some())
+                .map(|tracked_game|
+                    GameRecord::new(&tracked_game.info, &tracked_game.moves, &result));
+
+            if let (Some(game_store), Some(record)) = (&state.game_store, &record) {
+                // TODO enable error handling
+                let _ = game_store.record_game(record);
+            }
+
+            if let (Some(performance_stats), Some(record)) = (&state.performance_stats, &record) {
+                performance_stats.record(record);
+            }
+
+            bot.as_ref().on_game_finish(context, game.clone(), result, &client).await;
+
+            if let Some(rating_history) = &state.rating_history {
+                let rating_update =
+                    fetch_rating_update(&game, tracked_game.as_ref(), rating_diff, &client).await;
+
+                if let Some(rating_update) = rating_update {
+                    rating_history.record(rating_update.clone());
+                    bot.as_ref().on_rating_update(context, game, rating_update, &client).await;
+                }
+            }
+
+            if let (Some(config), Some(tracked_game)) = (&state.post_game_analysis, &tracked_game) {
+                post_game::post_summary(config, &client, &tracked_game.info, &tracked_game.moves)
+                    .await;
+            }
+
+            if let Some((opponent, game_id, request)) = rematch {
+                offer_rematch(&opponent, &game_id, &request, state, &client).await;
+            }
+        },
+        BotEvent::Challenge(challenge) => {
+            if state.session_log.was_accepted(&challenge.id) {
+                // Lichess re-sent a challenge that was already accepted, e.g. before a restart.
+                return;
+            }
+
+            let expected_speed = challenge.time_control.speed();
+
+            if state.speed_validation && challenge.speed != expected_speed {
+                let error = RunnerError::InconsistentSpeed {
+                    challenge_id: challenge.id.clone(),
+                    reported: challenge.speed,
+                    expected: expected_speed
+                };
+
+                bot.as_ref().on_error(context, None, error, &client).await;
+            }
+
+            if decline_while_suspended(&challenge, state, &client, context).await ||
+                    decline_spam(&challenge, state, &client, context).await {
+                return;
+            }
+
+            if answer_rematch(&challenge, state, &client, context).await {
+                return;
+            }
+
+            queue_challenge(&challenge, state, &client, context).await;
+
+            if challenge.rematch_of.is_some() {
+                bot.as_ref().on_rematch_offer(context, challenge, &client).await
+            }
+            else {
+                bot.as_ref().on_challenge(context, challenge, &client).await
+            }
+        },
+        BotEvent::ChallengeCanceled(challenge) => {
+            if let Some(challenge_queue) = &state.challenge_queue {
+                challenge_queue.lock().unwrap().remove(&challenge.id);
+            }
+
+            bot.as_ref().on_challenge_cancelled(context, challenge, &client).await
+        },
+        BotEvent::ChallengeDeclined(challenge) =>
+            bot.as_ref().on_challenge_declined(context, challenge, &client).await
+    }
+}
+
+async fn run_with_event_stream<E>(bot: Arc<impl Bot + Send + 'static>,
+    event_stream: impl Stream<Item = Result<BotEvent, E>>, client: BotClient, bot_id: UserId,
+    state: Arc<RunnerState>)
+where
+    E: Debug + Send + 'static
+{
+    let archiving = archive_games(Arc::clone(&state), client.clone(), bot_id.clone());
+    let snapshots = save_snapshots(Arc::clone(&state));
+    let spectators = refresh_spectators(Arc::clone(&state), client.clone());
+    let challenges = sweep_challenge_queue(Arc::clone(&state), client.clone());
+    let context = Arc::new(BotContext {
+        bot_id,
+        profile: state.profile.clone()
+    });
+    let tournament = play_tournament(
+        Arc::clone(&bot), Arc::clone(&state), client.clone(), Arc::clone(&context));
+
+    let events = event_stream.map(move |record| {
+        let bot = Arc::clone(&bot);
+        let client = client.clone();
+        let context = Arc::clone(&context);
+        let state = Arc::clone(&state);
+
+        let game_id = match &record {
+            Ok(BotEvent::GameStart(game) | BotEvent::GameFinish(game)) => game.id.clone(),
+            _ => None
+        };
+        let join_handle = {
+            let bot = Arc::clone(&bot);
+            let client = client.clone();
+            let context = Arc::clone(&context);
+            let state = Arc::clone(&state);
+
+            task::spawn(async move {
+                process_bot_event(
+                    record.unwrap(), bot, client, context.as_ref(), state.as_ref()).await;
+            })
+        };
+
+        async move {
+            if let Err(error) = join_handle.await {
+                if let Some(crash_dumper) = &state.crash_dumper {
+                    report_panic(&error, game_id, crash_dumper, bot.as_ref(), &client,
+                        context.as_ref(), state.as_ref()).await;
+                }
+
+                std::panic::resume_unwind(error.into_panic());
+            }
+        }
+    }).for_each_concurrent(None, |handled| handled);
+
+    let background = future::join5(archiving, tournament, snapshots, spectators, challenges);
+
+    future::select(pin!(events), pin!(background)).await;
+}
+
+/// Writes a crash dump for the given failed event handler task and reports it to the bot.
+async fn report_panic(error: &task::JoinError, game_id: Option<GameId>, crash_dumper: &CrashDumper,
+        bot: &(impl Bot + Send), client: &BotClient, context: &BotContext, state: &RunnerState) {
+    let message = error.to_string();
+    let games = state.tracked_games.lock().unwrap().iter()
+        .map(|(game_id, tracked_game)| CrashDumpGame {
+            game_id: game_id.clone(),
+            moves: tracked_game.moves.clone(),
+            white_time: tracked_game.white_time,
+            black_time: tracked_game.black_time
+        })
+        .collect();
+
+    // TODO enable error handling
+    let dump = crash_dumper.dump(message.clone(), game_id.clone(), games).ok();
+    let error = RunnerError::HandlerPanicked {
+        message,
+        dump
+    };
+
+    bot.on_error(context, game_id, error, client).await;
+}
+
+/// Exports the finished games of the bot into the [PgnArchive] of the given state, if any, in the
+/// configured interval. Every export after the first includes the games created since the
+/// previous export started or, if earlier, since the oldest game that was running at that time
+/// was created, so games which finish later are not missed. Never completes.
+async fn archive_games(state: Arc<RunnerState>, client: BotClient, bot_id: UserId) {
+    let Some((archive, interval)) = &state.pgn_archive
+    else {
+        return future::pending().await;
+    };
+    let mut since = None;
+
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let now = now.as_millis() as Timestamp;
+        let oldest_running = state.tracked_games.lock().unwrap().values()
+            .map(|tracked_game| tracked_game.info.created_at)
+            .min();
+        let next_since = oldest_running.map_or(now, |created_at| created_at.min(now));
+
+        // TODO enable error handling
+        if let Ok(pgn) = client.export_user_games_pgn(&bot_id, since).await {
+            if archive.archive_pgn(&pgn).is_ok() {
+                since = Some(next_since);
+            }
+        }
+
+        tokio::time::sleep(*interval).await;
+    }
+}
+
+/// Saves a [RunnerSnapshot] of the given state to its [GameStore] in the configured interval, if
+/// state snapshots are enabled. Never completes.
+async fn save_snapshots(state: Arc<RunnerState>) {
+    let (Some(game_store), Some(interval)) = (&state.game_store, state.snapshot_interval)
+    else {
+        return future::pending().await;
+    };
+
+    loop {
+        let snapshot = state.session_log.snapshot(state.active_games.lock().unwrap().iter());
+
+        // TODO enable error handling
+        let _ = game_store.save_snapshot(&snapshot);
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Refreshes the games featured on Lichess TV in the [SpectatorTracker] of the given state, if
+/// any, in the configured interval while the bot plays any games. Never completes.
+async fn refresh_spectators(state: Arc<RunnerState>, client: BotClient) {
+    let Some(spectator_tracker) = &state.spectator_tracker
+    else {
+        return future::pending().await;
+    };
+
+    loop {
+        let playing = !state.active_games.lock().unwrap().is_empty();
+
+        if playing {
+            // TODO enable error handling
+            if let Ok(channels) = client.get_tv_channels().await {
+                spectator_tracker.tv_refreshed(&channels);
+            }
+        }
+
+        tokio::time::sleep(spectator_tracker.refresh_interval()).await;
+    }
+}
+
+/// Declines expired challenges in the challenge queue of the given state, if any, frees the slots
+/// of timed out accepts, and accepts queued challenges for which slots became free, in the
+/// configured sweep interval. Never completes.
+async fn sweep_challenge_queue(state: Arc<RunnerState>, client: BotClient) {
+    let Some(challenge_queue) = &state.challenge_queue
+    else {
+        return future::pending().await;
+    };
+    let sweep_interval = challenge_queue.lock().unwrap().sweep_interval();
+
+    loop {
+        tokio::time::sleep(sweep_interval).await;
+        accept_queued_challenges(&state, &client).await;
+    }
+}
+
+/// Keeps the seat of the bot in the tournament configured in the given state, if any, by joining
+/// it whenever the bot does not take part, until the configured withdrawal time, at which it
+/// withdraws, or until the tournament finishes. If pairing anticipation is enabled, new pairings
+/// of games which have not started yet are reported to [Bot::on_tournament_pairing]. Never
+/// completes.
+async fn play_tournament(bot: Arc<impl Bot + Send>, state: Arc<RunnerState>, client: BotClient,
+        context: Arc<BotContext>) {
+    let Some(tournament) = &state.tournament
+    else {
+        return future::pending().await;
+    };
+    let config = &tournament.config;
+
+    loop {
+        // TODO enable error handling
+        let current = client.get_tournament(&config.tournament_id).await.ok();
+        let pairing = tournament.new_pairing(current.as_ref(), &context.bot_id)
+            .filter(|pairing| !state.active_games.lock().unwrap().contains(&pairing.game_id));
+
+        if let Some(pairing) = pairing {
+            bot.on_tournament_pairing(&context, pairing, &client).await;
+        }
+
+        match tournament.next_action(current.as_ref(), SystemTime::now()) {
+            TournamentAction::Join => {
+                // TODO enable error handling
+                let _ = client.join_tournament(
+                    &config.tournament_id, config.password.as_deref()).await;
+            },
+            TournamentAction::Withdraw => {
+                // TODO enable error handling
+                let _ = client.withdraw_from_tournament(&config.tournament_id).await;
+                break;
+            },
+            TournamentAction::Stop => break,
+            TournamentAction::Wait => { }
+        }
+
+        tokio::time::sleep(tournament.wait_time(SystemTime::now())).await;
+    }
+
+    tournament.stop();
+    future::pending().await
+}
+
+/// Reports lines of the given event stream of the bot or, if `game_id` is given, game event stream
+/// of the game with that ID which cannot be parsed to [Bot::on_error] and removes them from the
+/// stream, if the given config skips such lines.
+fn skip_invalid_lines<T, E>(stream: impl Stream<Item = Result<T, StreamError<E>>>,
+    bot: Arc<impl Bot + Send + 'static>, client: BotClient, context: BotContext,
+    game_id: Option<GameId>, config: &ParsingConfig)
+    -> impl Stream<Item = Result<T, StreamError<E>>>
+{
+    let skip = config.skip_invalid_lines;
+
+    stream.filter_map(move |event| {
+        let bot = Arc::clone(&bot);
+        let client = client.clone();
+        let context = context.clone();
+        let game_id = game_id.clone();
+
+        async move {
+            match event {
+                Err(StreamError::Parse(error)) if skip => {
+                    bot.on_error(&context, game_id, RunnerError::Parse(error), &client).await;
+                    None
+                },
+                event => Some(event)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io;
+    use std::iter;
+    use std::ops::Deref;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use futures::stream;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use tokio::sync::Notify;
+
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::cache::ResponseCache;
+    use crate::chess::position::FenIssue;
+    use crate::client::BotClientBuilder;
+    use crate::model::{Seconds, TimeControl};
+    use crate::model::bot_event::GameStartFinish;
+    use crate::model::challenge::{
+        ChallengeColor,
+        ChallengeDeclined,
+        ChallengePerf,
+        ChallengeStatus
+    };
+    use crate::model::game::{GamePerf, GameStatus, Speed, Variant};
+    use crate::model::game::chat::{ChatLine, ChatRoom};
+    use crate::model::game::event::{
+        ChatLineEvent,
+        GameEventPlayer,
+        GameFullEvent,
+        GameStateEvent,
+        OpponentGoneEvent
+    };
+    use crate::model::game::result::GameOutcome;
+    use crate::model::tournament::TournamentPairing;
+    use crate::model::user::User;
+    use crate::runner::parsing::StreamParseError;
+    use crate::runner::spectators::Spectators;
+    use crate::stats::WinRates;
+    use crate::store::{StoreResult, UserLists};
+    use crate::store::tests as store_tests;
+    use crate::test_util;
+
+    use super::*;
+
+    type TrackedBotEvents = Arc<Mutex<Vec<BotEvent>>>;
+    type TrackedGameEvents = Arc<Mutex<Vec<(GameContext, GameEvent)>>>;
+
+    struct MockBot {
+        bot_events: TrackedBotEvents,
+        game_events: TrackedGameEvents
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for MockBot {
+        async fn on_game_start(&self, _: &BotContext, game: GameStartFinish, _: &BotClient) {
+            self.bot_events.lock().unwrap().push(BotEvent::GameStart(game));
+        }
+
+        async fn on_game_finish(&self, _: &BotContext, game: GameStartFinish, _: GameResult,
+                _: &BotClient) {
+            self.bot_events.lock().unwrap().push(BotEvent::GameFinish(game));
+        }
+
+        async fn on_challenge(&self, _: &BotContext, challenge: Challenge, _: &BotClient) {
+            self.bot_events.lock().unwrap().push(BotEvent::Challenge(challenge));
+        }
+
+        async fn on_challenge_cancelled(&self, _: &BotContext, challenge: Challenge,
+                _: &BotClient) {
+            self.bot_events.lock().unwrap().push(BotEvent::ChallengeCanceled(challenge));
+        }
+
+        async fn on_challenge_declined(&self, _: &BotContext, challenge: ChallengeDeclined,
+                _: &BotClient) {
+            self.bot_events.lock().unwrap().push(BotEvent::ChallengeDeclined(challenge));
+        }
+
+        async fn on_game_state(&self, context: &GameContext, state: GameStateEvent, _: &BotClient) {
+            self.game_events.lock().unwrap().push((context.clone(), GameEvent::GameState(state)))
+        }
+
+        async fn on_chat_line(&self, context: &GameContext, chat_line: ChatLineEvent,
+                _: &BotClient) {
+            self.game_events.lock().unwrap().push((context.clone(), GameEvent::ChatLine(chat_line)))
+        }
+
+        async fn on_opponent_gone(&self, context: &GameContext, opponent_gone: OpponentGoneEvent,
+                _: &BotClient) {
+            self.game_events.lock().unwrap()
+                .push((context.clone(), GameEvent::OpponentGone(opponent_gone)))
+        }
+    }
+
+    fn create_mock_bot() -> (MockBot, TrackedBotEvents, TrackedGameEvents) {
+        let bot_events = Arc::new(Mutex::new(Vec::new()));
+        let game_events = Arc::new(Mutex::new(Vec::new()));
+        let mock_bot = MockBot {
+            bot_events: Arc::clone(&bot_events),
+            game_events: Arc::clone(&game_events)
+        };
+
+        (mock_bot, bot_events, game_events)
+    }
+
+    fn test_state(challenge_queue: Option<ChallengeQueueConfig>) -> Arc<RunnerState> {
+        Arc::new(RunnerState::new(challenge_queue))
+    }
+
+    fn test_game_event_info(id: &str) -> GameStartFinish {
+        GameStartFinish {
+            id: Some(id.to_owned()),
+            source: None,
+            status: None,
+            winner: None,
+            color: None,
+            rated: None,
+            speed: None,
+            variant: None,
+            compat: None
+        }
+    }
+
+    fn test_challenge(id: &str) -> Challenge {
+        Challenge {
+            id: id.to_owned(),
+            url: "testUrl".to_owned(),
+            status: ChallengeStatus::Created,
+            challenger: User {
+                rating: None,
+                provisional: false,
+                online: false,
+                id: "testUserId".to_owned(),
+                name: "testUserName".to_owned(),
+                title: None,
+                patron: false,
+            },
+            dest_user: None,
+            variant: None,
+            rated: false,
+            speed: Speed::UltraBullet,
+            time_control: TimeControl::Unlimited,
+            color: ChallengeColor::White,
+            perf: ChallengePerf {
+                icon: None,
+                name: None
+            },
+            direction: None,
+            initial_fen: None,
+            rematch_of: None,
+            decline: None,
+        }
+    }
+
+    #[rstest]
+    #[case::empty(vec![])]
+    #[case::on_game_start(vec![
+        BotEvent::GameStart(test_game_event_info("testGameStartId"))
+    ])]
+    #[case::on_game_finish(vec![
+        BotEvent::GameFinish(test_game_event_info("testGameFinishId"))
+    ])]
+    #[case::challenge(vec![
+        BotEvent::Challenge(test_challenge("testChallengeId"))
+    ])]
+    #[case::challenge_canceled(vec![
+        BotEvent::ChallengeCanceled(test_challenge("testChallengeCanceledId"))
+    ])]
+    #[case::challenge_declined(vec![
+        BotEvent::ChallengeDeclined(ChallengeDeclined {
+            id: "testChallengeDeclined".to_owned()
+        })
+    ])]
+    #[case::multiple_events(vec![
+        BotEvent::GameStart(test_game_event_info("firstEventId")),
+        BotEvent::Challenge(test_challenge("secondEventId")),
+        BotEvent::GameStart(test_game_event_info("thirdEventId"))
+    ])]
+    fn correct_events_are_called_on_bot(#[case] events: Vec<BotEvent>) {
+        let (bot, tracked_events, _) = create_mock_bot();
+        let event_results = events.iter()
+            .cloned()
+            .map(Ok)
+            .collect::<Vec<Result<_, &str>>>();
+        let stream = stream::iter(event_results);
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+
+        tokio_test::block_on(run_with_event_stream(
+            Arc::new(bot), stream, mock_client, "testId".to_owned(), test_state(None)));
+
+        let tracked_events = tracked_events.lock().unwrap();
+
+        assert_that!(tracked_events.deref()).contains_exactly_in_given_order(events);
+    }
+
+    async fn mount_challenge_response(server: &MockServer, challenge_id: &str, action: &str,
+            expected_calls: u64) {
+        Mock::given(method("POST"))
+            .and(path(format!("/challenge/{challenge_id}/{action}")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(expected_calls)
+            .mount(server)
+            .await;
+    }
+
+    #[rstest]
+    #[case::enqueue(ChallengeDecision::Enqueue { priority: 0 }, 1, 0)]
+    #[case::decline(ChallengeDecision::Decline(Some(DeclineReason::TooFast)), 0, 1)]
+    #[case::ignore(ChallengeDecision::Ignore, 0, 0)]
+    fn challenge_queue_applies_policy_decision(
+            #[case] decision: ChallengeDecision,
+            #[case] expected_accepts: u64,
+            #[case] expected_declines: u64) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, tracked_events, _) = create_mock_bot();
+            mount_challenge_response(&server, "testChallengeId", "accept", expected_accepts).await;
+            mount_challenge_response(&server, "testChallengeId", "decline", expected_declines)
+                .await;
+            let config = ChallengeQueueConfig::new(move |_: &Challenge| decision);
+            let challenge = test_challenge("testChallengeId");
+            let stream = stream::iter([Ok::<_, &str>(BotEvent::Challenge(challenge.clone()))]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), test_state(Some(config))).await;
+
+            let tracked_events = tracked_events.lock().unwrap();
+
+            assert_that!(tracked_events.deref())
+                .contains_exactly_in_given_order([BotEvent::Challenge(challenge)]);
+        });
+    }
+
+    fn test_challenge_from(id: &str, challenger_id: &str) -> Challenge {
+        let mut challenge = test_challenge(id);
+        challenge.challenger.id = challenger_id.to_owned();
+        challenge
+    }
+
+    #[test]
+    fn challenges_of_blocked_users_are_declined() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, tracked_events, _) = create_mock_bot();
+            mount_challenge_response(&server, "blockedChallengeId", "decline", 1).await;
+            mount_challenge_response(&server, "allowedChallengeId", "decline", 0).await;
+            let lists = Arc::new(ChallengerLists::default());
+            lists.block_user("blockedUserId").unwrap();
+            let state = RunnerState::new(None).with_challenger_lists(lists);
+            let allowed_challenge = test_challenge_from("allowedChallengeId", "allowedUserId");
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(
+                    test_challenge_from("blockedChallengeId", "blockedUserId"))),
+                Ok(BotEvent::Challenge(allowed_challenge.clone()))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), Arc::new(state)).await;
+
+            let tracked_events = tracked_events.lock().unwrap();
+
+            assert_that!(tracked_events.deref())
+                .contains_exactly_in_given_order([BotEvent::Challenge(allowed_challenge)]);
+        });
+    }
+
+    #[test]
+    fn rematches_of_last_game_are_accepted_and_others_passed_to_bot() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, tracked_events, _) = create_mock_bot();
+            mount_challenge_response(&server, "rematchChallengeId", "accept", 1).await;
+            mount_challenge_response(&server, "otherChallengeId", "accept", 0).await;
+            let state = RunnerState::new(None).with_rematches(RematchConfig::new(2));
+            state.rematch_tracker.as_ref().unwrap()
+                .game_finished(&"rematcherId".to_owned(), &"lastGameId".to_owned(), Instant::now());
+            let rematch = Challenge {
+                rematch_of: Some("lastGameId".to_owned()),
+                ..test_challenge_from("rematchChallengeId", "rematcherId")
+            };
+            let other_rematch = Challenge {
+                rematch_of: Some("olderGameId".to_owned()),
+                ..test_challenge_from("otherChallengeId", "rematcherId")
+            };
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(rematch)),
+                Ok(BotEvent::Challenge(other_rematch.clone()))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), Arc::new(state)).await;
+
+            let tracked_events = tracked_events.lock().unwrap();
+
+            assert_that!(tracked_events.deref())
+                .contains_exactly_in_given_order([BotEvent::Challenge(other_rematch)]);
+        });
+    }
+
+    #[rstest]
+    #[case::not_allowed(false, 1)]
+    #[case::allowed(true, 0)]
+    fn spam_protection_declines_challenges_exceeding_rate(#[case] allowed: bool,
+            #[case] expected_declines: u64) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            mount_challenge_response(&server, "testChallengeId", "decline", expected_declines)
+                .await;
+            let lists = Arc::new(ChallengerLists::default());
+
+            if allowed {
+                lists.allow_user("testUserId").unwrap();
+            }
+
+            let state = RunnerState::new(None)
+                .with_challenger_lists(lists)
+                .with_spam_protection(SpamProtectionConfig::new(1, Duration::from_secs(60)));
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(test_challenge("testChallengeId"))),
+                Ok(BotEvent::Challenge(test_challenge("testChallengeId")))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), Arc::new(state)).await;
+        });
+    }
+
+    #[rstest]
+    #[case::inactive(
+        ChallengeSchedule::new().with_maintenance_window("00:00-00:00".parse().unwrap()), 1, 0)]
+    #[case::active(
+        ChallengeSchedule::new().with_active_hours("00:00-00:00".parse().unwrap()), 0, 1)]
+    fn challenges_are_declined_outside_schedule(#[case] schedule: ChallengeSchedule,
+            #[case] expected_declines: u64, #[case] expected_handled: usize) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, tracked_events, _) = create_mock_bot();
+            mount_challenge_response(&server, "testChallengeId", "decline", expected_declines)
+                .await;
+            let state = RunnerState::new(None).with_schedule(schedule);
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(
+                    test_challenge_from("testChallengeId", "testUserId")))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), Arc::new(state)).await;
+
+            assert_that!(tracked_events.lock().unwrap().len()).is_equal_to(expected_handled);
+        });
+    }
+
+    #[test]
+    fn challenges_are_declined_while_playing_tournament() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, tracked_events, _) = create_mock_bot();
+            mount_challenge_response(&server, "testChallengeId", "decline", 1).await;
+            let state = RunnerState::new(None)
+                .with_tournament(TournamentConfig::new("testTournamentId"));
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(
+                    test_challenge_from("testChallengeId", "testUserId")))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), Arc::new(state)).await;
+
+            assert_that!(tracked_events.lock().unwrap().deref()).is_empty();
+        });
+    }
+
+    async fn mount_tournament_response(server: &MockServer, json: &str, times: u64) {
+        Mock::given(method("GET"))
+            .and(path("/tournament/testTournamentId"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(json))
+            .up_to_n_times(times)
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_tournament_action(server: &MockServer, action: &str, expected_calls: u64) {
+        Mock::given(method("POST"))
+            .and(path(format!("/tournament/testTournamentId/{action}")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(expected_calls)
+            .mount(server)
+            .await;
+    }
+
+    fn test_bot_context() -> Arc<BotContext> {
+        Arc::new(BotContext {
+            bot_id: "testId".to_owned(),
+            profile: ByAddress::default()
+        })
+    }
+
+    #[derive(Default)]
+    struct PairingBot {
+        pairings: Mutex<Vec<TournamentPairing>>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for PairingBot {
+        async fn on_tournament_pairing(&self, _: &BotContext, pairing: TournamentPairing,
+                _: &BotClient) {
+            self.pairings.lock().unwrap().push(pairing);
+        }
+    }
+
+    #[test]
+    fn new_tournament_pairing_is_reported_once() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            mount_tournament_response(&server, concat!(r#"{"id":"testTournamentId","#,
+                r#""fullName":"Test Arena","isStarted":true,"clock":{"limit":60,"increment":0},"#,
+                r#""variant":"standard","me":{"rank":1,"gameId":"testGameId"},"#,
+                r#""duels":[{"id":"testGameId","p":[{"n":"Opponent","r":1500},"#,
+                r#"{"n":"TestId","r":1600}]}]}"#), 3).await;
+            let state = Arc::new(RunnerState::new(None).with_tournament(
+                TournamentConfig::new("testTournamentId")
+                    .with_pairing_anticipation(Duration::from_millis(10))));
+            let bot = Arc::new(PairingBot::default());
+
+            let playing =
+                play_tournament(Arc::clone(&bot), state, client, test_bot_context());
+            let _ = tokio::time::timeout(Duration::from_millis(100), playing).await;
+
+            let pairings = bot.pairings.lock().unwrap();
+
+            assert_that!(pairings.len()).is_equal_to(1);
+            assert_that!(pairings[0].game_id.as_str()).is_equal_to("testGameId");
+            assert_that!(pairings[0].color).contains(Color::Black);
+            assert_that!(pairings[0].speed).contains(Speed::Bullet);
+            assert_that!(pairings[0].opponent.as_ref().map(|opponent| opponent.name.as_str()))
+                .contains("Opponent");
+        });
+    }
+
+    #[test]
+    fn tournament_is_joined_again_until_it_finishes() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            mount_tournament_response(&server,
+                r#"{"id":"testTournamentId","fullName":"Test Arena","isStarted":true}"#, 1).await;
+            mount_tournament_response(&server, concat!(r#"{"id":"testTournamentId","#,
+                r#""fullName":"Test Arena","isStarted":true,"me":{"rank":1,"withdraw":true}}"#),
+                1).await;
+            mount_tournament_response(&server,
+                r#"{"id":"testTournamentId","fullName":"Test Arena","isFinished":true}"#, 1).await;
+            mount_tournament_action(&server, "join", 2).await;
+            mount_tournament_action(&server, "withdraw", 0).await;
+            let state = Arc::new(RunnerState::new(None).with_tournament(
+                TournamentConfig::new("testTournamentId")
+                    .with_check_interval(Duration::from_millis(10))));
+            let tournament = Arc::clone(state.tournament.as_ref().unwrap());
+
+            let playing = play_tournament(
+                Arc::new(create_mock_bot().0), state, client, test_bot_context());
+            let _ = tokio::time::timeout(Duration::from_secs(1), playing).await;
+
+            assert_that!(tournament.is_playing()).is_false();
+        });
+    }
+
+    #[test]
+    fn runner_withdraws_from_tournament_at_configured_time() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            mount_tournament_response(&server, concat!(r#"{"id":"testTournamentId","#,
+                r#""fullName":"Test Arena","isStarted":true,"me":{"rank":1}}"#), 10).await;
+            mount_tournament_action(&server, "join", 0).await;
+            mount_tournament_action(&server, "withdraw", 1).await;
+            let withdraw_at = SystemTime::now() + Duration::from_millis(50);
+            let state = Arc::new(RunnerState::new(None).with_tournament(
+                TournamentConfig::new("testTournamentId").with_withdraw_at(withdraw_at)));
+            let tournament = Arc::clone(state.tournament.as_ref().unwrap());
+
+            let playing = play_tournament(
+                Arc::new(create_mock_bot().0), state, client, test_bot_context());
+            let _ = tokio::time::timeout(Duration::from_secs(1), playing).await;
+
+            assert_that!(tournament.is_playing()).is_false();
+        });
+    }
+
+    #[test]
+    fn challenge_queue_does_not_exceed_concurrent_game_limit() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            mount_challenge_response(&server, "firstChallengeId", "accept", 1).await;
+            mount_challenge_response(&server, "secondChallengeId", "accept", 0).await;
+            let config = ChallengeQueueConfig::new(
+                |_: &Challenge| ChallengeDecision::Enqueue { priority: 0 })
+                .with_max_concurrent_games(1);
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(test_challenge("firstChallengeId"))),
+                Ok(BotEvent::Challenge(test_challenge("secondChallengeId")))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), test_state(Some(config))).await;
+        });
+    }
+
+    #[test]
+    fn challenge_queue_accepts_next_challenge_after_game_finish() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            mount_challenge_response(&server, "testChallengeId", "accept", 1).await;
+            let config = ChallengeQueueConfig::new(
+                |_: &Challenge| ChallengeDecision::Enqueue { priority: 0 })
+                .with_max_concurrent_games(1);
+            let state = test_state(Some(config));
+            state.game_started(&"runningGameId".to_owned());
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(test_challenge("testChallengeId"))),
+                Ok(BotEvent::GameFinish(test_game_event_info("runningGameId")))
+            ]);
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testId".to_owned(), state).await;
+        });
+    }
+
+    #[test]
+    fn challenge_queue_accepts_next_challenge_before_rating_diff_is_fetched() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            mount_challenge_response(&server, "testChallengeId", "accept", 1).await;
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(500))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let config = ChallengeQueueConfig::new(
+                |_: &Challenge| ChallengeDecision::Enqueue { priority: 0 })
+                .with_max_concurrent_games(1);
+            let state = test_state(Some(config));
+            state.game_started(&"runningGameId".to_owned());
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(test_challenge("testChallengeId"))),
+                Ok(BotEvent::GameFinish(GameStartFinish {
+                    color: Some(Color::White),
+                    rated: Some(true),
+                    ..test_game_event_info("runningGameId")
+                }))
+            ]);
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testId".to_owned(), state).await;
+
+            let paths = server.received_requests().await.unwrap().into_iter()
+                .map(|request| request.url.path().to_owned())
+                .collect::<Vec<_>>();
+
+            assert_that!(paths).contains_exactly_in_given_order([
+                "/challenge/testChallengeId/accept".to_owned(),
+                "/games/export/_ids".to_owned()
+            ]);
+        });
+    }
+
+    fn delayed_end_of_stream() -> impl Stream<Item = Result<BotEvent, &'static str>> {
+        stream::once(async {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+
+            Err("end of test")
+        }).filter(|_| future::ready(false))
+    }
+
+    #[test]
+    fn challenge_queue_declines_expired_challenge_without_further_events() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            mount_challenge_response(&server, "testChallengeId", "decline", 1).await;
+            let config = ChallengeQueueConfig::new(
+                |_: &Challenge| ChallengeDecision::Enqueue { priority: 0 })
+                .with_max_concurrent_games(1)
+                .with_expiry(Duration::from_millis(50))
+                .with_sweep_interval(Duration::from_millis(10));
+            let state = test_state(Some(config));
+            state.game_started(&"runningGameId".to_owned());
+            let challenge = stream::once(async {
+                Ok(BotEvent::Challenge(test_challenge("testChallengeId")))
+            });
+
+            run_with_event_stream(Arc::new(bot), challenge.chain(delayed_end_of_stream()), client,
+                "testId".to_owned(), state).await;
+        });
+    }
+
+    #[test]
+    fn challenge_queue_accepts_next_challenge_if_accepted_game_does_not_start() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            mount_challenge_response(&server, "firstChallengeId", "accept", 1).await;
+            mount_challenge_response(&server, "secondChallengeId", "accept", 1).await;
+            let config = ChallengeQueueConfig::new(
+                |_: &Challenge| ChallengeDecision::Enqueue { priority: 0 })
+                .with_max_concurrent_games(1)
+                .with_accept_timeout(Duration::from_millis(50))
+                .with_sweep_interval(Duration::from_millis(10));
+            let challenges = stream::iter([
+                Ok(BotEvent::Challenge(test_challenge("firstChallengeId"))),
+                Ok(BotEvent::Challenge(test_challenge("secondChallengeId")))
+            ]);
+
+            run_with_event_stream(Arc::new(bot), challenges.chain(delayed_end_of_stream()),
+                client, "testId".to_owned(), test_state(Some(config))).await;
+        });
+    }
+
+    #[test]
+    fn challenge_queue_accepts_next_challenge_if_accepted_challenge_is_gone() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            Mock::given(method("POST"))
+                .and(path("/challenge/goneChallengeId/accept"))
+                .respond_with(ResponseTemplate::new(404)
+                    .set_body_string(r#"{"error":"Not found"}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+            mount_challenge_response(&server, "testChallengeId", "accept", 1).await;
+            let config = ChallengeQueueConfig::new(
+                |_: &Challenge| ChallengeDecision::Enqueue { priority: 0 })
+                .with_max_concurrent_games(1);
+            let state = test_state(Some(config));
+            state.game_started(&"runningGameId".to_owned());
+            let challenges = stream::iter([
+                Ok::<_, &str>(BotEvent::Challenge(test_challenge("goneChallengeId"))),
+                Ok(BotEvent::Challenge(test_challenge("testChallengeId")))
+            ]);
+            let game_finish = stream::once(async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+
+                Ok(BotEvent::GameFinish(test_game_event_info("runningGameId")))
+            });
+
+            run_with_event_stream(Arc::new(bot), challenges.chain(game_finish), client,
+                "testId".to_owned(), state).await;
+        });
+    }
+
+    struct ResultTrackingBot {
+        results: Arc<Mutex<Vec<GameResult>>>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for ResultTrackingBot {
+        async fn on_game_finish(&self, _: &BotContext, _: GameStartFinish, result: GameResult,
+                _: &BotClient) {
+            self.results.lock().unwrap().push(result);
+        }
+    }
+
+    #[rstest]
+    #[case::rated(Some(true), 1, Some(12))]
+    #[case::casual(Some(false), 0, None)]
+    fn game_finish_provides_game_result(#[case] rated: Option<bool>,
+            #[case] expected_exports: u64, #[case] expected_rating_diff: Option<Rating>) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let results = Arc::new(Mutex::new(Vec::new()));
+            let bot = ResultTrackingBot {
+                results: Arc::clone(&results)
+            };
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string("{\
+                        \"id\": \"testGameId\",\
+                        \"rated\": true,\
+                        \"speed\": \"blitz\",\
+                        \"createdAt\": 1234,\
+                        \"status\": \"mate\",\
+                        \"players\": {\
+                            \"white\": { \"rating\": 1500, \"ratingDiff\": -12 },\
+                            \"black\": { \"rating\": 1500, \"ratingDiff\": 12 }\
+                        },\
+                        \"winner\": \"black\"\
+                    }\n"))
+                .expect(expected_exports)
+                .mount(&server)
+                .await;
+
+            let state = test_state(None);
+            let game_id = "testGameId".to_owned();
+            let mut game_state = game_state_event("");
+            game_state.white_time = 1000;
+            game_state.black_time = 2000;
+            state.game_started(&game_id);
+            let info = store_tests::test_game_info(&game_id, "white", "testId");
+            state.track_game(&info, &game_state);
+            let stream = stream::once(async move {
+                Ok::<_, &str>(BotEvent::GameFinish(GameStartFinish {
+                    id: Some("testGameId".to_owned()),
+                    source: None,
+                    status: Some(GameStatus::Mate),
+                    winner: Some(Color::Black),
+                    color: Some(Color::Black),
+                    rated,
+                    speed: None,
+                    variant: None,
+                    compat: None
+                }))
+            });
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testId".to_owned(), state)
+                .await;
+
+            let results = results.lock().unwrap();
+            let expected_result = GameResult {
+                game_id: Some(game_id),
+                outcome: GameOutcome::Win,
+                status: Some(GameStatus::Mate),
+                bot_color: Some(Color::Black),
+                white_time: Some(1000),
+                black_time: Some(2000),
+                rating_diff: expected_rating_diff
+            };
+
+            assert_that!(results.deref()).contains_exactly_in_given_order([expected_result]);
+        });
+    }
+
+    struct RatingTrackingBot {
+        updates: Arc<Mutex<Vec<RatingUpdate>>>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for RatingTrackingBot {
+        async fn on_rating_update(&self, _: &BotContext, _: GameStartFinish,
+                update: RatingUpdate, _: &BotClient) {
+            self.updates.lock().unwrap().push(update);
+        }
+    }
+
+    #[rstest]
+    #[case::tracked_game(true, 1600)]
+    #[case::untracked_game(false, 1603)]
+    fn rated_game_finish_reports_rating_update(#[case] tracked: bool,
+            #[case] expected_old: Rating) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let updates = Arc::new(Mutex::new(Vec::new()));
+            let bot = RatingTrackingBot {
+                updates: Arc::clone(&updates)
+            };
+            let rating_history = Arc::new(RatingHistory::new());
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string("{\
+                        \"id\": \"testGameId\",\
+                        \"rated\": true,\
+                        \"speed\": \"blitz\",\
+                        \"createdAt\": 1234,\
+                        \"status\": \"mate\",\
+                        \"players\": {\
+                            \"white\": { \"rating\": 1500, \"ratingDiff\": -12 },\
+                            \"black\": { \"rating\": 1600, \"ratingDiff\": 12 }\
+                        },\
+                        \"winner\": \"black\"\
+                    }\n"))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/account"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "id": "testId",
+                    "username": "testId",
+                    "perfs": {
+                        "blitz": { "games": 10, "rating": 1615, "rd": 60, "prog": 15 }
+                    },
+                    "createdAt": 123,
+                    "seenAt": 321,
+                    "playTime": { "total": 12345, "tv": 0 },
+                    "url": "testUrl",
+                    "count": {
+                        "all": 10,
+                        "rated": 10,
+                        "ai": 0,
+                        "draw": 0,
+                        "drawH": 0,
+                        "loss": 0,
+                        "lossH": 0,
+                        "win": 10,
+                        "winH": 10,
+                        "bookmark": 0,
+                        "playing": 0,
+                        "import": 0,
+                        "me": 0
+                    }
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let state = Arc::new(
+                RunnerState::new(None).with_rating_history(Arc::clone(&rating_history)));
+            let game_id = "testGameId".to_owned();
+            state.game_started(&game_id);
+
+            if tracked {
+                let info = store_tests::test_game_info(&game_id, "white", "testId");
+                state.track_game(&info, &game_state_event(""));
+            }
+
+            let stream = stream::once(async move {
+                Ok::<_, &str>(BotEvent::GameFinish(GameStartFinish {
+                    id: Some("testGameId".to_owned()),
+                    source: None,
+                    status: Some(GameStatus::Mate),
+                    winner: Some(Color::Black),
+                    color: Some(Color::Black),
+                    rated: Some(true),
+                    speed: Some(Speed::Blitz),
+                    variant: Some(Variant::Standard),
+                    compat: None
+                }))
+            });
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testId".to_owned(), state)
+                .await;
+
+            let expected_update = RatingUpdate {
+                game_id: Some(game_id),
+                perf: PerfKey::Blitz,
+                old: expected_old,
+                new: 1615
+            };
+
+            assert_that!(updates.lock().unwrap().clone())
+                .contains_exactly_in_given_order([expected_update.clone()]);
+            assert_that!(rating_history.updates())
+                .contains_exactly_in_given_order([expected_update]);
+        });
+    }
+
+    #[test]
+    fn rated_game_finish_without_rating_history_does_not_fetch_profile() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let updates = Arc::new(Mutex::new(Vec::new()));
+            let bot = RatingTrackingBot {
+                updates: Arc::clone(&updates)
+            };
+
+            Mock::given(method("POST"))
+                .and(path("/games/export/_ids"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/account"))
+                .respond_with(ResponseTemplate::new(500))
+                .expect(0)
+                .mount(&server)
+                .await;
+
+            let state = Arc::new(RunnerState::new(None));
+            let stream = stream::once(async {
+                Ok::<_, &str>(BotEvent::GameFinish(GameStartFinish {
+                    color: Some(Color::Black),
+                    rated: Some(true),
+                    speed: Some(Speed::Blitz),
+                    variant: Some(Variant::Standard),
+                    ..test_game_event_info("testGameId")
+                }))
+            });
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testId".to_owned(), state)
+                .await;
+
+            assert_that!(updates.lock().unwrap().deref()).is_empty();
+        });
+    }
+
+    #[test]
+    fn finished_games_are_exported_into_pgn_archive_periodically() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let directory = std::env::temp_dir()
+                .join(format!("libot-runner-pgn-archive-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&directory);
+            let archive = Arc::new(PgnArchive::open(&directory, 100).unwrap());
+
+            Mock::given(method("GET"))
+                .and(path("/games/user/testId"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "[Site \"https://lichess.org/game1\"]\n[Result \"1-0\"]\n\n1. e4 1-0\n\n\
+                    [Site \"https://lichess.org/game2\"]\n[Result \"0-1\"]\n\n1. d4 0-1\n"))
+                .expect(2..)
+                .mount(&server)
+                .await;
+
+            let state = Arc::new(RunnerState::new(None)
+                .with_pgn_archive(Arc::clone(&archive), Duration::from_millis(20)));
+            let stream = stream::once(async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+
+                Err::<BotEvent, _>("end of test")
+            }).filter(|_| future::ready(false));
+
+            run_with_event_stream(Arc::new(create_mock_bot().0), stream, client,
+                "testId".to_owned(), state).await;
+
+            assert_that!(archive.len()).is_equal_to(2);
+            assert_that!(archive.contains("game1")).is_true();
+
+            std::fs::remove_dir_all(directory).unwrap();
+        });
+    }
+
+    #[derive(Default)]
+    struct MemoryGameStore(Mutex<Vec<GameRecord>>);
+
+    impl GameStore for MemoryGameStore {
+        fn record_game(&self, record: &GameRecord) -> StoreResult<()> {
+            self.0.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+
+        fn games(&self) -> StoreResult<Vec<GameRecord>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    #[derive(Default)]
+    struct SnapshotStore(Mutex<Option<RunnerSnapshot>>);
+
+    impl GameStore for SnapshotStore {
+        fn record_game(&self, _record: &GameRecord) -> StoreResult<()> {
+            Ok(())
+        }
+
+        fn games(&self) -> StoreResult<Vec<GameRecord>> {
+            Ok(Vec::new())
+        }
+
+        fn snapshot(&self) -> StoreResult<Option<RunnerSnapshot>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn save_snapshot(&self, snapshot: &RunnerSnapshot) -> StoreResult<()> {
+            *self.0.lock().unwrap() = Some(snapshot.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn state_snapshot_is_saved_periodically() {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let game_store = Arc::new(SnapshotStore::default());
+            let state = Arc::new(RunnerState::new(None)
+                .with_game_store(game_store.clone())
+                .with_state_snapshots(Duration::from_millis(20)));
+            state.game_started(&"game1".to_owned());
+            state.game_started(&"game2".to_owned());
+            state.session_log.chat_sent(&"game1".to_owned());
+            state.game_finished(&"game2".to_owned());
+            let stream = stream::once(async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+
+                Err::<BotEvent, _>("end of test")
+            }).filter(|_| future::ready(false));
+
+            run_with_event_stream(Arc::new(create_mock_bot().0), stream, client,
+                "testId".to_owned(), state).await;
+
+            assert_that!(game_store.snapshot().unwrap()).contains(RunnerSnapshot {
+                active_games: ["game1".to_owned()].into(),
+                accepted_challenges: Default::default(),
+                chatted_games: ["game1".to_owned()].into()
+            });
+        });
+    }
+
+    #[derive(Default)]
+    struct SpectatorBot(Mutex<Vec<Option<Spectators>>>);
+
+    #[async_trait::async_trait]
+    impl Bot for SpectatorBot {
+        async fn on_chat_line(&self, context: &GameContext, _: ChatLineEvent, _: &BotClient) {
+            self.0.lock().unwrap().push(context.spectators());
+        }
+    }
+
+    #[test]
+    fn spectators_of_running_game_are_tracked() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let bot = Arc::new(SpectatorBot::default());
+
+            Mock::given(method("GET"))
+                .and(path("/tv/channels"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                    r#"{"bot":{"user":{"id":"testbot","name":"TestBot"},"#,
+                    r#""gameId":"testGameId","color":"white"}}"#)))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testGameId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_delay(Duration::from_millis(100))
+                    .set_body_string(concat!(
+                        r#"{"type":"gameFull","id":"testGameId","variant":{},"speed":"blitz","#,
+                        r#""perf":{},"rated":false,"createdAt":1234,"white":{"id":"testbot"},"#,
+                        r#""black":{},"initialFen":"startpos","state":{"type":"gameState","#,
+                        r#""moves":"","wtime":60000,"btime":60000,"winc":0,"binc":0,"#,
+                        r#""status":"started"}}"#, "\n",
+                        r#"{"type":"chatLine","room":"spectator","username":"fan","text":"hi"}"#,
+                        "\n")))
+                .mount(&server)
+                .await;
+
+            let state = Arc::new(RunnerState::new(None)
+                .with_spectator_tracking(Duration::from_millis(10)));
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testGameId")))
+            ]);
+
+            run_with_event_stream(Arc::clone(&bot), stream, client, "testbot".to_owned(), state)
+                .await;
+
+            assert_that!(bot.0.lock().unwrap().clone()).contains_exactly_in_given_order([
+                Some(Spectators {
+                    tv_channels: vec!["bot".to_owned()],
+                    spectator_chatters: 1
+                })
+            ]);
+        });
+    }
+
+    #[test]
+    fn cloud_evals_are_prefetched_in_opponent_turn() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            let cache = Arc::new(ResponseCache::new(Duration::from_secs(60)));
+            let prefetcher = CloudEvalPrefetcher::new(Arc::clone(&cache))
+                .with_request_spacing(Duration::from_millis(1));
+
+            Mock::given(method("GET"))
+                .and(path("/cloud-eval"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testGameId"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                    r#"{"type":"gameFull","id":"testGameId","variant":{},"speed":"blitz","#,
+                    r#""perf":{},"rated":false,"createdAt":1234,"white":{"id":"testbot"},"#,
+                    r#""black":{},"initialFen":"startpos","state":{"type":"gameState","#,
+                    r#""moves":"e2e4","wtime":180000,"btime":180000,"winc":0,"binc":0,"#,
+                    r#""status":"started"}}"#, "\n")))
+                .mount(&server)
+                .await;
+
+            let state = Arc::new(RunnerState::new(None)
+                .with_cloud_eval_prefetcher(Arc::new(prefetcher)));
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testGameId")))
+            ]);
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testbot".to_owned(), state)
+                .await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            assert_that!(cache.get("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"))
+                .contains(None);
+        });
+    }
+
+    #[test]
+    fn finished_game_is_recorded_in_game_store() {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            let game_store = Arc::new(MemoryGameStore::default());
+            let state = Arc::new(RunnerState::new(None).with_game_store(game_store.clone()));
+            let game_id = "testGameId".to_owned();
+            let info = store_tests::test_game_info(&game_id, "testId", "opponent");
+            state.game_started(&game_id);
+            state.track_game(&info, &game_state_event(""));
+            state.update_tracked_game(&game_id, &game_state_event("e2e4 e7e5"));
+            let stream = stream::once(async {
+                Ok::<_, &str>(BotEvent::GameFinish(GameStartFinish {
+                    id: Some("testGameId".to_owned()),
+                    source: None,
+                    status: Some(GameStatus::Resign),
+                    winner: Some(Color::White),
+                    color: Some(Color::White),
+                    rated: None,
+                    speed: None,
+                    variant: None,
+                    compat: None
+                }))
+            });
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testId".to_owned(), state)
+                .await;
+
+            let games = game_store.games().unwrap();
+
+            assert_that!(games.len()).is_equal_to(1);
+            assert_that!(games[0].game_id.as_str()).is_equal_to("testGameId");
+            assert_that!(games[0].outcome).is_equal_to(GameOutcome::Win);
+            assert_that!(games[0].moves.as_str()).is_equal_to("e2e4 e7e5");
+            assert_that!(games[0].opponent.as_ref().and_then(|opponent| opponent.id.clone()))
+                .contains("opponent".to_owned());
+        });
+    }
+
+    #[test]
+    fn finished_game_is_recorded_in_performance_stats() {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            let performance_stats = Arc::new(PerformanceStats::new());
+            let state = Arc::new(RunnerState::new(None)
+                .with_performance_stats(Arc::clone(&performance_stats)));
+            let game_id = "testGameId".to_owned();
+            let info = store_tests::test_game_info(&game_id, "opponent", "testId");
+            state.game_started(&game_id);
+            state.track_game(&info, &game_state_event(""));
+            let stream = stream::once(async {
+                Ok::<_, &str>(BotEvent::GameFinish(GameStartFinish {
+                    id: Some("testGameId".to_owned()),
+                    source: None,
+                    status: Some(GameStatus::Draw),
+                    winner: None,
+                    color: Some(Color::Black),
+                    rated: None,
+                    speed: None,
+                    variant: None,
+                    compat: None
+                }))
+            });
+
+            run_with_event_stream(Arc::new(bot), stream, client, "testId".to_owned(), state)
+                .await;
+
+            let bucket = performance_stats.bucket(Speed::Blitz, Variant::Standard, Some(1500));
+
+            assert_that!(performance_stats.win_rates(&bucket)).contains(WinRates {
+                wins: 0,
+                draws: 1,
+                losses: 0
+            });
+        });
+    }
+
+    #[test]
+    fn game_start_event_with_game_id_causes_query_of_game_event_stream() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, tracked_events) = create_mock_bot();
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string("{\
+                        \"type\": \"gameFull\",\
+                        \"id\": \"testId\",\
+                        \"variant\": { },\
+                        \"clock\": null,\
+                        \"speed\": \"blitz\",\
+                        \"perf\": { },\
+                        \"rated\": false,\
+                        \"createdAt\": 1234,\
+                        \"white\": { },\
+                        \"black\": { },\
+                        \"initialFen\": \"testInitialFen\",\
+                        \"state\": {\
+                            \"type\": \"gameState\",\
+                            \"moves\": \"\",\
+                            \"wtime\": 120000,\
+                            \"btime\": 120000,\
+                            \"winc\": 0,\
+                            \"binc\": 0,\
+                            \"status\": \"created\"\
+                        }\
+                    }\n"))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let stream = stream::once(async {
+                Ok::<_, &str>(BotEvent::GameStart(GameStartFinish {
+                    id: Some("testId".to_owned()),
+                    source: None,
+                    status: None,
+                    winner: None,
+                    color: None,
+                    rated: None,
+                    speed: None,
+                    variant: None,
+                    compat: None
+                }))
+            });
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), test_state(None)).await;
+
+            let tracked_events = tracked_events.lock().unwrap();
+            let expected_event = GameStateEvent {
+                moves: "".to_string(),
+                white_time: 120000,
+                black_time: 120000,
+                white_increment: 0,
+                black_increment: 0,
+                status: GameStatus::Created,
+                winner: None,
+                white_draw_offer: false,
+                black_draw_offer: false,
+                white_take_back_proposal: false,
+                black_take_back_proposal: false,
+                skipped_states: 0,
+            };
+
+            assert_that!(tracked_events.deref()).has_length(1);
+            assert_that!(&tracked_events.deref()[0].1)
+                .is_equal_to(&GameEvent::GameState(expected_event.clone()));
+        });
+    }
+
+    const GAME_FULL_LINE: &str = concat!(
+        r#"{"type":"gameFull","id":"testId","variant":{},"speed":"blitz","perf":{},"#,
+        r#""rated":false,"createdAt":1234,"white":{},"black":{},"initialFen":"startpos","#,
+        r#""state":{"type":"gameState","moves":"","wtime":120000,"btime":120000,"winc":0,"#,
+        r#""binc":0,"status":"started"}}"#);
+
+    #[test]
+    fn game_event_stream_is_recorded() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, _, _) = create_mock_bot();
+            let recording_path = std::env::temp_dir()
+                .join(format!("libot-event-recording-{}.jsonl", std::process::id()));
+            let recorder = EventRecorder::create(&recording_path).unwrap();
+            let state = RunnerState::new(None).with_event_recorder(Arc::new(recorder));
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(format!("{GAME_FULL_LINE}\n\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testId")))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testId".to_owned(), Arc::new(state)).await;
+
+            let replay = EventReplay::open(&recording_path).unwrap();
+            std::fs::remove_file(recording_path).unwrap();
+
+            assert_that!(replay.lines()).has_length(1);
+            assert_that!(replay.lines()[0].game_id.clone()).contains("testId".to_owned());
+            assert_that!(replay.lines()[0].line.as_str()).is_equal_to(GAME_FULL_LINE);
+        });
+    }
+
+    #[test]
+    fn repeated_game_start_is_handled_once() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, tracked_bot_events, tracked_game_events) = create_mock_bot();
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(format!("{GAME_FULL_LINE}\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testId"))),
+                Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testId")))
+            ]);
+
+            run_with_event_stream(
+                Arc::new(bot), stream, client, "testBotId".to_owned(), test_state(None)).await;
+
+            assert_that!(tracked_bot_events.lock().unwrap().deref())
+                .contains_exactly_in_given_order([
+                    BotEvent::GameStart(test_game_event_info("testId"))
+                ]);
+            assert_that!(tracked_game_events.lock().unwrap().deref()).has_length(1);
+        });
+    }
+
+    #[derive(Default)]
+    struct ErrorTrackingBot {
+        errors: Mutex<Vec<(Option<GameId>, RunnerError)>>,
+        game_states: Mutex<usize>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for ErrorTrackingBot {
+        async fn on_error(&self, _: &BotContext, game_id: Option<GameId>,
+                error: RunnerError, _: &BotClient) {
+            self.errors.lock().unwrap().push((game_id, error));
+        }
+
+        async fn on_game_state(&self, _: &GameContext, _: GameStateEvent, _: &BotClient) {
+            *self.game_states.lock().unwrap() += 1;
+        }
+    }
+
+    #[test]
+    fn invalid_game_event_lines_are_reported_and_skipped() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let bot = Arc::new(ErrorTrackingBot::default());
+            let state = RunnerState::new(None).with_parsing_config(ParsingConfig::tolerant());
+            let game_state_line = r#"{"type":"gameState","moves":"e2e4","wtime":1,"btime":1,"#
+                .to_owned() + r#""winc":0,"binc":0,"status":"started"}"#;
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(format!("{GAME_FULL_LINE}\nnot json\n{game_state_line}\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let stream = stream::iter([
+                Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testId")))
+            ]);
+
+            run_with_event_stream(
+                Arc::clone(&bot), stream, client, "testBotId".to_owned(), Arc::new(state)).await;
+
+            assert_that!(bot.errors.lock().unwrap().deref()).contains_exactly_in_given_order([
+                (Some("testId".to_owned()), RunnerError::Parse(StreamParseError::InvalidLine {
+                    line: "not json".to_owned(),
+                    message: parsing::deserialize_line::<GameEvent>("not json").unwrap_err()
+                }))
+            ]);
+            assert_that!(*bot.game_states.lock().unwrap()).is_equal_to(2);
+        });
+    }
+
+    #[rstest]
+    #[case::consistent(Speed::Correspondence, true, false)]
+    #[case::inconsistent_without_validation(Speed::UltraBullet, false, false)]
+    #[case::inconsistent_with_validation(Speed::UltraBullet, true, true)]
+    fn inconsistent_challenge_speed_is_reported(#[case] speed: Speed, #[case] validation: bool,
+            #[case] expected_report: bool) {
+        let bot = Arc::new(ErrorTrackingBot::default());
+        let mut state = RunnerState::new(None);
+
+        if validation {
+            state = state.with_speed_validation();
+        }
+
+        let mut challenge = test_challenge("testChallengeId");
+        challenge.speed = speed;
+        let stream = stream::iter([Ok::<_, &str>(BotEvent::Challenge(challenge))]);
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+
+        tokio_test::block_on(run_with_event_stream(
+            Arc::clone(&bot), stream, mock_client, "testBotId".to_owned(), Arc::new(state)));
+
+        let expected_errors = iter::once((None, RunnerError::InconsistentSpeed {
+            challenge_id: "testChallengeId".to_owned(),
+            reported: Speed::UltraBullet,
+            expected: Speed::Correspondence
+        })).filter(|_| expected_report);
+
+        assert_that!(bot.errors.lock().unwrap().deref())
+            .contains_exactly_in_given_order(expected_errors);
+    }
+
+    #[derive(Default)]
+    struct PanickingBot {
+        errors: Mutex<Vec<(Option<GameId>, RunnerError)>>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for PanickingBot {
+        async fn on_game_start(&self, _: &BotContext, _: GameStartFinish, _: &BotClient) {
+            panic!("test panic");
+        }
+
+        async fn on_error(&self, _: &BotContext, game_id: Option<GameId>,
+                error: RunnerError, _: &BotClient) {
+            self.errors.lock().unwrap().push((game_id, error));
+        }
+    }
+
+    #[test]
+    fn handler_panic_writes_crash_dump_and_reports_it() {
+        let directory = std::env::temp_dir()
+            .join(format!("libot-runner-crash-dumps-{}", std::process::id()));
+        let crash_dumper = Arc::new(CrashDumper::new(&directory, 10));
+        crash_dumper.record(None, r#"{"type":"gameStart","game":{"id":"testId"}}"#);
+        let state = RunnerState::new(None).with_crash_dumper(Arc::clone(&crash_dumper));
+        let bot = Arc::new(PanickingBot::default());
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+        let stream = stream::iter([
+            Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testId")))
+        ]);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            tokio_test::block_on(run_with_event_stream(Arc::clone(&bot), stream, mock_client,
+                "testBotId".to_owned(), Arc::new(state)))
+        }));
+
+        assert_that!(result.is_err()).is_true();
+
+        let errors = bot.errors.lock().unwrap();
+
+        assert_that!(errors.len()).is_equal_to(1);
+        assert_that!(errors[0].0.clone()).contains("testId".to_owned());
+
+        let RunnerError::HandlerPanicked { message, dump: Some(dump) } = &errors[0].1
+        else {
+            panic!("expected handler panic with dump, got {:?}", errors[0].1);
+        };
+        let dump = events::CrashDump::open(dump).unwrap();
+
+        assert_that!(message.contains("test panic")).is_true();
+        assert_that!(dump.game_id).contains("testId".to_owned());
+        assert_that!(dump.lines.len()).is_equal_to(1);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn game_stream_opened_reports_whether_game_was_already_streamed() {
+        let state = RunnerState::new(None);
+        let game_id = "testId".to_owned();
+
+        assert_that!(state.game_stream_opened(&game_id)).is_true();
+        assert_that!(state.game_stream_opened(&game_id)).is_false();
+
+        state.game_stream_closed(&game_id);
+
+        assert_that!(state.game_stream_opened(&game_id)).is_true();
+    }
+
+    #[test]
+    fn game_start_after_closed_game_stream_opens_stream_again() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let (bot, tracked_bot_events, _) = create_mock_bot();
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(500))
+                .up_to_n_times(1)
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testId"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(format!("{GAME_FULL_LINE}\n")))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let first_start = stream::once(async {
+                Ok::<_, &str>(BotEvent::GameStart(test_game_event_info("testId")))
+            });
+            let second_start = stream::once(async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+
+                Ok(BotEvent::GameStart(test_game_event_info("testId")))
+            });
+            let state = test_state(None);
+
+            run_with_event_stream(Arc::new(bot), first_start.chain(second_start), client,
+                "testBotId".to_owned(), Arc::clone(&state)).await;
+
+            assert_that!(tracked_bot_events.lock().unwrap().deref()).has_length(2);
+            assert_that!(state.active_games.lock().unwrap().len()).is_equal_to(1);
+        });
+    }
+
+    struct FailingUserListStore;
+
+    impl GameStore for FailingUserListStore {
+        fn record_game(&self, _: &GameRecord) -> StoreResult<()> {
+            Ok(())
+        }
+
+        fn games(&self) -> StoreResult<Vec<GameRecord>> {
+            Ok(Vec::new())
+        }
+
+        fn user_lists(&self) -> StoreResult<UserLists> {
+            Err(io::Error::other("corrupt user lists").into())
+        }
+    }
+
+    #[test]
+    fn game_store_with_unreadable_user_lists_is_rejected() {
+        let (bot, _, _) = create_mock_bot();
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+        let runner = BotRunner::new(bot, mock_client);
+
+        assert_that!(runner.with_game_store(Arc::new(FailingUserListStore)).is_err()).is_true();
+    }
+
+    #[test]
+    fn watchdog_interval_is_half_of_timeout_if_enabled() {
+        let (bot, _, _) = create_mock_bot();
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+        let runner = BotRunner::new(bot, mock_client);
+
+        assert_that!(runner.watchdog_interval()).is_none();
+
+        let notifier = SystemdNotifier::new("/run/systemd/notify");
+        let runner = runner.with_systemd_notifier(notifier, Some(Duration::from_secs(30)));
+
+        assert_that!(runner.watchdog_interval()).contains(Duration::from_secs(15));
+    }
+
+    #[rstest]
+    #[case::live(Duration::from_secs(60), true)]
+    #[case::not_live(Duration::ZERO, false)]
+    fn watchdog_is_pinged_only_while_live(#[case] liveness_timeout: Duration,
+            #[case] expected_pinged: bool) {
+        let path = std::env::temp_dir().join(format!("libot-watchdog-{}-{expected_pinged}.sock",
+            std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let receiver = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+        let notifier = SystemdNotifier::new(&path);
+        let health_monitor = HealthMonitor::new(liveness_timeout);
+
+        std::thread::sleep(Duration::from_millis(5));
+        tokio_test::block_on(async {
+            let watchdog = ping_watchdog(
+                Some((&notifier, Duration::from_millis(10))), Some(&health_monitor));
+            let _ = tokio::time::timeout(Duration::from_millis(35), watchdog).await;
+        });
+
+        receiver.set_nonblocking(true).unwrap();
+        let mut buffer = [0; 16];
+        let pinged = receiver.recv(&mut buffer).is_ok();
+
+        assert_that!(pinged).is_equal_to(expected_pinged);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn active_games_are_reported_to_health_monitor() {
+        let monitor = Arc::new(HealthMonitor::new(Duration::from_secs(60)));
+        let state = RunnerState::new(None).with_health_monitor(Arc::clone(&monitor));
+
+        state.game_started(&"testId1".to_owned());
+        state.game_started(&"testId2".to_owned());
+        state.game_started(&"testId1".to_owned());
+
+        assert_that!(monitor.report().active_games).is_equal_to(2);
+
+        state.game_finished(&"testId1".to_owned());
+
+        assert_that!(monitor.report().active_games).is_equal_to(1);
+    }
+
+    #[test]
+    fn replayed_session_is_fed_through_bot() {
+        let (bot, tracked_bot_events, tracked_game_events) = create_mock_bot();
+        let recorded_line = |game_id: Option<&str>, line: &str| events::RecordedLine {
+            timestamp: 0,
+            game_id: game_id.map(str::to_owned),
+            line: line.to_owned()
+        };
+        let replay = EventReplay::new(vec![
+            recorded_line(None, r#"{"type":"gameStart","game":{"id":"testId"}}"#),
+            recorded_line(Some("testId"), GAME_FULL_LINE),
+            recorded_line(Some("testId"),
+                r#"{"type":"chatLine","room":"player","username":"testUser","text":"hi"}"#),
+            recorded_line(Some("otherId"), r#"{"type":"opponentGone","gone":true}"#)
+        ]);
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+
+        tokio_test::block_on(
+            BotRunner::new(bot, mock_client).replay(replay, "testBotId".to_owned()));
+
+        let tracked_game_events = tracked_game_events.lock().unwrap();
+
+        assert_that!(tracked_bot_events.lock().unwrap().deref())
+            .contains_exactly_in_given_order([
+                BotEvent::GameStart(test_game_event_info("testId"))
+            ]);
+        assert_that!(tracked_game_events.deref()).has_length(2);
+        assert!(matches!(&tracked_game_events[1].1, GameEvent::ChatLine(chat_line)
+            if chat_line.chat_line.text == "hi"));
+    }
+
+    fn player_with_id(id: &str) -> GameEventPlayer {
+        GameEventPlayer {
+            ai_level: None,
+            id: Some(id.to_owned()),
+            name: None,
+            title: None,
+            rating: None,
+            provisional: None
+        }
+    }
+
+    fn game_state_event(moves: &str) -> GameStateEvent {
+        GameStateEvent {
+            moves: moves.to_string(),
+            white_time: 1,
+            black_time: 2,
+            white_increment: 3,
+            black_increment: 4,
+            status: GameStatus::Created,
+            winner: None,
+            white_draw_offer: false,
+            black_draw_offer: false,
+            white_take_back_proposal: false,
+            black_take_back_proposal: false,
+            skipped_states: 0,
+        }
+    }
+
+    #[rstest]
+    #[case::no_further_events(vec![])]
+    #[case::game_state_event(vec![
+        GameEvent::GameState(game_state_event("testMoves2"))
+    ])]
+    #[case::chat_line(vec![
+        GameEvent::ChatLine(ChatLineEvent {
+            chat_line: ChatLine {
+                room: ChatRoom::Player,
+                username: "testUsername".to_owned(),
+                text: "testText".to_owned()
+            }
+        })
+    ])]
+    #[case::opponent_gone(vec![
+        GameEvent::OpponentGone(OpponentGoneEvent {
+            gone: true,
+            claim_win_in_seconds: Some(30)
+        })
+    ])]
+    fn correct_game_events_are_called_on_bot(#[case] events: Vec<GameEvent>) {
+        let game_info = GameInfo {
+            id: "testGameId".to_string(),
+            variant: Some(Variant::Standard),
+            clock: None,
+            speed: Speed::Bullet,
+            perf: GamePerf {
+                name: None,
+            },
+            rated: false,
+            created_at: 0,
+            white: player_with_id("testWhiteId"),
+            black: player_with_id("testBlackId"),
+            initial_fen: "testInitialFen".to_string(),
+            days_per_turn: None,
+            opening: None,
+            tournament_id: None,
+        };
+        let first_state_event = game_state_event("testMoves1");
+
+        let (bot, _, tracked_events) = create_mock_bot();
+        let event_results = iter::once(
+                GameEvent::GameFull(GameFullEvent {
+                    info: game_info.clone(),
+                    state: first_state_event.clone(),
+                }))
+            .chain(events.iter().cloned())
+            .map(Ok)
+            .collect::<Vec<Result<_, &str>>>();
+        let stream = stream::iter(event_results);
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+        let bot_id = "testId".to_owned();
+        let state = RunnerState::new(None);
+
+        tokio_test::block_on(run_with_game_event_stream(
+            Arc::new(bot), stream, mock_client, bot_id.clone(), &state));
+
+        let tracked_events = tracked_events.lock().unwrap();
+        let expected_context = GameContext {
+            bot_color: None,
+            bot_id,
+            info: game_info,
+            opponent_stats: None,
+            move_timer: Some(ByAddress(Arc::clone(&state.move_timer))),
+            position_tracker: Some(ByAddress(Arc::clone(&state.position_tracker))),
+            move_watcher: Some(ByAddress(Arc::clone(&state.move_watcher))),
+            game_cancellation: Some(ByAddress(Arc::clone(&state.game_cancellation))),
+            session_log: Some(ByAddress(Arc::clone(&state.session_log))),
+            spectators: state.spectator_tracker.clone().map(ByAddress)
+        };
+        let expected_events = events.into_iter()
+            .map(|event| (expected_context.clone(), event))
+            .collect::<Vec<_>>();
+
+        assert_that!(tracked_events.deref())
+            .has_length(expected_events.len() + 1)
+            .starts_with([(expected_context, GameEvent::GameState(first_state_event))])
+            .ends_with(expected_events);
+    }
+
+    #[rstest]
+    #[case::neither("testWhiteId", "testBlackId", "testBotId", None)]
+    #[case::white("testBotId", "testBlackId", "testBotId", Some(Color::White))]
+    #[case::black("testWhiteId", "testBotId", "testBotId", Some(Color::Black))]
+    fn game_context_has_correct_bot_color(
+            #[case] white_id: &str,
+            #[case] black_id: &str,
+            #[case] bot_id: &str,
+            #[case] expected_bot_color: Option<Color>) {
+        let game_info = GameInfo {
+            id: "testGameId".to_string(),
+            variant: Some(Variant::Standard),
+            clock: None,
+            speed: Speed::Classical,
+            perf: GamePerf {
+                name: None,
+            },
+            rated: false,
+            created_at: 0,
+            white: player_with_id(white_id),
+            black: player_with_id(black_id),
+            initial_fen: "testInitialFen".to_string(),
+            days_per_turn: None,
+            opening: None,
+            tournament_id: None,
+        };
+        let state_event = game_state_event("testMoves");
+
+        let (bot, _, tracked_events) = create_mock_bot();
+        let stream = stream::once(async {
+            Ok::<_, &str>(GameEvent::GameFull(
+                GameFullEvent {
+                    info: game_info.clone(),
+                    state: state_event
+                }))
+        });
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+
+        tokio_test::block_on(run_with_game_event_stream(
+            Arc::new(bot), stream, mock_client, bot_id.to_owned(), &RunnerState::new(None)));
+
+        let tracked_events = tracked_events.lock().unwrap();
+
+        assert_that!(tracked_events.deref()[0].0.bot_color).is_equal_to(expected_bot_color);
+    }
+
+    #[test]
+    fn game_context_contains_opponent_stats_if_enabled() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("GET"))
+                .and(path("/crosstable/testBotId/opponent"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{"users":{"testbotid":1.5,"opponent":0.5},"nbGames":2}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let game_store = Arc::new(MemoryGameStore::default());
+            let info = store_tests::test_game_info("oldGameId", "testBotId", "opponent");
+            let result = store_tests::test_result(GameOutcome::Win, Some(Color::White));
+            game_store.record_game(&GameRecord::new(&info, "e2e4", &result)).unwrap();
+            let state = RunnerState::new(None)
+                .with_game_store(game_store)
+                .with_opponent_stats();
+            let (bot, _, tracked_events) = create_mock_bot();
+            let game_info = store_tests::test_game_info("testGameId", "opponent", "testBotId");
+            let stream = stream::once(async {
+                Ok::<_, &str>(GameEvent::GameFull(GameFullEvent {
+                    info: game_info,
+                    state: game_state_event("")
+                }))
+            });
+
+            run_with_game_event_stream(
+                Arc::new(bot), stream, client, "testBotId".to_owned(), &state).await;
+
+            let tracked_events = tracked_events.lock().unwrap();
+            let stats = tracked_events.deref()[0].0.opponent_stats().unwrap();
+
+            assert_that!(stats.opponent_id.as_str()).is_equal_to("opponent");
+            assert_that!(stats.wins).is_equal_to(1);
+            assert_that!(stats.crosstable.map(|score| score.bot_score())).contains(1.5);
+        });
+    }
+
+    #[test]
+    fn game_starts_without_opponent_stats_if_collecting_them_times_out() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("GET"))
+                .and(path("/crosstable/testBotId/opponent"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(r#"{"users":{"testbotid":1.5,"opponent":0.5},"nbGames":2}"#)
+                    .set_delay(OPPONENT_STATS_TIMEOUT * 5))
+                .mount(&server)
+                .await;
+            let state = RunnerState::new(None).with_opponent_stats();
+            let (bot, _, tracked_events) = create_mock_bot();
+            let game_info = store_tests::test_game_info("testGameId", "opponent", "testBotId");
+            let stream = stream::once(async {
+                Ok::<_, &str>(GameEvent::GameFull(GameFullEvent {
+                    info: game_info,
+                    state: game_state_event("")
+                }))
+            });
+            let start = Instant::now();
+
+            run_with_game_event_stream(
+                Arc::new(bot), stream, client, "testBotId".to_owned(), &state).await;
+
+            let tracked_events = tracked_events.lock().unwrap();
+
+            assert_that!(start.elapsed()).is_less_than(OPPONENT_STATS_TIMEOUT * 2);
+            assert_that!(tracked_events.deref()[0].0.opponent_stats()).is_none();
+        });
+    }
+
+    #[derive(Default)]
+    struct OfferTrackingBot {
+        offers: Mutex<Vec<(OfferKind, Moves)>>,
+        offered: Mutex<Vec<(OfferKind, Color)>>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for OfferTrackingBot {
+        async fn on_draw_offered(&self, _: &GameContext, color: Color, _: &BotClient) {
+            self.offered.lock().unwrap().push((OfferKind::Draw, color));
+        }
+
+        async fn on_takeback_proposed(&self, _: &GameContext, color: Color, _: &BotClient) {
+            self.offered.lock().unwrap().push((OfferKind::Takeback, color));
+        }
+
+        async fn on_draw_offer(&self, _: &GameContext, state: GameStateEvent, _: &BotClient) {
+            self.offers.lock().unwrap().push((OfferKind::Draw, state.moves));
+        }
+
+        async fn on_takeback_proposal(&self, _: &GameContext, state: GameStateEvent,
+                _: &BotClient) {
+            self.offers.lock().unwrap().push((OfferKind::Takeback, state.moves));
+        }
+    }
+
+    fn game_state_with_offers(moves: &str, black_draw_offer: bool,
+            black_take_back_proposal: bool) -> GameStateEvent {
+        GameStateEvent {
+            black_draw_offer,
+            black_take_back_proposal,
+            ..game_state_event(moves)
+        }
+    }
+
+    fn offer_events() -> Vec<Result<GameEvent, &'static str>> {
+        let game_info = store_tests::test_game_info("testGameId", "testBotId", "opponent");
+
+        vec![
+            Ok(GameEvent::GameFull(GameFullEvent {
+                info: game_info,
+                state: game_state_with_offers("", false, false)
+            })),
+            Ok(GameEvent::GameState(game_state_with_offers("e2e4", true, false))),
+            Ok(GameEvent::GameState(game_state_with_offers("e2e4 e7e5", true, true))),
+            Ok(GameEvent::GameState(game_state_with_offers("e2e4 e7e5 g1f3", false, false))),
+            Ok(GameEvent::GameState(game_state_with_offers("e2e4 e7e5 g1f3 b8c6", true, false)))
+        ]
+    }
+
+    #[test]
+    fn new_offers_are_delegated_to_bot_without_policy() {
+        let bot = Arc::new(OfferTrackingBot::default());
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+
+        tokio_test::block_on(run_with_game_event_stream(Arc::clone(&bot),
+            stream::iter(offer_events()), mock_client, "testBotId".to_owned(),
+            &RunnerState::new(None)));
+
+        let mut offers = bot.offers.lock().unwrap().clone();
+        offers.sort_by_key(|(_, moves)| moves.len());
+
+        assert_that!(offers).contains_exactly_in_given_order([
+            (OfferKind::Draw, "e2e4".to_owned()),
+            (OfferKind::Takeback, "e2e4 e7e5".to_owned()),
+            (OfferKind::Draw, "e2e4 e7e5 g1f3 b8c6".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn offers_are_answered_according_to_policy() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/draw/no"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(2)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/takeback/yes"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let offer_policies = OfferPolicies {
+                draw: Some(Arc::new(OfferResponse::Decline)),
+                takeback: Some(Arc::new(OfferResponse::Accept))
+            };
+            let state = RunnerState::new(None).with_offer_policies(offer_policies);
+            let bot = Arc::new(OfferTrackingBot::default());
+
+            run_with_game_event_stream(Arc::clone(&bot), stream::iter(offer_events()), client,
+                "testBotId".to_owned(), &state).await;
+
+            assert_that!(bot.offers.lock().unwrap().deref()).is_empty();
+        });
+    }
+
+    #[test]
+    fn offered_hooks_are_called_for_both_colors() {
+        let bot = Arc::new(OfferTrackingBot::default());
+        let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+        let game_info = store_tests::test_game_info("testGameId", "testBotId", "opponent");
+        let events = [
+            GameEvent::GameFull(GameFullEvent {
+                info: game_info,
+                state: GameStateEvent {
+                    white_draw_offer: true,
+                    ..game_state_event("")
+                }
+            }),
+            GameEvent::GameState(GameStateEvent {
+                white_draw_offer: true,
+                black_take_back_proposal: true,
+                ..game_state_event("e2e4")
+            })
+        ];
+
+        tokio_test::block_on(run_with_game_event_stream(Arc::clone(&bot),
+            stream::iter(events.map(Ok::<_, &str>)), mock_client, "testBotId".to_owned(),
+            &RunnerState::new(None)));
+
+        assert_that!(bot.offered.lock().unwrap().deref()).contains_exactly_in_given_order([
+            (OfferKind::Draw, Color::White),
+            (OfferKind::Takeback, Color::Black)
+        ]);
+        assert_that!(bot.offers.lock().unwrap().deref()).contains_exactly_in_given_order([
+            (OfferKind::Takeback, "e2e4".to_owned())
+        ]);
+    }
+
+    #[derive(Default)]
+    struct MovingBot {
+        latencies: Mutex<Vec<Option<Duration>>>,
+        moved: Notify
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for MovingBot {
+        async fn on_game_state(&self, context: &GameContext, state: GameStateEvent,
+                client: &BotClient) {
+            let mov = if state.moves.is_empty() { "e2e4" } else { "g1f3" };
+
+            self.latencies.lock().unwrap().push(context.last_move_latency());
+            context.make_move(client, mov.to_owned(), false).await.unwrap();
+            self.moved.notify_one();
+        }
+    }
+
+    #[test]
+    fn move_latency_is_recorded_for_submitted_moves() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e2e4"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/g1f3"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let telemetry = Arc::new(MoveTelemetry::new());
+            let state = RunnerState::new(None).with_move_telemetry(Arc::clone(&telemetry));
+            let bot = Arc::new(MovingBot::default());
+            let game_info = store_tests::test_game_info("testGameId", "testBotId", "opponent");
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: game_info,
+                state: GameStateEvent {
+                    status: GameStatus::Started,
+                    ..game_state_event("")
+                }
+            });
+            let moving_bot = Arc::clone(&bot);
+
+            // The game state with the bot's move only arrives after the move was submitted.
+            let events = stream::once(async { game_full })
+                .chain(stream::once(async move {
+                    moving_bot.moved.notified().await;
+
+                    GameEvent::GameState(GameStateEvent {
+                        status: GameStatus::Started,
+                        ..game_state_event("e2e4 e7e5")
+                    })
+                }))
+                .map(Ok::<_, &str>);
+
+            run_with_game_event_stream(Arc::clone(&bot), events, client, "testBotId".to_owned(),
+                &state).await;
+
+            let latencies = bot.latencies.lock().unwrap().clone();
+            let metrics = telemetry.game(&"testGameId".to_owned()).unwrap();
+
+            assert_that!(latencies.len()).is_equal_to(2);
+            assert_that!(latencies[0]).is_none();
+            assert_that!(latencies[1]).is_some();
+            assert_that!(metrics.moves).is_equal_to(2);
+        });
+    }
+
+    #[rstest]
+    #[case::bot("TestBotId", true)]
+    #[case::opponent("opponent", false)]
+    fn chat_lines_of_bot_are_recorded_in_session_log(#[case] username: &str,
+            #[case] expected_recorded: bool) {
+        let game_info = store_tests::test_game_info("testGameId", "testBotId", "opponent");
+        let events = [
+            GameEvent::GameFull(GameFullEvent {
+                info: game_info,
+                state: game_state_event("")
+            }),
+            GameEvent::ChatLine(ChatLineEvent {
+                chat_line: ChatLine {
+                    room: ChatRoom::Player,
+                    username: username.to_owned(),
+                    text: "hello".to_owned()
+                }
+            })
+        ];
+        let client = BotClientBuilder::new().with_token("").build().unwrap();
+        let state = RunnerState::new(None);
+
+        tokio_test::block_on(run_with_game_event_stream(Arc::new(create_mock_bot().0),
+            stream::iter(events.map(Ok::<_, &str>)), client, "testBotId".to_owned(), &state));
+
+        assert_that!(state.session_log.has_chatted(&"testGameId".to_owned()))
+            .is_equal_to(expected_recorded);
+    }
+
+    #[derive(Default)]
+    struct ConfirmingBot {
+        results: Mutex<Vec<LibotResult<()>>>,
+        subscribed: Notify
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for ConfirmingBot {
+        async fn on_game_state(&self, context: &GameContext, state: GameStateEvent,
+                client: &BotClient) {
+            if state.moves.is_empty() {
+                let confirmation =
+                    context.make_move_confirmed(client, "e2e4".to_owned(), Duration::from_secs(5));
+                let (result, _) = futures::join!(confirmation, async {
+                    tokio::task::yield_now().await;
+                    self.subscribed.notify_one();
+                });
+
+                self.results.lock().unwrap().push(result);
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::confirmed("e2e4 e7e5", true)]
+    #[case::other_move("d2d4", false)]
+    fn move_made_in_initial_game_state_is_confirmed_by_following_game_state(
+            #[case] following_moves: &str, #[case] expected_confirmed: bool) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/move/e2e4"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let state = RunnerState::new(None);
+            let bot = Arc::new(ConfirmingBot::default());
+            let game_info = store_tests::test_game_info("testGameId", "testBotId", "opponent");
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: game_info,
+                state: GameStateEvent {
+                    status: GameStatus::Started,
+                    ..game_state_event("")
+                }
+            });
+            let confirming_bot = Arc::clone(&bot);
+            let following_moves = following_moves.to_owned();
+            let events = stream::once(async { game_full })
+                .chain(stream::once(async move {
+                    confirming_bot.subscribed.notified().await;
+
+                    GameEvent::GameState(GameStateEvent {
+                        status: GameStatus::Started,
+                        ..game_state_event(&following_moves)
+                    })
+                }))
+                .map(Ok::<_, &str>);
+
+            run_with_game_event_stream(Arc::clone(&bot), events, client, "testBotId".to_owned(),
+                &state).await;
+
+            let results = bot.results.lock().unwrap();
+
+            assert_that!(results.len()).is_equal_to(1);
+            assert_that!(results[0].is_ok()).is_equal_to(expected_confirmed);
+        });
+    }
+
+    #[derive(Default)]
+    struct SlowBot {
+        handled_states: Mutex<Vec<(String, usize)>>,
+        release: Notify
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for SlowBot {
+        async fn on_game_state(&self, _: &GameContext, state: GameStateEvent, _: &BotClient) {
+            let first = {
+                let mut handled_states = self.handled_states.lock().unwrap();
+                handled_states.push((state.moves, state.skipped_states));
+                handled_states.len() == 2
+            };
+
+            if first {
+                self.release.notified().await;
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::when_full(BackpressureConfig::new(1, 1).with_game_state_coalescing(true))]
+    #[case::latest_only(BackpressureConfig::new(20, 1).with_latest_game_state_only(true))]
+    fn game_states_are_coalesced_while_handlers_are_busy(#[case] backpressure: BackpressureConfig) {
+        tokio_test::block_on(async {
+            let bot = Arc::new(SlowBot::default());
+            let state = RunnerState::new(None).with_backpressure(backpressure);
+            let game_info = store_tests::test_game_info("testGameId", "testWhiteId", "testBlackId");
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: game_info,
+                state: game_state_event("")
+            });
+            let releasing_bot = Arc::clone(&bot);
+            let game_states = (1..=10)
+                .map(|index| GameEvent::GameState(game_state_event(&format!("testMoves{index}"))));
+            let events = stream::iter(iter::once(game_full).chain(game_states))
+                .then(|event| async {
+                    tokio::task::yield_now().await;
+                    event
+                })
+                .chain(stream::once(async move {
+                    releasing_bot.release.notify_one();
+                    tokio::task::yield_now().await;
+                    GameEvent::GameState(game_state_event("testMoves11"))
+                }))
+                .map(Ok::<_, &str>);
+            let mock_client = BotClientBuilder::new().with_token("").build().unwrap();
+
+            run_with_game_event_stream(Arc::clone(&bot), events, mock_client,
+                "testBotId".to_owned(), &state).await;
+
+            let handled_states = bot.handled_states.lock().unwrap();
+            let skipped_states = handled_states.iter()
+                .map(|(_, skipped_states)| skipped_states)
+                .sum::<usize>();
+
+            assert_that!(handled_states.len()).is_less_than(12);
+            assert_that!(handled_states.len() + skipped_states).is_equal_to(12);
+            assert_that!(handled_states.last().map(|(moves, _)| moves.clone()))
+                .contains("testMoves11".to_owned());
+        });
+    }
+
+    fn stale_test_context(bot_color: Option<Color>, initial_fen: &str) -> GameContext {
+        GameContext {
+            bot_color,
+            bot_id: "testBotId".to_owned(),
+            info: GameInfo {
+                initial_fen: initial_fen.to_owned(),
+                ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    #[rstest]
+    #[case::white_before_any_move(Some(Color::White), "startpos", "", true)]
+    #[case::white_after_own_move(Some(Color::White), "startpos", "e2e4", true)]
+    #[case::white_after_opponent_move(Some(Color::White), "startpos", "e2e4 e7e5", false)]
+    #[case::black_before_any_move(Some(Color::Black), "startpos", "", true)]
+    #[case::black_after_opponent_move(Some(Color::Black), "startpos", "e2e4", false)]
+    #[case::white_with_black_to_start(
+        Some(Color::White),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+        "",
+        true
+    )]
+    #[case::unknown_color(None, "startpos", "", false)]
+    fn is_stale_detects_missing_opponent_move(#[case] bot_color: Option<Color>,
+            #[case] initial_fen: &str, #[case] moves: &str, #[case] expected: bool) {
+        let game_context = stale_test_context(bot_color, initial_fen);
+
+        assert_that!(is_stale(&game_context, moves)).is_equal_to(expected);
+    }
+
+    async fn run_stale_game_test(game_info: GameInfo, moves: &str,
+            follow_up: impl Stream<Item = GameEvent> + Send, expected_aborts: u64) {
+        let (client, server) = test_util::setup_wiremock_test().await;
+        Mock::given(method("POST"))
+            .and(path("/bot/game/testGameId/abort"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(expected_aborts)
+            .mount(&server)
+            .await;
+        let state = RunnerState::new(None).with_stale_game_timeout(Duration::from_secs(3600));
+        let (bot, _, _) = create_mock_bot();
+        let game_full = GameEvent::GameFull(GameFullEvent {
+            info: game_info,
+            state: GameStateEvent {
+                status: GameStatus::Started,
+                ..game_state_event(moves)
+            }
+        });
+        let events = stream::once(async { game_full })
+            .chain(follow_up)
+            .map(Ok::<_, &str>);
+
+        run_with_game_event_stream(Arc::new(bot), events, client, "testBotId".to_owned(), &state)
+            .await;
+    }
+
+    fn delayed_end() -> impl Stream<Item = GameEvent> + Send {
+        stream::once(async { tokio::time::sleep(Duration::from_millis(200)).await })
+            .filter_map(|_| async { None })
+    }
+
+    #[test]
+    fn stale_game_is_aborted_after_timeout() {
+        let game_info = store_tests::test_game_info("testGameId", "testBotId", "opponent");
+
+        tokio_test::block_on(run_stale_game_test(game_info, "e2e4", delayed_end(), 1));
+    }
+
+    #[test]
+    fn game_is_not_aborted_after_opponent_moved() {
+        let game_info = store_tests::test_game_info("testGameId", "testBotId", "opponent");
+
+        tokio_test::block_on(run_stale_game_test(game_info, "e2e4 e7e5", delayed_end(), 0));
+    }
+
+    #[rstest]
+    #[case::opponent_gone(true, 1)]
+    #[case::opponent_back(false, 0)]
+    fn stale_game_is_aborted_when_opponent_is_gone(#[case] gone: bool,
+            #[case] expected_aborts: u64) {
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let game_info = GameInfo {
+            created_at: created_at as i64,
+            ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+        };
+        let opponent_gone = GameEvent::OpponentGone(OpponentGoneEvent {
+            gone,
+            claim_win_in_seconds: None
+        });
+
+        tokio_test::block_on(run_stale_game_test(
+            game_info, "e2e4", stream::once(async { opponent_gone }), expected_aborts));
+    }
+
+    #[derive(Default)]
+    struct ClaimTrackingBot {
+        claimable_wins: Mutex<usize>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for ClaimTrackingBot {
+        async fn on_claimable_win(&self, _: &GameContext, _: &BotClient) {
+            *self.claimable_wins.lock().unwrap() += 1;
+        }
+    }
+
+    fn opponent_gone_event(gone: bool, claim_win_in_seconds: Option<Seconds>) -> GameEvent {
+        GameEvent::OpponentGone(OpponentGoneEvent {
+            gone,
+            claim_win_in_seconds
+        })
+    }
+
+    #[rstest]
+    #[case::automatic_claim(true, vec![opponent_gone_event(true, Some(0))], 200, 1, 1)]
+    #[case::hook_only(false, vec![opponent_gone_event(true, Some(0))], 200, 0, 1)]
+    #[case::opponent_returned(
+        true,
+        vec![opponent_gone_event(true, Some(1)), opponent_gone_event(false, None)],
+        1200,
+        0,
+        0
+    )]
+    #[case::no_claim_time(true, vec![opponent_gone_event(true, None)], 200, 0, 0)]
+    fn win_is_claimed_when_opponent_gone_timer_elapses(#[case] automatic_win_claim: bool,
+            #[case] gone_events: Vec<GameEvent>, #[case] end_after_millis: u64,
+            #[case] expected_claims: u64, #[case] expected_hook_calls: usize) {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/claim-victory"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(expected_claims)
+                .mount(&server)
+                .await;
+            let mut state = RunnerState::new(None);
+
+            if automatic_win_claim {
+                state = state.with_automatic_win_claim();
+            }
+
+            let bot = Arc::new(ClaimTrackingBot::default());
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: store_tests::test_game_info("testGameId", "testBotId", "opponent"),
+                state: GameStateEvent {
+                    status: GameStatus::Started,
+                    ..game_state_event("e2e4 e7e5")
+                }
+            });
+            let end = stream::once(async move {
+                tokio::time::sleep(Duration::from_millis(end_after_millis)).await
+            }).filter_map(|_| async { None });
+            let events = stream::once(async { game_full })
+                .chain(stream::iter(gone_events))
+                .chain(end)
+                .map(Ok::<_, &str>);
+
+            run_with_game_event_stream(Arc::clone(&bot), events, client, "testBotId".to_owned(),
+                &state).await;
+
+            assert_that!(*bot.claimable_wins.lock().unwrap()).is_equal_to(expected_hook_calls);
+        });
+    }
+
+    #[derive(Default)]
+    struct InvalidPositionTrackingBot {
+        warnings: Mutex<Vec<InvalidPosition>>,
+        positions: Mutex<Vec<Option<String>>>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for InvalidPositionTrackingBot {
+        async fn on_invalid_position(&self, _: &GameContext, warning: InvalidPosition,
+                _: &BotClient) {
+            self.warnings.lock().unwrap().push(warning);
+        }
+
+        async fn on_game_state(&self, context: &GameContext, _: GameStateEvent, _: &BotClient) {
+            let fen = context.position().map(|position| position.to_fen());
+            self.positions.lock().unwrap().push(fen);
+        }
+    }
+
+    #[rstest]
+    #[case::valid(Variant::FromPosition, "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", 0)]
+    #[case::impossible_castling(Variant::FromPosition, "4k3/8/8/8/8/8/8/4K3 w K - 0 1", 1)]
+    #[case::unparsable(Variant::FromPosition, "not a fen", 1)]
+    #[case::other_variant(Variant::Standard, "4k3/8/8/8/8/8/8/4K3 w K - 0 1", 0)]
+    fn invalid_initial_position_is_reported(#[case] variant: Variant, #[case] initial_fen: &str,
+            #[case] expected_warnings: usize) {
+        tokio_test::block_on(async {
+            let (client, _) = test_util::setup_wiremock_test().await;
+            let state = RunnerState::new(None);
+            let bot = Arc::new(InvalidPositionTrackingBot::default());
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: GameInfo {
+                    variant: Some(variant),
+                    initial_fen: initial_fen.to_owned(),
+                    ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+                },
+                state: game_state_event("")
+            });
+            let events = stream::once(async { Ok::<_, &str>(game_full) });
+
+            run_with_game_event_stream(Arc::clone(&bot), events, client, "testBotId".to_owned(),
+                &state).await;
+
+            assert_that!(bot.warnings.lock().unwrap().len()).is_equal_to(expected_warnings);
+        });
+    }
+
+    #[test]
+    fn game_with_impossible_flags_is_tracked_from_normalized_position() {
+        tokio_test::block_on(async {
+            let (client, _) = test_util::setup_wiremock_test().await;
+            let state = RunnerState::new(None);
+            let bot = Arc::new(InvalidPositionTrackingBot::default());
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: GameInfo {
+                    variant: Some(Variant::FromPosition),
+                    initial_fen: "4k3/8/8/8/8/8/8/4K3 w K e6 0 1".to_owned(),
+                    ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+                },
+                state: game_state_event("")
+            });
+            let events = stream::once(async { Ok::<_, &str>(game_full) });
+
+            run_with_game_event_stream(Arc::clone(&bot), events, client, "testBotId".to_owned(),
+                &state).await;
+
+            let normalized_fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_owned();
+            assert_that!(bot.positions.lock().unwrap().clone())
+                .is_equal_to(vec![Some(normalized_fen.clone())]);
+            assert_that!(bot.warnings.lock().unwrap().clone()).is_equal_to(vec![
+                InvalidPosition::Normalized {
+                    fen: normalized_fen,
+                    issues: vec![
+                        FenIssue::ImpossibleCastlingRight('K'),
+                        FenIssue::ImpossibleEnPassantSquare("e6".parse().unwrap())
+                    ]
+                }
+            ]);
+        });
+    }
+
+    #[derive(Default)]
+    struct TurnTrackingBot {
+        turns: Mutex<Vec<Moves>>
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for TurnTrackingBot {
+        async fn on_my_turn(&self, _: &GameContext, state: GameStateEvent, _: &BotClient) {
+            self.turns.lock().unwrap().push(state.moves);
+        }
+    }
+
+    fn started_game_state(moves: &str) -> GameStateEvent {
+        GameStateEvent {
+            status: GameStatus::Started,
+            ..game_state_event(moves)
+        }
+    }
+
+    #[test]
+    fn on_my_turn_is_called_once_per_turn_of_bot() {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let state = RunnerState::new(None);
+            let bot = Arc::new(TurnTrackingBot::default());
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: store_tests::test_game_info("testGameId", "testBotId", "opponent"),
+                state: started_game_state("")
+            });
+            let follow_up = [
+                GameStateEvent {
+                    black_draw_offer: true,
+                    ..started_game_state("")
+                },
+                started_game_state("e2e4"),
+                started_game_state("e2e4 e7e5"),
+                started_game_state("e2e4 e7e5")
+            ].map(GameEvent::GameState);
+            let events = stream::once(async { game_full })
+                .chain(stream::iter(follow_up))
+                .map(Ok::<_, &str>);
+
+            run_with_game_event_stream(Arc::clone(&bot), events, client, "testBotId".to_owned(),
+                &state).await;
+
+            assert_that!(bot.turns.lock().unwrap().deref())
+                .contains_exactly_in_given_order(["".to_owned(), "e2e4 e7e5".to_owned()]);
+        });
+    }
+
+    #[rstest]
+    #[case::reminder_elapses(vec![], 2)]
+    #[case::bot_moved(vec![started_game_state("e2e4")], 1)]
+    fn correspondence_reminder_calls_on_my_turn_again(#[case] follow_up: Vec<GameStateEvent>,
+            #[case] expected_turns: usize) {
+        tokio_test::block_on(async {
+            let (client, _server) = test_util::setup_wiremock_test().await;
+            let state = RunnerState::new(None)
+                .with_correspondence_reminder(Duration::from_millis(200));
+            let bot = Arc::new(TurnTrackingBot::default());
+            let game_full = GameEvent::GameFull(GameFullEvent {
+                info: GameInfo {
+                    speed: Speed::Correspondence,
+                    days_per_turn: Some(1),
+                    ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+                },
+                state: GameStateEvent {
+                    white_time: 300,
+                    ..started_game_state("")
+                }
+            });
+            let end = stream::once(async {
+                tokio::time::sleep(Duration::from_millis(400)).await
+            }).filter_map(|_| async { None });
+            let events = stream::once(async { game_full })
+                .chain(stream::iter(follow_up.into_iter().map(GameEvent::GameState)))
+                .chain(end)
+                .map(Ok::<_, &str>);
+
+            run_with_game_event_stream(Arc::clone(&bot), events, client, "testBotId".to_owned(),
+                &state).await;
+
+            assert_that!(bot.turns.lock().unwrap().len()).is_equal_to(expected_turns);
+        });
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::watch::{self, Receiver, Sender};
+
+use crate::model::Move;
+use crate::model::game::GameId;
+
+/// A subscription to the moves of a game, obtained with [MoveWatcher::subscribe] before a
+/// move is submitted.
+pub(crate) struct MoveSubscription {
+    receiver: Receiver<Vec<Move>>,
+    ply: usize
+}
+
+impl MoveSubscription {
+
+    /// Waits until a game state of the game registers a move after the moves known at the time of
+    /// subscribing.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the first registered move is the given move. `false` if another move was
+    /// registered instead, the game finished or no move was registered before the timeout
+    /// elapsed.
+    pub(crate) async fn wait_for(mut self, mov: &Move, timeout: Duration) -> bool {
+        self.registered_move(timeout).await.as_ref() == Some(mov)
+    }
+
+    /// Waits until a game state of the game registers a move after the moves known at the time of
+    /// subscribing and returns that move, or [None] if the game finished or no move was
+    /// registered before the timeout elapsed. Unlike [MoveSubscription::wait_for], this can be
+    /// called repeatedly.
+    pub(crate) async fn registered_move(&mut self, timeout: Duration) -> Option<Move> {
+        let ply = self.ply;
+        let confirmation = self.receiver.wait_for(|moves| moves.len() > ply);
+
+        match tokio::time::timeout(timeout, confirmation).await {
+            Ok(Ok(moves)) => Some(moves[ply].clone()),
+            _ => None
+        }
+    }
+}
+
+/// Publishes the moves of each running game as they are reported in game states. Shared between
+/// the runner, which publishes the moves, and the [GameContext](crate::context::GameContext) of
+/// each game, which waits for the submitted move to appear in
+/// [GameContext::make_move_confirmed](crate::context::GameContext::make_move_confirmed).
+#[derive(Debug, Default)]
+pub(crate) struct MoveWatcher {
+    games: Mutex<HashMap<GameId, Sender<Vec<Move>>>>
+}
+
+impl MoveWatcher {
+
+    pub(crate) fn update(&self, game_id: &GameId, moves: &str) {
+        let moves = moves.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+        let mut games = self.games.lock().unwrap();
+
+        match games.get(game_id) {
+            Some(sender) => {
+                sender.send_replace(moves);
+            },
+            None => {
+                games.insert(game_id.clone(), watch::channel(moves).0);
+            }
+        }
+    }
+
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.games.lock().unwrap().remove(game_id);
+    }
+
+    /// Subscribes to the moves of the game with the given ID, returning [None] if no game state of
+    /// the game has been published.
+    pub(crate) fn subscribe(&self, game_id: &GameId) -> Option<MoveSubscription> {
+        let games = self.games.lock().unwrap();
+        let receiver = games.get(game_id)?.subscribe();
+        let ply = receiver.borrow().len();
+
+        Some(MoveSubscription {
+            receiver,
+            ply
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    fn game_id() -> GameId {
+        "testGameId".to_owned()
+    }
+
+    #[test]
+    fn subscribe_returns_none_for_unknown_game() {
+        let watcher = MoveWatcher::default();
+
+        assert_that!(watcher.subscribe(&game_id()).is_none()).is_true();
+    }
+
+    #[tokio::test]
+    async fn wait_for_confirms_registered_move() {
+        let watcher = Arc::new(MoveWatcher::default());
+        watcher.update(&game_id(), "e2e4 e7e5");
+        let subscription = watcher.subscribe(&game_id()).unwrap();
+        let publisher = Arc::clone(&watcher);
+
+        tokio::spawn(async move {
+            publisher.update(&game_id(), "e2e4 e7e5 g1f3");
+        });
+
+        assert_that!(subscription.wait_for(&"g1f3".to_owned(), TIMEOUT).await).is_true();
+    }
+
+    #[tokio::test]
+    async fn wait_for_rejects_other_registered_move() {
+        let watcher = MoveWatcher::default();
+        watcher.update(&game_id(), "");
+        let subscription = watcher.subscribe(&game_id()).unwrap();
+        watcher.update(&game_id(), "d2d4");
+
+        assert_that!(subscription.wait_for(&"e2e4".to_owned(), TIMEOUT).await).is_false();
+    }
+
+    #[tokio::test]
+    async fn wait_for_fails_if_game_finishes() {
+        let watcher = MoveWatcher::default();
+        watcher.update(&game_id(), "e2e4");
+        let subscription = watcher.subscribe(&game_id()).unwrap();
+        watcher.game_finished(&game_id());
+
+        assert_that!(subscription.wait_for(&"e7e5".to_owned(), TIMEOUT).await).is_false();
+    }
+
+    #[tokio::test]
+    async fn wait_for_fails_on_timeout() {
+        let watcher = MoveWatcher::default();
+        watcher.update(&game_id(), "e2e4");
+        let subscription = watcher.subscribe(&game_id()).unwrap();
+        let timeout = Duration::from_millis(20);
+
+        assert_that!(subscription.wait_for(&"e7e5".to_owned(), timeout).await).is_false();
+    }
+}
@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use crate::context::GameContext;
+use crate::model::game::Color;
+use crate::model::game::event::GameStateEvent;
+
+/// The kind of offer the opponent of a bot can make during a game.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OfferKind {
+    Draw,
+    Takeback
+}
+
+/// The response an [OfferPolicy] gives to an offer by the opponent.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OfferResponse {
+
+    /// Accepts the offer.
+    Accept,
+
+    /// Declines the offer.
+    Decline,
+
+    /// Neither accepts nor declines the offer, leaving it to
+    /// [Bot::on_draw_offer](crate::Bot::on_draw_offer) or
+    /// [Bot::on_takeback_proposal](crate::Bot::on_takeback_proposal).
+    Delegate
+}
+
+/// A policy which decides how to respond to a draw offer or takeback proposal by the opponent.
+/// This is implemented for all closures taking a [GameContext] and [GameStateEvent] reference and
+/// returning an [OfferResponse]. It is also implemented for [OfferResponse] itself, which always
+/// gives that response, so `OfferResponse::Decline` declines all offers.
+pub trait OfferPolicy : Send + Sync {
+
+    /// Decides how to respond to an offer made by the opponent.
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The [GameContext] of the game in which the offer was made.
+    /// * `state`: The game state in which the offer was made.
+    ///
+    /// # Returns
+    ///
+    /// An [OfferResponse] determining how the runner handles the offer.
+    fn respond(&self, context: &GameContext, state: &GameStateEvent) -> OfferResponse;
+}
+
+impl<F> OfferPolicy for F
+where
+    F: Fn(&GameContext, &GameStateEvent) -> OfferResponse + Send + Sync
+{
+    fn respond(&self, context: &GameContext, state: &GameStateEvent) -> OfferResponse {
+        self(context, state)
+    }
+}
+
+impl OfferPolicy for OfferResponse {
+    fn respond(&self, _context: &GameContext, _state: &GameStateEvent) -> OfferResponse {
+        *self
+    }
+}
+
+/// An [OfferPolicy] which accepts offers if the bot's evaluation of the position is at most a given
+/// threshold, i.e. the bot does not consider itself to be winning, and declines them otherwise.
+/// If no evaluation is available, the offer is delegated to the bot.
+pub struct EvaluationOfferPolicy<E> {
+    evaluate: E,
+    max_evaluation: i32
+}
+
+impl<E> EvaluationOfferPolicy<E>
+where
+    E: Fn(&GameContext, &GameStateEvent) -> Option<i32> + Send + Sync
+{
+
+    /// Creates a new evaluation-based offer policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `evaluate`: A function which evaluates the current position from the perspective of the
+    ///   bot, e.g. in centipawns, or returns [None] if no evaluation is available.
+    /// * `max_evaluation`: The highest evaluation at which offers are still accepted.
+    pub fn new(evaluate: E, max_evaluation: i32) -> EvaluationOfferPolicy<E> {
+        EvaluationOfferPolicy {
+            evaluate,
+            max_evaluation
+        }
+    }
+}
+
+impl<E> OfferPolicy for EvaluationOfferPolicy<E>
+where
+    E: Fn(&GameContext, &GameStateEvent) -> Option<i32> + Send + Sync
+{
+    fn respond(&self, context: &GameContext, state: &GameStateEvent) -> OfferResponse {
+        match (self.evaluate)(context, state) {
+            Some(evaluation) if evaluation <= self.max_evaluation => OfferResponse::Accept,
+            Some(_) => OfferResponse::Decline,
+            None => OfferResponse::Delegate
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct OfferPolicies {
+    pub(crate) draw: Option<Arc<dyn OfferPolicy>>,
+    pub(crate) takeback: Option<Arc<dyn OfferPolicy>>
+}
+
+impl OfferPolicies {
+
+    pub(crate) fn respond(&self, kind: OfferKind, context: &GameContext, state: &GameStateEvent)
+            -> OfferResponse {
+        let policy = match kind {
+            OfferKind::Draw => &self.draw,
+            OfferKind::Takeback => &self.takeback
+        };
+
+        policy.as_ref()
+            .map(|policy| policy.respond(context, state))
+            .unwrap_or(OfferResponse::Delegate)
+    }
+}
+
+/// Tracks the offers of both players across game states, since Lichess only reports whether an
+/// offer is pending and not when it was made.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct OfferTracker {
+    pending: Vec<(OfferKind, Color)>
+}
+
+impl OfferTracker {
+
+    /// Updates the tracker with the given game state and returns the offers, together with the
+    /// color of the player who made them, which were not pending in the previous state.
+    pub(crate) fn update(&mut self, state: &GameStateEvent) -> Vec<(OfferKind, Color)> {
+        let pending = [
+            (OfferKind::Draw, Color::White, state.white_draw_offer),
+            (OfferKind::Draw, Color::Black, state.black_draw_offer),
+            (OfferKind::Takeback, Color::White, state.white_take_back_proposal),
+            (OfferKind::Takeback, Color::Black, state.black_take_back_proposal)
+        ].into_iter()
+            .filter(|&(_, _, pending)| pending)
+            .map(|(kind, color, _)| (kind, color))
+            .collect::<Vec<_>>();
+        let new_offers = pending.iter()
+            .filter(|offer| !self.pending.contains(offer))
+            .copied()
+            .collect();
+
+        self.pending = pending;
+        new_offers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::game::{GameInfo, GamePerf, GameStatus, Speed};
+    use crate::model::game::event::GameEventPlayer;
+
+    use super::*;
+
+    fn test_player() -> GameEventPlayer {
+        GameEventPlayer {
+            ai_level: None,
+            id: None,
+            name: None,
+            title: None,
+            rating: None,
+            provisional: None
+        }
+    }
+
+    fn test_context() -> GameContext {
+        GameContext {
+            bot_id: "testBotId".to_owned(),
+            bot_color: Some(Color::White),
+            info: GameInfo {
+                id: "testGameId".to_owned(),
+                variant: None,
+                clock: None,
+                speed: Speed::Blitz,
+                perf: GamePerf {
+                    name: None
+                },
+                rated: false,
+                created_at: 0,
+                white: test_player(),
+                black: test_player(),
+                initial_fen: "startpos".to_owned(),
+                days_per_turn: None,
+                opening: None,
+                tournament_id: None
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    fn state_with_offers(white_draw_offer: bool, black_draw_offer: bool,
+            black_take_back_proposal: bool) -> GameStateEvent {
+        GameStateEvent {
+            moves: String::new(),
+            white_time: 0,
+            black_time: 0,
+            white_increment: 0,
+            black_increment: 0,
+            status: GameStatus::Started,
+            winner: None,
+            white_draw_offer,
+            black_draw_offer,
+            white_take_back_proposal: false,
+            black_take_back_proposal,
+            skipped_states: 0
+        }
+    }
+
+    #[test]
+    fn tracker_reports_only_new_offers() {
+        let mut tracker = OfferTracker::default();
+
+        let first = tracker.update(&state_with_offers(true, true, false));
+        let second = tracker.update(&state_with_offers(false, true, true));
+        let third = tracker.update(&state_with_offers(false, false, false));
+        let fourth = tracker.update(&state_with_offers(false, true, false));
+
+        assert_that!(first).contains_exactly_in_given_order([
+            (OfferKind::Draw, Color::White),
+            (OfferKind::Draw, Color::Black)
+        ]);
+        assert_that!(second)
+            .contains_exactly_in_given_order([(OfferKind::Takeback, Color::Black)]);
+        assert_that!(third).is_empty();
+        assert_that!(fourth).contains_exactly_in_given_order([(OfferKind::Draw, Color::Black)]);
+    }
+
+    #[rstest]
+    #[case::accept(Some(-50), OfferResponse::Accept)]
+    #[case::accept_at_threshold(Some(0), OfferResponse::Accept)]
+    #[case::decline(Some(50), OfferResponse::Decline)]
+    #[case::delegate(None, OfferResponse::Delegate)]
+    fn evaluation_policy_compares_with_threshold(#[case] evaluation: Option<i32>,
+            #[case] expected_response: OfferResponse) {
+        let policy =
+            EvaluationOfferPolicy::new(move |_: &GameContext, _: &GameStateEvent| evaluation, 0);
+
+        let response = policy.respond(&test_context(), &state_with_offers(false, true, false));
+
+        assert_that!(response).is_equal_to(expected_response);
+    }
+
+    #[test]
+    fn policies_delegate_if_not_configured() {
+        let policies = OfferPolicies {
+            draw: Some(Arc::new(OfferResponse::Decline)),
+            takeback: None
+        };
+        let context = test_context();
+        let state = state_with_offers(false, true, true);
+
+        assert_that!(policies.respond(OfferKind::Draw, &context, &state))
+            .is_equal_to(OfferResponse::Decline);
+        assert_that!(policies.respond(OfferKind::Takeback, &context, &state))
+            .is_equal_to(OfferResponse::Delegate);
+    }
+}
@@ -0,0 +1,619 @@
+use futures::Stream;
+use futures::stream::{self, StreamExt};
+
+use serde::de::DeserializeOwned;
+
+use thiserror::Error;
+
+const MAX_REPORTED_PREFIX_LENGTH: usize = 256;
+
+/// An error that occurs when a line received from one of the Lichess event streams cannot be
+/// turned into an event. Reported to [Bot::on_error](crate::Bot::on_error) as
+/// [RunnerError::Parse](crate::runner::error::RunnerError::Parse) if the runner is configured to
+/// skip such lines (see [ParsingConfig::with_invalid_line_skipping]).
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum StreamParseError {
+
+    /// The line is not a valid JSON representation of an event.
+    #[error("unparsable line {line:?}: {message}")]
+    InvalidLine {
+
+        /// The raw text of the line.
+        line: String,
+
+        /// A description of why the line could not be parsed.
+        message: String
+    },
+
+    /// The line exceeded the maximum line length (see [ParsingConfig::with_max_line_length]) and
+    /// was discarded.
+    #[error("line starting with {prefix:?} exceeds the maximum length of {max_length} bytes")]
+    LineTooLong {
+
+        /// The beginning of the line, limited to 256 bytes.
+        prefix: String,
+
+        /// The maximum line length that was exceeded.
+        max_length: usize
+    }
+}
+
+/// Configures how a [BotRunner](crate::runner::BotRunner) parses the NDJSON event streams of
+/// Lichess. Set using
+/// [BotRunner::with_parsing_config](crate::runner::BotRunner::with_parsing_config).
+///
+/// By default, lines may be arbitrarily long and an unparsable line causes a panic. Use
+/// [ParsingConfig::tolerant] to protect the runner against garbage, e.g. from a misbehaving proxy.
+///
+/// Lines are deserialized with `serde_json`, or with `simd-json` if the `simd-json` feature is
+/// enabled. Whether the latter is faster depends on the CPU and the length of the lines, so
+/// compare both on the target machine with `cargo bench --features simd-json`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ParsingConfig {
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) skip_invalid_lines: bool
+}
+
+impl ParsingConfig {
+
+    /// Creates a new config which does not limit the line length and does not skip unparsable
+    /// lines.
+    pub fn new() -> ParsingConfig {
+        ParsingConfig::default()
+    }
+
+    /// Creates a config which skips unparsable lines and discards lines longer than 1 MiB, which
+    /// is far more than any event sent by Lichess.
+    pub fn tolerant() -> ParsingConfig {
+        ParsingConfig::new()
+            .with_max_line_length(1024 * 1024)
+            .with_invalid_line_skipping(true)
+    }
+
+    /// Sets the maximum length of a line in bytes. Bytes of longer lines are discarded as they are
+    /// received instead of being buffered until the end of the line, so a stream without line
+    /// breaks cannot exhaust memory. The config is returned for chaining.
+    pub fn with_max_line_length(mut self, max_length: usize) -> ParsingConfig {
+        self.max_line_length = Some(max_length);
+        self
+    }
+
+    /// Sets whether lines which cannot be parsed or exceed the maximum line length are skipped.
+    /// Skipped lines are reported to [Bot::on_error](crate::Bot::on_error) with their raw text.
+    /// The config is returned for chaining.
+    pub fn with_invalid_line_skipping(mut self, enabled: bool) -> ParsingConfig {
+        self.skip_invalid_lines = enabled;
+        self
+    }
+
+    /// Parses a single line of an event stream, such as a
+    /// [BotEvent](crate::model::bot_event::BotEvent) or a
+    /// [GameEvent](crate::model::game::event::GameEvent), in the same way the runner does, i.e.
+    /// respecting the maximum line length and using the JSON parser selected by the features of
+    /// the crate. This allows testing custom inputs, e.g. from a fuzzer, against the parsing of
+    /// the runner.
+    ///
+    /// # Errors
+    ///
+    /// * [StreamParseError::LineTooLong] if the line exceeds the maximum line length.
+    /// * [StreamParseError::InvalidLine] if the line is not a valid JSON representation of `T`.
+    pub fn parse_line<T>(&self, line: &str) -> Result<T, StreamParseError>
+    where
+        T: DeserializeOwned
+    {
+        if let Some(max_length) = self.max_line_length {
+            if line.len() > max_length {
+                return Err(StreamParseError::LineTooLong {
+                    prefix: reported_prefix(line.as_bytes()),
+                    max_length
+                });
+            }
+        }
+
+        deserialize_line(line).map_err(|message| StreamParseError::InvalidLine {
+            line: line.to_owned(),
+            message
+        })
+    }
+}
+
+fn reported_prefix(line: &[u8]) -> String {
+    let prefix_length = line.len().min(MAX_REPORTED_PREFIX_LENGTH);
+
+    String::from_utf8_lossy(&line[..prefix_length]).into_owned()
+}
+
+/// An error in a stream of events parsed by [parse_stream], which either originates from the
+/// underlying stream of bytes or from parsing a line.
+#[derive(Debug)]
+pub(crate) enum StreamError<E> {
+    Transport(E),
+    Parse(StreamParseError)
+}
+
+/// Splits chunks of bytes into lines, keeping incomplete lines until they are completed by a
+/// later chunk. Lines exceeding the maximum length, if any, are reported once and discarded until
+/// the next line break.
+#[derive(Debug, Default)]
+pub(crate) struct LineBuffer {
+    buffer: Vec<u8>,
+    max_length: Option<usize>,
+    discarding: bool
+}
+
+impl LineBuffer {
+
+    pub(crate) fn new(max_length: Option<usize>) -> LineBuffer {
+        LineBuffer {
+            buffer: Vec::new(),
+            max_length,
+            discarding: false
+        }
+    }
+
+    fn exceeds_max_length(&self) -> bool {
+        self.max_length.is_some_and(|max_length| self.buffer.len() > max_length)
+    }
+
+    fn too_long(&mut self) -> StreamParseError {
+        let prefix = reported_prefix(&self.buffer);
+
+        self.buffer.clear();
+
+        StreamParseError::LineTooLong {
+            prefix,
+            max_length: self.max_length.unwrap_or_default()
+        }
+    }
+
+    fn complete_line(&mut self) -> Option<Result<String, StreamParseError>> {
+        if self.exceeds_max_length() {
+            return Some(Err(self.too_long()));
+        }
+
+        let line = String::from_utf8_lossy(&self.buffer).trim().to_owned();
+        self.buffer.clear();
+
+        if line.is_empty() {
+            None
+        }
+        else {
+            Some(Ok(line))
+        }
+    }
+
+    /// Appends the given chunk to the buffer.
+    ///
+    /// # Returns
+    ///
+    /// All non-empty lines completed by the chunk, with surrounding whitespace removed, or an
+    /// error for each line that exceeded the maximum length.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<Result<String, StreamParseError>> {
+        let mut lines = Vec::new();
+        let mut rest = chunk;
+
+        while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+            if self.discarding {
+                self.discarding = false;
+            }
+            else {
+                self.buffer.extend_from_slice(&rest[..end]);
+                lines.extend(self.complete_line());
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        if !self.discarding {
+            self.buffer.extend_from_slice(rest);
+
+            if self.exceeds_max_length() {
+                self.discarding = true;
+                lines.push(Err(self.too_long()));
+            }
+        }
+
+        lines
+    }
+
+    /// Completes the line remaining in the buffer at the end of the stream, if any.
+    pub(crate) fn finish(&mut self) -> Option<Result<String, StreamParseError>> {
+        if self.discarding {
+            self.discarding = false;
+            return None;
+        }
+
+        self.complete_line()
+    }
+}
+
+/// Deserializes a single line of an event stream using `serde_json`, returning a description of
+/// the error if it fails.
+#[cfg(not(feature = "simd-json"))]
+pub(crate) fn deserialize_line<T>(line: &str) -> Result<T, String>
+where
+    T: DeserializeOwned
+{
+    serde_json::from_str(line).map_err(|error| error.to_string())
+}
+
+/// Deserializes a single line of an event stream using `simd-json`, returning a description of
+/// the error if it fails. The parser works in place, so the line is copied first to keep it for
+/// error reporting.
+#[cfg(feature = "simd-json")]
+pub(crate) fn deserialize_line<T>(line: &str) -> Result<T, String>
+where
+    T: DeserializeOwned
+{
+    let mut bytes = line.as_bytes().to_vec();
+
+    simd_json::serde::from_slice(&mut bytes).map_err(|error| error.to_string())
+}
+
+fn parse_line<T, E>(line: Result<String, StreamParseError>) -> Result<T, StreamError<E>>
+where
+    T: DeserializeOwned
+{
+    let line = line.map_err(StreamError::Parse)?;
+
+    match deserialize_line(&line) {
+        Ok(event) => Ok(event),
+        Err(message) => Err(StreamError::Parse(StreamParseError::InvalidLine {
+            line,
+            message
+        }))
+    }
+}
+
+/// Parses the given stream of bytes received from one of the NDJSON event streams into a stream
+/// of events, one for each non-empty line. A final line without a line break is parsed as well.
+pub(crate) fn parse_stream<T, B, E>(stream: impl Stream<Item = Result<B, E>>,
+    config: &ParsingConfig) -> impl Stream<Item = Result<T, StreamError<E>>>
+where
+    T: DeserializeOwned,
+    B: AsRef<[u8]>
+{
+    let mut line_buffer = LineBuffer::new(config.max_line_length);
+
+    stream.map(Some).chain(stream::once(async { None })).flat_map(move |chunk| {
+        let events = match chunk {
+            Some(Ok(bytes)) => line_buffer.push(bytes.as_ref()).into_iter()
+                .map(parse_line)
+                .collect::<Vec<_>>(),
+            Some(Err(error)) => vec![Err(StreamError::Transport(error))],
+            None => line_buffer.finish().into_iter().map(parse_line).collect()
+        };
+
+        stream::iter(events)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct TestEvent {
+        id: u32
+    }
+
+    fn parse_chunks(chunks: &[&str], config: &ParsingConfig)
+            -> Vec<Result<TestEvent, StreamError<&'static str>>> {
+        let chunks = chunks.iter()
+            .map(|chunk| Ok::<_, &'static str>(chunk.as_bytes().to_vec()))
+            .collect::<Vec<_>>();
+
+        tokio_test::block_on(parse_stream(stream::iter(chunks), config).collect())
+    }
+
+    fn ids(events: &[Result<TestEvent, StreamError<&str>>]) -> Vec<Option<u32>> {
+        events.iter()
+            .map(|event| event.as_ref().ok().map(|event| event.id))
+            .collect()
+    }
+
+    #[test]
+    fn line_buffer_joins_lines_split_across_chunks() {
+        let mut line_buffer = LineBuffer::default();
+
+        let first = line_buffer.push(b"{\"a\":1}\n{\"b\"");
+        let second = line_buffer.push(b":2}\r\n\n{\"c\":3}");
+        let third = line_buffer.push(b"\n");
+
+        assert_that!(first).contains_exactly_in_given_order([Ok("{\"a\":1}".to_owned())]);
+        assert_that!(second).contains_exactly_in_given_order([Ok("{\"b\":2}".to_owned())]);
+        assert_that!(third).contains_exactly_in_given_order([Ok("{\"c\":3}".to_owned())]);
+    }
+
+    #[test]
+    fn line_buffer_discards_line_exceeding_max_length_until_line_break() {
+        let mut line_buffer = LineBuffer::new(Some(8));
+
+        let first = line_buffer.push(b"{\"a\":1}\n0123456789");
+        let second = line_buffer.push(b"0123456789");
+        let third = line_buffer.push(b"01\n{\"b\":2}\n");
+
+        assert_that!(first).contains_exactly_in_given_order([
+            Ok("{\"a\":1}".to_owned()),
+            Err(StreamParseError::LineTooLong {
+                prefix: "0123456789".to_owned(),
+                max_length: 8
+            })
+        ]);
+        assert_that!(second).is_empty();
+        assert_that!(third).contains_exactly_in_given_order([Ok("{\"b\":2}".to_owned())]);
+        assert_that!(line_buffer.finish()).is_none();
+    }
+
+    #[test]
+    fn line_buffer_rejects_complete_line_exceeding_max_length() {
+        let mut line_buffer = LineBuffer::new(Some(4));
+
+        let lines = line_buffer.push(b"{\"a\":1}\n{}\n");
+
+        assert_that!(lines.len()).is_equal_to(2);
+        assert!(matches!(lines[0], Err(StreamParseError::LineTooLong { max_length: 4, .. })));
+        assert_that!(&lines[1]).is_equal_to(&Ok("{}".to_owned()));
+    }
+
+    #[rstest]
+    #[case::single_chunk(&["{\"id\":1}\n{\"id\":2}\n"])]
+    #[case::split_mid_object(&["{\"id\"", ":1}\n{", "\"id\":2}\n"])]
+    #[case::split_at_line_break(&["{\"id\":1}", "\n", "{\"id\":2}\n"])]
+    #[case::byte_by_byte(&[
+        "{", "\"", "i", "d", "\"", ":", "1", "}", "\n", "{", "\"", "i", "d", "\"", ":", "2", "}",
+        "\n"
+    ])]
+    #[case::empty_chunks_and_lines(&["", "{\"id\":1}\n\n", "", "\r\n{\"id\":2}\n", ""])]
+    #[case::missing_final_line_break(&["{\"id\":1}\n{\"id\":", "2}"])]
+    fn parse_stream_handles_chunk_boundaries(#[case] chunks: &[&str]) {
+        let events = parse_chunks(chunks, &ParsingConfig::new());
+
+        assert_that!(ids(&events)).contains_exactly_in_given_order([Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn parse_stream_reports_invalid_line_with_raw_text() {
+        let events = parse_chunks(&["{\"id\":1}\nnot json\n{\"id\":2}\n"], &ParsingConfig::new());
+
+        assert_that!(ids(&events)).contains_exactly_in_given_order([Some(1), None, Some(2)]);
+        assert!(matches!(&events[1],
+            Err(StreamError::Parse(StreamParseError::InvalidLine { line, .. }))
+                if line == "not json"));
+    }
+
+    #[test]
+    fn parse_stream_reports_truncated_final_line() {
+        let events = parse_chunks(&["{\"id\":1}\n{\"id\""], &ParsingConfig::new());
+
+        assert_that!(ids(&events)).contains_exactly_in_given_order([Some(1), None]);
+    }
+
+    #[test]
+    fn parse_stream_reports_too_long_line() {
+        let config = ParsingConfig::new().with_max_line_length(16);
+        let events = parse_chunks(&["{\"id\":1}\n{\"id\":", "1234567890123}\n{\"id\":2}\n"],
+            &config);
+
+        assert_that!(ids(&events)).contains_exactly_in_given_order([Some(1), None, Some(2)]);
+        assert!(matches!(&events[1],
+            Err(StreamError::Parse(StreamParseError::LineTooLong { max_length: 16, .. }))));
+    }
+
+    #[test]
+    fn parse_stream_passes_transport_errors_through() {
+        let chunks = vec![Ok(b"{\"id\":1}\n".to_vec()), Err("testError")];
+        let events = tokio_test::block_on(
+            parse_stream::<TestEvent, _, _>(stream::iter(chunks), &ParsingConfig::new())
+                .collect::<Vec<_>>());
+
+        assert_that!(events.len()).is_equal_to(2);
+        assert!(matches!(events[1], Err(StreamError::Transport("testError"))));
+    }
+
+    #[test]
+    fn tolerant_config_skips_invalid_lines_and_limits_line_length() {
+        let config = ParsingConfig::tolerant();
+
+        assert_that!(config.skip_invalid_lines).is_true();
+        assert_that!(config.max_line_length).contains(1024 * 1024);
+    }
+
+    mod properties {
+
+        use proptest::prelude::*;
+
+        use serde_json::{Map, Value};
+
+        use crate::model::bot_event::BotEvent;
+        use crate::model::game::event::GameEvent;
+        use crate::model::user::preferences::UserPreferences;
+
+        use super::super::*;
+
+        #[derive(Clone, Copy, Debug)]
+        enum Sample {
+            Bot(&'static str),
+            Game(&'static str),
+            Preferences(&'static str)
+        }
+
+        impl Sample {
+
+            fn line(self) -> &'static str {
+                match self {
+                    Sample::Bot(line) | Sample::Game(line) | Sample::Preferences(line) => line
+                }
+            }
+
+            fn parse(self, config: &ParsingConfig, line: &str) -> Result<(), StreamParseError> {
+                match self {
+                    Sample::Bot(_) => config.parse_line::<BotEvent>(line).map(|_| ()),
+                    Sample::Game(_) => config.parse_line::<GameEvent>(line).map(|_| ()),
+                    Sample::Preferences(_) =>
+                        config.parse_line::<UserPreferences>(line).map(|_| ())
+                }
+            }
+        }
+
+        const SAMPLES: [Sample; 7] = [
+            Sample::Bot(concat!(r#"{"type":"challenge","challenge":{"id":"testId","#,
+                r#""url":"https://lichess.org/testId","status":"created","challenger":{"#,
+                r#""id":"opponent","name":"Opponent","rating":1950,"title":"BOT","#,
+                r#""online":true},"destUser":{"id":"testbot","name":"TestBot","rating":2000,"#,
+                r#""title":"BOT","online":true},"variant":{"key":"standard","#,
+                r#""name":"Standard","short":"Std"},"rated":true,"speed":"bullet","#,
+                r#""timeControl":{"type":"clock","limit":60,"increment":0,"show":"1+0"},"#,
+                r#""color":"random","perf":{"icon":"T","name":"Bullet"}}}"#)),
+            Sample::Bot(concat!(r#"{"type":"gameStart","game":{"gameId":"testId","#,
+                r#""fullId":"testIdFull","color":"white","#,
+                r#""fen":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","#,
+                r#""hasMoved":false,"isMyTurn":true,"lastMove":"","opponent":{"#,
+                r#""id":"opponent","username":"Opponent","rating":1950},"perf":"bullet","#,
+                r#""rated":true,"secondsLeft":60,"source":"friend","speed":"bullet","#,
+                r#""variant":{"key":"standard","name":"Standard"},"#,
+                r#""compat":{"bot":true,"board":true},"id":"testId"}}"#)),
+            Sample::Game(concat!(r#"{"type":"gameFull","id":"testId","variant":{"#,
+                r#""key":"standard","name":"Standard","short":"Std"},"clock":{"#,
+                r#""initial":60000,"increment":0},"speed":"bullet","perf":{"name":"Bullet"},"#,
+                r#""rated":true,"createdAt":1700000000000,"white":{"id":"testbot","#,
+                r#""name":"TestBot","title":"BOT","rating":2000},"black":{"id":"opponent","#,
+                r#""name":"Opponent","rating":1950,"provisional":false},"#,
+                r#""initialFen":"startpos","state":{"type":"gameState","#,
+                r#""moves":"e2e4 e7e5 g1f3","wtime":58000,"btime":59000,"winc":0,"binc":0,"#,
+                r#""status":"started"}}"#)),
+            Sample::Game(concat!(r#"{"type":"gameState","moves":"e2e4 e7e5","#,
+                r#""wtime":41230,"btime":39870,"winc":0,"binc":0,"status":"started"}"#)),
+            Sample::Game(
+                r#"{"type":"chatLine","room":"player","username":"opponent","text":"Hi!"}"#),
+            Sample::Game(r#"{"type":"opponentGone","gone":true,"claimWinInSeconds":10}"#),
+            Sample::Preferences(concat!(r#"{"prefs":{"bgImg":"testBackgroundImage","#,
+                r#""theme":"testTheme","pieceSet":"testPieceSet","theme3d":"testTheme3d","#,
+                r#""pieceSet3d":"testPieceSet3d","soundSet":"testSoundSet","blindfold":0,"#,
+                r#""autoQueen":1,"autoThreefold":1,"takeback":1,"moretime":1,"clockTenths":0,"#,
+                r#""animation":0,"coords":0,"replay":0,"challenge":1,"message":1,"#,
+                r#""submitMove":11,"confirmResign":0,"insightShare":0,"keyboardMove":0,"#,
+                r#""zen":0,"ratings":0,"moveEvent":0,"rookCastle":0},"#,
+                r#""language":"testLanguage"}"#))
+        ];
+
+        fn sample() -> impl Strategy<Value = Sample> {
+            proptest::sample::select(SAMPLES.to_vec())
+        }
+
+        fn json_value() -> impl Strategy<Value = Value> {
+            let leaf = prop_oneof![
+                Just(Value::Null),
+                any::<bool>().prop_map(Value::from),
+                any::<i64>().prop_map(Value::from),
+                any::<f64>().prop_map(Value::from),
+                ".{0,16}".prop_map(Value::from)
+            ];
+
+            leaf.prop_recursive(3, 32, 4, |inner| prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::vec(("[a-zA-Z]{1,8}", inner), 0..4)
+                    .prop_map(|fields| Value::Object(fields.into_iter().collect()))
+            ])
+        }
+
+        /// Calls the given function on every object in the given value, including nested ones.
+        fn for_each_object(value: &mut Value, f: &mut impl FnMut(&mut Map<String, Value>)) {
+            match value {
+                Value::Object(object) => {
+                    for field in object.values_mut() {
+                        for_each_object(field, f);
+                    }
+
+                    f(object);
+                },
+                Value::Array(values) => {
+                    for value in values {
+                        for_each_object(value, f);
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        proptest! {
+
+            #[test]
+            fn arbitrary_lines_never_panic(sample in sample(), line in ".{0,256}") {
+                let _ = sample.parse(&ParsingConfig::tolerant(), &line);
+            }
+
+            #[test]
+            fn arbitrary_json_never_panics(sample in sample(), value in json_value()) {
+                let _ = sample.parse(&ParsingConfig::tolerant(), &value.to_string());
+            }
+
+            #[test]
+            fn unknown_fields_are_ignored(sample in sample(), name in "unknown[a-zA-Z]{0,8}",
+                    value in json_value()) {
+                let mut json = serde_json::from_str::<Value>(sample.line()).unwrap();
+
+                for_each_object(&mut json, &mut |object| {
+                    object.insert(name.clone(), value.clone());
+                });
+
+                prop_assert!(sample.parse(&ParsingConfig::tolerant(), &json.to_string()).is_ok());
+            }
+
+            #[test]
+            fn null_fields_never_panic(sample in sample(), index in any::<prop::sample::Index>()) {
+                let mut json = serde_json::from_str::<Value>(sample.line()).unwrap();
+                let mut field_count = 0;
+
+                for_each_object(&mut json, &mut |object| field_count += object.len());
+
+                let null_index = index.index(field_count);
+                let mut field_index = 0;
+
+                for_each_object(&mut json, &mut |object| {
+                    for field in object.values_mut() {
+                        if field_index == null_index {
+                            *field = Value::Null;
+                        }
+
+                        field_index += 1;
+                    }
+                });
+
+                let _ = sample.parse(&ParsingConfig::tolerant(), &json.to_string());
+            }
+
+            #[test]
+            fn truncated_lines_are_rejected(sample in sample(),
+                    index in any::<prop::sample::Index>()) {
+                let line = sample.line();
+                let length = index.index(line.len());
+
+                if line.is_char_boundary(length) {
+                    let result = sample.parse(&ParsingConfig::tolerant(), &line[..length]);
+
+                    prop_assert!(result.is_err());
+                }
+            }
+
+            #[test]
+            fn lines_exceeding_max_length_are_rejected(sample in sample(), excess in 1..64usize) {
+                let line = sample.line();
+                let config = ParsingConfig::tolerant()
+                    .with_max_line_length(line.len().saturating_sub(excess));
+                let is_too_long = matches!(sample.parse(&config, line),
+                    Err(StreamParseError::LineTooLong { .. }));
+
+                prop_assert!(is_too_long);
+                prop_assert!(sample.parse(&ParsingConfig::tolerant(), line).is_ok());
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::chess::{ChessError, ChessResult};
+use crate::chess::position::Position;
+use crate::chess::uci::UciMove;
+use crate::model::game::{GameId, GameInfo};
+
+#[derive(Debug)]
+struct TrackedPosition {
+    initial: Position,
+    position: Position,
+    moves: Vec<String>
+}
+
+impl TrackedPosition {
+
+    fn update(&mut self, moves: &str) -> ChessResult<()> {
+        let moves = moves.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+
+        if !moves.starts_with(&self.moves) {
+            self.position = self.initial.clone();
+            self.moves.clear();
+        }
+
+        for mov in &moves[self.moves.len()..] {
+            self.position.play(&mov.parse()?)?;
+        }
+
+        self.moves = moves;
+        Ok(())
+    }
+}
+
+/// Tracks the current [Position] of each running game by replaying the moves of each game state,
+/// following the rules of the game's variant. Shared between the runner, which updates the
+/// positions, the [GameContext](crate::context::GameContext), which exposes them to the bot, and
+/// the [BotClient](crate::client::BotClient), which validates moves against them before
+/// submitting. Games whose initial position or moves cannot be parsed are not tracked.
+#[derive(Debug, Default)]
+pub(crate) struct PositionTracker {
+    games: Mutex<HashMap<GameId, TrackedPosition>>
+}
+
+impl PositionTracker {
+
+    pub(crate) fn game_started(&self, info: &GameInfo, moves: &str) {
+        let Ok(initial) = Position::from_game_info(info)
+        else {
+            return;
+        };
+        let mut tracked = TrackedPosition {
+            position: initial.clone(),
+            initial,
+            moves: Vec::new()
+        };
+
+        if tracked.update(moves).is_ok() {
+            self.games.lock().unwrap().insert(info.id.clone(), tracked);
+        }
+    }
+
+    pub(crate) fn update(&self, game_id: &GameId, moves: &str) {
+        let mut games = self.games.lock().unwrap();
+
+        if let Some(tracked) = games.get_mut(game_id) {
+            if tracked.update(moves).is_err() {
+                games.remove(game_id);
+            }
+        }
+    }
+
+    pub(crate) fn position(&self, game_id: &GameId) -> Option<Position> {
+        self.with_position(game_id, Position::clone)
+    }
+
+    /// Evaluates the given function on the current position of the game with the given ID without
+    /// cloning it, returning [None] if the game is not tracked.
+    pub(crate) fn with_position<T>(&self, game_id: &GameId, f: impl FnOnce(&Position) -> T)
+            -> Option<T> {
+        self.games.lock().unwrap().get(game_id).map(|tracked| f(&tracked.position))
+    }
+
+    /// Evaluates the given function on the moves in UCI notation played so far in the game with
+    /// the given ID, returning [None] if the game is not tracked.
+    pub(crate) fn with_moves<T>(&self, game_id: &GameId, f: impl FnOnce(&[String]) -> T)
+            -> Option<T> {
+        self.games.lock().unwrap().get(game_id).map(|tracked| f(&tracked.moves))
+    }
+
+    /// Checks whether the given move in UCI notation is legal in the current position of the game
+    /// with the given ID. Moves in games which are not tracked are always accepted.
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::InvalidUciMove] if the move is not valid UCI notation and
+    /// [ChessError::IllegalMove] if it is illegal in the tracked position.
+    pub(crate) fn validate_move(&self, game_id: &GameId, mov: &str) -> ChessResult<()> {
+        let games = self.games.lock().unwrap();
+        let Some(tracked) = games.get(game_id)
+        else {
+            return Ok(());
+        };
+        let parsed = mov.parse::<UciMove>()?;
+
+        if tracked.position.is_legal(&parsed) {
+            Ok(())
+        }
+        else {
+            Err(ChessError::IllegalMove(mov.to_owned()))
+        }
+    }
+
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.games.lock().unwrap().remove(game_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde_json::json;
+
+    use crate::chess::PieceKind;
+    use crate::model::game::{Color, Variant};
+
+    use super::*;
+
+    fn game_info(variant: Variant, initial_fen: &str) -> GameInfo {
+        serde_json::from_value(json!({
+            "id": "testGameId",
+            "variant": { "key": variant.key() },
+            "speed": "blitz",
+            "perf": {},
+            "rated": false,
+            "createdAt": 0,
+            "white": {},
+            "black": {},
+            "initialFen": initial_fen
+        })).unwrap()
+    }
+
+    fn game_id() -> GameId {
+        "testGameId".to_owned()
+    }
+
+    #[test]
+    fn tracks_moves_incrementally() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::Standard, "startpos"), "e2e4");
+        tracker.update(&game_id(), "e2e4 e7e5");
+        let position = tracker.position(&game_id()).unwrap();
+
+        assert_that!(position.to_fen()).is_equal_to(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2".to_owned());
+    }
+
+    #[test]
+    fn replays_moves_after_takeback() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::Standard, "startpos"), "e2e4 e7e5");
+        tracker.update(&game_id(), "e2e4 c7c5");
+        let position = tracker.position(&game_id()).unwrap();
+
+        assert_that!(position.piece_at("c5".parse().unwrap())).is_some();
+        assert_that!(position.piece_at("e5".parse().unwrap())).is_none();
+    }
+
+    #[test]
+    fn tracks_crazyhouse_pockets() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::Crazyhouse, "startpos"), "e2e4 d7d5 e4d5");
+        let pockets = tracker.position(&game_id()).unwrap().pockets();
+
+        assert_that!(pockets.of(Color::White).count(PieceKind::Pawn)).is_equal_to(1);
+        assert_that!(pockets.of(Color::Black).is_empty()).is_true();
+    }
+
+    #[test]
+    fn tracks_three_check_checks() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::ThreeCheck, "startpos"), "e2e4 e7e5 f1c4 b8c6");
+        tracker.update(&game_id(), "e2e4 e7e5 f1c4 b8c6 c4f7");
+        let checks = tracker.with_position(&game_id(), |position| {
+            (position.checks_given(Color::White), position.remaining_checks(Color::White))
+        });
+
+        assert_that!(checks).contains((1, 2));
+    }
+
+    #[test]
+    fn tracks_king_of_the_hill_win() {
+        let tracker = PositionTracker::default();
+        let info = game_info(Variant::KingOfTheHill, "startpos");
+
+        tracker.game_started(&info, "e2e3 e7e6 e1e2 e8e7 e2d3 e7d6");
+        tracker.update(&game_id(), "e2e3 e7e6 e1e2 e8e7 e2d3 e7d6 d3d4");
+        let winner = tracker.with_position(&game_id(), Position::variant_winner);
+
+        assert_that!(winner).contains(Some(Color::White));
+    }
+
+    #[test]
+    fn stops_tracking_after_illegal_move() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::Standard, "startpos"), "");
+        tracker.update(&game_id(), "e2e5");
+
+        assert_that!(tracker.position(&game_id())).is_none();
+    }
+
+    #[test]
+    fn does_not_track_game_with_invalid_initial_fen() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::FromPosition, "invalid"), "");
+
+        assert_that!(tracker.position(&game_id())).is_none();
+        assert_that!(tracker.validate_move(&game_id(), "e2e5")).is_ok();
+    }
+
+    #[test]
+    fn validates_drop_moves() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::Crazyhouse, "startpos"), "e2e4 d7d5 e4d5 d8d5");
+
+        assert_that!(tracker.validate_move(&game_id(), "P@e6")).is_ok();
+        assert_that!(tracker.validate_move(&game_id(), "Q@e6"))
+            .contains_error(ChessError::IllegalMove("Q@e6".to_owned()));
+        assert_that!(tracker.validate_move(&game_id(), "X@e6"))
+            .contains_error(ChessError::InvalidUciMove("X@e6".to_owned()));
+    }
+
+    #[test]
+    fn forgets_finished_games() {
+        let tracker = PositionTracker::default();
+
+        tracker.game_started(&game_info(Variant::Standard, "startpos"), "");
+        tracker.game_finished(&game_id());
+
+        assert_that!(tracker.position(&game_id())).is_none();
+    }
+}
@@ -0,0 +1,288 @@
+use std::time::Duration;
+
+use crate::analysis::{AnalysisLimit, Analyser};
+use crate::analysis::summary::{CENTIPAWN_CAP, GameSummary};
+use crate::chess::position::Position;
+use crate::chess::uci;
+use crate::client::BotClient;
+use crate::model::analysis::Score;
+use crate::model::game::{Color, GameInfo};
+use crate::model::game::chat::ChatRoom;
+
+/// Configuration of the post-game analysis of a [BotRunner](crate::runner::BotRunner), which
+/// posts a [GameSummary] of every finished game of the bot, i.e. the accuracy and the number of
+/// inaccuracies, mistakes and blunders of both players, to the chat of the game. The summary of
+/// the server analysis is used if Lichess already analysed the game or, if enabled with
+/// [PostGameAnalysisConfig::with_analysis_request], finishes the requested analysis in time.
+/// Otherwise, the game is analysed with the local [Analyser], if one is configured, or no summary
+/// is posted. Messages are subject to the chat throttle of the client.
+#[derive(Clone)]
+pub struct PostGameAnalysisConfig {
+    pub(crate) analysis_request: Option<(Duration, Duration)>,
+    pub(crate) local_analysis: Option<(Analyser, AnalysisLimit)>,
+    pub(crate) room: ChatRoom
+}
+
+impl PostGameAnalysisConfig {
+
+    /// Creates a new configuration which posts summaries of the server analysis to the
+    /// [ChatRoom::Spectator] room, without local analysis.
+    pub fn new() -> PostGameAnalysisConfig {
+        PostGameAnalysisConfig {
+            analysis_request: None,
+            local_analysis: None,
+            room: ChatRoom::Spectator
+        }
+    }
+
+    /// Requests the server analysis of every finished game with
+    /// [BotClient::request_game_analysis] and waits for it with
+    /// [BotClient::wait_for_game_analysis] before falling back to the local analysis. The
+    /// configuration is returned for chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `poll_interval`: The time between two checks whether the analysis is available.
+    /// * `timeout`: The maximum time to wait for the analysis.
+    pub fn with_analysis_request(mut self, poll_interval: Duration, timeout: Duration)
+            -> PostGameAnalysisConfig {
+        self.analysis_request = Some((poll_interval, timeout));
+        self
+    }
+
+    /// Analyses games which Lichess did not analyse with the given [Analyser], evaluating every
+    /// position of the game until the given limit, which should be low to keep the analysis
+    /// quick. The configuration is returned for chaining.
+    pub fn with_local_analysis(mut self, analyser: Analyser, limit: AnalysisLimit)
+            -> PostGameAnalysisConfig {
+        self.local_analysis = Some((analyser, limit));
+        self
+    }
+
+    /// Sets the chat room to which summaries are posted. The configuration is returned for
+    /// chaining.
+    pub fn with_room(mut self, room: ChatRoom) -> PostGameAnalysisConfig {
+        self.room = room;
+        self
+    }
+}
+
+impl Default for PostGameAnalysisConfig {
+    fn default() -> PostGameAnalysisConfig {
+        PostGameAnalysisConfig::new()
+    }
+}
+
+/// Evaluates the given position from White's perspective. Positions in which the game is over
+/// are scored without asking the analyser, since engines report no evaluation for them.
+async fn evaluate(analyser: &Analyser, position: &Position, limit: AnalysisLimit)
+        -> Option<Score> {
+    if position.is_checkmate() {
+        return Some(match position.side_to_move() {
+            Color::White => Score::Centipawns(-CENTIPAWN_CAP),
+            Color::Black => Score::Centipawns(CENTIPAWN_CAP)
+        });
+    }
+
+    if position.is_stalemate() {
+        return Some(Score::Centipawns(0));
+    }
+
+    // TODO enable error handling
+    analyser.analyse_position(&position.to_fen(), limit, 1).await.ok()?.score()
+}
+
+async fn analyse_locally(analyser: &Analyser, limit: AnalysisLimit, info: &GameInfo,
+        moves: &str) -> Option<GameSummary> {
+    let mut position = Position::from_game_info(info).ok()?;
+    let first_mover = position.side_to_move();
+    let mut scores = vec![evaluate(analyser, &position, limit).await?];
+
+    for mov in uci::parse_uci_moves(moves).ok()? {
+        position.play(&mov).ok()?;
+        scores.push(evaluate(analyser, &position, limit).await?);
+    }
+
+    GameSummary::from_scores(&scores, first_mover)
+}
+
+/// Determines the summary of the given finished game, preferring the server analysis.
+pub(crate) async fn summarize(config: &PostGameAnalysisConfig, client: &BotClient,
+        info: &GameInfo, moves: &str) -> Option<GameSummary> {
+    // TODO enable error handling
+    let server_game = match config.analysis_request {
+        Some((poll_interval, timeout)) => {
+            let _ = client.request_game_analysis(info.id.clone()).await;
+
+            client.wait_for_game_analysis(info.id.clone(), poll_interval, timeout).await.ok()
+                .flatten()
+        },
+        None => client.export_game_with_analysis(info.id.clone()).await.ok()
+    };
+    let server_summary = server_game.and_then(|game| GameSummary::from_exported_game(&game));
+
+    if server_summary.is_some() {
+        return server_summary;
+    }
+
+    let (analyser, limit) = config.local_analysis.as_ref()?;
+
+    analyse_locally(analyser, *limit, info, moves).await
+}
+
+/// Posts the summary of the given finished game to its chat, if one can be determined.
+pub(crate) async fn post_summary(config: &PostGameAnalysisConfig, client: &BotClient,
+        info: &GameInfo, moves: &str) {
+    if let Some(summary) = summarize(config, client, info, moves).await {
+        // TODO enable error handling
+        let _ = client.send_chat_message(info.id.clone(), config.room, summary.to_chat_message())
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use wiremock::{Mock, ResponseTemplate};
+    use wiremock::matchers::{body_string_contains, method, path};
+
+    use crate::analysis::summary::PlayerSummary;
+    use crate::analysis::uci_engine::UciEngine;
+    use crate::store::tests as store_tests;
+    use crate::test_util;
+
+    use super::*;
+
+    const EXPORT_PATH: &str = "/games/export/_ids";
+
+    const CONSTANT_ENGINE: &str = r#"
+        while read line; do
+            case "$line" in
+                uci) echo "uciok";;
+                isready) echo "readyok";;
+                "go "*) echo "info depth 1 multipv 1 score cp 0 pv a2a3"; echo "bestmove a2a3";;
+                quit) exit 0;;
+            esac
+        done"#;
+
+    fn exported_game(analysis: &str) -> String {
+        format!(concat!(r#"{{"id":"testGameId","rated":false,"speed":"blitz","#,
+            r#""createdAt":1514505150384,"status":"mate","players":{{"white":{{{}}},"#,
+            r#""black":{{{}}}}}}}"#, "\n"), analysis, analysis)
+    }
+
+    fn test_info() -> GameInfo {
+        GameInfo {
+            initial_fen: "startpos".to_owned(),
+            ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+        }
+    }
+
+    #[test]
+    fn server_analysis_is_posted_to_spectator_chat() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let analysis = concat!(r#""analysis":{"inaccuracy":1,"mistake":2,"blunder":3,"#,
+                r#""acpl":40,"accuracy":80}"#);
+
+            Mock::given(method("POST"))
+                .and(path(EXPORT_PATH))
+                .respond_with(ResponseTemplate::new(200).set_body_string(exported_game(analysis)))
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/bot/game/testGameId/chat"))
+                .and(body_string_contains("room=spectator"))
+                .and(body_string_contains("80%25+accuracy"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            post_summary(&PostGameAnalysisConfig::new(), &client, &test_info(), "e2e4 e7e5")
+                .await;
+        });
+    }
+
+    #[test]
+    fn requested_server_analysis_is_awaited() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let analysis = concat!(r#""analysis":{"inaccuracy":0,"mistake":0,"blunder":2,"#,
+                r#""acpl":40}"#);
+            let config = PostGameAnalysisConfig::new()
+                .with_analysis_request(Duration::from_millis(10), Duration::from_secs(10));
+
+            Mock::given(method("POST"))
+                .and(path("/testGameId/request-analysis"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path(EXPORT_PATH))
+                .respond_with(ResponseTemplate::new(200).set_body_string(exported_game("")))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path(EXPORT_PATH))
+                .respond_with(ResponseTemplate::new(200).set_body_string(exported_game(analysis)))
+                .mount(&server)
+                .await;
+
+            let summary = summarize(&config, &client, &test_info(), "e2e4").await.unwrap();
+
+            assert_that!(summary.white.blunders).is_equal_to(2);
+        });
+    }
+
+    #[test]
+    fn no_summary_without_server_or_local_analysis() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+
+            Mock::given(method("POST"))
+                .and(path(EXPORT_PATH))
+                .respond_with(ResponseTemplate::new(200).set_body_string(exported_game("")))
+                .mount(&server)
+                .await;
+
+            let summary =
+                summarize(&PostGameAnalysisConfig::new(), &client, &test_info(), "e2e4").await;
+
+            assert_that!(summary).is_none();
+        });
+    }
+
+    #[test]
+    fn local_analysis_is_used_if_server_analysis_is_missing() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let engine = UciEngine::start("sh", ["-c", CONSTANT_ENGINE]).await.unwrap();
+            let config = PostGameAnalysisConfig::new().with_local_analysis(
+                Analyser::new().with_local_engine(engine), AnalysisLimit::Depth(1));
+
+            Mock::given(method("POST"))
+                .and(path(EXPORT_PATH))
+                .respond_with(ResponseTemplate::new(200).set_body_string(exported_game("")))
+                .mount(&server)
+                .await;
+
+            // Fool's mate, so the final position is scored as a win for Black.
+            let summary = summarize(&config, &client, &test_info(), "f2f3 e7e5 g2g4 d8h4").await
+                .unwrap();
+            let perfect = PlayerSummary {
+                accuracy: Some(100),
+                ..PlayerSummary::default()
+            };
+
+            assert_that!(summary).is_equal_to(GameSummary {
+                white: perfect,
+                black: perfect
+            });
+        });
+    }
+}
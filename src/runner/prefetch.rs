@@ -0,0 +1,416 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::book::OpeningBook;
+use crate::cache::ResponseCache;
+use crate::chess::position::Position;
+use crate::chess::uci::UciMove;
+use crate::client::BotClient;
+use crate::context::GameContext;
+use crate::error::LibotResult;
+use crate::model::cloud_eval::CloudEvaluation;
+use crate::model::game::{Color, GameId};
+use crate::model::game::event::GameStateEvent;
+
+/// The number of moves the opponent is assumed to still play with its remaining clock when
+/// estimating its think time.
+const EXPECTED_REMAINING_MOVES: u32 = 30;
+
+/// Prefetches the [CloudEvaluation]s of the positions after the most likely replies of the
+/// opponent while it thinks, so the bot can answer common moves without waiting for the cloud
+/// evaluation. Register with a runner using
+/// [BotRunner::with_cloud_eval_prefetch](crate::runner::BotRunner::with_cloud_eval_prefetch) and
+/// keep another reference to query evaluations with [CloudEvalPrefetcher::cloud_eval] when it is
+/// the bot's turn.
+///
+/// Whenever it becomes the opponent's turn, its think time is estimated from its clock. If it is
+/// long enough, the evaluation of the current position is fetched, whose principal variations
+/// are the first candidates for the opponent's reply. They are preceded by replies announced with
+/// [CloudEvalPrefetcher::expect_replies], e.g. from the engine's MultiPV search, and followed by
+/// the moves of the opening book, if any. The evaluations after the replies are fetched one at a
+/// time with a pause between requests, until the estimated think time is over or the opponent
+/// moved.
+pub struct CloudEvalPrefetcher {
+    cache: Arc<ResponseCache<Option<CloudEvaluation>>>,
+    book: Option<Arc<dyn OpeningBook>>,
+    max_replies: usize,
+    multi_pv: u32,
+    request_spacing: Duration,
+    min_think_time: Duration,
+    expected_replies: Mutex<HashMap<GameId, Vec<UciMove>>>,
+    tasks: Mutex<HashMap<GameId, JoinHandle<()>>>
+}
+
+impl CloudEvalPrefetcher {
+
+    /// Creates a new prefetcher which stores the evaluations in the given cache. By default, it
+    /// prefetches the evaluations after up to 3 replies with 3 principal variations each, pauses
+    /// 250 milliseconds between requests, and only prefetches if the opponent is expected to
+    /// think for at least 2 seconds.
+    pub fn new(cache: Arc<ResponseCache<Option<CloudEvaluation>>>) -> CloudEvalPrefetcher {
+        CloudEvalPrefetcher {
+            cache,
+            book: None,
+            max_replies: 3,
+            multi_pv: 3,
+            request_spacing: Duration::from_millis(250),
+            min_think_time: Duration::from_secs(2),
+            expected_replies: Mutex::new(HashMap::new()),
+            tasks: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Sets an opening book whose moves are considered as likely replies of the opponent. The
+    /// prefetcher is returned for chaining.
+    pub fn with_book(mut self, book: Arc<dyn OpeningBook>) -> CloudEvalPrefetcher {
+        self.book = Some(book);
+        self
+    }
+
+    /// Sets the maximum number of replies of the opponent after which the evaluation is
+    /// prefetched in each turn. The prefetcher is returned for chaining.
+    pub fn with_max_replies(mut self, max_replies: usize) -> CloudEvalPrefetcher {
+        self.max_replies = max_replies;
+        self
+    }
+
+    /// Sets the number of principal variations requested for every evaluation. The prefetcher is
+    /// returned for chaining.
+    pub fn with_multi_pv(mut self, multi_pv: u32) -> CloudEvalPrefetcher {
+        self.multi_pv = multi_pv;
+        self
+    }
+
+    /// Sets the pause between two prefetch requests, which keeps the prefetcher from exhausting
+    /// the rate limit of the cloud evaluation. The prefetcher is returned for chaining.
+    pub fn with_request_spacing(mut self, request_spacing: Duration) -> CloudEvalPrefetcher {
+        self.request_spacing = request_spacing;
+        self
+    }
+
+    /// Sets the minimum estimated think time of the opponent for which evaluations are
+    /// prefetched, e.g. to skip bullet games in which the opponent premoves. The prefetcher is
+    /// returned for chaining.
+    pub fn with_min_think_time(mut self, min_think_time: Duration) -> CloudEvalPrefetcher {
+        self.min_think_time = min_think_time;
+        self
+    }
+
+    /// The cache in which the evaluations are stored.
+    pub fn cache(&self) -> &Arc<ResponseCache<Option<CloudEvaluation>>> {
+        &self.cache
+    }
+
+    /// Gets the cloud evaluation of the given position from the cache or, if it was not
+    /// prefetched, from Lichess using [BotClient::get_cloud_eval].
+    ///
+    /// # Returns
+    ///
+    /// The [CloudEvaluation] of the position, or [None] if the position is not in the cloud
+    /// evaluation database.
+    pub async fn cloud_eval(&self, client: &BotClient, position: &Position)
+            -> LibotResult<Option<CloudEvaluation>> {
+        let fen = position.to_fen();
+
+        self.cache.get_or_fetch(&fen, client.get_cloud_eval(&fen, self.multi_pv)).await
+    }
+
+    /// Announces replies of the opponent which the bot expects in the given game, e.g. the second
+    /// moves of the principal variations of a MultiPV search of the bot's engine. They are
+    /// prefetched first in the opponent's next turn.
+    pub fn expect_replies(&self, game_id: &GameId, replies: impl IntoIterator<Item = UciMove>) {
+        let replies = replies.into_iter().collect();
+
+        self.expected_replies.lock().unwrap().insert(game_id.clone(), replies);
+    }
+
+    /// Orders the candidates for the opponent's reply in the given position by likelihood.
+    pub(crate) fn predict_replies(&self, position: &Position, expected: Vec<UciMove>,
+            evaluation: Option<&CloudEvaluation>) -> Vec<UciMove> {
+        let evaluation_replies = evaluation.into_iter()
+            .flat_map(|evaluation| &evaluation.pvs)
+            .filter_map(|line| line.moves.split_whitespace().next()?.parse().ok());
+        let mut book_entries = self.book.iter()
+            .flat_map(|book| book.entries(position))
+            .collect::<Vec<_>>();
+        book_entries.sort_by_key(|entry| Reverse(entry.weight));
+
+        let legal_moves = position.legal_moves();
+        let mut replies = Vec::new();
+        let candidates = expected.into_iter()
+            .chain(evaluation_replies)
+            .chain(book_entries.into_iter().map(|entry| entry.mov));
+
+        for reply in candidates {
+            if replies.len() >= self.max_replies {
+                break;
+            }
+
+            if legal_moves.contains(&reply) && !replies.contains(&reply) {
+                replies.push(reply);
+            }
+        }
+
+        replies
+    }
+
+    /// Prefetches the evaluations after the likely replies in the given position until the given
+    /// deadline.
+    pub(crate) async fn prefetch(&self, client: &BotClient, game_id: &GameId, position: Position,
+            deadline: Instant) {
+        let expected = self.expected_replies.lock().unwrap().remove(game_id).unwrap_or_default();
+
+        // Failed prefetches are not cached, so the bot fetches the evaluation again if needed.
+        let evaluation = self.cloud_eval(client, &position).await.ok().flatten();
+
+        for reply in self.predict_replies(&position, expected, evaluation.as_ref()) {
+            if Instant::now() + self.request_spacing >= deadline {
+                break;
+            }
+
+            tokio::time::sleep(self.request_spacing).await;
+
+            let mut position = position.clone();
+
+            if position.play(&reply).is_ok() {
+                let _ = self.cloud_eval(client, &position).await;
+            }
+        }
+    }
+
+    /// Handles the given game state, i.e. stops prefetching for the previous turn and, if it is
+    /// the opponent's turn and it is expected to think long enough, starts prefetching for this
+    /// one.
+    pub(crate) fn game_state(self: &Arc<Self>, client: &BotClient, game_context: &GameContext,
+            state: &GameStateEvent) {
+        let game_id = &game_context.info.id;
+        self.stop(game_id);
+
+        let Some(bot_color) = game_context.bot_color
+        else {
+            return;
+        };
+
+        if !state.status.is_running() || game_context.is_my_turn(state) {
+            return;
+        }
+
+        let think_time = think_time(state, bot_color.opposite());
+
+        if think_time < self.min_think_time {
+            return;
+        }
+
+        let Some(position) = game_context.position()
+        else {
+            return;
+        };
+        let prefetcher = Arc::clone(self);
+        let client = client.clone();
+        let task_game_id = game_id.clone();
+        let deadline = Instant::now() + think_time;
+        let task = tokio::spawn(async move {
+            prefetcher.prefetch(&client, &task_game_id, position, deadline).await
+        });
+
+        self.tasks.lock().unwrap().insert(game_id.clone(), task);
+    }
+
+    fn stop(&self, game_id: &GameId) {
+        if let Some(task) = self.tasks.lock().unwrap().remove(game_id) {
+            task.abort();
+        }
+    }
+
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.stop(game_id);
+        self.expected_replies.lock().unwrap().remove(game_id);
+    }
+}
+
+/// Estimates the time the player of the given color will think about its next move in the given
+/// game state, assuming it spreads its remaining clock evenly over [EXPECTED_REMAINING_MOVES]
+/// moves and additionally uses its increment.
+pub(crate) fn think_time(state: &GameStateEvent, color: Color) -> Duration {
+    let (time, increment) = match color {
+        Color::White => (state.white_time, state.white_increment),
+        Color::Black => (state.black_time, state.black_increment)
+    };
+    let millis = time.max(0) / EXPECTED_REMAINING_MOVES as i64 + increment.max(0);
+
+    Duration::from_millis(millis as u64)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use wiremock::{Mock, ResponseTemplate};
+    use wiremock::matchers::{method, path, query_param};
+
+    use crate::book::BookEntry;
+    use crate::test_util;
+
+    use super::*;
+
+    const AFTER_E4_FEN: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+
+    struct FixedBook(Vec<BookEntry>);
+
+    impl OpeningBook for FixedBook {
+        fn entries(&self, _: &Position) -> Vec<BookEntry> {
+            self.0.clone()
+        }
+    }
+
+    fn book_entry(mov: &str, weight: u32) -> BookEntry {
+        BookEntry {
+            mov: mov.parse().unwrap(),
+            weight,
+            wins: 0,
+            draws: 0,
+            losses: 0
+        }
+    }
+
+    fn mov(uci: &str) -> UciMove {
+        uci.parse().unwrap()
+    }
+
+    fn prefetcher() -> CloudEvalPrefetcher {
+        CloudEvalPrefetcher::new(Arc::new(ResponseCache::new(Duration::from_secs(60))))
+    }
+
+    fn evaluation(lines: &[&str]) -> CloudEvaluation {
+        let pvs = lines.iter()
+            .map(|moves| serde_json::json!({ "moves": moves, "cp": 0 }))
+            .collect::<Vec<_>>();
+
+        serde_json::from_value(serde_json::json!({
+            "fen": AFTER_E4_FEN,
+            "knodes": 1000,
+            "depth": 30,
+            "pvs": pvs
+        })).unwrap()
+    }
+
+    fn game_state(white_time: i64, black_time: i64, increment: i64) -> GameStateEvent {
+        serde_json::from_value(serde_json::json!({
+            "type": "gameState",
+            "moves": "e2e4",
+            "wtime": white_time,
+            "btime": black_time,
+            "winc": increment,
+            "binc": increment,
+            "status": "started"
+        })).unwrap()
+    }
+
+    #[test]
+    fn replies_are_ordered_by_source_and_deduplicated() {
+        let prefetcher = prefetcher()
+            .with_max_replies(4)
+            .with_book(Arc::new(FixedBook(vec![
+                book_entry("e7e6", 1),
+                book_entry("d7d5", 10),
+                book_entry("c7c5", 5)
+            ])));
+        let position = Position::from_fen(AFTER_E4_FEN).unwrap();
+        let evaluation = evaluation(&["c7c5 g1f3", "e7e5 g1f3"]);
+
+        let replies =
+            prefetcher.predict_replies(&position, vec![mov("g8f6")], Some(&evaluation));
+
+        assert_that!(replies).contains_exactly_in_given_order(
+            [mov("g8f6"), mov("c7c5"), mov("e7e5"), mov("d7d5")]);
+    }
+
+    #[test]
+    fn illegal_replies_are_ignored() {
+        let prefetcher = prefetcher();
+        let position = Position::from_fen(AFTER_E4_FEN).unwrap();
+
+        let replies = prefetcher.predict_replies(&position, vec![mov("e2e4"), mov("e7e5")], None);
+
+        assert_that!(replies).contains_exactly_in_given_order([mov("e7e5")]);
+    }
+
+    #[rstest]
+    #[case::white(Color::White, 3000 + 2000)]
+    #[case::black(Color::Black, 6000 + 2000)]
+    fn think_time_is_estimated_from_clock(#[case] color: Color, #[case] expected_millis: u64) {
+        let state = game_state(90_000, 180_000, 2000);
+
+        assert_that!(think_time(&state, color)).is_equal_to(Duration::from_millis(expected_millis));
+    }
+
+    #[test]
+    fn evaluations_after_likely_replies_are_prefetched() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let prefetcher = prefetcher().with_request_spacing(Duration::from_millis(1));
+            let game_id = "testGameId".to_owned();
+
+            Mock::given(method("GET"))
+                .and(path("/cloud-eval"))
+                .and(query_param("fen", AFTER_E4_FEN))
+                .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                    r#"{"fen":"","knodes":1000,"depth":30,"#,
+                    r#""pvs":[{"moves":"c7c5 g1f3","cp":30}]}"#)))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/cloud-eval"))
+                .respond_with(ResponseTemplate::new(404))
+                .expect(2)
+                .mount(&server)
+                .await;
+
+            prefetcher.expect_replies(&game_id, [mov("e7e5")]);
+
+            let position = Position::from_fen(AFTER_E4_FEN).unwrap();
+            let deadline = Instant::now() + Duration::from_secs(10);
+            prefetcher.prefetch(&client, &game_id, position.clone(), deadline).await;
+
+            let mut after_c5 = position.clone();
+            after_c5.play(&mov("c7c5")).unwrap();
+
+            assert_that!(prefetcher.cache().stats().entries).is_equal_to(3);
+            assert_that!(prefetcher.cloud_eval(&client, &after_c5).await).contains_value(None);
+            assert_that!(prefetcher.cache().stats().hits).is_equal_to(1);
+        });
+    }
+
+    #[test]
+    fn prefetching_stops_at_deadline() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let prefetcher = prefetcher().with_request_spacing(Duration::from_secs(1));
+            let game_id = "testGameId".to_owned();
+
+            Mock::given(method("GET"))
+                .and(path("/cloud-eval"))
+                .respond_with(ResponseTemplate::new(404))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            prefetcher.expect_replies(&game_id, [mov("e7e5")]);
+
+            let position = Position::from_fen(AFTER_E4_FEN).unwrap();
+            let deadline = Instant::now() + Duration::from_millis(500);
+            prefetcher.prefetch(&client, &game_id, position, deadline).await;
+
+            assert_that!(prefetcher.cache().stats().entries).is_equal_to(1);
+        });
+    }
+}
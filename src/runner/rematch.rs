@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::model::challenge::{ChallengeBuilder, ChallengeColor, ChallengeRequest};
+use crate::model::game::{Color, GameId, GameInfo, Variant};
+use crate::model::user::UserId;
+
+/// Configuration of the automatic rematches of a [BotRunner](crate::runner::BotRunner). After a
+/// game against a Lichess user finishes, the runner offers a rematch with the same settings and
+/// swapped colors, and accepts rematches offered by the opponent, until the bot played the
+/// configured number of games in a row against that opponent. Further rematches are declined with
+/// [DeclineReason::Later](crate::model::challenge::DeclineReason::Later) until the cooldown has
+/// passed since the last game against the opponent, after which a new series may start. Set using
+/// [BotRunner::with_rematches](crate::runner::BotRunner::with_rematches).
+///
+/// Accepted rematches bypass the challenge queue, since they replace the game which just finished.
+/// Rematches which are neither accepted nor declined are passed to
+/// [Bot::on_rematch_offer](crate::Bot::on_rematch_offer).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RematchConfig {
+    pub(crate) max_games: u32,
+    pub(crate) offer: bool,
+    pub(crate) accept: bool,
+    pub(crate) offer_delay: Duration,
+    pub(crate) cooldown: Duration
+}
+
+impl RematchConfig {
+
+    /// Creates a new configuration which both offers and accepts rematches, waits 5 seconds
+    /// before offering one, and has a cooldown of 10 minutes.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_games`: The maximum number of games in a row against the same opponent, including
+    ///   the first one.
+    pub fn new(max_games: u32) -> RematchConfig {
+        RematchConfig {
+            max_games,
+            offer: true,
+            accept: true,
+            offer_delay: Duration::from_secs(5),
+            cooldown: Duration::from_secs(600)
+        }
+    }
+
+    /// Sets whether the bot offers rematches itself by challenging the opponent. The
+    /// configuration is returned for chaining.
+    pub fn with_offers(mut self, offer: bool) -> RematchConfig {
+        self.offer = offer;
+        self
+    }
+
+    /// Sets whether rematches offered by the opponent are accepted automatically. The
+    /// configuration is returned for chaining.
+    pub fn with_acceptance(mut self, accept: bool) -> RematchConfig {
+        self.accept = accept;
+        self
+    }
+
+    /// Sets the time to wait after a game finished before offering a rematch. If the opponent
+    /// offers a rematch in the meantime, the bot does not offer one itself. The configuration is
+    /// returned for chaining.
+    pub fn with_offer_delay(mut self, offer_delay: Duration) -> RematchConfig {
+        self.offer_delay = offer_delay;
+        self
+    }
+
+    /// Sets the time after the last game against an opponent after which a new series against
+    /// them starts, i.e. the number of games in a row is reset. The configuration is returned for
+    /// chaining.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> RematchConfig {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+/// What the runner does with an incoming rematch challenge.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum RematchDecision {
+    Accept,
+    Decline,
+    Pass
+}
+
+#[derive(Debug)]
+struct Series {
+    games: u32,
+    last_game: GameId,
+    finished_at: Instant,
+    offered_by_opponent: bool
+}
+
+#[derive(Debug)]
+pub(crate) struct RematchTracker {
+    config: RematchConfig,
+    series: Mutex<HashMap<UserId, Series>>
+}
+
+impl RematchTracker {
+
+    pub(crate) fn new(config: RematchConfig) -> RematchTracker {
+        RematchTracker {
+            config,
+            series: Mutex::new(HashMap::new())
+        }
+    }
+
+    pub(crate) fn offer_delay(&self) -> Duration {
+        self.config.offer_delay
+    }
+
+    /// Records that the given game against the given opponent finished at the given instant.
+    ///
+    /// # Returns
+    ///
+    /// `true` if and only if the bot should offer a rematch after the offer delay.
+    pub(crate) fn game_finished(&self, opponent: &UserId, game_id: &GameId, now: Instant) -> bool {
+        let mut series = self.series.lock().unwrap();
+        let cooldown = self.config.cooldown;
+        let games = series.get(opponent)
+            .filter(|series| now.saturating_duration_since(series.finished_at) < cooldown)
+            .map_or(1, |series| series.games + 1);
+
+        series.insert(opponent.clone(), Series {
+            games,
+            last_game: game_id.clone(),
+            finished_at: now,
+            offered_by_opponent: false
+        });
+
+        self.config.offer && games < self.config.max_games
+    }
+
+    /// Indicates whether a rematch of the given game should still be offered after the offer
+    /// delay, i.e. no later game against the opponent finished and the opponent did not offer a
+    /// rematch in the meantime.
+    pub(crate) fn should_offer(&self, opponent: &UserId, game_id: &GameId) -> bool {
+        self.series.lock().unwrap().get(opponent)
+            .is_some_and(|series| &series.last_game == game_id && !series.offered_by_opponent)
+    }
+
+    /// Decides what to do with a rematch of the given game offered by the given challenger.
+    /// Rematches of games other than the last one against the challenger are passed on.
+    pub(crate) fn rematch_offered(&self, challenger: &UserId, rematch_of: &GameId)
+            -> RematchDecision {
+        let mut series = self.series.lock().unwrap();
+
+        let Some(series) = series.get_mut(challenger)
+            .filter(|series| &series.last_game == rematch_of)
+        else {
+            return RematchDecision::Pass;
+        };
+
+        series.offered_by_opponent = true;
+
+        match (self.config.accept, series.games < self.config.max_games) {
+            (false, _) => RematchDecision::Pass,
+            (true, true) => RematchDecision::Accept,
+            (true, false) => RematchDecision::Decline
+        }
+    }
+}
+
+/// Gets the opponent of the bot with the given ID in the given game, if it is a Lichess user.
+pub(crate) fn opponent(info: &GameInfo, bot_id: &str) -> Option<(Color, UserId)> {
+    let color = info.color_of(bot_id)?;
+    let opponent = info.player_ref(color.opposite()).id()?.to_owned();
+
+    Some((color, opponent))
+}
+
+/// Builds a challenge for a rematch of the given game, in which the bot played the given color.
+/// Colors are swapped and all other settings are kept.
+pub(crate) fn rematch_request(info: &GameInfo, color: Color) -> Option<ChallengeRequest> {
+    let color = match color.opposite() {
+        Color::White => ChallengeColor::White,
+        Color::Black => ChallengeColor::Black
+    };
+    let variant = info.variant_or_standard();
+    let mut builder = ChallengeBuilder::new()
+        .with_rated(info.rated)
+        .with_color(color)
+        .with_variant(variant);
+
+    if let Some(clock) = info.clock {
+        builder = builder.with_clock(clock.limit?, clock.increment?);
+    }
+    else if let Some(days) = info.days_per_turn {
+        builder = builder.with_days(days);
+    }
+
+    if variant == Variant::FromPosition {
+        builder = builder.with_fen(info.initial_fen.clone());
+    }
+
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn opponent_id() -> UserId {
+        "opponent".to_owned()
+    }
+
+    fn game_id(index: u32) -> GameId {
+        format!("game{index}")
+    }
+
+    fn tracker(max_games: u32) -> RematchTracker {
+        RematchTracker::new(RematchConfig::new(max_games).with_cooldown(Duration::from_secs(60)))
+    }
+
+    #[rstest]
+    #[case::first_game(1, true)]
+    #[case::second_game(2, true)]
+    #[case::last_game(3, false)]
+    fn rematch_is_offered_until_max_games(#[case] games: u32, #[case] expected: bool) {
+        let tracker = tracker(3);
+        let now = Instant::now();
+
+        for index in 1..games {
+            tracker.game_finished(&opponent_id(), &game_id(index), now);
+        }
+
+        assert_that!(tracker.game_finished(&opponent_id(), &game_id(games), now))
+            .is_equal_to(expected);
+    }
+
+    #[test]
+    fn series_restarts_after_cooldown() {
+        let tracker = tracker(2);
+        let now = Instant::now();
+
+        tracker.game_finished(&opponent_id(), &game_id(1), now);
+        tracker.game_finished(&opponent_id(), &game_id(2), now);
+
+        let later = now + Duration::from_secs(61);
+
+        assert_that!(tracker.game_finished(&opponent_id(), &game_id(3), later)).is_true();
+    }
+
+    #[test]
+    fn rematches_are_not_offered_if_disabled() {
+        let tracker = RematchTracker::new(RematchConfig::new(3).with_offers(false));
+
+        assert_that!(tracker.game_finished(&opponent_id(), &game_id(1), Instant::now()))
+            .is_false();
+    }
+
+    #[test]
+    fn rematch_offered_by_opponent_is_accepted_and_suppresses_own_offer() {
+        let tracker = tracker(3);
+        tracker.game_finished(&opponent_id(), &game_id(1), Instant::now());
+
+        assert_that!(tracker.rematch_offered(&opponent_id(), &game_id(1)))
+            .is_equal_to(RematchDecision::Accept);
+        assert_that!(tracker.should_offer(&opponent_id(), &game_id(1))).is_false();
+    }
+
+    #[test]
+    fn rematch_after_last_game_of_series_is_declined() {
+        let tracker = tracker(2);
+        let now = Instant::now();
+        tracker.game_finished(&opponent_id(), &game_id(1), now);
+        tracker.game_finished(&opponent_id(), &game_id(2), now);
+
+        assert_that!(tracker.rematch_offered(&opponent_id(), &game_id(2)))
+            .is_equal_to(RematchDecision::Decline);
+    }
+
+    #[rstest]
+    #[case::unknown_game("otherGame", RematchConfig::new(3))]
+    #[case::acceptance_disabled("game1", RematchConfig::new(3).with_acceptance(false))]
+    fn rematch_is_passed_on(#[case] rematch_of: &str, #[case] config: RematchConfig) {
+        let tracker = RematchTracker::new(config);
+        tracker.game_finished(&opponent_id(), &game_id(1), Instant::now());
+
+        assert_that!(tracker.rematch_offered(&opponent_id(), &rematch_of.to_owned()))
+            .is_equal_to(RematchDecision::Pass);
+    }
+
+    fn game_info(clock: &str) -> GameInfo {
+        serde_json::from_str(&format!(r#"{{
+            "id": "game1",
+            "variant": {{ "key": "chess960" }},
+            "clock": {clock},
+            "speed": "blitz",
+            "perf": {{ }},
+            "rated": true,
+            "createdAt": 1234,
+            "white": {{ "id": "testbot" }},
+            "black": {{ "id": "opponent" }},
+            "initialFen": "startpos",
+            "daysPerTurn": 3
+        }}"#)).unwrap()
+    }
+
+    #[test]
+    fn opponent_is_other_player() {
+        let info = game_info("null");
+
+        assert_that!(opponent(&info, "testbot")).contains((Color::White, opponent_id()));
+        assert_that!(opponent(&info, "stranger")).is_none();
+    }
+
+    #[rstest]
+    #[case::real_time(r#"{ "initial": 180000, "increment": 2000 }"#,
+        ChallengeBuilder::new().with_clock(180, 2))]
+    #[case::correspondence("null", ChallengeBuilder::new().with_days(3))]
+    fn rematch_request_keeps_settings_and_swaps_colors(#[case] clock: &str,
+            #[case] expected: ChallengeBuilder) {
+        let expected = expected
+            .with_rated(true)
+            .with_color(ChallengeColor::Black)
+            .with_variant(Variant::Chess960)
+            .build()
+            .unwrap();
+
+        assert_that!(rematch_request(&game_info(clock), Color::White)).contains(expected);
+    }
+
+    #[test]
+    fn rematch_is_not_offered_after_later_game() {
+        let tracker = tracker(5);
+        let now = Instant::now();
+        tracker.game_finished(&opponent_id(), &game_id(1), now);
+        tracker.game_finished(&opponent_id(), &game_id(2), now);
+
+        assert_that!(tracker.should_offer(&opponent_id(), &game_id(1))).is_false();
+        assert_that!(tracker.should_offer(&opponent_id(), &game_id(2))).is_true();
+    }
+}
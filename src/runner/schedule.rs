@@ -0,0 +1,315 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const MINUTES_PER_WEEK: u32 = 7 * MINUTES_PER_DAY;
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// The index of the weekday of the Unix epoch, a Thursday, in [DAY_NAMES].
+const EPOCH_WEEKDAY: u64 = 3;
+
+/// An error which occurs when parsing a [TimeWindow] from a specification.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ScheduleParseError {
+
+    /// The specification does not consist of an optional day list and a time range.
+    #[error("invalid time window specification: {0:?}")]
+    InvalidFormat(String),
+
+    /// A day in the day list is not a known weekday (`Mon` to `Sun`) or `*`.
+    #[error("unknown weekday: {0:?}")]
+    UnknownDay(String),
+
+    /// A time of the time range is not of the form `HH:MM` or out of range.
+    #[error("invalid time of day: {0:?}")]
+    InvalidTime(String)
+}
+
+/// A recurring weekly time window in UTC, such as the active hours of a bot or a maintenance
+/// window. Time windows are parsed from cron-like specifications consisting of an optional list
+/// of weekdays and a time range, for example:
+///
+/// * `Mon-Fri 08:00-22:00`: Mondays to Fridays from 8 AM to 10 PM.
+/// * `Sat,Sun 10:00-14:00`: On weekends from 10 AM to 2 PM.
+/// * `* 22:00-02:00`: Every day from 10 PM to 2 AM of the next day.
+/// * `03:00-04:00`: Every day from 3 AM to 4 AM.
+///
+/// Weekdays are given by their three-letter English abbreviation, case-insensitively, or `*` for
+/// all days. If the end of the time range is not after its start, the window extends into the
+/// next day, so `00:00-00:00` covers entire days. The end `24:00` denotes midnight at the end of
+/// the day.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TimeWindow {
+    days: [bool; 7],
+    start: u32,
+    end: u32
+}
+
+impl TimeWindow {
+
+    /// Determines whether the given time lies within this window.
+    pub fn contains(&self, time: SystemTime) -> bool {
+        let minute_of_week = minute_of_week(time);
+        let length = if self.end > self.start {
+            self.end - self.start
+        }
+        else {
+            self.end + MINUTES_PER_DAY - self.start
+        };
+
+        self.days.iter()
+            .enumerate()
+            .filter(|(_, &active)| active)
+            .any(|(day, _)| {
+                let start = day as u32 * MINUTES_PER_DAY + self.start;
+                let offset = (minute_of_week + MINUTES_PER_WEEK - start) % MINUTES_PER_WEEK;
+
+                offset < length
+            })
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = ScheduleParseError;
+
+    fn from_str(spec: &str) -> Result<TimeWindow, ScheduleParseError> {
+        let parts = spec.split_whitespace().collect::<Vec<_>>();
+        let (days, range) = match parts.as_slice() {
+            [range] => ([true; 7], *range),
+            [days, range] => (parse_days(days)?, *range),
+            _ => return Err(ScheduleParseError::InvalidFormat(spec.to_owned()))
+        };
+        let (start, end) = range.split_once('-')
+            .ok_or_else(|| ScheduleParseError::InvalidFormat(spec.to_owned()))?;
+
+        Ok(TimeWindow {
+            days,
+            start: parse_time(start)? % MINUTES_PER_DAY,
+            end: parse_time(end)?
+        })
+    }
+}
+
+impl Display for TimeWindow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.days == [true; 7] {
+            write!(f, "*")?;
+        }
+        else {
+            let days = self.days.iter()
+                .zip(DAY_NAMES)
+                .filter(|(&active, _)| active)
+                .map(|(_, name)| name)
+                .collect::<Vec<_>>();
+
+            write!(f, "{}", days.join(","))?;
+        }
+
+        write!(f, " {:02}:{:02}-{:02}:{:02}",
+            self.start / 60, self.start % 60, self.end / 60, self.end % 60)
+    }
+}
+
+fn parse_day(day: &str) -> Result<usize, ScheduleParseError> {
+    DAY_NAMES.iter()
+        .position(|name| name.eq_ignore_ascii_case(day))
+        .ok_or_else(|| ScheduleParseError::UnknownDay(day.to_owned()))
+}
+
+fn parse_days(spec: &str) -> Result<[bool; 7], ScheduleParseError> {
+    if spec == "*" {
+        return Ok([true; 7]);
+    }
+
+    let mut days = [false; 7];
+
+    for item in spec.split(',') {
+        match item.split_once('-') {
+            Some((first, last)) => {
+                let first = parse_day(first)?;
+                let last = parse_day(last)?;
+                let count = (last + 7 - first) % 7 + 1;
+
+                for offset in 0..count {
+                    days[(first + offset) % 7] = true;
+                }
+            },
+            None => days[parse_day(item)?] = true
+        }
+    }
+
+    Ok(days)
+}
+
+fn parse_time(time: &str) -> Result<u32, ScheduleParseError> {
+    let invalid = || ScheduleParseError::InvalidTime(time.to_owned());
+    let (hours, minutes) = time.split_once(':').ok_or_else(invalid)?;
+    let hours = hours.parse::<u32>().map_err(|_| invalid())?;
+    let minutes = minutes.parse::<u32>().map_err(|_| invalid())?;
+
+    if minutes >= 60 || hours > 24 || (hours == 24 && minutes > 0) {
+        return Err(invalid());
+    }
+
+    Ok(hours * 60 + minutes)
+}
+
+fn minute_of_week(time: SystemTime) -> u32 {
+    let minutes = time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 60)
+        .unwrap_or(0);
+    let day = (minutes / MINUTES_PER_DAY as u64 + EPOCH_WEEKDAY) % 7;
+
+    (day * MINUTES_PER_DAY as u64 + minutes % MINUTES_PER_DAY as u64) as u32
+}
+
+/// A schedule which determines when a [BotRunner](crate::runner::BotRunner) accepts challenges.
+/// The runner is active if the current time lies within any of the active hours, or if no active
+/// hours are configured, and outside all maintenance windows. While the runner is inactive,
+/// incoming challenges are declined with
+/// [DeclineReason::Later](crate::model::challenge::DeclineReason::Later), but ongoing games are
+/// played until they finish. Register a schedule with
+/// [BotRunner::with_schedule](crate::runner::BotRunner::with_schedule).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ChallengeSchedule {
+    active_hours: Vec<TimeWindow>,
+    maintenance_windows: Vec<TimeWindow>
+}
+
+impl ChallengeSchedule {
+
+    /// Creates a new schedule without any active hours or maintenance windows, i.e. one which is
+    /// always active.
+    pub fn new() -> ChallengeSchedule {
+        ChallengeSchedule::default()
+    }
+
+    /// Adds the given [TimeWindow] to the active hours of this schedule. Once any active hours
+    /// are configured, the schedule is inactive outside of them. The schedule is returned for
+    /// chaining.
+    pub fn with_active_hours(mut self, window: TimeWindow) -> ChallengeSchedule {
+        self.active_hours.push(window);
+        self
+    }
+
+    /// Adds the given [TimeWindow] as a maintenance window, during which the schedule is inactive
+    /// regardless of the active hours. The schedule is returned for chaining.
+    pub fn with_maintenance_window(mut self, window: TimeWindow) -> ChallengeSchedule {
+        self.maintenance_windows.push(window);
+        self
+    }
+
+    /// Determines whether this schedule is active, i.e. new challenges are accepted, at the given
+    /// time.
+    pub fn is_active_at(&self, time: SystemTime) -> bool {
+        let in_active_hours = self.active_hours.is_empty() ||
+            self.active_hours.iter().any(|window| window.contains(time));
+
+        in_active_hours && !self.maintenance_windows.iter().any(|window| window.contains(time))
+    }
+
+    /// Determines whether this schedule is currently active (see
+    /// [ChallengeSchedule::is_active_at]).
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(SystemTime::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    /// Monday, 2024-01-01, 00:00 UTC.
+    const MONDAY: u64 = 1_704_067_200;
+
+    fn time(day: u64, hours: u64, minutes: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(MONDAY + ((day * 24 + hours) * 60 + minutes) * 60)
+    }
+
+    fn window(spec: &str) -> TimeWindow {
+        spec.parse().unwrap()
+    }
+
+    #[rstest]
+    #[case::inside(0, 12, 0, true)]
+    #[case::at_start(4, 8, 0, true)]
+    #[case::at_end(4, 22, 0, false)]
+    #[case::before_start(2, 7, 59, false)]
+    #[case::weekend(5, 12, 0, false)]
+    fn weekday_window(#[case] day: u64, #[case] hours: u64, #[case] minutes: u64,
+            #[case] expected: bool) {
+        assert_that!(window("Mon-Fri 08:00-22:00").contains(time(day, hours, minutes)))
+            .is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::evening(6, 23, 0, true)]
+    #[case::after_midnight_into_monday(0, 1, 30, true)]
+    #[case::after_midnight_of_monday(1, 1, 30, false)]
+    #[case::afternoon(6, 15, 0, false)]
+    fn window_crossing_midnight(#[case] day: u64, #[case] hours: u64, #[case] minutes: u64,
+            #[case] expected: bool) {
+        assert_that!(window("Sun 22:00-02:00").contains(time(day, hours, minutes)))
+            .is_equal_to(expected);
+    }
+
+    #[test]
+    fn whole_day_window_contains_entire_day() {
+        let window = window("sat 00:00-00:00");
+
+        assert_that!(window.contains(time(5, 0, 0))).is_true();
+        assert_that!(window.contains(time(5, 23, 59))).is_true();
+        assert_that!(window.contains(time(6, 0, 0))).is_false();
+    }
+
+    #[test]
+    fn day_range_wraps_around_week() {
+        assert_that!(window("Sat-Mon 00:00-24:00")).is_equal_to(window("sat,sun,mon 00:00-24:00"));
+    }
+
+    #[rstest]
+    #[case::missing_range("Mon-Fri", ScheduleParseError::InvalidTime("Mon".to_owned()))]
+    #[case::too_many_parts("Mon 08:00-10:00 x",
+        ScheduleParseError::InvalidFormat("Mon 08:00-10:00 x".to_owned()))]
+    #[case::unknown_day("Mon,Xyz 08:00-10:00", ScheduleParseError::UnknownDay("Xyz".to_owned()))]
+    #[case::invalid_time("08:00-25:00", ScheduleParseError::InvalidTime("25:00".to_owned()))]
+    #[case::invalid_minutes("08:60-10:00", ScheduleParseError::InvalidTime("08:60".to_owned()))]
+    fn invalid_specifications_are_rejected(#[case] spec: &str,
+            #[case] expected: ScheduleParseError) {
+        assert_that!(spec.parse::<TimeWindow>()).contains_error(expected);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let window = window("Mon-Wed,Sat 08:30-24:00");
+
+        assert_that!(window.to_string().as_str()).is_equal_to("mon,tue,wed,sat 08:30-24:00");
+        assert_that!(window.to_string().parse::<TimeWindow>()).contains_value(window);
+    }
+
+    #[test]
+    fn schedule_is_active_within_active_hours_outside_maintenance() {
+        let schedule = ChallengeSchedule::new()
+            .with_active_hours(window("08:00-22:00"))
+            .with_maintenance_window(window("Wed 12:00-13:00"));
+
+        assert_that!(schedule.is_active_at(time(2, 11, 0))).is_true();
+        assert_that!(schedule.is_active_at(time(2, 12, 30))).is_false();
+        assert_that!(schedule.is_active_at(time(2, 23, 0))).is_false();
+    }
+
+    #[test]
+    fn empty_schedule_is_always_active() {
+        assert_that!(ChallengeSchedule::new().is_active_at(time(3, 3, 0))).is_true();
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::model::game::GameId;
+use crate::store::RunnerSnapshot;
+
+/// Records the challenges accepted and the games chatted in by the bot of a runner, so they can
+/// be included in a [RunnerSnapshot] and restored after the process was restarted.
+#[derive(Debug, Default)]
+pub(crate) struct SessionLog {
+    accepted_challenges: Mutex<HashSet<GameId>>,
+    chatted_games: Mutex<HashSet<GameId>>
+}
+
+impl SessionLog {
+
+    /// Restores the accepted challenges and chatted games of the given snapshot.
+    pub(crate) fn restore(&self, snapshot: &RunnerSnapshot) {
+        self.accepted_challenges.lock().unwrap()
+            .extend(snapshot.accepted_challenges.iter().cloned());
+        self.chatted_games.lock().unwrap().extend(snapshot.chatted_games.iter().cloned());
+    }
+
+    pub(crate) fn challenge_accepted(&self, challenge_id: &GameId) {
+        self.accepted_challenges.lock().unwrap().insert(challenge_id.clone());
+    }
+
+    pub(crate) fn was_accepted(&self, challenge_id: &GameId) -> bool {
+        self.accepted_challenges.lock().unwrap().contains(challenge_id)
+    }
+
+    pub(crate) fn chat_sent(&self, game_id: &GameId) {
+        self.chatted_games.lock().unwrap().insert(game_id.clone());
+    }
+
+    pub(crate) fn has_chatted(&self, game_id: &GameId) -> bool {
+        self.chatted_games.lock().unwrap().contains(game_id)
+    }
+
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.accepted_challenges.lock().unwrap().remove(game_id);
+        self.chatted_games.lock().unwrap().remove(game_id);
+    }
+
+    /// Creates a snapshot of this log and the given active games.
+    pub(crate) fn snapshot<'a>(&self, active_games: impl IntoIterator<Item = &'a GameId>)
+            -> RunnerSnapshot {
+        RunnerSnapshot {
+            active_games: active_games.into_iter().cloned().collect(),
+            accepted_challenges: self.accepted_challenges.lock().unwrap().iter().cloned().collect(),
+            chatted_games: self.chatted_games.lock().unwrap().iter().cloned().collect()
+        }
+    }
+}
+
+/// Removes all games from the given snapshot which are not among the given ongoing games, since
+/// they finished while the process was not running.
+pub(crate) fn reconcile(snapshot: RunnerSnapshot, ongoing_games: &HashSet<GameId>)
+        -> RunnerSnapshot {
+    let is_ongoing = |game_id: &GameId| ongoing_games.contains(game_id);
+
+    RunnerSnapshot {
+        active_games: snapshot.active_games.into_iter().filter(is_ongoing).collect(),
+        accepted_challenges: snapshot.accepted_challenges.into_iter().filter(is_ongoing).collect(),
+        chatted_games: snapshot.chatted_games.into_iter().filter(is_ongoing).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn ids(ids: &[&str]) -> HashSet<GameId> {
+        ids.iter().map(|&id| id.to_owned()).collect()
+    }
+
+    #[test]
+    fn finished_games_are_removed_from_log() {
+        let log = SessionLog::default();
+        log.challenge_accepted(&"game1".to_owned());
+        log.chat_sent(&"game1".to_owned());
+        log.chat_sent(&"game2".to_owned());
+
+        log.game_finished(&"game1".to_owned());
+
+        assert_that!(log.was_accepted(&"game1".to_owned())).is_false();
+        assert_that!(log.has_chatted(&"game1".to_owned())).is_false();
+        assert_that!(log.has_chatted(&"game2".to_owned())).is_true();
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_restore() {
+        let log = SessionLog::default();
+        log.challenge_accepted(&"game1".to_owned());
+        log.chat_sent(&"game2".to_owned());
+        let snapshot = log.snapshot(&ids(&["game1", "game2"]));
+
+        let restored = SessionLog::default();
+        restored.restore(&snapshot);
+
+        assert_that!(restored.snapshot(&ids(&["game1", "game2"]))).is_equal_to(snapshot);
+    }
+
+    #[test]
+    fn reconcile_drops_games_which_are_no_longer_ongoing() {
+        let snapshot = RunnerSnapshot {
+            active_games: ["game1", "game2"].map(str::to_owned).into(),
+            accepted_challenges: ["game2"].map(str::to_owned).into(),
+            chatted_games: ["game1", "game2"].map(str::to_owned).into()
+        };
+
+        let reconciled = reconcile(snapshot, &ids(&["game1", "game3"]));
+
+        assert_that!(reconciled).is_equal_to(RunnerSnapshot {
+            active_games: ["game1"].map(str::to_owned).into(),
+            accepted_challenges: Default::default(),
+            chatted_games: ["game1"].map(str::to_owned).into()
+        });
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::model::user::UserId;
+use crate::store::{GameStore, StoreResult, UserLists};
+
+/// Configuration of the challenge spam protection of a [BotRunner](crate::runner::BotRunner).
+/// Challenges of users which sent more than the configured number of challenges within the
+/// configured window are declined with
+/// [DeclineReason::Later](crate::model::challenge::DeclineReason::Later). Users on the allowlist
+/// of the runner (see [BotRunner::allow_user](crate::runner::BotRunner::allow_user)) are exempt.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SpamProtectionConfig {
+    pub(crate) max_challenges: usize,
+    pub(crate) window: Duration
+}
+
+impl SpamProtectionConfig {
+
+    /// Creates a new spam protection configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_challenges`: The maximum number of challenges a user may send within the window.
+    ///   Every further challenge within the window is declined.
+    /// * `window`: The time span over which challenges of a user are counted.
+    pub fn new(max_challenges: usize, window: Duration) -> SpamProtectionConfig {
+        SpamProtectionConfig {
+            max_challenges,
+            window
+        }
+    }
+}
+
+pub(crate) struct ChallengeRateTracker {
+    config: SpamProtectionConfig,
+    recent: HashMap<UserId, VecDeque<Instant>>
+}
+
+impl ChallengeRateTracker {
+
+    pub(crate) fn new(config: SpamProtectionConfig) -> ChallengeRateTracker {
+        ChallengeRateTracker {
+            config,
+            recent: HashMap::new()
+        }
+    }
+
+    /// Records a challenge of the given user at the given instant and returns whether the user
+    /// exceeded the configured rate with it. Declined challenges count as well, so users which keep
+    /// challenging remain declined until they pause for the configured window.
+    pub(crate) fn record(&mut self, challenger: &str, now: Instant) -> bool {
+        let window = self.config.window;
+
+        self.recent.retain(|_, challenges| {
+            while challenges.front()
+                    .is_some_and(|&sent_at| now.saturating_duration_since(sent_at) >= window) {
+                challenges.pop_front();
+            }
+
+            !challenges.is_empty()
+        });
+
+        let challenges = self.recent.entry(challenger.to_owned()).or_default();
+        challenges.push_back(now);
+
+        challenges.len() > self.config.max_challenges
+    }
+}
+
+/// The allowlist and blocklist of challengers of a [BotRunner](crate::runner::BotRunner).
+/// Challenges of blocked users are always declined, while allowed users are exempt from the
+/// [SpamProtectionConfig]. If the runner has a [GameStore], the lists are loaded from it and every
+/// change is saved to it. Obtain the lists with
+/// [BotRunner::challenger_lists](crate::runner::BotRunner::challenger_lists) to change them while
+/// the bot is running.
+#[derive(Default)]
+pub struct ChallengerLists {
+    lists: Mutex<UserLists>,
+    store: Mutex<Option<Arc<dyn GameStore>>>
+}
+
+impl ChallengerLists {
+
+    /// Loads the lists persisted in the given store, adds the users listed so far, and saves all
+    /// future changes to the store. Users listed so far take precedence over the persisted lists.
+    pub(crate) fn attach_store(&self, store: Arc<dyn GameStore>) -> StoreResult<()> {
+        let mut lists = self.lists.lock().unwrap();
+        let mut persisted = store.user_lists()?;
+
+        for user_id in &lists.allowed {
+            persisted.blocked.remove(user_id);
+            persisted.allowed.insert(user_id.clone());
+        }
+
+        for user_id in &lists.blocked {
+            persisted.allowed.remove(user_id);
+            persisted.blocked.insert(user_id.clone());
+        }
+
+        *lists = persisted;
+        *self.store.lock().unwrap() = Some(store);
+
+        self.save(&lists)
+    }
+
+    fn save(&self, lists: &UserLists) -> StoreResult<()> {
+        match self.store.lock().unwrap().as_ref() {
+            Some(store) => store.save_user_lists(lists),
+            None => Ok(())
+        }
+    }
+
+    fn update(&self, update: impl FnOnce(&mut UserLists)) -> StoreResult<()> {
+        let mut lists = self.lists.lock().unwrap();
+        update(&mut lists);
+
+        self.save(&lists)
+    }
+
+    /// Adds the user with the given ID to the blocklist, removing them from the allowlist.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError](crate::store::StoreError) if the lists cannot be saved. The user is
+    /// blocked regardless.
+    pub fn block_user(&self, user_id: impl Into<UserId>) -> StoreResult<()> {
+        let user_id = user_id.into();
+
+        self.update(|lists| {
+            lists.allowed.remove(&user_id);
+            lists.blocked.insert(user_id);
+        })
+    }
+
+    /// Adds the user with the given ID to the allowlist, removing them from the blocklist.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError](crate::store::StoreError) if the lists cannot be saved. The user is
+    /// allowed regardless.
+    pub fn allow_user(&self, user_id: impl Into<UserId>) -> StoreResult<()> {
+        let user_id = user_id.into();
+
+        self.update(|lists| {
+            lists.blocked.remove(&user_id);
+            lists.allowed.insert(user_id);
+        })
+    }
+
+    /// Removes the user with the given ID from both lists.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError](crate::store::StoreError) if the lists cannot be saved. The user is
+    /// removed regardless.
+    pub fn unlist_user(&self, user_id: &str) -> StoreResult<()> {
+        self.update(|lists| {
+            lists.allowed.remove(user_id);
+            lists.blocked.remove(user_id);
+        })
+    }
+
+    /// Indicates whether the user with the given ID is on the allowlist.
+    pub fn is_allowed(&self, user_id: &str) -> bool {
+        self.lists.lock().unwrap().allowed.contains(user_id)
+    }
+
+    /// Indicates whether the user with the given ID is on the blocklist.
+    pub fn is_blocked(&self, user_id: &str) -> bool {
+        self.lists.lock().unwrap().blocked.contains(user_id)
+    }
+
+    /// Gets a snapshot of the current lists.
+    pub fn user_lists(&self) -> UserLists {
+        self.lists.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::store::GameRecord;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct UserListStore(Mutex<UserLists>);
+
+    impl GameStore for UserListStore {
+        fn record_game(&self, _: &GameRecord) -> StoreResult<()> {
+            Ok(())
+        }
+
+        fn games(&self) -> StoreResult<Vec<GameRecord>> {
+            Ok(Vec::new())
+        }
+
+        fn user_lists(&self) -> StoreResult<UserLists> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn save_user_lists(&self, user_lists: &UserLists) -> StoreResult<()> {
+            *self.0.lock().unwrap() = user_lists.clone();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rate_tracker_declines_challenges_exceeding_rate() {
+        let mut tracker =
+            ChallengeRateTracker::new(SpamProtectionConfig::new(2, Duration::from_secs(60)));
+        let start = Instant::now();
+
+        assert_that!(tracker.record("spammer", start)).is_false();
+        assert_that!(tracker.record("spammer", start + Duration::from_secs(1))).is_false();
+        assert_that!(tracker.record("other", start + Duration::from_secs(2))).is_false();
+        assert_that!(tracker.record("spammer", start + Duration::from_secs(3))).is_true();
+    }
+
+    #[test]
+    fn rate_tracker_forgets_challenges_outside_window() {
+        let mut tracker =
+            ChallengeRateTracker::new(SpamProtectionConfig::new(1, Duration::from_secs(60)));
+        let start = Instant::now();
+
+        assert_that!(tracker.record("user", start)).is_false();
+        assert_that!(tracker.record("user", start + Duration::from_secs(30))).is_true();
+        assert_that!(tracker.record("user", start + Duration::from_secs(120))).is_false();
+    }
+
+    #[test]
+    fn user_is_on_at_most_one_list() {
+        let lists = ChallengerLists::default();
+
+        lists.block_user("user").unwrap();
+        lists.allow_user("user").unwrap();
+
+        assert_that!(lists.is_allowed("user")).is_true();
+        assert_that!(lists.is_blocked("user")).is_false();
+
+        lists.unlist_user("user").unwrap();
+
+        assert_that!(lists.user_lists()).is_equal_to(UserLists::default());
+    }
+
+    #[test]
+    fn lists_are_merged_with_and_saved_to_store() {
+        let store = Arc::new(UserListStore::default());
+        store.0.lock().unwrap().blocked.extend(["persisted".to_owned(), "friend".to_owned()]);
+        let lists = ChallengerLists::default();
+        lists.allow_user("friend").unwrap();
+
+        lists.attach_store(Arc::clone(&store) as Arc<dyn GameStore>).unwrap();
+        lists.block_user("spammer").unwrap();
+
+        let saved = store.0.lock().unwrap().clone();
+
+        assert_that!(saved.allowed.clone())
+            .contains_exactly_in_any_order(["friend".to_owned()]);
+        assert_that!(saved.blocked.clone())
+            .contains_exactly_in_any_order(["persisted".to_owned(), "spammer".to_owned()]);
+        assert_that!(lists.user_lists()).is_equal_to(saved);
+    }
+}
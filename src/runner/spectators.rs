@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::model::game::GameId;
+use crate::model::game::chat::{ChatLine, ChatRoom};
+use crate::model::tv::TvChannels;
+
+/// The name of the account which posts system messages in game chats.
+const SYSTEM_USERNAME: &str = "lichess";
+
+/// Indications of how popular a running game of the bot is, available through
+/// [GameContext::spectators](crate::context::GameContext::spectators). Lichess does not report
+/// the number of spectators of a game, so this is derived from Lichess TV and the spectator chat.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Spectators {
+
+    /// The names of the Lichess TV channels on which the game is currently featured, such as
+    /// `bot` or `best`, in alphabetical order.
+    pub tv_channels: Vec<String>,
+
+    /// The number of distinct users who posted in the spectator chat of the game.
+    pub spectator_chatters: u32
+}
+
+impl Spectators {
+
+    /// Indicates whether the game is currently featured on any Lichess TV channel.
+    pub fn is_featured(&self) -> bool {
+        !self.tv_channels.is_empty()
+    }
+
+    /// Indicates whether there is any sign that the game is being watched, i.e. it is featured on
+    /// Lichess TV or someone posted in the spectator chat.
+    pub fn is_watched(&self) -> bool {
+        self.is_featured() || self.spectator_chatters > 0
+    }
+}
+
+/// Tracks the [Spectators] of the running games of the bot. The runner refreshes the featured
+/// games from Lichess TV in the configured interval and reports the chat lines of every game.
+#[derive(Debug)]
+pub(crate) struct SpectatorTracker {
+    refresh_interval: Duration,
+    tv_channels: Mutex<HashMap<GameId, Vec<String>>>,
+    spectator_chatters: Mutex<HashMap<GameId, HashSet<String>>>
+}
+
+impl SpectatorTracker {
+
+    pub(crate) fn new(refresh_interval: Duration) -> SpectatorTracker {
+        SpectatorTracker {
+            refresh_interval,
+            tv_channels: Mutex::new(HashMap::new()),
+            spectator_chatters: Mutex::new(HashMap::new())
+        }
+    }
+
+    pub(crate) fn refresh_interval(&self) -> Duration {
+        self.refresh_interval
+    }
+
+    pub(crate) fn spectators(&self, game_id: &GameId) -> Spectators {
+        let tv_channels = self.tv_channels.lock().unwrap().get(game_id).cloned();
+
+        Spectators {
+            tv_channels: tv_channels.unwrap_or_default(),
+            spectator_chatters: self.spectator_chatters.lock().unwrap().get(game_id)
+                .map_or(0, |chatters| chatters.len() as u32)
+        }
+    }
+
+    /// Replaces the featured games with those of the given channels.
+    pub(crate) fn tv_refreshed(&self, channels: &TvChannels) {
+        let mut tv_channels = HashMap::<GameId, Vec<String>>::new();
+
+        for (channel, game) in channels {
+            tv_channels.entry(game.game_id.clone()).or_default().push(channel.clone());
+        }
+
+        for channels in tv_channels.values_mut() {
+            channels.sort();
+        }
+
+        *self.tv_channels.lock().unwrap() = tv_channels;
+    }
+
+    /// Records the given line posted in the chat of the game with the given ID. Lines in the
+    /// player room and system messages are ignored.
+    pub(crate) fn chat_line(&self, game_id: &GameId, chat_line: &ChatLine) {
+        if chat_line.room != ChatRoom::Spectator ||
+                chat_line.username.eq_ignore_ascii_case(SYSTEM_USERNAME) {
+            return;
+        }
+
+        self.spectator_chatters.lock().unwrap()
+            .entry(game_id.clone())
+            .or_default()
+            .insert(chat_line.username.to_lowercase());
+    }
+
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.tv_channels.lock().unwrap().remove(game_id);
+        self.spectator_chatters.lock().unwrap().remove(game_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn game_id() -> GameId {
+        "testGameId".to_owned()
+    }
+
+    fn chat_line(room: ChatRoom, username: &str) -> ChatLine {
+        ChatLine {
+            room,
+            username: username.to_owned(),
+            text: "hello".to_owned()
+        }
+    }
+
+    fn tv_channels(games: &[(&str, &str)]) -> TvChannels {
+        let channels = games.iter()
+            .map(|(channel, game_id)| (channel.to_string(), serde_json::json!({
+                "user": { "id": "someuser", "name": "SomeUser" },
+                "gameId": game_id,
+                "color": "white"
+            })))
+            .collect::<serde_json::Map<_, _>>();
+
+        serde_json::from_value(channels.into()).unwrap()
+    }
+
+    #[test]
+    fn featured_channels_are_collected_per_game() {
+        let tracker = SpectatorTracker::new(Duration::from_secs(60));
+
+        tracker.tv_refreshed(&tv_channels(
+            &[("bot", "testGameId"), ("best", "testGameId"), ("blitz", "otherGameId")]));
+
+        let spectators = tracker.spectators(&game_id());
+
+        assert_that!(&spectators.tv_channels)
+            .contains_exactly_in_given_order(["best".to_owned(), "bot".to_owned()]);
+        assert_that!(spectators.is_featured()).is_true();
+    }
+
+    #[test]
+    fn game_is_no_longer_featured_after_refresh() {
+        let tracker = SpectatorTracker::new(Duration::from_secs(60));
+
+        tracker.tv_refreshed(&tv_channels(&[("bot", "testGameId")]));
+        tracker.tv_refreshed(&tv_channels(&[]));
+
+        assert_that!(tracker.spectators(&game_id()).is_watched()).is_false();
+    }
+
+    #[test]
+    fn distinct_spectator_chatters_are_counted() {
+        let tracker = SpectatorTracker::new(Duration::from_secs(60));
+
+        tracker.chat_line(&game_id(), &chat_line(ChatRoom::Spectator, "alice"));
+        tracker.chat_line(&game_id(), &chat_line(ChatRoom::Spectator, "Alice"));
+        tracker.chat_line(&game_id(), &chat_line(ChatRoom::Spectator, "bob"));
+        tracker.chat_line(&game_id(), &chat_line(ChatRoom::Spectator, "lichess"));
+        tracker.chat_line(&game_id(), &chat_line(ChatRoom::Player, "carol"));
+
+        let spectators = tracker.spectators(&game_id());
+
+        assert_that!(spectators.spectator_chatters).is_equal_to(2);
+        assert_that!(spectators.is_watched()).is_true();
+        assert_that!(spectators.is_featured()).is_false();
+    }
+
+    #[test]
+    fn finished_game_is_forgotten() {
+        let tracker = SpectatorTracker::new(Duration::from_secs(60));
+        tracker.chat_line(&game_id(), &chat_line(ChatRoom::Spectator, "alice"));
+
+        tracker.game_finished(&game_id());
+
+        assert_that!(tracker.spectators(&game_id())).is_equal_to(Spectators::default());
+    }
+}
@@ -0,0 +1,322 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use reqwest::{Method, Response, StatusCode};
+
+use tokio::sync::Semaphore;
+
+use crate::client::BotClient;
+use crate::error::{LibotRequestError, LibotResult};
+
+/// Configures how the [BotRunner](crate::runner::BotRunner) paces opening the event streams of
+/// games, since Lichess limits how many of them can be opened in a short period of time. This
+/// matters mostly after a restart with many ongoing games, whose streams are all opened at once.
+/// Set using
+/// [BotRunner::with_game_stream_pacing](crate::runner::BotRunner::with_game_stream_pacing).
+///
+/// Consecutive stream opens are spaced out by a fixed spacing plus a random jitter, and at most
+/// the configured number of opens is in flight at the same time. If Lichess answers with
+/// `429 Too Many Requests`, the open is retried after an exponential backoff, during which no
+/// other game streams are opened either.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct GameStreamPacing {
+    pub(crate) spacing: Duration,
+    pub(crate) jitter: Duration,
+    pub(crate) jitter_seed: Option<u64>,
+    pub(crate) max_concurrent_opens: usize,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) max_attempts: u32
+}
+
+impl GameStreamPacing {
+
+    /// Creates a new config with default values, i.e. a spacing of 500 milliseconds with up to
+    /// 250 milliseconds of jitter, at most 2 concurrent opens, and up to 5 attempts per stream
+    /// with a backoff starting at 5 seconds and doubling up to 60 seconds.
+    pub fn new() -> GameStreamPacing {
+        GameStreamPacing {
+            spacing: Duration::from_millis(500),
+            jitter: Duration::from_millis(250),
+            jitter_seed: None,
+            max_concurrent_opens: 2,
+            initial_backoff: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(60),
+            max_attempts: 5
+        }
+    }
+
+    /// Sets the minimum time between opening two consecutive game streams. The config is returned
+    /// for chaining.
+    pub fn with_spacing(mut self, spacing: Duration) -> GameStreamPacing {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the maximum random time which is added to the spacing and the backoff, so that bots
+    /// restarting at the same time do not open their streams in lockstep. The config is returned
+    /// for chaining.
+    pub fn with_jitter(mut self, jitter: Duration) -> GameStreamPacing {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the seed of the random number generator which draws the jitter, so the pacing is
+    /// reproducible. By default, the generator is seeded from the operating system. The config is
+    /// returned for chaining.
+    pub fn with_jitter_seed(mut self, seed: u64) -> GameStreamPacing {
+        self.jitter_seed = Some(seed);
+        self
+    }
+
+    /// Sets the maximum number of game streams which are being opened at the same time, i.e. for
+    /// which no response was received yet. Values below 1 are treated as 1. Streams which are
+    /// already open do not count towards this limit. The config is returned for chaining.
+    pub fn with_max_concurrent_opens(mut self, max_concurrent_opens: usize) -> GameStreamPacing {
+        self.max_concurrent_opens = max_concurrent_opens.max(1);
+        self
+    }
+
+    /// Sets the backoff after the first rate-limited attempt to open a stream and the maximum
+    /// backoff, up to which it doubles with every further rate-limited attempt. The config is
+    /// returned for chaining.
+    pub fn with_backoff(mut self, initial_backoff: Duration, max_backoff: Duration)
+            -> GameStreamPacing {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets the maximum number of attempts to open a single game stream while Lichess answers with
+    /// `429 Too Many Requests`. Values below 1 are treated as 1. The config is returned for
+    /// chaining.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> GameStreamPacing {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+}
+
+impl Default for GameStreamPacing {
+    fn default() -> GameStreamPacing {
+        GameStreamPacing::new()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct GameStreamPacer {
+    config: GameStreamPacing,
+    opening: Semaphore,
+    next_open: Mutex<Instant>,
+    rng: Mutex<StdRng>
+}
+
+impl GameStreamPacer {
+
+    pub(crate) fn new(config: GameStreamPacing) -> GameStreamPacer {
+        let rng = match config.jitter_seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        GameStreamPacer {
+            opening: Semaphore::new(config.max_concurrent_opens),
+            config,
+            next_open: Mutex::new(Instant::now()),
+            rng: Mutex::new(rng)
+        }
+    }
+
+    fn jitter(&self) -> Duration {
+        self.config.jitter.mul_f64(self.rng.lock().unwrap().gen())
+    }
+
+    /// Reserves the next slot for opening a stream and returns the time at which it starts.
+    fn reserve(&self) -> Instant {
+        let mut next_open = self.next_open.lock().unwrap();
+        let open_at = (*next_open).max(Instant::now());
+        *next_open = open_at + self.config.spacing + self.jitter();
+
+        open_at
+    }
+
+    /// Delays all further stream opens until the given backoff has elapsed.
+    fn back_off(&self, backoff: Duration) {
+        let mut next_open = self.next_open.lock().unwrap();
+        *next_open = (*next_open).max(Instant::now() + backoff + self.jitter());
+    }
+
+    /// Opens the game event stream at the given path with the given client, waiting for a slot
+    /// according to the configured pacing and retrying rate-limited attempts.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError] of the last attempt to open the stream.
+    pub(crate) async fn open(&self, client: &BotClient, path: &str) -> LibotResult<Response> {
+        // The semaphore is never closed, so acquiring a permit cannot fail.
+        let _permit = self.opening.acquire().await.ok();
+        let mut backoff = self.config.initial_backoff;
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            tokio::time::sleep_until(self.reserve().into()).await;
+
+            match client.send_request(Method::GET, path).await {
+                Err(LibotRequestError::ApiError { status: StatusCode::TOO_MANY_REQUESTS, .. })
+                        if attempts < self.config.max_attempts => {
+                    self.back_off(backoff);
+                    backoff = (backoff * 2).min(self.config.max_backoff);
+                },
+                result => return result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use wiremock::{Mock, ResponseTemplate};
+    use wiremock::matchers::{method, path};
+
+    use crate::test_util;
+
+    use super::*;
+
+    fn pacing(spacing_millis: u64) -> GameStreamPacing {
+        GameStreamPacing::new()
+            .with_spacing(Duration::from_millis(spacing_millis))
+            .with_jitter(Duration::ZERO)
+            .with_backoff(Duration::from_millis(50), Duration::from_millis(80))
+    }
+
+    #[test]
+    fn first_slot_starts_immediately_and_later_ones_are_spaced() {
+        let before = Instant::now();
+        let pacer = GameStreamPacer::new(pacing(100));
+
+        let first = pacer.reserve();
+        let second = pacer.reserve();
+        let third = pacer.reserve();
+
+        assert_that!(first.saturating_duration_since(before))
+            .is_less_than(Duration::from_millis(50));
+        assert_that!(second - first).is_equal_to(Duration::from_millis(100));
+        assert_that!(third - second).is_equal_to(Duration::from_millis(100));
+    }
+
+    #[test]
+    fn jitter_is_added_to_spacing() {
+        let pacer = GameStreamPacer::new(pacing(100).with_jitter(Duration::from_millis(50)));
+
+        let first = pacer.reserve();
+        let second = pacer.reserve();
+
+        assert_that!(second - first).is_greater_than_or_equal_to(Duration::from_millis(100));
+        assert_that!(second - first).is_less_than_or_equal_to(Duration::from_millis(150));
+    }
+
+    #[test]
+    fn jitter_with_same_seed_is_reproducible() {
+        let jitters = || {
+            let pacer = GameStreamPacer::new(pacing(100)
+                .with_jitter(Duration::from_millis(50))
+                .with_jitter_seed(42));
+
+            (0..10).map(|_| pacer.jitter()).collect::<Vec<_>>()
+        };
+
+        assert_that!(jitters()).is_equal_to(jitters());
+    }
+
+    #[test]
+    fn backoff_delays_next_slot() {
+        let pacer = GameStreamPacer::new(pacing(0));
+        let before = Instant::now();
+
+        pacer.back_off(Duration::from_secs(10));
+
+        assert_that!(pacer.reserve() - before)
+            .is_greater_than_or_equal_to(Duration::from_secs(10));
+    }
+
+    #[test]
+    fn rate_limited_open_is_retried_after_backoff() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let pacer = GameStreamPacer::new(pacing(0));
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testGameId"))
+                .respond_with(ResponseTemplate::new(429))
+                .up_to_n_times(2)
+                .expect(2)
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testGameId"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let started_at = Instant::now();
+            let response = pacer.open(&client, "/bot/game/stream/testGameId").await;
+
+            assert_that!(response.unwrap().status()).is_equal_to(StatusCode::OK);
+            assert_that!(started_at.elapsed())
+                .is_greater_than_or_equal_to(Duration::from_millis(130));
+        });
+    }
+
+    #[test]
+    fn open_fails_after_max_attempts() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let pacer = GameStreamPacer::new(pacing(0).with_max_attempts(2));
+
+            Mock::given(method("GET"))
+                .and(path("/bot/game/stream/testGameId"))
+                .respond_with(ResponseTemplate::new(429))
+                .expect(2)
+                .mount(&server)
+                .await;
+
+            let result = pacer.open(&client, "/bot/game/stream/testGameId").await;
+
+            assert!(matches!(result, Err(LibotRequestError::ApiError { status, .. })
+                if status == StatusCode::TOO_MANY_REQUESTS));
+        });
+    }
+
+    #[test]
+    fn concurrent_opens_are_spaced() {
+        tokio_test::block_on(async {
+            let (client, server) = test_util::setup_wiremock_test().await;
+            let pacer = GameStreamPacer::new(pacing(50).with_max_concurrent_opens(1));
+
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(3)
+                .mount(&server)
+                .await;
+
+            let started_at = Instant::now();
+            let (first, second, third) = tokio::join!(
+                pacer.open(&client, "/bot/game/stream/first"),
+                pacer.open(&client, "/bot/game/stream/second"),
+                pacer.open(&client, "/bot/game/stream/third"));
+
+            assert_that!(first).is_ok();
+            assert_that!(second).is_ok();
+            assert_that!(third).is_ok();
+            assert_that!(started_at.elapsed())
+                .is_greater_than_or_equal_to(Duration::from_millis(100));
+        });
+    }
+}
@@ -0,0 +1,193 @@
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::time::Duration;
+
+const NOTIFY_SOCKET: &str = "NOTIFY_SOCKET";
+const WATCHDOG_USEC: &str = "WATCHDOG_USEC";
+const WATCHDOG_PID: &str = "WATCHDOG_PID";
+
+fn watchdog_timeout_from(usec: Option<&str>, pid: Option<&str>, own_pid: u32)
+        -> Option<Duration> {
+    if let Some(pid) = pid {
+        if pid.trim().parse::<u32>().ok()? != own_pid {
+            return None;
+        }
+    }
+
+    let usec = usec?.trim().parse::<u64>().ok().filter(|&usec| usec > 0)?;
+
+    Some(Duration::from_micros(usec))
+}
+
+/// Gets the timeout of the systemd watchdog of this process, i.e. the time after which systemd
+/// considers the service failed if it received no watchdog ping. This is configured with
+/// `WatchdogSec=` in the unit file and passed to the process in the `WATCHDOG_USEC` environment
+/// variable.
+///
+/// # Returns
+///
+/// The watchdog timeout, or [None] if the watchdog is not enabled for this process.
+pub fn watchdog_timeout() -> Option<Duration> {
+    let usec = env::var(WATCHDOG_USEC).ok();
+    let pid = env::var(WATCHDOG_PID).ok();
+
+    watchdog_timeout_from(usec.as_deref(), pid.as_deref(), std::process::id())
+}
+
+/// Sends notifications on the state of the service to systemd using the `sd_notify` protocol, for
+/// services with `Type=notify` in their unit file. The
+/// [BotRunner](crate::runner::BotRunner) can send these notifications automatically, see
+/// [BotRunner::with_systemd_notify](crate::runner::BotRunner::with_systemd_notify).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SystemdNotifier {
+    socket: OsString
+}
+
+impl SystemdNotifier {
+
+    /// Creates a notifier which sends notifications to the socket with the given address. Names
+    /// starting with `@` refer to abstract sockets.
+    pub fn new(socket: impl Into<OsString>) -> SystemdNotifier {
+        SystemdNotifier {
+            socket: socket.into()
+        }
+    }
+
+    /// Creates a notifier which sends notifications to the socket specified by systemd in the
+    /// `NOTIFY_SOCKET` environment variable.
+    ///
+    /// # Returns
+    ///
+    /// The notifier, or [None] if the process is not run by systemd with notifications enabled.
+    pub fn from_env() -> Option<SystemdNotifier> {
+        env::var_os(NOTIFY_SOCKET)
+            .filter(|socket| !socket.is_empty())
+            .map(SystemdNotifier::new)
+    }
+
+    /// Sends the given raw notification, consisting of newline-separated `KEY=VALUE` assignments,
+    /// to systemd.
+    ///
+    /// # Errors
+    ///
+    /// Any I/O error that occurs while sending the notification. On platforms other than Unix,
+    /// an error of kind [io::ErrorKind::Unsupported] is always returned.
+    #[cfg(unix)]
+    pub fn notify(&self, state: &str) -> io::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::net::UnixDatagram;
+
+        let socket = UnixDatagram::unbound()?;
+        let address = self.socket.as_bytes();
+
+        match address.strip_prefix(b"@") {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                use std::os::unix::net::SocketAddr;
+
+                let address = SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &address)?;
+            },
+            _ => {
+                socket.send_to(state.as_bytes(), &self.socket)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the given raw notification, consisting of newline-separated `KEY=VALUE` assignments,
+    /// to systemd.
+    ///
+    /// # Errors
+    ///
+    /// Any I/O error that occurs while sending the notification. On platforms other than Unix,
+    /// an error of kind [io::ErrorKind::Unsupported] is always returned.
+    #[cfg(not(unix))]
+    pub fn notify(&self, _state: &str) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Notifies systemd that the service finished starting up.
+    ///
+    /// # Errors
+    ///
+    /// See [SystemdNotifier::notify].
+    pub fn ready(&self) -> io::Result<()> {
+        self.notify("READY=1")
+    }
+
+    /// Notifies systemd that the service is still healthy, resetting the watchdog timer.
+    ///
+    /// # Errors
+    ///
+    /// See [SystemdNotifier::notify].
+    pub fn watchdog(&self) -> io::Result<()> {
+        self.notify("WATCHDOG=1")
+    }
+
+    /// Notifies systemd that the service is shutting down.
+    ///
+    /// # Errors
+    ///
+    /// See [SystemdNotifier::notify].
+    pub fn stopping(&self) -> io::Result<()> {
+        self.notify("STOPPING=1")
+    }
+
+    /// Sends a free-form status text to systemd, which is shown by `systemctl status`.
+    ///
+    /// # Errors
+    ///
+    /// See [SystemdNotifier::notify].
+    pub fn status(&self, status: &str) -> io::Result<()> {
+        self.notify(&format!("STATUS={}", status.replace('\n', " ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::os::unix::net::UnixDatagram;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::not_set(None, None, None)]
+    #[case::set(Some("30000000"), None, Some(Duration::from_secs(30)))]
+    #[case::own_pid(Some("30000000"), Some("42"), Some(Duration::from_secs(30)))]
+    #[case::other_pid(Some("30000000"), Some("43"), None)]
+    #[case::zero(Some("0"), None, None)]
+    #[case::invalid(Some("soon"), None, None)]
+    fn watchdog_timeout_is_parsed_from_environment(#[case] usec: Option<&str>,
+            #[case] pid: Option<&str>, #[case] expected: Option<Duration>) {
+        assert_that!(watchdog_timeout_from(usec, pid, 42)).is_equal_to(expected);
+    }
+
+    #[test]
+    fn notifications_are_sent_to_socket() {
+        let path = env::temp_dir().join(format!("libot-notify-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        let notifier = SystemdNotifier::new(&path);
+        let mut buffer = [0; 64];
+
+        notifier.ready().unwrap();
+        let received = receiver.recv(&mut buffer).unwrap();
+
+        assert_that!(&buffer[..received]).is_equal_to(b"READY=1".as_slice());
+
+        notifier.status("playing\n2 games").unwrap();
+        let received = receiver.recv(&mut buffer).unwrap();
+
+        assert_that!(&buffer[..received]).is_equal_to(b"STATUS=playing 2 games".as_slice());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::model::Milliseconds;
+use crate::model::game::GameId;
+
+/// Aggregated move timing metrics of the bot in a single game. The latency of a move is the time
+/// between the runner receiving the game state in which it became the bot's turn and the bot
+/// submitting its move with [GameContext::make_move](crate::context::GameContext::make_move).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct GameTelemetry {
+
+    /// The number of moves for which a latency was measured.
+    pub moves: u32,
+
+    /// The sum of all measured latencies.
+    pub total_latency: Duration,
+
+    /// The highest measured latency, if any moves were measured.
+    pub max_latency: Option<Duration>,
+
+    /// The latency of the most recent move, if any moves were measured.
+    pub last_latency: Option<Duration>,
+
+    /// The number of moves whose latency exceeded the bot's increment, i.e. which reduced the time
+    /// remaining on the bot's clock.
+    pub moves_over_increment: u32
+}
+
+impl GameTelemetry {
+
+    /// The average latency of all measured moves, or [None] if no moves were measured.
+    pub fn average_latency(&self) -> Option<Duration> {
+        if self.moves == 0 {
+            None
+        }
+        else {
+            Some(self.total_latency / self.moves)
+        }
+    }
+
+    fn record(&mut self, latency: Duration, increment: Duration) {
+        self.moves += 1;
+        self.total_latency += latency;
+        self.max_latency = self.max_latency.max(Some(latency));
+        self.last_latency = Some(latency);
+
+        if latency > increment {
+            self.moves_over_increment += 1;
+        }
+    }
+}
+
+/// Collects [GameTelemetry] for all games played by a [BotRunner](crate::runner::BotRunner) it is
+/// registered with using
+/// [BotRunner::with_move_telemetry](crate::runner::BotRunner::with_move_telemetry). Keep another
+/// reference to read the metrics while the bot is running, e.g. to export them to a monitoring
+/// system.
+#[derive(Debug, Default)]
+pub struct MoveTelemetry {
+    games: Mutex<HashMap<GameId, GameTelemetry>>
+}
+
+impl MoveTelemetry {
+
+    /// Creates a new, empty move telemetry.
+    pub fn new() -> MoveTelemetry {
+        MoveTelemetry::default()
+    }
+
+    /// Gets the metrics of the game with the given ID, or [None] if no moves of that game were
+    /// measured.
+    pub fn game(&self, game_id: &GameId) -> Option<GameTelemetry> {
+        self.games.lock().unwrap().get(game_id).copied()
+    }
+
+    /// Gets the metrics of all games in which moves were measured, indexed by game ID.
+    pub fn games(&self) -> HashMap<GameId, GameTelemetry> {
+        self.games.lock().unwrap().clone()
+    }
+
+    /// Removes the metrics of all games, e.g. after they have been exported.
+    pub fn clear(&self) {
+        self.games.lock().unwrap().clear();
+    }
+
+    fn record(&self, game_id: &GameId, latency: Duration, increment: Duration) {
+        self.games.lock().unwrap()
+            .entry(game_id.clone())
+            .or_default()
+            .record(latency, increment);
+    }
+}
+
+#[derive(Debug, Default)]
+struct GameTimer {
+    turn_started: Option<Instant>,
+    ply: usize,
+    increment: Duration,
+    last_latency: Option<Duration>
+}
+
+/// Measures the time between the bot getting the turn and submitting its move in each running
+/// game. Shared between the runner, which starts the turns, and the
+/// [BotClient](crate::client::BotClient), which registers submitted moves.
+#[derive(Debug, Default)]
+pub(crate) struct MoveTimer {
+    games: Mutex<HashMap<GameId, GameTimer>>,
+    telemetry: Option<Arc<MoveTelemetry>>
+}
+
+impl MoveTimer {
+
+    pub(crate) fn new(telemetry: Option<Arc<MoveTelemetry>>) -> MoveTimer {
+        MoveTimer {
+            games: Mutex::new(HashMap::new()),
+            telemetry
+        }
+    }
+
+    /// Starts the bot's turn at the given ply, unless the turn at that ply has already started,
+    /// e.g. because the game state was updated by a draw offer.
+    pub(crate) fn turn_started(&self, game_id: &GameId, ply: usize, increment: Milliseconds,
+            now: Instant) {
+        let mut games = self.games.lock().unwrap();
+        let game = games.entry(game_id.clone()).or_default();
+
+        if game.turn_started.is_some() && game.ply == ply {
+            return;
+        }
+
+        game.turn_started = Some(now);
+        game.ply = ply;
+        game.increment = Duration::from_millis(increment.max(0) as u64);
+    }
+
+    pub(crate) fn turn_ended(&self, game_id: &GameId) {
+        if let Some(game) = self.games.lock().unwrap().get_mut(game_id) {
+            game.turn_started = None;
+        }
+    }
+
+    pub(crate) fn move_submitted(&self, game_id: &GameId, now: Instant) {
+        let mut games = self.games.lock().unwrap();
+        let Some(game) = games.get_mut(game_id)
+        else {
+            return;
+        };
+        let Some(turn_started) = game.turn_started.take()
+        else {
+            return;
+        };
+        let latency = now.saturating_duration_since(turn_started);
+
+        game.last_latency = Some(latency);
+
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.record(game_id, latency, game.increment);
+        }
+    }
+
+    pub(crate) fn last_move_latency(&self, game_id: &GameId) -> Option<Duration> {
+        self.games.lock().unwrap().get(game_id)?.last_latency
+    }
+
+    pub(crate) fn game_finished(&self, game_id: &GameId) {
+        self.games.lock().unwrap().remove(game_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn game_id() -> GameId {
+        "testGameId".to_owned()
+    }
+
+    #[test]
+    fn latency_is_measured_from_turn_start_to_move_submission() {
+        let telemetry = Arc::new(MoveTelemetry::new());
+        let timer = MoveTimer::new(Some(Arc::clone(&telemetry)));
+        let start = Instant::now();
+
+        timer.turn_started(&game_id(), 0, 1000, start);
+        timer.move_submitted(&game_id(), start + Duration::from_millis(400));
+        timer.turn_started(&game_id(), 2, 1000, start + Duration::from_secs(2));
+        timer.turn_started(&game_id(), 2, 1000, start + Duration::from_secs(3));
+        timer.move_submitted(&game_id(), start + Duration::from_millis(3500));
+
+        let metrics = telemetry.game(&game_id()).unwrap();
+
+        assert_that!(timer.last_move_latency(&game_id())).contains(Duration::from_millis(1500));
+        assert_that!(metrics.moves).is_equal_to(2);
+        assert_that!(metrics.average_latency()).contains(Duration::from_millis(950));
+        assert_that!(metrics.max_latency).contains(Duration::from_millis(1500));
+        assert_that!(metrics.moves_over_increment).is_equal_to(1);
+    }
+
+    #[test]
+    fn moves_outside_of_turn_are_not_measured() {
+        let telemetry = Arc::new(MoveTelemetry::new());
+        let timer = MoveTimer::new(Some(Arc::clone(&telemetry)));
+        let start = Instant::now();
+
+        timer.move_submitted(&game_id(), start);
+        timer.turn_started(&game_id(), 0, 0, start);
+        timer.turn_ended(&game_id());
+        timer.move_submitted(&game_id(), start + Duration::from_secs(1));
+
+        assert_that!(timer.last_move_latency(&game_id())).is_none();
+        assert_that!(telemetry.game(&game_id())).is_none();
+    }
+
+    #[test]
+    fn finished_games_are_forgotten_by_timer_but_not_telemetry() {
+        let telemetry = Arc::new(MoveTelemetry::new());
+        let timer = MoveTimer::new(Some(Arc::clone(&telemetry)));
+        let start = Instant::now();
+
+        timer.turn_started(&game_id(), 0, 0, start);
+        timer.move_submitted(&game_id(), start + Duration::from_millis(10));
+        timer.game_finished(&game_id());
+
+        assert_that!(timer.last_move_latency(&game_id())).is_none();
+        assert_that!(telemetry.games().len()).is_equal_to(1);
+    }
+}
@@ -0,0 +1,310 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::context::GameContext;
+use crate::model::game::{GameId, TournamentId};
+use crate::model::tournament::{Tournament, TournamentPairing};
+
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A policy which decides for every game of the tournament played by a
+/// [BotRunner](crate::runner::BotRunner) in tournament mode whether the bot goes berserk. This is
+/// implemented for all closures taking a [GameContext] reference and returning a `bool`.
+pub trait BerserkPolicy : Send + Sync {
+
+    /// Decides whether the bot goes berserk in the given game.
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The context of the tournament game which is about to start.
+    ///
+    /// # Returns
+    ///
+    /// `true` if and only if the bot should go berserk.
+    fn berserk(&self, context: &GameContext) -> bool;
+}
+
+impl<F> BerserkPolicy for F
+where
+    F: Fn(&GameContext) -> bool + Send + Sync
+{
+    fn berserk(&self, context: &GameContext) -> bool {
+        self(context)
+    }
+}
+
+/// Configuration of the tournament mode of a [BotRunner](crate::runner::BotRunner), in which the
+/// runner joins an arena tournament, keeps its seat by joining again whenever the bot was paused,
+/// plays all paired games and withdraws at a configured time. While the runner plays in the
+/// tournament, incoming challenges are declined with
+/// [DeclineReason::Later](crate::model::challenge::DeclineReason::Later) without passing them to
+/// the challenge queue or [Bot::on_challenge](crate::Bot::on_challenge). Once the runner withdrew
+/// or the tournament finished, challenges are handled as usual again.
+#[derive(Clone)]
+pub struct TournamentConfig {
+    pub(crate) tournament_id: TournamentId,
+    pub(crate) password: Option<String>,
+    pub(crate) withdraw_at: Option<SystemTime>,
+    pub(crate) check_interval: Duration,
+    pub(crate) berserk_policy: Option<Arc<dyn BerserkPolicy>>,
+    pub(crate) pairing_interval: Option<Duration>
+}
+
+impl TournamentConfig {
+
+    /// Creates a new tournament configuration for the arena tournament with the given ID. By
+    /// default, the runner plays until the tournament finishes, checks its seat every 30 seconds
+    /// and never goes berserk.
+    pub fn new(tournament_id: impl Into<TournamentId>) -> TournamentConfig {
+        TournamentConfig {
+            tournament_id: tournament_id.into(),
+            password: None,
+            withdraw_at: None,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            berserk_policy: None,
+            pairing_interval: None
+        }
+    }
+
+    /// Sets the password with which to join the tournament, if it is private. The configuration
+    /// is returned for chaining.
+    pub fn with_password(mut self, password: impl Into<String>) -> TournamentConfig {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Sets the time at which the runner withdraws from the tournament. Games which are running
+    /// at that time are still played until they finish. The configuration is returned for
+    /// chaining.
+    pub fn with_withdraw_at(mut self, withdraw_at: SystemTime) -> TournamentConfig {
+        self.withdraw_at = Some(withdraw_at);
+        self
+    }
+
+    /// Sets the interval in which the runner checks whether it still takes part in the
+    /// tournament and joins it again if it was paused. The configuration is returned for
+    /// chaining.
+    pub fn with_check_interval(mut self, check_interval: Duration) -> TournamentConfig {
+        self.check_interval = check_interval;
+        self
+    }
+
+    /// Sets the [BerserkPolicy] which decides in which tournament games the bot goes berserk. The
+    /// configuration is returned for chaining.
+    pub fn with_berserk_policy(mut self, berserk_policy: impl BerserkPolicy + 'static)
+            -> TournamentConfig {
+        self.berserk_policy = Some(Arc::new(berserk_policy));
+        self
+    }
+
+    /// Enables pairing anticipation, in which the runner queries the tournament in the given
+    /// interval and calls [Bot::on_tournament_pairing](crate::Bot::on_tournament_pairing) as soon
+    /// as the bot was paired for a new game, which is often before the start event of the game
+    /// arrives. Lichess does not stream tournament pairings, so the interval should be short to
+    /// gain time, at the cost of more requests. The configuration is returned for chaining.
+    pub fn with_pairing_anticipation(mut self, interval: Duration) -> TournamentConfig {
+        self.pairing_interval = Some(interval);
+        self
+    }
+}
+
+/// The next step of a runner in tournament mode, as decided by [TournamentMode::next_action].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum TournamentAction {
+    Join,
+    Withdraw,
+    Stop,
+    Wait
+}
+
+pub(crate) struct TournamentMode {
+    pub(crate) config: TournamentConfig,
+    playing: AtomicBool,
+    last_pairing: Mutex<Option<GameId>>
+}
+
+impl TournamentMode {
+
+    pub(crate) fn new(config: TournamentConfig) -> TournamentMode {
+        TournamentMode {
+            config,
+            playing: AtomicBool::new(true),
+            last_pairing: Mutex::new(None)
+        }
+    }
+
+    /// Whether the runner still plays in the tournament, i.e. it neither withdrew nor did the
+    /// tournament finish.
+    pub(crate) fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn stop(&self) {
+        self.playing.store(false, Ordering::Release);
+    }
+
+    /// Decides the next step given the current state of the tournament, or [None] if it could not
+    /// be queried, at the given time.
+    pub(crate) fn next_action(&self, tournament: Option<&Tournament>, now: SystemTime)
+            -> TournamentAction {
+        if self.config.withdraw_at.is_some_and(|withdraw_at| now >= withdraw_at) {
+            return TournamentAction::Withdraw;
+        }
+
+        match tournament {
+            Some(tournament) if tournament.is_finished => TournamentAction::Stop,
+            Some(tournament) if !tournament.is_joined() => TournamentAction::Join,
+            _ => TournamentAction::Wait
+        }
+    }
+
+    /// The time to wait before the next check at the given time, which is at most the configured
+    /// check interval and ends at the configured withdrawal time.
+    pub(crate) fn wait_time(&self, now: SystemTime) -> Duration {
+        let check_interval = self.config.check_interval;
+        let check_interval = self.config.pairing_interval
+            .map_or(check_interval, |pairing_interval| pairing_interval.min(check_interval));
+
+        self.config.withdraw_at
+            .map(|withdraw_at| withdraw_at.duration_since(now).unwrap_or_default())
+            .map_or(check_interval, |until_withdrawal| until_withdrawal.min(check_interval))
+    }
+
+    /// Determines the pairing of the bot in the given state of the tournament, if pairing
+    /// anticipation is enabled and the bot was paired for a different game than at the previous
+    /// call.
+    pub(crate) fn new_pairing(&self, tournament: Option<&Tournament>, bot_name: &str)
+            -> Option<TournamentPairing> {
+        self.config.pairing_interval?;
+
+        let pairing = tournament?.pairing(bot_name)?;
+        let mut last_pairing = self.last_pairing.lock().unwrap();
+
+        if last_pairing.as_ref() == Some(&pairing.game_id) {
+            return None;
+        }
+
+        *last_pairing = Some(pairing.game_id.clone());
+        Some(pairing)
+    }
+
+    /// Determines whether the bot goes berserk in the given game, which must not have started
+    /// yet.
+    pub(crate) fn berserk(&self, context: &GameContext) -> bool {
+        context.tournament_id.as_ref() == Some(&self.config.tournament_id) &&
+            self.config.berserk_policy.as_ref().is_some_and(|policy| policy.berserk(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::UNIX_EPOCH;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::game::{Color, GameInfo};
+    use crate::model::tournament::TournamentParticipation;
+    use crate::store::tests as store_tests;
+
+    use super::*;
+
+    fn time(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn test_tournament(is_finished: bool, withdraw: Option<bool>) -> Tournament {
+        Tournament {
+            id: "testTournamentId".to_owned(),
+            full_name: "Test Arena".to_owned(),
+            nb_players: 2,
+            is_started: true,
+            is_finished,
+            seconds_to_start: None,
+            seconds_to_finish: None,
+            berserkable: true,
+            clock: None,
+            variant: None,
+            duels: Vec::new(),
+            me: withdraw.map(|withdraw| TournamentParticipation {
+                rank: 1,
+                withdraw,
+                game_id: None,
+                pause_delay: None
+            })
+        }
+    }
+
+    fn test_context(tournament_id: Option<&str>) -> GameContext {
+        GameContext {
+            bot_id: "testBotId".to_owned(),
+            bot_color: Some(Color::White),
+            info: GameInfo {
+                tournament_id: tournament_id.map(str::to_owned),
+                ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    #[rstest]
+    #[case::not_joined(Some(test_tournament(false, None)), 100, TournamentAction::Join)]
+    #[case::paused(Some(test_tournament(false, Some(true))), 100, TournamentAction::Join)]
+    #[case::joined(Some(test_tournament(false, Some(false))), 100, TournamentAction::Wait)]
+    #[case::finished(Some(test_tournament(true, Some(false))), 100, TournamentAction::Stop)]
+    #[case::unavailable(None, 100, TournamentAction::Wait)]
+    #[case::withdrawal_time(Some(test_tournament(false, Some(false))), 200,
+        TournamentAction::Withdraw)]
+    fn next_action(#[case] tournament: Option<Tournament>, #[case] now: u64,
+            #[case] expected: TournamentAction) {
+        let mode = TournamentMode::new(
+            TournamentConfig::new("testTournamentId").with_withdraw_at(time(200)));
+
+        assert_that!(mode.next_action(tournament.as_ref(), time(now))).is_equal_to(expected);
+    }
+
+    #[rstest]
+    #[case::no_withdrawal(None, 100, 30)]
+    #[case::withdrawal_later(Some(200), 100, 30)]
+    #[case::withdrawal_soon(Some(110), 100, 10)]
+    #[case::withdrawal_passed(Some(90), 100, 0)]
+    fn wait_time(#[case] withdraw_at: Option<u64>, #[case] now: u64, #[case] expected: u64) {
+        let mut config = TournamentConfig::new("testTournamentId");
+
+        if let Some(withdraw_at) = withdraw_at {
+            config = config.with_withdraw_at(time(withdraw_at));
+        }
+
+        let mode = TournamentMode::new(config);
+
+        assert_that!(mode.wait_time(time(now))).is_equal_to(Duration::from_secs(expected));
+    }
+
+    #[rstest]
+    #[case::tournament_game(Some("testTournamentId"), true)]
+    #[case::other_tournament(Some("otherTournamentId"), false)]
+    #[case::casual_game(None, false)]
+    fn berserk_only_in_tournament_games(#[case] tournament_id: Option<&str>,
+            #[case] expected: bool) {
+        let mode = TournamentMode::new(TournamentConfig::new("testTournamentId")
+            .with_berserk_policy(|_: &GameContext| true));
+
+        assert_that!(mode.berserk(&test_context(tournament_id))).is_equal_to(expected);
+    }
+
+    #[test]
+    fn no_berserk_without_policy() {
+        let mode = TournamentMode::new(TournamentConfig::new("testTournamentId"));
+
+        assert_that!(mode.berserk(&test_context(Some("testTournamentId")))).is_false();
+    }
+}
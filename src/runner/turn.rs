@@ -0,0 +1,166 @@
+use std::time::{Duration, Instant};
+
+use crate::context::GameContext;
+use crate::model::game::{Color, Speed};
+use crate::model::game::event::GameStateEvent;
+
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Tracks the turns of the bot in a single game, so each turn is only reported once, even if
+/// Lichess sends several game states with the same moves.
+#[derive(Default)]
+pub(crate) struct TurnTracker {
+    turn_plies: Option<usize>
+}
+
+impl TurnTracker {
+
+    /// Registers the given game state.
+    ///
+    /// # Returns
+    ///
+    /// `true` if and only if the game state starts a new turn of the bot, i.e. it is the bot's
+    /// turn and the previous game state was not in the same turn.
+    pub(crate) fn update(&mut self, game_context: &GameContext, state: &GameStateEvent) -> bool {
+        if !game_context.is_my_turn(state) {
+            self.turn_plies = None;
+            return false;
+        }
+
+        let plies = state.moves.split_whitespace().count();
+
+        self.turn_plies.replace(plies) != Some(plies)
+    }
+}
+
+/// Computes the instant at which the bot should be reminded of its turn in a correspondence game,
+/// which is the given time before the turn runs out. The time of the turn is limited by both the
+/// days per turn of the game and the remaining clock of the bot, if the game has one.
+///
+/// # Arguments
+///
+/// * `game_context`: The context of the game.
+/// * `state`: The game state which started the turn of the bot.
+/// * `remind_before`: The time before the end of the turn at which to remind the bot.
+/// * `now`: The instant at which the turn started.
+///
+/// # Returns
+///
+/// The instant of the reminder, or [None] if the game is not a correspondence game or the turn is
+/// not longer than `remind_before`.
+pub(crate) fn reminder_at(game_context: &GameContext, state: &GameStateEvent,
+        remind_before: Duration, now: Instant) -> Option<Instant> {
+    if game_context.speed != Speed::Correspondence {
+        return None;
+    }
+
+    let turn_millis = game_context.days_per_turn
+        .map(|days| days.max(0) as u64 * MILLIS_PER_DAY);
+    let clock_millis = match game_context.bot_color? {
+        Color::White => state.white_time,
+        Color::Black => state.black_time
+    };
+    let clock_millis = Some(clock_millis).filter(|&millis| millis > 0).map(|millis| millis as u64);
+    let turn_millis = match (turn_millis, clock_millis) {
+        (Some(turn_millis), Some(clock_millis)) => turn_millis.min(clock_millis),
+        (turn_millis, clock_millis) => turn_millis.or(clock_millis)?
+    };
+    let turn_time = Duration::from_millis(turn_millis);
+
+    if turn_time <= remind_before {
+        return None;
+    }
+
+    Some(now + (turn_time - remind_before))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::{Days, Milliseconds};
+    use crate::model::game::{GameInfo, GameStatus};
+    use crate::store::tests as store_tests;
+
+    use super::*;
+
+    fn test_context(speed: Speed, days_per_turn: Option<Days>) -> GameContext {
+        GameContext {
+            bot_color: Some(Color::White),
+            bot_id: "testBotId".to_owned(),
+            info: GameInfo {
+                speed,
+                days_per_turn,
+                ..store_tests::test_game_info("testGameId", "testBotId", "opponent")
+            },
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: None,
+            move_watcher: None,
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        }
+    }
+
+    fn test_state(moves: &str, white_time: Milliseconds) -> GameStateEvent {
+        GameStateEvent {
+            moves: moves.to_owned(),
+            white_time,
+            black_time: 1000,
+            white_increment: 0,
+            black_increment: 0,
+            status: GameStatus::Started,
+            winner: None,
+            white_draw_offer: false,
+            black_draw_offer: false,
+            white_take_back_proposal: false,
+            black_take_back_proposal: false,
+            skipped_states: 0
+        }
+    }
+
+    #[test]
+    fn turn_tracker_reports_each_turn_once() {
+        let context = test_context(Speed::Blitz, None);
+        let mut tracker = TurnTracker::default();
+
+        assert_that!(tracker.update(&context, &test_state("", 1000))).is_true();
+        assert_that!(tracker.update(&context, &test_state("", 1000))).is_false();
+        assert_that!(tracker.update(&context, &test_state("e2e4", 1000))).is_false();
+        assert_that!(tracker.update(&context, &test_state("e2e4 e7e5", 1000))).is_true();
+        assert_that!(tracker.update(&context, &test_state("e2e4 e7e5", 1000))).is_false();
+    }
+
+    #[test]
+    fn turn_tracker_reports_turn_again_after_takeback() {
+        let context = test_context(Speed::Blitz, None);
+        let mut tracker = TurnTracker::default();
+
+        assert_that!(tracker.update(&context, &test_state("", 1000))).is_true();
+        assert_that!(tracker.update(&context, &test_state("e2e4", 1000))).is_false();
+        assert_that!(tracker.update(&context, &test_state("", 1000))).is_true();
+    }
+
+    #[rstest]
+    #[case::not_correspondence(Speed::Classical, Some(1), 0, None)]
+    #[case::days_per_turn_only(Speed::Correspondence, Some(1), 0, Some(MILLIS_PER_DAY - 1000))]
+    #[case::clock_shorter_than_days(Speed::Correspondence, Some(1), 5000, Some(4000))]
+    #[case::clock_only(Speed::Correspondence, None, 5000, Some(4000))]
+    #[case::turn_too_short(Speed::Correspondence, Some(1), 1000, None)]
+    #[case::unlimited(Speed::Correspondence, None, 0, None)]
+    fn reminder_at_works(#[case] speed: Speed, #[case] days_per_turn: Option<Days>,
+            #[case] white_time: Milliseconds, #[case] expected_delay_millis: Option<u64>) {
+        let context = test_context(speed, days_per_turn);
+        let state = test_state("", white_time);
+        let now = Instant::now();
+
+        let reminder = reminder_at(&context, &state, Duration::from_secs(1), now);
+
+        assert_that!(reminder.map(|reminder| (reminder - now).as_millis() as u64))
+            .is_equal_to(expected_delay_millis);
+    }
+}
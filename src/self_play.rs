@@ -0,0 +1,753 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+
+use http::StatusCode;
+
+use reqwest::{Request, Response};
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::Bot;
+use crate::chess::ChessResult;
+use crate::chess::position::Position;
+use crate::chess::uci::UciMove;
+use crate::client::{BotClient, BotClientBuilder};
+use crate::by_address::ByAddress;
+use crate::context::{BotContext, GameContext};
+use crate::elo::Sprt;
+use crate::error::LibotResult;
+use crate::match_manager::{MatchGame, MatchResult, MatchStatus};
+use crate::model::{Milliseconds, Seconds};
+use crate::model::bot_event::GameStartFinish;
+use crate::model::game::{Clock, Color, GameId, GameInfo, GamePerf, GameStatus, Variant};
+use crate::model::game::event::{GameEventPlayer, GameStateEvent};
+use crate::model::game::result::{self, GameResult};
+use crate::runner::move_confirmation::MoveWatcher;
+use crate::runner::position_tracker::PositionTracker;
+use crate::transport::HttpTransport;
+
+/// The user IDs of the first and second bot in a [SelfPlay] match.
+const PLAYER_IDS: [&str; 2] = ["selfplayfirst", "selfplaysecond"];
+
+const BASE_URL: &str = "http://self-play.invalid/api";
+
+/// The time for which hooks which are still running when a game ends may finish before they are
+/// dropped.
+const HOOK_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// The number of half-moves without capture or pawn move after which a game is drawn.
+const FIFTY_MOVE_PLIES: u32 = 100;
+
+type Hooks<'a> = FuturesUnordered<BoxFuture<'a, ()>>;
+
+/// A request of a bot concerning the running game, received by the [SelfPlayTransport].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Action {
+    Move {
+        mov: UciMove,
+        offer_draw: bool
+    },
+    Resign,
+    Abort,
+    OfferDraw,
+    DeclineDraw
+}
+
+#[derive(Debug)]
+struct ArenaGame {
+    game_id: GameId,
+    position: Position,
+    plies: usize,
+    mover: Option<usize>
+}
+
+/// The state shared between the driver of a [SelfPlay] match and the transports of both bots.
+#[derive(Debug)]
+struct Arena {
+    game: Mutex<Option<ArenaGame>>,
+    actions: UnboundedSender<(usize, Action)>
+}
+
+impl Arena {
+
+    fn respond(&self, player: usize, request: &Request) -> (StatusCode, &'static str) {
+        const OK: (StatusCode, &str) = (StatusCode::OK, r#"{"ok":true}"#);
+        const NOT_FOUND: (StatusCode, &str) = (StatusCode::NOT_FOUND, r#"{"error":"Not found"}"#);
+        const BAD_REQUEST: (StatusCode, &str) =
+            (StatusCode::BAD_REQUEST, r#"{"error":"Not your turn, or game already over"}"#);
+
+        let url = request.url();
+        let segments = url.path_segments()
+            .map(|segments| segments.collect::<Vec<_>>())
+            .unwrap_or_default();
+        let Some(index) = segments.iter().position(|&segment| segment == "game")
+        else {
+            return NOT_FOUND;
+        };
+        let offer_draw = url.query_pairs()
+            .any(|(key, value)| key == "offeringDraw" && value == "true");
+        let mut game = self.game.lock().unwrap();
+        let Some(game) = game.as_mut()
+            .filter(|game| segments.get(index + 1) == Some(&game.game_id.as_str()))
+        else {
+            return BAD_REQUEST;
+        };
+
+        let action = match segments.get(index + 2..).unwrap_or_default() {
+            ["move", mov] => {
+                let Ok(mov) = mov.parse::<UciMove>()
+                else {
+                    return BAD_REQUEST;
+                };
+
+                if game.mover != Some(player) || !game.position.is_legal(&mov) {
+                    return BAD_REQUEST;
+                }
+
+                game.mover = None;
+
+                Action::Move {
+                    mov,
+                    offer_draw
+                }
+            },
+            ["resign"] => Action::Resign,
+            ["abort"] if game.plies < 2 => Action::Abort,
+            ["abort"] => return BAD_REQUEST,
+            ["draw", "yes"] => Action::OfferDraw,
+            ["draw", "no"] => Action::DeclineDraw,
+            ["chat"] => return OK,
+            _ => return NOT_FOUND
+        };
+
+        // The driver holds the receiver for as long as the arena is in use.
+        let _ = self.actions.send((player, action));
+
+        OK
+    }
+}
+
+/// The [HttpTransport] of the client of one bot in a [SelfPlay] match, which answers the requests
+/// concerning the running game in memory instead of sending them to Lichess.
+#[derive(Debug)]
+struct SelfPlayTransport {
+    arena: Arc<Arena>,
+    player: usize
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for SelfPlayTransport {
+    async fn execute(&self, request: Request) -> LibotResult<Response> {
+        let (status, body) = self.arena.respond(self.player, &request);
+        let response = http::Response::builder().status(status).body(body);
+
+        // The status and body are always valid, so building the response cannot fail.
+        Ok(Response::from(response.unwrap()))
+    }
+}
+
+/// The reason why a turn ended.
+enum TurnEnd {
+    Moved {
+        mov: UciMove,
+        offer_draw: bool,
+        elapsed: Duration
+    },
+    GameOver(GameStatus, Option<Color>)
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1
+    }
+}
+
+fn player(player_id: &str) -> GameEventPlayer {
+    GameEventPlayer {
+        ai_level: None,
+        id: Some(player_id.to_owned()),
+        name: Some(player_id.to_owned()),
+        title: None,
+        rating: None,
+        provisional: None
+    }
+}
+
+/// Determines whether the game ended in the given position, which was reached after the given
+/// number of plies.
+fn game_end(position: &Position, repetitions: u32, plies: usize, max_plies: usize)
+        -> Option<(GameStatus, Option<Color>)> {
+    if position.is_variant_end() {
+        Some((GameStatus::VariantEnd, position.variant_winner()))
+    }
+    else if position.is_checkmate() {
+        Some((GameStatus::Mate, Some(position.side_to_move().opposite())))
+    }
+    else if position.is_stalemate() {
+        Some((GameStatus::Stalemate, None))
+    }
+    else if position.is_insufficient_material() || repetitions >= 3 ||
+            position.halfmove_clock() >= FIFTY_MOVE_PLIES || plies >= max_plies {
+        Some((GameStatus::Draw, None))
+    }
+    else {
+        None
+    }
+}
+
+fn state_hooks<'a>(bots: [&'a dyn Bot; 2], contexts: &'a [GameContext; 2],
+        clients: &'a [BotClient; 2], state: &GameStateEvent, mover: Option<usize>)
+        -> impl Iterator<Item = BoxFuture<'a, ()>> {
+    let state = state.clone();
+
+    (0..2).map(move |player| {
+        let (bot, context, client) = (bots[player], &contexts[player], &clients[player]);
+        let state = state.clone();
+
+        Box::pin(async move {
+            bot.on_game_state(context, state.clone(), client).await;
+
+            if mover == Some(player) {
+                bot.on_my_turn(context, state, client).await;
+            }
+        }) as BoxFuture<'a, ()>
+    })
+}
+
+/// Plays a match of a fixed number of games between two [Bot]s in-process, without involving
+/// Lichess, for example to validate a change to an engine. Both bots receive the same hooks as
+/// when run by a [BotRunner](crate::runner::BotRunner): the initial state and every following
+/// state of each game are passed to [Bot::on_game_state], followed by [Bot::on_my_turn] for the
+/// side to move. The clients passed to the hooks answer moves, resignations, draw offers, and
+/// chat messages for the running game in memory. Other requests fail.
+///
+/// The rules of [Position] decide which moves are legal and when a game ends. Games are drawn by
+/// insufficient material, threefold repetition, or the fifty-move rule, without a claim, and are
+/// adjudicated as draws once they reach the maximum number of plies. A bot which does not move
+/// before its clock runs out loses on time. The first bot plays White in the first game and
+/// colors alternate.
+///
+/// The result is reported as a [MatchResult] from the perspective of the first bot, which allows
+/// estimating the Elo difference between the bots with [MatchResult::elo_estimate].
+pub struct SelfPlay<A, B> {
+    first: A,
+    second: B,
+    games: u32,
+    clock: Clock,
+    max_plies: usize,
+    openings: Vec<(String, Position)>,
+    sprt: Option<Sprt>
+}
+
+impl<A: Bot, B: Bot> SelfPlay<A, B> {
+
+    /// Creates a new self-play match with a clock of one minute plus one second increment, in
+    /// which games are adjudicated as draws after 400 plies.
+    ///
+    /// # Arguments
+    ///
+    /// * `first`: The bot from whose perspective the [MatchResult] is reported.
+    /// * `second`: The opponent of the first bot.
+    /// * `games`: The number of games to play.
+    pub fn new(first: A, second: B, games: u32) -> SelfPlay<A, B> {
+        SelfPlay {
+            first,
+            second,
+            games,
+            clock: Clock {
+                limit: Some(60),
+                increment: Some(1)
+            },
+            max_plies: 400,
+            openings: Vec::new(),
+            sprt: None
+        }
+    }
+
+    /// Sets the clock of every game. The match is returned for chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: The initial time of each side in seconds.
+    /// * `increment`: The time added after each move in seconds.
+    pub fn with_clock(mut self, limit: Seconds, increment: Seconds) -> SelfPlay<A, B> {
+        self.clock = Clock {
+            limit: Some(limit),
+            increment: Some(increment)
+        };
+        self
+    }
+
+    /// Sets the number of plies after which a game is adjudicated as a draw. The match is
+    /// returned for chaining.
+    pub fn with_max_plies(mut self, max_plies: usize) -> SelfPlay<A, B> {
+        self.max_plies = max_plies;
+        self
+    }
+
+    /// Sets openings from which the games start, given as space-separated moves in UCI notation
+    /// from the standard starting position. Each opening is played twice, once with each bot as
+    /// White, before the next one is used, which avoids repeating the same game between
+    /// deterministic bots. The match is returned for chaining.
+    ///
+    /// # Errors
+    ///
+    /// [ChessError::InvalidUciMove](crate::chess::ChessError::InvalidUciMove) or
+    /// [ChessError::IllegalMove](crate::chess::ChessError::IllegalMove) if any opening contains
+    /// an invalid or illegal move.
+    pub fn with_openings(mut self, openings: impl IntoIterator<Item = impl Into<String>>)
+            -> ChessResult<SelfPlay<A, B>> {
+        self.openings = openings.into_iter()
+            .map(|opening| {
+                let opening = opening.into();
+                let mut position = Position::standard();
+                position.play_uci_moves(&opening)?;
+
+                Ok((opening, position))
+            })
+            .collect::<ChessResult<Vec<_>>>()?;
+
+        Ok(self)
+    }
+
+    /// Sets a sequential probability ratio test which is evaluated after every game. Once it
+    /// accepts one of its hypotheses, the match stops early, so the number of games passed to
+    /// [SelfPlay::new] acts as the maximum. The match is returned for chaining.
+    pub fn with_sprt(mut self, sprt: Sprt) -> SelfPlay<A, B> {
+        self.sprt = Some(sprt);
+        self
+    }
+
+    fn game_info(&self, game_id: &GameId, white: usize) -> GameInfo {
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        GameInfo {
+            id: game_id.clone(),
+            variant: Some(Variant::Standard),
+            clock: Some(self.clock),
+            speed: self.clock.speed(),
+            perf: GamePerf {
+                name: None
+            },
+            rated: false,
+            created_at: created_at.as_millis() as i64,
+            white: player(PLAYER_IDS[white]),
+            black: player(PLAYER_IDS[1 - white]),
+            initial_fen: "startpos".to_owned(),
+            days_per_turn: None,
+            opening: None,
+            tournament_id: None
+        }
+    }
+
+    fn game_state(&self, moves: &[String], clocks: [Milliseconds; 2], status: GameStatus,
+            winner: Option<Color>, draw_offer: Option<Color>) -> GameStateEvent {
+        let increment = self.clock.increment.unwrap_or(0) as Milliseconds * 1000;
+
+        GameStateEvent {
+            moves: moves.join(" "),
+            white_time: clocks[0],
+            black_time: clocks[1],
+            white_increment: increment,
+            black_increment: increment,
+            status,
+            winner,
+            white_draw_offer: draw_offer == Some(Color::White),
+            black_draw_offer: draw_offer == Some(Color::Black),
+            white_take_back_proposal: false,
+            black_take_back_proposal: false,
+            skipped_states: 0
+        }
+    }
+
+    fn game_start_finish(&self, game_id: &GameId, color: Color, status: GameStatus,
+            winner: Option<Color>) -> GameStartFinish {
+        GameStartFinish {
+            id: Some(game_id.clone()),
+            source: None,
+            status: Some(status),
+            winner,
+            color: Some(color),
+            rated: Some(false),
+            speed: Some(self.clock.speed()),
+            variant: Some(Variant::Standard),
+            compat: None
+        }
+    }
+
+    /// Plays the match by playing one game after the other. Aborted games count towards the
+    /// number of games, but not towards the score. If the [Sprt] set with [SelfPlay::with_sprt]
+    /// accepts a hypothesis, the match stops early, which is reported in the
+    /// [MatchResult::status].
+    pub async fn play(&self) -> MatchResult {
+        let (sender, mut actions) = mpsc::unbounded_channel();
+        let arena = Arc::new(Arena {
+            game: Mutex::new(None),
+            actions: sender
+        });
+        let client = |player| {
+            let transport = SelfPlayTransport {
+                arena: Arc::clone(&arena),
+                player
+            };
+
+            // The token is a valid header value, so building the client cannot fail.
+            BotClientBuilder::new()
+                .with_token(PLAYER_IDS[player])
+                .with_base_url(BASE_URL)
+                .with_transport(transport)
+                .build()
+                .unwrap()
+        };
+        let clients = [client(0), client(1)];
+        let mut match_result = MatchResult {
+            opponent: PLAYER_IDS[1].to_owned(),
+            games: Vec::new(),
+            status: MatchStatus::Completed
+        };
+
+        for index in 0..self.games {
+            match_result.games.push(self.play_game(index, &clients, &arena, &mut actions).await);
+
+            let decision = self.sprt.as_ref().and_then(|sprt|
+                sprt.decide(match_result.wins(), match_result.draws(), match_result.losses()));
+
+            if let Some(hypothesis) = decision {
+                match_result.status = MatchStatus::Concluded(hypothesis);
+                break;
+            }
+        }
+
+        match_result
+    }
+
+    async fn play_game(&self, index: u32, clients: &[BotClient; 2], arena: &Arena,
+            actions: &mut UnboundedReceiver<(usize, Action)>) -> MatchGame {
+        let first_color = if index.is_multiple_of(2) { Color::White } else { Color::Black };
+        let white = color_index(first_color);
+        let player_of = |color: Color| color_index(color) ^ white;
+        let color_of = |player: usize| if player == white { Color::White } else { Color::Black };
+        let game_id = format!("selfPlay{index}");
+        let info = self.game_info(&game_id, white);
+        let (opening, mut position) = match self.openings.len() {
+            0 => (String::new(), Position::standard()),
+            count => self.openings[(index / 2) as usize % count].clone()
+        };
+        let mut moves = opening.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+        let position_tracker = Arc::new(PositionTracker::default());
+        let move_watcher = Arc::new(MoveWatcher::default());
+        let context = |player: usize| GameContext {
+            bot_id: PLAYER_IDS[player].to_owned(),
+            bot_color: Some(color_of(player)),
+            info: info.clone(),
+            opponent_stats: None,
+            move_timer: None,
+            position_tracker: Some(ByAddress(Arc::clone(&position_tracker))),
+            move_watcher: Some(ByAddress(Arc::clone(&move_watcher))),
+            game_cancellation: None,
+            session_log: None,
+            spectators: None
+        };
+        let contexts = [context(0), context(1)];
+        let bot_context = |player: usize| BotContext {
+            bot_id: PLAYER_IDS[player].to_owned(),
+            profile: ByAddress::default()
+        };
+        let bot_contexts = [bot_context(0), bot_context(1)];
+        let bots: [&dyn Bot; 2] = [&self.first, &self.second];
+        let limit = self.clock.limit.unwrap_or(0) as Milliseconds * 1000;
+        let increment = self.clock.increment.unwrap_or(0) as Milliseconds * 1000;
+        let mut clocks = [limit; 2];
+        let mut draw_offer = None;
+        let mut repetitions = HashMap::from([(position.fen_key(), 1)]);
+        let mut hooks = Hooks::new();
+
+        position_tracker.game_started(&info, &moves.join(" "));
+
+        for player in 0..2 {
+            let game = self.game_start_finish(&game_id, color_of(player), GameStatus::Started,
+                None);
+
+            bots[player].on_game_start(&bot_contexts[player], game, &clients[player]).await;
+        }
+
+        let (status, winner) = loop {
+            let mover_color = position.side_to_move();
+            let mover = player_of(mover_color);
+            let state = self.game_state(&moves, clocks, GameStatus::Started, None, draw_offer);
+
+            position_tracker.update(&game_id, &state.moves);
+            move_watcher.update(&game_id, &state.moves);
+            hooks.extend(state_hooks(bots, &contexts, clients, &state, Some(mover)));
+            *arena.game.lock().unwrap() = Some(ArenaGame {
+                game_id: game_id.clone(),
+                position: position.clone(),
+                plies: moves.len(),
+                mover: Some(mover)
+            });
+
+            let turn_start = Instant::now();
+            let remaining = clocks[color_index(mover_color)].max(0) as u64;
+            let deadline = tokio::time::sleep(Duration::from_millis(remaining));
+            let mut deadline = std::pin::pin!(deadline);
+
+            let turn_end = loop {
+                let action = tokio::select! {
+                    Some(()) = hooks.next() => continue,
+                    action = actions.recv() => action,
+                    () = &mut deadline => None
+                };
+                let Some((player, action)) = action
+                else {
+                    let winner = Some(mover_color.opposite())
+                        .filter(|&winner| position.has_mating_material(winner));
+
+                    break TurnEnd::GameOver(GameStatus::OutOfTime, winner);
+                };
+                let color = color_of(player);
+
+                match action {
+                    Action::Move { mov, offer_draw } => break TurnEnd::Moved {
+                        mov,
+                        offer_draw,
+                        elapsed: turn_start.elapsed()
+                    },
+                    Action::Resign =>
+                        break TurnEnd::GameOver(GameStatus::Resign, Some(color.opposite())),
+                    Action::Abort => break TurnEnd::GameOver(GameStatus::Aborted, None),
+                    Action::OfferDraw if draw_offer == Some(color.opposite()) =>
+                        break TurnEnd::GameOver(GameStatus::Draw, None),
+                    Action::OfferDraw => draw_offer = Some(color),
+                    Action::DeclineDraw if draw_offer == Some(color.opposite()) =>
+                        draw_offer = None,
+                    Action::DeclineDraw => continue
+                }
+
+                let state =
+                    self.game_state(&moves, clocks, GameStatus::Started, None, draw_offer);
+
+                hooks.extend(state_hooks(bots, &contexts, clients, &state, None));
+            };
+
+            let (mov, offer_draw, elapsed) = match turn_end {
+                TurnEnd::Moved { mov, offer_draw, elapsed } => (mov, offer_draw, elapsed),
+                TurnEnd::GameOver(status, winner) => break (status, winner)
+            };
+            let clock = &mut clocks[color_index(mover_color)];
+            *clock = (*clock - elapsed.as_millis() as Milliseconds).max(0) + increment;
+
+            // The transport only accepts legal moves, so this does not fail.
+            if position.play(&mov).is_err() {
+                continue;
+            }
+
+            moves.push(mov.to_string());
+
+            let accepted = offer_draw && draw_offer == Some(mover_color.opposite());
+
+            if offer_draw {
+                draw_offer = Some(mover_color);
+            }
+            else if draw_offer == Some(mover_color.opposite()) {
+                draw_offer = None;
+            }
+
+            let repetition_count = repetitions.entry(position.fen_key()).or_insert(0);
+            *repetition_count += 1;
+
+            if accepted {
+                break (GameStatus::Draw, None);
+            }
+
+            if let Some(end) = game_end(&position, *repetition_count, moves.len(), self.max_plies) {
+                break end;
+            }
+        };
+
+        *arena.game.lock().unwrap() = None;
+
+        let state = self.game_state(&moves, clocks, status, winner, None);
+
+        position_tracker.update(&game_id, &state.moves);
+        move_watcher.update(&game_id, &state.moves);
+        hooks.extend(state_hooks(bots, &contexts, clients, &state, None));
+
+        // Hooks which do not finish in time, e.g. because they wait for a move which will never
+        // be played, are dropped.
+        let _ = tokio::time::timeout(HOOK_GRACE_PERIOD, hooks.for_each(|()| async { })).await;
+
+        for player in 0..2 {
+            let game = self.game_start_finish(&game_id, color_of(player), status, winner);
+            let result = GameResult::new(&game, Some((clocks[0], clocks[1])), None);
+
+            bots[player].on_game_finish(&bot_contexts[player], game, result, &clients[player])
+                .await;
+        }
+
+        MatchGame {
+            game_id,
+            bot_color: first_color,
+            outcome: result::outcome(Some(status), winner, Some(first_color)),
+            status
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::elo::SprtHypothesis;
+    use crate::model::game::result::GameOutcome;
+
+    use super::*;
+
+    const FOOLS_MATE: [&str; 4] = ["f2f3", "e7e5", "g2g4", "d8h4"];
+
+    /// Plays the moves of a fixed script at the respective plies, or the first legal move once the
+    /// script ends.
+    struct ScriptedBot {
+        script: Vec<&'static str>
+    }
+
+    impl ScriptedBot {
+        fn new(script: impl IntoIterator<Item = &'static str>) -> ScriptedBot {
+            ScriptedBot {
+                script: script.into_iter().collect()
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Bot for ScriptedBot {
+        async fn on_my_turn(&self, context: &GameContext, state: GameStateEvent,
+                client: &BotClient) {
+            let ply = state.moves.split_whitespace().count();
+            let mov = match self.script.get(ply) {
+                Some(mov) => mov.to_string(),
+                None => context.position().unwrap().legal_moves()[0].to_string()
+            };
+
+            context.make_move(client, mov, false).await.unwrap();
+        }
+    }
+
+    struct ResigningBot;
+
+    #[async_trait::async_trait]
+    impl Bot for ResigningBot {
+        async fn on_my_turn(&self, context: &GameContext, _: GameStateEvent,
+                client: &BotClient) {
+            client.resign_game(context.info.id.clone()).await.unwrap();
+        }
+    }
+
+    struct IdleBot;
+
+    #[async_trait::async_trait]
+    impl Bot for IdleBot { }
+
+    fn outcomes(result: &MatchResult) -> Vec<(GameStatus, GameOutcome)> {
+        result.games.iter().map(|game| (game.status, game.outcome)).collect()
+    }
+
+    #[test]
+    fn checkmate_decides_games_with_alternating_colors() {
+        tokio_test::block_on(async {
+            let self_play =
+                SelfPlay::new(ScriptedBot::new(FOOLS_MATE), ScriptedBot::new(FOOLS_MATE), 2);
+
+            let result = self_play.play().await;
+
+            assert_that!(outcomes(&result)).contains_exactly_in_given_order([
+                (GameStatus::Mate, GameOutcome::Loss),
+                (GameStatus::Mate, GameOutcome::Win)
+            ]);
+            assert_that!(result.games[0].bot_color).is_equal_to(Color::White);
+            assert_that!(result.games[1].bot_color).is_equal_to(Color::Black);
+            assert_that!(result.status).is_equal_to(MatchStatus::Completed);
+        });
+    }
+
+    #[test]
+    fn games_are_adjudicated_as_draws_after_max_plies() {
+        tokio_test::block_on(async {
+            let self_play = SelfPlay::new(ScriptedBot::new([]), ScriptedBot::new([]), 1)
+                .with_max_plies(6);
+
+            let result = self_play.play().await;
+
+            assert_that!(outcomes(&result))
+                .contains_exactly_in_given_order([(GameStatus::Draw, GameOutcome::Draw)]);
+        });
+    }
+
+    #[test]
+    fn games_start_from_openings() {
+        tokio_test::block_on(async {
+            let self_play = SelfPlay::new(ScriptedBot::new([]), ScriptedBot::new(FOOLS_MATE), 1)
+                .with_openings(["f2f3 e7e5 g2g4"])
+                .unwrap();
+
+            let result = self_play.play().await;
+
+            assert_that!(outcomes(&result))
+                .contains_exactly_in_given_order([(GameStatus::Mate, GameOutcome::Loss)]);
+        });
+    }
+
+    #[test]
+    fn invalid_openings_are_rejected() {
+        let self_play = SelfPlay::new(IdleBot, IdleBot, 1).with_openings(["e2e4 e2e4"]);
+
+        assert_that!(self_play.is_err()).is_true();
+    }
+
+    #[test]
+    fn bot_which_does_not_move_loses_on_time() {
+        tokio_test::block_on(async {
+            let self_play = SelfPlay::new(IdleBot, ScriptedBot::new([]), 1).with_clock(0, 0);
+
+            let result = self_play.play().await;
+
+            assert_that!(outcomes(&result))
+                .contains_exactly_in_given_order([(GameStatus::OutOfTime, GameOutcome::Loss)]);
+        });
+    }
+
+    #[test]
+    fn resignation_ends_game() {
+        tokio_test::block_on(async {
+            let self_play = SelfPlay::new(ScriptedBot::new([]), ResigningBot, 2);
+
+            let result = self_play.play().await;
+
+            assert_that!(outcomes(&result)).contains_exactly_in_given_order([
+                (GameStatus::Resign, GameOutcome::Win),
+                (GameStatus::Resign, GameOutcome::Win)
+            ]);
+        });
+    }
+
+    #[test]
+    fn sprt_stops_match_early() {
+        tokio_test::block_on(async {
+            let self_play =
+                SelfPlay::new(ScriptedBot::new(FOOLS_MATE), ScriptedBot::new(FOOLS_MATE), 1000)
+                    .with_sprt(Sprt::new(0.0, 200.0));
+
+            let result = self_play.play().await;
+
+            assert_that!(result.status).is_equal_to(MatchStatus::Concluded(SprtHypothesis::Null));
+            assert_that!(result.games.len()).is_less_than(1000);
+            assert_that!(result.wins().abs_diff(result.losses())).is_less_than_or_equal_to(1);
+        });
+    }
+}
@@ -0,0 +1,666 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::client::BotClient;
+use crate::model::{Milliseconds, Moves};
+use crate::model::game::{Color, GameId, Speed, Variant};
+use crate::model::game::result::GameOutcome;
+use crate::model::user::{PerfKey, Rating, UserId};
+use crate::model::user::crosstable::Crosstable;
+use crate::model::user::preferences::Language;
+use crate::store::{GameRecord, GameStore, StoreResult};
+
+/// The number of half-moves by which games are grouped into openings in [OpponentStats].
+pub const OPENING_PLIES: usize = 4;
+
+const MAX_OPENINGS: usize = 5;
+
+/// How often a certain opening line occurred in the games against an opponent.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct OpeningFrequency {
+
+    /// The first [OPENING_PLIES] half-moves of the games in UCI notation, separated by spaces.
+    pub moves: Moves,
+
+    /// The number of games in which this line was played.
+    pub games: u32
+}
+
+/// The lifetime score between the bot and an opponent on Lichess, as reported by the crosstable
+/// endpoint. Points are given in halves, since draws count half a point.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CrosstableScore {
+    pub games: u32,
+    pub bot_half_points: u32,
+    pub opponent_half_points: u32
+}
+
+impl CrosstableScore {
+
+    fn from_crosstable(crosstable: &Crosstable, bot_id: &str, opponent_id: &str)
+            -> CrosstableScore {
+        let half_points = |user_id: &str| crosstable.users.iter()
+            .find(|(id, _)| id.eq_ignore_ascii_case(user_id))
+            .map(|(_, &points)| (points * 2.0).round() as u32)
+            .unwrap_or(0);
+
+        CrosstableScore {
+            games: crosstable.games,
+            bot_half_points: half_points(bot_id),
+            opponent_half_points: half_points(opponent_id)
+        }
+    }
+
+    /// The total points scored by the bot.
+    pub fn bot_score(&self) -> f64 {
+        self.bot_half_points as f64 / 2.0
+    }
+
+    /// The total points scored by the opponent.
+    pub fn opponent_score(&self) -> f64 {
+        self.opponent_half_points as f64 / 2.0
+    }
+}
+
+/// Statistics about the games of the bot against a specific opponent, aggregated from a
+/// [GameStore] and, if available, the Lichess crosstable. Available during a game via
+/// [GameContext::opponent_stats](crate::context::GameContext::opponent_stats) if enabled with
+/// [BotRunner::with_opponent_stats](crate::runner::BotRunner::with_opponent_stats).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct OpponentStats {
+
+    /// The user ID of the opponent.
+    pub opponent_id: UserId,
+
+    /// The number of games against the opponent recorded in the game store.
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+
+    /// The most common opening lines in the recorded games, most frequent first.
+    pub common_openings: Vec<OpeningFrequency>,
+
+    /// The average time the opponent spent per move in recorded games with a clock, if any.
+    pub average_move_time: Option<Milliseconds>,
+
+    /// The lifetime score against the opponent according to Lichess, if it could be fetched.
+    pub crosstable: Option<CrosstableScore>,
+
+    /// The preferred language of the opponent according to their profile, if known. Can be used
+    /// to localize chat messages, see [ChatLocalizer](crate::chat_i18n::ChatLocalizer).
+    pub language: Option<Language>
+}
+
+fn opponent_move_time(game: &GameRecord) -> Option<(Milliseconds, u32)> {
+    let bot_color = game.bot_color?;
+    let clock = game.clock?;
+    let limit = clock.limit? as Milliseconds * 1000;
+    let increment = clock.increment.unwrap_or(0) as Milliseconds * 1000;
+    let remaining = match bot_color {
+        Color::White => game.black_time?,
+        Color::Black => game.white_time?
+    };
+    let plies = game.moves.split_whitespace().count();
+    let opponent_moves = match bot_color {
+        Color::White => plies / 2,
+        Color::Black => plies.div_ceil(2)
+    } as Milliseconds;
+
+    if opponent_moves == 0 {
+        return None;
+    }
+
+    let used = (limit + increment * opponent_moves - remaining).max(0);
+
+    Some((used, opponent_moves as u32))
+}
+
+impl OpponentStats {
+
+    /// Aggregates statistics from the given recorded games. Games which are not against the given
+    /// opponent are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `opponent_id`: The user ID of the opponent.
+    /// * `games`: The recorded games to aggregate, e.g. from [GameStore::games_against].
+    pub fn from_games(opponent_id: impl Into<UserId>, games: &[GameRecord]) -> OpponentStats {
+        let opponent_id = opponent_id.into();
+        let mut stats = OpponentStats {
+            opponent_id,
+            ..OpponentStats::default()
+        };
+        let mut openings = HashMap::<Moves, u32>::new();
+        let mut total_move_time = 0;
+        let mut total_moves = 0;
+
+        let games = games.iter()
+            .filter(|game| game.opponent.as_ref()
+                .and_then(|opponent| opponent.id.as_deref())
+                .is_some_and(|id| id == stats.opponent_id));
+
+        for game in games {
+            stats.games += 1;
+
+            match game.outcome {
+                GameOutcome::Win => stats.wins += 1,
+                GameOutcome::Draw => stats.draws += 1,
+                GameOutcome::Loss => stats.losses += 1,
+                GameOutcome::Aborted | GameOutcome::Unknown => { }
+            }
+
+            let opening = game.moves.split_whitespace()
+                .take(OPENING_PLIES)
+                .collect::<Vec<_>>();
+
+            if opening.len() == OPENING_PLIES {
+                *openings.entry(opening.join(" ")).or_default() += 1;
+            }
+
+            if let Some((move_time, moves)) = opponent_move_time(game) {
+                total_move_time += move_time;
+                total_moves += moves;
+            }
+        }
+
+        let mut common_openings = openings.into_iter()
+            .map(|(moves, games)| OpeningFrequency { moves, games })
+            .collect::<Vec<_>>();
+        common_openings.sort_by(|opening_1, opening_2| opening_2.games.cmp(&opening_1.games)
+            .then_with(|| opening_1.moves.cmp(&opening_2.moves)));
+        common_openings.truncate(MAX_OPENINGS);
+
+        stats.common_openings = common_openings;
+
+        if total_moves > 0 {
+            stats.average_move_time = Some(total_move_time / total_moves as Milliseconds);
+        }
+
+        stats
+    }
+
+    /// The score of the bot in the recorded games in the range `[0, 1]`, where wins count 1 and
+    /// draws count 0.5, or [None] if no decisive or drawn games are recorded.
+    pub fn score(&self) -> Option<f64> {
+        let decided_games = self.wins + self.draws + self.losses;
+
+        if decided_games == 0 {
+            None
+        }
+        else {
+            Some((self.wins as f64 + self.draws as f64 * 0.5) / decided_games as f64)
+        }
+    }
+}
+
+/// Collects the [OpponentStats] of the bot against the given opponent.
+pub(crate) async fn collect_opponent_stats(game_store: Option<&dyn GameStore>,
+        client: &BotClient, bot_id: &str, opponent_id: &str) -> OpponentStats {
+    // TODO enable error handling
+    let games = game_store
+        .and_then(|game_store| game_store.games_against(opponent_id).ok())
+        .unwrap_or_default();
+    let mut stats = OpponentStats::from_games(opponent_id, &games);
+
+    // TODO enable error handling
+    if let Ok(crosstable) = client.get_crosstable(bot_id, opponent_id).await {
+        stats.crosstable = Some(CrosstableScore::from_crosstable(&crosstable, bot_id, opponent_id));
+    }
+
+    // TODO enable error handling
+    if let Ok(profile) = client.get_profile(opponent_id.to_owned()).await {
+        stats.language = profile.language;
+    }
+
+    stats
+}
+
+/// The change of the bot's rating in one category caused by a finished rated game.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RatingUpdate {
+
+    /// The ID of the game which caused the change, if known.
+    pub game_id: Option<GameId>,
+
+    /// The rating category which changed.
+    pub perf: PerfKey,
+
+    /// The rating of the bot before the game.
+    pub old: Rating,
+
+    /// The rating of the bot after the game.
+    pub new: Rating
+}
+
+impl RatingUpdate {
+
+    /// The rating points gained by the bot, which is negative if the bot lost rating.
+    pub fn delta(&self) -> Rating {
+        self.new - self.old
+    }
+}
+
+/// Records every [RatingUpdate] of a [BotRunner](crate::runner::BotRunner) it is registered with
+/// using [BotRunner::with_rating_history](crate::runner::BotRunner::with_rating_history). Keep
+/// another reference to read the history while the bot is running.
+#[derive(Debug, Default)]
+pub struct RatingHistory {
+    updates: Mutex<Vec<RatingUpdate>>
+}
+
+impl RatingHistory {
+
+    /// Creates a new, empty rating history.
+    pub fn new() -> RatingHistory {
+        RatingHistory::default()
+    }
+
+    /// Gets all recorded updates in the order in which they occurred.
+    pub fn updates(&self) -> Vec<RatingUpdate> {
+        self.updates.lock().unwrap().clone()
+    }
+
+    /// Gets the most recent update in the given rating category, or [None] if none was recorded.
+    pub fn latest(&self, perf: PerfKey) -> Option<RatingUpdate> {
+        self.updates.lock().unwrap().iter()
+            .rev()
+            .find(|update| update.perf == perf)
+            .cloned()
+    }
+
+    /// The sum of all recorded rating changes in the given rating category.
+    pub fn total_delta(&self, perf: PerfKey) -> Rating {
+        self.updates.lock().unwrap().iter()
+            .filter(|update| update.perf == perf)
+            .map(RatingUpdate::delta)
+            .sum()
+    }
+
+    pub(crate) fn record(&self, update: RatingUpdate) {
+        self.updates.lock().unwrap().push(update);
+    }
+}
+
+/// The category of games for which [PerformanceStats] aggregates results, i.e. games of one speed
+/// and variant against opponents in one rating band.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PerformanceBucket {
+    pub speed: Speed,
+    pub variant: Variant,
+
+    /// The lower bound of the rating band of the opponent, or [None] if the rating of the
+    /// opponent is unknown, e.g. for games against the Lichess AI.
+    pub rating_band: Option<Rating>
+}
+
+/// The numbers of games won, drawn, and lost by the bot within a [PerformanceBucket]. All rates
+/// are 0 if there are no games.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct WinRates {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32
+}
+
+impl WinRates {
+
+    /// The total number of games.
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    fn rate(&self, count: u32) -> f64 {
+        if self.games() == 0 {
+            0.0
+        }
+        else {
+            count as f64 / self.games() as f64
+        }
+    }
+
+    /// The fraction of games won by the bot in the range `[0, 1]`.
+    pub fn win_rate(&self) -> f64 {
+        self.rate(self.wins)
+    }
+
+    /// The fraction of drawn games in the range `[0, 1]`.
+    pub fn draw_rate(&self) -> f64 {
+        self.rate(self.draws)
+    }
+
+    /// The fraction of games lost by the bot in the range `[0, 1]`.
+    pub fn loss_rate(&self) -> f64 {
+        self.rate(self.losses)
+    }
+}
+
+/// Rolling win, draw, and loss rates of the bot, bucketed by speed, variant, and rating band of
+/// the opponent. Only the most recent games of every bucket are considered, so the rates follow
+/// changes in the strength of the bot. Can be used to select opponents against which the bot
+/// performs well or which provide a challenge.
+///
+/// Register with a [BotRunner](crate::runner::BotRunner) using
+/// [BotRunner::with_performance_stats](crate::runner::BotRunner::with_performance_stats) to record
+/// every finished game, and keep another reference to read the rates. To continue with the rates
+/// from earlier runs, load the games of a [GameStore] with [PerformanceStats::load_from_store].
+#[derive(Debug)]
+pub struct PerformanceStats {
+    window: usize,
+    band_width: Rating,
+    buckets: Mutex<HashMap<PerformanceBucket, VecDeque<GameOutcome>>>
+}
+
+impl PerformanceStats {
+
+    /// Creates new, empty statistics which consider the last 50 games of every bucket and use
+    /// rating bands of 200 points.
+    pub fn new() -> PerformanceStats {
+        PerformanceStats {
+            window: 50,
+            band_width: 200,
+            buckets: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Sets the number of most recent games of every bucket which are considered. Values below 1
+    /// are treated as 1. The statistics are returned for chaining.
+    pub fn with_window(mut self, window: usize) -> PerformanceStats {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Sets the width of the rating bands of opponents. Values below 1 are treated as 1. The
+    /// statistics are returned for chaining.
+    pub fn with_band_width(mut self, band_width: Rating) -> PerformanceStats {
+        self.band_width = band_width.max(1);
+        self
+    }
+
+    /// Gets the bucket of games of the given speed and variant against an opponent with the given
+    /// rating.
+    pub fn bucket(&self, speed: Speed, variant: Variant, opponent_rating: Option<Rating>)
+            -> PerformanceBucket {
+        PerformanceBucket {
+            speed,
+            variant,
+            rating_band: opponent_rating
+                .map(|rating| rating.div_euclid(self.band_width) * self.band_width)
+        }
+    }
+
+    /// Gets the rates within the given bucket, or [None] if no games were recorded in it.
+    pub fn win_rates(&self, bucket: &PerformanceBucket) -> Option<WinRates> {
+        self.buckets.lock().unwrap().get(bucket).map(count_outcomes)
+    }
+
+    /// Gets the rates of all buckets in which games were recorded.
+    pub fn all_win_rates(&self) -> HashMap<PerformanceBucket, WinRates> {
+        self.buckets.lock().unwrap().iter()
+            .map(|(&bucket, outcomes)| (bucket, count_outcomes(outcomes)))
+            .collect()
+    }
+
+    /// Records the given finished games in the order of their creation. Games which were aborted
+    /// or whose outcome is unknown are ignored.
+    pub fn load_games(&self, games: &[GameRecord]) {
+        let mut games = games.iter().collect::<Vec<_>>();
+        games.sort_by_key(|game| game.created_at);
+
+        for game in games {
+            self.record(game);
+        }
+    }
+
+    /// Records all games of the given [GameStore] like [PerformanceStats::load_games].
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError](crate::store::StoreError) that occurs while reading the games.
+    pub fn load_from_store(&self, game_store: &dyn GameStore) -> StoreResult<()> {
+        self.load_games(&game_store.games()?);
+        Ok(())
+    }
+
+    pub(crate) fn record(&self, game: &GameRecord) {
+        if !matches!(game.outcome, GameOutcome::Win | GameOutcome::Draw | GameOutcome::Loss) {
+            return;
+        }
+
+        let opponent_rating = game.opponent.as_ref().and_then(|opponent| opponent.rating);
+        let variant = game.variant.unwrap_or(Variant::Standard);
+        let bucket = self.bucket(game.speed, variant, opponent_rating);
+        let mut buckets = self.buckets.lock().unwrap();
+        let outcomes = buckets.entry(bucket).or_default();
+
+        outcomes.push_back(game.outcome);
+
+        if outcomes.len() > self.window {
+            outcomes.pop_front();
+        }
+    }
+}
+
+impl Default for PerformanceStats {
+    fn default() -> PerformanceStats {
+        PerformanceStats::new()
+    }
+}
+
+fn count_outcomes(outcomes: &VecDeque<GameOutcome>) -> WinRates {
+    let mut win_rates = WinRates::default();
+
+    for outcome in outcomes {
+        match outcome {
+            GameOutcome::Win => win_rates.wins += 1,
+            GameOutcome::Draw => win_rates.draws += 1,
+            GameOutcome::Loss => win_rates.losses += 1,
+            GameOutcome::Aborted | GameOutcome::Unknown => { }
+        }
+    }
+
+    win_rates
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::game::Clock;
+    use crate::store::tests::{test_game_info, test_result};
+
+    use super::*;
+
+    fn record(opponent_id: &str, bot_color: Color, outcome: GameOutcome, moves: &str)
+            -> GameRecord {
+        let info = match bot_color {
+            Color::White => test_game_info("testGameId", "bot", opponent_id),
+            Color::Black => test_game_info("testGameId", opponent_id, "bot")
+        };
+
+        GameRecord::new(&info, moves, &test_result(outcome, Some(bot_color)))
+    }
+
+    #[test]
+    fn results_are_counted_for_opponent_only() {
+        let games = [
+            record("alice", Color::White, GameOutcome::Win, ""),
+            record("alice", Color::Black, GameOutcome::Draw, ""),
+            record("alice", Color::White, GameOutcome::Loss, ""),
+            record("alice", Color::White, GameOutcome::Aborted, ""),
+            record("bob", Color::White, GameOutcome::Win, "")
+        ];
+
+        let stats = OpponentStats::from_games("alice", &games);
+
+        assert_that!(stats.games).is_equal_to(4);
+        assert_that!(stats.wins).is_equal_to(1);
+        assert_that!(stats.draws).is_equal_to(1);
+        assert_that!(stats.losses).is_equal_to(1);
+        assert_that!(stats.score()).contains(0.5);
+    }
+
+    #[test]
+    fn score_is_none_without_games() {
+        assert_that!(OpponentStats::from_games("alice", &[]).score()).is_none();
+    }
+
+    #[test]
+    fn common_openings_are_sorted_by_frequency() {
+        let games = [
+            record("alice", Color::White, GameOutcome::Win, "d2d4 d7d5 c2c4 e7e6"),
+            record("alice", Color::White, GameOutcome::Win, "e2e4 e7e5 g1f3 b8c6 f1b5"),
+            record("alice", Color::White, GameOutcome::Win, "e2e4 e7e5 g1f3 b8c6 f1c4"),
+            record("alice", Color::White, GameOutcome::Win, "e2e4")
+        ];
+
+        let stats = OpponentStats::from_games("alice", &games);
+
+        assert_that!(stats.common_openings).contains_exactly_in_given_order([
+            OpeningFrequency {
+                moves: "e2e4 e7e5 g1f3 b8c6".to_owned(),
+                games: 2
+            },
+            OpeningFrequency {
+                moves: "d2d4 d7d5 c2c4 e7e6".to_owned(),
+                games: 1
+            }
+        ]);
+    }
+
+    #[rstest]
+    #[case::bot_white(Color::White, "e2e4 e7e5 g1f3 b8c6", 150_000, Some(16_000))]
+    #[case::bot_black(Color::Black, "e2e4 e7e5 g1f3", 150_000, Some(16_000))]
+    #[case::no_opponent_moves(Color::White, "e2e4", 180_000, None)]
+    fn average_move_time_uses_clock_and_increment(#[case] bot_color: Color,
+            #[case] moves: &str, #[case] opponent_time: Milliseconds,
+            #[case] expected_average: Option<Milliseconds>) {
+        let mut game = record("alice", bot_color, GameOutcome::Win, moves);
+        game.clock = Some(Clock {
+            limit: Some(180),
+            increment: Some(1)
+        });
+
+        match bot_color {
+            Color::White => game.black_time = Some(opponent_time),
+            Color::Black => game.white_time = Some(opponent_time)
+        }
+
+        let stats = OpponentStats::from_games("alice", &[game]);
+
+        assert_that!(stats.average_move_time).is_equal_to(expected_average);
+    }
+
+    #[test]
+    fn crosstable_score_is_read_case_insensitively() {
+        let crosstable = Crosstable {
+            users: HashMap::from([
+                ("Bot".to_owned(), 3.5),
+                ("alice".to_owned(), 1.5)
+            ]),
+            games: 5
+        };
+
+        let score = CrosstableScore::from_crosstable(&crosstable, "bot", "alice");
+
+        assert_that!(score.games).is_equal_to(5);
+        assert_that!(score.bot_score()).is_equal_to(3.5);
+        assert_that!(score.opponent_score()).is_equal_to(1.5);
+    }
+
+    fn rating_update(perf: PerfKey, old: Rating, new: Rating) -> RatingUpdate {
+        RatingUpdate {
+            game_id: None,
+            perf,
+            old,
+            new
+        }
+    }
+
+    #[test]
+    fn rating_update_delta() {
+        assert_that!(rating_update(PerfKey::Blitz, 1500, 1492).delta()).is_equal_to(-8);
+    }
+
+    #[test]
+    fn rating_history_aggregates_updates_per_category() {
+        let history = RatingHistory::new();
+
+        history.record(rating_update(PerfKey::Blitz, 1500, 1508));
+        history.record(rating_update(PerfKey::Bullet, 1400, 1390));
+        history.record(rating_update(PerfKey::Blitz, 1508, 1503));
+
+        assert_that!(history.updates().len()).is_equal_to(3);
+        assert_that!(history.latest(PerfKey::Blitz))
+            .contains(rating_update(PerfKey::Blitz, 1508, 1503));
+        assert_that!(history.latest(PerfKey::Rapid)).is_none();
+        assert_that!(history.total_delta(PerfKey::Blitz)).is_equal_to(3);
+        assert_that!(history.total_delta(PerfKey::Bullet)).is_equal_to(-10);
+    }
+
+    fn rated_record(outcome: GameOutcome, speed: Speed, opponent_rating: Rating,
+            created_at: i64) -> GameRecord {
+        let mut game = record("alice", Color::White, outcome, "");
+        game.speed = speed;
+        game.created_at = created_at;
+        game.opponent.as_mut().unwrap().rating = Some(opponent_rating);
+        game
+    }
+
+    #[test]
+    fn win_rates_are_bucketed_by_speed_and_rating_band() {
+        let stats = PerformanceStats::new().with_band_width(100);
+        stats.load_games(&[
+            rated_record(GameOutcome::Win, Speed::Blitz, 1510, 1),
+            rated_record(GameOutcome::Draw, Speed::Blitz, 1599, 2),
+            rated_record(GameOutcome::Loss, Speed::Blitz, 1600, 3),
+            rated_record(GameOutcome::Loss, Speed::Bullet, 1550, 4),
+            rated_record(GameOutcome::Aborted, Speed::Blitz, 1550, 5)
+        ]);
+
+        let bucket = stats.bucket(Speed::Blitz, Variant::Standard, Some(1550));
+        let win_rates = stats.win_rates(&bucket).unwrap();
+
+        assert_that!(bucket.rating_band).contains(1500);
+        assert_that!(win_rates).is_equal_to(WinRates {
+            wins: 1,
+            draws: 1,
+            losses: 0
+        });
+        assert_that!(win_rates.win_rate()).is_equal_to(0.5);
+        assert_that!(stats.all_win_rates()).has_length(3);
+    }
+
+    #[test]
+    fn only_most_recent_games_are_considered() {
+        let stats = PerformanceStats::new().with_window(2);
+        stats.load_games(&[
+            rated_record(GameOutcome::Win, Speed::Blitz, 1500, 3),
+            rated_record(GameOutcome::Loss, Speed::Blitz, 1500, 1),
+            rated_record(GameOutcome::Draw, Speed::Blitz, 1500, 2)
+        ]);
+
+        let bucket = stats.bucket(Speed::Blitz, Variant::Standard, Some(1500));
+
+        assert_that!(stats.win_rates(&bucket)).contains(WinRates {
+            wins: 1,
+            draws: 1,
+            losses: 0
+        });
+    }
+
+    #[test]
+    fn games_without_opponent_rating_have_no_rating_band() {
+        let stats = PerformanceStats::new();
+        let mut game = record("alice", Color::White, GameOutcome::Win, "");
+        game.opponent.as_mut().unwrap().rating = None;
+        stats.load_games(&[game]);
+
+        let bucket = stats.bucket(Speed::Blitz, Variant::Standard, None);
+
+        assert_that!(stats.win_rates(&bucket).map(|win_rates| win_rates.games())).contains(1);
+    }
+}
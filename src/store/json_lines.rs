@@ -0,0 +1,230 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::store::{GameRecord, GameStore, RunnerSnapshot, StoreResult, UserLists};
+
+/// A [GameStore] which appends every game as one line of JSON to a file. The file is created if it
+/// does not exist yet and can be inspected or processed with any tool that reads JSON lines.
+/// [UserLists] are saved as JSON next to it, in a file with the extension `users.json`, and the
+/// latest [RunnerSnapshot] in a file with the extension `snapshot.json`.
+#[derive(Debug)]
+pub struct JsonLinesGameStore {
+    path: PathBuf,
+    lock: Mutex<()>
+}
+
+impl JsonLinesGameStore {
+
+    /// Opens the JSON lines file at the given path as a game store, creating it if necessary.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the file in which to store games.
+    ///
+    /// # Errors
+    ///
+    /// [StoreError::Io](crate::store::StoreError::Io) if the file cannot be created or opened.
+    pub fn open(path: impl AsRef<Path>) -> StoreResult<JsonLinesGameStore> {
+        let path = path.as_ref().to_owned();
+
+        OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(JsonLinesGameStore {
+            path,
+            lock: Mutex::new(())
+        })
+    }
+
+    /// The path of the file in which games are stored.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path of the file in which [UserLists] are stored.
+    pub fn user_lists_path(&self) -> PathBuf {
+        self.path.with_extension("users.json")
+    }
+
+    /// The path of the file in which the latest [RunnerSnapshot] is stored.
+    pub fn snapshot_path(&self) -> PathBuf {
+        self.path.with_extension("snapshot.json")
+    }
+}
+
+impl GameStore for JsonLinesGameStore {
+    fn record_game(&self, record: &GameRecord) -> StoreResult<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn games(&self) -> StoreResult<Vec<GameRecord>> {
+        let content = {
+            let _guard = self.lock.lock().unwrap();
+            fs::read_to_string(&self.path)?
+        };
+
+        let games = content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(games)
+    }
+
+    fn user_lists(&self) -> StoreResult<UserLists> {
+        let path = self.user_lists_path();
+        let _guard = self.lock.lock().unwrap();
+
+        if !path.exists() {
+            return Ok(UserLists::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save_user_lists(&self, user_lists: &UserLists) -> StoreResult<()> {
+        let json = serde_json::to_string_pretty(user_lists)?;
+        let _guard = self.lock.lock().unwrap();
+        fs::write(self.user_lists_path(), json)?;
+
+        Ok(())
+    }
+
+    fn snapshot(&self) -> StoreResult<Option<RunnerSnapshot>> {
+        let path = self.snapshot_path();
+        let _guard = self.lock.lock().unwrap();
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    fn save_snapshot(&self, snapshot: &RunnerSnapshot) -> StoreResult<()> {
+        let json = serde_json::to_string_pretty(snapshot)?;
+        let _guard = self.lock.lock().unwrap();
+        fs::write(self.snapshot_path(), json)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::model::game::Color;
+    use crate::model::game::result::GameOutcome;
+    use crate::store::tests::{test_game_info, test_result};
+
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("libot-json-lines-{}-{name}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        path
+    }
+
+    #[test]
+    fn open_creates_empty_store() {
+        let path = test_path("empty");
+
+        let store = JsonLinesGameStore::open(&path).unwrap();
+
+        assert_that!(store.games().unwrap()).is_empty();
+        assert_that!(path.exists()).is_true();
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn recorded_games_are_persisted() {
+        let path = test_path("persisted");
+        let info = test_game_info("testGameId", "bot", "opponent");
+        let record = GameRecord::new(&info, "e2e4 e7e5",
+            &test_result(GameOutcome::Loss, Some(Color::Black)));
+
+        {
+            let store = JsonLinesGameStore::open(&path).unwrap();
+            store.record_game(&record).unwrap();
+            store.record_game(&record).unwrap();
+        }
+
+        let store = JsonLinesGameStore::open(&path).unwrap();
+
+        assert_that!(store.games().unwrap())
+            .contains_exactly_in_given_order([record.clone(), record]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn user_lists_are_persisted() {
+        let path = test_path("user-lists");
+        let mut user_lists = UserLists::default();
+        user_lists.allowed.insert("friend".to_owned());
+        user_lists.blocked.insert("spammer".to_owned());
+
+        {
+            let store = JsonLinesGameStore::open(&path).unwrap();
+
+            assert_that!(store.user_lists().unwrap()).is_equal_to(UserLists::default());
+
+            store.save_user_lists(&user_lists).unwrap();
+        }
+
+        let store = JsonLinesGameStore::open(&path).unwrap();
+
+        assert_that!(store.user_lists().unwrap()).is_equal_to(user_lists);
+
+        fs::remove_file(store.user_lists_path()).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn snapshot_is_persisted() {
+        let path = test_path("snapshot");
+        let mut snapshot = RunnerSnapshot::default();
+        snapshot.active_games.insert("testGameId".to_owned());
+        snapshot.chatted_games.insert("testGameId".to_owned());
+
+        {
+            let store = JsonLinesGameStore::open(&path).unwrap();
+
+            assert_that!(store.snapshot().unwrap()).is_none();
+
+            store.save_snapshot(&snapshot).unwrap();
+        }
+
+        let store = JsonLinesGameStore::open(&path).unwrap();
+
+        assert_that!(store.snapshot().unwrap()).contains(snapshot);
+
+        fs::remove_file(store.snapshot_path()).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn games_fails_for_corrupt_file() {
+        let path = test_path("corrupt");
+        fs::write(&path, "not json\n").unwrap();
+
+        let store = JsonLinesGameStore::open(&path).unwrap();
+
+        assert_that!(store.games()).is_err();
+
+        fs::remove_file(path).unwrap();
+    }
+}
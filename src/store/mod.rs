@@ -0,0 +1,475 @@
+use std::collections::BTreeSet;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use serde_json::Error as JsonError;
+
+use thiserror::Error;
+
+use crate::chess::pgn::{PgnError, PgnGame, PgnNode};
+use crate::chess::position::Position;
+use crate::model::{Milliseconds, Moves, Timestamp};
+use crate::model::game::{Clock, Color, GameId, GameInfo, GameStatus, Speed, Variant};
+use crate::model::game::event::GameEventPlayer;
+use crate::model::game::result::{GameOutcome, GameResult};
+use crate::model::user::{AiLevel, PlayerRef, Rating, UserId};
+
+pub mod json_lines;
+pub mod pgn_archive;
+
+/// An error that occurs when accessing a [GameStore].
+#[derive(Debug, Error)]
+pub enum StoreError {
+
+    #[error("error accessing game store: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("error serializing or deserializing game record: {0}")]
+    Json(#[from] JsonError),
+
+    #[error("error reading archived PGN: {0}")]
+    Pgn(#[from] PgnError)
+}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// The opponent of the bot in a [GameRecord].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpponentRecord {
+    pub id: Option<UserId>,
+    pub name: Option<String>,
+    pub rating: Option<Rating>,
+    pub ai_level: Option<AiLevel>
+}
+
+/// A finished game as recorded in a [GameStore].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameRecord {
+    pub game_id: GameId,
+    pub created_at: Timestamp,
+    pub variant: Option<Variant>,
+    pub speed: Speed,
+    pub clock: Option<Clock>,
+    pub rated: bool,
+
+    /// The color played by the bot, if known.
+    pub bot_color: Option<Color>,
+
+    /// The opponent of the bot, if the bot's color is known.
+    pub opponent: Option<OpponentRecord>,
+
+    /// The [GameOutcome] from the perspective of the bot.
+    pub outcome: GameOutcome,
+    pub status: Option<GameStatus>,
+
+    /// The rating change of the bot as a result of this game, if known.
+    pub rating_diff: Option<Rating>,
+
+    /// The time remaining on White's clock at the end of the game, if known.
+    pub white_time: Option<Milliseconds>,
+
+    /// The time remaining on Black's clock at the end of the game, if known.
+    pub black_time: Option<Milliseconds>,
+
+    /// The initial position of the game in FEN, or `startpos` for the standard position.
+    pub initial_fen: String,
+
+    /// The moves of the game in UCI notation, separated by spaces.
+    pub moves: Moves,
+
+    /// The game in PGN, if the moves could be converted to SAN.
+    pub pgn: Option<String>
+}
+
+fn opponent_record(player: &GameEventPlayer) -> OpponentRecord {
+    OpponentRecord {
+        id: player.id.clone(),
+        name: player.name.clone(),
+        rating: player.rating,
+        ai_level: player.ai_level
+    }
+}
+
+fn result_tag(result: &GameResult) -> &'static str {
+    match (result.outcome, result.bot_color) {
+        (GameOutcome::Draw, _) => "1/2-1/2",
+        (GameOutcome::Win, Some(Color::White)) | (GameOutcome::Loss, Some(Color::Black)) => "1-0",
+        (GameOutcome::Win, Some(Color::Black)) | (GameOutcome::Loss, Some(Color::White)) => "0-1",
+        _ => "*"
+    }
+}
+
+fn player_name(player: PlayerRef) -> String {
+    match player {
+        PlayerRef::User { name, .. } => name,
+        PlayerRef::Ai(ai_level) => format!("Stockfish level {ai_level}"),
+        PlayerRef::Anonymous => "?".to_owned()
+    }
+}
+
+fn initial_position(info: &GameInfo) -> Option<Position> {
+    match info.variant {
+        None | Some(Variant::Standard | Variant::Chess960 | Variant::FromPosition) => { },
+        Some(_) => return None
+    }
+
+    if info.initial_fen == "startpos" {
+        Some(Position::standard())
+    }
+    else {
+        Position::from_fen(&info.initial_fen).ok()
+    }
+}
+
+fn game_pgn(info: &GameInfo, moves: &str, result: &GameResult) -> Option<String> {
+    let mut position = initial_position(info)?;
+    let mut nodes = Vec::new();
+
+    for mov in moves.split_whitespace() {
+        let mov = mov.parse().ok()?;
+
+        nodes.push(PgnNode {
+            san: position.to_san(&mov).ok()?,
+            variations: Vec::new()
+        });
+        position.play(&mov).ok()?;
+    }
+
+    let mode = if info.rated { "Rated" } else { "Casual" };
+    let speed = serde_json::to_value(info.speed).ok()?;
+    let mut tags = vec![
+        ("Event".to_owned(), format!("{mode} {} game", speed.as_str().unwrap_or("?"))),
+        ("Site".to_owned(), info.url()),
+        ("White".to_owned(), player_name(info.player_ref(Color::White))),
+        ("Black".to_owned(), player_name(info.player_ref(Color::Black))),
+        ("Result".to_owned(), result_tag(result).to_owned())
+    ];
+
+    if let Some(rating) = info.player_ref(Color::White).rating() {
+        tags.push(("WhiteElo".to_owned(), rating.to_string()));
+    }
+
+    if let Some(rating) = info.player_ref(Color::Black).rating() {
+        tags.push(("BlackElo".to_owned(), rating.to_string()));
+    }
+
+    let time_control = match info.clock {
+        Some(Clock { limit: Some(limit), increment }) =>
+            format!("{limit}+{}", increment.unwrap_or(0)),
+        _ => "-".to_owned()
+    };
+    tags.push(("TimeControl".to_owned(), time_control));
+
+    if info.initial_fen != "startpos" {
+        tags.push(("FEN".to_owned(), info.initial_fen.clone()));
+        tags.push(("SetUp".to_owned(), "1".to_owned()));
+    }
+
+    Some(PgnGame {
+        tags,
+        moves: nodes
+    }.to_string())
+}
+
+impl GameRecord {
+
+    pub(crate) fn new(info: &GameInfo, moves: &str, result: &GameResult) -> GameRecord {
+        let opponent = result.bot_color
+            .map(|bot_color| opponent_record(info.player(bot_color.opposite())));
+
+        GameRecord {
+            game_id: info.id.clone(),
+            created_at: info.created_at,
+            variant: info.variant,
+            speed: info.speed,
+            clock: info.clock,
+            rated: info.rated,
+            bot_color: result.bot_color,
+            opponent,
+            outcome: result.outcome,
+            status: result.status,
+            rating_diff: result.rating_diff,
+            white_time: result.white_time,
+            black_time: result.black_time,
+            initial_fen: info.initial_fen.clone(),
+            moves: moves.to_owned(),
+            pgn: game_pgn(info, moves, result)
+        }
+    }
+}
+
+/// The users whose challenges the runner always or never considers, as managed with
+/// [BotRunner::allow_user](crate::runner::BotRunner::allow_user) and
+/// [BotRunner::block_user](crate::runner::BotRunner::block_user). A user is on at most one of the
+/// lists.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserLists {
+
+    /// The users which are exempt from the challenge rate limit.
+    #[serde(default)]
+    pub allowed: BTreeSet<UserId>,
+
+    /// The users whose challenges are always declined.
+    #[serde(default)]
+    pub blocked: BTreeSet<UserId>
+}
+
+/// A snapshot of the state of a [BotRunner](crate::runner::BotRunner), which is saved
+/// periodically if enabled with
+/// [BotRunner::with_state_snapshots](crate::runner::BotRunner::with_state_snapshots), so a
+/// restarted process can reconcile with the games Lichess still reports as ongoing.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnerSnapshot {
+
+    /// The IDs of the games the runner was playing.
+    #[serde(default)]
+    pub active_games: BTreeSet<GameId>,
+
+    /// The IDs of the challenges the bot accepted whose games had not finished.
+    #[serde(default)]
+    pub accepted_challenges: BTreeSet<GameId>,
+
+    /// The IDs of the games in whose chat the bot had sent a message.
+    #[serde(default)]
+    pub chatted_games: BTreeSet<GameId>
+}
+
+/// A persistent storage of finished games. If a store is registered with
+/// [BotRunner::with_game_store](crate::runner::BotRunner::with_game_store), the runner records
+/// every finished game in it. Bots can keep a reference to the same store to query past games,
+/// e.g. for opponent preparation or statistics.
+pub trait GameStore : Send + Sync {
+
+    /// Adds the given game to this store.
+    ///
+    /// # Arguments
+    ///
+    /// * `record`: The [GameRecord] to store.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError] if the game cannot be stored.
+    fn record_game(&self, record: &GameRecord) -> StoreResult<()>;
+
+    /// Queries all games in this store in the order in which they were recorded.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError] if the games cannot be read.
+    fn games(&self) -> StoreResult<Vec<GameRecord>>;
+
+    /// Queries all games in this store against the opponent with the given user ID, in the order
+    /// in which they were recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `opponent_id`: The user ID of the opponent whose games to query.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError] if the games cannot be read.
+    fn games_against(&self, opponent_id: &str) -> StoreResult<Vec<GameRecord>> {
+        let games = self.games()?.into_iter()
+            .filter(|game| game.opponent.as_ref()
+                .and_then(|opponent| opponent.id.as_deref())
+                .is_some_and(|id| id == opponent_id))
+            .collect();
+
+        Ok(games)
+    }
+
+    /// Loads the [UserLists] last saved with [GameStore::save_user_lists]. By default, no lists
+    /// are persisted, so empty lists are returned.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError] if the lists cannot be read.
+    fn user_lists(&self) -> StoreResult<UserLists> {
+        Ok(UserLists::default())
+    }
+
+    /// Saves the given [UserLists], replacing any previously saved lists. By default, the lists
+    /// are not persisted.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError] if the lists cannot be saved.
+    fn save_user_lists(&self, _user_lists: &UserLists) -> StoreResult<()> {
+        Ok(())
+    }
+
+    /// Loads the [RunnerSnapshot] last saved with [GameStore::save_snapshot], or [None] if no
+    /// snapshot was saved. By default, no snapshots are persisted, so [None] is returned.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError] if the snapshot cannot be read.
+    fn snapshot(&self) -> StoreResult<Option<RunnerSnapshot>> {
+        Ok(None)
+    }
+
+    /// Saves the given [RunnerSnapshot], replacing any previously saved snapshot. By default, the
+    /// snapshot is not persisted.
+    ///
+    /// # Errors
+    ///
+    /// Any [StoreError] if the snapshot cannot be saved.
+    fn save_snapshot(&self, _snapshot: &RunnerSnapshot) -> StoreResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use std::sync::Mutex;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use crate::model::game::GamePerf;
+
+    use super::*;
+
+    pub(crate) fn test_player(id: &str, rating: Rating) -> GameEventPlayer {
+        GameEventPlayer {
+            ai_level: None,
+            id: Some(id.to_owned()),
+            name: Some(id.to_uppercase()),
+            title: None,
+            rating: Some(rating),
+            provisional: None
+        }
+    }
+
+    pub(crate) fn test_game_info(id: &str, white_id: &str, black_id: &str) -> GameInfo {
+        GameInfo {
+            id: id.to_owned(),
+            variant: Some(Variant::Standard),
+            clock: Some(Clock {
+                limit: Some(180),
+                increment: Some(2)
+            }),
+            speed: Speed::Blitz,
+            perf: GamePerf {
+                name: None
+            },
+            rated: true,
+            created_at: 1234,
+            white: test_player(white_id, 1500),
+            black: test_player(black_id, 1600),
+            initial_fen: "startpos".to_owned(),
+            days_per_turn: None,
+            opening: None,
+            tournament_id: None
+        }
+    }
+
+    pub(crate) fn test_result(outcome: GameOutcome, bot_color: Option<Color>) -> GameResult {
+        GameResult {
+            game_id: Some("testGameId".to_owned()),
+            outcome,
+            status: Some(GameStatus::Mate),
+            bot_color,
+            white_time: None,
+            black_time: None,
+            rating_diff: None
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryGameStore(Mutex<Vec<GameRecord>>);
+
+    impl GameStore for MemoryGameStore {
+        fn record_game(&self, record: &GameRecord) -> StoreResult<()> {
+            self.0.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+
+        fn games(&self) -> StoreResult<Vec<GameRecord>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    #[test]
+    fn game_record_contains_opponent_and_pgn() {
+        let info = test_game_info("testGameId", "bot", "opponent");
+        let result = test_result(GameOutcome::Win, Some(Color::White));
+
+        let record = GameRecord::new(&info, "f2f3 e7e5 g2g4 d8h4", &result);
+
+        assert_that!(record.opponent.as_ref().and_then(|opponent| opponent.id.clone()))
+            .contains("opponent".to_owned());
+        assert_that!(record.opponent.as_ref().and_then(|opponent| opponent.rating))
+            .contains(1600);
+        assert_that!(record.pgn.unwrap().as_str()).is_equal_to(
+            "[Event \"Rated blitz game\"]\n\
+            [Site \"https://lichess.org/testGameId\"]\n\
+            [White \"BOT\"]\n\
+            [Black \"OPPONENT\"]\n\
+            [Result \"1-0\"]\n\
+            [WhiteElo \"1500\"]\n\
+            [BlackElo \"1600\"]\n\
+            [TimeControl \"180+2\"]\n\
+            \n\
+            1. f3 e5 2. g4 Qh4# 1-0\n");
+    }
+
+    #[rstest]
+    #[case::white_win(GameOutcome::Win, Some(Color::White), "1-0")]
+    #[case::black_win(GameOutcome::Win, Some(Color::Black), "0-1")]
+    #[case::white_loss(GameOutcome::Loss, Some(Color::White), "0-1")]
+    #[case::draw(GameOutcome::Draw, None, "1/2-1/2")]
+    #[case::aborted(GameOutcome::Aborted, Some(Color::White), "*")]
+    fn result_tag_works(#[case] outcome: GameOutcome, #[case] bot_color: Option<Color>,
+            #[case] expected_tag: &str) {
+        assert_that!(result_tag(&test_result(outcome, bot_color))).is_equal_to(expected_tag);
+    }
+
+    #[test]
+    fn game_record_has_no_pgn_for_unsupported_variant() {
+        let mut info = test_game_info("testGameId", "bot", "opponent");
+        info.variant = Some(Variant::Atomic);
+
+        let record = GameRecord::new(&info, "e2e4", &test_result(GameOutcome::Draw, None));
+
+        assert_that!(record.pgn).is_none();
+        assert_that!(record.opponent).is_none();
+    }
+
+    #[test]
+    fn game_record_pgn_contains_initial_fen() {
+        let mut info = test_game_info("testGameId", "bot", "opponent");
+        info.variant = Some(Variant::FromPosition);
+        info.initial_fen = "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1".to_owned();
+
+        let record = GameRecord::new(&info, "e1c1", &test_result(GameOutcome::Draw, None));
+
+        assert_that!(record.pgn.unwrap().as_str())
+            .contains("[FEN \"4k3/8/8/8/8/8/8/R3K3 w Q - 0 1\"]\n[SetUp \"1\"]\n\n1. O-O-O");
+    }
+
+    #[test]
+    fn games_against_filters_by_opponent() {
+        let store = MemoryGameStore::default();
+        let result = test_result(GameOutcome::Win, Some(Color::White));
+
+        for (game_id, opponent_id) in [("game1", "alice"), ("game2", "bob"), ("game3", "alice")] {
+            let info = test_game_info(game_id, "bot", opponent_id);
+            store.record_game(&GameRecord::new(&info, "", &result)).unwrap();
+        }
+
+        let game_ids = store.games_against("alice").unwrap().into_iter()
+            .map(|game| game.game_id)
+            .collect::<Vec<_>>();
+
+        assert_that!(game_ids)
+            .contains_exactly_in_given_order(["game1".to_owned(), "game3".to_owned()]);
+    }
+}
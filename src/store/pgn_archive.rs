@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::chess::pgn::{self, PgnGame};
+use crate::model::game::GameId;
+use crate::store::StoreResult;
+
+const FILE_PREFIX: &str = "games-";
+const FILE_SUFFIX: &str = ".pgn";
+
+/// Gets the Lichess ID of the given game from its `GameId` tag or, if absent, the last segment of
+/// its `Site` tag.
+fn game_id(game: &PgnGame) -> Option<GameId> {
+    game.tag("GameId")
+        .or_else(|| game.tag("Site")?.rsplit('/').next())
+        .filter(|id| !id.is_empty())
+        .map(str::to_owned)
+}
+
+fn file_index(path: &Path) -> Option<u32> {
+    path.file_name()?.to_str()?
+        .strip_prefix(FILE_PREFIX)?
+        .strip_suffix(FILE_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+fn file_name(index: u32) -> String {
+    format!("{FILE_PREFIX}{index:04}{FILE_SUFFIX}")
+}
+
+#[derive(Debug)]
+struct ArchiveState {
+    archived: HashSet<GameId>,
+    file_index: u32,
+    games_in_file: usize
+}
+
+/// A local archive of games in PGN, e.g. as training data. Games are appended to files named
+/// `games-0001.pgn`, `games-0002.pgn`, and so on in the archive directory, where a new file is
+/// started once the current one holds the configured maximum number of games. Each game is
+/// archived only once, identified by its Lichess game ID. Register an archive with
+/// [BotRunner::with_pgn_archive](crate::runner::BotRunner::with_pgn_archive) to periodically
+/// export the finished games of the bot into it.
+#[derive(Debug)]
+pub struct PgnArchive {
+    directory: PathBuf,
+    max_games_per_file: usize,
+    state: Mutex<ArchiveState>
+}
+
+impl PgnArchive {
+
+    /// Opens the archive in the given directory, creating the directory if necessary. The IDs of
+    /// all games in existing archive files are read, so they are not archived again.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory`: The directory in which the archive files are stored.
+    /// * `max_games_per_file`: The number of games after which a new archive file is started.
+    ///
+    /// # Errors
+    ///
+    /// * [StoreError::Io](crate::store::StoreError::Io) if the directory or an existing archive
+    ///   file cannot be created or read.
+    /// * [StoreError::Pgn](crate::store::StoreError::Pgn) if an existing archive file contains
+    ///   malformed PGN.
+    pub fn open(directory: impl AsRef<Path>, max_games_per_file: usize)
+            -> StoreResult<PgnArchive> {
+        let directory = directory.as_ref().to_owned();
+        fs::create_dir_all(&directory)?;
+
+        let mut state = ArchiveState {
+            archived: HashSet::new(),
+            file_index: 1,
+            games_in_file: 0
+        };
+
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            let Some(index) = file_index(&path)
+            else {
+                continue;
+            };
+            let games = pgn::read_pgn(&fs::read_to_string(&path)?)?;
+
+            if index >= state.file_index {
+                state.file_index = index;
+                state.games_in_file = games.len();
+            }
+
+            state.archived.extend(games.iter().filter_map(game_id));
+        }
+
+        Ok(PgnArchive {
+            directory,
+            max_games_per_file: max_games_per_file.max(1),
+            state: Mutex::new(state)
+        })
+    }
+
+    /// The directory in which the archive files are stored.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Indicates whether the game with the given ID is already archived.
+    pub fn contains(&self, game_id: &str) -> bool {
+        self.state.lock().unwrap().archived.contains(game_id)
+    }
+
+    /// The number of games with a known ID in this archive.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().archived.len()
+    }
+
+    /// Indicates whether this archive contains no games with a known ID.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends all given games which are not archived yet to the archive. Games without an ID
+    /// cannot be deduplicated and are always appended.
+    ///
+    /// # Returns
+    ///
+    /// The number of newly archived games.
+    ///
+    /// # Errors
+    ///
+    /// [StoreError::Io](crate::store::StoreError::Io) if an archive file cannot be written.
+    pub fn archive_games(&self, games: &[PgnGame]) -> StoreResult<usize> {
+        let mut state = self.state.lock().unwrap();
+        let mut archived_games = 0;
+
+        for game in games {
+            let game_id = game_id(game);
+
+            if game_id.as_ref().is_some_and(|game_id| state.archived.contains(game_id)) {
+                continue;
+            }
+
+            if state.games_in_file >= self.max_games_per_file {
+                state.file_index += 1;
+                state.games_in_file = 0;
+            }
+
+            let path = self.directory.join(file_name(state.file_index));
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            file.write_all(format!("{game}\n").as_bytes())?;
+
+            state.games_in_file += 1;
+            state.archived.extend(game_id);
+            archived_games += 1;
+        }
+
+        Ok(archived_games)
+    }
+
+    /// Parses the given PGN, e.g. from
+    /// [BotClient::export_user_games_pgn](crate::client::BotClient::export_user_games_pgn), and
+    /// appends all games which are not archived yet to the archive (see
+    /// [PgnArchive::archive_games]).
+    ///
+    /// # Returns
+    ///
+    /// The number of newly archived games.
+    ///
+    /// # Errors
+    ///
+    /// * [StoreError::Pgn](crate::store::StoreError::Pgn) if the given PGN is malformed.
+    /// * [StoreError::Io](crate::store::StoreError::Io) if an archive file cannot be written.
+    pub fn archive_pgn(&self, pgn: &str) -> StoreResult<usize> {
+        self.archive_games(&pgn::read_pgn(pgn)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("libot-pgn-archive-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+
+        path
+    }
+
+    fn test_pgn(game_ids: &[&str]) -> String {
+        game_ids.iter()
+            .map(|game_id| format!("[Site \"https://lichess.org/{game_id}\"]\n\
+                [Result \"1-0\"]\n\n1. e4 e5 1-0\n\n"))
+            .collect()
+    }
+
+    #[test]
+    fn games_are_deduplicated_by_id() {
+        let directory = test_directory("deduplicated");
+        let archive = PgnArchive::open(&directory, 10).unwrap();
+
+        assert_that!(archive.archive_pgn(&test_pgn(&["game1", "game2"])).unwrap())
+            .is_equal_to(2);
+        assert_that!(archive.archive_pgn(&test_pgn(&["game2", "game3"])).unwrap())
+            .is_equal_to(1);
+        assert_that!(archive.len()).is_equal_to(3);
+        assert_that!(archive.contains("game3")).is_true();
+
+        let content = fs::read_to_string(directory.join("games-0001.pgn")).unwrap();
+
+        assert_that!(pgn::read_pgn(&content).unwrap().len()).is_equal_to(3);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn files_are_rotated_after_maximum_number_of_games() {
+        let directory = test_directory("rotated");
+        let archive = PgnArchive::open(&directory, 2).unwrap();
+
+        archive.archive_pgn(&test_pgn(&["game1", "game2", "game3"])).unwrap();
+
+        let first = fs::read_to_string(directory.join("games-0001.pgn")).unwrap();
+        let second = fs::read_to_string(directory.join("games-0002.pgn")).unwrap();
+
+        assert_that!(pgn::read_pgn(&first).unwrap().len()).is_equal_to(2);
+        assert_that!(pgn::read_pgn(&second).unwrap().len()).is_equal_to(1);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn reopened_archive_continues_existing_files() {
+        let directory = test_directory("reopened");
+
+        PgnArchive::open(&directory, 2).unwrap()
+            .archive_pgn(&test_pgn(&["game1", "game2", "game3"])).unwrap();
+
+        let archive = PgnArchive::open(&directory, 2).unwrap();
+
+        assert_that!(archive.len()).is_equal_to(3);
+        assert_that!(archive.archive_pgn(&test_pgn(&["game3", "game4"])).unwrap())
+            .is_equal_to(1);
+
+        let second = fs::read_to_string(directory.join("games-0002.pgn")).unwrap();
+
+        assert_that!(pgn::read_pgn(&second).unwrap().len()).is_equal_to(2);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
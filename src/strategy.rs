@@ -0,0 +1,380 @@
+use rand::Rng;
+
+use crate::model::Move;
+use crate::model::analysis::{Evaluation, Score, UciInfo};
+use crate::model::game::Color;
+use crate::model::user::Rating;
+
+/// The centipawn value of a forced mate, from which the number of moves to mate is subtracted, so
+/// that faster mates are preferred.
+const MATE_CENTIPAWNS: i32 = 100_000;
+
+/// The loss in centipawns compared to the best move from which on a move counts as a blunder.
+const BLUNDER_LOSS: i32 = 150;
+
+/// The rating at and above which [MovePolicy::for_rating] always plays the best move.
+const MAX_TARGET_RATING: Rating = 2800;
+
+/// The rating range below [MAX_TARGET_RATING] over which [MovePolicy::for_rating] weakens its
+/// play.
+const TARGET_RATING_RANGE: Rating = 2000;
+
+/// The temperature of [MovePolicy::for_rating] at the lowest target rating.
+const MAX_TARGET_TEMPERATURE: u32 = 200;
+
+/// The blunder probability of [MovePolicy::for_rating] at the lowest target rating.
+const MAX_TARGET_BLUNDER_PROBABILITY: f64 = 0.15;
+
+/// A move considered by a [MovePolicy], together with the engine's score after playing it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Candidate {
+
+    /// The move in UCI notation.
+    pub mov: Move,
+
+    /// The score after the move from the perspective of the side playing it.
+    pub score: Score
+}
+
+impl Candidate {
+
+    /// Converts the principal variations of the given [UciInfo]s, as returned by
+    /// [UciEngine::analyse](crate::analysis::uci_engine::UciEngine::analyse) with a MultiPV
+    /// greater than one, into candidates. Infos without moves are skipped.
+    pub fn from_uci_infos(infos: &[UciInfo]) -> Vec<Candidate> {
+        infos.iter()
+            .filter_map(|info| Some(Candidate {
+                mov: info.pv.first()?.clone(),
+                score: info.score
+            }))
+            .collect()
+    }
+
+    /// Converts the lines of the given [Evaluation], whose scores are given from White's
+    /// perspective, into candidates for the given side to move. Lines without moves are skipped.
+    pub fn from_evaluation(evaluation: &Evaluation, side_to_move: Color) -> Vec<Candidate> {
+        evaluation.lines.iter()
+            .filter_map(|line| Some(Candidate {
+                mov: line.moves.first()?.clone(),
+                score: line.score.relative_to(side_to_move)
+            }))
+            .collect()
+    }
+
+    fn centipawns(&self) -> i32 {
+        match self.score {
+            Score::Centipawns(centipawns) => centipawns,
+            Score::Mate(moves) if moves > 0 => MATE_CENTIPAWNS - moves,
+            Score::Mate(moves) => -MATE_CENTIPAWNS - moves
+        }
+    }
+}
+
+/// The rule by which a [MovePolicy] chooses among the [Candidate]s reported by an engine.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MoveSelection {
+
+    /// Choose the candidate with the highest score.
+    #[default]
+    Best,
+
+    /// Choose a random candidate with a probability proportional to `exp(-loss / temperature)`,
+    /// where the loss is the difference in centipawns to the best candidate. Higher temperatures
+    /// make weaker moves more likely, while a temperature of 0 always chooses the best candidate.
+    Weighted {
+        temperature: u32
+    },
+
+    /// Choose the best candidate whose score does not exceed the given number of centipawns, so
+    /// the bot keeps the game close instead of converting a winning position. If every candidate
+    /// exceeds the cap, the one with the lowest score is chosen.
+    EvalCap {
+        max_centipawns: i32
+    }
+}
+
+/// A policy which chooses the move to play among multiple candidates reported by an engine, such
+/// as the principal variations of a MultiPV search. Besides always playing the best move, this
+/// allows fielding weaker, more human-like bots, e.g. for casual games, by choosing moves at
+/// random weighted by their evaluation, capping the evaluation and injecting occasional blunders.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MovePolicy {
+    selection: MoveSelection,
+    blunder_probability: f64
+}
+
+impl MovePolicy {
+
+    /// Creates a new policy which chooses moves according to the given [MoveSelection] and never
+    /// blunders on purpose.
+    pub fn new(selection: MoveSelection) -> MovePolicy {
+        MovePolicy {
+            selection,
+            blunder_probability: 0.0
+        }
+    }
+
+    /// Creates a policy which roughly plays at the given target rating, by choosing weighted
+    /// moves with a temperature and blunder probability that grow as the target rating falls
+    /// below 2800. This is a heuristic, so the actual rating of the bot also depends on the
+    /// strength of the engine and the number of candidates.
+    pub fn for_rating(rating: Rating) -> MovePolicy {
+        let weakness = (MAX_TARGET_RATING - rating).clamp(0, TARGET_RATING_RANGE) as f64 /
+            TARGET_RATING_RANGE as f64;
+        let temperature = (weakness * MAX_TARGET_TEMPERATURE as f64).round() as u32;
+
+        MovePolicy::new(MoveSelection::Weighted { temperature })
+            .with_blunders(weakness * MAX_TARGET_BLUNDER_PROBABILITY)
+    }
+
+    /// Sets the probability with which the policy deliberately blunders, i.e. chooses a random
+    /// candidate which loses at least 150 centipawns compared to the best one, or the worst
+    /// candidate if none does. The probability is clamped to `[0, 1]`. The policy is returned for
+    /// chaining.
+    pub fn with_blunders(mut self, probability: f64) -> MovePolicy {
+        self.blunder_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The [MoveSelection] of this policy.
+    pub fn selection(&self) -> MoveSelection {
+        self.selection
+    }
+
+    /// The probability with which this policy deliberately blunders.
+    pub fn blunder_probability(&self) -> f64 {
+        self.blunder_probability
+    }
+
+    /// Chooses a move among the given candidates. Random choices are made using the thread-local
+    /// random number generator, see [MovePolicy::select_with_rng] to provide another one.
+    ///
+    /// # Returns
+    ///
+    /// The chosen candidate, or [None] if there are no candidates.
+    pub fn select<'candidates>(&self, candidates: &'candidates [Candidate])
+            -> Option<&'candidates Candidate> {
+        self.select_with_rng(candidates, &mut rand::thread_rng())
+    }
+
+    /// Chooses a move among the given candidates like [MovePolicy::select], but makes random
+    /// choices using the given random number generator, e.g. a seeded one for reproducible games.
+    ///
+    /// # Returns
+    ///
+    /// The chosen candidate, or [None] if there are no candidates.
+    pub fn select_with_rng<'candidates>(&self, candidates: &'candidates [Candidate],
+            rng: &mut impl Rng) -> Option<&'candidates Candidate> {
+        self.select_with(candidates, rng.gen(), rng.gen())
+    }
+
+    fn select_with<'candidates>(&self, candidates: &'candidates [Candidate], blunder_random: f64,
+            selection_random: f64) -> Option<&'candidates Candidate> {
+        let best = candidates.iter().map(Candidate::centipawns).max()?;
+
+        if blunder_random < self.blunder_probability {
+            let blunders = candidates.iter()
+                .filter(|candidate| best - candidate.centipawns() >= BLUNDER_LOSS)
+                .collect::<Vec<_>>();
+
+            if blunders.is_empty() {
+                return candidates.iter().min_by_key(|candidate| candidate.centipawns());
+            }
+
+            let index = (selection_random * blunders.len() as f64) as usize;
+
+            return blunders.get(index).or(blunders.last()).copied();
+        }
+
+        match self.selection {
+            MoveSelection::Best | MoveSelection::Weighted { temperature: 0 } =>
+                candidates.iter().max_by_key(|candidate| candidate.centipawns()),
+            MoveSelection::Weighted { temperature } =>
+                select_weighted(candidates, best, temperature, selection_random),
+            MoveSelection::EvalCap { max_centipawns } => candidates.iter()
+                .filter(|candidate| candidate.centipawns() <= max_centipawns)
+                .max_by_key(|candidate| candidate.centipawns())
+                .or_else(|| candidates.iter().min_by_key(|candidate| candidate.centipawns()))
+        }
+    }
+}
+
+fn select_weighted(candidates: &[Candidate], best: i32, temperature: u32, random: f64)
+        -> Option<&Candidate> {
+    let weights = candidates.iter()
+        .map(|candidate| {
+            let loss = (best - candidate.centipawns()) as f64;
+
+            (-loss / temperature as f64).exp()
+        })
+        .collect::<Vec<_>>();
+    let mut remaining = random * weights.iter().sum::<f64>();
+
+    for (candidate, weight) in candidates.iter().zip(weights) {
+        if remaining < weight {
+            return Some(candidate);
+        }
+
+        remaining -= weight;
+    }
+
+    candidates.last()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use rstest::rstest;
+
+    use crate::model::analysis::{EvaluationSource, PrincipalVariation};
+
+    use super::*;
+
+    fn candidate(mov: &str, score: Score) -> Candidate {
+        Candidate {
+            mov: mov.to_owned(),
+            score
+        }
+    }
+
+    fn test_candidates() -> Vec<Candidate> {
+        vec![
+            candidate("e2e4", Score::Centipawns(500)),
+            candidate("d2d4", Score::Centipawns(450)),
+            candidate("g1f3", Score::Centipawns(100)),
+            candidate("f2f3", Score::Centipawns(-200))
+        ]
+    }
+
+    fn select(policy: MovePolicy, blunder_random: f64, selection_random: f64) -> Option<Move> {
+        policy.select_with(&test_candidates(), blunder_random, selection_random)
+            .map(|candidate| candidate.mov.clone())
+    }
+
+    #[test]
+    fn best_selection_chooses_highest_score() {
+        let policy = MovePolicy::new(MoveSelection::Best);
+
+        assert_that!(select(policy, 0.5, 0.99)).contains("e2e4".to_owned());
+    }
+
+    #[test]
+    fn mate_is_preferred_over_centipawns() {
+        let candidates = vec![
+            candidate("e2e4", Score::Centipawns(900)),
+            candidate("d1h5", Score::Mate(2)),
+            candidate("d1f3", Score::Mate(3))
+        ];
+
+        let selected = MovePolicy::default().select(&candidates);
+
+        assert_that!(selected.map(|candidate| candidate.mov.as_str())).contains("d1h5");
+    }
+
+    #[rstest]
+    #[case::first(0.0, "e2e4")]
+    #[case::second(0.8, "d2d4")]
+    #[case::third(0.99, "g1f3")]
+    #[case::fourth(0.9999, "f2f3")]
+    fn weighted_selection_follows_scores(#[case] random: f64, #[case] expected: &str) {
+        let policy = MovePolicy::new(MoveSelection::Weighted { temperature: 100 });
+
+        assert_that!(select(policy, 0.5, random)).contains(expected.to_owned());
+    }
+
+    #[rstest]
+    #[case::below_best(470, "d2d4")]
+    #[case::below_all(-500, "f2f3")]
+    #[case::above_all(1000, "e2e4")]
+    fn eval_cap_chooses_best_move_below_cap(#[case] max_centipawns: i32,
+            #[case] expected: &str) {
+        let policy = MovePolicy::new(MoveSelection::EvalCap { max_centipawns });
+
+        assert_that!(select(policy, 0.5, 0.0)).contains(expected.to_owned());
+    }
+
+    #[rstest]
+    #[case::first_blunder(0.0, "g1f3")]
+    #[case::second_blunder(0.99, "f2f3")]
+    fn blunder_chooses_losing_move(#[case] random: f64, #[case] expected: &str) {
+        let policy = MovePolicy::new(MoveSelection::Best).with_blunders(0.1);
+
+        assert_that!(select(policy, 0.05, random)).contains(expected.to_owned());
+        assert_that!(select(policy, 0.2, random)).contains("e2e4".to_owned());
+    }
+
+    #[test]
+    fn blunder_without_losing_move_chooses_worst_candidate() {
+        let candidates = vec![
+            candidate("e2e4", Score::Centipawns(30)),
+            candidate("d2d4", Score::Centipawns(20))
+        ];
+        let policy = MovePolicy::default().with_blunders(1.0);
+
+        let selected = policy.select(&candidates);
+
+        assert_that!(selected.map(|candidate| candidate.mov.as_str())).contains("d2d4");
+    }
+
+    #[test]
+    fn select_with_seeded_rng_is_reproducible() {
+        let candidates = test_candidates();
+        let policy = MovePolicy::for_rating(1200);
+        let select = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            (0..20)
+                .map(|_| policy.select_with_rng(&candidates, &mut rng).unwrap().mov.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_that!(select(42)).is_equal_to(select(42));
+    }
+
+    #[test]
+    fn select_returns_none_without_candidates() {
+        assert_that!(MovePolicy::default().select(&[])).is_none();
+    }
+
+    #[rstest]
+    #[case::strong(3000, 0, 0.0)]
+    #[case::medium(1800, 100, 0.075)]
+    #[case::weak(500, 200, 0.15)]
+    fn policy_for_rating(#[case] rating: Rating, #[case] expected_temperature: u32,
+            #[case] expected_blunder_probability: f64) {
+        let policy = MovePolicy::for_rating(rating);
+
+        assert_that!(policy.selection())
+            .is_equal_to(MoveSelection::Weighted { temperature: expected_temperature });
+        assert_that!(policy.blunder_probability())
+            .is_close_to(expected_blunder_probability, 1e-9);
+    }
+
+    #[test]
+    fn candidates_from_evaluation_are_relative_to_side_to_move() {
+        let evaluation = Evaluation {
+            source: EvaluationSource::LocalEngine,
+            depth: 20,
+            lines: vec![
+                PrincipalVariation {
+                    score: Score::Centipawns(-50),
+                    moves: vec!["e7e5".to_owned(), "g1f3".to_owned()]
+                },
+                PrincipalVariation {
+                    score: Score::Centipawns(20),
+                    moves: Vec::new()
+                }
+            ]
+        };
+
+        let candidates = Candidate::from_evaluation(&evaluation, Color::Black);
+
+        assert_that!(candidates)
+            .contains_exactly_in_given_order([candidate("e7e5", Score::Centipawns(50))]);
+    }
+}
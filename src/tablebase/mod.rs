@@ -0,0 +1,349 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::ResponseCache;
+use crate::chess::position::Position;
+use crate::chess::uci::UciMove;
+use crate::error::LibotResult;
+
+pub mod online;
+
+#[cfg(feature = "syzygy")]
+pub mod syzygy;
+
+/// The maximum number of pieces, including kings, for which tablebases are commonly available.
+pub const MAX_TABLEBASE_PIECES: usize = 7;
+
+/// The theoretical outcome of a tablebase position from the perspective of the side to move.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TablebaseCategory {
+
+    /// The side to move wins.
+    Win,
+
+    /// The side to move wins, but only if the 50-move rule is ignored.
+    CursedWin,
+
+    /// The position is drawn.
+    Draw,
+
+    /// The side to move loses, but can save a draw by the 50-move rule.
+    BlessedLoss,
+
+    /// The side to move loses.
+    Loss,
+
+    /// The side to move wins or has a cursed win, which cannot be distinguished due to missing
+    /// DTZ precision.
+    MaybeWin,
+
+    /// The side to move loses or has a blessed loss, which cannot be distinguished due to missing
+    /// DTZ precision.
+    MaybeLoss,
+
+    /// The outcome is not known.
+    Unknown
+}
+
+impl TablebaseCategory {
+
+    fn rank_for_mover(self) -> i32 {
+        match self {
+            TablebaseCategory::Loss => 6,
+            TablebaseCategory::MaybeLoss => 5,
+            TablebaseCategory::BlessedLoss => 4,
+            TablebaseCategory::Draw => 3,
+            TablebaseCategory::Unknown => 2,
+            TablebaseCategory::CursedWin => 1,
+            TablebaseCategory::MaybeWin => 0,
+            TablebaseCategory::Win => -1
+        }
+    }
+}
+
+/// A move from a probed position, together with the outcome of the position after the move.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct TablebaseMove {
+
+    /// The move from the probed position.
+    pub mov: UciMove,
+
+    /// The [TablebaseCategory] of the position after the move, i.e. from the perspective of the
+    /// opponent.
+    pub category: TablebaseCategory,
+
+    /// The distance to zeroing (capture or pawn move) of the position after the move in
+    /// half-moves, if known.
+    pub dtz: Option<i32>
+}
+
+/// The result of probing a position in a [Tablebase].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct TablebaseProbe {
+
+    /// The [TablebaseCategory] of the probed position from the perspective of the side to move.
+    pub category: TablebaseCategory,
+
+    /// The distance to zeroing (capture or pawn move) in half-moves, if known.
+    pub dtz: Option<i32>,
+
+    /// All legal moves in the probed position with their outcomes. This may be empty if the
+    /// tablebase only provides the outcome of the position itself.
+    pub moves: Vec<TablebaseMove>
+}
+
+impl TablebaseProbe {
+
+    /// Selects the move which achieves the best outcome for the side to move. Among winning moves,
+    /// the one which reaches a zeroing move fastest is preferred. Among losing moves, the one
+    /// which delays it the longest is preferred.
+    ///
+    /// # Returns
+    ///
+    /// The best move, or [None] if no moves are known.
+    pub fn best_move(&self) -> Option<UciMove> {
+        self.moves.iter()
+            .max_by_key(|mov| {
+                let rank = mov.category.rank_for_mover();
+                let dtz = mov.dtz.unwrap_or(0).abs();
+
+                if rank > TablebaseCategory::Draw.rank_for_mover() {
+                    (rank, -dtz)
+                }
+                else {
+                    (rank, dtz)
+                }
+            })
+            .map(|mov| mov.mov)
+    }
+}
+
+/// A source of perfect endgame information, such as the Lichess online tablebase or local
+/// Syzygy table files.
+#[async_trait::async_trait]
+pub trait Tablebase : Send + Sync {
+
+    /// Probes the given position.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: The position to probe.
+    ///
+    /// # Returns
+    ///
+    /// The [TablebaseProbe] for the given position, or [None] if this tablebase does not cover
+    /// the position.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError](crate::error::LibotRequestError) if probing the tablebase fails.
+    async fn probe(&self, position: &Position) -> LibotResult<Option<TablebaseProbe>>;
+}
+
+/// A [Tablebase] which probes a primary tablebase first and only queries a fallback tablebase if
+/// the primary one does not cover the position or fails. This allows combining fast local tables
+/// with the online tablebase.
+pub struct FallbackTablebase<P, F> {
+    primary: P,
+    fallback: F
+}
+
+impl<P: Tablebase, F: Tablebase> FallbackTablebase<P, F> {
+
+    /// Creates a new fallback tablebase.
+    ///
+    /// # Arguments
+    ///
+    /// * `primary`: The tablebase to probe first.
+    /// * `fallback`: The tablebase to probe if `primary` does not cover a position or fails.
+    pub fn new(primary: P, fallback: F) -> FallbackTablebase<P, F> {
+        FallbackTablebase {
+            primary,
+            fallback
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<P: Tablebase, F: Tablebase> Tablebase for FallbackTablebase<P, F> {
+    async fn probe(&self, position: &Position) -> LibotResult<Option<TablebaseProbe>> {
+        match self.primary.probe(position).await {
+            Ok(Some(probe)) => Ok(Some(probe)),
+            _ => self.fallback.probe(position).await
+        }
+    }
+}
+
+/// A [Tablebase] which caches the probes of another tablebase, such as the rate-limited
+/// [OnlineTablebase](online::OnlineTablebase), in a [ResponseCache]. Positions the tablebase does
+/// not cover are cached as well, while failed probes are not. Keep another reference to the cache
+/// to read its [CacheStats](crate::cache::CacheStats) or save it.
+pub struct CachedTablebase<T> {
+    tablebase: T,
+    cache: Arc<ResponseCache<Option<TablebaseProbe>>>
+}
+
+impl<T: Tablebase> CachedTablebase<T> {
+
+    /// Creates a new cached tablebase.
+    ///
+    /// # Arguments
+    ///
+    /// * `tablebase`: The tablebase to probe for positions which are not cached.
+    /// * `cache`: The cache in which to store the probes.
+    pub fn new(tablebase: T, cache: Arc<ResponseCache<Option<TablebaseProbe>>>)
+            -> CachedTablebase<T> {
+        CachedTablebase {
+            tablebase,
+            cache
+        }
+    }
+
+    /// The cache in which the probes are stored.
+    pub fn cache(&self) -> &Arc<ResponseCache<Option<TablebaseProbe>>> {
+        &self.cache
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Tablebase> Tablebase for CachedTablebase<T> {
+    async fn probe(&self, position: &Position) -> LibotResult<Option<TablebaseProbe>> {
+        self.cache.get_or_fetch(&position.to_fen(), self.tablebase.probe(position)).await
+    }
+}
+
+pub(crate) fn piece_count(position: &Position) -> usize {
+    position.pieces().count()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use reqwest::StatusCode;
+
+    use rstest::rstest;
+
+    use crate::error::LibotRequestError;
+
+    use super::*;
+
+    fn tablebase_move(mov: &str, category: TablebaseCategory, dtz: Option<i32>)
+            -> TablebaseMove {
+        TablebaseMove {
+            mov: mov.parse().unwrap(),
+            category,
+            dtz
+        }
+    }
+
+    #[rstest]
+    #[case::prefers_win_over_draw(vec![
+        tablebase_move("a1a2", TablebaseCategory::Draw, Some(0)),
+        tablebase_move("a1a3", TablebaseCategory::Loss, Some(-5))
+    ], "a1a3")]
+    #[case::prefers_fast_win(vec![
+        tablebase_move("a1a2", TablebaseCategory::Loss, Some(-9)),
+        tablebase_move("a1a3", TablebaseCategory::Loss, Some(-3))
+    ], "a1a3")]
+    #[case::prefers_slow_loss(vec![
+        tablebase_move("a1a2", TablebaseCategory::Win, Some(4)),
+        tablebase_move("a1a3", TablebaseCategory::Win, Some(12))
+    ], "a1a3")]
+    #[case::prefers_draw_over_cursed_loss(vec![
+        tablebase_move("a1a2", TablebaseCategory::CursedWin, Some(110)),
+        tablebase_move("a1a3", TablebaseCategory::Draw, None)
+    ], "a1a3")]
+    fn best_move(#[case] moves: Vec<TablebaseMove>, #[case] expected_move: &str) {
+        let probe = TablebaseProbe {
+            category: TablebaseCategory::Win,
+            dtz: None,
+            moves
+        };
+
+        assert_that!(probe.best_move()).contains(expected_move.parse::<UciMove>().unwrap());
+    }
+
+    enum FixedTablebase {
+        Covered,
+        NotCovered,
+        Failing
+    }
+
+    struct CountingTablebase {
+        behavior: FixedTablebase,
+        probes: AtomicUsize
+    }
+
+    impl CountingTablebase {
+        fn new(behavior: FixedTablebase) -> CountingTablebase {
+            CountingTablebase {
+                behavior,
+                probes: AtomicUsize::new(0)
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Tablebase for &CountingTablebase {
+        async fn probe(&self, _: &Position) -> LibotResult<Option<TablebaseProbe>> {
+            self.probes.fetch_add(1, Ordering::SeqCst);
+
+            match self.behavior {
+                FixedTablebase::Covered => Ok(Some(TablebaseProbe {
+                    category: TablebaseCategory::Draw,
+                    dtz: Some(0),
+                    moves: Vec::new()
+                })),
+                FixedTablebase::NotCovered => Ok(None),
+                FixedTablebase::Failing => Err(LibotRequestError::ApiError {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    body: None,
+                    url: "http://localhost".parse().unwrap()
+                })
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::covered(FixedTablebase::Covered, 1, true)]
+    #[case::not_covered(FixedTablebase::NotCovered, 1, true)]
+    #[case::failing(FixedTablebase::Failing, 2, false)]
+    fn cached_tablebase(#[case] behavior: FixedTablebase, #[case] expected_probes: usize,
+            #[case] expected_ok: bool) {
+        let inner = CountingTablebase::new(behavior);
+        let cache = Arc::new(ResponseCache::new(Duration::from_secs(60)));
+        let tablebase = CachedTablebase::new(&inner, Arc::clone(&cache));
+        let position = Position::standard();
+
+        let first = tokio_test::block_on(tablebase.probe(&position));
+        let second = tokio_test::block_on(tablebase.probe(&position));
+
+        assert_that!(inner.probes.load(Ordering::SeqCst)).is_equal_to(expected_probes);
+        assert_that!(first.is_ok()).is_equal_to(expected_ok);
+        assert_that!(second.is_ok()).is_equal_to(expected_ok);
+        assert_that!(cache.stats().misses).is_equal_to(expected_probes as u64);
+    }
+
+    #[rstest]
+    #[case::primary_covers(FixedTablebase::Covered, 0)]
+    #[case::primary_does_not_cover(FixedTablebase::NotCovered, 1)]
+    #[case::primary_fails(FixedTablebase::Failing, 1)]
+    fn fallback_tablebase(#[case] primary_behavior: FixedTablebase,
+            #[case] expected_fallback_probes: usize) {
+        let primary = CountingTablebase::new(primary_behavior);
+        let fallback = CountingTablebase::new(FixedTablebase::Covered);
+        let tablebase = FallbackTablebase::new(&primary, &fallback);
+
+        let probe = tokio_test::block_on(tablebase.probe(&Position::standard())).unwrap();
+
+        assert_that!(probe).is_some();
+        assert_that!(fallback.probes.load(Ordering::SeqCst)).is_equal_to(expected_fallback_probes);
+    }
+}
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use reqwest::Client;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chess::position::Position;
+use crate::client::{handle_error, join_url};
+use crate::error::LibotResult;
+use crate::tablebase::{
+    MAX_TABLEBASE_PIECES,
+    piece_count,
+    Tablebase,
+    TablebaseCategory,
+    TablebaseMove,
+    TablebaseProbe
+};
+
+/// The base URL of the public Lichess tablebase server.
+pub const DEFAULT_TABLEBASE_URL: &str = "https://tablebase.lichess.ovh";
+
+#[derive(Deserialize)]
+struct OnlineTablebaseMove {
+    uci: String,
+    category: TablebaseCategory,
+    dtz: Option<i32>
+}
+
+#[derive(Deserialize)]
+struct OnlineTablebaseResponse {
+    category: TablebaseCategory,
+    dtz: Option<i32>,
+
+    #[serde(default)]
+    moves: Vec<OnlineTablebaseMove>
+}
+
+/// A [Tablebase] which queries the Lichess online tablebase. Positions with more than
+/// [MAX_TABLEBASE_PIECES] pieces are not sent to the server.
+#[derive(Clone, Debug)]
+pub struct OnlineTablebase {
+    client: Client,
+    base_url: Arc<str>
+}
+
+impl OnlineTablebase {
+
+    /// Creates a new online tablebase client using the [DEFAULT_TABLEBASE_URL].
+    pub fn new() -> OnlineTablebase {
+        OnlineTablebase {
+            client: Client::new(),
+            base_url: DEFAULT_TABLEBASE_URL.into()
+        }
+    }
+
+    /// Overrides the base URL of the tablebase server to query. The client is returned for
+    /// chaining.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> OnlineTablebase {
+        self.base_url = base_url.into().into();
+        self
+    }
+}
+
+impl Default for OnlineTablebase {
+    fn default() -> OnlineTablebase {
+        OnlineTablebase::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl Tablebase for OnlineTablebase {
+    async fn probe(&self, position: &Position) -> LibotResult<Option<TablebaseProbe>> {
+        #[derive(Serialize)]
+        struct Query {
+            fen: String
+        }
+
+        if piece_count(position) > MAX_TABLEBASE_PIECES {
+            return Ok(None);
+        }
+
+        let url = join_url(&self.base_url, "/standard");
+        let query = Query {
+            fen: position.to_fen()
+        };
+        let response = handle_error(self.client.get(url).query(&query).send().await).await?;
+        let response: OnlineTablebaseResponse = response.json().await?;
+
+        if response.category == TablebaseCategory::Unknown {
+            return Ok(None);
+        }
+
+        let moves = response.moves.into_iter()
+            .filter_map(|mov| Some(TablebaseMove {
+                mov: mov.uci.parse().ok()?,
+                category: mov.category,
+                dtz: mov.dtz
+            }))
+            .collect();
+
+        Ok(Some(TablebaseProbe {
+            category: response.category,
+            dtz: response.dtz,
+            moves
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde_json::json;
+
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use wiremock::matchers::{method, path, query_param};
+
+    use crate::chess::position::STANDARD_FEN;
+    use crate::chess::uci::UciMove;
+
+    use super::*;
+
+    const ENDGAME_FEN: &str = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+
+    async fn probe(server: &MockServer, fen: &str) -> LibotResult<Option<TablebaseProbe>> {
+        let tablebase = OnlineTablebase::new().with_base_url(server.uri());
+        let position = Position::from_fen(fen).unwrap();
+
+        tablebase.probe(&position).await
+    }
+
+    #[test]
+    fn probe_parses_response() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+
+            Mock::given(method("GET"))
+                .and(path("/standard"))
+                .and(query_param("fen", ENDGAME_FEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "checkmate": false,
+                    "stalemate": false,
+                    "dtz": 31,
+                    "precise_dtz": 31,
+                    "dtm": 31,
+                    "category": "win",
+                    "moves": [
+                        {
+                            "uci": "h1h7",
+                            "san": "Rh7",
+                            "category": "loss",
+                            "dtz": -30
+                        },
+                        {
+                            "uci": "e1f1",
+                            "san": "Kf1",
+                            "category": "loss",
+                            "dtz": -32
+                        }
+                    ]
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let probe = probe(&server, ENDGAME_FEN).await.unwrap().unwrap();
+
+            assert_that!(probe.category).is_equal_to(TablebaseCategory::Win);
+            assert_that!(probe.dtz).contains(31);
+            assert_that!(probe.moves.len()).is_equal_to(2);
+            assert_that!(probe.best_move()).contains("h1h7".parse::<UciMove>().unwrap());
+        });
+    }
+
+    #[test]
+    fn probe_returns_none_for_unknown_category() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+
+            Mock::given(method("GET"))
+                .and(path("/standard"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "category": "unknown",
+                    "dtz": null,
+                    "moves": []
+                })))
+                .mount(&server)
+                .await;
+
+            assert_that!(probe(&server, ENDGAME_FEN).await.unwrap()).is_none();
+        });
+    }
+
+    #[test]
+    fn probe_does_not_query_server_for_too_many_pieces() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(500))
+                .expect(0)
+                .mount(&server)
+                .await;
+
+            assert_that!(probe(&server, STANDARD_FEN).await.unwrap()).is_none();
+        });
+    }
+
+    #[test]
+    fn probe_fails_on_server_error() {
+        tokio_test::block_on(async {
+            let server = MockServer::start().await;
+
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(429))
+                .mount(&server)
+                .await;
+
+            assert_that!(probe(&server, ENDGAME_FEN).await).is_err();
+        });
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use shakmaty::{CastlingMode, Chess, Position as _};
+use shakmaty::fen::Fen;
+
+use shakmaty_syzygy::{AmbiguousWdl, SyzygyError, Tablebase as SyzygyTables, Wdl};
+
+use crate::chess::PieceKind;
+use crate::chess::position::Position;
+use crate::error::{LibotRequestError, LibotResult};
+use crate::model::game::{Color, Variant};
+use crate::tablebase::{Tablebase, TablebaseCategory, TablebaseMove, TablebaseProbe};
+
+const WDL_EXTENSION: &str = "rtbw";
+const DTZ_EXTENSION: &str = "rtbz";
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+const PIECE_ORDER: [PieceKind; 6] = [
+    PieceKind::King,
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+    PieceKind::Pawn
+];
+
+fn material_side(position: &Position, color: Color) -> String {
+    PIECE_ORDER.iter()
+        .flat_map(|&kind| {
+            let count = position.pieces()
+                .filter(|(_, piece)| piece.color == color && piece.kind == kind)
+                .count();
+
+            std::iter::repeat_n(kind.to_char().to_ascii_uppercase(), count)
+        })
+        .collect()
+}
+
+/// Computes the Syzygy material key of the given position, such as `KQvKR`, with white's pieces
+/// first.
+pub fn material_key(position: &Position) -> String {
+    format!("{}v{}", material_side(position, Color::White), material_side(position, Color::Black))
+}
+
+fn has_magic(path: &Path, magic: [u8; 4]) -> io::Result<bool> {
+    let mut header = [0; 4];
+    let mut file = File::open(path)?;
+
+    match file.read_exact(&mut header) {
+        Ok(()) => Ok(header == magic),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error)
+    }
+}
+
+fn category_of(wdl: AmbiguousWdl) -> TablebaseCategory {
+    match wdl {
+        AmbiguousWdl::Loss => TablebaseCategory::Loss,
+        AmbiguousWdl::MaybeLoss => TablebaseCategory::MaybeLoss,
+        AmbiguousWdl::BlessedLoss => TablebaseCategory::BlessedLoss,
+        AmbiguousWdl::Draw => TablebaseCategory::Draw,
+        AmbiguousWdl::CursedWin => TablebaseCategory::CursedWin,
+        AmbiguousWdl::MaybeWin => TablebaseCategory::MaybeWin,
+        AmbiguousWdl::Win => TablebaseCategory::Win
+    }
+}
+
+/// Determines the [TablebaseCategory] of a position from its WDL value after a zeroing move if
+/// no DTZ table is available. Unless the position was reached by a zeroing move, a win or loss
+/// may have turned into a cursed win or blessed loss since.
+fn category_without_dtz(wdl: Wdl, halfmoves: u32) -> TablebaseCategory {
+    match wdl {
+        Wdl::Win if halfmoves > 0 => TablebaseCategory::MaybeWin,
+        Wdl::Loss if halfmoves > 0 => TablebaseCategory::MaybeLoss,
+        wdl => category_of(AmbiguousWdl::from(wdl))
+    }
+}
+
+/// A local collection of Syzygy tablebase files (`.rtbw` for WDL and `.rtbz` for DTZ). Opening
+/// the collection discovers and validates all table files in the given directories, so bots can
+/// check which endgames are covered locally before consulting the network.
+///
+/// Positions of Standard, Chess960 and From Position games without castling rights are probed
+/// with the WDL and, if available, DTZ tables of their material. Tables are opened lazily when
+/// they are first needed and read synchronously. Positions of other variants, with castling
+/// rights or with material that is not covered are reported as not covered, so combining this
+/// tablebase with the online one in a [FallbackTablebase](crate::tablebase::FallbackTablebase)
+/// still yields a result.
+#[derive(Debug, Default)]
+pub struct SyzygyTablebase {
+    tables: SyzygyTables<Chess>,
+    wdl_tables: HashSet<String>,
+    dtz_tables: HashSet<String>
+}
+
+impl SyzygyTablebase {
+
+    /// Creates a new, empty Syzygy tablebase.
+    pub fn new() -> SyzygyTablebase {
+        SyzygyTablebase::default()
+    }
+
+    /// Adds all Syzygy table files in the given directory. Files with unknown extensions are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory`: The directory containing `.rtbw` and `.rtbz` files.
+    ///
+    /// # Returns
+    ///
+    /// The number of table files which were added.
+    ///
+    /// # Errors
+    ///
+    /// * Any [io::Error] if the directory or a table file cannot be read.
+    /// * An [io::Error] of kind [io::ErrorKind::InvalidData] if a table file has an invalid
+    ///   header.
+    pub fn add_directory(&mut self, directory: impl AsRef<Path>) -> io::Result<usize> {
+        let mut added = 0;
+
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+
+            if self.add_file(&path)? {
+                added += 1;
+            }
+        }
+
+        Ok(added)
+    }
+
+    fn add_file(&mut self, path: &Path) -> io::Result<bool> {
+        let Some(key) = path.file_stem().and_then(|stem| stem.to_str())
+        else {
+            return Ok(false);
+        };
+        let (tables, magic) = match path.extension().and_then(|extension| extension.to_str()) {
+            Some(WDL_EXTENSION) => (&mut self.wdl_tables, WDL_MAGIC),
+            Some(DTZ_EXTENSION) => (&mut self.dtz_tables, DTZ_MAGIC),
+            _ => return Ok(false)
+        };
+
+        if !has_magic(path, magic)? {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("invalid Syzygy table header in {}", path.display())));
+        }
+
+        match self.tables.add_file(path) {
+            Ok(()) => { },
+
+            // The file name does not denote a material configuration, such as `KvK.rtbw`.
+            Err(error) if error.kind() == io::ErrorKind::InvalidInput => return Ok(false),
+            Err(error) => return Err(error)
+        }
+
+        tables.insert(key.to_owned());
+
+        Ok(true)
+    }
+
+    /// Creates a new Syzygy tablebase from all table files in the given directory. See
+    /// [SyzygyTablebase::add_directory].
+    ///
+    /// # Errors
+    ///
+    /// As in [SyzygyTablebase::add_directory].
+    pub fn open(directory: impl AsRef<Path>) -> io::Result<SyzygyTablebase> {
+        let mut tablebase = SyzygyTablebase::new();
+        tablebase.add_directory(directory)?;
+        Ok(tablebase)
+    }
+
+    /// The maximum number of pieces, including kings, of any table in this tablebase.
+    pub fn max_pieces(&self) -> usize {
+        self.tables.max_pieces()
+    }
+
+    fn has_table(tables: &HashSet<String>, position: &Position) -> bool {
+        let key = material_key(position);
+        let (white, black) = key.split_once('v').unwrap();
+
+        tables.contains(&key) || tables.contains(&format!("{black}v{white}"))
+    }
+
+    /// Indicates whether a WDL table for the material of the given position is available.
+    pub fn has_wdl_table(&self, position: &Position) -> bool {
+        SyzygyTablebase::has_table(&self.wdl_tables, position)
+    }
+
+    /// Indicates whether a DTZ table for the material of the given position is available.
+    pub fn has_dtz_table(&self, position: &Position) -> bool {
+        SyzygyTablebase::has_table(&self.dtz_tables, position)
+    }
+
+    /// Probes the category and DTZ of the given position, from the perspective of the side to
+    /// move. The DTZ is [None] if no DTZ table is available.
+    fn probe_category(&self, position: &Chess)
+            -> Result<(TablebaseCategory, Option<i32>), SyzygyError> {
+        match self.tables.probe_dtz(position) {
+            Ok(dtz) => {
+                let wdl = AmbiguousWdl::from_dtz_and_halfmoves(dtz, position.halfmoves());
+
+                Ok((category_of(wdl), Some(dtz.ignore_rounding().0)))
+            },
+            Err(SyzygyError::MissingTable { .. }) => {
+                let wdl = self.tables.probe_wdl_after_zeroing(position)?;
+
+                Ok((category_without_dtz(wdl, position.halfmoves()), None))
+            },
+            Err(error) => Err(error)
+        }
+    }
+
+    fn probe_move(&self, position: &Chess, mov: shakmaty::Move)
+            -> Result<TablebaseMove, SyzygyError> {
+        let uci = mov.to_uci(CastlingMode::Standard).to_string();
+        let mut after_move = position.clone();
+        after_move.play_unchecked(mov);
+
+        let (category, dtz) = match self.probe_category(&after_move) {
+            Ok(result) => result,
+
+            // The position after a capture may require a table which is not available.
+            Err(SyzygyError::MissingTable { .. }) => (TablebaseCategory::Unknown, None),
+            Err(error) => return Err(error)
+        };
+
+        Ok(TablebaseMove {
+            mov: uci.parse().unwrap(),
+            category,
+            dtz
+        })
+    }
+
+    fn probe_position(&self, position: &Chess) -> Result<TablebaseProbe, SyzygyError> {
+        let (category, dtz) = self.probe_category(position)?;
+        let moves = position.legal_moves().into_iter()
+            .map(|mov| self.probe_move(position, mov))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TablebaseProbe {
+            category,
+            dtz,
+            moves
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Tablebase for SyzygyTablebase {
+    async fn probe(&self, position: &Position) -> LibotResult<Option<TablebaseProbe>> {
+        if !matches!(position.variant(),
+                Variant::Standard | Variant::Chess960 | Variant::FromPosition) {
+            return Ok(None);
+        }
+
+        let position = Fen::from_ascii(position.to_fen().as_bytes()).ok()
+            .and_then(|fen| fen.into_position::<Chess>(CastlingMode::Chess960).ok());
+        let Some(position) = position
+        else {
+            return Ok(None);
+        };
+
+        match self.probe_position(&position) {
+            Ok(probe) => Ok(Some(probe)),
+            Err(SyzygyError::Castling | SyzygyError::TooManyPieces |
+                SyzygyError::MissingTable { .. }) => Ok(None),
+            Err(error) => Err(LibotRequestError::TablebaseError(Box::new(error)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::PathBuf;
+
+    use kernal::prelude::*;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    /// Creates a directory with table files which consist of the given header, padded to the
+    /// smallest size of a valid table file. Their contents cannot be decoded.
+    fn test_directory(name: &str, files: &[(&str, [u8; 4])]) -> PathBuf {
+        let directory = std::env::temp_dir()
+            .join(format!("libot-syzygy-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        for (file_name, header) in files {
+            let mut content = header.to_vec();
+            content.resize(16, 0);
+            std::fs::write(directory.join(file_name), content).unwrap();
+        }
+
+        directory
+    }
+
+    #[rstest]
+    #[case::white_stronger("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", "KQvK")]
+    #[case::mixed("r3k3/8/8/8/8/8/4P3/2B1K1N1 w - - 0 1", "KBNPvKR")]
+    fn material_key_works(#[case] fen: &str, #[case] expected_key: &str) {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_that!(material_key(&position)).is_equal_to(expected_key.to_owned());
+    }
+
+    #[test]
+    fn open_discovers_valid_tables() {
+        let directory = test_directory("valid", &[
+            ("KQvK.rtbw", WDL_MAGIC),
+            ("KQvK.rtbz", DTZ_MAGIC),
+            ("KRPvKR.rtbw", WDL_MAGIC),
+            ("README.txt", *b"text")
+        ]);
+
+        let tablebase = SyzygyTablebase::open(&directory).unwrap();
+        let kq_vs_k = Position::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let k_vs_kq = Position::from_fen("3qk3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let kr_vs_k = Position::from_fen("4k3/8/8/8/8/8/8/3RK3 w - - 0 1").unwrap();
+
+        assert_that!(tablebase.max_pieces()).is_equal_to(5);
+        assert_that!(tablebase.has_wdl_table(&kq_vs_k)).is_true();
+        assert_that!(tablebase.has_dtz_table(&kq_vs_k)).is_true();
+        assert_that!(tablebase.has_wdl_table(&k_vs_kq)).is_true();
+        assert_that!(tablebase.has_wdl_table(&kr_vs_k)).is_false();
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn open_rejects_invalid_header() {
+        let directory = test_directory("invalid", &[("KQvK.rtbw", DTZ_MAGIC)]);
+
+        let result = SyzygyTablebase::open(&directory).map_err(|error| error.kind());
+
+        assert_that!(result).contains_error(io::ErrorKind::InvalidData);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[rstest]
+    #[case::win_after_zeroing(Wdl::Win, 0, TablebaseCategory::Win)]
+    #[case::win(Wdl::Win, 10, TablebaseCategory::MaybeWin)]
+    #[case::cursed_win(Wdl::CursedWin, 10, TablebaseCategory::CursedWin)]
+    #[case::draw(Wdl::Draw, 10, TablebaseCategory::Draw)]
+    #[case::blessed_loss(Wdl::BlessedLoss, 10, TablebaseCategory::BlessedLoss)]
+    #[case::loss(Wdl::Loss, 10, TablebaseCategory::MaybeLoss)]
+    #[case::loss_after_zeroing(Wdl::Loss, 0, TablebaseCategory::Loss)]
+    fn category_without_dtz_works(#[case] wdl: Wdl, #[case] halfmoves: u32,
+            #[case] expected_category: TablebaseCategory) {
+        assert_that!(category_without_dtz(wdl, halfmoves)).is_equal_to(expected_category);
+    }
+
+    #[rstest]
+    #[case::missing_table("missing", "4k3/8/8/8/8/8/8/3RK3 w - - 0 1", Variant::Standard)]
+    #[case::too_many_pieces("pieces", "4k3/pppppppp/8/8/8/8/8/3QK3 w - - 0 1", Variant::Standard)]
+    #[case::castling_rights("castling", "4k3/8/8/8/8/8/8/3QK2R w K - 0 1", Variant::Standard)]
+    #[case::other_variant("variant", "4k3/8/8/8/8/8/8/3QK3 w - - 0 1", Variant::Atomic)]
+    fn probe_does_not_cover_position(#[case] name: &str, #[case] fen: &str,
+            #[case] variant: Variant) {
+        let directory = test_directory(name, &[
+            ("KQvK.rtbw", WDL_MAGIC),
+            ("KQvK.rtbz", DTZ_MAGIC)
+        ]);
+        let tablebase = SyzygyTablebase::open(&directory).unwrap();
+        let position = Position::from_fen_with_variant(fen, variant).unwrap();
+
+        let probe = tokio_test::block_on(tablebase.probe(&position)).unwrap();
+
+        assert_that!(probe).is_none();
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn probe_fails_for_corrupted_table() {
+        let directory = test_directory("corrupted", &[("KQvK.rtbw", WDL_MAGIC)]);
+        let tablebase = SyzygyTablebase::open(&directory).unwrap();
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+
+        let result = tokio_test::block_on(tablebase.probe(&position));
+
+        assert!(matches!(result, Err(LibotRequestError::TablebaseError(_))));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
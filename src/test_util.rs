@@ -7,6 +7,8 @@ pub async fn setup_wiremock_test() -> (BotClient, MockServer) {
     let client = BotClientBuilder::new()
         .with_token("mock_token")
         .with_base_url(server.uri())
+        .with_gif_base_url(server.uri())
+        .with_site_base_url(server.uri())
         .build()
         .unwrap();
 
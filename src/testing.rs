@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use reqwest::Method;
+
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{method, path};
+
+use crate::client::{ApiMode, BotClient, BotClientBuilder};
+
+const TEST_TOKEN: &str = "test_token";
+const EVENT_PATH: &str = "/stream/event";
+
+/// A line which is cut off in the middle of a JSON object, as Lichess sends it when the
+/// connection breaks while a line is transmitted.
+const TRUNCATED_LINE: &str = r#"{"type":"gameState","moves":"e2e4 e7"#;
+
+/// A line which is not valid JSON at all.
+const MALFORMED_LINE: &str = r#"{"type":gameState,"moves":}"#;
+
+/// The script of a single connection to a streaming endpoint of a [LichessScenario], i.e. the
+/// NDJSON lines the server sends before the stream ends. Lines added after
+/// [StreamScript::with_disconnect] are ignored.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct StreamScript {
+    body: String,
+    disconnected: bool
+}
+
+impl StreamScript {
+
+    /// Creates a new, empty script, which represents a stream that ends immediately.
+    pub fn new() -> StreamScript {
+        StreamScript::default()
+    }
+
+    /// Appends the given line, usually a JSON-encoded event, to the stream. The script is
+    /// returned for chaining.
+    pub fn with_line(mut self, line: impl AsRef<str>) -> StreamScript {
+        if !self.disconnected {
+            self.body.push_str(line.as_ref());
+            self.body.push('\n');
+        }
+
+        self
+    }
+
+    /// Appends an empty line to the stream, which Lichess sends periodically to keep the
+    /// connection alive. The script is returned for chaining.
+    pub fn with_keep_alive(self) -> StreamScript {
+        self.with_line("")
+    }
+
+    /// Appends a complete line which is not valid JSON to the stream. The script is returned for
+    /// chaining.
+    pub fn with_malformed_line(self) -> StreamScript {
+        self.with_line(MALFORMED_LINE)
+    }
+
+    /// Lets the connection break while the next line is transmitted, i.e. the stream ends with
+    /// the beginning of a line that is never completed. The script is returned for chaining.
+    pub fn with_disconnect(mut self) -> StreamScript {
+        if !self.disconnected {
+            self.body.push_str(TRUNCATED_LINE);
+            self.disconnected = true;
+        }
+
+        self
+    }
+
+    fn response(&self) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .set_body_raw(self.body.clone().into_bytes(), "application/x-ndjson")
+    }
+}
+
+/// A builder for a [LichessScenario], i.e. a mock Lichess server which simulates failures that
+/// bots encounter in practice, such as rate limits, broken connections and malformed lines in
+/// streams. Failures are configured per endpoint and take precedence over any mocks mounted on
+/// the [LichessScenario::server] afterwards, so these can provide the regular responses.
+pub struct ScenarioBuilder {
+    api_mode: ApiMode,
+    mocks: Vec<Mock>
+}
+
+impl ScenarioBuilder {
+
+    /// Creates a new builder for a scenario without any failures, in which the client uses the
+    /// [ApiMode::Bot].
+    pub fn new() -> ScenarioBuilder {
+        ScenarioBuilder {
+            api_mode: ApiMode::Bot,
+            mocks: Vec::new()
+        }
+    }
+
+    /// Sets the [ApiMode] of the client of the scenario, which determines the paths of the game
+    /// streams. The builder is returned for chaining.
+    pub fn with_api_mode(mut self, api_mode: ApiMode) -> ScenarioBuilder {
+        self.api_mode = api_mode;
+        self
+    }
+
+    /// Answers the next requests to the given endpoint with `429 Too Many Requests` and a
+    /// `Retry-After` header, as Lichess does when a client exceeds its rate limit. Subsequent
+    /// requests are answered by the other mocks of the scenario. The builder is returned for
+    /// chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `method`: The HTTP method of the rate-limited endpoint.
+    /// * `endpoint`: The path of the rate-limited endpoint, such as `/account`.
+    /// * `times`: The number of requests which are rate limited.
+    /// * `retry_after`: The time reported in the `Retry-After` header, rounded down to seconds.
+    pub fn with_rate_limit(mut self, method: Method, endpoint: impl Into<String>, times: u64,
+            retry_after: Duration) -> ScenarioBuilder {
+        let response = ResponseTemplate::new(429)
+            .insert_header("Retry-After", retry_after.as_secs().to_string().as_str());
+
+        self.mocks.push(Mock::given(self::method(method.as_str()))
+            .and(path(endpoint.into()))
+            .respond_with(response)
+            .up_to_n_times(times));
+        self
+    }
+
+    /// Adds a connection to the event stream of the bot, which follows the given script. Every
+    /// call adds one connection, which are served in the order in which they were added, so a
+    /// bot which reconnects after a [StreamScript::with_disconnect] receives the next script. The
+    /// builder is returned for chaining.
+    pub fn with_event_stream(self, script: StreamScript) -> ScenarioBuilder {
+        self.with_stream(EVENT_PATH.to_owned(), script)
+    }
+
+    /// Adds a connection to the stream of the game with the given ID, which follows the given
+    /// script. As for [ScenarioBuilder::with_event_stream], connections are served in the order
+    /// in which they were added. The builder is returned for chaining.
+    pub fn with_game_stream(self, game_id: impl AsRef<str>, script: StreamScript)
+            -> ScenarioBuilder {
+        let path = format!("{}/game/stream/{}", self.api_mode.prefix(), game_id.as_ref());
+
+        self.with_stream(path, script)
+    }
+
+    fn with_stream(mut self, endpoint: String, script: StreamScript) -> ScenarioBuilder {
+        self.mocks.push(Mock::given(method("GET"))
+            .and(path(endpoint))
+            .respond_with(script.response())
+            .up_to_n_times(1));
+        self
+    }
+
+    /// Starts the mock server and mounts the configured failures.
+    pub async fn build(self) -> LichessScenario {
+        let server = MockServer::start().await;
+
+        for mock in self.mocks {
+            server.register(mock).await;
+        }
+
+        LichessScenario {
+            api_mode: self.api_mode,
+            server
+        }
+    }
+}
+
+impl Default for ScenarioBuilder {
+    fn default() -> ScenarioBuilder {
+        ScenarioBuilder::new()
+    }
+}
+
+/// A running mock Lichess server built with a [ScenarioBuilder]. The server stops when the
+/// scenario is dropped.
+pub struct LichessScenario {
+    api_mode: ApiMode,
+    server: MockServer
+}
+
+impl LichessScenario {
+
+    /// The underlying [MockServer], on which additional mocks can be mounted, for example to
+    /// answer requests once a rate limit has passed, or to verify the received requests.
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// The base URL of the mock server.
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Creates a [BotClient] which sends all requests to the mock server.
+    pub fn client(&self) -> BotClient {
+        BotClientBuilder::new()
+            .with_token(TEST_TOKEN)
+            .with_base_url(self.server.uri())
+            .with_gif_base_url(self.server.uri())
+            .with_site_base_url(self.server.uri())
+            .with_api_mode(self.api_mode)
+            .build()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use futures::StreamExt;
+
+    use kernal::prelude::*;
+
+    use crate::client::tests as client_tests;
+
+    use super::*;
+
+    #[test]
+    fn rate_limited_requests_are_answered_with_retry_after() {
+        tokio_test::block_on(async {
+            let scenario = ScenarioBuilder::new()
+                .with_rate_limit(Method::GET, "/account", 1, Duration::from_secs(30))
+                .build()
+                .await;
+            let client = scenario.client();
+
+            Mock::given(method("GET"))
+                .and(path("/account"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(client_tests::get_test_user_json()))
+                .mount(scenario.server())
+                .await;
+
+            assert_that!(client.get_my_profile().await).is_err();
+            assert_that!(client.rate_limit_info().and_then(|info| info.retry_after))
+                .contains(Duration::from_secs(30));
+            assert_that!(client.get_my_profile().await).is_ok();
+        });
+    }
+
+    #[test]
+    fn event_stream_connections_follow_scripts_in_order() {
+        tokio_test::block_on(async {
+            let scenario = ScenarioBuilder::new()
+                .with_event_stream(StreamScript::new()
+                    .with_line(r#"{"type":"first"}"#)
+                    .with_keep_alive()
+                    .with_malformed_line()
+                    .with_disconnect()
+                    .with_line(r#"{"type":"ignored"}"#))
+                .with_event_stream(StreamScript::new().with_line(r#"{"type":"second"}"#))
+                .build()
+                .await;
+            let client = scenario.client();
+
+            let first = client.stream_raw_events().await.unwrap().collect::<Vec<_>>().await;
+            let second = client.stream_raw_events().await.unwrap().collect::<Vec<_>>().await;
+
+            assert_that!(first.len()).is_equal_to(2);
+            assert_that!(first[0].as_ref().ok().and_then(|event| event["type"].as_str()))
+                .contains("first");
+            assert_that!(&first[1]).is_err();
+            assert_that!(second.len()).is_equal_to(1);
+            assert_that!(second[0].as_ref().ok().and_then(|event| event["type"].as_str()))
+                .contains("second");
+        });
+    }
+
+    #[test]
+    fn disconnect_ends_stream_in_middle_of_line() {
+        tokio_test::block_on(async {
+            let scenario = ScenarioBuilder::new()
+                .with_event_stream(StreamScript::new()
+                    .with_line("{}")
+                    .with_disconnect()
+                    .with_line("{}"))
+                .build()
+                .await;
+
+            let response = reqwest::get(format!("{}{EVENT_PATH}", scenario.uri())).await.unwrap();
+
+            assert_that!(response.text().await.unwrap())
+                .is_equal_to(format!("{{}}\n{TRUNCATED_LINE}"));
+        });
+    }
+
+    #[test]
+    fn game_stream_path_depends_on_api_mode() {
+        tokio_test::block_on(async {
+            let scenario = ScenarioBuilder::new()
+                .with_api_mode(ApiMode::Board)
+                .with_game_stream("testGameId", StreamScript::new().with_malformed_line())
+                .build()
+                .await;
+
+            let response = reqwest::get(format!("{}/board/game/stream/testGameId", scenario.uri()))
+                .await
+                .unwrap();
+
+            assert_that!(response.text().await.unwrap())
+                .is_equal_to(format!("{MALFORMED_LINE}\n"));
+        });
+    }
+}
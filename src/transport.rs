@@ -0,0 +1,135 @@
+use std::fmt::Debug;
+
+use reqwest::{Client, Request, Response};
+
+use crate::error::LibotResult;
+
+/// Sends the HTTP requests of a [BotClient](crate::client::BotClient). By default, requests are
+/// sent with a [ReqwestTransport]. Set a different transport with
+/// [BotClientBuilder::with_transport](crate::client::BotClientBuilder::with_transport), e.g. to
+/// answer requests from memory in tests or to send them with a different HTTP stack.
+///
+/// Requests and responses are represented by the types of [reqwest]. Responses of other stacks
+/// can be converted from an `http::Response` with [Response::from], whose body may be anything
+/// convertible into a [reqwest::Body], including a stream.
+#[async_trait::async_trait]
+pub trait HttpTransport : Debug + Send + Sync {
+
+    /// Sends the given request, which already contains the authorization header of the client,
+    /// and returns the response regardless of its status. Unsuccessful statuses are handled by
+    /// the client.
+    ///
+    /// # Errors
+    ///
+    /// Any [LibotRequestError](crate::error::LibotRequestError) if the request could not be sent,
+    /// such as [LibotRequestError::ReqwestError](crate::error::LibotRequestError::ReqwestError)
+    /// or [LibotRequestError::TransportError](crate::error::LibotRequestError::TransportError)
+    /// for errors of other HTTP stacks.
+    async fn execute(&self, request: Request) -> LibotResult<Response>;
+}
+
+/// The default [HttpTransport], which sends requests with a [reqwest] [Client].
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: Client
+}
+
+impl ReqwestTransport {
+
+    /// Creates a new transport which sends requests with the given client.
+    pub fn new(client: Client) -> ReqwestTransport {
+        ReqwestTransport {
+            client
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn execute(&self, request: Request) -> LibotResult<Response> {
+        Ok(self.client.execute(request).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex};
+
+    use kernal::prelude::*;
+
+    use reqwest::{Method, StatusCode};
+    use reqwest::header::AUTHORIZATION;
+
+    use crate::client::BotClientBuilder;
+    use crate::error::LibotRequestError;
+
+    use super::*;
+
+    type SentRequests = Arc<Mutex<Vec<(Method, String, Option<String>)>>>;
+
+    #[derive(Debug, Default)]
+    struct InMemoryTransport {
+        requests: SentRequests
+    }
+
+    #[async_trait::async_trait]
+    impl HttpTransport for InMemoryTransport {
+        async fn execute(&self, request: Request) -> LibotResult<Response> {
+            let authorization = request.headers().get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned);
+            self.requests.lock().unwrap()
+                .push((request.method().clone(), request.url().path().to_owned(), authorization));
+
+            let response = match request.url().path() {
+                "/api/account/playing" => http::Response::new(r#"{"nowPlaying":[]}"#),
+                _ => http::Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(r#"{"error":"Not found"}"#)
+                    .unwrap()
+            };
+
+            Ok(Response::from(response))
+        }
+    }
+
+    #[test]
+    fn requests_are_sent_through_custom_transport() {
+        tokio_test::block_on(async {
+            let requests = SentRequests::default();
+            let transport = InMemoryTransport {
+                requests: Arc::clone(&requests)
+            };
+            let client = BotClientBuilder::new()
+                .with_token("test_token")
+                .with_transport(transport)
+                .build()
+                .unwrap();
+
+            let games = client.get_ongoing_games(10).await.unwrap();
+            let expected_request = (Method::GET, "/api/account/playing".to_owned(),
+                Some("Bearer test_token".to_owned()));
+
+            assert_that!(games).is_empty();
+            assert_that!(requests.lock().unwrap().clone())
+                .contains_exactly_in_given_order([expected_request]);
+        })
+    }
+
+    #[test]
+    fn unsuccessful_status_of_custom_transport_is_reported_as_api_error() {
+        tokio_test::block_on(async {
+            let client = BotClientBuilder::new()
+                .with_token("test_token")
+                .with_transport(InMemoryTransport::default())
+                .build()
+                .unwrap();
+
+            let result = client.abort_game("testGameId".to_owned()).await;
+
+            assert!(matches!(result, Err(LibotRequestError::ApiError { status, .. })
+                if status == StatusCode::NOT_FOUND));
+        })
+    }
+}
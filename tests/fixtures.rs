@@ -0,0 +1,233 @@
+use std::fmt::Debug;
+use std::fs;
+
+use kernal::prelude::*;
+
+use libot::model::TimeControl;
+use libot::model::bot_event::BotEvent;
+use libot::model::challenge::{Challenge, ChallengeDirection, Challenges, DeclineReason};
+use libot::model::game::{Clock, Color, GameStatus, Speed, Variant};
+use libot::model::game::event::{GameEvent, GameFullEvent};
+use libot::model::game::export::ExportedGame;
+use libot::model::user::{AiLevel, PlayerRef, Title, UserProfile};
+use libot::model::user::preferences::UserPreferences;
+use libot::runner::parsing::ParsingConfig;
+
+use rstest::rstest;
+
+use serde::de::DeserializeOwned;
+
+use serde_json::Value;
+
+fn read_fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+
+    fs::read_to_string(&path).unwrap_or_else(|error| panic!("could not read {path}: {error}"))
+}
+
+/// Deserializes the fixture with the given name both from its pretty-printed form and from a
+/// single line as sent in the event streams, which is parsed like the runner does, and checks that
+/// both agree.
+fn load<T>(name: &str) -> T
+where
+    T: DeserializeOwned + Debug + PartialEq
+{
+    let json = read_fixture(name);
+    let value = serde_json::from_str::<T>(&json)
+        .unwrap_or_else(|error| panic!("could not deserialize {name}: {error}"));
+    let line = serde_json::from_str::<Value>(&json).unwrap().to_string();
+    let value_from_line = ParsingConfig::new().parse_line::<T>(&line)
+        .unwrap_or_else(|error| panic!("could not parse {name} as a line: {error}"));
+
+    assert_that!(&value_from_line).is_equal_to(&value);
+
+    value
+}
+
+fn load_game_full(name: &str) -> GameFullEvent {
+    match load::<GameEvent>(name) {
+        GameEvent::GameFull(game_full) => game_full,
+        event => panic!("expected gameFull in {name}, but got {event:?}")
+    }
+}
+
+fn load_challenge(name: &str) -> Challenge {
+    match load::<BotEvent>(name) {
+        BotEvent::Challenge(challenge) | BotEvent::ChallengeCanceled(challenge) => challenge,
+        event => panic!("expected a challenge in {name}, but got {event:?}")
+    }
+}
+
+fn clock(limit: i32, increment: i32) -> Clock {
+    Clock {
+        limit: Some(limit),
+        increment: Some(increment)
+    }
+}
+
+#[rstest]
+#[case::challenge("challenge.json")]
+#[case::challenge_correspondence("challenge_correspondence.json")]
+#[case::challenge_canceled("challenge_canceled.json")]
+#[case::challenge_declined("challenge_declined.json")]
+#[case::game_start("game_start.json")]
+#[case::game_finish("game_finish.json")]
+fn bot_event_fixtures_deserialize(#[case] name: &str) {
+    load::<BotEvent>(name);
+}
+
+#[rstest]
+#[case::game_full("game_full.json")]
+#[case::game_full_correspondence("game_full_correspondence.json")]
+#[case::game_full_ai("game_full_ai.json")]
+#[case::game_state("game_state.json")]
+#[case::game_state_finished("game_state_finished.json")]
+#[case::chat_line("chat_line.json")]
+#[case::opponent_gone("opponent_gone.json")]
+fn game_event_fixtures_deserialize(#[case] name: &str) {
+    load::<GameEvent>(name);
+}
+
+#[test]
+fn real_time_game_full_has_clock_in_seconds() {
+    let GameFullEvent { info, state } = load_game_full("game_full.json");
+
+    assert_that!(info.variant).contains(Variant::Standard);
+    assert_that!(info.clock).contains(clock(180, 2));
+    assert_that!(info.speed).is_equal_to(Speed::Blitz);
+    assert_that!(info.perf.name).contains("Blitz".to_owned());
+    assert_that!(info.days_per_turn).is_none();
+    assert_that!(info.white.rating).contains(2012);
+    assert_that!(info.white.title).contains(Title::Bot);
+    assert_that!(info.black.provisional).is_none();
+    assert_that!(state.white_increment).is_equal_to(2000);
+}
+
+#[test]
+fn correspondence_game_full_has_days_per_turn_and_no_clock() {
+    let GameFullEvent { info, .. } = load_game_full("game_full_correspondence.json");
+
+    assert_that!(info.variant).contains(Variant::FromPosition);
+    assert_that!(info.clock).is_none();
+    assert_that!(info.days_per_turn).contains(3);
+    assert_that!(info.color_of("testbot")).contains(Color::Black);
+}
+
+#[test]
+fn game_full_against_ai_reports_ai_level() {
+    let GameFullEvent { info, .. } = load_game_full("game_full_ai.json");
+
+    assert_that!(info.variant).contains(Variant::Chess960);
+    assert_that!(info.clock).contains(clock(600, 0));
+    assert_that!(info.player_ref(Color::White))
+        .is_equal_to(PlayerRef::Ai(AiLevel::try_from(3).unwrap()));
+}
+
+#[test]
+fn finished_game_state_has_winner() {
+    let GameEvent::GameState(state) = load::<GameEvent>("game_state_finished.json")
+    else {
+        panic!("expected gameState");
+    };
+
+    assert_that!(state.status).is_equal_to(GameStatus::Mate);
+    assert_that!(state.winner).contains(Color::Black);
+}
+
+#[test]
+fn challenge_has_variant_clock_and_challenger_rating() {
+    let challenge = load_challenge("challenge.json");
+
+    assert_that!(challenge.variant).contains(Variant::Standard);
+    assert_that!(challenge.challenger.rating).contains(1874);
+    assert_that!(challenge.dest_user.and_then(|user| user.title)).contains(Title::Bot);
+    assert_that!(challenge.time_control.speed()).is_equal_to(challenge.speed);
+    assert_that!(challenge.time_control).is_equal_to(TimeControl::Clock {
+        clock: clock(180, 2),
+        show: Some("3+2".to_owned())
+    });
+}
+
+#[test]
+fn correspondence_challenge_has_days_per_turn() {
+    let challenge = load_challenge("challenge_correspondence.json");
+
+    assert_that!(challenge.variant).contains(Variant::FromPosition);
+    assert_that!(challenge.time_control).is_equal_to(TimeControl::Correspondence {
+        days_per_turn: Some(3)
+    });
+    assert_that!(challenge.initial_fen).is_some();
+}
+
+#[test]
+fn declined_challenge_has_decline_reason() {
+    let json = read_fixture("challenge_declined.json");
+    let challenge_json = serde_json::from_str::<Value>(&json).unwrap()["challenge"].clone();
+    let challenge = serde_json::from_value::<Challenge>(challenge_json).unwrap();
+
+    assert_that!(challenge.direction).contains(ChallengeDirection::Out);
+    assert_that!(challenge.decline.map(|decline| decline.key)).contains(DeclineReason::NoBot);
+}
+
+#[test]
+fn challenge_list_has_incoming_and_outgoing_challenges() {
+    let challenges = load::<Challenges>("challenges.json");
+
+    assert_that!(&challenges.incoming).has_length(1);
+    assert_that!(challenges.incoming[0].variant).contains(Variant::Atomic);
+    assert_that!(&challenges.outgoing).has_length(1);
+    assert_that!(&challenges.outgoing[0].time_control).is_equal_to(&TimeControl::Unlimited);
+}
+
+#[rstest]
+#[case::game_start("game_start.json", GameStatus::Started, None)]
+#[case::game_finish("game_finish.json", GameStatus::Mate, Some(Color::Black))]
+fn game_start_and_finish_have_all_fields(#[case] name: &str, #[case] status: GameStatus,
+        #[case] winner: Option<Color>) {
+    let game = match load::<BotEvent>(name) {
+        BotEvent::GameStart(game) | BotEvent::GameFinish(game) => game,
+        event => panic!("expected gameStart or gameFinish, but got {event:?}")
+    };
+
+    assert_that!(game.id).contains("aBcDeFgH".to_owned());
+    assert_that!(game.source).is_some();
+    assert_that!(game.status).contains(status);
+    assert_that!(game.winner).is_equal_to(winner);
+    assert_that!(game.color).contains(Color::White);
+    assert_that!(game.rated).contains(true);
+    assert_that!(game.speed).contains(Speed::Blitz);
+    assert_that!(game.variant).contains(Variant::Standard);
+    assert_that!(game.compat).is_some();
+}
+
+#[test]
+fn account_has_perfs_and_profile() {
+    let account = load::<UserProfile>("account.json");
+
+    assert_that!(account.title).contains(Title::Bot);
+    assert_that!(account.perfs.blitz.map(|perf| perf.rating)).contains(2012);
+    assert_that!(account.perfs.classical.map(|perf| perf.prov)).contains(true);
+    assert_that!(account.profile.bio).contains("A test bot.".to_owned());
+    assert_that!(account.playing).is_some();
+    assert_that!(account.followable).is_true();
+}
+
+#[test]
+fn exported_game_has_players_and_opening() {
+    let game = load::<ExportedGame>("exported_game.json");
+
+    assert_that!(game.status).is_equal_to(GameStatus::Mate);
+    assert_that!(game.winner).contains(Color::Black);
+    assert_that!(game.opening.map(|opening| opening.eco)).contains("A00".to_owned());
+    assert_that!(game.players.white.analysis.and_then(|analysis| analysis.accuracy))
+        .contains(42);
+    assert_that!(game.moves.as_str()).is_equal_to("f3 e5 g4 Qh4#");
+}
+
+#[test]
+fn preferences_deserialize() {
+    let preferences = load::<UserPreferences>("preferences.json");
+
+    assert_that!(preferences.dark).is_true();
+    assert_that!(preferences.language.as_str()).is_equal_to("en-GB");
+}